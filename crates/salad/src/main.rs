@@ -10,14 +10,17 @@ use rustc_hash::FxHashMap;
 use rustc_hash::FxHashSet;
 use sald_core::compiler::Compiler;
 use sald_core::lexer::Scanner;
+use sald_core::package::{self, Lockfile, Source};
 use sald_core::parser::Parser as SaldParser;
 use sald_core::vm::VM;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::convert::Infallible;
 use std::fs::{self, File};
 use std::io::{stdout, Read, Write};
 use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::oneshot;
@@ -91,7 +94,7 @@ pub struct ProjectConfig {
     #[serde(default = "default_main")]
     pub main: String,
     #[serde(default)]
-    pub modules: FxHashMap<String, String>,
+    pub modules: BTreeMap<String, Source>,
 }
 
 fn default_main() -> String {
@@ -107,7 +110,7 @@ impl Default for ProjectConfig {
             author: None,
             license: Some("MIT".to_string()),
             main: default_main(),
-            modules: FxHashMap::default(),
+            modules: BTreeMap::new(),
         }
     }
 }
@@ -228,7 +231,7 @@ fn default_salad_json(name: &str, author: Option<&str>) -> String {
         author: author.map(|s| s.to_string()),
         license: Some("MIT".to_string()),
         main: "main.sald".to_string(),
-        modules: FxHashMap::default(),
+        modules: BTreeMap::new(),
     };
     serde_json::to_string_pretty(&config).unwrap()
 }
@@ -265,18 +268,22 @@ fn save_config(project_root: &Path, config: &ProjectConfig) -> Result<(), String
 
 fn check_modules(project_root: &Path) -> Result<ModuleCheckResult, String> {
     let config = parse_config(&project_root.join("salad.json"))?;
-    let sald_modules_dir = project_root.join("sald_modules");
     let mut result = ModuleCheckResult::new();
     let mut checked: FxHashSet<String> = FxHashSet::default();
-    let mut to_check: Vec<(String, String)> = config.modules.into_iter().collect();
+    let mut to_check: Vec<(String, Source, PathBuf)> = config
+        .modules
+        .into_iter()
+        .map(|(name, source)| (name, source, project_root.to_path_buf()))
+        .collect();
 
-    while let Some((module_name, required_version)) = to_check.pop() {
+    while let Some((module_name, source, declared_in)) = to_check.pop() {
         if checked.contains(&module_name) {
             continue;
         }
         checked.insert(module_name.clone());
+        let required_version = source.to_string();
 
-        let module_dir = sald_modules_dir.join(&module_name);
+        let module_dir = package::source_dir(project_root, &declared_in, &module_name, &source);
         let module_config_path = module_dir.join("salad.json");
 
         if !module_dir.exists() || !module_config_path.exists() {
@@ -292,7 +299,7 @@ fn check_modules(project_root: &Path) -> Result<ModuleCheckResult, String> {
             }
         };
 
-        if module_config.version != required_version {
+        if matches!(&source, Source::Registry(v) if *v != module_config.version) {
             result.version_mismatch.push((
                 module_name.clone(),
                 required_version.clone(),
@@ -306,9 +313,9 @@ fn check_modules(project_root: &Path) -> Result<ModuleCheckResult, String> {
             module_config.version.clone(),
         ));
 
-        for (dep_name, dep_version) in module_config.modules {
+        for (dep_name, dep_source) in module_config.modules {
             if !checked.contains(&dep_name) {
-                to_check.push((dep_name, dep_version));
+                to_check.push((dep_name, dep_source, module_dir.clone()));
             }
         }
     }
@@ -491,6 +498,17 @@ fn cmd_check() {
                 println!("{} {} {}", "x".red(), name.cyan(), version.dimmed());
             }
 
+            if let Some(lock) = load_lockfile(&project_root) {
+                for name in lock.verify(&project_root) {
+                    println!(
+                        "{} {} {}",
+                        "~".yellow(),
+                        name.cyan(),
+                        format!("(changed since {} was written)", package::LOCK_FILE).dimmed()
+                    );
+                }
+            }
+
             println!();
             if result.is_ok() {
                 print_success("All modules OK");
@@ -756,12 +774,18 @@ async fn cmd_install() {
     fs::create_dir_all(&sald_modules_dir).ok();
 
     let client = reqwest::Client::new();
+    let lock = load_lockfile(&project_root);
     let mut installed = 0;
     let mut failed = 0;
-    let mut to_install: Vec<(String, String)> = config.modules.into_iter().collect();
+    let mut to_install: Vec<(String, Source, PathBuf)> = config
+        .modules
+        .into_iter()
+        .rev()
+        .map(|(name, source)| (name, source, project_root.clone()))
+        .collect();
     let mut done: FxHashSet<String> = FxHashSet::default();
 
-    while let Some((name, version)) = to_install.pop() {
+    while let Some((name, source, declared_in)) = to_install.pop() {
         if done.contains(&name) {
             continue;
         }
@@ -770,33 +794,45 @@ async fn cmd_install() {
             "{} {} {}",
             "+".green(),
             name.cyan().bold(),
-            version.dimmed()
+            source.to_string().dimmed()
         );
         stdout().flush().ok();
 
-        if install_package(&client, &sald_modules_dir, &name, &version)
-            .await
-            .is_some()
+        match fetch_package(
+            &client,
+            &project_root,
+            &declared_in,
+            &name,
+            &source,
+            lock.as_ref(),
+        )
+        .await
         {
-            println!();
-            installed += 1;
-            done.insert(name.clone());
-
-            // Add transitive deps
-            let dep_config_path = sald_modules_dir.join(&name).join("salad.json");
-            if let Ok(dep_config) = parse_config(&dep_config_path) {
-                for (dep_name, dep_version) in dep_config.modules {
-                    if !done.contains(&dep_name) {
-                        to_install.push((dep_name, dep_version));
+            Some(module_dir) => {
+                println!();
+                installed += 1;
+                done.insert(name.clone());
+
+                // Add transitive deps
+                if let Ok(dep_config) = parse_config(&module_dir.join("salad.json")) {
+                    for (dep_name, dep_source) in dep_config.modules.into_iter().rev() {
+                        if !done.contains(&dep_name) {
+                            to_install.push((dep_name, dep_source, module_dir.clone()));
+                        }
                     }
                 }
             }
-        } else {
-            println!(" {}", "failed".red());
-            failed += 1;
+            None => {
+                println!(" {}", "failed".red());
+                failed += 1;
+            }
         }
     }
 
+    if failed == 0 {
+        write_lockfile(&project_root);
+    }
+
     let elapsed = start.elapsed();
     println!();
     if failed == 0 {
@@ -811,6 +847,85 @@ async fn cmd_install() {
     println!();
 }
 
+/// Reads `salad.lock`, warning instead of failing if it can't be used.
+fn load_lockfile(project_root: &Path) -> Option<Lockfile> {
+    match Lockfile::load(project_root) {
+        Ok(lock) => lock,
+        Err(e) => {
+            print_warn(&e);
+            None
+        }
+    }
+}
+
+/// Resolves the installed tree and writes `salad.lock`.
+fn write_lockfile(project_root: &Path) {
+    match package::resolve(project_root).and_then(|lock| lock.save(project_root)) {
+        Ok(()) => {}
+        Err(e) => print_warn(&format!("{} not updated: {}", package::LOCK_FILE, e)),
+    }
+}
+
+/// Installs one dependency and returns the directory it ended up in.
+/// Git dependencies are checked out at the commit in `salad.lock` when the
+/// lockfile has one for the same repository.
+async fn fetch_package(
+    client: &reqwest::Client,
+    project_root: &Path,
+    declared_in: &Path,
+    name: &str,
+    source: &Source,
+    lock: Option<&Lockfile>,
+) -> Option<PathBuf> {
+    let module_dir = package::source_dir(project_root, declared_in, name, source);
+    match source {
+        Source::Registry(version) => {
+            let modules_dir = project_root.join(package::MODULES_DIR);
+            fs::create_dir_all(&modules_dir).ok();
+            install_package(client, &modules_dir, name, version).await?;
+        }
+        Source::Path { .. } => {
+            if !module_dir.join("salad.json").exists() {
+                return None;
+            }
+        }
+        Source::Git { git, rev } => {
+            let locked = lock
+                .and_then(|lock| lock.get(name))
+                .filter(|p| p.source.starts_with(&format!("git+{}#", git)))
+                .and_then(|p| p.git_commit());
+            install_git(&module_dir, git, locked.or(rev.as_deref()))?;
+        }
+    }
+    Some(module_dir)
+}
+
+fn install_git(module_dir: &Path, url: &str, rev: Option<&str>) -> Option<()> {
+    if module_dir.exists() {
+        fs::remove_dir_all(module_dir).ok();
+    }
+    let cloned = Command::new("git")
+        .args(["clone", "--quiet", url])
+        .arg(module_dir)
+        .status()
+        .ok()?;
+    if !cloned.success() {
+        return None;
+    }
+    if let Some(rev) = rev {
+        let checked_out = Command::new("git")
+            .arg("-C")
+            .arg(module_dir)
+            .args(["checkout", "--quiet", rev])
+            .status()
+            .ok()?;
+        if !checked_out.success() {
+            return None;
+        }
+    }
+    Some(())
+}
+
 async fn install_package(
     client: &reqwest::Client,
     modules_dir: &Path,
@@ -907,6 +1022,7 @@ async fn cmd_add(packages: &[String]) {
     let client = reqwest::Client::new();
     let sald_modules_dir = project_root.join("sald_modules");
     fs::create_dir_all(&sald_modules_dir).ok();
+    let lock = load_lockfile(&project_root);
 
     let mut added = 0;
     let mut failed = 0;
@@ -964,7 +1080,9 @@ async fn cmd_add(packages: &[String]) {
             .await
             .is_some()
         {
-            config.modules.insert(name.clone(), version.clone());
+            config
+                .modules
+                .insert(name.clone(), Source::Registry(version.clone()));
             println!();
             added += 1;
 
@@ -972,12 +1090,16 @@ async fn cmd_add(packages: &[String]) {
             let mut done: FxHashSet<String> = FxHashSet::default();
             done.insert(name.clone());
 
-            let dep_config_path = sald_modules_dir.join(&name).join("salad.json");
-            if let Ok(dep_config) = parse_config(&dep_config_path) {
-                let mut to_install: Vec<(String, String)> =
-                    dep_config.modules.into_iter().collect();
+            let module_dir = sald_modules_dir.join(&name);
+            if let Ok(dep_config) = parse_config(&module_dir.join("salad.json")) {
+                let mut to_install: Vec<(String, Source, PathBuf)> = dep_config
+                    .modules
+                    .into_iter()
+                    .rev()
+                    .map(|(n, s)| (n, s, module_dir.clone()))
+                    .collect();
 
-                while let Some((dep_name, dep_version)) = to_install.pop() {
+                while let Some((dep_name, dep_source, declared_in)) = to_install.pop() {
                     if done.contains(&dep_name) {
                         continue;
                     }
@@ -986,30 +1108,38 @@ async fn cmd_add(packages: &[String]) {
                         "{} {} {}",
                         "+".green(),
                         dep_name.cyan(),
-                        dep_version.dimmed()
+                        dep_source.to_string().dimmed()
                     );
                     stdout().flush().ok();
 
-                    if install_package(&client, &sald_modules_dir, &dep_name, &dep_version)
-                        .await
-                        .is_some()
+                    match fetch_package(
+                        &client,
+                        &project_root,
+                        &declared_in,
+                        &dep_name,
+                        &dep_source,
+                        lock.as_ref(),
+                    )
+                    .await
                     {
-                        done.insert(dep_name.clone());
-                        println!();
-                        added += 1;
-
-                        // Check for more transitive deps
-                        let nested_config = sald_modules_dir.join(&dep_name).join("salad.json");
-                        if let Ok(nested) = parse_config(&nested_config) {
-                            for (n, v) in nested.modules {
-                                if !done.contains(&n) {
-                                    to_install.push((n, v));
+                        Some(dep_dir) => {
+                            done.insert(dep_name.clone());
+                            println!();
+                            added += 1;
+
+                            // Check for more transitive deps
+                            if let Ok(nested) = parse_config(&dep_dir.join("salad.json")) {
+                                for (n, s) in nested.modules.into_iter().rev() {
+                                    if !done.contains(&n) {
+                                        to_install.push((n, s, dep_dir.clone()));
+                                    }
                                 }
                             }
                         }
-                    } else {
-                        println!(" {}", "failed".red());
-                        failed += 1;
+                        None => {
+                            println!(" {}", "failed".red());
+                            failed += 1;
+                        }
                     }
                 }
             }
@@ -1021,6 +1151,9 @@ async fn cmd_add(packages: &[String]) {
 
     // Save config
     save_config(&project_root, &config).ok();
+    if failed == 0 {
+        write_lockfile(&project_root);
+    }
 
     let elapsed = start.elapsed();
     println!();
@@ -1072,6 +1205,9 @@ fn cmd_remove(packages: &[String]) {
     }
 
     save_config(&project_root, &config).ok();
+    if removed > 0 {
+        write_lockfile(&project_root);
+    }
 
     println!();
     print_success(&format!("{} packages removed", removed));
@@ -1087,7 +1223,7 @@ async fn cmd_run(args: &[String]) {
         }
     };
 
-    sald_core::set_project_root(&project_root);
+    sald_core::workspace::set_default_project_root(&project_root);
 
     if let Ok(result) = check_modules(&project_root) {
         if !result.is_ok() {
@@ -82,6 +82,7 @@ pub enum AssignOp {
     MulAssign,
     DivAssign,
     ModAssign,
+    NullCoalesceAssign,
 }
 
 impl AssignOp {
@@ -93,6 +94,7 @@ impl AssignOp {
             TokenKind::StarEqual => Some(AssignOp::MulAssign),
             TokenKind::SlashEqual => Some(AssignOp::DivAssign),
             TokenKind::PercentEqual => Some(AssignOp::ModAssign),
+            TokenKind::QuestionQuestionEqual => Some(AssignOp::NullCoalesceAssign),
             _ => None,
         }
     }
@@ -108,11 +110,17 @@ pub enum Literal {
     String(String),
     Boolean(bool),
     Null,
+    /// `d"2024-06-01"`, validated by the compiler
+    Date(String),
+    /// `t"12:30:00"`, validated by the compiler
+    Time(String),
 }
 
 #[derive(Debug, Clone)]
 pub struct CallArg {
     pub name: Option<String>,
+    /// `**dict`, passing the dictionary's entries as named arguments
+    pub is_dict_spread: bool,
 
     pub value: Expr,
     pub span: Span,
@@ -242,6 +250,13 @@ pub enum Expr {
         span: Span,
     },
 
+    /// `import(path)`: imports a module chosen at runtime and evaluates to
+    /// its namespace
+    Import {
+        path: Box<Expr>,
+        span: Span,
+    },
+
     Return {
         value: Option<Box<Expr>>,
         span: Span,
@@ -366,6 +381,7 @@ impl Expr {
             Expr::Block { span, .. } => *span,
             Expr::Dictionary { span, .. } => *span,
             Expr::Await { span, .. } => *span,
+            Expr::Import { span, .. } => *span,
             Expr::Return { span, .. } => *span,
             Expr::Throw { span, .. } => *span,
             Expr::Break { span } => *span,
@@ -382,3 +398,19 @@ impl Expr {
         )
     }
 }
+
+/// The function of a `fun name(...) { }` expression, which parses as a block
+/// that declares the function and evaluates to it.
+pub fn block_function<'a>(
+    statements: &'a [super::Stmt],
+    expr: Option<&Expr>,
+) -> Option<&'a super::FunctionDef> {
+    match (statements, expr) {
+        ([super::Stmt::Function { def }], Some(Expr::Identifier { name, .. }))
+            if *name == def.name =>
+        {
+            Some(def)
+        }
+        _ => None,
+    }
+}
@@ -5,6 +5,8 @@ use crate::error::Span;
 pub struct FunctionParam {
     pub name: String,
     pub is_variadic: bool,
+    /// `**name`, collecting named arguments that match no other parameter
+    pub is_kwargs: bool,
     pub default_value: Option<Expr>,
     pub span: Span,
 }
@@ -16,6 +18,21 @@ pub struct Decorator {
     pub span: Span,
 }
 
+/// `key: "value"` condition of an `@cfg(...)` declaration
+#[derive(Debug, Clone)]
+pub struct CfgPredicate {
+    pub key: String,
+    pub value: String,
+    pub span: Span,
+}
+
+impl CfgPredicate {
+    /// Whether the build being compiled for matches.
+    pub fn holds(&self) -> bool {
+        crate::target::cfg_value(&self.key) == Some(self.value.as_str())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FunctionDef {
     pub name: String,
@@ -24,6 +41,8 @@ pub struct FunctionDef {
     pub is_static: bool,
     pub is_async: bool,
     pub decorators: Vec<Decorator>,
+    /// Text of the `///` comment above the declaration
+    pub doc: Option<String>,
     pub span: Span,
 }
 
@@ -34,6 +53,8 @@ pub struct ClassDef {
     pub implements: Vec<String>,
     pub methods: Vec<FunctionDef>,
     pub decorators: Vec<Decorator>,
+    /// Text of the `///` comment above the declaration
+    pub doc: Option<String>,
     pub span: Span,
 }
 
@@ -138,12 +159,24 @@ pub enum Stmt {
         span: Span,
     },
 
+    /// Breakpoint; a no-op unless a debugger hook is installed
+    Debugger {
+        span: Span,
+    },
+
     Import {
         path: String,
         alias: Option<String>,
         span: Span,
     },
 
+    /// `export A, B` inside a namespace: adds names it can see, such as an
+    /// `import ... as` alias, to the namespace's members
+    Export {
+        names: Vec<String>,
+        span: Span,
+    },
+
     TryCatch {
         try_body: Box<Stmt>,
         catch_var: String,
@@ -177,9 +210,42 @@ pub enum Stmt {
     Interface {
         def: InterfaceDef,
     },
+
+    /// Declaration compiled only when every predicate holds
+    Cfg {
+        predicates: Vec<CfgPredicate>,
+        body: Box<Stmt>,
+        span: Span,
+    },
 }
 
 impl Stmt {
+    /// The declaration itself if its `@cfg` predicates hold for this build,
+    /// `None` if it is compiled out.
+    pub fn active(&self) -> Option<&Stmt> {
+        match self {
+            Stmt::Cfg {
+                predicates, body, ..
+            } => {
+                if predicates.iter().all(CfgPredicate::holds) {
+                    body.active()
+                } else {
+                    None
+                }
+            }
+            stmt => Some(stmt),
+        }
+    }
+
+    /// The declaration behind any `@cfg` guards, whether or not they hold.
+    /// Tooling uses this to see every declaration in a file.
+    pub fn without_cfg(&self) -> &Stmt {
+        match self {
+            Stmt::Cfg { body, .. } => body.without_cfg(),
+            stmt => stmt,
+        }
+    }
+
     pub fn span(&self) -> Span {
         match self {
             Stmt::Let { span, .. } => *span,
@@ -195,13 +261,16 @@ impl Stmt {
             Stmt::For { span, .. } => *span,
             Stmt::Break { span } => *span,
             Stmt::Continue { span } => *span,
+            Stmt::Debugger { span } => *span,
             Stmt::Import { span, .. } => *span,
+            Stmt::Export { span, .. } => *span,
             Stmt::TryCatch { span, .. } => *span,
             Stmt::Throw { span, .. } => *span,
             Stmt::Namespace { span, .. } => *span,
             Stmt::Const { span, .. } => *span,
             Stmt::Enum { span, .. } => *span,
             Stmt::Interface { def } => def.span,
+            Stmt::Cfg { span, .. } => *span,
         }
     }
 }
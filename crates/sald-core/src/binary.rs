@@ -1,102 +1,576 @@
-use crate::compiler::chunk::{Chunk, ClassConstant, Constant, FunctionConstant, UpvalueInfo};
+use crate::compiler::chunk::{
+    Chunk, ClassConstant, Constant, FunctionBody, FunctionConstant, UpvalueInfo,
+};
 use crate::error::{Position, Span};
 use crate::vm::interner::intern;
+use rustc_hash::FxHashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
 
 const MAGIC: &[u8; 4] = b"SALD";
-const VERSION: u8 = 4;
-
+const VERSION: u8 = 5;
+
+/// Set when the file contains `d"..."`/`t"..."` constants.
+pub const FEATURE_DATE_LITERALS: u32 = 1 << 0;
+/// Set when everything between the header and the checksum is zstd compressed.
+pub const FEATURE_ZSTD: u32 = 1 << 1;
+/// Set when a function has a `**` parameter, marked by bit 1 of its
+/// variadic byte.
+pub const FEATURE_KWARGS: u32 = 1 << 2;
+/// Feature flags this build can load. Files using any other flag are refused.
+pub const SUPPORTED_FEATURES: u32 = FEATURE_DATE_LITERALS | FEATURE_ZSTD | FEATURE_KWARGS;
+
+/// Writes a version 5 `.saldc` image:
+///
+/// ```text
+/// "SALD" | version: u8 | features: u32
+/// string pool: u32 count, then (u32 len, utf-8 bytes) per string
+/// chunk: constants, code, spans (nested function chunks inline)
+/// checksum: u32 FNV-1a of every preceding byte
+/// ```
+///
+/// All integers are little endian. Strings inside the chunk are u32 indices
+/// into the pool.
 pub fn serialize(chunk: &Chunk) -> Vec<u8> {
-    let mut out = Vec::new();
+    encode(chunk, false)
+}
 
-    out.extend_from_slice(MAGIC);
-    out.push(VERSION);
+/// Like `serialize`, but zstd compresses the string pool and chunk.
+pub fn serialize_compressed(chunk: &Chunk) -> Vec<u8> {
+    encode(chunk, true)
+}
 
-    write_u32(&mut out, chunk.constants.len() as u32);
-    for constant in &chunk.constants {
-        serialize_constant(&mut out, constant);
-    }
+fn encode(chunk: &Chunk, compress: bool) -> Vec<u8> {
+    let mut writer = Writer::default();
+    let mut body = Vec::new();
+    writer.chunk(&mut body, chunk);
 
-    write_u32(&mut out, chunk.code.len() as u32);
-    out.extend_from_slice(&chunk.code);
+    let mut payload = Vec::with_capacity(body.len() + 64);
+    write_u32(&mut payload, writer.strings.len() as u32);
+    for s in &writer.strings {
+        write_string(&mut payload, s);
+    }
+    payload.extend_from_slice(&body);
 
-    write_u32(&mut out, chunk.spans.len() as u32);
-    for span in &chunk.spans {
-        write_u32(&mut out, span.start.line as u32);
-        write_u32(&mut out, span.start.column as u32);
-        write_u32(&mut out, span.start.offset as u32);
-        write_u32(&mut out, span.end.line as u32);
-        write_u32(&mut out, span.end.column as u32);
-        write_u32(&mut out, span.end.offset as u32);
+    let mut features = writer.features;
+    if compress {
+        if let Ok(compressed) = zstd::bulk::compress(&payload, 0) {
+            payload = compressed;
+            features |= FEATURE_ZSTD;
+        }
     }
 
+    let mut out = Vec::with_capacity(payload.len() + 13);
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    write_u32(&mut out, features);
+    out.extend_from_slice(&payload);
+
+    let sum = checksum(&out);
+    write_u32(&mut out, sum);
     out
 }
 
+/// Loads a `.saldc` file produced by `serialize`.
+///
+/// Format versions 1, 2, 4 and 5 are accepted. Anything else fails with
+/// `Unsupported version: N`; damaged files fail with `Invalid file: ...` or
+/// `Unexpected end of file`. `tests/corpus` pins this behavior.
 pub fn deserialize(data: &[u8]) -> Result<Chunk, String> {
-    let mut cursor = 0;
+    load(data, false)
+}
+
+/// Like `deserialize`, but version 5 function bodies are only decoded when
+/// the function is first created. A corrupt body then surfaces as a runtime
+/// error at that point instead of failing the load.
+pub fn deserialize_lazy(data: &[u8]) -> Result<Chunk, String> {
+    load(data, true)
+}
 
+fn load(data: &[u8], lazy: bool) -> Result<Chunk, String> {
     if data.len() < 5 {
         return Err("Invalid file: too short".to_string());
     }
     if &data[0..4] != MAGIC {
         return Err("Invalid file: not a .saldc file".to_string());
     }
-    cursor += 4;
 
-    let version = data[cursor];
-    if version != VERSION && version != 1 && version != 2 {
-        return Err(format!("Unsupported version: {}", version));
+    match data[4] {
+        VERSION => deserialize_pooled(data, lazy),
+        version @ (1 | 2 | 4) => Reader::new(data, 5, version).chunk(),
+        version => Err(format!("Unsupported version: {}", version)),
+    }
+}
+
+const STANDALONE_MAGIC: &[u8; 8] = b"SALDEXE\0";
+
+/// Appends `chunk` to a copy of the runtime executable so the result runs the
+/// program on its own. A payload already attached to `runtime` is replaced.
+///
+/// ```text
+/// runtime | .saldc image | u64 image length | "SALDEXE\0"
+/// ```
+pub fn build_standalone(runtime: &[u8], chunk: &Chunk) -> Vec<u8> {
+    let runtime = match standalone_len(runtime) {
+        Some(image_len) if image_len + 16 <= runtime.len() as u64 => {
+            &runtime[..runtime.len() - 16 - image_len as usize]
+        }
+        _ => runtime,
+    };
+    let image = serialize(chunk);
+
+    let mut out = Vec::with_capacity(runtime.len() + image.len() + 16);
+    out.extend_from_slice(runtime);
+    out.extend_from_slice(&image);
+    out.extend_from_slice(&(image.len() as u64).to_le_bytes());
+    out.extend_from_slice(STANDALONE_MAGIC);
+    out
+}
+
+/// Loads the program embedded by `build_standalone` into the executable at
+/// `path`. Returns `None` when the executable carries no program.
+pub fn read_standalone(path: &Path) -> Result<Option<Chunk>, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    if len < 16 {
+        return Ok(None);
+    }
+
+    let mut trailer = [0u8; 16];
+    file.seek(SeekFrom::Start(len - 16))
+        .and_then(|_| file.read_exact(&mut trailer))
+        .map_err(|e| e.to_string())?;
+    let (start, end) = match standalone_len(&trailer) {
+        Some(image_len) if image_len <= len - 16 => (len - 16 - image_len, len - 16),
+        Some(_) => return Err("Invalid file: corrupt standalone payload".to_string()),
+        None => return Ok(None),
+    };
+
+    let mut image = vec![0u8; (end - start) as usize];
+    file.seek(SeekFrom::Start(start))
+        .and_then(|_| file.read_exact(&mut image))
+        .map_err(|e| e.to_string())?;
+    deserialize_lazy(&image).map(Some)
+}
+
+/// Reads the payload length from the trailer at the end of `data`.
+fn standalone_len(data: &[u8]) -> Option<u64> {
+    let trailer = &data[data.len().checked_sub(16)?..];
+    if &trailer[8..] != STANDALONE_MAGIC {
+        return None;
+    }
+    let mut len = [0u8; 8];
+    len.copy_from_slice(&trailer[..8]);
+    Some(u64::from_le_bytes(len))
+}
+
+fn deserialize_pooled(data: &[u8], lazy: bool) -> Result<Chunk, String> {
+    if data.len() < 13 {
+        return Err("Invalid file: too short".to_string());
+    }
+    let (content, tail) = data.split_at(data.len() - 4);
+    let stored = u32::from_le_bytes([tail[0], tail[1], tail[2], tail[3]]);
+    if checksum(content) != stored {
+        return Err("Invalid file: checksum mismatch".to_string());
+    }
+
+    let features = u32::from_le_bytes([content[5], content[6], content[7], content[8]]);
+    let unknown = features & !SUPPORTED_FEATURES;
+    if unknown != 0 {
+        return Err(format!("Unsupported feature flags: {:#x}", unknown));
+    }
+
+    let decompressed;
+    let mut payload = &content[9..];
+    if features & FEATURE_ZSTD != 0 {
+        decompressed = zstd::stream::decode_all(payload)
+            .map_err(|e| format!("Invalid file: cannot decompress: {}", e))?;
+        payload = &decompressed;
+    }
+
+    let image: Option<Arc<[u8]>> = lazy.then(|| Arc::from(payload));
+    let payload = image.as_deref().unwrap_or(payload);
+    let mut reader = Reader::new(payload, 0, VERSION);
+    reader.image = image.clone();
+
+    let string_count = reader.u32()? as usize;
+    let mut strings = Vec::with_capacity(string_count.min(payload.len()));
+    for _ in 0..string_count {
+        strings.push(read_string(payload, &mut reader.cursor)?);
+    }
+    reader.strings = strings.into();
+
+    let chunk = reader.chunk()?;
+    if reader.cursor != payload.len() {
+        return Err("Invalid file: trailing data".to_string());
+    }
+    Ok(chunk)
+}
+
+/// A version 5 function body that has not been decoded yet.
+pub(crate) struct EncodedBody {
+    image: Arc<[u8]>,
+    start: usize,
+    end: usize,
+    strings: Arc<[String]>,
+}
+
+pub(crate) fn decode_body(body: &EncodedBody) -> Result<Chunk, String> {
+    let mut reader = Reader::new(&body.image[..body.end], body.start, VERSION);
+    reader.strings = body.strings.clone();
+    reader.image = Some(body.image.clone());
+
+    let chunk = reader.chunk()?;
+    if reader.cursor != body.end {
+        return Err("Invalid file: function chunk length mismatch".to_string());
     }
-    cursor += 1;
+    Ok(chunk)
+}
 
-    let constant_count = read_u32(data, &mut cursor)? as usize;
-    let mut constants = Vec::with_capacity(constant_count);
-    for _ in 0..constant_count {
-        constants.push(deserialize_constant(data, &mut cursor, version)?);
+/// FNV-1a, 32 bit.
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[derive(Default)]
+struct Writer {
+    strings: Vec<String>,
+    string_ids: FxHashMap<String, u32>,
+    features: u32,
+}
+
+impl Writer {
+    fn string(&mut self, out: &mut Vec<u8>, s: &str) {
+        let id = match self.string_ids.get(s) {
+            Some(id) => *id,
+            None => {
+                let id = self.strings.len() as u32;
+                self.strings.push(s.to_string());
+                self.string_ids.insert(s.to_string(), id);
+                id
+            }
+        };
+        write_u32(out, id);
     }
 
-    let code_len = read_u32(data, &mut cursor)? as usize;
-    if cursor + code_len > data.len() {
-        return Err("Invalid file: truncated code".to_string());
+    fn optional_string(&mut self, out: &mut Vec<u8>, s: &Option<String>) {
+        match s {
+            Some(s) => {
+                out.push(1);
+                self.string(out, s);
+            }
+            None => out.push(0),
+        }
     }
-    let code = data[cursor..cursor + code_len].to_vec();
-    cursor += code_len;
 
-    let spans_len = read_u32(data, &mut cursor)? as usize;
-    let mut spans = Vec::with_capacity(spans_len);
+    fn chunk(&mut self, out: &mut Vec<u8>, chunk: &Chunk) {
+        write_u32(out, chunk.constants.len() as u32);
+        for constant in &chunk.constants {
+            self.constant(out, constant);
+        }
 
-    if version == 1 {
-        for _ in 0..spans_len {
-            let line = read_u32(data, &mut cursor)? as usize;
-            spans.push(Span::single(line, 1, 0));
+        write_u32(out, chunk.code.len() as u32);
+        out.extend_from_slice(&chunk.code);
+
+        write_u32(out, chunk.spans.len() as u32);
+        for span in &chunk.spans {
+            write_u32(out, span.start.line as u32);
+            write_u32(out, span.start.column as u32);
+            write_u32(out, span.start.offset as u32);
+            write_u32(out, span.end.line as u32);
+            write_u32(out, span.end.column as u32);
+            write_u32(out, span.end.offset as u32);
         }
-    } else {
-        for _ in 0..spans_len {
-            let start_line = read_u32(data, &mut cursor)? as usize;
-            let start_column = read_u32(data, &mut cursor)? as usize;
-            let start_offset = read_u32(data, &mut cursor)? as usize;
-            let end_line = read_u32(data, &mut cursor)? as usize;
-            let end_column = read_u32(data, &mut cursor)? as usize;
-            let end_offset = read_u32(data, &mut cursor)? as usize;
-            spans.push(Span::new(
-                Position::new(start_line, start_column, start_offset),
-                Position::new(end_line, end_column, end_offset),
-            ));
+    }
+
+    fn constant(&mut self, out: &mut Vec<u8>, constant: &Constant) {
+        match constant {
+            Constant::Number(n) => {
+                out.push(0);
+                out.extend_from_slice(&n.to_le_bytes());
+            }
+            Constant::String(s) => {
+                out.push(1);
+                self.string(out, s);
+            }
+            Constant::Function(f) => {
+                out.push(2);
+                self.string(out, &f.name);
+                write_u32(out, f.arity as u32);
+                if f.has_kwargs {
+                    self.features |= FEATURE_KWARGS;
+                }
+                out.push(f.is_variadic as u8 | (f.has_kwargs as u8) << 1);
+                out.push(if f.is_async { 1 } else { 0 });
+
+                write_u32(out, f.upvalue_count as u32);
+                for upvalue in &f.upvalues {
+                    out.push(upvalue.index);
+                    out.push(if upvalue.is_local { 1 } else { 0 });
+                }
+
+                write_u32(out, f.param_names.len() as u32);
+                for name in &f.param_names {
+                    self.string(out, name);
+                }
+                write_u32(out, f.default_count as u32);
+
+                write_u32(out, f.decorators.len() as u32);
+                for decorator in &f.decorators {
+                    self.string(out, decorator);
+                }
+
+                self.optional_string(out, &f.namespace_context);
+                self.optional_string(out, &f.class_context);
+
+                let mut body = Vec::new();
+                match f.chunk.load() {
+                    Ok(chunk) => self.chunk(&mut body, chunk),
+                    Err(_) => self.chunk(&mut body, &Chunk::default()),
+                }
+                write_u32(out, body.len() as u32);
+                out.extend_from_slice(&body);
+            }
+            Constant::Class(c) => {
+                out.push(3);
+                self.string(out, &c.name);
+                write_u32(out, c.methods.len() as u32);
+                for (name, idx, is_static) in &c.methods {
+                    self.string(out, name);
+                    write_u32(out, *idx as u32);
+                    out.push(if *is_static { 1 } else { 0 });
+                }
+            }
+            Constant::Date(n) => {
+                self.features |= FEATURE_DATE_LITERALS;
+                out.push(4);
+                out.extend_from_slice(&n.to_le_bytes());
+            }
+            Constant::Time(n) => {
+                self.features |= FEATURE_DATE_LITERALS;
+                out.push(5);
+                out.extend_from_slice(&n.to_le_bytes());
+            }
         }
     }
+}
 
-    Ok(Chunk {
-        code,
-        constants,
-        spans,
-    })
+/// Reads chunks of any supported version. Before version 5 strings are
+/// stored inline and nested function chunks are complete files of their own.
+struct Reader<'a> {
+    data: &'a [u8],
+    cursor: usize,
+    version: u8,
+    strings: Arc<[String]>,
+    /// Set when function bodies should be left encoded
+    image: Option<Arc<[u8]>>,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], cursor: usize, version: u8) -> Self {
+        Self {
+            data,
+            cursor,
+            version,
+            strings: Arc::from([]),
+            image: None,
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        read_u32(self.data, &mut self.cursor)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .data
+            .get(self.cursor)
+            .ok_or_else(|| "Unexpected end of file".to_string())?;
+        self.cursor += 1;
+        Ok(byte)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        if self.version < VERSION {
+            return read_string(self.data, &mut self.cursor);
+        }
+        let id = self.u32()? as usize;
+        self.strings
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Invalid file: string index {} out of range", id))
+    }
+
+    fn optional_string(&mut self) -> Result<Option<String>, String> {
+        if self.byte()? != 0 {
+            Ok(Some(self.string()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn chunk(&mut self) -> Result<Chunk, String> {
+        let constant_count = self.u32()? as usize;
+        let mut constants = Vec::with_capacity(constant_count.min(self.data.len()));
+        for _ in 0..constant_count {
+            constants.push(self.constant()?);
+        }
+
+        let code_len = self.u32()? as usize;
+        if self.cursor + code_len > self.data.len() {
+            return Err("Invalid file: truncated code".to_string());
+        }
+        let code = self.data[self.cursor..self.cursor + code_len].to_vec();
+        self.cursor += code_len;
+
+        let spans_len = self.u32()? as usize;
+        let mut spans = Vec::with_capacity(spans_len.min(self.data.len()));
+
+        if self.version == 1 {
+            for _ in 0..spans_len {
+                let line = self.u32()? as usize;
+                spans.push(Span::single(line, 1, 0));
+            }
+        } else {
+            for _ in 0..spans_len {
+                let start_line = self.u32()? as usize;
+                let start_column = self.u32()? as usize;
+                let start_offset = self.u32()? as usize;
+                let end_line = self.u32()? as usize;
+                let end_column = self.u32()? as usize;
+                let end_offset = self.u32()? as usize;
+                spans.push(Span::new(
+                    Position::new(start_line, start_column, start_offset),
+                    Position::new(end_line, end_column, end_offset),
+                ));
+            }
+        }
+
+        Ok(Chunk {
+            code,
+            constants,
+            spans,
+        })
+    }
+
+    fn constant(&mut self) -> Result<Constant, String> {
+        let tag = self.byte()?;
+
+        match tag {
+            0 => Ok(Constant::Number(read_f64(self.data, &mut self.cursor)?)),
+            1 => {
+                let s = self.string()?;
+                Ok(Constant::String(intern(&s)))
+            }
+            2 => self.function(),
+            3 => {
+                let name = self.string()?;
+                let method_count = self.u32()? as usize;
+                let mut methods = Vec::with_capacity(method_count.min(self.data.len()));
+                for _ in 0..method_count {
+                    let method_name = self.string()?;
+                    let idx = self.u32()? as usize;
+                    let is_static = self.byte()? != 0;
+                    methods.push((method_name, idx, is_static));
+                }
+                Ok(Constant::Class(ClassConstant { name, methods }))
+            }
+            4 => Ok(Constant::Date(read_f64(self.data, &mut self.cursor)?)),
+            5 => Ok(Constant::Time(read_f64(self.data, &mut self.cursor)?)),
+            _ => Err(format!("Unknown constant type: {}", tag)),
+        }
+    }
+
+    fn function(&mut self) -> Result<Constant, String> {
+        let name = self.string()?;
+        let arity = self.u32()? as usize;
+        let flags = self.byte()?;
+        let is_async = self.byte()? != 0;
+
+        let upvalue_count = self.u32()? as usize;
+        let mut upvalues = Vec::with_capacity(upvalue_count.min(self.data.len()));
+        for _ in 0..upvalue_count {
+            let index = self.byte()?;
+            let is_local = self.byte()? != 0;
+            upvalues.push(UpvalueInfo { index, is_local });
+        }
+
+        let param_count = self.u32()? as usize;
+        let mut param_names = Vec::with_capacity(param_count.min(self.data.len()));
+        for _ in 0..param_count {
+            param_names.push(self.string()?);
+        }
+        let default_count = self.u32()? as usize;
+
+        if self.version == 2 {
+            self.byte()?;
+        }
+        let decorator_count = self.u32()? as usize;
+        let mut decorators = Vec::with_capacity(decorator_count.min(self.data.len()));
+        for _ in 0..decorator_count {
+            decorators.push(self.string()?);
+        }
+
+        let (namespace_context, class_context) = if self.version >= 4 {
+            (self.optional_string()?, self.optional_string()?)
+        } else {
+            (None, None)
+        };
+
+        let chunk_len = self.u32()? as usize;
+        if self.cursor + chunk_len > self.data.len() {
+            return Err("Unexpected end of file".to_string());
+        }
+        let chunk_end = self.cursor + chunk_len;
+        let chunk = if self.version < VERSION {
+            let chunk = deserialize(&self.data[self.cursor..chunk_end])?;
+            self.cursor = chunk_end;
+            FunctionBody::from(chunk)
+        } else if let Some(image) = &self.image {
+            let body = EncodedBody {
+                image: image.clone(),
+                start: self.cursor,
+                end: chunk_end,
+                strings: self.strings.clone(),
+            };
+            self.cursor = chunk_end;
+            FunctionBody::encoded(body)
+        } else {
+            let chunk = self.chunk()?;
+            if self.cursor != chunk_end {
+                return Err("Invalid file: function chunk length mismatch".to_string());
+            }
+            FunctionBody::from(chunk)
+        };
+
+        Ok(Constant::Function(FunctionConstant {
+            name,
+            arity,
+            is_variadic: flags & 1 != 0,
+            is_async,
+            upvalue_count,
+            upvalues,
+            chunk,
+            file: String::new(),
+            has_kwargs: flags & 2 != 0,
+            param_names,
+            default_count,
+            decorators,
+            namespace_context,
+            class_context,
+        }))
+    }
 }
 
-fn write_u32(out: &mut Vec<u8>, value: u32) {
+pub(crate) fn write_u32(out: &mut Vec<u8>, value: u32) {
     out.extend_from_slice(&value.to_le_bytes());
 }
 
-fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32, String> {
+pub(crate) fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32, String> {
     if *cursor + 4 > data.len() {
         return Err("Unexpected end of file".to_string());
     }
@@ -110,13 +584,13 @@ fn read_u32(data: &[u8], cursor: &mut usize) -> Result<u32, String> {
     Ok(u32::from_le_bytes(bytes))
 }
 
-fn write_string(out: &mut Vec<u8>, s: &str) {
+pub(crate) fn write_string(out: &mut Vec<u8>, s: &str) {
     let bytes = s.as_bytes();
     write_u32(out, bytes.len() as u32);
     out.extend_from_slice(bytes);
 }
 
-fn write_optional_string(out: &mut Vec<u8>, s: &Option<String>) {
+pub(crate) fn write_optional_string(out: &mut Vec<u8>, s: &Option<String>) {
     match s {
         Some(str) => {
             out.push(1);
@@ -128,7 +602,10 @@ fn write_optional_string(out: &mut Vec<u8>, s: &Option<String>) {
     }
 }
 
-fn read_optional_string(data: &[u8], cursor: &mut usize) -> Result<Option<String>, String> {
+pub(crate) fn read_optional_string(
+    data: &[u8],
+    cursor: &mut usize,
+) -> Result<Option<String>, String> {
     if *cursor >= data.len() {
         return Err("Unexpected end of file".to_string());
     }
@@ -141,7 +618,7 @@ fn read_optional_string(data: &[u8], cursor: &mut usize) -> Result<Option<String
     }
 }
 
-fn read_string(data: &[u8], cursor: &mut usize) -> Result<String, String> {
+pub(crate) fn read_string(data: &[u8], cursor: &mut usize) -> Result<String, String> {
     let len = read_u32(data, cursor)? as usize;
     if *cursor + len > data.len() {
         return Err("Unexpected end of file".to_string());
@@ -152,178 +629,12 @@ fn read_string(data: &[u8], cursor: &mut usize) -> Result<String, String> {
     Ok(s)
 }
 
-fn serialize_constant(out: &mut Vec<u8>, constant: &Constant) {
-    match constant {
-        Constant::Number(n) => {
-            out.push(0);
-            out.extend_from_slice(&n.to_le_bytes());
-        }
-        Constant::String(s) => {
-            out.push(1);
-            write_string(out, s);
-        }
-        Constant::Function(f) => {
-            out.push(2);
-            write_string(out, &f.name);
-            write_u32(out, f.arity as u32);
-            out.push(if f.is_variadic { 1 } else { 0 });
-            out.push(if f.is_async { 1 } else { 0 });
-
-            write_u32(out, f.upvalue_count as u32);
-            for upvalue in &f.upvalues {
-                out.push(upvalue.index);
-                out.push(if upvalue.is_local { 1 } else { 0 });
-            }
-
-            write_u32(out, f.param_names.len() as u32);
-            for name in &f.param_names {
-                write_string(out, name);
-            }
-            write_u32(out, f.default_count as u32);
-
-            write_u32(out, f.decorators.len() as u32);
-            for decorator in &f.decorators {
-                write_string(out, decorator);
-            }
-
-            write_optional_string(out, &f.namespace_context);
-            write_optional_string(out, &f.class_context);
-            let chunk_bytes = serialize(&f.chunk);
-            write_u32(out, chunk_bytes.len() as u32);
-            out.extend_from_slice(&chunk_bytes);
-        }
-        Constant::Class(c) => {
-            out.push(3);
-            write_string(out, &c.name);
-            write_u32(out, c.methods.len() as u32);
-            for (name, idx, is_static) in &c.methods {
-                write_string(out, name);
-                write_u32(out, *idx as u32);
-                out.push(if *is_static { 1 } else { 0 });
-            }
-        }
-    }
-}
-
-fn deserialize_constant(data: &[u8], cursor: &mut usize, version: u8) -> Result<Constant, String> {
-    if *cursor >= data.len() {
+pub(crate) fn read_f64(data: &[u8], cursor: &mut usize) -> Result<f64, String> {
+    if *cursor + 8 > data.len() {
         return Err("Unexpected end of file".to_string());
     }
-
-    let tag = data[*cursor];
-    *cursor += 1;
-
-    match tag {
-        0 => {
-            if *cursor + 8 > data.len() {
-                return Err("Unexpected end of file".to_string());
-            }
-            let bytes = [
-                data[*cursor],
-                data[*cursor + 1],
-                data[*cursor + 2],
-                data[*cursor + 3],
-                data[*cursor + 4],
-                data[*cursor + 5],
-                data[*cursor + 6],
-                data[*cursor + 7],
-            ];
-            *cursor += 8;
-            Ok(Constant::Number(f64::from_le_bytes(bytes)))
-        }
-        1 => {
-            let s = read_string(data, cursor)?;
-            Ok(Constant::String(intern(&s)))
-        }
-        2 => {
-            let name = read_string(data, cursor)?;
-            let arity = read_u32(data, cursor)? as usize;
-            if *cursor >= data.len() {
-                return Err("Unexpected end of file".to_string());
-            }
-            let is_variadic = data[*cursor] != 0;
-            *cursor += 1;
-
-            if *cursor >= data.len() {
-                return Err("Unexpected end of file".to_string());
-            }
-            let is_async = data[*cursor] != 0;
-            *cursor += 1;
-
-            let upvalue_count = read_u32(data, cursor)? as usize;
-            let mut upvalues = Vec::with_capacity(upvalue_count);
-            for _ in 0..upvalue_count {
-                if *cursor + 2 > data.len() {
-                    return Err("Unexpected end of file".to_string());
-                }
-                let index = data[*cursor];
-                let is_local = data[*cursor + 1] != 0;
-                *cursor += 2;
-                upvalues.push(UpvalueInfo { index, is_local });
-            }
-
-            let param_count = read_u32(data, cursor)? as usize;
-            let mut param_names = Vec::with_capacity(param_count);
-            for _ in 0..param_count {
-                param_names.push(read_string(data, cursor)?);
-            }
-            let default_count = read_u32(data, cursor)? as usize;
-
-            if version == 2 {
-                if *cursor >= data.len() {
-                    return Err("Unexpected end of file".to_string());
-                }
-
-                *cursor += 1;
-            }
-            let decorator_count = read_u32(data, cursor)? as usize;
-            let mut decorators = Vec::with_capacity(decorator_count);
-            for _ in 0..decorator_count {
-                decorators.push(read_string(data, cursor)?);
-            }
-
-            let (namespace_context, class_context) = if version >= 4 {
-                let ns = read_optional_string(data, cursor)?;
-                let cls = read_optional_string(data, cursor)?;
-                (ns, cls)
-            } else {
-                (None, None)
-            };
-            let chunk_len = read_u32(data, cursor)? as usize;
-            if *cursor + chunk_len > data.len() {
-                return Err("Unexpected end of file".to_string());
-            }
-            let chunk = deserialize(&data[*cursor..*cursor + chunk_len])?;
-            *cursor += chunk_len;
-            Ok(Constant::Function(FunctionConstant {
-                name,
-                arity,
-                is_variadic,
-                is_async,
-                upvalue_count,
-                upvalues,
-                chunk,
-                file: String::new(),
-                param_names,
-                default_count,
-                decorators,
-                namespace_context,
-                class_context,
-            }))
-        }
-        3 => {
-            let name = read_string(data, cursor)?;
-            let method_count = read_u32(data, cursor)? as usize;
-            let mut methods = Vec::with_capacity(method_count);
-            for _ in 0..method_count {
-                let method_name = read_string(data, cursor)?;
-                let idx = read_u32(data, cursor)? as usize;
-                let is_static = data[*cursor] != 0;
-                *cursor += 1;
-                methods.push((method_name, idx, is_static));
-            }
-            Ok(Constant::Class(ClassConstant { name, methods }))
-        }
-        _ => Err(format!("Unknown constant type: {}", tag)),
-    }
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[*cursor..*cursor + 8]);
+    *cursor += 8;
+    Ok(f64::from_le_bytes(bytes))
 }
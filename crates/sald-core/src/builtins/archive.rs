@@ -0,0 +1,592 @@
+//! Zip and tar archives
+//! The format follows the archive's extension: `.zip`, `.tar`, `.tar.gz` or
+//! `.tgz`. Extraction refuses entries that would land outside the destination,
+//! whether through `..`, an absolute path, a link pointing out of it or a
+//! symlink already extracted on the way
+
+use super::docs::ClassDoc;
+use super::{bytes_to_value, check_arity, check_arity_range, get_string_arg};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+struct Entry {
+    name: String,
+    size: u64,
+    is_dir: bool,
+}
+
+pub fn create_archive_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("list".to_string(), archive_list);
+    static_methods.insert("extract".to_string(), archive_extract);
+    static_methods.insert("create".to_string(), archive_create);
+    static_methods.insert("read".to_string(), archive_read);
+    static_methods.insert("readText".to_string(), archive_read_text);
+
+    Class::new_with_static("Archive", static_methods)
+}
+
+/// API documentation for the `Archive` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Archive", "Create and extract zip, tar and tar.gz archives")
+        .method(
+            "list",
+            "list(path)",
+            "Get the entries as dicts with name, size and isDir",
+        )
+        .method(
+            "extract",
+            "extract(path, dest, entries?)",
+            "Extract all entries, or only the named files and directories",
+        )
+        .method(
+            "create",
+            "create(path, sources, base?)",
+            "Archive files and directories, naming entries relative to base",
+        )
+        .method("read", "read(path, name)", "Read one entry as a byte array")
+        .method("readText", "readText(path, name)", "Read one entry as text")
+}
+
+fn format_of(path: &str) -> Result<Format, String> {
+    let lower = path.to_lowercase();
+    if lower.ends_with(".zip") {
+        Ok(Format::Zip)
+    } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+        Ok(Format::TarGz)
+    } else if lower.ends_with(".tar") {
+        Ok(Format::Tar)
+    } else {
+        Err(format!(
+            "Cannot tell the archive format of '{}', expected .zip, .tar, .tar.gz or .tgz",
+            path
+        ))
+    }
+}
+
+fn open(path: &str) -> Result<File, String> {
+    File::open(crate::workspace::resolve(path))
+        .map_err(|e| format!("Cannot open '{}': {}", path, e))
+}
+
+fn zip_archive(path: &str) -> Result<zip::ZipArchive<File>, String> {
+    zip::ZipArchive::new(open(path)?).map_err(|e| format!("Invalid zip '{}': {}", path, e))
+}
+
+fn tar_archive(path: &str, format: Format) -> Result<tar::Archive<Box<dyn Read>>, String> {
+    let file = open(path)?;
+    let reader: Box<dyn Read> = if format == Format::TarGz {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// Entry name as a relative path, or None if it could escape the destination.
+fn safe_path(name: &str) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+/// Whether a link stored at `entry` pointing at `target` stays inside the archive root.
+fn link_is_safe(entry: &Path, target: &Path) -> bool {
+    let mut depth = entry.components().count() as i64 - 1;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Creates the directories leading to `relative` under `root`, or returns false
+/// if any existing component on the way is a symlink or the entry's parent
+/// resolves outside `root`.
+fn prepare_parent(root: &Path, relative: &Path) -> Result<bool, String> {
+    let mut path = root.to_path_buf();
+    for component in relative.components() {
+        path.push(component);
+        match std::fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => return Ok(false),
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    let out = root.join(relative);
+    let Some(parent) = out.parent() else {
+        return Ok(true);
+    };
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let parent = parent.canonicalize().map_err(|e| e.to_string())?;
+    let root = root.canonicalize().map_err(|e| e.to_string())?;
+    Ok(parent.starts_with(root))
+}
+
+fn is_selected(name: &str, selection: &Option<Vec<String>>) -> bool {
+    let Some(selection) = selection else {
+        return true;
+    };
+    let name = name.trim_end_matches('/');
+    selection.iter().any(|wanted| {
+        let wanted = wanted.trim_end_matches('/');
+        name == wanted
+            || name
+                .strip_prefix(wanted)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+fn entries(path: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    match format_of(path)? {
+        Format::Zip => {
+            let mut archive = zip_archive(path)?;
+            for i in 0..archive.len() {
+                let file = archive.by_index(i).map_err(|e| e.to_string())?;
+                entries.push(Entry {
+                    name: file.name().to_string(),
+                    size: file.size(),
+                    is_dir: file.is_dir(),
+                });
+            }
+        }
+        format => {
+            let mut archive = tar_archive(path, format)?;
+            for entry in archive.entries().map_err(|e| e.to_string())? {
+                let entry = entry.map_err(|e| e.to_string())?;
+                let name = entry.path().map_err(|e| e.to_string())?;
+                entries.push(Entry {
+                    name: name.to_string_lossy().replace('\\', "/"),
+                    size: entry.header().size().unwrap_or(0),
+                    is_dir: entry.header().entry_type().is_dir(),
+                });
+            }
+        }
+    }
+    Ok(entries)
+}
+
+fn archive_list(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+
+    let list = entries(&path)?
+        .into_iter()
+        .map(|entry| {
+            let mut dict = FxHashMap::default();
+            dict.insert("name".to_string(), Value::String(Rc::from(entry.name)));
+            dict.insert("size".to_string(), Value::Number(entry.size as f64));
+            dict.insert("isDir".to_string(), Value::Boolean(entry.is_dir));
+            Value::Dictionary(Rc::new(RefCell::new(dict.into())))
+        })
+        .collect();
+    Ok(Value::Array(Rc::new(RefCell::new(list))))
+}
+
+fn archive_extract(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    let dest = crate::workspace::resolve(&get_string_arg(&args[1], "dest")?);
+    let selection = match args.get(2) {
+        None | Some(Value::Null) => None,
+        Some(Value::Array(names)) => Some(
+            names
+                .borrow()
+                .iter()
+                .map(|name| get_string_arg(name, "entries"))
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        Some(other) => {
+            return Err(format!(
+                "Argument 'entries' must be an array of names, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    std::fs::create_dir_all(&dest)
+        .map_err(|e| format!("Cannot create '{}': {}", dest.display(), e))?;
+
+    let unsafe_entry = |name: &str| format!("Refusing to extract unsafe entry '{}'", name);
+    let mut extracted = Vec::new();
+    match format_of(&path)? {
+        Format::Zip => {
+            let mut archive = zip_archive(&path)?;
+            for i in 0..archive.len() {
+                let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
+                let name = file.name().to_string();
+                if !is_selected(&name, &selection) {
+                    continue;
+                }
+                let relative = safe_path(&name).ok_or_else(|| unsafe_entry(&name))?;
+                if !prepare_parent(&dest, &relative)? {
+                    return Err(unsafe_entry(&name));
+                }
+                let out = dest.join(relative);
+                if file.is_dir() {
+                    std::fs::create_dir_all(&out).map_err(|e| e.to_string())?;
+                } else {
+                    let mut target = File::create(&out)
+                        .map_err(|e| format!("Cannot create '{}': {}", out.display(), e))?;
+                    std::io::copy(&mut file, &mut target).map_err(|e| e.to_string())?;
+                }
+                extracted.push(Value::String(Rc::from(name)));
+            }
+        }
+        format => {
+            let mut archive = tar_archive(&path, format)?;
+            for entry in archive.entries().map_err(|e| e.to_string())? {
+                let mut entry = entry.map_err(|e| e.to_string())?;
+                let name = entry
+                    .path()
+                    .map_err(|e| e.to_string())?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                if !is_selected(&name, &selection) {
+                    continue;
+                }
+                let relative = safe_path(&name).ok_or_else(|| unsafe_entry(&name))?;
+                if let Some(target) = entry.link_name().map_err(|e| e.to_string())? {
+                    if !link_is_safe(&relative, &target) {
+                        return Err(unsafe_entry(&name));
+                    }
+                }
+                if !prepare_parent(&dest, &relative)? {
+                    return Err(unsafe_entry(&name));
+                }
+                let unpacked = entry
+                    .unpack_in(&dest)
+                    .map_err(|e| format!("Cannot extract '{}': {}", name, e))?;
+                if !unpacked {
+                    return Err(unsafe_entry(&name));
+                }
+                extracted.push(Value::String(Rc::from(name)));
+            }
+        }
+    }
+    Ok(Value::Array(Rc::new(RefCell::new(extracted.into()))))
+}
+
+/// Files and directories under each source, named relative to `base`, or to
+/// the source's parent directory when no base is given.
+fn collect_sources(
+    sources: &[String],
+    base: Option<&Path>,
+) -> Result<Vec<(PathBuf, String)>, String> {
+    fn walk(path: &Path, root: &Path, out: &mut Vec<(PathBuf, String)>) -> Result<(), String> {
+        let name = path
+            .strip_prefix(root)
+            .map_err(|_| format!("'{}' is not under '{}'", path.display(), root.display()))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if path.is_dir() {
+            if !name.is_empty() {
+                out.push((path.to_path_buf(), format!("{}/", name)));
+            }
+            let mut children: Vec<PathBuf> = std::fs::read_dir(path)
+                .map_err(|e| format!("Cannot read '{}': {}", path.display(), e))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect();
+            children.sort();
+            for child in children {
+                walk(&child, root, out)?;
+            }
+        } else if path.exists() {
+            out.push((path.to_path_buf(), name));
+        } else {
+            return Err(format!("'{}' does not exist", path.display()));
+        }
+        Ok(())
+    }
+
+    let mut out = Vec::new();
+    for source in sources {
+        let path = crate::workspace::resolve(source);
+        let root = match base {
+            Some(base) => base.to_path_buf(),
+            None => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        walk(&path, &root, &mut out)?;
+    }
+    Ok(out)
+}
+
+fn write_tar<W: Write>(writer: W, files: &[(PathBuf, String)]) -> Result<W, String> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+    for (path, name) in files {
+        builder
+            .append_path_with_name(path, name)
+            .map_err(|e| format!("Cannot add '{}': {}", path.display(), e))?;
+    }
+    builder.into_inner().map_err(|e| e.to_string())
+}
+
+fn archive_create(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    let sources = match &args[1] {
+        Value::String(source) => vec![source.to_string()],
+        Value::Array(sources) => sources
+            .borrow()
+            .iter()
+            .map(|source| get_string_arg(source, "sources"))
+            .collect::<Result<Vec<_>, _>>()?,
+        other => {
+            return Err(format!(
+                "Argument 'sources' must be a path or array of paths, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let base = match args.get(2) {
+        None | Some(Value::Null) => None,
+        Some(base) => Some(crate::workspace::resolve(&get_string_arg(base, "base")?)),
+    };
+
+    let format = format_of(&path)?;
+    let files = collect_sources(&sources, base.as_deref())?;
+    let file = File::create(crate::workspace::resolve(&path))
+        .map_err(|e| format!("Cannot create '{}': {}", path, e))?;
+    match format {
+        Format::Zip => {
+            let mut zip = zip::ZipWriter::new(file);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (source, name) in &files {
+                if name.ends_with('/') {
+                    zip.add_directory(name.as_str(), options)
+                        .map_err(|e| e.to_string())?;
+                } else {
+                    zip.start_file(name.as_str(), options)
+                        .map_err(|e| e.to_string())?;
+                    std::io::copy(&mut open(&source.to_string_lossy())?, &mut zip)
+                        .map_err(|e| format!("Cannot add '{}': {}", source.display(), e))?;
+                }
+            }
+            zip.finish().map_err(|e| e.to_string())?;
+        }
+        Format::Tar => {
+            write_tar(file, &files)?;
+        }
+        Format::TarGz => {
+            let encoder = write_tar(GzEncoder::new(file, flate2::Compression::default()), &files)?;
+            encoder.finish().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(Value::Number(files.len() as f64))
+}
+
+fn read_entry(args: &[Value]) -> Result<Vec<u8>, String> {
+    check_arity(2, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    let name = get_string_arg(&args[1], "name")?;
+
+    let mut data = Vec::new();
+    match format_of(&path)? {
+        Format::Zip => {
+            let mut archive = zip_archive(&path)?;
+            let mut file = archive
+                .by_name(&name)
+                .map_err(|_| format!("No entry '{}' in '{}'", name, path))?;
+            file.read_to_end(&mut data).map_err(|e| e.to_string())?;
+            return Ok(data);
+        }
+        format => {
+            let mut archive = tar_archive(&path, format)?;
+            for entry in archive.entries().map_err(|e| e.to_string())? {
+                let mut entry = entry.map_err(|e| e.to_string())?;
+                let entry_name = entry.path().map_err(|e| e.to_string())?;
+                if entry_name.to_string_lossy().replace('\\', "/") == name {
+                    entry.read_to_end(&mut data).map_err(|e| e.to_string())?;
+                    return Ok(data);
+                }
+            }
+        }
+    }
+    Err(format!("No entry '{}' in '{}'", name, path))
+}
+
+fn archive_read(args: &[Value]) -> Result<Value, String> {
+    Ok(bytes_to_value(&read_entry(args)?))
+}
+
+fn archive_read_text(args: &[Value]) -> Result<Value, String> {
+    let text =
+        String::from_utf8(read_entry(args)?).map_err(|_| "Entry is not valid UTF-8".to_string())?;
+    Ok(Value::String(Rc::from(text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::eval_err;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_refuses_chained_symlinks() {
+        let dir = std::env::temp_dir().join(format!("sald-archive-chain-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("chain.tar");
+
+        let mut builder = tar::Builder::new(std::fs::File::create(&archive).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder
+            .append_data(&mut header, "a/", std::io::empty())
+            .unwrap();
+        for (link, target) in [("a/b", ".."), ("a/b/c", "..")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_mode(0o777);
+            header.set_size(0);
+            builder.append_link(&mut header, link, target).unwrap();
+        }
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(1);
+        builder
+            .append_data(&mut header, "a/b/c/pwned.txt", &b"x"[..])
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let root = dir.to_str().unwrap().replace('\\', "/");
+        let error = eval_err(&format!(
+            "Archive.extract(\"{0}/chain.tar\", \"{0}/out/dest\")",
+            root
+        ));
+        let leaked = dir.join("pwned.txt").exists() || dir.join("out/pwned.txt").exists();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert!(
+            error.contains("Refusing to extract unsafe entry"),
+            "{}",
+            error
+        );
+        assert!(!leaked);
+    }
+
+    #[test]
+    fn test_archive_create_list_extract() {
+        let dir = std::env::temp_dir().join(format!("sald-archive-{}", std::process::id()));
+        let site = dir.join("site");
+        std::fs::create_dir_all(site.join("css")).unwrap();
+        std::fs::write(site.join("index.html"), "<h1>hi</h1>").unwrap();
+        std::fs::write(site.join("css/main.css"), "h1 {}").unwrap();
+        let root = dir.to_str().unwrap().replace('\\', "/");
+
+        // An entry that climbs out of the destination must be refused
+        let evil = std::fs::File::create(dir.join("evil.zip")).unwrap();
+        let mut zip = zip::ZipWriter::new(evil);
+        zip.start_file("../escaped.txt", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, b"x").unwrap();
+        zip.finish().unwrap();
+
+        let mut engine = Engine::new();
+        engine
+            .eval(&format!(
+                "let root = \"{}\"\n\
+                 let counts = []\n\
+                 let texts = []\n\
+                 for name in [\"site.zip\", \"site.tar.gz\"] {{\n\
+                     let archive = root + \"/\" + name\n\
+                     counts.push(Archive.create(archive, root + \"/site\"))\n\
+                     counts.push(Archive.list(archive).length())\n\
+                     let out = root + \"/out-\" + name\n\
+                     counts.push(Archive.extract(archive, out, [\"site/css\"]).length())\n\
+                     texts.push(File.read(out + \"/site/css/main.css\"))\n\
+                     texts.push(Path.exists(out + \"/site/index.html\") ? \"leaked\" : \"skipped\")\n\
+                     texts.push(Archive.readText(archive, \"site/index.html\"))\n\
+                 }}",
+                root
+            ))
+            .unwrap();
+        let escaped = engine.eval(&format!(
+            "Archive.extract(\"{0}/evil.zip\", \"{0}/evil\")",
+            root
+        ));
+
+        let counts: Vec<f64> = engine.eval_as("counts").unwrap();
+        let texts: Vec<String> = engine.eval_as("texts").unwrap();
+        let leaked = dir.join("escaped.txt").exists();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(counts, [4.0, 4.0, 2.0, 4.0, 4.0, 2.0]);
+        assert_eq!(
+            texts,
+            [
+                "h1 {}",
+                "skipped",
+                "<h1>hi</h1>",
+                "h1 {}",
+                "skipped",
+                "<h1>hi</h1>"
+            ]
+        );
+        assert!(escaped.is_err());
+        assert!(!leaked);
+    }
+
+    #[test]
+    fn test_archive_paths_are_relative_to_the_project() {
+        let dir = std::env::temp_dir().join(format!("sald-archive-root-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("site")).unwrap();
+        std::fs::write(dir.join("site/index.html"), "<h1>hi</h1>").unwrap();
+
+        let mut engine = Engine::new();
+        engine.vm().set_project_root(&dir);
+        let result: (f64, Vec<String>, String) = engine
+            .eval_as(
+                "let count = Archive.create(\"site.tar\", \"site/index.html\", \"site\")\n\
+                 let r = [count, Archive.extract(\"site.tar\", \"out\"), Archive.readText(\"site.tar\", \"index.html\")]\n\
+                 r",
+            )
+            .unwrap();
+        let extracted = std::fs::read_to_string(dir.join("out/index.html"));
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(
+            result,
+            (
+                1.0,
+                vec!["index.html".to_string()],
+                "<h1>hi</h1>".to_string()
+            )
+        );
+        assert_eq!(extracted.unwrap(), "<h1>hi</h1>");
+    }
+}
@@ -0,0 +1,799 @@
+//! Command line parsing
+//! `Args.new` starts a parser that scripts fill with flags, options,
+//! positionals and subcommands. `parse()` reads the script's arguments and
+//! returns a dict keyed by argument name; `--help` and usage errors raise the
+//! text to show, which the CLI prints before exiting with `args_exit`'s
+//! status, while `tryParse` reports them to the script
+
+use super::docs::ClassDoc;
+use super::string::display_width;
+use super::{check_arity, check_arity_range, get_string_arg, native_instance, native_state};
+use crate::vm::value::{Class, Dict, NativeInstanceFn, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// How a command line program ends after `parse` raised help or a usage error
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgsExit {
+    /// 0 after `--help`, 2 after a usage error
+    pub code: i32,
+    /// Help text for stdout or the usage error for stderr
+    pub output: String,
+}
+
+thread_local! {
+    /// The last help or usage error `parse` raised and its exit status
+    static RAISED: RefCell<Option<(String, i32)>> = const { RefCell::new(None) };
+}
+
+/// The exit a program owes when `thrown`, the value a script failed with,
+/// is help or a usage error raised by `parse`
+pub fn args_exit(thrown: &Value) -> Option<ArgsExit> {
+    let Value::String(message) = thrown else {
+        return None;
+    };
+    RAISED.with(|raised| match &*raised.borrow() {
+        Some((output, code)) if **output == **message => Some(ArgsExit {
+            code: *code,
+            output: output.clone(),
+        }),
+        _ => None,
+    })
+}
+
+fn raise_exit(output: String, code: i32) -> Result<Value, String> {
+    RAISED.with(|raised| *raised.borrow_mut() = Some((output.clone(), code)));
+    Err(output)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Flag,
+    Option,
+    Positional,
+}
+
+#[derive(Clone, Copy)]
+enum ValueType {
+    String,
+    Number,
+    Int,
+}
+
+#[derive(Clone)]
+struct Arg {
+    name: String,
+    kind: Kind,
+    value_type: ValueType,
+    short: Option<char>,
+    help: String,
+    default: Value,
+    required: bool,
+    multiple: bool,
+    choices: Vec<Value>,
+    env: Option<String>,
+}
+
+#[derive(Clone, Default)]
+struct Spec {
+    name: String,
+    about: String,
+    args: Vec<Arg>,
+    commands: Vec<(String, Rc<RefCell<Spec>>)>,
+}
+
+enum Parsed {
+    Values(FxHashMap<String, Value>),
+    Help(String),
+}
+
+/// A usage error together with the help of the command it happened in.
+struct Failure {
+    message: String,
+    help: String,
+}
+
+pub fn create_args_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("new".to_string(), args_new);
+
+    Class::new_with_static("Args", static_methods)
+}
+
+fn create_parser_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("flag".to_string(), parser_flag);
+    instance_methods.insert("option".to_string(), parser_option);
+    instance_methods.insert("positional".to_string(), parser_positional);
+    instance_methods.insert("command".to_string(), parser_command);
+    instance_methods.insert("help".to_string(), parser_help);
+    instance_methods.insert("parse".to_string(), parser_parse);
+    instance_methods.insert("tryParse".to_string(), parser_try_parse);
+
+    Class::new_with_instance("ArgParser", instance_methods, None)
+}
+
+/// API documentation for the `Args` and `ArgParser` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new("Args", "Declarative command line parsing").method(
+            "new",
+            "new(name, about?)",
+            "Start an ArgParser for a program or subcommand",
+        ),
+        ClassDoc::new(
+            "ArgParser",
+            "Argument definitions; each method returns the parser for chaining. \
+             Options dicts accept short, help, default, type (\"string\", \"number\", \"int\"), \
+             required, multiple, choices and env",
+        )
+        .method(
+            "flag",
+            "flag(name, options?)",
+            "Boolean --name switch; multiple counts repeats",
+        )
+        .method(
+            "option",
+            "option(name, options?)",
+            "--name value option; multiple collects an array",
+        )
+        .method(
+            "positional",
+            "positional(name, options?)",
+            "Positional argument; multiple takes the rest",
+        )
+        .method(
+            "command",
+            "command(name, parser)",
+            "Subcommand parsed by another ArgParser",
+        )
+        .method("help", "help()", "Generated help text")
+        .method(
+            "parse",
+            "parse(argv?)",
+            "Parse the script's arguments; raises help or usage errors",
+        )
+        .method(
+            "tryParse",
+            "tryParse(argv?)",
+            "Parse, raising usage errors; --help returns {help: text}",
+        ),
+    ]
+}
+
+fn string(s: impl Into<String>) -> Value {
+    Value::String(Rc::from(s.into()))
+}
+
+fn dict(values: FxHashMap<String, Value>) -> Value {
+    Value::Dictionary(Rc::new(RefCell::new(values.into())))
+}
+
+fn args_new(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let spec = Spec {
+        name: get_string_arg(&args[0], "name")?,
+        about: match args.get(1) {
+            None | Some(Value::Null) => String::new(),
+            Some(value) => get_string_arg(value, "about")?,
+        },
+        ..Spec::default()
+    };
+    Ok(native_instance(create_parser_class(), spec))
+}
+
+fn parser_spec(recv: &Value) -> Result<Rc<RefCell<Spec>>, String> {
+    native_state(recv, "ArgParser")
+}
+
+fn define(recv: &Value, args: &[Value], kind: Kind) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+        return Err(format!("Invalid argument name '{}'", name));
+    }
+    let options = match args.get(1) {
+        None | Some(Value::Null) => Dict::new(),
+        Some(Value::Dictionary(options)) => options.borrow().clone(),
+        Some(other) => {
+            return Err(format!(
+                "Argument 'options' must be a dictionary, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let text = |key: &str| -> Result<Option<String>, String> {
+        match options.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => get_string_arg(value, key).map(Some),
+        }
+    };
+    let boolean = |key: &str| matches!(options.get(key), Some(Value::Boolean(true)));
+
+    let short = match text("short")? {
+        None => None,
+        Some(short) => {
+            let mut chars = short.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_alphanumeric() => Some(c),
+                _ => {
+                    return Err(format!(
+                        "Option 'short' must be one character, got '{}'",
+                        short
+                    ))
+                }
+            }
+        }
+    };
+    let value_type = match text("type")?.as_deref() {
+        None | Some("string") => ValueType::String,
+        Some("number") => ValueType::Number,
+        Some("int") => ValueType::Int,
+        Some(other) => {
+            return Err(format!(
+                "Unknown type '{}', expected string, number or int",
+                other
+            ))
+        }
+    };
+    let choices = match options.get("choices") {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(choices)) => choices.borrow().to_vec(),
+        Some(other) => {
+            return Err(format!(
+                "Option 'choices' must be an array, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let default = options.get("default").cloned().unwrap_or(Value::Null);
+    let required = match options.get("required") {
+        Some(Value::Boolean(required)) => *required,
+        // Positionals are required unless they have a default
+        _ => kind == Kind::Positional && default.is_null(),
+    };
+    let arg = Arg {
+        name,
+        kind,
+        value_type,
+        short: if kind == Kind::Positional {
+            None
+        } else {
+            short
+        },
+        help: text("help")?.unwrap_or_default(),
+        default,
+        required,
+        multiple: boolean("multiple"),
+        choices,
+        env: text("env")?,
+    };
+
+    let spec = parser_spec(recv)?;
+    {
+        let mut spec = spec.borrow_mut();
+        if arg.name == "help" || arg.short == Some('h') {
+            return Err("--help and -h are reserved for the generated help".to_string());
+        }
+        for existing in &spec.args {
+            if existing.name == arg.name {
+                return Err(format!("Argument '{}' is already defined", arg.name));
+            }
+            if arg.short.is_some() && existing.short == arg.short {
+                return Err(format!(
+                    "Short option '-{}' is already used by '{}'",
+                    existing.short.unwrap_or_default(),
+                    existing.name
+                ));
+            }
+        }
+        if kind == Kind::Positional {
+            let earlier = spec.args.iter().filter(|a| a.kind == Kind::Positional);
+            for earlier in earlier {
+                if earlier.multiple {
+                    return Err(format!(
+                        "Positional '{}' cannot follow '{}', which takes the rest",
+                        arg.name, earlier.name
+                    ));
+                }
+                if arg.required && !earlier.required {
+                    return Err(format!(
+                        "Required positional '{}' cannot follow optional '{}'",
+                        arg.name, earlier.name
+                    ));
+                }
+            }
+        }
+        spec.args.push(arg);
+    }
+    Ok(recv.clone())
+}
+
+fn parser_flag(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    define(recv, args, Kind::Flag)
+}
+
+fn parser_option(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    define(recv, args, Kind::Option)
+}
+
+fn parser_positional(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    define(recv, args, Kind::Positional)
+}
+
+fn parser_command(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    let command = parser_spec(&args[1]).map_err(|_| {
+        format!(
+            "Argument 'parser' must be an ArgParser, got {}",
+            args[1].type_name()
+        )
+    })?;
+    let spec = parser_spec(recv)?;
+    if Rc::ptr_eq(&command, &spec) {
+        return Err("A parser cannot be its own subcommand".to_string());
+    }
+    let mut spec = spec.borrow_mut();
+    if spec.commands.iter().any(|(existing, _)| *existing == name) {
+        return Err(format!("Command '{}' is already defined", name));
+    }
+    spec.commands.push((name, command));
+    Ok(recv.clone())
+}
+
+fn metavar(arg: &Arg) -> &'static str {
+    match arg.value_type {
+        ValueType::String => "<value>",
+        ValueType::Number => "<number>",
+        ValueType::Int => "<int>",
+    }
+}
+
+fn positional_usage(arg: &Arg) -> String {
+    let dots = if arg.multiple { "..." } else { "" };
+    if arg.required {
+        format!("<{}>{}", arg.name, dots)
+    } else {
+        format!("[{}]{}", arg.name, dots)
+    }
+}
+
+fn help_text(spec: &Spec, program: &str) -> String {
+    let positionals: Vec<&Arg> = spec
+        .args
+        .iter()
+        .filter(|arg| arg.kind == Kind::Positional)
+        .collect();
+    let options: Vec<&Arg> = spec
+        .args
+        .iter()
+        .filter(|arg| arg.kind != Kind::Positional)
+        .collect();
+
+    let mut usage = format!("Usage: {} [options]", program);
+    for arg in &positionals {
+        usage.push(' ');
+        usage.push_str(&positional_usage(arg));
+    }
+    if !spec.commands.is_empty() {
+        usage.push_str(" <command>");
+    }
+
+    // (left column, description) rows per section
+    let mut sections: Vec<(&str, Vec<(String, String)>)> = Vec::new();
+    let describe = |arg: &Arg| {
+        let mut text = arg.help.clone();
+        if arg.required && arg.kind == Kind::Option {
+            text.push_str(" (required)");
+        }
+        if !arg.default.is_null() {
+            text.push_str(&format!(" (default: {})", arg.default));
+        }
+        if !arg.choices.is_empty() {
+            let choices: Vec<String> = arg.choices.iter().map(|c| c.to_string()).collect();
+            text.push_str(&format!(" [{}]", choices.join(", ")));
+        }
+        if let Some(env) = &arg.env {
+            text.push_str(&format!(" [env: {}]", env));
+        }
+        text.trim().to_string()
+    };
+    if !positionals.is_empty() {
+        let rows = positionals
+            .iter()
+            .map(|arg| (positional_usage(arg), describe(arg)))
+            .collect();
+        sections.push(("Arguments", rows));
+    }
+    let mut rows: Vec<(String, String)> = options
+        .iter()
+        .map(|arg| {
+            let short = match arg.short {
+                Some(short) => format!("-{}, ", short),
+                None => "    ".to_string(),
+            };
+            let value = match arg.kind {
+                Kind::Option => format!(" {}", metavar(arg)),
+                _ => String::new(),
+            };
+            (format!("{}--{}{}", short, arg.name, value), describe(arg))
+        })
+        .collect();
+    rows.push(("-h, --help".to_string(), "Show this help".to_string()));
+    sections.push(("Options", rows));
+    if !spec.commands.is_empty() {
+        let rows = spec
+            .commands
+            .iter()
+            .map(|(name, sub)| (name.clone(), sub.borrow().about.clone()))
+            .collect();
+        sections.push(("Commands", rows));
+    }
+
+    let width = sections
+        .iter()
+        .flat_map(|(_, rows)| rows.iter().map(|(left, _)| display_width(left)))
+        .max()
+        .unwrap_or(0);
+    let mut help = usage;
+    if !spec.about.is_empty() {
+        help.push_str(&format!("\n\n{}", spec.about));
+    }
+    for (title, rows) in sections {
+        help.push_str(&format!("\n\n{}:", title));
+        for (left, text) in rows {
+            let pad = " ".repeat(width - display_width(&left));
+            let line = format!("  {}{}  {}", left, pad, text);
+            help.push('\n');
+            help.push_str(line.trim_end());
+        }
+    }
+    help
+}
+
+fn convert(arg: &Arg, raw: &str) -> Result<Value, String> {
+    let value = match arg.value_type {
+        ValueType::String => string(raw),
+        ValueType::Number => raw
+            .trim()
+            .parse::<f64>()
+            .map(Value::Number)
+            .map_err(|_| format!("'{}' expects a number, got '{}'", arg.name, raw))?,
+        ValueType::Int => raw
+            .trim()
+            .parse::<i64>()
+            .map(|n| Value::Number(n as f64))
+            .map_err(|_| format!("'{}' expects an integer, got '{}'", arg.name, raw))?,
+    };
+    if !arg.choices.is_empty() && !arg.choices.contains(&value) {
+        let choices: Vec<String> = arg.choices.iter().map(|c| c.to_string()).collect();
+        return Err(format!(
+            "'{}' must be one of {}, got '{}'",
+            arg.name,
+            choices.join(", "),
+            raw
+        ));
+    }
+    Ok(value)
+}
+
+fn store(values: &mut FxHashMap<String, Value>, arg: &Arg, raw: &str) -> Result<(), String> {
+    let value = convert(arg, raw)?;
+    if arg.multiple {
+        let list = values
+            .entry(arg.name.clone())
+            .or_insert_with(|| Value::Array(Rc::new(RefCell::new(Vec::new().into()))));
+        if let Value::Array(list) = list {
+            list.borrow_mut().push(value);
+        }
+    } else {
+        values.insert(arg.name.clone(), value);
+    }
+    Ok(())
+}
+
+fn set_flag(values: &mut FxHashMap<String, Value>, arg: &Arg, on: bool) {
+    let value = if !arg.multiple {
+        Value::Boolean(on)
+    } else if !on {
+        Value::Number(0.0)
+    } else {
+        match values.get(&arg.name) {
+            Some(Value::Number(count)) => Value::Number(count + 1.0),
+            _ => Value::Number(1.0),
+        }
+    };
+    values.insert(arg.name.clone(), value);
+}
+
+/// Whether `arg` should be read as a value, such as a negative number,
+/// rather than as options.
+fn is_value(spec: &Spec, arg: &str) -> bool {
+    arg == "-"
+        || !arg.starts_with('-')
+        || (arg[1..].parse::<f64>().is_ok()
+            && !spec
+                .args
+                .iter()
+                .any(|a| a.short.is_some_and(|s| s.is_ascii_digit())))
+}
+
+fn parse_spec(spec: &RefCell<Spec>, argv: &[String], program: &str) -> Result<Parsed, Failure> {
+    let spec = spec.borrow().clone();
+    let fail = |message: String| Failure {
+        message,
+        help: help_text(&spec, program),
+    };
+    let find_long = |name: &str| {
+        spec.args
+            .iter()
+            .find(|a| a.kind != Kind::Positional && a.name == name)
+    };
+
+    let mut values: FxHashMap<String, Value> = FxHashMap::default();
+    let mut positionals: Vec<&str> = Vec::new();
+    let mut command = None;
+    let mut only_positionals = false;
+    let mut index = 0;
+    while index < argv.len() {
+        let arg = argv[index].as_str();
+        index += 1;
+
+        if only_positionals || is_value(&spec, arg) {
+            if !spec.commands.is_empty() && positionals.is_empty() {
+                if let Some((name, sub)) = spec.commands.iter().find(|(name, _)| name == arg) {
+                    let program = format!("{} {}", program, name);
+                    match parse_spec(sub, &argv[index..], &program)? {
+                        Parsed::Help(help) => return Ok(Parsed::Help(help)),
+                        Parsed::Values(sub_values) => command = Some((name.clone(), sub_values)),
+                    }
+                    break;
+                }
+                if !spec.args.iter().any(|a| a.kind == Kind::Positional) {
+                    return Err(fail(format!("Unknown command '{}'", arg)));
+                }
+            }
+            positionals.push(arg);
+            continue;
+        }
+        if arg == "--" {
+            only_positionals = true;
+            continue;
+        }
+        if arg == "--help" || arg == "-h" {
+            return Ok(Parsed::Help(help_text(&spec, program)));
+        }
+
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (long, None),
+            };
+            let (opt, on) = match find_long(name) {
+                Some(opt) => (opt, true),
+                None => match name.strip_prefix("no-").and_then(find_long) {
+                    Some(opt) if opt.kind == Kind::Flag => (opt, false),
+                    _ => return Err(fail(format!("Unknown option '--{}'", name))),
+                },
+            };
+            if opt.kind == Kind::Flag {
+                if inline.is_some() {
+                    return Err(fail(format!("Flag '--{}' does not take a value", name)));
+                }
+                set_flag(&mut values, opt, on);
+                continue;
+            }
+            let raw = match inline {
+                Some(raw) => raw,
+                None if index < argv.len() => {
+                    index += 1;
+                    argv[index - 1].as_str()
+                }
+                None => return Err(fail(format!("Option '--{}' needs a value", name))),
+            };
+            store(&mut values, opt, raw).map_err(fail)?;
+            continue;
+        }
+
+        // A cluster of short options such as -vf or -n5
+        let shorts = &arg[1..];
+        for (offset, short) in shorts.char_indices() {
+            let Some(opt) = spec.args.iter().find(|a| a.short == Some(short)) else {
+                return Err(fail(format!("Unknown option '-{}'", short)));
+            };
+            if opt.kind == Kind::Flag {
+                set_flag(&mut values, opt, true);
+                continue;
+            }
+            let rest = &shorts[offset + short.len_utf8()..];
+            let raw = if !rest.is_empty() {
+                rest.trim_start_matches('=')
+            } else if index < argv.len() {
+                index += 1;
+                argv[index - 1].as_str()
+            } else {
+                return Err(fail(format!("Option '-{}' needs a value", short)));
+            };
+            store(&mut values, opt, raw).map_err(fail)?;
+            break;
+        }
+    }
+
+    let mut rest = positionals.into_iter();
+    for arg in &spec.args {
+        match arg.kind {
+            Kind::Positional if arg.multiple => {
+                for raw in rest.by_ref() {
+                    store(&mut values, arg, raw).map_err(fail)?;
+                }
+            }
+            Kind::Positional => {
+                if let Some(raw) = rest.next() {
+                    store(&mut values, arg, raw).map_err(fail)?;
+                }
+            }
+            _ => {}
+        }
+        if values.contains_key(&arg.name) {
+            continue;
+        }
+        if let Some(raw) = arg.env.as_ref().and_then(|env| std::env::var(env).ok()) {
+            match arg.kind {
+                Kind::Flag => {
+                    let on = !matches!(raw.as_str(), "" | "0" | "false" | "no" | "off");
+                    set_flag(&mut values, arg, on);
+                }
+                _ => store(&mut values, arg, &raw).map_err(fail)?,
+            }
+            continue;
+        }
+        if arg.required {
+            let shown = match arg.kind {
+                Kind::Positional => format!("argument <{}>", arg.name),
+                _ => format!("option '--{}'", arg.name),
+            };
+            return Err(fail(format!("Missing required {}", shown)));
+        }
+        let default = match arg.kind {
+            Kind::Flag if arg.default.is_null() && arg.multiple => Value::Number(0.0),
+            Kind::Flag if arg.default.is_null() => Value::Boolean(false),
+            _ if arg.default.is_null() && arg.multiple => {
+                Value::Array(Rc::new(RefCell::new(Vec::new().into())))
+            }
+            _ => arg.default.clone(),
+        };
+        values.insert(arg.name.clone(), default);
+    }
+    if let Some(extra) = rest.next() {
+        return Err(fail(format!("Unexpected argument '{}'", extra)));
+    }
+
+    if !spec.commands.is_empty() {
+        match command {
+            Some((name, sub_values)) => {
+                values.insert(name.clone(), dict(sub_values));
+                values.insert("command".to_string(), string(name));
+            }
+            None => {
+                values.insert("command".to_string(), Value::Null);
+            }
+        }
+    }
+    Ok(Parsed::Values(values))
+}
+
+fn argv_arg(args: &[Value]) -> Result<Vec<String>, String> {
+    check_arity_range(0, 1, args.len())?;
+    match args.first() {
+        None | Some(Value::Null) => Ok(super::process::script_args()),
+        Some(Value::Array(items)) => Ok(items.borrow().iter().map(|v| v.to_string()).collect()),
+        Some(other) => Err(format!(
+            "Argument 'argv' must be an array, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn parser_help(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let spec = parser_spec(recv)?;
+    let spec = spec.borrow();
+    Ok(string(help_text(&spec, &spec.name)))
+}
+
+fn parser_parse(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let argv = argv_arg(args)?;
+    let spec = parser_spec(recv)?;
+    let name = spec.borrow().name.clone();
+    match parse_spec(&spec, &argv, &name) {
+        Ok(Parsed::Values(values)) => Ok(dict(values)),
+        Ok(Parsed::Help(help)) => raise_exit(help, 0),
+        Err(failure) => {
+            let usage = failure.help.lines().next().unwrap_or_default();
+            raise_exit(
+                format!(
+                    "error: {}\n\n{}\n\nFor more information, try '--help'.",
+                    failure.message, usage
+                ),
+                2,
+            )
+        }
+    }
+}
+
+fn parser_try_parse(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let argv = argv_arg(args)?;
+    let spec = parser_spec(recv)?;
+    let name = spec.borrow().name.clone();
+    match parse_spec(&spec, &argv, &name) {
+        Ok(Parsed::Values(values)) => Ok(dict(values)),
+        Ok(Parsed::Help(help)) => {
+            let mut values = FxHashMap::default();
+            values.insert("help".to_string(), string(help));
+            Ok(dict(values))
+        }
+        Err(failure) => Err(failure.message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_args_parser() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let build = Args.new(\"build\").flag(\"release\", {\"short\": \"r\"})\n\
+                 let cli = Args.new(\"tool\")\n\
+                     .flag(\"verbose\", {\"short\": \"v\", \"multiple\": true})\n\
+                     .option(\"jobs\", {\"short\": \"j\", \"type\": \"int\", \"default\": 1})\n\
+                     .command(\"build\", build)\n\
+                 let parsed = cli.tryParse([\"-vvj\", \"4\", \"build\", \"-r\"])",
+            )
+            .unwrap();
+        let summary: Vec<f64> = engine
+            .eval_as(
+                "[parsed[\"verbose\"], parsed[\"jobs\"], parsed[\"build\"][\"release\"] ? 1 : 0]",
+            )
+            .unwrap();
+        assert_eq!(summary, [2.0, 4.0, 1.0]);
+        let command: String = engine.eval_as("parsed[\"command\"]").unwrap();
+        assert_eq!(command, "build");
+        let help: String = engine
+            .eval_as("cli.tryParse([\"--help\"])[\"help\"]")
+            .unwrap();
+        assert!(help.starts_with("Usage: tool [options] <command>"));
+        assert!(engine.eval("cli.tryParse([\"--jobs\", \"x\"])").is_err());
+    }
+
+    #[test]
+    fn test_parse_raises_help_and_usage_errors() {
+        let mut engine = Engine::new();
+        engine
+            .eval("let cli = Args.new(\"tool\").option(\"jobs\", {\"type\": \"int\"})")
+            .unwrap();
+        let help: String = engine
+            .eval_as("let h = null\ntry { cli.parse([\"--help\"]) } catch e { h = e }\nh")
+            .unwrap();
+        assert!(help.starts_with("Usage: tool [options]"));
+
+        assert!(engine.eval("cli.parse([\"--jobs\", \"x\"])").is_err());
+        let exit = engine.vm().uncaught().and_then(super::args_exit).unwrap();
+        assert_eq!(exit.code, 2);
+        assert!(exit.output.starts_with("error: "));
+        assert!(exit.output.ends_with("For more information, try '--help'."));
+
+        assert!(engine.eval("cli.parse([\"--help\"])").is_err());
+        let exit = engine.vm().uncaught().and_then(super::args_exit).unwrap();
+        assert_eq!((exit.code, exit.output), (0, help));
+
+        assert!(engine.eval("throw \"other\"").is_err());
+        assert!(engine.vm().uncaught().and_then(super::args_exit).is_none());
+    }
+}
@@ -1,8 +1,13 @@
-use super::{check_arity, get_number_arg, get_string_arg};
+use super::collections::key_with;
+use super::date::compare_dates;
+use super::docs::ClassDoc;
+use super::{array_mut, check_arity, check_arity_range, get_number_arg, get_string_arg};
 use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
 use crate::vm::value::{Class, NativeInstanceFn, Value};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::hash_map::Entry;
 use std::rc::Rc;
 
 pub fn create_array_class() -> Class {
@@ -34,6 +39,9 @@ pub fn create_array_class() -> Class {
     instance_methods.insert("fill".to_string(), array_fill);
     instance_methods.insert("flat".to_string(), array_flat);
     instance_methods.insert("toReversed".to_string(), array_to_reversed);
+    instance_methods.insert("zip".to_string(), array_zip);
+    instance_methods.insert("chunk".to_string(), array_chunk);
+    instance_methods.insert("window".to_string(), array_window);
 
     callable_methods.insert("map".to_string(), array_map);
     callable_methods.insert("filter".to_string(), array_filter);
@@ -44,22 +52,132 @@ pub fn create_array_class() -> Class {
     callable_methods.insert("some".to_string(), array_some);
     callable_methods.insert("every".to_string(), array_every);
     callable_methods.insert("sort".to_string(), array_sort);
+    callable_methods.insert("sortInPlace".to_string(), array_sort);
     callable_methods.insert("flatMap".to_string(), array_flat_map);
     callable_methods.insert("toSorted".to_string(), array_to_sorted);
+    callable_methods.insert("sortBy".to_string(), array_sort_by);
+    callable_methods.insert("groupBy".to_string(), array_group_by);
+    callable_methods.insert("unique".to_string(), array_unique);
+    callable_methods.insert("binarySearch".to_string(), array_binary_search);
+    callable_methods.insert("bisect".to_string(), array_bisect);
 
     let mut class = Class::new_with_instance("Array", instance_methods, Some(array_constructor));
     class.callable_native_instance_methods = callable_methods;
     class
 }
 
+/// API documentation for the `Array` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Array", "Array operations")
+        .method(
+            "range",
+            "range(end) / range(start, end, step?)",
+            "Create range array",
+        )
+        .method("length", "length()", "Get array length")
+        .method("push", "push(item)", "Add item to end")
+        .method("pop", "pop()", "Remove and return last item")
+        .method("shift", "shift()", "Remove and return first item")
+        .method("unshift", "unshift(item)", "Add item to beginning")
+        .method("first", "first()", "Get first item")
+        .method("last", "last()", "Get last item")
+        .method("get", "get(index)", "Get item at index")
+        .method("set", "set(index, value)", "Set item at index")
+        .method("at", "at(index)", "Get item at index (negative allowed)")
+        .method("contains", "contains(item)", "Check if contains item")
+        .method("indexOf", "indexOf(item)", "Find index of item")
+        .method(
+            "lastIndexOf",
+            "lastIndexOf(item)",
+            "Find last index of item",
+        )
+        .method("join", "join(separator)", "Join items to string")
+        .method("reverse", "reverse()", "Reverse in-place")
+        .method("toReversed", "toReversed()", "Return reversed copy")
+        .method("slice", "slice(start, end?)", "Get sub-array")
+        .method(
+            "splice",
+            "splice(start, deleteCount, ...items)",
+            "Remove/insert items",
+        )
+        .method("concat", "concat(other)", "Concatenate arrays")
+        .method("clear", "clear()", "Remove all items")
+        .method("isEmpty", "isEmpty()", "Check if empty")
+        .method("fill", "fill(value, start?, end?)", "Fill with value")
+        .method("flat", "flat(depth?)", "Flatten nested arrays")
+        .method("flatMap", "flatMap(fn)", "Map then flatten")
+        .method("map", "map(fn)", "Transform each item")
+        .method("filter", "filter(fn)", "Filter items")
+        .method("forEach", "forEach(fn)", "Iterate items")
+        .method("reduce", "reduce(fn, initial?)", "Reduce to single value")
+        .method("find", "find(fn)", "Find first matching item")
+        .method("findIndex", "findIndex(fn)", "Find index of first match")
+        .method("findLast", "findLast(fn)", "Find last matching item")
+        .method(
+            "findLastIndex",
+            "findLastIndex(fn)",
+            "Find index of last match",
+        )
+        .method("some", "some(fn)", "Check if any match")
+        .method("every", "every(fn)", "Check if all match")
+        .method(
+            "sort",
+            "sort(fn?)",
+            "Stable sort in-place, by fn(a, b) < 0 or by default null < booleans < numbers < strings < arrays < others",
+        )
+        .method(
+            "sortInPlace",
+            "sortInPlace(fn?)",
+            "Alias of sort, which never copies the array",
+        )
+        .method("toSorted", "toSorted(fn?)", "Return sorted copy, like sort")
+        .method(
+            "sortBy",
+            "sortBy(keyFn)",
+            "Stable sort in-place by the key of each item",
+        )
+        .method(
+            "groupBy",
+            "groupBy(keyFn)",
+            "Dict from each key to the items that have it",
+        )
+        .method(
+            "unique",
+            "unique(keyFn?)",
+            "Items without repeats, keeping the first of each",
+        )
+        .method(
+            "zip",
+            "zip(...others)",
+            "Arrays of items at the same index, as long as the shortest",
+        )
+        .method("chunk", "chunk(size)", "Split into arrays of size items")
+        .method(
+            "window",
+            "window(size)",
+            "Overlapping arrays of size consecutive items",
+        )
+        .method(
+            "binarySearch",
+            "binarySearch(value, keyFn?)",
+            "Index of value in a sorted array, or -1",
+        )
+        .method(
+            "bisect",
+            "bisect(value, keyFn?)",
+            "Index where value would be inserted to keep a sorted array sorted",
+        )
+        .method("includes", "includes(item)", "Check if includes item")
+}
+
 fn array_constructor(args: &[Value]) -> Result<Value, String> {
     if args.is_empty() {
-        Ok(Value::Array(Rc::new(RefCell::new(Vec::new()))))
+        Ok(Value::Array(Rc::new(RefCell::new(Vec::new().into()))))
     } else if args.len() == 1 {
         if let Value::Number(n) = &args[0] {
             let size = *n as usize;
             let arr: Vec<Value> = vec![Value::Null; size];
-            Ok(Value::Array(Rc::new(RefCell::new(arr))))
+            Ok(Value::Array(Rc::new(RefCell::new(arr.into()))))
         } else {
             Err(format!("Expected a number, got {}", args[0].type_name()))
         }
@@ -80,7 +198,7 @@ fn array_length(recv: &Value, args: &[Value]) -> Result<Value, String> {
 fn array_push(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     if let Value::Array(arr) = recv {
-        let mut arr = arr.borrow_mut();
+        let mut arr = array_mut(arr)?;
         arr.push(args[0].clone());
         Ok(Value::Number(arr.len() as f64))
     } else {
@@ -91,7 +209,7 @@ fn array_push(recv: &Value, args: &[Value]) -> Result<Value, String> {
 fn array_pop(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     if let Value::Array(arr) = recv {
-        Ok(arr.borrow_mut().pop().unwrap_or(Value::Null))
+        Ok(array_mut(arr)?.pop().unwrap_or(Value::Null))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -100,7 +218,7 @@ fn array_pop(recv: &Value, args: &[Value]) -> Result<Value, String> {
 fn array_shift(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     if let Value::Array(arr) = recv {
-        let mut guard = arr.borrow_mut();
+        let mut guard = array_mut(arr)?;
         if guard.is_empty() {
             Ok(Value::Null)
         } else {
@@ -116,7 +234,7 @@ fn array_unshift(recv: &Value, args: &[Value]) -> Result<Value, String> {
         return Err("Expected at least 1 argument".to_string());
     }
     if let Value::Array(arr) = recv {
-        let mut guard = arr.borrow_mut();
+        let mut guard = array_mut(arr)?;
 
         for (i, arg) in args.iter().enumerate() {
             guard.insert(i, arg.clone());
@@ -131,7 +249,7 @@ fn array_remove_at(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     let idx = get_number_arg(&args[0], "index")? as i64;
     if let Value::Array(arr) = recv {
-        let mut guard = arr.borrow_mut();
+        let mut guard = array_mut(arr)?;
         let len = guard.len() as i64;
 
         let actual_idx = if idx < 0 { len + idx } else { idx };
@@ -152,7 +270,7 @@ fn array_splice(recv: &Value, args: &[Value]) -> Result<Value, String> {
     let start = get_number_arg(&args[0], "start")? as i64;
 
     if let Value::Array(arr) = recv {
-        let mut guard = arr.borrow_mut();
+        let mut guard = array_mut(arr)?;
         let len = guard.len() as i64;
 
         let actual_start = if start < 0 {
@@ -182,7 +300,7 @@ fn array_splice(recv: &Value, args: &[Value]) -> Result<Value, String> {
             guard.insert(actual_start + i, item.clone());
         }
 
-        Ok(Value::Array(Rc::new(RefCell::new(removed))))
+        Ok(Value::Array(Rc::new(RefCell::new(removed.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -220,7 +338,7 @@ fn array_set(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(2, args.len())?;
     if let Value::Array(arr) = recv {
         let idx = get_number_arg(&args[0], "index")? as usize;
-        let mut arr = arr.borrow_mut();
+        let mut arr = array_mut(arr)?;
         if idx < arr.len() {
             arr[idx] = args[1].clone();
             drop(arr);
@@ -274,7 +392,7 @@ fn array_join(recv: &Value, args: &[Value]) -> Result<Value, String> {
 fn array_reverse(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     if let Value::Array(arr) = recv {
-        arr.borrow_mut().reverse();
+        array_mut(arr)?.reverse();
         Ok(recv.clone())
     } else {
         Err("Receiver must be an array".to_string())
@@ -318,7 +436,7 @@ fn array_slice(recv: &Value, args: &[Value]) -> Result<Value, String> {
         } else {
             Vec::new()
         };
-        Ok(Value::Array(Rc::new(RefCell::new(result))))
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -345,7 +463,7 @@ fn array_concat(recv: &Value, args: &[Value]) -> Result<Value, String> {
 fn array_clear(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     if let Value::Array(arr) = recv {
-        arr.borrow_mut().clear();
+        array_mut(arr)?.clear();
         Ok(recv.clone())
     } else {
         Err("Receiver must be an array".to_string())
@@ -366,7 +484,7 @@ fn array_keys(recv: &Value, args: &[Value]) -> Result<Value, String> {
     if let Value::Array(arr) = recv {
         let len = arr.borrow().len();
         let keys: Vec<Value> = (0..len).map(|i| Value::Number(i as f64)).collect();
-        Ok(Value::Array(Rc::new(RefCell::new(keys))))
+        Ok(Value::Array(Rc::new(RefCell::new(keys.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -385,7 +503,7 @@ fn array_map(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Resu
             result.push(call_result);
         }
 
-        Ok(Value::Array(Rc::new(RefCell::new(result))))
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -410,7 +528,7 @@ fn array_filter(
             }
         }
 
-        Ok(Value::Array(Rc::new(RefCell::new(result))))
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -559,39 +677,18 @@ fn array_every(
 }
 
 fn array_sort(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
-    if args.len() > 1 {
-        return Err(format!("Expected 0-1 arguments but got {}", args.len()));
-    }
-
+    check_arity_range(0, 1, args.len())?;
     if let Value::Array(arr) = recv {
-        let mut arr_mut = arr.borrow_mut();
-
-        if args.is_empty() {
-            arr_mut.sort_by(|a, b| {
-                let a_str = format!("{}", a);
-                let b_str = format!("{}", b);
-                a_str.cmp(&b_str)
-            });
-        } else {
-            let comparator = &args[0];
-            let mut items: Vec<Value> = arr_mut.drain(..).collect();
-
-            let len = items.len();
-            for i in 0..len {
-                for j in 0..(len - 1 - i) {
-                    let cmp_result =
-                        caller.call(comparator, vec![items[j].clone(), items[j + 1].clone()])?;
-                    if let Value::Number(n) = cmp_result {
-                        if n > 0.0 {
-                            items.swap(j, j + 1);
-                        }
-                    }
-                }
+        // Sorted out of the cell so the comparator may run any code, and put
+        // back untouched if it fails
+        let items = std::mem::take(&mut **array_mut(arr)?);
+        match sorted(&items, args.first(), caller) {
+            Ok(result) => **arr.borrow_mut() = result,
+            Err(e) => {
+                **arr.borrow_mut() = items;
+                return Err(e);
             }
-
-            *arr_mut = items;
         }
-
         Ok(recv.clone())
     } else {
         Err("Receiver must be an array".to_string())
@@ -621,7 +718,7 @@ fn array_fill(recv: &Value, args: &[Value]) -> Result<Value, String> {
     }
     if let Value::Array(arr) = recv {
         let value = args[0].clone();
-        let mut arr_mut = arr.borrow_mut();
+        let mut arr_mut = array_mut(arr)?;
         let len = arr_mut.len() as i64;
 
         let start = if args.len() > 1 {
@@ -683,7 +780,7 @@ fn array_flat(recv: &Value, args: &[Value]) -> Result<Value, String> {
         let arr_ref = arr.borrow();
         let mut result = Vec::new();
         flatten_array(&arr_ref, depth, &mut result);
-        Ok(Value::Array(Rc::new(RefCell::new(result))))
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -693,9 +790,9 @@ fn array_to_reversed(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     if let Value::Array(arr) = recv {
         let arr_ref = arr.borrow();
-        let mut result: Vec<Value> = arr_ref.clone();
+        let mut result: Vec<Value> = arr_ref.to_vec();
         result.reverse();
-        Ok(Value::Array(Rc::new(RefCell::new(result))))
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -723,7 +820,7 @@ fn array_flat_map(
                 result.push(mapped);
             }
         }
-        Ok(Value::Array(Rc::new(RefCell::new(result))))
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
     } else {
         Err("Receiver must be an array".to_string())
     }
@@ -734,37 +831,354 @@ fn array_to_sorted(
     args: &[Value],
     caller: &mut dyn ValueCaller,
 ) -> Result<Value, String> {
-    if args.len() > 1 {
-        return Err(format!("Expected 0-1 arguments but got {}", args.len()));
+    check_arity_range(0, 1, args.len())?;
+    if let Value::Array(arr) = recv {
+        let items = arr.borrow().clone();
+        let result = sorted(&items, args.first(), caller)?;
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
+    } else {
+        Err("Receiver must be an array".to_string())
+    }
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Boolean(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        _ => 5,
     }
+}
 
-    if let Value::Array(arr) = recv {
-        let arr_ref = arr.borrow();
-        let mut result: Vec<Value> = arr_ref.clone();
-
-        if args.is_empty() {
-            result.sort_by(|a, b| {
-                let a_str = format!("{}", a);
-                let b_str = format!("{}", b);
-                a_str.cmp(&b_str)
-            });
+/// Total order used when no comparator is given: null, then booleans
+/// (false first), numbers (NaN last), strings by code point, arrays item by
+/// item, and finally other values. Dates compare by time; other values of the
+/// same kind fall back to their display form.
+pub(super) fn compare_values(a: &Value, b: &Value) -> Ordering {
+    if let Some(Ok(ordering)) = compare_dates(a, b) {
+        return ordering;
+    }
+    match (a, b) {
+        (Value::Boolean(x), Value::Boolean(y)) => x.cmp(y),
+        (Value::Number(x), Value::Number(y)) => match (x.is_nan(), y.is_nan()) {
+            (false, false) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+            (x_nan, y_nan) => x_nan.cmp(&y_nan),
+        },
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => {
+            let (x, y) = (x.borrow().clone(), y.borrow().clone());
+            x.iter()
+                .zip(y.iter())
+                .map(|(x, y)| compare_values(x, y))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| x.len().cmp(&y.len()))
+        }
+        _ => type_rank(a)
+            .cmp(&type_rank(b))
+            .then_with(|| a.type_name().cmp(b.type_name()))
+            .then_with(|| a.to_string().cmp(&b.to_string())),
+    }
+}
+
+/// Stable merge sort. Unlike `slice::sort_by` it stops cleanly on the first
+/// comparator error and tolerates comparators that are not consistent.
+fn merge_sort(
+    items: &[Value],
+    compare: &mut dyn FnMut(&Value, &Value) -> Result<Ordering, String>,
+) -> Result<Vec<Value>, String> {
+    if items.len() <= 1 {
+        return Ok(items.to_vec());
+    }
+    let (left, right) = items.split_at(items.len() / 2);
+    let (left, right) = (merge_sort(left, compare)?, merge_sort(right, compare)?);
+    let mut result = Vec::with_capacity(items.len());
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        // Ties take from the left half, which keeps the sort stable
+        if compare(&right[j], &left[i])? == Ordering::Less {
+            result.push(right[j].clone());
+            j += 1;
         } else {
-            let comparator = &args[0];
-            let len = result.len();
-            for i in 0..len {
-                for j in 0..(len - 1 - i) {
-                    let cmp_result =
-                        caller.call(comparator, vec![result[j].clone(), result[j + 1].clone()])?;
-                    if let Value::Number(n) = cmp_result {
-                        if n > 0.0 {
-                            result.swap(j, j + 1);
-                        }
-                    }
-                }
+            result.push(left[i].clone());
+            i += 1;
+        }
+    }
+    result.extend_from_slice(&left[i..]);
+    result.extend_from_slice(&right[j..]);
+    Ok(result)
+}
+
+/// Sorts with a comparator returning a negative number, zero or a positive
+/// number, or by `compare_values` without one.
+fn sorted(
+    items: &[Value],
+    comparator: Option<&Value>,
+    caller: &mut dyn ValueCaller,
+) -> Result<Vec<Value>, String> {
+    match comparator {
+        Some(comparator) => merge_sort(items, &mut |a, b| match caller
+            .call(comparator, vec![a.clone(), b.clone()])?
+        {
+            Value::Number(n) => Ok(n.partial_cmp(&0.0).unwrap_or(Ordering::Equal)),
+            other => Err(format!(
+                "Comparator must return a number, got {}",
+                other.type_name()
+            )),
+        }),
+        None => merge_sort(items, &mut |a, b| Ok(compare_values(a, b))),
+    }
+}
+
+pub(super) fn size_arg(value: &Value) -> Result<usize, String> {
+    let size = get_number_arg(value, "size")?;
+    if size < 1.0 || size.fract() != 0.0 {
+        return Err("Argument 'size' must be a positive integer".to_string());
+    }
+    Ok(size as usize)
+}
+
+/// Applies `key_fn` to every item, or returns the items themselves.
+fn keys_of(
+    items: &[Value],
+    key_fn: Option<&Value>,
+    caller: &mut dyn ValueCaller,
+) -> Result<Vec<Value>, String> {
+    match key_fn {
+        Some(key_fn) => items
+            .iter()
+            .map(|item| caller.call(key_fn, vec![item.clone()]))
+            .collect(),
+        None => Ok(items.to_vec()),
+    }
+}
+
+fn array_sort_by(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    if let Value::Array(arr) = recv {
+        let items = arr.borrow().to_vec();
+        let keys = keys_of(&items, Some(&args[0]), caller)?;
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        // sort_by is stable, so items with equal keys keep their order
+        order.sort_by(|&a, &b| compare_values(&keys[a], &keys[b]));
+        **array_mut(arr)? = order.into_iter().map(|i| items[i].clone()).collect();
+        Ok(recv.clone())
+    } else {
+        Err("Receiver must be an array".to_string())
+    }
+}
+
+fn array_group_by(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    if let Value::Array(arr) = recv {
+        let items = arr.borrow().clone();
+        let mut groups: FxHashMap<String, Value> = FxHashMap::default();
+        for item in items {
+            let group = caller.call(&args[0], vec![item.clone()])?.to_string();
+            if let Value::Array(members) = groups
+                .entry(group)
+                .or_insert_with(|| Value::Array(Rc::new(RefCell::new(Vec::new().into()))))
+            {
+                members.borrow_mut().push(item);
+            }
+        }
+        Ok(Value::Dictionary(Rc::new(RefCell::new(groups.into()))))
+    } else {
+        Err("Receiver must be an array".to_string())
+    }
+}
+
+fn array_unique(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    if let Value::Array(arr) = recv {
+        let items = arr.borrow().clone();
+        let keys = keys_of(&items, args.first(), caller)?;
+        let mut seen = FxHashMap::default();
+        let mut result = Vec::new();
+        for (item, k) in items.into_iter().zip(keys.iter()) {
+            let key = key_with(k, &mut |key| Ok(seen.get(key).cloned()), caller)?;
+            if let Entry::Vacant(slot) = seen.entry(key) {
+                slot.insert(k.clone());
+                result.push(item);
+            }
+        }
+        Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
+    } else {
+        Err("Receiver must be an array".to_string())
+    }
+}
+
+fn array_zip(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    if let Value::Array(arr) = recv {
+        let mut columns = vec![arr.borrow().clone()];
+        for other in args {
+            match other {
+                Value::Array(other) => columns.push(other.borrow().clone()),
+                other => return Err(format!("Expected an array, got {}", other.type_name())),
             }
         }
-        Ok(Value::Array(Rc::new(RefCell::new(result))))
+        let len = columns.iter().map(|column| column.len()).min().unwrap_or(0);
+        let rows = (0..len)
+            .map(|i| {
+                let row = columns.iter().map(|column| column[i].clone()).collect();
+                Value::Array(Rc::new(RefCell::new(row)))
+            })
+            .collect();
+        Ok(Value::Array(Rc::new(RefCell::new(rows))))
+    } else {
+        Err("Receiver must be an array".to_string())
+    }
+}
+
+fn array_chunk(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let size = size_arg(&args[0])?;
+    if let Value::Array(arr) = recv {
+        let chunks = arr
+            .borrow()
+            .chunks(size)
+            .map(|chunk| Value::Array(Rc::new(RefCell::new(chunk.to_vec().into()))))
+            .collect();
+        Ok(Value::Array(Rc::new(RefCell::new(chunks))))
+    } else {
+        Err("Receiver must be an array".to_string())
+    }
+}
+
+fn array_window(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let size = size_arg(&args[0])?;
+    if let Value::Array(arr) = recv {
+        let windows = arr
+            .borrow()
+            .windows(size)
+            .map(|window| Value::Array(Rc::new(RefCell::new(window.to_vec().into()))))
+            .collect();
+        Ok(Value::Array(Rc::new(RefCell::new(windows))))
     } else {
         Err("Receiver must be an array".to_string())
     }
 }
+
+/// Leftmost position where `value` fits in a sorted array, and whether the
+/// item there equals it.
+fn search_sorted(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<(usize, bool), String> {
+    check_arity_range(1, 2, args.len())?;
+    if let Value::Array(arr) = recv {
+        let items = arr.borrow().clone();
+        let (mut low, mut high) = (0, items.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let item = match args.get(1) {
+                Some(key_fn) => caller.call(key_fn, vec![items[mid].clone()])?,
+                None => items[mid].clone(),
+            };
+            if compare_values(&item, &args[0]) == Ordering::Less {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        let found = match items.get(low) {
+            Some(item) => {
+                let item = match args.get(1) {
+                    Some(key_fn) => caller.call(key_fn, vec![item.clone()])?,
+                    None => item.clone(),
+                };
+                compare_values(&item, &args[0]) == Ordering::Equal
+            }
+            None => false,
+        };
+        Ok((low, found))
+    } else {
+        Err("Receiver must be an array".to_string())
+    }
+}
+
+fn array_binary_search(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let (index, found) = search_sorted(recv, args, caller)?;
+    Ok(Value::Number(if found { index as f64 } else { -1.0 }))
+}
+
+fn array_bisect(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let (index, _) = search_sorted(recv, args, caller)?;
+    Ok(Value::Number(index as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_array_grouping_and_search() {
+        let mut engine = Engine::new();
+        let stable: String = engine
+            .eval_as(
+                "[[2, \"a\"], [1, \"b\"], [2, \"c\"]].sortBy(|x| x[0]).map(|x| x[1]).join(\"\")",
+            )
+            .unwrap();
+        assert_eq!(stable, "bac");
+        let groups: String = engine
+            .eval_as("Json.stringify([1, 2, 3].groupBy(|x| x > 1 ? \"big\" : \"small\")[\"big\"])")
+            .unwrap();
+        assert_eq!(groups, "[2,3]");
+        let unique: Vec<f64> = engine.eval_as("[3, 1, 3, 2, 1].unique()").unwrap();
+        assert_eq!(unique, [3.0, 1.0, 2.0]);
+        let shape: String = engine
+            .eval_as(
+                "Json.stringify([[1, 2].zip([3, 4]), [1, 2, 3].chunk(2), [1, 2, 3].window(2)])",
+            )
+            .unwrap();
+        assert_eq!(shape, "[[[1,3],[2,4]],[[1,2],[3]],[[1,2],[2,3]]]");
+        let found: Vec<f64> = engine
+            .eval_as("[[1, 3, 5].binarySearch(5), [1, 3, 5].binarySearch(2), [1, 3, 5].bisect(4)]")
+            .unwrap();
+        assert_eq!(found, [2.0, -1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_array_sort_order_and_stability() {
+        let mut engine = Engine::new();
+        let numbers: Vec<f64> = engine.eval_as("[10, 9, 100, 1].sort()").unwrap();
+        assert_eq!(numbers, [1.0, 9.0, 10.0, 100.0]);
+        let mixed: String = engine
+            .eval_as("Json.stringify([\"b\", 2, null, [1], true, \"a\", 1].toSorted())")
+            .unwrap();
+        assert_eq!(mixed, "[null,true,1,2,\"a\",\"b\",[1]]");
+        let stable: String = engine
+            .eval_as(
+                "[[2, \"x\"], [1, \"y\"], [2, \"a\"], [1, \"b\"]].sortInPlace(|a, b| a[0] - b[0]).map(|p| p[1]).join(\"\")",
+            )
+            .unwrap();
+        assert_eq!(stable, "ybxa");
+        engine.eval("let kept = [3, 1, 2]").unwrap();
+        assert!(engine.eval("kept.sort(|a, b| \"bad\")").is_err());
+        let kept: Vec<f64> = engine.eval_as("kept").unwrap();
+        assert_eq!(kept, [3.0, 1.0, 2.0]);
+    }
+}
@@ -1,6 +1,7 @@
 //! Thread-safe Channel implementation using crossbeam-channel
 //! Allows communication between async workers
 
+use super::docs::ClassDoc;
 use super::{check_arity, check_arity_range, get_number_arg};
 use crate::vm::value::{Class, Instance, NativeInstanceFn, NativeStaticFn, SendValue, Value};
 use rustc_hash::FxHashMap;
@@ -42,6 +43,24 @@ pub fn create_channel_class() -> Class {
     class
 }
 
+/// API documentation for the `Channel` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Channel", "Go-style channels for async communication")
+        .method("send", "await send(value)", "Send a value to the channel")
+        .method(
+            "receive",
+            "await receive()",
+            "Receive a value from the channel",
+        )
+        .method(
+            "tryReceive",
+            "tryReceive()",
+            "Non-blocking receive, returns null if empty",
+        )
+        .method("close", "close()", "Close the channel")
+        .method("isClosed", "isClosed()", "Check if channel is closed")
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn channel_new(args: &[Value]) -> Result<Value, String> {
     check_arity_range(0, 1, args.len())?;
@@ -50,7 +69,11 @@ fn channel_new(args: &[Value]) -> Result<Value, String> {
         16
     } else {
         let size = get_number_arg(&args[0], "bufferSize")? as usize;
-        if size == 0 { 1 } else { size }
+        if size == 0 {
+            1
+        } else {
+            size
+        }
     };
 
     let class = Rc::new(create_channel_class());
@@ -80,7 +103,11 @@ fn channel_new(args: &[Value]) -> Result<Value, String> {
         16
     } else {
         let size = get_number_arg(&args[0], "bufferSize")? as usize;
-        if size == 0 { 1 } else { size }
+        if size == 0 {
+            1
+        } else {
+            size
+        }
     };
 
     let class = Rc::new(create_channel_class());
@@ -152,7 +179,7 @@ fn channel_send(recv: &Value, args: &[Value]) -> Result<Value, String> {
 
         // Convert to SendValue for thread-safe transfer
         let send_val = SendValue::from_value(&args[0])?;
-        
+
         match state.sender.send(send_val) {
             Ok(()) => Ok(Value::Boolean(true)),
             Err(_) => Err("Channel send failed: receiver dropped".to_string()),
@@ -247,7 +274,9 @@ fn channel_close(recv: &Value, args: &[Value]) -> Result<Value, String> {
     if let Value::Instance(inst) = recv {
         let inst = inst.borrow();
         let state = get_channel_state(&inst)?;
-        state.closed.store(true, std::sync::atomic::Ordering::SeqCst);
+        state
+            .closed
+            .store(true, std::sync::atomic::Ordering::SeqCst);
         Ok(Value::Null)
     } else {
         Err("close() must be called on a Channel instance".to_string())
@@ -291,7 +320,9 @@ fn channel_is_closed(recv: &Value, args: &[Value]) -> Result<Value, String> {
         let inst = inst.borrow();
         let state = get_channel_state(&inst)?;
         let state_ref = state.borrow();
-        Ok(Value::Boolean(state_ref.closed && state_ref.buffer.is_empty()))
+        Ok(Value::Boolean(
+            state_ref.closed && state_ref.buffer.is_empty(),
+        ))
     } else {
         Err("isClosed() must be called on a Channel instance".to_string())
     }
@@ -0,0 +1,591 @@
+//! Child processes
+//! `Process.spawn` starts a program with piped stdio. Output pipes are drained
+//! by background threads into per-child buffers, so a child never stalls on a
+//! full stderr while the script reads stdout. `wait()` returns a future for
+//! the exit code; `Process.pipeline` runs commands connected like a shell pipe
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_bytes_arg, get_string_arg, native_state};
+use crate::vm::value::{Class, Dict, Instance, NativeInstanceFn, SendValue, Value};
+use crossbeam_channel::{Receiver, Sender};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::io::{Read, Write};
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How often `wait` polls, leaving the child free for `kill` in between
+const WAIT_POLL: Duration = Duration::from_millis(5);
+
+/// Output of one child stream, filled by a reader thread.
+struct Pipe {
+    chunks: Receiver<Vec<u8>>,
+    buf: Vec<u8>,
+    eof: bool,
+}
+
+struct Running {
+    child: Arc<Mutex<Child>>,
+    stdin: Option<ChildStdin>,
+    stdout: Option<Pipe>,
+    stderr: Option<Pipe>,
+}
+
+fn create_child_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("write".to_string(), child_write);
+    instance_methods.insert("closeStdin".to_string(), child_close_stdin);
+    instance_methods.insert("read".to_string(), child_read);
+    instance_methods.insert("readLine".to_string(), child_read_line);
+    instance_methods.insert("readAll".to_string(), child_read_all);
+    instance_methods.insert("readErr".to_string(), child_read_err);
+    instance_methods.insert("readErrLine".to_string(), child_read_err_line);
+    instance_methods.insert("readErrAll".to_string(), child_read_err_all);
+    instance_methods.insert("wait".to_string(), child_wait);
+    instance_methods.insert("exitCode".to_string(), child_exit_code);
+    instance_methods.insert("kill".to_string(), child_kill);
+
+    Class::new_with_instance("ChildProcess", instance_methods, None)
+}
+
+/// API documentation for the `ChildProcess` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("ChildProcess", "Running program returned by Process.spawn")
+        .property("pid", "Process id")
+        .method(
+            "write",
+            "write(data)",
+            "Write a string or byte array to stdin",
+        )
+        .method(
+            "closeStdin",
+            "closeStdin()",
+            "Close stdin so the child sees end of input",
+        )
+        .method(
+            "read",
+            "read()",
+            "Next available stdout text, or null at the end",
+        )
+        .method(
+            "readLine",
+            "readLine()",
+            "Next stdout line, or null at the end",
+        )
+        .method("readAll", "readAll()", "Rest of stdout as text")
+        .method(
+            "readErr",
+            "readErr()",
+            "Next available stderr text, or null at the end",
+        )
+        .method(
+            "readErrLine",
+            "readErrLine()",
+            "Next stderr line, or null at the end",
+        )
+        .method("readErrAll", "readErrAll()", "Rest of stderr as text")
+        .method(
+            "wait",
+            "await wait()",
+            "Wait for the child to exit, returns its exit code",
+        )
+        .method(
+            "exitCode",
+            "exitCode()",
+            "Exit code if the child has exited, otherwise null",
+        )
+        .method(
+            "kill",
+            "kill(signal?)",
+            "Send a signal such as \"SIGTERM\", SIGKILL by default",
+        )
+}
+
+/// Stdio setting from an options dict, piped unless told otherwise.
+fn stdio_option(options: &FxHashMap<String, Value>, name: &str) -> Result<Stdio, String> {
+    match options.get(name) {
+        None | Some(Value::Null) => Ok(Stdio::piped()),
+        Some(Value::String(mode)) => match mode.as_ref() {
+            "pipe" => Ok(Stdio::piped()),
+            "inherit" => Ok(Stdio::inherit()),
+            "null" => Ok(Stdio::null()),
+            other => Err(format!(
+                "Option '{}' must be \"pipe\", \"inherit\" or \"null\", got \"{}\"",
+                name, other
+            )),
+        },
+        Some(other) => Err(format!(
+            "Option '{}' must be a string, got {}",
+            name,
+            other.type_name()
+        )),
+    }
+}
+
+/// Applies the `cwd`, `env` and `clearEnv` options to a command.
+fn apply_options(command: &mut Command, options: &FxHashMap<String, Value>) -> Result<(), String> {
+    if let Some(Value::Boolean(true)) = options.get("clearEnv") {
+        command.env_clear();
+    }
+    match options.get("env") {
+        None | Some(Value::Null) => {}
+        Some(Value::Dictionary(env)) => {
+            for (key, value) in env.borrow().iter() {
+                match value {
+                    Value::Null => command.env_remove(key),
+                    value => command.env(key, value.to_string()),
+                };
+            }
+        }
+        Some(other) => {
+            return Err(format!(
+                "Option 'env' must be a dictionary, got {}",
+                other.type_name()
+            ))
+        }
+    }
+    match options.get("cwd") {
+        None | Some(Value::Null) => {}
+        Some(value) => {
+            command.current_dir(crate::workspace::resolve(&get_string_arg(value, "cwd")?));
+        }
+    }
+    Ok(())
+}
+
+fn options_arg(value: Option<&Value>) -> Result<Dict, String> {
+    match value {
+        None | Some(Value::Null) => Ok(Dict::new()),
+        Some(Value::Dictionary(dict)) => Ok(dict.borrow().clone()),
+        Some(other) => Err(format!(
+            "Argument 'options' must be a dictionary, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn string_list(value: Option<&Value>, name: &str) -> Result<Vec<String>, String> {
+    match value {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => Ok(items.borrow().iter().map(|v| v.to_string()).collect()),
+        Some(other) => Err(format!(
+            "Argument '{}' must be an array, got {}",
+            name,
+            other.type_name()
+        )),
+    }
+}
+
+fn drain(mut source: impl Read + Send + 'static) -> Pipe {
+    let (tx, rx): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = crossbeam_channel::unbounded();
+    std::thread::spawn(move || {
+        let mut chunk = [0u8; 8192];
+        loop {
+            match source.read(&mut chunk) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if tx.send(chunk[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    Pipe {
+        chunks: rx,
+        buf: Vec::new(),
+        eof: false,
+    }
+}
+
+pub(crate) fn process_spawn(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 3, args.len())?;
+    let program = get_string_arg(&args[0], "command")?;
+    let program_args = string_list(args.get(1), "args")?;
+    let options = options_arg(args.get(2))?;
+
+    let mut command = Command::new(&program);
+    command
+        .args(&program_args)
+        .stdin(stdio_option(&options, "stdin")?)
+        .stdout(stdio_option(&options, "stdout")?)
+        .stderr(stdio_option(&options, "stderr")?);
+    apply_options(&mut command, &options)?;
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to spawn '{}': {}", program, e))?;
+    let pid = child.id();
+    let running = Running {
+        stdin: child.stdin.take(),
+        stdout: child.stdout.take().map(drain),
+        stderr: child.stderr.take().map(drain),
+        child: Arc::new(Mutex::new(child)),
+    };
+
+    let mut instance = Instance::with_native(Rc::new(create_child_class()), running);
+    instance
+        .fields
+        .insert("pid".to_string(), Value::Number(pid as f64));
+    Ok(Value::Instance(Rc::new(RefCell::new(instance))))
+}
+
+fn with_child<T>(
+    recv: &Value,
+    f: impl FnOnce(&mut Running) -> Result<T, String>,
+) -> Result<T, String> {
+    let running = native_state::<Running>(recv, "ChildProcess")?;
+    let mut running = running.borrow_mut();
+    f(&mut running)
+}
+
+fn child_write(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let data = match &args[0] {
+        Value::Array(_) => get_bytes_arg(&args[0], "data")?,
+        other => other.to_string().into_bytes(),
+    };
+    with_child(recv, |running| match running.stdin.as_mut() {
+        Some(stdin) => stdin
+            .write_all(&data)
+            .and_then(|_| stdin.flush())
+            .map_err(|e| format!("Failed to write to child stdin: {}", e)),
+        None => Err("Child stdin is closed or not piped".to_string()),
+    })?;
+    Ok(Value::Number(data.len() as f64))
+}
+
+fn child_close_stdin(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    with_child(recv, |running| {
+        running.stdin = None;
+        Ok(Value::Null)
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Stream {
+    Out,
+    Err,
+}
+
+fn pipe_of(running: &mut Running, stream: Stream) -> Result<&mut Pipe, String> {
+    match stream {
+        Stream::Out => running.stdout.as_mut(),
+        Stream::Err => running.stderr.as_mut(),
+    }
+    .ok_or_else(|| "Child output is not piped".to_string())
+}
+
+/// Takes what `take` picks from the stream's buffer, blocking for more output
+/// while it picks nothing and the stream is still open.
+fn pull(
+    recv: &Value,
+    stream: Stream,
+    take: impl Fn(&mut Vec<u8>, bool) -> Option<Vec<u8>>,
+) -> Result<Option<Vec<u8>>, String> {
+    loop {
+        let (taken, chunks) = with_child(recv, |running| {
+            let pipe = pipe_of(running, stream)?;
+            let taken = take(&mut pipe.buf, pipe.eof);
+            Ok((taken, (!pipe.eof).then(|| pipe.chunks.clone())))
+        })?;
+        if taken.is_some() {
+            return Ok(taken);
+        }
+        let Some(chunks) = chunks else {
+            return Ok(None);
+        };
+        // Block outside the table borrow so other handles stay usable
+        let chunk = chunks.recv().ok();
+        with_child(recv, |running| {
+            let pipe = pipe_of(running, stream)?;
+            match chunk {
+                Some(chunk) => pipe.buf.extend_from_slice(&chunk),
+                None => pipe.eof = true,
+            }
+            Ok(())
+        })?;
+    }
+}
+
+fn text(bytes: Option<Vec<u8>>) -> Value {
+    match bytes {
+        Some(bytes) => Value::String(Rc::from(String::from_utf8_lossy(&bytes).into_owned())),
+        None => Value::Null,
+    }
+}
+
+/// Everything buffered, minus a trailing character that is still incomplete.
+fn take_available(buf: &mut Vec<u8>, eof: bool) -> Option<Vec<u8>> {
+    if buf.is_empty() {
+        return None;
+    }
+    let end = match std::str::from_utf8(buf) {
+        Err(e) if !eof && e.error_len().is_none() => e.valid_up_to(),
+        _ => buf.len(),
+    };
+    (end > 0).then(|| buf.drain(..end).collect())
+}
+
+fn take_line(buf: &mut Vec<u8>, eof: bool) -> Option<Vec<u8>> {
+    match buf.iter().position(|&b| b == b'\n') {
+        Some(end) => {
+            let mut line: Vec<u8> = buf.drain(..=end).collect();
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            Some(line)
+        }
+        None if eof && !buf.is_empty() => Some(std::mem::take(buf)),
+        None => None,
+    }
+}
+
+fn take_all(buf: &mut Vec<u8>, eof: bool) -> Option<Vec<u8>> {
+    eof.then(|| std::mem::take(buf))
+}
+
+fn child_read(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    pull(recv, Stream::Out, take_available).map(text)
+}
+
+fn child_read_line(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    pull(recv, Stream::Out, take_line).map(text)
+}
+
+fn child_read_all(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    pull(recv, Stream::Out, take_all).map(text)
+}
+
+fn child_read_err(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    pull(recv, Stream::Err, take_available).map(text)
+}
+
+fn child_read_err_line(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    pull(recv, Stream::Err, take_line).map(text)
+}
+
+fn child_read_err_all(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    pull(recv, Stream::Err, take_all).map(text)
+}
+
+/// Exit code, or 128 plus the signal number for a child killed by a signal.
+fn exit_code(status: ExitStatus) -> f64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return (128 + signal) as f64;
+        }
+    }
+    status.code().unwrap_or(-1) as f64
+}
+
+fn child_wait(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let child = with_child(recv, |running| {
+        // The child may be waiting for end of input
+        running.stdin = None;
+        Ok(running.child.clone())
+    })?;
+
+    let (tx, rx) = crossbeam_channel::bounded(1);
+    std::thread::spawn(move || {
+        let result = loop {
+            let status = child
+                .lock()
+                .map_err(|e| e.to_string())
+                .and_then(|mut child| {
+                    child
+                        .try_wait()
+                        .map_err(|e| format!("Failed to wait for child: {}", e))
+                });
+            match status {
+                Ok(Some(status)) => break Ok(SendValue::Number(exit_code(status))),
+                Ok(None) => std::thread::sleep(WAIT_POLL),
+                Err(e) => break Err(e),
+            }
+        };
+        let _ = tx.send(result);
+    });
+    Ok(Value::Future(Rc::new(RefCell::new(Some(rx)))))
+}
+
+fn child_exit_code(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let child = with_child(recv, |running| Ok(running.child.clone()))?;
+    let mut child = child.lock().map_err(|e| e.to_string())?;
+    match child.try_wait() {
+        Ok(Some(status)) => Ok(Value::Number(exit_code(status))),
+        Ok(None) => Ok(Value::Null),
+        Err(e) => Err(format!("Failed to check child: {}", e)),
+    }
+}
+
+fn child_kill(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let child = with_child(recv, |running| Ok(running.child.clone()))?;
+    let mut child = child.lock().map_err(|e| e.to_string())?;
+    if !matches!(child.try_wait(), Ok(None)) {
+        return Ok(Value::Boolean(false));
+    }
+
+    #[cfg(unix)]
+    {
+        let signal = match args.first() {
+            None | Some(Value::Null) => libc::SIGKILL,
+            Some(value) => super::system::get_signal_arg(value)?,
+        };
+        // SAFETY: kill only sends a signal to the child we still own
+        if unsafe { libc::kill(child.id() as libc::pid_t, signal) } != 0 {
+            return Err(format!(
+                "Failed to signal child: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    #[cfg(not(unix))]
+    child
+        .kill()
+        .map_err(|e| format!("Failed to kill child: {}", e))?;
+
+    Ok(Value::Boolean(true))
+}
+
+/// One pipeline stage: an array is a program and its arguments, a string is
+/// handed to the shell.
+fn stage_command(stage: &Value) -> Result<Command, String> {
+    match stage {
+        Value::Array(parts) => {
+            let parts: Vec<String> = parts.borrow().iter().map(|v| v.to_string()).collect();
+            let (program, rest) = parts
+                .split_first()
+                .ok_or("Pipeline stage cannot be empty")?;
+            let mut command = Command::new(program);
+            command.args(rest);
+            Ok(command)
+        }
+        Value::String(line) => {
+            #[cfg(windows)]
+            let command = {
+                let mut command = Command::new("cmd");
+                command.args(["/C", line.as_ref()]);
+                command
+            };
+            #[cfg(not(windows))]
+            let command = {
+                let mut command = Command::new("sh");
+                command.args(["-c", line.as_ref()]);
+                command
+            };
+            Ok(command)
+        }
+        other => Err(format!(
+            "Pipeline stage must be an array or string, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+pub(crate) fn process_pipeline(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let stages = match &args[0] {
+        Value::Array(stages) => stages.borrow().clone(),
+        other => {
+            return Err(format!(
+                "Argument 'commands' must be an array, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    if stages.is_empty() {
+        return Err("Pipeline needs at least one command".to_string());
+    }
+    let options = options_arg(args.get(1))?;
+    let input = match options.get("input") {
+        None | Some(Value::Null) => None,
+        Some(value) => Some(get_bytes_arg(value, "input")?),
+    };
+
+    let mut children = Vec::with_capacity(stages.len());
+    let mut previous: Option<std::process::ChildStdout> = None;
+    let mut stderr = Vec::with_capacity(stages.len());
+    for (index, stage) in stages.iter().enumerate() {
+        let mut command = stage_command(stage)?;
+        apply_options(&mut command, &options)?;
+        let stdin = match previous.take() {
+            Some(stdout) => Stdio::from(stdout),
+            None if input.is_some() => Stdio::piped(),
+            None => Stdio::null(),
+        };
+        command
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to spawn pipeline stage {}: {}", index + 1, e))?;
+        if index + 1 < stages.len() {
+            previous = child.stdout.take();
+        }
+        stderr.push(child.stderr.take().map(drain));
+        children.push(child);
+    }
+
+    if let (Some(input), Some(stdin)) = (input, children[0].stdin.take()) {
+        std::thread::spawn(move || {
+            let mut stdin = stdin;
+            let _ = stdin.write_all(&input);
+        });
+    }
+
+    let last = children.last_mut().expect("pipeline has a stage");
+    let mut stdout = Vec::new();
+    if let Some(mut out) = last.stdout.take() {
+        out.read_to_end(&mut stdout)
+            .map_err(|e| format!("Failed to read pipeline output: {}", e))?;
+    }
+
+    let mut codes = Vec::with_capacity(children.len());
+    for child in children.iter_mut() {
+        let status = child
+            .wait()
+            .map_err(|e| format!("Failed to wait for pipeline: {}", e))?;
+        codes.push(Value::Number(exit_code(status)));
+    }
+    let errors: Vec<u8> = stderr
+        .into_iter()
+        .flatten()
+        .flat_map(|pipe| pipe.chunks.iter().flatten().collect::<Vec<u8>>())
+        .collect();
+
+    let mut result = FxHashMap::default();
+    result.insert(
+        "stdout".to_string(),
+        Value::String(Rc::from(String::from_utf8_lossy(&stdout).into_owned())),
+    );
+    result.insert(
+        "stderr".to_string(),
+        Value::String(Rc::from(String::from_utf8_lossy(&errors).into_owned())),
+    );
+    result.insert(
+        "code".to_string(),
+        codes.last().cloned().unwrap_or(Value::Null),
+    );
+    result.insert(
+        "codes".to_string(),
+        Value::Array(Rc::new(RefCell::new(codes.into()))),
+    );
+    Ok(Value::Dictionary(Rc::new(RefCell::new(result.into()))))
+}
@@ -0,0 +1,65 @@
+//! Current time for Timer, Date and Cron
+//! Tests can install a fake clock that stands still until advanced, so code
+//! that reads the time or waits on timers runs instantly and deterministically.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+struct FakeClock {
+    /// Real instant when the fake clock was installed
+    start: Instant,
+    /// Time since the Unix epoch the fake clock started at
+    wall: Duration,
+    elapsed: Duration,
+}
+
+thread_local! {
+    static FAKE: RefCell<Option<FakeClock>> = const { RefCell::new(None) };
+}
+
+/// Monotonic time used to schedule timers.
+pub(crate) fn now() -> Instant {
+    FAKE.with(|fake| match &*fake.borrow() {
+        Some(clock) => clock.start + clock.elapsed,
+        None => Instant::now(),
+    })
+}
+
+/// Time since the Unix epoch.
+pub(crate) fn unix_time() -> Duration {
+    FAKE.with(|fake| match &*fake.borrow() {
+        Some(clock) => clock.wall + clock.elapsed,
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default(),
+    })
+}
+
+pub(crate) fn is_fake() -> bool {
+    FAKE.with(|fake| fake.borrow().is_some())
+}
+
+/// Freezes the clock at `wall` since the Unix epoch, or at the current time.
+pub(crate) fn install_fake(wall: Option<Duration>) {
+    let wall = wall.unwrap_or_else(unix_time);
+    FAKE.with(|fake| {
+        *fake.borrow_mut() = Some(FakeClock {
+            start: Instant::now(),
+            wall,
+            elapsed: Duration::ZERO,
+        })
+    });
+}
+
+pub(crate) fn use_real() {
+    FAKE.with(|fake| *fake.borrow_mut() = None);
+}
+
+/// Moves the fake clock forward. Does nothing on the real clock.
+pub(crate) fn advance(by: Duration) {
+    FAKE.with(|fake| {
+        if let Some(clock) = fake.borrow_mut().as_mut() {
+            clock.elapsed += by;
+        }
+    });
+}
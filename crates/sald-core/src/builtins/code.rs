@@ -0,0 +1,310 @@
+//! Compiling and running Sald source from a script
+//! Code runs like an imported module: on its own stack, with the pure builtin
+//! classes and the globals it is given, never the globals of the script that
+//! runs it. Builtins that reach files, processes, the network, the console or
+//! other modules are left out, as are the file readers on Json, and `import`
+//! is refused; pass them in `globals` to grant them.
+
+use super::docs::ClassDoc;
+use super::{
+    check_arity, check_arity_range, get_string_arg, native_instance, native_state, NamedArgs,
+};
+use crate::compiler::Compiler;
+use crate::lexer::Scanner;
+use crate::parser::Parser;
+use crate::vm::caller::{CallableNativeInstanceFn, CallableNativeStaticFn, ValueCaller};
+use crate::vm::value::{Class, Function, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+/// File name reported for errors in evaluated code
+const CODE_FILE: &str = "<code>";
+
+/// Builtins visible to evaluated code: those without I/O or outside state
+const PURE_BUILTINS: &[&str] = &[
+    "String", "Number", "Boolean", "Null", "Array", "Dict", "Function", "Type", "Math", "Json",
+    "Regex", "Yaml", "Toml", "MsgPack", "Set", "Map", "Counter", "Deque", "Heap", "Iter", "Matrix",
+    "Reflect", "Encoding",
+];
+
+/// Members of the pure builtins that read files, left out of the copies
+/// evaluated code sees
+const FILE_MEMBERS: &[(&str, &str)] = &[("Json", "parseLines"), ("Json", "events")];
+
+/// What `Code.compile` hands back, run on each call
+struct Compiled {
+    script: Rc<Function>,
+    source: String,
+}
+
+pub fn create_code_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("compile".to_string(), code_compile);
+    callable_methods.insert("eval".to_string(), code_eval);
+
+    let mut class = Class::new_with_static("Code", static_methods);
+    class.callable_native_static_methods = callable_methods;
+    class.named_arg_methods.insert("eval".to_string());
+    class
+}
+
+fn create_compiled_class() -> Class {
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    callable_methods.insert("run".to_string(), compiled_run);
+    callable_methods.insert("__call__".to_string(), compiled_run);
+
+    let mut class = Class::new_with_instance("CompiledCode", FxHashMap::default(), None);
+    class.callable_native_instance_methods = callable_methods;
+    class.named_arg_methods.insert("run".to_string());
+    class.named_arg_methods.insert("__call__".to_string());
+    class
+}
+
+/// API documentation for the `Code` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Code", "Compile and run Sald source in an isolated scope")
+        .method(
+            "eval",
+            "eval(source, globals?)",
+            "Run source with only the pure builtins and the globals dict, returning its last value",
+        )
+        .method(
+            "compile",
+            "compile(source)",
+            "Compile source once into code that is run by calling it with a globals dict",
+        )
+}
+
+fn compile(source: &str) -> Result<Rc<Function>, String> {
+    let location = |e: crate::error::SaldError| {
+        format!(
+            "{} at {}:{}:{}",
+            e.message, CODE_FILE, e.span.start.line, e.span.start.column
+        )
+    };
+    let tokens = Scanner::new(source, CODE_FILE)
+        .scan_tokens()
+        .map_err(location)?;
+    let program = Parser::new(tokens, CODE_FILE, source)
+        .parse()
+        .map_err(location)?;
+    let mut compiler = Compiler::new(CODE_FILE, source);
+    compiler.deny_imports();
+    let chunk = compiler.compile_repl(&program).map_err(location)?;
+    let mut script = Function::new(CODE_FILE, 0, chunk);
+    script.file = CODE_FILE.to_string();
+    Ok(Rc::new(script))
+}
+
+/// The pure builtins plus the entries of the `globals` dict argument
+fn sandbox_globals(globals: Option<&Value>) -> Result<FxHashMap<String, Value>, String> {
+    let mut table = super::create_builtin_classes();
+    table.retain(|name, _| PURE_BUILTINS.contains(&name.as_str()));
+    for (class, member) in FILE_MEMBERS {
+        if let Some(Value::Class(class)) = table.get_mut(*class) {
+            let class = Rc::make_mut(class);
+            class.native_static_methods.remove(*member);
+            class.callable_native_static_methods.remove(*member);
+        }
+    }
+    match globals {
+        None | Some(Value::Null) => {}
+        Some(Value::Dictionary(dict)) => {
+            table.extend(dict.borrow().iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        Some(other) => {
+            return Err(format!(
+                "globals must be a Dictionary, got {}",
+                other.type_name()
+            ))
+        }
+    }
+    Ok(table)
+}
+
+fn code_eval(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    let (args, named) = NamedArgs::split(args, &["globals"])?;
+    check_arity_range(1, 2, args.len())?;
+    let source = get_string_arg(&args[0], "source")?;
+    let script = compile(&source)?;
+    let globals = sandbox_globals(named.get(args, 1, "globals")?.as_ref())?;
+    caller.run_script(script, source, globals)
+}
+
+fn code_compile(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let source = get_string_arg(&args[0], "source")?;
+    let script = compile(&source)?;
+    let state = Compiled {
+        script,
+        source: source.clone(),
+    };
+    let compiled = native_instance(create_compiled_class(), state);
+    if let Value::Instance(inst) = &compiled {
+        inst.borrow_mut().fields.insert(
+            "source".to_string(),
+            Value::String(Rc::from(source.as_str())),
+        );
+    }
+    Ok(compiled)
+}
+
+fn compiled_run(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let (args, named) = NamedArgs::split(args, &["globals"])?;
+    check_arity_range(0, 1, args.len())?;
+    let (script, source) = {
+        let state = native_state::<Compiled>(recv, "CompiledCode")?;
+        let state = state.borrow();
+        (state.script.clone(), state.source.clone())
+    };
+    let globals = sandbox_globals(named.get(args, 0, "globals")?.as_ref())?;
+    caller.run_script(script, source, globals)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::{eval, eval_err};
+    use crate::vm::value::Value;
+
+    #[test]
+    fn test_eval_sees_pure_builtins() {
+        assert_eq!(eval("Code.eval(\"Math.abs(-2)\")"), "2");
+        assert_eq!(eval("Code.eval(\"Json.stringify([1])\")"), "[1]");
+    }
+
+    #[test]
+    fn test_eval_hides_builtins_with_side_effects() {
+        for name in ["File", "Process", "Console", "System", "Module", "Code"] {
+            let err = eval_err(&format!("Code.eval(\"{name}\")"));
+            assert!(err.contains(&format!("Undefined variable '{name}'")), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_globals_can_grant_a_hidden_builtin() {
+        assert_eq!(eval("Code.eval(\"C.abs(-3)\", {\"C\": Math})"), "3");
+        let granted = "Code.compile(\"Console\")({\"Console\": Console}) == Console";
+        assert_eq!(eval(granted), "true");
+    }
+
+    #[test]
+    fn test_globals_can_be_passed_by_name() {
+        assert_eq!(eval("Code.eval(\"x + 1\", globals: {\"x\": 2})"), "3");
+        let compiled = "let c = Code.compile(\"x * 2\")\nlet r = [c(globals: {\"x\": 3}), c.run(globals: {\"x\": 4})]\nr";
+        assert_eq!(eval(compiled), "[6, 8]");
+        let err = eval_err("Code.eval(\"1\", scope: {})");
+        assert!(err.contains("Unexpected named argument 'scope'"), "{err}");
+    }
+
+    #[test]
+    fn test_compiled_code_keeps_its_script_out_of_fields() {
+        let source = "let c = Code.compile(\"x * 2\")\nlet r = [c({\"x\": 3}), c.source, Reflect.fields(c)]\nr";
+        assert_eq!(eval(source), "[6, x * 2, [source]]");
+    }
+
+    #[test]
+    fn test_eval_cannot_import() {
+        let dir = std::env::temp_dir().join(format!("sald-code-import-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("leak.sald"), "let Leaked = File").unwrap();
+        let module = dir.join("leak.sald").to_str().unwrap().replace('\\', "/");
+
+        let statement = eval_err(&format!("Code.eval(\"import \\\"{module}\\\"\\nLeaked\")"));
+        let dynamic = eval_err(&format!("Code.eval(\"import(\\\"{module}\\\")\")"));
+        let compiled = eval_err(&format!("Code.compile(\"import \\\"{module}\\\" as m\")"));
+        std::fs::remove_dir_all(&dir).ok();
+
+        for err in [statement, dynamic, compiled] {
+            assert!(err.contains("Imports are not allowed here"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_eval_cannot_read_files_through_pure_builtins() {
+        let dir = std::env::temp_dir().join(format!("sald-code-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("secret.txt"), "\"s3cr3t\"\n").unwrap();
+        let path = dir.join("secret.txt").to_str().unwrap().replace('\\', "/");
+
+        // Every static member of every builtin evaluated code sees, called
+        // with the path and, when it hands back a reader, read from
+        let mut probes = Vec::new();
+        for (name, class) in super::super::create_builtin_classes() {
+            let Value::Class(class) = class else { continue };
+            if !super::PURE_BUILTINS.contains(&name.as_str()) {
+                continue;
+            }
+            let members = class
+                .native_static_methods
+                .keys()
+                .chain(class.callable_native_static_methods.keys());
+            for member in members {
+                probes.push(format!(
+                    "try {{ let v = {name}.{member}(p)\\n out.push(v)\\n out.push(v.next()) }} catch e {{}}"
+                ));
+            }
+        }
+        let source = format!("let out = []\\n{}\\nout", probes.join("\\n"));
+        let script = format!(
+            "Code.eval(\"{}\", {{\"p\": \"{path}\"}})",
+            source.replace('"', "\\\"")
+        );
+        let leaked = eval(&script);
+        let direct = eval(&format!("Json.parseLines(\"{path}\").next()"));
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(direct, "s3cr3t");
+        assert!(!leaked.contains("s3cr3t"), "{leaked}");
+    }
+
+    #[test]
+    fn test_eval_errors_keep_their_kind_and_thrown_value() {
+        let thrown = "class E { fun init(self, code) { self.code = code } }\n\
+                      let r = null\n\
+                      try { Code.eval(\"throw err\", {\"err\": E(4)}) } catch e { r = e.code }\nr";
+        assert_eq!(eval(thrown), "4");
+        let err = eval_err("Code.eval(\"throw 5\")");
+        assert!(err.contains("Uncaught exception: 5 at <code>:1:1"), "{err}");
+        assert!(!err.contains("Uncaught exception: Uncaught"), "{err}");
+        let err = eval_err("Code.eval(\"missing\")");
+        assert!(
+            err.starts_with("NameError: Undefined variable 'missing'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_code_eval_and_compile() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                r#"
+let secret = "hidden"
+let sum = Code.eval("let xs = [1, 2, 3]\nxs.map(|x| x * k)", {"k": 10})
+let greet = Code.compile("'Hello, ' + who + '!'")
+let greetings = [greet({"who": "Ada"}), greet.run({"who": "Lin"})]
+let leaked = null
+try { Code.eval("secret") } catch e { leaked = e }
+"#,
+            )
+            .unwrap();
+        assert_eq!(engine.eval_as::<Vec<i64>>("sum").unwrap(), [10, 20, 30]);
+        assert_eq!(
+            engine.eval_as::<Vec<String>>("greetings").unwrap(),
+            ["Hello, Ada!", "Hello, Lin!"]
+        );
+        assert_eq!(
+            engine.eval_as::<String>("leaked").unwrap(),
+            "Undefined variable 'secret' at <code>:1:1"
+        );
+        let err = engine.eval("Code.compile(\"let = 1\")").unwrap_err();
+        assert!(err.message().contains("at <code>:1:5"));
+    }
+}
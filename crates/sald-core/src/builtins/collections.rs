@@ -0,0 +1,1062 @@
+//! Set, Map and Counter collections
+//! All keep insertion order and accept any hashable value as a key: numbers,
+//! strings, booleans and null by value, arrays by their contents and other
+//! objects by identity, except instances of classes defining `__hash__`, which
+//! are keyed by what it returns and told apart by `__eq__` when hashes
+//! collide. Contents live in the instance's native handle and are freed with
+//! it.
+//! `at(i)` and `length()` make them usable in `for ... in`
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, native_instance, native_state};
+use crate::vm::caller::{CallableNativeInstanceFn, CallableNativeStaticFn, ValueCaller};
+use crate::vm::value::{Class, Instance, NativeInstanceFn, Value};
+use indexmap::IndexMap;
+use rustc_hash::{FxHashMap, FxHasher};
+use std::cell::RefCell;
+use std::hash::BuildHasherDefault;
+use std::rc::Rc;
+
+/// Hashable identity of a value used as a Set element or Map key.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Key {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(Rc<str>),
+    Array(Vec<Key>),
+    Ref(usize),
+    /// Key of what an instance's `__hash__` returned, and which of the
+    /// unequal instances with that hash it is. Those are numbered from 0
+    /// without gaps, so a lookup stops at the first free number.
+    Hashed(Box<Key>, usize),
+}
+
+type Entries = IndexMap<Key, (Value, Value), BuildHasherDefault<FxHasher>>;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Set,
+    Map,
+    Counter,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Set => "Set",
+            Kind::Map => "Map",
+            Kind::Counter => "Counter",
+        }
+    }
+}
+
+/// Set elements are stored as entries whose value is unused, Counter entries
+/// hold their count.
+struct Store {
+    kind: Kind,
+    entries: Entries,
+}
+
+pub fn create_set_class() -> Class {
+    let mut callable_static: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    callable_static.insert("new".to_string(), set_new);
+
+    callable_methods.insert("add".to_string(), set_add);
+    callable_methods.insert("has".to_string(), collection_has);
+    callable_methods.insert("delete".to_string(), collection_delete);
+    instance_methods.insert("clear".to_string(), collection_clear);
+    instance_methods.insert("length".to_string(), collection_length);
+    instance_methods.insert("isEmpty".to_string(), collection_is_empty);
+    instance_methods.insert("at".to_string(), collection_at);
+    instance_methods.insert("toArray".to_string(), collection_keys);
+    instance_methods.insert("copy".to_string(), collection_copy);
+    callable_methods.insert("union".to_string(), set_union);
+    callable_methods.insert("intersect".to_string(), set_intersect);
+    callable_methods.insert("difference".to_string(), set_difference);
+    callable_methods.insert("isSubset".to_string(), set_is_subset);
+    instance_methods.insert("toJson".to_string(), collection_keys);
+    instance_methods.insert("toString".to_string(), collection_to_string);
+    callable_methods.insert("forEach".to_string(), set_for_each);
+
+    let mut class = Class::new_with_instance("Set", instance_methods, None);
+    class.callable_constructor = Some(set_new);
+    class.callable_native_static_methods = callable_static;
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+pub fn create_map_class() -> Class {
+    let mut callable_static: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    callable_static.insert("new".to_string(), map_new);
+
+    callable_methods.insert("set".to_string(), map_set);
+    callable_methods.insert("get".to_string(), map_get);
+    callable_methods.insert("has".to_string(), collection_has);
+    callable_methods.insert("delete".to_string(), collection_delete);
+    instance_methods.insert("clear".to_string(), collection_clear);
+    instance_methods.insert("length".to_string(), collection_length);
+    instance_methods.insert("isEmpty".to_string(), collection_is_empty);
+    instance_methods.insert("at".to_string(), collection_at);
+    instance_methods.insert("keys".to_string(), collection_keys);
+    instance_methods.insert("values".to_string(), map_values);
+    instance_methods.insert("entries".to_string(), map_entries);
+    instance_methods.insert("copy".to_string(), collection_copy);
+    instance_methods.insert("toJson".to_string(), map_to_json);
+    instance_methods.insert("toString".to_string(), collection_to_string);
+    callable_methods.insert("forEach".to_string(), map_for_each);
+
+    let mut class = Class::new_with_instance("Map", instance_methods, None);
+    class.callable_constructor = Some(map_new);
+    class.callable_native_static_methods = callable_static;
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+pub fn create_counter_class() -> Class {
+    let mut callable_static: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    callable_static.insert("new".to_string(), counter_new);
+
+    callable_methods.insert("add".to_string(), counter_add);
+    callable_methods.insert("update".to_string(), counter_update);
+    callable_methods.insert("get".to_string(), counter_get);
+    callable_methods.insert("set".to_string(), counter_set);
+    callable_methods.insert("has".to_string(), collection_has);
+    callable_methods.insert("delete".to_string(), collection_delete);
+    instance_methods.insert("clear".to_string(), collection_clear);
+    instance_methods.insert("length".to_string(), collection_length);
+    instance_methods.insert("isEmpty".to_string(), collection_is_empty);
+    instance_methods.insert("at".to_string(), collection_at);
+    instance_methods.insert("total".to_string(), counter_total);
+    instance_methods.insert("mostCommon".to_string(), counter_most_common);
+    instance_methods.insert("keys".to_string(), collection_keys);
+    instance_methods.insert("values".to_string(), map_values);
+    instance_methods.insert("entries".to_string(), map_entries);
+    instance_methods.insert("copy".to_string(), collection_copy);
+    instance_methods.insert("toJson".to_string(), map_to_json);
+    instance_methods.insert("toString".to_string(), collection_to_string);
+    callable_methods.insert("forEach".to_string(), map_for_each);
+
+    let mut class = Class::new_with_instance("Counter", instance_methods, None);
+    class.callable_constructor = Some(counter_new);
+    class.callable_native_static_methods = callable_static;
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Set`, `Map` and `Counter` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new(
+            "Set",
+            "Insertion-ordered set of unique values, iterable with for-in",
+        )
+        .method(
+            "new",
+            "Set(items?)",
+            "Create a set from an array, Set or Map keys",
+        )
+        .method("add", "add(value)", "Add a value, returns the set")
+        .method("has", "has(value)", "Check if a value is in the set")
+        .method(
+            "delete",
+            "delete(value)",
+            "Remove a value, returns whether it was present",
+        )
+        .method("clear", "clear()", "Remove all values")
+        .method("length", "length()", "Number of values")
+        .method("isEmpty", "isEmpty()", "Check if the set is empty")
+        .method(
+            "at",
+            "at(index)",
+            "Value at an insertion position, negative from the end",
+        )
+        .method("toArray", "toArray()", "Values in insertion order")
+        .method("copy", "copy()", "Shallow copy")
+        .method("union", "union(other)", "Values in either set")
+        .method("intersect", "intersect(other)", "Values in both sets")
+        .method("difference", "difference(other)", "Values not in other")
+        .method(
+            "isSubset",
+            "isSubset(other)",
+            "Check if every value is in other",
+        )
+        .method("forEach", "forEach(fn)", "Call fn with each value"),
+        ClassDoc::new(
+            "Map",
+            "Insertion-ordered map with keys of any type, iterable over keys with for-in",
+        )
+        .method(
+            "new",
+            "Map(entries?)",
+            "Create a map from a dict, [key, value] pairs or another Map",
+        )
+        .method("set", "set(key, value)", "Set a value, returns the map")
+        .method(
+            "get",
+            "get(key, default?)",
+            "Value for key, or default (null)",
+        )
+        .method("has", "has(key)", "Check if a key is present")
+        .method(
+            "delete",
+            "delete(key)",
+            "Remove a key, returns whether it was present",
+        )
+        .method("clear", "clear()", "Remove all entries")
+        .method("length", "length()", "Number of entries")
+        .method("isEmpty", "isEmpty()", "Check if the map is empty")
+        .method(
+            "at",
+            "at(index)",
+            "Key at an insertion position, negative from the end",
+        )
+        .method("keys", "keys()", "Keys in insertion order")
+        .method("values", "values()", "Values in insertion order")
+        .method(
+            "entries",
+            "entries()",
+            "[key, value] pairs in insertion order",
+        )
+        .method("copy", "copy()", "Shallow copy")
+        .method("forEach", "forEach(fn)", "Call fn with each value and key"),
+        ClassDoc::new(
+            "Counter",
+            "Counts occurrences of values, iterable over the counted values with for-in",
+        )
+        .method(
+            "new",
+            "Counter(items?)",
+            "Count the items of an array, or copy counts from a dict or Counter",
+        )
+        .method(
+            "add",
+            "add(value, n?)",
+            "Add n (1) to a value's count, returns the new count",
+        )
+        .method(
+            "update",
+            "update(items)",
+            "Add counts from an array, dict or Counter, returns the counter",
+        )
+        .method("get", "get(value)", "Count of a value, 0 when absent")
+        .method(
+            "set",
+            "set(value, n)",
+            "Set a value's count, returns the counter",
+        )
+        .method("has", "has(value)", "Check if a value has been counted")
+        .method(
+            "delete",
+            "delete(value)",
+            "Forget a value, returns whether it was counted",
+        )
+        .method("clear", "clear()", "Remove all counts")
+        .method("length", "length()", "Number of distinct values")
+        .method("isEmpty", "isEmpty()", "Check if nothing has been counted")
+        .method("total", "total()", "Sum of all counts")
+        .method(
+            "mostCommon",
+            "mostCommon(n?)",
+            "[value, count] pairs, highest count first, ties in insertion order",
+        )
+        .method("keys", "keys()", "Counted values in insertion order")
+        .method("values", "values()", "Counts in insertion order")
+        .method(
+            "entries",
+            "entries()",
+            "[value, count] pairs in insertion order",
+        )
+        .method("copy", "copy()", "Shallow copy")
+        .method(
+            "forEach",
+            "forEach(fn)",
+            "Call fn with each count and value",
+        ),
+    ]
+}
+
+pub(crate) fn key(value: &Value) -> Result<Key, String> {
+    Ok(match value {
+        Value::Null => Key::Null,
+        Value::Boolean(b) => Key::Bool(*b),
+        // 0 and -0 are equal, so they must hash alike
+        Value::Number(n) if *n == 0.0 => Key::Number(0),
+        Value::Number(n) => Key::Number(n.to_bits()),
+        Value::String(s) => Key::String(s.clone()),
+        Value::Array(items) => {
+            Key::Array(items.borrow().iter().map(key).collect::<Result<_, _>>()?)
+        }
+        Value::Dictionary(dict) => Key::Ref(Rc::as_ptr(dict) as usize),
+        Value::Function(func) => Key::Ref(Rc::as_ptr(func) as usize),
+        Value::Class(class) => Key::Ref(Rc::as_ptr(class) as usize),
+        Value::Instance(inst) => {
+            // Calling `__hash__` needs the VM, which key_with only has for
+            // the outermost value
+            if inst.borrow().class.methods.contains_key("__hash__") {
+                return Err(format!(
+                    "'{}' defines __hash__, so it cannot be part of an array key",
+                    inst.borrow().class_name
+                ));
+            }
+            Key::Ref(Rc::as_ptr(inst) as usize)
+        }
+        Value::Future(future) => Key::Ref(Rc::as_ptr(future) as usize),
+        Value::Namespace { members, .. } => Key::Ref(Rc::as_ptr(members) as usize),
+        Value::Enum { variants, .. } => Key::Ref(Rc::as_ptr(variants) as usize),
+        other => return Err(format!("Cannot use a {} as a key", other.type_name())),
+    })
+}
+
+/// Like `key`, but keys instances of classes defining `__hash__` by its
+/// result, comparing them with `__eq__` to the instances with the same hash
+/// that `stored` finds under each key tried.
+pub(crate) fn key_with(
+    value: &Value,
+    stored: &mut dyn FnMut(&Key) -> Result<Option<Value>, String>,
+    caller: &mut dyn ValueCaller,
+) -> Result<Key, String> {
+    let Some(hash) = super::bound_user_method(value, "__hash__") else {
+        return key(value);
+    };
+    let hash = key(&caller.call(&hash, Vec::new())?)?;
+    let eq = super::bound_user_method(value, "__eq__");
+    let mut n = 0;
+    loop {
+        let k = Key::Hashed(Box::new(hash.clone()), n);
+        let Some(other) = stored(&k)? else {
+            return Ok(k);
+        };
+        let same = match (value, &other, &eq) {
+            (Value::Instance(a), Value::Instance(b), _) if Rc::ptr_eq(a, b) => true,
+            (_, _, Some(eq)) => caller.call(eq, vec![other])?.is_truthy(),
+            (_, _, None) => false,
+        };
+        if same {
+            return Ok(k);
+        }
+        n += 1;
+    }
+}
+
+/// Key of `value` among `entries`
+fn key_in(value: &Value, entries: &Entries, caller: &mut dyn ValueCaller) -> Result<Key, String> {
+    key_with(
+        value,
+        &mut |k| Ok(entries.get(k).map(|(stored, _)| stored.clone())),
+        caller,
+    )
+}
+
+/// Key of `value` in the collection `recv`. The store is only borrowed
+/// between calls, which may run `__eq__`.
+fn store_key(recv: &Value, value: &Value, caller: &mut dyn ValueCaller) -> Result<Key, String> {
+    key_with(
+        value,
+        &mut |k| {
+            with_store(recv, |store| {
+                Ok(store.entries.get(k).map(|(v, _)| v.clone()))
+            })
+        },
+        caller,
+    )
+}
+
+/// Numbers the instances sharing each hash from 0 again, after some were
+/// removed
+fn renumber(entries: Entries) -> Entries {
+    let mut numbers: FxHashMap<Key, Vec<usize>> = FxHashMap::default();
+    for k in entries.keys() {
+        if let Key::Hashed(hash, n) = k {
+            numbers.entry((**hash).clone()).or_default().push(*n);
+        }
+    }
+    for ns in numbers.values_mut() {
+        ns.sort_unstable();
+    }
+    entries
+        .into_iter()
+        .map(|(k, entry)| match k {
+            Key::Hashed(hash, n) => {
+                let rank = numbers[&*hash].binary_search(&n).unwrap_or(n);
+                (Key::Hashed(hash, rank), entry)
+            }
+            k => (k, entry),
+        })
+        .collect()
+}
+
+fn array(items: Vec<Value>) -> Value {
+    Value::Array(Rc::new(RefCell::new(items.into())))
+}
+
+fn register(kind: Kind, entries: Entries) -> Value {
+    let class = match kind {
+        Kind::Set => create_set_class(),
+        Kind::Map => create_map_class(),
+        Kind::Counter => create_counter_class(),
+    };
+    native_instance(class, Store { kind, entries })
+}
+
+fn with_store<T>(
+    recv: &Value,
+    f: impl FnOnce(&mut Store) -> Result<T, String>,
+) -> Result<T, String> {
+    let store = native_state::<Store>(recv, "Set, Map or Counter")?;
+    let mut store = store.borrow_mut();
+    f(&mut store)
+}
+
+/// Snapshot of another collection's entries, for set operations and copies.
+fn entries_of(value: &Value) -> Option<(Kind, Entries)> {
+    let Value::Instance(inst) = value else {
+        return None;
+    };
+    let store = inst.borrow().native::<Store>()?;
+    let store = store.borrow();
+    Some((store.kind, store.entries.clone()))
+}
+
+/// Class name of an instance, type name of anything else
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Instance(inst) => inst.borrow().class_name.clone(),
+        other => other.type_name().to_string(),
+    }
+}
+
+/// Entries to start a collection with: an array of items (or of pairs for a
+/// map), a dict, or another collection.
+fn initial_entries(
+    source: Option<&Value>,
+    is_map: bool,
+    caller: &mut dyn ValueCaller,
+) -> Result<Entries, String> {
+    let mut entries = Entries::default();
+    match source {
+        None | Some(Value::Null) => {}
+        Some(Value::Array(items)) => {
+            // Copied, as `__hash__` and `__eq__` may change the array
+            let items: Vec<Value> = items.borrow().iter().cloned().collect();
+            for item in &items {
+                let (k, v) = if is_map {
+                    match item {
+                        Value::Array(pair) if pair.borrow().len() == 2 => {
+                            let pair = pair.borrow();
+                            (pair[0].clone(), pair[1].clone())
+                        }
+                        _ => return Err("Map entries must be [key, value] pairs".to_string()),
+                    }
+                } else {
+                    (item.clone(), Value::Null)
+                };
+                let k_key = key_in(&k, &entries, caller)?;
+                entries.insert(k_key, (k, v));
+            }
+        }
+        Some(Value::Dictionary(dict)) if is_map => {
+            let pairs: Vec<(Value, Value)> = {
+                let dict = dict.borrow();
+                dict.iter().map(|(k, v)| (dict.key(k), v.clone())).collect()
+            };
+            for (k, v) in pairs {
+                let k_key = key_in(&k, &entries, caller)?;
+                entries.insert(k_key, (k, v));
+            }
+        }
+        Some(other) => match entries_of(other) {
+            Some((_, source)) if is_map => entries = source,
+            Some((_, source)) => {
+                for (k, (key_value, _)) in source {
+                    entries.insert(k, (key_value, Value::Null));
+                }
+            }
+            None => {
+                return Err(format!(
+                    "TypeError: Cannot create a {} from a {}",
+                    if is_map { "Map" } else { "Set" },
+                    describe(other)
+                ))
+            }
+        },
+    }
+    Ok(entries)
+}
+
+fn set_new(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    Ok(register(
+        Kind::Set,
+        initial_entries(args.first(), false, caller)?,
+    ))
+}
+
+fn map_new(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    Ok(register(
+        Kind::Map,
+        initial_entries(args.first(), true, caller)?,
+    ))
+}
+
+/// Whether two values are collections of the same kind with the same
+/// contents, in any order.
+pub(crate) fn collections_equal(a: &Value, b: &Value) -> bool {
+    let (Some((a_kind, a)), Some((b_kind, b))) = (entries_of(a), entries_of(b)) else {
+        return false;
+    };
+    a_kind == b_kind
+        && a.len() == b.len()
+        && a.iter().all(|(k, (_, value))| {
+            b.get(k)
+                .is_some_and(|(_, other)| a_kind == Kind::Set || value == other)
+        })
+}
+
+/// Display form such as `Set {1, 2}` or `Map {a: 1}`, if `inst` is a
+/// collection.
+pub(crate) fn format_collection(inst: &Instance) -> Option<String> {
+    let store = inst.native::<Store>()?;
+    let (kind, entries) = {
+        let store = store.borrow();
+        (store.kind, store.entries.clone())
+    };
+    let items: Vec<String> = entries
+        .values()
+        .map(|(k, v)| match kind {
+            Kind::Set => k.to_string(),
+            _ => format!("{}: {}", k, v),
+        })
+        .collect();
+    Some(format!("{} {{{}}}", kind.name(), items.join(", ")))
+}
+
+fn counter_new(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let mut entries = Entries::default();
+    if let Some(source) = args.first().filter(|source| !source.is_null()) {
+        add_counts(&mut entries, source, caller)?;
+    }
+    Ok(register(Kind::Counter, entries))
+}
+
+fn count_arg(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(n) => Ok(*n),
+        other => Err(format!("Count must be a number, got {}", other.type_name())),
+    }
+}
+
+fn add_count(
+    entries: &mut Entries,
+    value: &Value,
+    n: f64,
+    caller: &mut dyn ValueCaller,
+) -> Result<f64, String> {
+    let k = key_in(value, entries, caller)?;
+    add_count_at(entries, k, value, n)
+}
+
+fn add_count_at(entries: &mut Entries, k: Key, value: &Value, n: f64) -> Result<f64, String> {
+    let entry = entries
+        .entry(k)
+        .or_insert((value.clone(), Value::Number(0.0)));
+    let count = count_arg(&entry.1)? + n;
+    entry.1 = Value::Number(count);
+    Ok(count)
+}
+
+/// Adds one per item of an array, or the counts of a dict, Map or Counter.
+fn add_counts(
+    entries: &mut Entries,
+    source: &Value,
+    caller: &mut dyn ValueCaller,
+) -> Result<(), String> {
+    match source {
+        Value::Array(items) => {
+            let items: Vec<Value> = items.borrow().iter().cloned().collect();
+            for item in &items {
+                add_count(entries, item, 1.0, caller)?;
+            }
+        }
+        Value::Dictionary(dict) => {
+            let counts: Vec<(Value, Value)> = {
+                let dict = dict.borrow();
+                dict.iter().map(|(k, n)| (dict.key(k), n.clone())).collect()
+            };
+            for (k, n) in counts {
+                add_count(entries, &k, count_arg(&n)?, caller)?;
+            }
+        }
+        other => match entries_of(other) {
+            Some((Kind::Set, source)) => {
+                for (k, _) in source.into_values() {
+                    add_count(entries, &k, 1.0, caller)?;
+                }
+            }
+            Some((_, source)) => {
+                for (k, n) in source.into_values() {
+                    add_count(entries, &k, count_arg(&n)?, caller)?;
+                }
+            }
+            None => return Err(format!("TypeError: Cannot count a {}", describe(other))),
+        },
+    }
+    Ok(())
+}
+
+fn set_add(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let k = store_key(recv, &args[0], caller)?;
+    with_store(recv, |store| {
+        store
+            .entries
+            .entry(k)
+            .or_insert((args[0].clone(), Value::Null));
+        Ok(())
+    })?;
+    Ok(recv.clone())
+}
+
+fn map_set(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let k = store_key(recv, &args[0], caller)?;
+    with_store(recv, |store| {
+        store.entries.insert(k, (args[0].clone(), args[1].clone()));
+        Ok(())
+    })?;
+    Ok(recv.clone())
+}
+
+fn map_get(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let k = store_key(recv, &args[0], caller)?;
+    let value = with_store(recv, |store| {
+        Ok(store.entries.get(&k).map(|(_, v)| v.clone()))
+    })?;
+    Ok(value.unwrap_or_else(|| args.get(1).cloned().unwrap_or(Value::Null)))
+}
+
+fn collection_has(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let k = store_key(recv, &args[0], caller)?;
+    let has = with_store(recv, |store| Ok(store.entries.contains_key(&k)))?;
+    Ok(Value::Boolean(has))
+}
+
+fn collection_delete(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let k = store_key(recv, &args[0], caller)?;
+    let removed = with_store(recv, |store| {
+        let removed = store.entries.shift_remove(&k);
+        if matches!(k, Key::Hashed(..)) {
+            store.entries = renumber(std::mem::take(&mut store.entries));
+        }
+        Ok(removed)
+    })?;
+    Ok(Value::Boolean(removed.is_some()))
+}
+
+fn collection_clear(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let removed = with_store(recv, |store| Ok(std::mem::take(&mut store.entries)))?;
+    drop(removed);
+    Ok(Value::Null)
+}
+
+fn collection_length(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let len = with_store(recv, |store| Ok(store.entries.len()))?;
+    Ok(Value::Number(len as f64))
+}
+
+fn collection_is_empty(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let empty = with_store(recv, |store| Ok(store.entries.is_empty()))?;
+    Ok(Value::Boolean(empty))
+}
+
+fn collection_at(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let index = get_number_arg(&args[0], "index")? as i64;
+    with_store(recv, |store| {
+        let len = store.entries.len() as i64;
+        let index = if index < 0 { len + index } else { index };
+        if index < 0 || index >= len {
+            return Ok(Value::Null);
+        }
+        Ok(store
+            .entries
+            .get_index(index as usize)
+            .map(|(_, (k, _))| k.clone())
+            .unwrap_or(Value::Null))
+    })
+}
+
+fn collection_keys(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let keys = with_store(recv, |store| {
+        Ok(store.entries.values().map(|(k, _)| k.clone()).collect())
+    })?;
+    Ok(array(keys))
+}
+
+fn map_values(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let values = with_store(recv, |store| {
+        Ok(store.entries.values().map(|(_, v)| v.clone()).collect())
+    })?;
+    Ok(array(values))
+}
+
+fn map_entries(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let entries = with_store(recv, |store| {
+        Ok(store
+            .entries
+            .values()
+            .map(|(k, v)| array(vec![k.clone(), v.clone()]))
+            .collect())
+    })?;
+    Ok(array(entries))
+}
+
+fn collection_copy(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (kind, entries) = entries_of(recv).ok_or("Receiver must be a Set, Map or Counter")?;
+    Ok(register(kind, entries))
+}
+
+fn collection_to_string(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::String(Rc::from(recv.to_string())))
+}
+
+/// A map whose keys are all strings becomes an object, otherwise a list of
+/// [key, value] pairs.
+fn map_to_json(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (_, entries) = entries_of(recv).ok_or("Receiver must be a Map or Counter")?;
+    if entries.keys().all(|k| matches!(k, Key::String(_))) {
+        let dict = entries
+            .into_values()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+        return Ok(Value::Dictionary(Rc::new(RefCell::new(dict))));
+    }
+    Ok(array(
+        entries
+            .into_values()
+            .map(|(k, v)| array(vec![k, v]))
+            .collect(),
+    ))
+}
+
+fn other_set(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Entries, String> {
+    check_arity(1, args.len())?;
+    initial_entries(Some(&args[0]), false, caller)
+}
+
+fn set_union(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    let other = other_set(args, caller)?;
+    let (_, mut entries) = entries_of(recv).ok_or("Receiver must be a Set")?;
+    for (value, _) in other.into_values() {
+        let k = key_in(&value, &entries, caller)?;
+        entries.entry(k).or_insert((value, Value::Null));
+    }
+    Ok(register(Kind::Set, entries))
+}
+
+fn set_intersect(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    filter_set(recv, args, true, caller)
+}
+
+fn set_difference(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    filter_set(recv, args, false, caller)
+}
+
+/// The receiver's items that are (or are not) in the set argument
+fn filter_set(
+    recv: &Value,
+    args: &[Value],
+    keep_shared: bool,
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let other = other_set(args, caller)?;
+    let (_, entries) = entries_of(recv).ok_or("Receiver must be a Set")?;
+    let mut kept = Entries::default();
+    for (k, entry) in entries {
+        if other.contains_key(&key_in(&entry.0, &other, caller)?) == keep_shared {
+            kept.insert(k, entry);
+        }
+    }
+    Ok(register(Kind::Set, renumber(kept)))
+}
+
+fn set_is_subset(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let other = other_set(args, caller)?;
+    let (_, entries) = entries_of(recv).ok_or("Receiver must be a Set")?;
+    for (value, _) in entries.into_values() {
+        if !other.contains_key(&key_in(&value, &other, caller)?) {
+            return Ok(Value::Boolean(false));
+        }
+    }
+    Ok(Value::Boolean(true))
+}
+
+fn set_for_each(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let (_, entries) = entries_of(recv).ok_or("Receiver must be a Set")?;
+    for (value, _) in entries.into_values() {
+        caller.call(&args[0], vec![value])?;
+    }
+    Ok(Value::Null)
+}
+
+fn map_for_each(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let (_, entries) = entries_of(recv).ok_or("Receiver must be a Map")?;
+    for (k, v) in entries.into_values() {
+        caller.call(&args[0], vec![v, k])?;
+    }
+    Ok(Value::Null)
+}
+
+fn counter_add(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let n = match args.get(1) {
+        Some(n) => get_number_arg(n, "n")?,
+        None => 1.0,
+    };
+    let k = store_key(recv, &args[0], caller)?;
+    let count = with_store(recv, |store| {
+        add_count_at(&mut store.entries, k, &args[0], n)
+    })?;
+    Ok(Value::Number(count))
+}
+
+fn counter_update(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    // Counted separately first, so a counter can be updated from itself
+    let mut added = Entries::default();
+    add_counts(&mut added, &args[0], caller)?;
+    for (value, n) in added.into_values() {
+        let k = store_key(recv, &value, caller)?;
+        with_store(recv, |store| {
+            add_count_at(&mut store.entries, k, &value, count_arg(&n)?)
+        })?;
+    }
+    Ok(recv.clone())
+}
+
+fn counter_get(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let k = store_key(recv, &args[0], caller)?;
+    let count = with_store(recv, |store| {
+        Ok(store.entries.get(&k).map(|(_, n)| n.clone()))
+    })?;
+    Ok(count.unwrap_or(Value::Number(0.0)))
+}
+
+fn counter_set(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let k = store_key(recv, &args[0], caller)?;
+    let n = get_number_arg(&args[1], "n")?;
+    with_store(recv, |store| {
+        store.entries.insert(k, (args[0].clone(), Value::Number(n)));
+        Ok(())
+    })?;
+    Ok(recv.clone())
+}
+
+fn counter_total(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let total = with_store(recv, |store| {
+        store.entries.values().map(|(_, n)| count_arg(n)).sum()
+    })?;
+    Ok(Value::Number(total))
+}
+
+fn counter_most_common(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let limit = match args.first() {
+        None | Some(Value::Null) => usize::MAX,
+        Some(n) => get_number_arg(n, "n")?.max(0.0) as usize,
+    };
+    let (_, entries) = entries_of(recv).ok_or("Receiver must be a Counter")?;
+    let mut counted = entries
+        .into_values()
+        .map(|(k, n)| Ok((k, count_arg(&n)?)))
+        .collect::<Result<Vec<_>, String>>()?;
+    // Stable, so equal counts keep insertion order
+    counted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    Ok(array(
+        counted
+            .into_iter()
+            .take(limit)
+            .map(|(k, n)| array(vec![k, Value::Number(n)]))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::eval;
+
+    #[test]
+    fn test_user_id_field_is_not_a_collection() {
+        let setup = r#"
+            class User { fun init(self, id) { self._id = id } }
+            let s = Set([1, 2])
+            let u = User(1)
+        "#;
+        assert_eq!(eval(&format!("{setup}\n\"\" + u")), "<User instance>");
+        assert_eq!(eval(&format!("{setup}\nu == s")), "false");
+        assert_eq!(eval(&format!("{setup}\ns == Set([2, 1])")), "true");
+    }
+
+    #[test]
+    fn test_colliding_hashes_are_told_apart_by_eq() {
+        let setup = r#"
+            class Pt {
+                fun init(self, x, y) {
+                    self.x = x
+                    self.y = y
+                }
+                fun __hash__(self) { return self.x * 31 + self.y }
+                fun __eq__(self, other) { return self.x == other.x && self.y == other.y }
+            }
+            let s = Set()
+            s.add(Pt(1, 0))
+            s.add(Pt(0, 31))
+            s.add(Pt(1, 0))
+            let t = Set()
+            t.add(Pt(0, 31))
+        "#;
+        let run = |source: &str| eval(&format!("{setup}\n{source}"));
+        assert_eq!(run("s.length()"), "2");
+        assert_eq!(run("s.has(Pt(0, 31))"), "true");
+        assert_eq!(run("s.has(Pt(31, 0))"), "false");
+        assert_eq!(run("s.delete(Pt(1, 0))\ns.length()"), "1");
+        assert_eq!(run("s.delete(Pt(1, 0))\ns.has(Pt(0, 31))"), "true");
+        assert_eq!(run("s.difference(t).has(Pt(1, 0))"), "true");
+        assert_eq!(run("s.difference(t).has(Pt(0, 31))"), "false");
+        assert_eq!(run("s.intersect(t).length()"), "1");
+        assert_eq!(run("t.union(s).length()"), "2");
+        assert_eq!(run("t.isSubset(s)"), "true");
+        assert_eq!(
+            run("let items = [Pt(1, 0), Pt(0, 31), Pt(1, 0)]\nitems.unique().length()"),
+            "2"
+        );
+    }
+
+    #[test]
+    fn test_constructors_hash_instances_like_add() {
+        let setup = r#"
+            class K {
+                fun init(self, id) { self.id = id }
+                fun __hash__(self) { return self.id }
+                fun __eq__(self, other) { return self.id == other.id }
+            }
+            let items = [K(1), K(2), K(1)]
+        "#;
+        let run = |source: &str| eval(&format!("{setup}\n{source}"));
+        assert_eq!(run("Set([K(1), K(1)]).length()"), "1");
+        assert_eq!(run("Set(items).length()"), "2");
+        assert_eq!(run("Set.new(items).has(K(2))"), "true");
+        assert_eq!(run("Map([[K(1), 1], [K(1), 2]]).get(K(1))"), "2");
+        assert_eq!(run("Counter(items).get(K(1))"), "2");
+        assert_eq!(run("Counter().update(items).get(K(1))"), "2");
+        assert_eq!(run("Set([K(1)]).union(items).length()"), "2");
+
+        let added = run("let s = Set()\nfor k in items { s.add(k) }\ns.length()");
+        assert_eq!(added, run("Set(items).length()"));
+    }
+
+    #[test]
+    fn test_set_and_map_collections() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let s = Set([3, 1, 3, [1, 2]])\n\
+                 s.add(2)\n\
+                 let seen = []\n\
+                 for x in s { seen.push(x) }\n\
+                 let m = Map([[1, \"one\"], [[1, 2], \"pair\"]])\n\
+                 let keys = []\n\
+                 for k in m { keys.push(k) }",
+            )
+            .unwrap();
+        let seen: String = engine.eval_as("Json.stringify(seen)").unwrap();
+        assert_eq!(seen, "[3,1,[1,2],2]");
+        let keys: String = engine.eval_as("Json.stringify(keys)").unwrap();
+        assert_eq!(keys, "[1,[1,2]]");
+        let pair: String = engine.eval_as("m.get([1, 2])").unwrap();
+        assert_eq!(pair, "pair");
+        let equal: bool = engine
+            .eval_as("s == Set([2, [1, 2], 1, 3]) && s != Set([1])")
+            .unwrap();
+        assert!(equal);
+        let common: Vec<f64> = engine.eval_as("s.intersect([1, 2, 9]).toArray()").unwrap();
+        assert_eq!(common, [1.0, 2.0]);
+        let json: String = engine
+            .eval_as("Json.stringify(Map({\"a\": Set([1])}))")
+            .unwrap();
+        assert_eq!(json, "{\"a\":[1]}");
+    }
+}
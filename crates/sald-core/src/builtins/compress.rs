@@ -0,0 +1,358 @@
+//! Compression
+//! gzip, zlib, raw deflate and zstd, either in one shot over a byte array or
+//! string, incrementally through a stream object, or file to file. Open
+//! streams live in a per-thread table keyed by the id stored on the instance
+
+use super::docs::ClassDoc;
+use super::{
+    bytes_to_value, check_arity, check_arity_range, get_bytes_arg, get_number_arg, get_string_arg,
+    native_instance, native_state,
+};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use flate2::write::{
+    DeflateDecoder, DeflateEncoder, GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder,
+};
+use rustc_hash::FxHashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+/// A compressor or decompressor writing its output into a buffer.
+trait Codec: Write {
+    fn output(&mut self) -> &mut Vec<u8>;
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<u8>>;
+}
+
+macro_rules! flate_codec {
+    ($($ty:ident),*) => {$(
+        impl Codec for $ty<Vec<u8>> {
+            fn output(&mut self) -> &mut Vec<u8> {
+                self.get_mut()
+            }
+            fn finish(self: Box<Self>) -> std::io::Result<Vec<u8>> {
+                (*self).finish()
+            }
+        }
+    )*};
+}
+
+flate_codec!(
+    GzEncoder,
+    GzDecoder,
+    ZlibEncoder,
+    ZlibDecoder,
+    DeflateEncoder,
+    DeflateDecoder
+);
+
+impl Codec for zstd::stream::write::Encoder<'static, Vec<u8>> {
+    fn output(&mut self) -> &mut Vec<u8> {
+        self.get_mut()
+    }
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<u8>> {
+        (*self).finish()
+    }
+}
+
+impl Codec for zstd::stream::write::Decoder<'static, Vec<u8>> {
+    fn output(&mut self) -> &mut Vec<u8> {
+        self.get_mut()
+    }
+    fn finish(mut self: Box<Self>) -> std::io::Result<Vec<u8>> {
+        self.flush()?;
+        Ok(self.into_inner())
+    }
+}
+
+/// State of a `CompressStream`, `None` once it is finished
+type Stream = Option<Box<dyn Codec>>;
+
+pub fn create_compress_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("compress".to_string(), compress_compress);
+    static_methods.insert("decompress".to_string(), compress_decompress);
+    static_methods.insert("decompressText".to_string(), compress_decompress_text);
+    static_methods.insert("compressor".to_string(), compress_compressor);
+    static_methods.insert("decompressor".to_string(), compress_decompressor);
+    static_methods.insert("compressFile".to_string(), compress_compress_file);
+    static_methods.insert("decompressFile".to_string(), compress_decompress_file);
+
+    Class::new_with_static("Compress", static_methods)
+}
+
+fn create_stream_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("write".to_string(), stream_write);
+    instance_methods.insert("finish".to_string(), stream_finish);
+
+    Class::new_with_instance("CompressStream", instance_methods, None)
+}
+
+/// API documentation for the `Compress` class
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new(
+            "Compress",
+            "gzip, zlib, deflate and zstd compression over byte arrays and strings",
+        )
+        .method(
+            "compress",
+            "compress(data, format, level?)",
+            "Compress a string or byte array to a byte array",
+        )
+        .method(
+            "decompress",
+            "decompress(bytes, format)",
+            "Decompress a byte array",
+        )
+        .method(
+            "decompressText",
+            "decompressText(bytes, format)",
+            "Decompress a byte array holding UTF-8 text",
+        )
+        .method(
+            "compressor",
+            "compressor(format, level?)",
+            "Start a streaming compressor",
+        )
+        .method(
+            "decompressor",
+            "decompressor(format)",
+            "Start a streaming decompressor",
+        )
+        .method(
+            "compressFile",
+            "compressFile(src, dest, format, level?)",
+            "Compress one file into another without loading it whole",
+        )
+        .method(
+            "decompressFile",
+            "decompressFile(src, dest, format)",
+            "Decompress one file into another without loading it whole",
+        ),
+        ClassDoc::new(
+            "CompressStream",
+            "Streaming codec returned by Compress.compressor and Compress.decompressor",
+        )
+        .method(
+            "write",
+            "write(data)",
+            "Feed a chunk, returns the output bytes ready so far",
+        )
+        .method(
+            "finish",
+            "finish()",
+            "End the stream, returns the remaining output bytes",
+        ),
+    ]
+}
+
+fn get_level(args: &[Value], index: usize) -> Result<Option<i32>, String> {
+    match args.get(index) {
+        None | Some(Value::Null) => Ok(None),
+        Some(level) => Ok(Some(get_number_arg(level, "level")? as i32)),
+    }
+}
+
+fn codec(format: &str, compress: bool, level: Option<i32>) -> Result<Box<dyn Codec>, String> {
+    let flate_level = match level {
+        Some(level) if format != "zstd" && !(0..=9).contains(&level) => {
+            return Err(format!("{} level must be from 0 to 9", format))
+        }
+        Some(level) if format == "zstd" && !(1..=22).contains(&level) => {
+            return Err("zstd level must be from 1 to 22".to_string())
+        }
+        Some(_) if format == "zstd" => flate2::Compression::default(),
+        Some(level) => flate2::Compression::new(level as u32),
+        None => flate2::Compression::default(),
+    };
+    let out = Vec::new();
+    Ok(match (format, compress) {
+        ("gzip", true) => Box::new(GzEncoder::new(out, flate_level)),
+        ("gzip", false) => Box::new(GzDecoder::new(out)),
+        ("zlib", true) => Box::new(ZlibEncoder::new(out, flate_level)),
+        ("zlib", false) => Box::new(ZlibDecoder::new(out)),
+        ("deflate", true) => Box::new(DeflateEncoder::new(out, flate_level)),
+        ("deflate", false) => Box::new(DeflateDecoder::new(out)),
+        ("zstd", true) => Box::new(
+            zstd::stream::write::Encoder::new(out, level.unwrap_or(0))
+                .map_err(|e| e.to_string())?,
+        ),
+        ("zstd", false) => {
+            Box::new(zstd::stream::write::Decoder::new(out).map_err(|e| e.to_string())?)
+        }
+        _ => {
+            return Err(format!(
+                "Unknown format '{}', expected gzip, zlib, deflate or zstd",
+                format
+            ))
+        }
+    })
+}
+
+fn run(mut codec: Box<dyn Codec>, data: &[u8]) -> Result<Vec<u8>, String> {
+    codec.write_all(data).map_err(|e| e.to_string())?;
+    codec.finish().map_err(|e| e.to_string())
+}
+
+fn compress_compress(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let data = get_bytes_arg(&args[0], "data")?;
+    let format = get_string_arg(&args[1], "format")?;
+    let codec = codec(&format, true, get_level(args, 2)?)?;
+    Ok(bytes_to_value(&run(codec, &data)?))
+}
+
+fn decompress(args: &[Value]) -> Result<Vec<u8>, String> {
+    check_arity(2, args.len())?;
+    let data = get_bytes_arg(&args[0], "bytes")?;
+    let format = get_string_arg(&args[1], "format")?;
+    run(codec(&format, false, None)?, &data).map_err(|e| format!("Invalid {} data: {}", format, e))
+}
+
+fn compress_decompress(args: &[Value]) -> Result<Value, String> {
+    Ok(bytes_to_value(&decompress(args)?))
+}
+
+fn compress_decompress_text(args: &[Value]) -> Result<Value, String> {
+    let text = String::from_utf8(decompress(args)?)
+        .map_err(|_| "Decompressed data is not valid UTF-8".to_string())?;
+    Ok(Value::String(Rc::from(text)))
+}
+
+fn new_stream(codec: Box<dyn Codec>) -> Value {
+    native_instance(create_stream_class(), Some(codec) as Stream)
+}
+
+fn compress_compressor(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let format = get_string_arg(&args[0], "format")?;
+    Ok(new_stream(codec(&format, true, get_level(args, 1)?)?))
+}
+
+fn compress_decompressor(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let format = get_string_arg(&args[0], "format")?;
+    Ok(new_stream(codec(&format, false, None)?))
+}
+
+fn stream_write(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let data = get_bytes_arg(&args[0], "data")?;
+    let stream = native_state::<Stream>(recv, "CompressStream")?;
+    let mut stream = stream.borrow_mut();
+    let codec = stream.as_mut().ok_or("Stream is already finished")?;
+    if let Err(e) = codec.write_all(&data) {
+        *stream = None;
+        return Err(e.to_string());
+    }
+    Ok(bytes_to_value(&std::mem::take(codec.output())))
+}
+
+fn stream_finish(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let stream = native_state::<Stream>(recv, "CompressStream")?;
+    let codec = stream
+        .borrow_mut()
+        .take()
+        .ok_or("Stream is already finished")?;
+    Ok(bytes_to_value(&codec.finish().map_err(|e| e.to_string())?))
+}
+
+/// Pipes `src` through the codec into `dest` a chunk at a time.
+fn pipe_file(src: &str, dest: &str, mut codec: Box<dyn Codec>) -> Result<(), String> {
+    let mut input = File::open(crate::workspace::resolve(src))
+        .map_err(|e| format!("Cannot open '{}': {}", src, e))?;
+    let mut output = File::create(crate::workspace::resolve(dest))
+        .map_err(|e| format!("Cannot create '{}': {}", dest, e))?;
+    let write_err = |e: std::io::Error| format!("Cannot write '{}': {}", dest, e);
+
+    let mut chunk = vec![0; 64 * 1024];
+    loop {
+        let n = input.read(&mut chunk).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        codec.write_all(&chunk[..n]).map_err(|e| e.to_string())?;
+        output.write_all(codec.output()).map_err(write_err)?;
+        codec.output().clear();
+    }
+    let rest = codec.finish().map_err(|e| e.to_string())?;
+    output.write_all(&rest).map_err(write_err)
+}
+
+fn compress_compress_file(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(3, 4, args.len())?;
+    let src = get_string_arg(&args[0], "src")?;
+    let dest = get_string_arg(&args[1], "dest")?;
+    let format = get_string_arg(&args[2], "format")?;
+    pipe_file(&src, &dest, codec(&format, true, get_level(args, 3)?)?)?;
+    Ok(Value::Null)
+}
+
+fn compress_decompress_file(args: &[Value]) -> Result<Value, String> {
+    check_arity(3, args.len())?;
+    let src = get_string_arg(&args[0], "src")?;
+    let dest = get_string_arg(&args[1], "dest")?;
+    let format = get_string_arg(&args[2], "format")?;
+    pipe_file(&src, &dest, codec(&format, false, None)?)?;
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_compress_round_trips() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let text = \"hello hello hello hello hello\"\n\
+                 let results = []\n\
+                 for format in [\"gzip\", \"zlib\", \"deflate\", \"zstd\"] {\n\
+                     let packed = Compress.compress(text, format, 9)\n\
+                     results.push(Compress.decompressText(packed, format) == text)\n\
+                     let out = Compress.compressor(format)\n\
+                     let chunks = out.write(\"hello \").concat(out.write(\"world\")).concat(out.finish())\n\
+                     let back = Compress.decompressor(format)\n\
+                     results.push(back.write(chunks).concat(back.finish()).length())\n\
+                 }",
+            )
+            .unwrap();
+
+        let results: Vec<serde_json::Value> = engine.eval_as("results").unwrap();
+        assert_eq!(results.len(), 8);
+        for pair in results.chunks(2) {
+            assert_eq!(pair[0], serde_json::json!(true));
+            assert_eq!(pair[1], serde_json::json!(11));
+        }
+        assert!(engine
+            .eval("Compress.decompress([1, 2, 3], \"gzip\")")
+            .is_err());
+    }
+
+    #[test]
+    fn test_compress_files_relative_to_the_project() {
+        let dir = std::env::temp_dir().join(format!("sald-compress-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("log.txt"), "line\n".repeat(100)).unwrap();
+
+        let mut engine = Engine::new();
+        engine.vm().set_project_root(&dir);
+        engine
+            .eval(
+                "Compress.compressFile(\"log.txt\", \"log.txt.gz\", \"gzip\")\n\
+                 Compress.decompressFile(\"log.txt.gz\", \"back.txt\", \"gzip\")",
+            )
+            .unwrap();
+        let back = std::fs::read_to_string(dir.join("back.txt"));
+        let packed = std::fs::metadata(dir.join("log.txt.gz")).map(|m| m.len());
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(back.unwrap(), "line\n".repeat(100));
+        assert!(packed.unwrap() < 500);
+    }
+}
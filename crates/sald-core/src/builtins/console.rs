@@ -1,73 +1,473 @@
-use super::check_arity;
-use crate::vm::value::{Class, NativeStaticFn, Value};
+//! Console output
+//! Output goes through per-stream writers that hosts can replace, and is
+//! indented by the open `Console.group`s.
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, get_string_arg, NamedArgs};
+use crate::inspect::{inspect, inspect_with, InspectOptions};
+use crate::vm::caller::{CallableNativeStaticFn, ValueCaller};
+use crate::vm::value::{Class, DisplayText, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use unicode_width::UnicodeWidthStr;
 
-pub fn create_console_class() -> Class {
-    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+/// Output stream of the console
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleStream {
+    Stdout,
+    Stderr,
+}
 
-    static_methods.insert("print".to_string(), console_print);
-    static_methods.insert("println".to_string(), console_println);
-    static_methods.insert("input".to_string(), console_input);
-    static_methods.insert("clear".to_string(), console_clear);
+#[cfg(not(target_arch = "wasm32"))]
+type Stamp = std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+type Stamp = f64;
+
+thread_local! {
+    #[cfg(not(target_arch = "wasm32"))]
+    static WRITERS: RefCell<[Option<Box<dyn std::io::Write>>; 2]> = const { RefCell::new([None, None]) };
+    static GROUP_DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// Whether the last output ended a line, so the next gets indented
+    static AT_LINE_START: Cell<bool> = const { Cell::new(true) };
+    static TIMERS: RefCell<FxHashMap<String, Stamp>> = RefCell::new(FxHashMap::default());
+}
+
+/// Sends console output for `stream` on this thread to `writer`, or back to
+/// the process's stdout or stderr with `None`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_console_writer(stream: ConsoleStream, writer: Option<Box<dyn std::io::Write>>) {
+    WRITERS.with(|writers| writers.borrow_mut()[stream as usize] = writer);
+}
 
-    Class::new_with_static("Console", static_methods)
+/// Writes `text` to stderr, or where `set_console_writer` sent it.
+pub(crate) fn write_stderr(text: &str) {
+    write(ConsoleStream::Stderr, text);
 }
 
-fn console_print(args: &[Value]) -> Result<Value, String> {
+/// Writes `text` to `stream`, indenting each new line for the open groups.
+fn write(stream: ConsoleStream, text: &str) {
+    let indent = "  ".repeat(GROUP_DEPTH.with(Cell::get));
     let mut output = String::new();
-    for (i, arg) in args.iter().enumerate() {
-        if i > 0 {
-            output.push(' ');
+    for piece in text.split_inclusive('\n') {
+        if AT_LINE_START.with(Cell::get) && piece != "\n" {
+            output.push_str(&indent);
         }
-        output.push_str(&arg.to_string());
+        output.push_str(piece);
+        AT_LINE_START.with(|start| start.set(piece.ends_with('\n')));
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     {
-        print!("{}", output);
         use std::io::Write;
-        std::io::stdout().flush().ok();
+        let written = WRITERS.with(|writers| {
+            let mut writers = writers.borrow_mut();
+            let writer = writers[stream as usize].as_mut()?;
+            writer.write_all(output.as_bytes()).ok();
+            writer.flush().ok();
+            Some(())
+        });
+        if written.is_none() {
+            match stream {
+                ConsoleStream::Stdout => {
+                    let mut stdout = std::io::stdout();
+                    stdout.write_all(output.as_bytes()).ok();
+                    stdout.flush().ok();
+                }
+                ConsoleStream::Stderr => {
+                    std::io::stderr().write_all(output.as_bytes()).ok();
+                }
+            }
+        }
     }
 
     #[cfg(target_arch = "wasm32")]
-    {
-        crate::wasm::wasm_println(output);
+    match stream {
+        ConsoleStream::Stdout => crate::wasm::write_stdout(&output),
+        ConsoleStream::Stderr => crate::wasm::write_stderr(&output),
     }
+}
+
+pub fn create_console_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+
+    callable_methods.insert("print".to_string(), console_print);
+    callable_methods.insert("println".to_string(), console_println);
+    callable_methods.insert("log".to_string(), console_log);
+    static_methods.insert("inspect".to_string(), console_inspect);
+    callable_methods.insert("error".to_string(), console_error);
+    static_methods.insert("table".to_string(), console_table);
+    static_methods.insert("group".to_string(), console_group);
+    static_methods.insert("groupEnd".to_string(), console_group_end);
+    static_methods.insert("time".to_string(), console_time);
+    static_methods.insert("timeLog".to_string(), console_time_log);
+    static_methods.insert("timeEnd".to_string(), console_time_end);
+    static_methods.insert("input".to_string(), console_input);
+    static_methods.insert("clear".to_string(), console_clear);
+
+    let mut class = Class::new_with_static("Console", static_methods);
+    class.callable_native_static_methods = callable_methods;
+    class.named_arg_methods.insert("inspect".to_string());
+    class
+}
+
+/// API documentation for the `Console` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Console", "Console I/O operations")
+        .method("print", "print(...args)", "Print without newline")
+        .method("println", "println(...args)", "Print with newline")
+        .method(
+            "log",
+            "log(...args)",
+            "Print with newline, showing strings as-is, instances by their toString() and other values as inspect does",
+        )
+        .method(
+            "inspect",
+            "inspect(value, ?depth, ?colors)",
+            "Readable multi-line rendering of value; nesting below depth (default 2, null for all) is abbreviated. Options may also be passed by name, as in inspect(value, depth: 0)",
+        )
+        .method("error", "error(...args)", "Like log, but writes to stderr")
+        .method(
+            "table",
+            "table(data, ?columns)",
+            "Print an array or dictionary of rows as a table, optionally only some columns",
+        )
+        .method(
+            "group",
+            "group(?label)",
+            "Print label and indent later output until groupEnd",
+        )
+        .method("groupEnd", "groupEnd()", "Close the innermost group")
+        .method("time", "time(?label)", "Start a named timer")
+        .method(
+            "timeLog",
+            "timeLog(?label)",
+            "Print the time elapsed on a timer",
+        )
+        .method(
+            "timeEnd",
+            "timeEnd(?label)",
+            "Print the time elapsed on a timer and stop it",
+        )
+        .method("input", "input(prompt?)", "Read user input")
+        .method("clear", "clear()", "Clear the console")
+}
+
+/// What the classes' own `toString` return for the instances in `args`,
+/// including those nested in arrays and dictionaries
+fn display_text(args: &[Value], caller: &mut dyn ValueCaller) -> Result<DisplayText, String> {
+    let mut text = DisplayText::default();
+    for arg in args {
+        text.extend(super::user_display_text(arg, &mut |method, args| {
+            caller.call(method, args)
+        })?);
+    }
+    Ok(text)
+}
 
+fn console_print(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    let text = display_text(args, caller)?;
+    let mut output = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            output.push(' ');
+        }
+        output.push_str(&arg.display_with(&text));
+    }
+    write(ConsoleStream::Stdout, &output);
     Ok(Value::Null)
 }
 
-fn console_println(args: &[Value]) -> Result<Value, String> {
+fn console_println(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    let text = display_text(args, caller)?;
     let mut output = String::new();
     for (i, arg) in args.iter().enumerate() {
         if i > 0 {
             output.push(' ');
         }
-        output.push_str(&arg.to_string());
+        output.push_str(&arg.display_with(&text));
     }
+    write(ConsoleStream::Stdout, &format!("{}\n", output));
+    Ok(Value::Null)
+}
 
+/// Strings as-is, other values as `inspect` renders them with `text`
+fn log_line(args: &[Value], colors: bool, text: &DisplayText) -> String {
+    let options = InspectOptions {
+        colors,
+        ..InspectOptions::default()
+    };
+    let parts: Vec<String> = args
+        .iter()
+        .map(|arg| match arg {
+            Value::String(s) => s.to_string(),
+            other => inspect_with(other, &options, text),
+        })
+        .collect();
+    format!("{}\n", parts.join(" "))
+}
+
+fn console_log(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    let text = display_text(args, caller)?;
     #[cfg(not(target_arch = "wasm32"))]
-    {
-        println!("{}", output);
+    let colors = super::term::colors_enabled();
+    #[cfg(target_arch = "wasm32")]
+    let colors = false;
+    write(ConsoleStream::Stdout, &log_line(args, colors, &text));
+    Ok(Value::Null)
+}
+
+fn console_error(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    let text = display_text(args, caller)?;
+    write(ConsoleStream::Stderr, &log_line(args, false, &text));
+    Ok(Value::Null)
+}
+
+fn console_group(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    if let Some(label) = args.first() {
+        write(
+            ConsoleStream::Stdout,
+            &log_line(std::slice::from_ref(label), false, &DisplayText::default()),
+        );
     }
+    GROUP_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    Ok(Value::Null)
+}
 
+fn console_group_end(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    GROUP_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+    Ok(Value::Null)
+}
+
+fn timer_label(args: &[Value]) -> Result<String, String> {
+    check_arity_range(0, 1, args.len())?;
+    match args.first() {
+        None | Some(Value::Null) => Ok("default".to_string()),
+        Some(label) => get_string_arg(label, "label"),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn stamp() -> Stamp {
+    super::clock::now()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn stamp() -> Stamp {
+    js_sys::Date::now()
+}
+
+fn elapsed_ms(since: Stamp) -> f64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        (stamp() - since).as_secs_f64() * 1000.0
+    }
     #[cfg(target_arch = "wasm32")]
     {
-        crate::wasm::wasm_println(output);
+        stamp() - since
+    }
+}
+
+fn console_time(args: &[Value]) -> Result<Value, String> {
+    let label = timer_label(args)?;
+    TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        if timers.contains_key(&label) {
+            return Err(format!("Timer '{}' is already running", label));
+        }
+        timers.insert(label, stamp());
+        Ok(Value::Null)
+    })
+}
+
+fn log_elapsed(args: &[Value], stop: bool) -> Result<Value, String> {
+    let label = timer_label(args)?;
+    let start = TIMERS.with(|timers| {
+        let mut timers = timers.borrow_mut();
+        if stop {
+            timers.remove(&label)
+        } else {
+            timers.get(&label).copied()
+        }
+    });
+    let start = start.ok_or_else(|| format!("No timer named '{}'", label))?;
+    write(
+        ConsoleStream::Stdout,
+        &format!("{}: {:.3}ms\n", label, elapsed_ms(start)),
+    );
+    Ok(Value::Null)
+}
+
+fn console_time_log(args: &[Value]) -> Result<Value, String> {
+    log_elapsed(args, false)
+}
+
+fn console_time_end(args: &[Value]) -> Result<Value, String> {
+    log_elapsed(args, true)
+}
+
+/// Cells of one table row by column name
+type Cells = Vec<(String, Value)>;
+
+/// Rows of `data` as (index, cells) pairs. Dictionary rows give one
+/// column per key, array rows one per position, and other values go in a
+/// "Values" column.
+fn table_rows(data: &Value) -> Result<Vec<(String, Cells)>, String> {
+    let rows: Vec<(String, Value)> = match data {
+        Value::Array(items) => items
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(i, row)| (i.to_string(), row.clone()))
+            .collect(),
+        Value::Dictionary(dict) => {
+            let mut rows: Vec<(String, Value)> = dict
+                .borrow()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            rows.sort_by(|a, b| a.0.cmp(&b.0));
+            rows
+        }
+        other => {
+            return Err(format!(
+                "Argument 'data' must be an array or dictionary, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    Ok(rows
+        .into_iter()
+        .map(|(index, row)| {
+            let cells = match &row {
+                Value::Dictionary(dict) => {
+                    let mut cells: Vec<(String, Value)> = dict
+                        .borrow()
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect();
+                    cells.sort_by(|a, b| a.0.cmp(&b.0));
+                    cells
+                }
+                Value::Array(items) => items
+                    .borrow()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v.clone()))
+                    .collect(),
+                other => vec![("Values".to_string(), other.clone())],
+            };
+            (index, cells)
+        })
+        .collect())
+}
+
+fn console_table(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let rows = table_rows(&args[0])?;
+
+    let mut columns: Vec<String> = Vec::new();
+    match args.get(1) {
+        None | Some(Value::Null) => {
+            for (_, cells) in &rows {
+                for (column, _) in cells {
+                    if !columns.contains(column) {
+                        columns.push(column.clone());
+                    }
+                }
+            }
+        }
+        Some(Value::Array(names)) => {
+            for name in names.borrow().iter() {
+                columns.push(get_string_arg(name, "columns")?);
+            }
+        }
+        Some(other) => {
+            return Err(format!(
+                "Argument 'columns' must be an array of strings, got {}",
+                other.type_name()
+            ))
+        }
+    }
+
+    let options = InspectOptions {
+        depth: Some(0),
+        ..InspectOptions::default()
+    };
+    let mut header = vec!["(index)".to_string()];
+    header.extend(columns.iter().cloned());
+    let mut lines = vec![header];
+    for (index, cells) in &rows {
+        let mut line = vec![index.clone()];
+        for column in &columns {
+            let cell = cells
+                .iter()
+                .find(|(name, _)| name == column)
+                .map(|(_, value)| inspect(value, &options).replace('\n', " "))
+                .unwrap_or_default();
+            line.push(cell);
+        }
+        lines.push(line);
     }
 
+    let widths: Vec<usize> = (0..lines[0].len())
+        .map(|i| lines.iter().map(|l| l[i].width()).max().unwrap_or(0))
+        .collect();
+    let rule = |left: &str, middle: &str, right: &str| {
+        let parts: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        format!("{}{}{}\n", left, parts.join(middle), right)
+    };
+    let row = |cells: &[String]| {
+        let parts: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, w)| format!(" {}{} ", cell, " ".repeat(w - cell.width())))
+            .collect();
+        format!("│{}│\n", parts.join("│"))
+    };
+
+    let mut output = rule("┌", "┬", "┐");
+    output.push_str(&row(&lines[0]));
+    output.push_str(&rule("├", "┼", "┤"));
+    for line in &lines[1..] {
+        output.push_str(&row(line));
+    }
+    output.push_str(&rule("└", "┴", "┘"));
+    write(ConsoleStream::Stdout, &output);
     Ok(Value::Null)
 }
 
+fn console_inspect(args: &[Value]) -> Result<Value, String> {
+    let (args, named) = NamedArgs::split(args, &["depth", "colors"])?;
+    check_arity_range(1, 3, args.len())?;
+    let mut options = InspectOptions::default();
+    match named.get(args, 1, "depth")? {
+        None => {}
+        Some(Value::Null) => options.depth = None,
+        Some(depth) => {
+            let depth = get_number_arg(&depth, "depth")?;
+            if depth < 0.0 {
+                return Err("Argument 'depth' must not be negative".to_string());
+            }
+            options.depth = (depth.is_finite()).then_some(depth as usize);
+        }
+    }
+    if let Some(colors) = named.get(args, 2, "colors")? {
+        options.colors = colors.is_truthy();
+    }
+    Ok(Value::String(Rc::from(inspect(&args[0], &options))))
+}
+
 fn console_input(args: &[Value]) -> Result<Value, String> {
     #[cfg(not(target_arch = "wasm32"))]
     {
         if !args.is_empty() {
-            print!("{}", args[0]);
-            use std::io::Write;
-            std::io::stdout().flush().ok();
+            write(ConsoleStream::Stdout, &args[0].to_string());
         }
+        AT_LINE_START.with(|start| start.set(true));
 
         let mut input = String::new();
         std::io::stdin()
@@ -80,8 +480,11 @@ fn console_input(args: &[Value]) -> Result<Value, String> {
 
     #[cfg(target_arch = "wasm32")]
     {
-        let _ = args;
-        Err("Console.input() is not available in WASM playground".to_string())
+        let prompt = args.first().map(|p| p.to_string()).unwrap_or_default();
+        match crate::wasm::read_line(&prompt) {
+            Some(line) => Ok(Value::String(Rc::from(line?))),
+            None => Err("Console.input() is not available in WASM playground".to_string()),
+        }
     }
 }
 
@@ -97,3 +500,125 @@ fn console_clear(args: &[Value]) -> Result<Value, String> {
 
     Ok(Value::Null)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{set_console_writer, ConsoleStream};
+    use crate::engine::Engine;
+    use crate::test_util::{eval, eval_err};
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    const POINT: &str = "class P {
+    fun init(self, x) { self.x = x }
+    fun toString(self) { return \"P\" + self.x }
+}
+";
+
+    struct Capture(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// What `source` writes to `stream`
+    fn output(stream: ConsoleStream, source: &str) -> String {
+        let out = Rc::new(RefCell::new(Vec::new()));
+        set_console_writer(stream, Some(Box::new(Capture(out.clone()))));
+        eval(source);
+        set_console_writer(stream, None);
+        let bytes = out.borrow().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_println_shows_nested_instances_by_to_string() {
+        let source = format!("{POINT}Console.println([P(1), {{\"k\": P(2)}}], P(3))");
+        assert_eq!(
+            output(ConsoleStream::Stdout, &source),
+            "[P1, {\"k\": P2}] P3\n"
+        );
+    }
+
+    #[test]
+    fn test_inspected_output_shows_nested_instances_by_to_string() {
+        let source = format!(
+            "{POINT}class Box {{ fun init(self, v) {{ self.v = v }} }}\n\
+             Console.error([P(1)], Box(P(2)))"
+        );
+        assert_eq!(
+            output(ConsoleStream::Stderr, &source),
+            "[ P1 ] Box { v: P2 }\n"
+        );
+    }
+
+    #[test]
+    fn test_nested_to_string_errors_are_raised() {
+        let err = eval_err("class B { fun toString(self) { return 1 } }\nConsole.println([B()])");
+        assert!(
+            err.contains("toString() must return a String, got Number"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_console_table_group_and_writers() {
+        struct Capture(Rc<RefCell<Vec<u8>>>);
+        impl Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let out = Rc::new(RefCell::new(Vec::new()));
+        let err = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_console_output(ConsoleStream::Stdout, Capture(out.clone()));
+        engine.set_console_output(ConsoleStream::Stderr, Capture(err.clone()));
+        engine
+            .eval(
+                r#"
+Console.group("rows")
+Console.table([{"a": 1, "b": "x"}, {"a": 22}])
+Console.groupEnd()
+Console.error("bad", [1])
+Console.time()
+Console.timeEnd()
+"#,
+            )
+            .unwrap();
+        crate::builtins::set_console_writer(ConsoleStream::Stdout, None);
+        crate::builtins::set_console_writer(ConsoleStream::Stderr, None);
+
+        let out = String::from_utf8(out.borrow().clone()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[..7],
+            [
+                "rows",
+                "  ┌─────────┬────┬─────┐",
+                "  │ (index) │ a  │ b   │",
+                "  ├─────────┼────┼─────┤",
+                "  │ 0       │ 1  │ 'x' │",
+                "  │ 1       │ 22 │     │",
+                "  └─────────┴────┴─────┘",
+            ]
+        );
+        assert!(lines[7].starts_with("default: ") && lines[7].ends_with("ms"));
+        assert_eq!(
+            String::from_utf8(err.borrow().clone()).unwrap(),
+            "bad [ 1 ]\n"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Cron schedules
+//! Standard five-field expressions (minute, hour, day of month, month, day of
+//! week) with `*`, lists, ranges, steps, month and weekday names and the
+//! `@hourly`-style shorthands. Times are UTC, like `Date`
+
+use super::clock;
+use super::date::{date_parts, make_date, DateKind};
+use super::docs::ClassDoc;
+use super::timer::{schedule, Repeat};
+use super::{check_arity, check_arity_range, get_string_arg};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use chrono::{DateTime, Datelike, Timelike};
+use rustc_hash::FxHashMap;
+use std::time::{Duration, Instant};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Parsed schedule, one bit per allowed value of each field.
+pub(crate) struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+pub fn create_cron_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("schedule".to_string(), cron_schedule);
+    static_methods.insert("next".to_string(), cron_next);
+    static_methods.insert("isValid".to_string(), cron_is_valid);
+
+    Class::new_with_static("Cron", static_methods)
+}
+
+/// API documentation for the `Cron` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Cron", "Cron-style schedules in UTC")
+        .method(
+            "schedule",
+            "schedule(expression, fn)",
+            "Call fn at every time matching expression, returns a TimerHandle",
+        )
+        .method(
+            "next",
+            "next(expression, after?)",
+            "Next Date matching expression after now or after a Date",
+        )
+        .method(
+            "isValid",
+            "isValid(expression)",
+            "Check if expression parses",
+        )
+}
+
+fn parse_value(text: &str, min: u32, names: &[&str]) -> Result<u32, String> {
+    if let Some(index) = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(text))
+    {
+        return Ok(index as u32 + min);
+    }
+    text.parse()
+        .map_err(|_| format!("Invalid value '{}' in cron expression", text))
+}
+
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("Invalid step '{}' in cron expression", step))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                parse_value(start, min, names)?,
+                parse_value(end, min, names)?,
+            )
+        } else {
+            let start = parse_value(range, min, names)?;
+            // `5/15` means from 5 to the end in steps of 15
+            (start, if part.contains('/') { max } else { start })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "'{}' is outside {}-{} in cron expression",
+                part, min, max
+            ));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl CronSchedule {
+    pub(crate) fn parse(expression: &str) -> Result<Self, String> {
+        let expression = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron expression needs 5 fields but '{}' has {}",
+                expression,
+                fields.len()
+            ));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7, &WEEKDAYS)?;
+        // Both 0 and 7 are Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days: parse_field(day, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTHS)?,
+            weekdays,
+            any_day: day.starts_with('*'),
+            any_weekday: weekday.starts_with('*'),
+        })
+    }
+
+    fn day_matches(&self, day: u32, weekday: u32) -> bool {
+        let day_ok = self.days & (1 << day) != 0;
+        let weekday_ok = self.weekdays & (1 << weekday) != 0;
+        // When both day fields are restricted, either one may match
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day_ok || weekday_ok,
+            _ => day_ok && weekday_ok,
+        }
+    }
+
+    /// First matching minute strictly after the Unix time `after`.
+    pub(crate) fn next_after(&self, after: i64) -> Option<i64> {
+        let mut time = DateTime::from_timestamp(after - after.rem_euclid(60) + 60, 0)?;
+        // Any schedule that can fire at all does so within a leap cycle
+        let limit = time + chrono::Duration::days(366 * 8);
+        while time < limit {
+            let next = if self.months & (1 << time.month()) == 0 {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time.with_day(1)?
+                    .with_year(year)?
+                    .with_month(month)?
+                    .with_hour(0)?
+                    .with_minute(0)?
+            } else if !self.day_matches(time.day(), time.weekday().num_days_from_sunday()) {
+                (time + chrono::Duration::days(1))
+                    .with_hour(0)?
+                    .with_minute(0)?
+            } else if self.hours & (1 << time.hour()) == 0 {
+                (time + chrono::Duration::hours(1)).with_minute(0)?
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time + chrono::Duration::minutes(1)
+            } else {
+                return Some(time.timestamp());
+            };
+            time = next;
+        }
+        None
+    }
+
+    pub(crate) fn next_instant(&self) -> Option<Instant> {
+        let now = clock::unix_time();
+        let next = self.next_after(now.as_secs() as i64)?;
+        let wait = Duration::from_secs(next as u64).checked_sub(now)?;
+        Some(clock::now() + wait)
+    }
+}
+
+fn cron_schedule(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let schedule_expr = CronSchedule::parse(&get_string_arg(&args[0], "expression")?)?;
+    let due = schedule_expr
+        .next_instant()
+        .ok_or("Cron expression never matches")?;
+    Ok(schedule(
+        due,
+        Repeat::Cron(Box::new(schedule_expr)),
+        args[1].clone(),
+    ))
+}
+
+fn cron_next(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let schedule = CronSchedule::parse(&get_string_arg(&args[0], "expression")?)?;
+    let after = match args.get(1) {
+        None | Some(Value::Null) => clock::unix_time().as_secs() as i64,
+        Some(value) => match date_parts(value) {
+            Some((seconds, DateKind::Date)) => seconds.floor() as i64,
+            _ => return Err("Argument 'after' must be a Date".to_string()),
+        },
+    };
+    let next = schedule
+        .next_after(after)
+        .ok_or("Cron expression never matches")?;
+    Ok(make_date(next as f64, DateKind::Date))
+}
+
+fn cron_is_valid(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let valid = match &args[0] {
+        Value::String(expression) => CronSchedule::parse(expression).is_ok(),
+        _ => false,
+    };
+    Ok(Value::Boolean(valid))
+}
@@ -1,11 +1,28 @@
-use super::{check_arity, get_number_arg, get_string_arg};
+use super::date::{make_date, DateKind};
+use super::docs::ClassDoc;
+use super::{
+    bytes_to_value, check_arity, check_arity_range, get_bytes_arg, get_number_arg, get_string_arg,
+};
 use crate::vm::value::{Class, NativeStaticFn, Value};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, OsRng, Payload};
+use aes_gcm::{Aes128Gcm, Aes256Gcm, Nonce};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Argon2, Params};
 use base64::{engine::general_purpose, Engine};
 use hmac::{Hmac, Mac};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey};
+use rsa::signature::{SignatureEncoding, Signer, Verifier};
+use rsa::{RsaPrivateKey, RsaPublicKey};
 use rustc_hash::FxHashMap;
 use sha2::{Digest, Sha256, Sha512};
 use std::cell::RefCell;
 use std::rc::Rc;
+use subtle::ConstantTimeEq;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::GeneralName;
+use x509_parser::objects::{oid2sn, oid_registry};
+use x509_parser::oid_registry::Oid;
 
 type HmacSha256 = Hmac<Sha256>;
 type HmacSha512 = Hmac<Sha512>;
@@ -16,38 +33,138 @@ pub fn create_crypto_class() -> Class {
     static_methods.insert("hash".to_string(), crypto_hash);
     static_methods.insert("hmac".to_string(), crypto_hmac);
     static_methods.insert("uuid".to_string(), crypto_uuid);
+    static_methods.insert("nanoid".to_string(), crypto_nanoid);
     static_methods.insert("randomBytes".to_string(), crypto_random_bytes);
     static_methods.insert("randomInt".to_string(), crypto_random_int);
+    static_methods.insert("timingSafeEqual".to_string(), crypto_timing_safe_equal);
     static_methods.insert("base64Encode".to_string(), crypto_base64_encode);
     static_methods.insert("base64Decode".to_string(), crypto_base64_decode);
+    static_methods.insert("x509".to_string(), crypto_x509);
+    static_methods.insert("hmacBytes".to_string(), crypto_hmac_bytes);
+    static_methods.insert("pbkdf2".to_string(), crypto_pbkdf2);
+    static_methods.insert("hashPassword".to_string(), crypto_hash_password);
+    static_methods.insert("verifyPassword".to_string(), crypto_verify_password);
+    static_methods.insert("encrypt".to_string(), crypto_encrypt);
+    static_methods.insert("decrypt".to_string(), crypto_decrypt);
+    static_methods.insert("generateKeyPair".to_string(), crypto_generate_key_pair);
+    static_methods.insert("sign".to_string(), crypto_sign);
+    static_methods.insert("verify".to_string(), crypto_verify);
 
     Class::new_with_static("Crypto", static_methods)
 }
 
+/// API documentation for the `Crypto` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Crypto", "Cryptographic operations")
+        .method(
+            "hash",
+            "hash(algorithm, data)",
+            "Hash data with algorithm (sha256, md5, etc.)",
+        )
+        .method(
+            "hmac",
+            "hmac(algorithm, key, data)",
+            "HMAC signature as hex; key and data may be strings or bytes",
+        )
+        .method(
+            "hmacBytes",
+            "hmacBytes(algorithm, key, data)",
+            "HMAC signature as bytes",
+        )
+        .method(
+            "pbkdf2",
+            "pbkdf2(password, salt, iterations, length, algorithm?)",
+            "Derive length key bytes with PBKDF2-HMAC (sha256 by default, or sha512)",
+        )
+        .method(
+            "hashPassword",
+            "hashPassword(password, options?)",
+            "Argon2id PHC string with a random salt; options {memoryKib, iterations, parallelism}",
+        )
+        .method(
+            "verifyPassword",
+            "verifyPassword(password, hash)",
+            "Check a password against a hashPassword() string",
+        )
+        .method(
+            "encrypt",
+            "encrypt(key, plaintext, aad?)",
+            "AES-GCM with a 16 or 32 byte key; returns nonce followed by ciphertext and tag",
+        )
+        .method(
+            "decrypt",
+            "decrypt(key, data, aad?)",
+            "Reverse encrypt(), failing if the data or aad was tampered with",
+        )
+        .method(
+            "generateKeyPair",
+            "generateKeyPair(algorithm, bits?)",
+            "{publicKey, privateKey} bytes for ed25519, or DER-encoded rsa (2048 bits by default)",
+        )
+        .method(
+            "sign",
+            "sign(algorithm, privateKey, data)",
+            "Signature bytes; rsa uses PKCS#1 v1.5 with SHA-256",
+        )
+        .method(
+            "verify",
+            "verify(algorithm, publicKey, data, signature)",
+            "Check a signature made by sign()",
+        )
+        .method("uuid", "uuid()", "Generate UUID v4")
+        .method(
+            "nanoid",
+            "nanoid(size?, alphabet?)",
+            "Generate a short URL-safe random id, 21 characters by default",
+        )
+        .method(
+            "randomBytes",
+            "randomBytes(length)",
+            "Random bytes from the operating system's secure generator",
+        )
+        .method(
+            "timingSafeEqual",
+            "timingSafeEqual(a, b)",
+            "Compare strings or bytes in time independent of where they differ",
+        )
+        .method(
+            "randomInt",
+            "randomInt(min, max)",
+            "Generate random integer",
+        )
+        .method("base64Encode", "base64Encode(data)", "Encode to base64")
+        .method("base64Decode", "base64Decode(data)", "Decode from base64")
+        .method(
+            "x509",
+            "x509(certificate)",
+            "Inspect a PEM string or DER byte array certificate",
+        )
+}
+
 fn crypto_hash(args: &[Value]) -> Result<Value, String> {
     check_arity(2, args.len())?;
     let algorithm = get_string_arg(&args[0], "algorithm")?.to_lowercase();
-    let data = get_string_arg(&args[1], "data")?;
+    let data = get_bytes_arg(&args[1], "data")?;
 
     let hash_hex = match algorithm.as_str() {
         "sha256" => {
             let mut hasher = Sha256::new();
-            hasher.update(data.as_bytes());
+            hasher.update(&data);
             hex::encode(hasher.finalize())
         }
         "sha512" => {
             let mut hasher = Sha512::new();
-            hasher.update(data.as_bytes());
+            hasher.update(&data);
             hex::encode(hasher.finalize())
         }
         "md5" => {
-            let digest = md5::compute(data.as_bytes());
+            let digest = md5::compute(&data);
             hex::encode(digest.as_ref())
         }
         "sha1" => {
             use sha1::{Digest as Sha1Digest, Sha1};
             let mut hasher = Sha1::new();
-            hasher.update(data.as_bytes());
+            hasher.update(&data);
             hex::encode(hasher.finalize())
         }
         _ => {
@@ -62,33 +179,37 @@ fn crypto_hash(args: &[Value]) -> Result<Value, String> {
 }
 
 fn crypto_hmac(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::String(Rc::from(hex::encode(hmac_args(args)?))))
+}
+
+fn crypto_hmac_bytes(args: &[Value]) -> Result<Value, String> {
+    Ok(bytes_to_value(&hmac_args(args)?))
+}
+
+fn hmac_args(args: &[Value]) -> Result<Vec<u8>, String> {
     check_arity(3, args.len())?;
     let algorithm = get_string_arg(&args[0], "algorithm")?.to_lowercase();
-    let key = get_string_arg(&args[1], "key")?;
-    let data = get_string_arg(&args[2], "data")?;
+    let key = get_bytes_arg(&args[1], "key")?;
+    let data = get_bytes_arg(&args[2], "data")?;
 
-    let hmac_hex = match algorithm.as_str() {
+    match algorithm.as_str() {
         "sha256" => {
-            let mut mac = HmacSha256::new_from_slice(key.as_bytes())
-                .map_err(|e| format!("HMAC error: {}", e))?;
-            mac.update(data.as_bytes());
-            hex::encode(mac.finalize().into_bytes())
+            let mut mac =
+                HmacSha256::new_from_slice(&key).map_err(|e| format!("HMAC error: {}", e))?;
+            mac.update(&data);
+            Ok(mac.finalize().into_bytes().to_vec())
         }
         "sha512" => {
-            let mut mac = HmacSha512::new_from_slice(key.as_bytes())
-                .map_err(|e| format!("HMAC error: {}", e))?;
-            mac.update(data.as_bytes());
-            hex::encode(mac.finalize().into_bytes())
-        }
-        _ => {
-            return Err(format!(
-                "Unsupported HMAC algorithm: {}. Use sha256 or sha512",
-                algorithm
-            ))
+            let mut mac =
+                HmacSha512::new_from_slice(&key).map_err(|e| format!("HMAC error: {}", e))?;
+            mac.update(&data);
+            Ok(mac.finalize().into_bytes().to_vec())
         }
-    };
-
-    Ok(Value::String(Rc::from(hmac_hex)))
+        _ => Err(format!(
+            "Unsupported HMAC algorithm: {}. Use sha256 or sha512",
+            algorithm
+        )),
+    }
 }
 
 fn crypto_uuid(_args: &[Value]) -> Result<Value, String> {
@@ -96,21 +217,57 @@ fn crypto_uuid(_args: &[Value]) -> Result<Value, String> {
     Ok(Value::String(Rc::from(id)))
 }
 
+fn crypto_nanoid(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let size = match args.first() {
+        None | Some(Value::Null) => 21,
+        Some(size) => get_number_arg(size, "size")? as usize,
+    };
+    let alphabet: Vec<char> = match args.get(1) {
+        Some(alphabet) => get_string_arg(alphabet, "alphabet")?.chars().collect(),
+        None => "_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ"
+            .chars()
+            .collect(),
+    };
+    if alphabet.is_empty() || size > 1024 {
+        return Err("nanoid needs a non-empty alphabet and a size up to 1024".to_string());
+    }
+
+    use rand::Rng;
+    let mut rng = rand::rng();
+    let id: String = (0..size)
+        .map(|_| alphabet[rng.random_range(0..alphabet.len())])
+        .collect();
+    Ok(Value::String(Rc::from(id)))
+}
+
 fn crypto_random_bytes(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
-    let length = get_number_arg(&args[0], "length")? as usize;
+    let length = get_number_arg(&args[0], "length")?;
 
-    if length > 1024 * 1024 {
+    if length < 0.0 || length.fract() != 0.0 {
+        return Err("randomBytes length must be a non-negative integer".to_string());
+    }
+    if length > (1024 * 1024) as f64 {
         return Err("randomBytes length cannot exceed 1MB".to_string());
     }
 
-    use rand::Rng;
-    let mut rng = rand::rng();
-    let bytes: Vec<Value> = (0..length)
-        .map(|_| Value::Number(rng.random::<u8>() as f64))
-        .collect();
+    let mut bytes = vec![0u8; length as usize];
+    OsRng
+        .try_fill_bytes(&mut bytes)
+        .map_err(|e| format!("Secure random generator failed: {}", e))?;
+    Ok(bytes_to_value(&bytes))
+}
 
-    Ok(Value::Array(Rc::new(RefCell::new(bytes))))
+/// Only the lengths may leak through timing; equal-length inputs are always
+/// compared in full.
+fn crypto_timing_safe_equal(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let a = get_bytes_arg(&args[0], "a")?;
+    let b = get_bytes_arg(&args[1], "b")?;
+    Ok(Value::Boolean(
+        a.len() == b.len() && bool::from(a.ct_eq(&b)),
+    ))
 }
 
 fn crypto_random_int(args: &[Value]) -> Result<Value, String> {
@@ -148,3 +305,509 @@ fn crypto_base64_decode(args: &[Value]) -> Result<Value, String> {
 
     Ok(Value::String(Rc::from(decoded)))
 }
+
+/// Returns the subject, issuer, validity, names and fingerprint of the first
+/// certificate in a PEM string or a DER byte array.
+fn crypto_x509(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let der = match &args[0] {
+        Value::String(pem) => {
+            let (_, pem) = x509_parser::pem::parse_x509_pem(pem.as_bytes())
+                .map_err(|e| format!("Invalid PEM certificate: {}", e))?;
+            pem.contents
+        }
+        Value::Array(bytes) => bytes
+            .borrow()
+            .iter()
+            .map(|b| match b {
+                Value::Number(n) if (0.0..=255.0).contains(n) => Ok(*n as u8),
+                _ => Err("Certificate bytes must be numbers from 0 to 255".to_string()),
+            })
+            .collect::<Result<Vec<u8>, String>>()?,
+        other => {
+            return Err(format!(
+                "Expected a PEM string or byte array but got {}",
+                other.type_name()
+            ))
+        }
+    };
+
+    let (_, cert) = x509_parser::parse_x509_certificate(&der)
+        .map_err(|e| format!("Invalid certificate: {}", e))?;
+    Ok(certificate_info(&cert, &der))
+}
+
+fn certificate_info(cert: &X509Certificate, der: &[u8]) -> Value {
+    let string = |s: String| Value::String(Rc::from(s));
+    let mut info: FxHashMap<String, Value> = FxHashMap::default();
+
+    info.insert(
+        "version".to_string(),
+        Value::Number(cert.version().0 as f64 + 1.0),
+    );
+    info.insert("serial".to_string(), string(cert.raw_serial_as_string()));
+    info.insert("subject".to_string(), string(cert.subject().to_string()));
+    info.insert("issuer".to_string(), string(cert.issuer().to_string()));
+    info.insert(
+        "selfSigned".to_string(),
+        Value::Boolean(cert.subject() == cert.issuer()),
+    );
+
+    let validity = cert.validity();
+    info.insert(
+        "notBefore".to_string(),
+        make_date(validity.not_before.timestamp() as f64, DateKind::Date),
+    );
+    info.insert(
+        "notAfter".to_string(),
+        make_date(validity.not_after.timestamp() as f64, DateKind::Date),
+    );
+    info.insert("valid".to_string(), Value::Boolean(validity.is_valid()));
+    info.insert("isCA".to_string(), Value::Boolean(cert.is_ca()));
+
+    let names: Vec<Value> = cert
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|san| {
+            san.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(s) | GeneralName::RFC822Name(s) | GeneralName::URI(s) => {
+                        Some(s.to_string())
+                    }
+                    GeneralName::IPAddress(ip) => ip_address(ip),
+                    _ => None,
+                })
+                .map(string)
+                .collect()
+        })
+        .unwrap_or_default();
+    info.insert(
+        "subjectAltNames".to_string(),
+        Value::Array(Rc::new(RefCell::new(names.into()))),
+    );
+
+    info.insert(
+        "signatureAlgorithm".to_string(),
+        string(oid_name(&cert.signature_algorithm.algorithm)),
+    );
+    info.insert(
+        "publicKeyAlgorithm".to_string(),
+        string(oid_name(&cert.public_key().algorithm.algorithm)),
+    );
+    info.insert(
+        "fingerprint".to_string(),
+        string(hex::encode(Sha256::digest(der))),
+    );
+
+    Value::Dictionary(Rc::new(RefCell::new(info.into())))
+}
+
+fn oid_name(oid: &Oid) -> String {
+    oid2sn(oid, oid_registry())
+        .map(str::to_string)
+        .unwrap_or_else(|_| oid.to_id_string())
+}
+
+fn ip_address(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string()),
+        16 => Some(std::net::Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string()),
+        _ => None,
+    }
+}
+
+fn crypto_pbkdf2(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(4, 5, args.len())?;
+    let password = get_bytes_arg(&args[0], "password")?;
+    let salt = get_bytes_arg(&args[1], "salt")?;
+    let iterations = get_number_arg(&args[2], "iterations")?;
+    let length = get_number_arg(&args[3], "length")?;
+    if iterations < 1.0 || iterations > u32::MAX as f64 {
+        return Err("Argument 'iterations' must be a positive integer".to_string());
+    }
+    if !(1.0..=1024.0).contains(&length) {
+        return Err("Argument 'length' must be from 1 to 1024".to_string());
+    }
+    let algorithm = match args.get(4) {
+        None | Some(Value::Null) => "sha256".to_string(),
+        Some(algorithm) => get_string_arg(algorithm, "algorithm")?.to_lowercase(),
+    };
+
+    let mut key = vec![0u8; length as usize];
+    match algorithm.as_str() {
+        "sha256" => pbkdf2::pbkdf2_hmac::<Sha256>(&password, &salt, iterations as u32, &mut key),
+        "sha512" => pbkdf2::pbkdf2_hmac::<Sha512>(&password, &salt, iterations as u32, &mut key),
+        _ => {
+            return Err(format!(
+                "Unsupported PBKDF2 algorithm: {}. Use sha256 or sha512",
+                algorithm
+            ))
+        }
+    }
+    Ok(bytes_to_value(&key))
+}
+
+fn crypto_hash_password(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let password = get_bytes_arg(&args[0], "password")?;
+    let params = match args.get(1) {
+        None | Some(Value::Null) => Params::default(),
+        Some(Value::Dictionary(options)) => {
+            let options = options.borrow();
+            let option = |name: &str, default: u32| -> Result<u32, String> {
+                match options.get(name) {
+                    None | Some(Value::Null) => Ok(default),
+                    Some(value) => Ok(get_number_arg(value, name)? as u32),
+                }
+            };
+            Params::new(
+                option("memoryKib", Params::DEFAULT_M_COST)?,
+                option("iterations", Params::DEFAULT_T_COST)?,
+                option("parallelism", Params::DEFAULT_P_COST)?,
+                None,
+            )
+            .map_err(|e| format!("Invalid password hashing options: {}", e))?
+        }
+        Some(other) => {
+            return Err(format!(
+                "Argument 'options' must be a dictionary, got {}",
+                other.type_name()
+            ))
+        }
+    };
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password(&password, &salt)
+        .map_err(|e| format!("Password hashing failed: {}", e))?;
+    Ok(Value::String(Rc::from(hash.to_string())))
+}
+
+fn crypto_verify_password(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let password = get_bytes_arg(&args[0], "password")?;
+    let hash = get_string_arg(&args[1], "hash")?;
+    let hash = PasswordHash::new(&hash).map_err(|e| format!("Invalid password hash: {}", e))?;
+    // Parameters come from the hash string itself
+    Ok(Value::Boolean(
+        Argon2::default().verify_password(&password, &hash).is_ok(),
+    ))
+}
+
+const NONCE_LEN: usize = 12;
+
+/// Runs AES-GCM with the key size picking AES-128 or AES-256.
+fn aes_gcm(key: &[u8], nonce: &[u8], payload: Payload, encrypt: bool) -> Result<Vec<u8>, String> {
+    use aes_gcm::KeyInit;
+
+    let nonce = Nonce::from_slice(nonce);
+    let result = match key.len() {
+        16 => {
+            let cipher = Aes128Gcm::new_from_slice(key).map_err(|e| e.to_string())?;
+            if encrypt {
+                cipher.encrypt(nonce, payload)
+            } else {
+                cipher.decrypt(nonce, payload)
+            }
+        }
+        32 => {
+            let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| e.to_string())?;
+            if encrypt {
+                cipher.encrypt(nonce, payload)
+            } else {
+                cipher.decrypt(nonce, payload)
+            }
+        }
+        n => return Err(format!("AES-GCM keys must be 16 or 32 bytes, got {}", n)),
+    };
+    result.map_err(|_| {
+        if encrypt {
+            "Encryption failed".to_string()
+        } else {
+            "Decryption failed: wrong key or tampered data".to_string()
+        }
+    })
+}
+
+fn crypto_encrypt(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let key = get_bytes_arg(&args[0], "key")?;
+    let plaintext = get_bytes_arg(&args[1], "plaintext")?;
+    let aad = match args.get(2) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(aad) => get_bytes_arg(aad, "aad")?,
+    };
+
+    let mut output = vec![0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut output);
+    let ciphertext = aes_gcm(
+        &key,
+        &output,
+        Payload {
+            msg: &plaintext,
+            aad: &aad,
+        },
+        true,
+    )?;
+    output.extend(ciphertext);
+    Ok(bytes_to_value(&output))
+}
+
+fn crypto_decrypt(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let key = get_bytes_arg(&args[0], "key")?;
+    let data = get_bytes_arg(&args[1], "data")?;
+    let aad = match args.get(2) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(aad) => get_bytes_arg(aad, "aad")?,
+    };
+    if data.len() < NONCE_LEN {
+        return Err("Decryption failed: data is too short".to_string());
+    }
+
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = aes_gcm(
+        &key,
+        nonce,
+        Payload {
+            msg: ciphertext,
+            aad: &aad,
+        },
+        false,
+    )?;
+    Ok(bytes_to_value(&plaintext))
+}
+
+fn crypto_generate_key_pair(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let algorithm = get_string_arg(&args[0], "algorithm")?.to_lowercase();
+
+    let (public_key, private_key) = match algorithm.as_str() {
+        "ed25519" => {
+            let mut seed = [0u8; 32];
+            OsRng.fill_bytes(&mut seed);
+            let signing = ed25519_dalek::SigningKey::from_bytes(&seed);
+            (signing.verifying_key().to_bytes().to_vec(), seed.to_vec())
+        }
+        "rsa" => {
+            let bits = match args.get(1) {
+                None | Some(Value::Null) => 2048,
+                Some(bits) => get_number_arg(bits, "bits")? as usize,
+            };
+            if !(1024..=8192).contains(&bits) {
+                return Err("RSA keys must be from 1024 to 8192 bits".to_string());
+            }
+            let private = RsaPrivateKey::new(&mut OsRng, bits)
+                .map_err(|e| format!("RSA key generation failed: {}", e))?;
+            let public_der = private
+                .to_public_key()
+                .to_public_key_der()
+                .map_err(|e| e.to_string())?;
+            let private_der = private.to_pkcs8_der().map_err(|e| e.to_string())?;
+            (
+                public_der.as_bytes().to_vec(),
+                private_der.as_bytes().to_vec(),
+            )
+        }
+        _ => return Err(unsupported_signature(&algorithm)),
+    };
+
+    let mut pair = FxHashMap::default();
+    pair.insert("publicKey".to_string(), bytes_to_value(&public_key));
+    pair.insert("privateKey".to_string(), bytes_to_value(&private_key));
+    pair.insert("algorithm".to_string(), Value::String(Rc::from(algorithm)));
+    Ok(Value::Dictionary(Rc::new(RefCell::new(pair.into()))))
+}
+
+fn unsupported_signature(algorithm: &str) -> String {
+    format!(
+        "Unsupported signature algorithm: {}. Use ed25519 or rsa",
+        algorithm
+    )
+}
+
+fn crypto_sign(args: &[Value]) -> Result<Value, String> {
+    check_arity(3, args.len())?;
+    let algorithm = get_string_arg(&args[0], "algorithm")?.to_lowercase();
+    let key = get_bytes_arg(&args[1], "privateKey")?;
+    let data = get_bytes_arg(&args[2], "data")?;
+
+    let signature = match algorithm.as_str() {
+        "ed25519" => {
+            let seed: [u8; 32] = key
+                .as_slice()
+                .try_into()
+                .map_err(|_| "Ed25519 private keys must be 32 bytes".to_string())?;
+            ed25519_dalek::SigningKey::from_bytes(&seed)
+                .sign(&data)
+                .to_bytes()
+                .to_vec()
+        }
+        "rsa" => {
+            let private = RsaPrivateKey::from_pkcs8_der(&key)
+                .map_err(|e| format!("Invalid RSA private key: {}", e))?;
+            rsa::pkcs1v15::SigningKey::<Sha256>::new(private)
+                .sign(&data)
+                .to_vec()
+        }
+        _ => return Err(unsupported_signature(&algorithm)),
+    };
+    Ok(bytes_to_value(&signature))
+}
+
+fn crypto_verify(args: &[Value]) -> Result<Value, String> {
+    check_arity(4, args.len())?;
+    let algorithm = get_string_arg(&args[0], "algorithm")?.to_lowercase();
+    let key = get_bytes_arg(&args[1], "publicKey")?;
+    let data = get_bytes_arg(&args[2], "data")?;
+    let signature = get_bytes_arg(&args[3], "signature")?;
+
+    let valid = match algorithm.as_str() {
+        "ed25519" => {
+            let key: [u8; 32] = key
+                .as_slice()
+                .try_into()
+                .map_err(|_| "Ed25519 public keys must be 32 bytes".to_string())?;
+            let key = ed25519_dalek::VerifyingKey::from_bytes(&key)
+                .map_err(|e| format!("Invalid Ed25519 public key: {}", e))?;
+            ed25519_dalek::Signature::from_slice(&signature)
+                .is_ok_and(|signature| key.verify(&data, &signature).is_ok())
+        }
+        "rsa" => {
+            let public = RsaPublicKey::from_public_key_der(&key)
+                .map_err(|e| format!("Invalid RSA public key: {}", e))?;
+            rsa::pkcs1v15::Signature::try_from(signature.as_slice()).is_ok_and(|signature| {
+                rsa::pkcs1v15::VerifyingKey::<Sha256>::new(public)
+                    .verify(&data, &signature)
+                    .is_ok()
+            })
+        }
+        _ => return Err(unsupported_signature(&algorithm)),
+    };
+    Ok(Value::Boolean(valid))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::vm::value::Value;
+
+    #[test]
+    fn test_crypto_x509_inspects_pem() {
+        let mut engine = Engine::new();
+        engine.set_global(
+            "pem",
+            Value::String(std::rc::Rc::from(
+                "-----BEGIN CERTIFICATE-----\n\
+MIIBwzCCAWigAwIBAgIULJZOz0NH2FlmDy+/VLUQusNL+cowCgYIKoZIzj0EAwIw\n\
+IzESMBAGA1UEAwwJc2FsZC50ZXN0MQ0wCwYDVQQKDARTYWxkMCAXDTI2MTAxNjE5\n\
+NDUyMFoYDzIxMjYwOTIyMTk0NTIwWjAjMRIwEAYDVQQDDAlzYWxkLnRlc3QxDTAL\n\
+BgNVBAoMBFNhbGQwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAQIyOnOOqrUvmcZ\n\
+lEDrG/E5R0N9t9jn+bG3H2ONRYez08pPf0js27FkIUGRJ1+Z0dBRc+clu2Kqa+BW\n\
+YTTmtw2ho3gwdjAdBgNVHQ4EFgQUTu6ssNa6CAfWjL1TI/04D5ZQa4QwHwYDVR0j\n\
+BBgwFoAUTu6ssNa6CAfWjL1TI/04D5ZQa4QwIwYDVR0RBBwwGoIJc2FsZC50ZXN0\n\
+gg13d3cuc2FsZC50ZXN0MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSQAw\n\
+RgIhAJHSOMNHBfXSqWGJevr1NEIcYh9ypklhHgo0CYinQw4OAiEAo67x/URXZ4+h\n\
+fiFL2RwWXicDPAG2FKUU7RSzzh4+lWg=\n\
+-----END CERTIFICATE-----\n",
+            )),
+        );
+        engine.eval("let c = Crypto.x509(pem)").unwrap();
+        let result: (String, String, f64, bool, Vec<String>, String) = engine
+            .eval_as(
+                "[c[\"subject\"], c[\"serial\"], c[\"notAfter\"].year(), c[\"isCA\"], \
+                 c[\"subjectAltNames\"], c[\"fingerprint\"]]",
+            )
+            .unwrap();
+        assert_eq!(result.0, "CN=sald.test, O=Sald");
+        assert_eq!(
+            result.1,
+            "2c:96:4e:cf:43:47:d8:59:66:0f:2f:bf:54:b5:10:ba:c3:4b:f9:ca"
+        );
+        assert_eq!(result.2, 2126.0);
+        assert!(result.3);
+        assert_eq!(result.4, ["sald.test", "www.sald.test"]);
+        assert!(result.5.starts_with("a9499b4a5d8b8c8a"));
+    }
+
+    #[test]
+    fn test_crypto_macs_passwords_ciphers_and_signatures() {
+        let mut engine = Engine::new();
+        let derived: String = engine
+            .eval_as("Encoding.hexEncode(Crypto.pbkdf2(\"password\", \"salt\", 1, 32))")
+            .unwrap();
+        assert_eq!(
+            derived,
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        let mac: bool = engine
+            .eval_as("Encoding.hexEncode(Crypto.hmacBytes(\"sha256\", [107, 101, 121], \"data\")) == Crypto.hmac(\"sha256\", \"key\", \"data\")")
+            .unwrap();
+        assert!(mac);
+
+        engine
+            .eval(
+                "let h = Crypto.hashPassword(\"hunter2\", {\"memoryKib\": 64, \"iterations\": 1})",
+            )
+            .unwrap();
+        let passwords: Vec<bool> = engine
+            .eval_as(
+                "[Crypto.verifyPassword(\"hunter2\", h), Crypto.verifyPassword(\"hunter3\", h)]",
+            )
+            .unwrap();
+        assert_eq!(passwords, [true, false]);
+
+        engine
+            .eval("let key = Crypto.randomBytes(32)\nlet sealed = Crypto.encrypt(key, \"secret\", \"id\")")
+            .unwrap();
+        let opened: Vec<u8> = engine
+            .eval_as("Crypto.decrypt(key, sealed, \"id\")")
+            .unwrap();
+        assert_eq!(opened, b"secret");
+        assert!(engine
+            .eval("Crypto.decrypt(key, sealed, \"other\")")
+            .is_err());
+
+        for algorithm in ["ed25519", "rsa"] {
+            engine
+                .eval(&format!(
+                    "let pair = Crypto.generateKeyPair(\"{0}\", 1024)\n\
+                     let sig = Crypto.sign(\"{0}\", pair[\"privateKey\"], \"msg\")",
+                    algorithm
+                ))
+                .unwrap();
+            let verified: Vec<bool> = engine
+                .eval_as(&format!(
+                    "[Crypto.verify(\"{0}\", pair[\"publicKey\"], \"msg\", sig), \
+                     Crypto.verify(\"{0}\", pair[\"publicKey\"], \"msg!\", sig)]",
+                    algorithm
+                ))
+                .unwrap();
+            assert_eq!(verified, [true, false], "{}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_crypto_random_bytes_and_timing_safe_equal() {
+        let mut engine = Engine::new();
+        let bytes: Vec<u8> = engine.eval_as("Crypto.randomBytes(16)").unwrap();
+        assert_eq!(bytes.len(), 16);
+        assert!(engine.eval("Crypto.randomBytes(-1)").is_err());
+        let equal: bool = engine
+            .eval_as(
+                "let mac = Crypto.hmacBytes(\"sha256\", \"k\", \"m\")\n\
+                 let same = Crypto.hmacBytes(\"sha256\", \"k\", \"m\")\n\
+                 let other = Crypto.hmacBytes(\"sha256\", \"k\", \"n\")\n\
+                 Crypto.timingSafeEqual(mac, same) == true && Crypto.timingSafeEqual(mac, other) == false",
+            )
+            .unwrap();
+        assert!(equal);
+        let strings: Vec<bool> = engine
+            .eval_as("[Crypto.timingSafeEqual(\"token\", \"token\"), Crypto.timingSafeEqual(\"token\", \"tok\")]")
+            .unwrap();
+        assert_eq!(strings, [true, false]);
+    }
+}
@@ -1,11 +1,26 @@
-use super::{check_arity, get_string_arg};
-use crate::vm::value::{Class, NativeStaticFn, Value};
+use super::docs::ClassDoc;
+use super::{check_arity, get_number_arg, get_string_arg};
+use crate::vm::value::{Class, Instance, NativeInstanceFn, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::rc::Rc;
-use std::time::{SystemTime, UNIX_EPOCH};
+
+thread_local! {
+    static DATE_CLASS: Rc<Class> = Rc::new(create_date_class());
+}
+
+/// Whether a Date value is a calendar date (with optional time of day) or a
+/// bare time of day. Only values of the same kind can be compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateKind {
+    Date,
+    Time,
+}
 
 pub fn create_date_class() -> Class {
     let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
 
     static_methods.insert("now".to_string(), date_now);
     static_methods.insert("timestamp".to_string(), date_timestamp);
@@ -16,51 +31,210 @@ pub fn create_date_class() -> Class {
     static_methods.insert("minute".to_string(), date_minute);
     static_methods.insert("second".to_string(), date_second);
     static_methods.insert("format".to_string(), date_format);
+    static_methods.insert("parse".to_string(), date_parse);
+    static_methods.insert("parseTime".to_string(), date_parse_time);
+    static_methods.insert("fromTimestamp".to_string(), date_from_timestamp);
 
-    Class::new_with_static("Date", static_methods)
+    instance_methods.insert("year".to_string(), date_get_year);
+    instance_methods.insert("month".to_string(), date_get_month);
+    instance_methods.insert("day".to_string(), date_get_day);
+    instance_methods.insert("hour".to_string(), date_get_hour);
+    instance_methods.insert("minute".to_string(), date_get_minute);
+    instance_methods.insert("second".to_string(), date_get_second);
+    instance_methods.insert("weekday".to_string(), date_get_weekday);
+    instance_methods.insert("timestamp".to_string(), date_get_timestamp);
+    instance_methods.insert("format".to_string(), date_format_value);
+    instance_methods.insert("toString".to_string(), date_to_string);
+
+    let mut class = Class::new_with_instance("Date", instance_methods, None);
+    class.native_static_methods = static_methods;
+    class
 }
 
-fn get_current_datetime() -> (i32, u32, u32, u32, u32, u32, u64) {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
+/// API documentation for the `Date` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Date", "Date and time functions")
+        .method("now", "now()", "Current datetime string")
+        .method("timestamp", "timestamp()", "Unix timestamp in seconds")
+        .method("year", "year()", "Current year")
+        .method("month", "month()", "Current month (1-12)")
+        .method("day", "day()", "Current day (1-31)")
+        .method("hour", "hour()", "Current hour (0-23)")
+        .method("minute", "minute()", "Current minute (0-59)")
+        .method("second", "second()", "Current second (0-59)")
+        .method("format", "format(pattern)", "Format datetime")
+}
 
-    let secs = now.as_secs();
-    let days_since_epoch = secs / 86400;
-    let time_of_day = secs % 86400;
+/// Native state of a Date instance
+struct DateState {
+    seconds: f64,
+    kind: DateKind,
+}
+
+/// Creates a Date value. `seconds` is a Unix timestamp for `DateKind::Date`
+/// and seconds since midnight for `DateKind::Time`.
+pub fn make_date(seconds: f64, kind: DateKind) -> Value {
+    let class = DATE_CLASS.with(|c| c.clone());
+    let instance = Instance::with_native(class, DateState { seconds, kind });
+    Value::Instance(Rc::new(RefCell::new(instance)))
+}
 
-    let hour = (time_of_day / 3600) as u32;
-    let minute = ((time_of_day % 3600) / 60) as u32;
-    let second = (time_of_day % 60) as u32;
+fn date_fields(inst: &Instance) -> Option<(f64, DateKind)> {
+    let state = inst.native::<DateState>()?;
+    let state = state.borrow();
+    Some((state.seconds, state.kind))
+}
 
-    let mut days = days_since_epoch as i64;
-    let mut year = 1970i32;
+/// Returns the seconds and kind of a Date value, or `None` for other values.
+pub fn date_parts(value: &Value) -> Option<(f64, DateKind)> {
+    match value {
+        Value::Instance(inst) => date_fields(&inst.borrow()),
+        _ => None,
+    }
+}
 
-    loop {
-        let days_in_year = if is_leap_year(year) { 366 } else { 365 };
-        if days < days_in_year {
-            break;
+/// Orders two Date values. Returns `None` if either value is not a Date and
+/// an error if a date is compared with a time of day.
+pub fn compare_dates(a: &Value, b: &Value) -> Option<Result<Ordering, String>> {
+    let (a_secs, a_kind) = date_parts(a)?;
+    let (b_secs, b_kind) = date_parts(b)?;
+    if a_kind != b_kind {
+        return Some(Err("Cannot compare a date with a time of day".to_string()));
+    }
+    Some(Ok(a_secs.partial_cmp(&b_secs).unwrap_or(Ordering::Equal)))
+}
+
+/// Formats a Date instance as `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` or `HH:MM:SS`.
+pub fn format_date_instance(inst: &Instance) -> Option<String> {
+    let (seconds, kind) = date_fields(inst)?;
+    let (year, month, day, hour, minute, second) = split_timestamp(seconds.floor() as i64);
+    Some(match kind {
+        DateKind::Time => format!("{:02}:{:02}:{:02}", hour, minute, second),
+        DateKind::Date if hour == 0 && minute == 0 && second == 0 => {
+            format!("{:04}-{:02}-{:02}", year, month, day)
         }
-        days -= days_in_year;
-        year += 1;
+        DateKind::Date => format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            year, month, day, hour, minute, second
+        ),
+    })
+}
+
+/// Parses `YYYY-MM-DD`, optionally followed by `HH:MM[:SS]` separated by a
+/// space or `T`, into a Unix timestamp.
+pub fn parse_date_literal(text: &str) -> Result<f64, String> {
+    let (date, time) = match text.find(['T', ' ']) {
+        Some(pos) => (&text[..pos], Some(&text[pos + 1..])),
+        None => (text, None),
+    };
+
+    let parts: Vec<&str> = date.split('-').collect();
+    if parts.len() != 3 || parts[0].len() != 4 || parts[1].len() != 2 || parts[2].len() != 2 {
+        return Err(format!("Invalid date '{}': expected YYYY-MM-DD", text));
+    }
+    let year = parse_field(parts[0], text)? as i64;
+    let month = parse_field(parts[1], text)?;
+    let day = parse_field(parts[2], text)?;
+    if !(1..=12).contains(&month) {
+        return Err(format!("Invalid date '{}': month must be 1-12", text));
+    }
+    if day < 1 || day > days_in_month(year as i32, month) {
+        return Err(format!("Invalid date '{}': day out of range", text));
     }
 
-    let days_in_months: [i64; 12] = if is_leap_year(year) {
-        [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-    } else {
-        [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+    let time_of_day = match time {
+        Some(time) => parse_time_literal(time).map_err(|_| {
+            format!(
+                "Invalid date '{}': expected time as HH:MM or HH:MM:SS",
+                text
+            )
+        })?,
+        None => 0.0,
     };
 
-    let mut month = 1u32;
-    for days_in_month in days_in_months.iter() {
-        if days < *days_in_month {
-            break;
-        }
-        days -= days_in_month;
-        month += 1;
+    Ok((days_from_civil(year, month, day) * 86400) as f64 + time_of_day)
+}
+
+/// Parses `HH:MM` or `HH:MM:SS` into seconds since midnight.
+pub fn parse_time_literal(text: &str) -> Result<f64, String> {
+    let parts: Vec<&str> = text.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || parts.iter().any(|p| p.len() != 2) {
+        return Err(format!(
+            "Invalid time '{}': expected HH:MM or HH:MM:SS",
+            text
+        ));
     }
+    let hour = parse_field(parts[0], text)?;
+    let minute = parse_field(parts[1], text)?;
+    let second = match parts.get(2) {
+        Some(p) => parse_field(p, text)?,
+        None => 0,
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(format!("Invalid time '{}': value out of range", text));
+    }
+    Ok((hour * 3600 + minute * 60 + second) as f64)
+}
+
+fn parse_field(field: &str, text: &str) -> Result<u32, String> {
+    if !field.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid date or time '{}'", text));
+    }
+    field
+        .parse()
+        .map_err(|_| format!("Invalid date or time '{}'", text))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as i32, month, day)
+}
+
+fn split_timestamp(secs: i64) -> (i32, u32, u32, u32, u32, u32) {
+    let days = secs.div_euclid(86400);
+    let time_of_day = secs.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    (
+        year,
+        month,
+        day,
+        (time_of_day / 3600) as u32,
+        ((time_of_day % 3600) / 60) as u32,
+        (time_of_day % 60) as u32,
+    )
+}
 
-    let day = (days + 1) as u32;
+fn get_current_datetime() -> (i32, u32, u32, u32, u32, u32, u64) {
+    let secs = super::clock::unix_time().as_secs();
+    let (year, month, day, hour, minute, second) = split_timestamp(secs as i64);
     (year, month, day, hour, minute, second, secs)
 }
 
@@ -123,14 +297,126 @@ fn date_format(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     let format_str = get_string_arg(&args[0], "format")?;
     let (year, month, day, hour, minute, second, _) = get_current_datetime();
+    let result = apply_format(&format_str, (year, month, day, hour, minute, second));
+    Ok(Value::String(Rc::from(result)))
+}
 
-    let result = format_str
+fn apply_format(format_str: &str, parts: (i32, u32, u32, u32, u32, u32)) -> String {
+    let (year, month, day, hour, minute, second) = parts;
+    format_str
         .replace("YYYY", &format!("{:04}", year))
         .replace("MM", &format!("{:02}", month))
         .replace("DD", &format!("{:02}", day))
         .replace("HH", &format!("{:02}", hour))
         .replace("mm", &format!("{:02}", minute))
-        .replace("ss", &format!("{:02}", second));
+        .replace("ss", &format!("{:02}", second))
+}
 
-    Ok(Value::String(Rc::from(result)))
+fn date_parse(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    Ok(make_date(parse_date_literal(&text)?, DateKind::Date))
+}
+
+fn date_parse_time(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    Ok(make_date(parse_time_literal(&text)?, DateKind::Time))
+}
+
+fn date_from_timestamp(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let seconds = get_number_arg(&args[0], "timestamp")?;
+    Ok(make_date(seconds, DateKind::Date))
+}
+
+fn receiver_parts(recv: &Value, method: &str) -> Result<(f64, DateKind), String> {
+    date_parts(recv).ok_or_else(|| format!("{}() must be called on a Date", method))
+}
+
+fn calendar_parts(recv: &Value, method: &str) -> Result<(i32, u32, u32, u32, u32, u32), String> {
+    let (seconds, kind) = receiver_parts(recv, method)?;
+    if kind == DateKind::Time && matches!(method, "year" | "month" | "day" | "weekday") {
+        return Err(format!("{}() is not available on a time of day", method));
+    }
+    Ok(split_timestamp(seconds.floor() as i64))
+}
+
+fn date_get_year(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (year, ..) = calendar_parts(recv, "year")?;
+    Ok(Value::Number(year as f64))
+}
+
+fn date_get_month(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (_, month, ..) = calendar_parts(recv, "month")?;
+    Ok(Value::Number(month as f64))
+}
+
+fn date_get_day(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (_, _, day, ..) = calendar_parts(recv, "day")?;
+    Ok(Value::Number(day as f64))
+}
+
+fn date_get_hour(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (_, _, _, hour, ..) = calendar_parts(recv, "hour")?;
+    Ok(Value::Number(hour as f64))
+}
+
+fn date_get_minute(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (_, _, _, _, minute, _) = calendar_parts(recv, "minute")?;
+    Ok(Value::Number(minute as f64))
+}
+
+fn date_get_second(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (.., second) = calendar_parts(recv, "second")?;
+    Ok(Value::Number(second as f64))
+}
+
+/// Day of the week, 0 for Sunday through 6 for Saturday.
+fn date_get_weekday(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    calendar_parts(recv, "weekday")?;
+    let (seconds, _) = receiver_parts(recv, "weekday")?;
+    let days = (seconds.floor() as i64).div_euclid(86400);
+    Ok(Value::Number((days + 4).rem_euclid(7) as f64))
+}
+
+fn date_get_timestamp(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (seconds, _) = receiver_parts(recv, "timestamp")?;
+    Ok(Value::Number(seconds))
+}
+
+fn date_format_value(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let format_str = get_string_arg(&args[0], "format")?;
+    let parts = calendar_parts(recv, "format")?;
+    Ok(Value::String(Rc::from(apply_format(&format_str, parts))))
+}
+
+fn date_to_string(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::String(Rc::from(recv.to_string())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::eval;
+
+    #[test]
+    fn test_date_keeps_its_value_out_of_fields() {
+        let source = "let d = d\"2024-06-01 12:30\"\n\
+                      let r = [Reflect.fields(d), d, d.hour(), d < d\"2024-06-02\"]\nr";
+        assert_eq!(eval(source), "[[], 2024-06-01 12:30:00, 12, true]");
+        assert_eq!(
+            eval("let t = t\"08:15\"\nlet r = [Reflect.fields(t), t]\nr"),
+            "[[], 08:15:00]"
+        );
+    }
 }
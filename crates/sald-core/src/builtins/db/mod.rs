@@ -0,0 +1,373 @@
+//! Database connections
+//! `Db.connect(url)` opens a connection through the driver registered for the
+//! URL's scheme. Postgres (`postgres://`) and MySQL (`mysql://`) drivers are
+//! built in with the `postgres` and `mysql` cargo features, and can also be
+//! imported as `native:postgres` and `native:mysql`. Hosts add drivers for
+//! other databases with `register_driver`.
+
+#[cfg(feature = "mysql")]
+mod mysql;
+#[cfg(feature = "postgres")]
+mod postgres;
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_string_arg, native_instance, native_state};
+use crate::native_module::NativeModule;
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// A row as column names and values, in column order
+pub type DbRow = Vec<(String, Value)>;
+
+/// Opens connections for some URL schemes.
+pub trait DbDriver: Send + Sync {
+    /// Schemes handled, such as `postgres`
+    fn schemes(&self) -> &[&str];
+
+    fn connect(&self, url: &str) -> Result<Box<dyn DbConnection>, String>;
+}
+
+/// An open connection. Parameters are bound, in order, to the placeholders of
+/// the statement, written the way the database expects them.
+pub trait DbConnection {
+    /// Runs a statement and returns the rows it produces
+    fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<DbRow>, String>;
+
+    /// Runs a statement and returns the number of rows it changed
+    fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64, String>;
+}
+
+static DRIVERS: RwLock<Vec<Arc<dyn DbDriver>>> = RwLock::new(Vec::new());
+
+/// Makes `driver` available to `Db.connect` in every VM. A driver registered
+/// later wins over earlier ones and the built-in drivers for the same scheme.
+pub fn register_driver(driver: Arc<dyn DbDriver>) {
+    DRIVERS.write().push(driver);
+}
+
+/// Registered drivers, latest first, then the built-in ones
+fn drivers() -> Vec<Arc<dyn DbDriver>> {
+    #[allow(unused_mut)]
+    let mut drivers: Vec<Arc<dyn DbDriver>> = DRIVERS.read().iter().rev().cloned().collect();
+    #[cfg(feature = "postgres")]
+    drivers.push(Arc::new(postgres::Postgres));
+    #[cfg(feature = "mysql")]
+    drivers.push(Arc::new(mysql::MySql));
+    drivers
+}
+
+/// The built-in driver served as `native:<name>`, when it was compiled in
+pub(crate) fn native_module(name: &str) -> Option<Arc<dyn NativeModule>> {
+    match name {
+        #[cfg(feature = "postgres")]
+        "postgres" => Some(Arc::new(postgres::Postgres)),
+        #[cfg(feature = "mysql")]
+        "mysql" => Some(Arc::new(mysql::MySql)),
+        _ => None,
+    }
+}
+
+/// State of a connection instance; `None` once closed
+struct Connection {
+    inner: Option<Box<dyn DbConnection>>,
+    in_transaction: bool,
+}
+
+pub fn create_db_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("connect".to_string(), db_connect);
+    static_methods.insert("drivers".to_string(), db_drivers);
+
+    Class::new_with_static("Db", static_methods)
+}
+
+fn create_connection_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("query".to_string(), connection_query);
+    instance_methods.insert("execute".to_string(), connection_execute);
+    instance_methods.insert("close".to_string(), connection_close);
+    callable_methods.insert("transaction".to_string(), connection_transaction);
+
+    let mut class = Class::new_with_instance("DbConnection", instance_methods, None);
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Db` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Db", "Connect to databases through the driver for the URL scheme")
+        .method(
+            "connect",
+            "connect(url)",
+            "Open a DbConnection, such as Db.connect(\"postgres://user@host/db\")",
+        )
+        .method("drivers", "drivers()", "Get the URL schemes a driver is available for")
+        .method(
+            "query",
+            "query(sql, params?)",
+            "Run a statement and get its rows as dicts keyed by column",
+        )
+        .method(
+            "execute",
+            "execute(sql, params?)",
+            "Run a statement and get the number of rows it changed",
+        )
+        .method(
+            "transaction",
+            "transaction(fn)",
+            "Call fn with the connection inside a transaction, committed when fn returns and rolled back when it throws",
+        )
+        .method("close", "close()", "Close the connection")
+}
+
+/// Opens a connection to `url` and wraps it for scripts
+pub(crate) fn connect(url: &str) -> Result<Value, String> {
+    let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+    let driver = drivers()
+        .into_iter()
+        .find(|driver| driver.schemes().contains(&scheme))
+        .ok_or_else(|| match scheme {
+            "postgres" | "postgresql" => {
+                "Postgres support is not built in; build Sald with the 'postgres' feature"
+                    .to_string()
+            }
+            "mysql" => {
+                "MySQL support is not built in; build Sald with the 'mysql' feature".to_string()
+            }
+            _ => format!("No database driver for '{}'", url),
+        })?;
+    let inner = driver.connect(url)?;
+    Ok(native_instance(
+        create_connection_class(),
+        Connection {
+            inner: Some(inner),
+            in_transaction: false,
+        },
+    ))
+}
+
+fn db_connect(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    connect(&get_string_arg(&args[0], "url")?)
+}
+
+fn db_drivers(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let mut schemes: Vec<&str> = Vec::new();
+    let drivers = drivers();
+    for driver in &drivers {
+        for scheme in driver.schemes() {
+            if !schemes.contains(scheme) {
+                schemes.push(scheme);
+            }
+        }
+    }
+    let schemes = schemes
+        .into_iter()
+        .map(|scheme| Value::String(Rc::from(scheme)))
+        .collect();
+    Ok(Value::Array(Rc::new(RefCell::new(schemes))))
+}
+
+/// Runs `f` on the open connection behind `recv`
+fn with_connection<T>(
+    recv: &Value,
+    f: impl FnOnce(&mut dyn DbConnection) -> Result<T, String>,
+) -> Result<T, String> {
+    let state = native_state::<Connection>(recv, "DbConnection")?;
+    let mut state = state.borrow_mut();
+    match state.inner.as_mut() {
+        Some(inner) => f(inner.as_mut()),
+        None => Err("Connection is closed".to_string()),
+    }
+}
+
+/// The statement and its parameters, from `(sql, params?)`
+fn statement(args: &[Value]) -> Result<(String, Vec<Value>), String> {
+    check_arity_range(1, 2, args.len())?;
+    let sql = get_string_arg(&args[0], "sql")?;
+    let params = match args.get(1) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(params)) => params.borrow().to_vec(),
+        Some(other) => {
+            return Err(format!(
+                "Argument 'params' must be an array, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    Ok((sql, params))
+}
+
+fn connection_query(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let (sql, params) = statement(args)?;
+    let rows = with_connection(recv, |conn| conn.query(&sql, &params))?;
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            let row: FxHashMap<String, Value> = row.into_iter().collect();
+            Value::Dictionary(Rc::new(RefCell::new(row.into())))
+        })
+        .collect();
+    Ok(Value::Array(Rc::new(RefCell::new(rows))))
+}
+
+fn connection_execute(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let (sql, params) = statement(args)?;
+    let changed = with_connection(recv, |conn| conn.execute(&sql, &params))?;
+    Ok(Value::Number(changed as f64))
+}
+
+fn connection_close(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    native_state::<Connection>(recv, "DbConnection")?
+        .borrow_mut()
+        .inner = None;
+    Ok(Value::Null)
+}
+
+fn connection_transaction(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let state = native_state::<Connection>(recv, "DbConnection")?;
+    if state.borrow().in_transaction {
+        return Err("A transaction is already open on this connection".to_string());
+    }
+    with_connection(recv, |conn| conn.execute("BEGIN", &[]))?;
+    state.borrow_mut().in_transaction = true;
+    let result = caller.call(&args[0], vec![recv.clone()]);
+    state.borrow_mut().in_transaction = false;
+    match result {
+        Ok(value) => {
+            with_connection(recv, |conn| conn.execute("COMMIT", &[]))?;
+            Ok(value)
+        }
+        Err(e) => {
+            // The script's error matters more than a failed rollback
+            let _ = with_connection(recv, |conn| conn.execute("ROLLBACK", &[]));
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{register_driver, DbConnection, DbDriver, DbRow};
+    use crate::test_util::{eval, eval_err};
+    use crate::vm::value::Value;
+    use parking_lot::Mutex;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    /// Statements run through the `fake` driver, in order
+    static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct FakeDriver;
+
+    impl DbDriver for FakeDriver {
+        fn schemes(&self) -> &[&str] {
+            &["fake"]
+        }
+
+        fn connect(&self, _url: &str) -> Result<Box<dyn DbConnection>, String> {
+            Ok(Box::new(FakeConnection))
+        }
+    }
+
+    struct FakeConnection;
+
+    fn record(kind: &str, sql: &str, params: &[Value]) {
+        let params: Vec<String> = params.iter().map(Value::to_string).collect();
+        LOG.lock().push(
+            format!("{} {} {}", kind, sql, params.join(","))
+                .trim()
+                .to_string(),
+        );
+    }
+
+    impl DbConnection for FakeConnection {
+        fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<DbRow>, String> {
+            record("query", sql, params);
+            Ok(vec![vec![
+                ("id".to_string(), Value::Number(1.0)),
+                ("name".to_string(), Value::String(Rc::from("ada"))),
+            ]])
+        }
+
+        fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64, String> {
+            record("execute", sql, params);
+            Ok(params.len() as u64)
+        }
+    }
+
+    #[test]
+    fn test_connection_runs_statements_and_transactions() {
+        register_driver(Arc::new(FakeDriver));
+        let result = eval(
+            r#"
+            let db = Db.connect("fake://local")
+            let row = db.query("SELECT", [7])[0]
+            let changed = db.transaction(|c| c.execute("INSERT", [1, "a"]))
+            let failed = false
+            try {
+                db.transaction(|c| {
+                    c.execute("UPDATE")
+                    throw "boom"
+                })
+            } catch e {
+                failed = true
+            }
+            db.close()
+            let result = [row["name"], changed, failed, Db.drivers().contains("fake")]
+            result
+            "#,
+        );
+        assert_eq!(result, "[ada, 2, true, true]");
+        assert_eq!(
+            *LOG.lock(),
+            [
+                "query SELECT 7",
+                "execute BEGIN",
+                "execute INSERT 1,a",
+                "execute COMMIT",
+                "execute BEGIN",
+                "execute UPDATE",
+                "execute ROLLBACK",
+            ]
+        );
+
+        let err =
+            eval_err("let db = Db.connect(\"fake://local\")\ndb.close()\ndb.query(\"SELECT\")");
+        assert!(err.contains("Connection is closed"), "{}", err);
+    }
+
+    #[test]
+    fn test_connect_needs_a_driver_for_the_scheme() {
+        let err = eval_err("Db.connect(\"nosuch://host\")");
+        assert!(
+            err.contains("No database driver for 'nosuch://host'"),
+            "{}",
+            err
+        );
+        #[cfg(not(feature = "postgres"))]
+        {
+            let err = eval_err("Db.connect(\"postgres://host/db\")");
+            assert!(
+                err.contains("build Sald with the 'postgres' feature"),
+                "{}",
+                err
+            );
+        }
+    }
+}
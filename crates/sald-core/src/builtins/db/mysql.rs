@@ -0,0 +1,107 @@
+//! MySQL driver on the `mysql` client
+//! Parameters are written `?`. Statements with parameters and all queries use
+//! prepared statements, so column values keep their types; statements without
+//! parameters, such as `BEGIN`, run as plain text.
+
+use super::{connect, DbConnection, DbDriver, DbRow};
+use crate::builtins::{check_arity, get_string_arg};
+use crate::native_module::{NativeExports, NativeModule};
+use crate::vm::value::Value;
+use mysql::prelude::Queryable;
+use mysql::{Conn, Opts, Params, Row};
+use std::rc::Rc;
+
+pub(super) struct MySql;
+
+impl DbDriver for MySql {
+    fn schemes(&self) -> &[&str] {
+        &["mysql"]
+    }
+
+    fn connect(&self, url: &str) -> Result<Box<dyn DbConnection>, String> {
+        let opts = Opts::from_url(url).map_err(|e| format!("Invalid MySQL URL: {}", e))?;
+        let conn = Conn::new(opts).map_err(|e| format!("Cannot connect to MySQL: {}", e))?;
+        Ok(Box::new(MySqlConnection(conn)))
+    }
+}
+
+impl NativeModule for MySql {
+    fn name(&self) -> &str {
+        "mysql"
+    }
+
+    fn register(&self, exports: &mut NativeExports) {
+        exports.function("connect", |args| {
+            check_arity(1, args.len())?;
+            connect(&get_string_arg(&args[0], "url")?)
+        });
+    }
+}
+
+struct MySqlConnection(Conn);
+
+impl DbConnection for MySqlConnection {
+    fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<DbRow>, String> {
+        let rows: Vec<Row> = self
+            .0
+            .exec(sql, to_params(params)?)
+            .map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_values).collect())
+    }
+
+    fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64, String> {
+        let result = if params.is_empty() {
+            self.0.query_drop(sql)
+        } else {
+            self.0.exec_drop(sql, to_params(params)?)
+        };
+        result.map_err(|e| e.to_string())?;
+        Ok(self.0.affected_rows())
+    }
+}
+
+fn to_params(params: &[Value]) -> Result<Params, String> {
+    if params.is_empty() {
+        return Ok(Params::Empty);
+    }
+    params
+        .iter()
+        .map(|value| match value {
+            Value::Null => Ok(mysql::Value::NULL),
+            Value::Boolean(b) => Ok(mysql::Value::Int(*b as i64)),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                Ok(mysql::Value::Int(*n as i64))
+            }
+            Value::Number(n) => Ok(mysql::Value::Double(*n)),
+            Value::String(s) => Ok(mysql::Value::Bytes(s.as_bytes().to_vec())),
+            other => Err(format!("Cannot bind a {} parameter", other.type_name())),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Params::Positional)
+}
+
+fn row_values(row: &Row) -> DbRow {
+    row.columns_ref()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let value = match row.as_ref(i) {
+                None | Some(mysql::Value::NULL) => Value::Null,
+                Some(mysql::Value::Bytes(bytes)) => match std::str::from_utf8(bytes) {
+                    Ok(text) => Value::String(Rc::from(text)),
+                    Err(_) => crate::builtins::bytes_to_value(bytes),
+                },
+                Some(mysql::Value::Int(n)) => Value::Number(*n as f64),
+                Some(mysql::Value::UInt(n)) => Value::Number(*n as f64),
+                Some(mysql::Value::Float(n)) => Value::Number(*n as f64),
+                Some(mysql::Value::Double(n)) => Value::Number(*n),
+                Some(date @ mysql::Value::Date(..)) | Some(date @ mysql::Value::Time(..)) => {
+                    // Quoted SQL literal, such as '2024-01-31 12:00:00'
+                    let text = date.as_sql(true);
+                    Value::String(Rc::from(text.trim_matches('\'')))
+                }
+            };
+            (column.name_str().to_string(), value)
+        })
+        .collect()
+}
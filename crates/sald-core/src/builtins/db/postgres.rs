@@ -0,0 +1,177 @@
+//! Postgres driver on the synchronous `postgres` client
+//! Parameters are written `$1`, `$2`, ... and converted to the type Postgres
+//! expects for each. Columns of types without a Sald counterpart must be cast
+//! to text in the query.
+
+use super::{connect, DbConnection, DbDriver, DbRow};
+use crate::builtins::{bytes_to_value, check_arity, get_string_arg};
+use crate::native_module::{NativeExports, NativeModule};
+use crate::vm::value::Value;
+use postgres::types::{ToSql, Type};
+use postgres::{Client, NoTls, Row};
+use std::rc::Rc;
+
+pub(super) struct Postgres;
+
+impl DbDriver for Postgres {
+    fn schemes(&self) -> &[&str] {
+        &["postgres", "postgresql"]
+    }
+
+    fn connect(&self, url: &str) -> Result<Box<dyn DbConnection>, String> {
+        let client = Client::connect(url, NoTls)
+            .map_err(|e| format!("Cannot connect to Postgres: {}", e))?;
+        Ok(Box::new(PostgresConnection(client)))
+    }
+}
+
+impl NativeModule for Postgres {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    fn register(&self, exports: &mut NativeExports) {
+        exports.function("connect", |args| {
+            check_arity(1, args.len())?;
+            connect(&get_string_arg(&args[0], "url")?)
+        });
+    }
+}
+
+struct PostgresConnection(Client);
+
+type Params = Vec<Box<dyn ToSql + Sync>>;
+
+impl PostgresConnection {
+    /// Prepares `sql` and converts `params` to the types it takes
+    fn prepare(
+        &mut self,
+        sql: &str,
+        params: &[Value],
+    ) -> Result<(postgres::Statement, Params), String> {
+        let statement = self.0.prepare(sql).map_err(|e| e.to_string())?;
+        let types = statement.params();
+        if types.len() != params.len() {
+            return Err(format!(
+                "Statement takes {} parameters, got {}",
+                types.len(),
+                params.len()
+            ));
+        }
+        let params = types
+            .iter()
+            .zip(params)
+            .map(|(ty, value)| to_sql(ty, value))
+            .collect::<Result<_, _>>()?;
+        Ok((statement, params))
+    }
+}
+
+impl DbConnection for PostgresConnection {
+    fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<DbRow>, String> {
+        let (statement, params) = self.prepare(sql, params)?;
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref()).collect();
+        let rows = self
+            .0
+            .query(&statement, &params)
+            .map_err(|e| e.to_string())?;
+        rows.iter().map(row_values).collect()
+    }
+
+    fn execute(&mut self, sql: &str, params: &[Value]) -> Result<u64, String> {
+        let (statement, params) = self.prepare(sql, params)?;
+        let params: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref()).collect();
+        self.0
+            .execute(&statement, &params)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// `value` as the parameter type `ty`; null binds SQL NULL
+fn to_sql(ty: &Type, value: &Value) -> Result<Box<dyn ToSql + Sync>, String> {
+    fn convert<T>(value: &Value, f: impl Fn(&Value) -> Option<T>) -> Result<Option<T>, ()> {
+        match value {
+            Value::Null => Ok(None),
+            value => f(value).map(Some).ok_or(()),
+        }
+    }
+    let integer = |v: &Value| match v {
+        Value::Number(n) if n.fract() == 0.0 => Some(*n),
+        _ => None,
+    };
+    let number = |v: &Value| match v {
+        Value::Number(n) => Some(*n),
+        _ => None,
+    };
+    let converted: Result<Box<dyn ToSql + Sync>, ()> = match *ty {
+        Type::BOOL => convert(value, |v| match v {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        })
+        .map(|v| Box::new(v) as _),
+        Type::INT2 => convert(value, |v| integer(v).map(|n| n as i16)).map(|v| Box::new(v) as _),
+        Type::INT4 => convert(value, |v| integer(v).map(|n| n as i32)).map(|v| Box::new(v) as _),
+        Type::INT8 => convert(value, |v| integer(v).map(|n| n as i64)).map(|v| Box::new(v) as _),
+        Type::FLOAT4 => convert(value, |v| number(v).map(|n| n as f32)).map(|v| Box::new(v) as _),
+        Type::FLOAT8 => convert(value, number).map(|v| Box::new(v) as _),
+        _ => convert(value, |v| match v {
+            Value::String(s) => Some(s.to_string()),
+            _ => None,
+        })
+        .map(|v| Box::new(v) as _),
+    };
+    converted.map_err(|_| format!("Cannot bind a {} to a {} parameter", value.type_name(), ty))
+}
+
+fn row_values(row: &Row) -> Result<DbRow, String> {
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let error = |e: postgres::Error| format!("Column '{}': {}", column.name(), e);
+            let number = |n: Option<f64>| n.map_or(Value::Null, Value::Number);
+            let value = match *column.type_() {
+                Type::BOOL => row
+                    .try_get::<_, Option<bool>>(i)
+                    .map_err(error)?
+                    .map_or(Value::Null, Value::Boolean),
+                Type::INT2 => number(
+                    row.try_get::<_, Option<i16>>(i)
+                        .map_err(error)?
+                        .map(f64::from),
+                ),
+                Type::INT4 => number(
+                    row.try_get::<_, Option<i32>>(i)
+                        .map_err(error)?
+                        .map(f64::from),
+                ),
+                Type::INT8 => number(
+                    row.try_get::<_, Option<i64>>(i)
+                        .map_err(error)?
+                        .map(|n| n as f64),
+                ),
+                Type::FLOAT4 => number(
+                    row.try_get::<_, Option<f32>>(i)
+                        .map_err(error)?
+                        .map(f64::from),
+                ),
+                Type::FLOAT8 => number(row.try_get::<_, Option<f64>>(i).map_err(error)?),
+                Type::BYTEA => row
+                    .try_get::<_, Option<Vec<u8>>>(i)
+                    .map_err(error)?
+                    .map_or(Value::Null, |bytes| bytes_to_value(&bytes)),
+                _ => row
+                    .try_get::<_, Option<String>>(i)
+                    .map_err(|_| {
+                        format!(
+                            "Column '{}' has type {}, cast it to text in the query",
+                            column.name(),
+                            column.type_()
+                        )
+                    })?
+                    .map_or(Value::Null, |s| Value::String(Rc::from(s))),
+            };
+            Ok((column.name().to_string(), value))
+        })
+        .collect()
+}
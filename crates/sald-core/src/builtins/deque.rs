@@ -0,0 +1,330 @@
+//! Double-ended queue
+//! `Deque` pushes and pops at both ends in constant time. An optional
+//! maximum length turns it into a ring buffer that drops items from the
+//! opposite end. Contents live in the instance's native handle and are freed
+//! with it
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, native_instance, native_state};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+struct Store {
+    items: VecDeque<Value>,
+    max_length: Option<usize>,
+}
+
+pub fn create_deque_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    static_methods.insert("new".to_string(), deque_new);
+
+    instance_methods.insert("pushBack".to_string(), deque_push_back);
+    instance_methods.insert("pushFront".to_string(), deque_push_front);
+    instance_methods.insert("popBack".to_string(), deque_pop_back);
+    instance_methods.insert("popFront".to_string(), deque_pop_front);
+    instance_methods.insert("peekBack".to_string(), deque_peek_back);
+    instance_methods.insert("peekFront".to_string(), deque_peek_front);
+    instance_methods.insert("length".to_string(), deque_length);
+    instance_methods.insert("isEmpty".to_string(), deque_is_empty);
+    instance_methods.insert("at".to_string(), deque_at);
+    instance_methods.insert("rotate".to_string(), deque_rotate);
+    instance_methods.insert("clear".to_string(), deque_clear);
+    instance_methods.insert("toArray".to_string(), deque_to_array);
+    instance_methods.insert("toJson".to_string(), deque_to_array);
+    instance_methods.insert("toString".to_string(), deque_to_string);
+    callable_methods.insert("forEach".to_string(), deque_for_each);
+
+    let mut class = Class::new_with_instance("Deque", instance_methods, Some(deque_new));
+    class.native_static_methods = static_methods;
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Deque` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "Deque",
+        "Double-ended queue with constant time push and pop at both ends",
+    )
+    .method(
+        "new",
+        "Deque(items?, maxLength?)",
+        "Create a deque, dropping from the other end once maxLength is reached",
+    )
+    .method(
+        "pushBack",
+        "pushBack(value)",
+        "Add to the back, returns the new length",
+    )
+    .method(
+        "pushFront",
+        "pushFront(value)",
+        "Add to the front, returns the new length",
+    )
+    .method(
+        "popBack",
+        "popBack()",
+        "Remove from the back, null when empty",
+    )
+    .method(
+        "popFront",
+        "popFront()",
+        "Remove from the front, null when empty",
+    )
+    .method("peekBack", "peekBack()", "Last value without removing it")
+    .method(
+        "peekFront",
+        "peekFront()",
+        "First value without removing it",
+    )
+    .method("length", "length()", "Number of values")
+    .method("isEmpty", "isEmpty()", "Check if the deque is empty")
+    .method(
+        "at",
+        "at(index)",
+        "Value at a position from the front, negative from the back",
+    )
+    .method(
+        "rotate",
+        "rotate(n)",
+        "Move n values from the back to the front, negative the other way",
+    )
+    .method("clear", "clear()", "Remove all values")
+    .method("toArray", "toArray()", "Values from front to back")
+    .method(
+        "forEach",
+        "forEach(fn)",
+        "Call fn with each value, front first",
+    )
+}
+
+fn array(items: Vec<Value>) -> Value {
+    Value::Array(Rc::new(RefCell::new(items.into())))
+}
+
+fn register(store: Store) -> Value {
+    native_instance(create_deque_class(), store)
+}
+
+fn with_deque<T>(recv: &Value, f: impl FnOnce(&mut Store) -> T) -> Result<T, String> {
+    let store = native_state::<Store>(recv, "Deque")?;
+    let mut store = store.borrow_mut();
+    Ok(f(&mut store))
+}
+
+fn deque_new(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let max_length = match args.get(1) {
+        None | Some(Value::Null) => None,
+        Some(value) => {
+            let max = get_number_arg(value, "maxLength")?;
+            if max < 1.0 || max.fract() != 0.0 {
+                return Err("Argument 'maxLength' must be a positive integer".to_string());
+            }
+            Some(max as usize)
+        }
+    };
+    let mut items: VecDeque<Value> = match args.first() {
+        None | Some(Value::Null) => VecDeque::new(),
+        Some(Value::Array(items)) => items.borrow().iter().cloned().collect(),
+        Some(other) => {
+            return Err(format!(
+                "Argument 'items' must be an array, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    if let Some(max) = max_length {
+        let excess = items.len().saturating_sub(max);
+        items.drain(..excess);
+    }
+    Ok(register(Store { items, max_length }))
+}
+
+fn deque_push_back(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let (len, dropped) = with_deque(recv, |store| {
+        store.items.push_back(args[0].clone());
+        let dropped = match store.max_length {
+            Some(max) if store.items.len() > max => store.items.pop_front(),
+            _ => None,
+        };
+        (store.items.len(), dropped)
+    })?;
+    drop(dropped);
+    Ok(Value::Number(len as f64))
+}
+
+fn deque_push_front(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let (len, dropped) = with_deque(recv, |store| {
+        store.items.push_front(args[0].clone());
+        let dropped = match store.max_length {
+            Some(max) if store.items.len() > max => store.items.pop_back(),
+            _ => None,
+        };
+        (store.items.len(), dropped)
+    })?;
+    drop(dropped);
+    Ok(Value::Number(len as f64))
+}
+
+fn deque_pop_back(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(with_deque(recv, |store| store.items.pop_back())?.unwrap_or(Value::Null))
+}
+
+fn deque_pop_front(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(with_deque(recv, |store| store.items.pop_front())?.unwrap_or(Value::Null))
+}
+
+fn deque_peek_back(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(with_deque(recv, |store| store.items.back().cloned())?.unwrap_or(Value::Null))
+}
+
+fn deque_peek_front(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(with_deque(recv, |store| store.items.front().cloned())?.unwrap_or(Value::Null))
+}
+
+fn deque_length(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(
+        with_deque(recv, |store| store.items.len())? as f64
+    ))
+}
+
+fn deque_is_empty(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Boolean(with_deque(recv, |store| {
+        store.items.is_empty()
+    })?))
+}
+
+fn deque_at(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let index = get_number_arg(&args[0], "index")? as i64;
+    let value = with_deque(recv, |store| {
+        let len = store.items.len() as i64;
+        let index = if index < 0 { len + index } else { index };
+        if index < 0 || index >= len {
+            return None;
+        }
+        store.items.get(index as usize).cloned()
+    })?;
+    Ok(value.unwrap_or(Value::Null))
+}
+
+fn deque_rotate(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let n = get_number_arg(&args[0], "n")? as i64;
+    with_deque(recv, |store| {
+        let len = store.items.len() as i64;
+        if len > 0 {
+            store.items.rotate_right(n.rem_euclid(len) as usize);
+        }
+    })?;
+    Ok(recv.clone())
+}
+
+fn deque_clear(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let removed = with_deque(recv, |store| std::mem::take(&mut store.items))?;
+    drop(removed);
+    Ok(Value::Null)
+}
+
+fn deque_to_array(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let items = with_deque(recv, |store| store.items.iter().cloned().collect())?;
+    Ok(array(items))
+}
+
+fn deque_to_string(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let items: Vec<String> = with_deque(recv, |store| {
+        store.items.iter().map(|item| item.to_string()).collect()
+    })?;
+    Ok(Value::String(Rc::from(format!(
+        "Deque [{}]",
+        items.join(", ")
+    ))))
+}
+
+fn deque_for_each(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let items: Vec<Value> = with_deque(recv, |store| store.items.iter().cloned().collect())?;
+    for item in items {
+        caller.call(&args[0], vec![item])?;
+    }
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::{eval, eval_err};
+
+    #[test]
+    fn test_deque_keeps_its_own_contents() {
+        let result = eval(
+            r#"
+            let c = Counter(["x", "x"])
+            let d = Deque([1, 2])
+            d.toArray()
+            "#,
+        );
+        assert_eq!(result, "[1, 2]");
+    }
+
+    #[test]
+    fn test_deque_is_not_a_collection() {
+        let error = eval_err("Counter(Deque([1]))");
+        assert!(
+            error.contains("TypeError: Cannot count a Deque"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_deque_heap_and_counter() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let d = Deque([1, 2], 3)\n\
+                 d.pushFront(0)\n\
+                 d.pushBack(3)\n\
+                 let h = Heap([[2, \"b\"], [1, \"a\"]])\n\
+                 h.push([0, \"z\"])\n\
+                 let desc = Heap([1, 3, 2], |a, b| b - a)\n\
+                 let c = Counter(\"a b r a c a d a b r a\".split(\" \"))",
+            )
+            .unwrap();
+        let items: Vec<f64> = engine.eval_as("d.toArray()").unwrap();
+        assert_eq!(items, [1.0, 2.0, 3.0]);
+        let front: f64 = engine.eval_as("d.popFront()").unwrap();
+        assert_eq!(front, 1.0);
+        let first: String = engine.eval_as("h.pop()[1] + h.pop()[1]").unwrap();
+        assert_eq!(first, "za");
+        let sorted: Vec<f64> = engine.eval_as("desc.toArray()").unwrap();
+        assert_eq!(sorted, [3.0, 2.0, 1.0]);
+        let common: String = engine.eval_as("Json.stringify(c.mostCommon(2))").unwrap();
+        assert_eq!(common, "[[\"a\",5],[\"b\",2]]");
+        let total: f64 = engine.eval_as("c.total()").unwrap();
+        assert_eq!(total, 11.0);
+    }
+}
@@ -1,34 +1,189 @@
-use super::{check_arity, check_arity_range, get_string_arg};
-use crate::vm::value::{Class, NativeInstanceFn, Value};
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, dict_mut, get_string_arg, NamedArgs};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{Class, Dict, DictSlot, NativeInstanceFn, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 pub fn create_dict_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
     let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    static_methods.insert("fromEntries".to_string(), dict_from_entries);
 
     instance_methods.insert("length".to_string(), dict_length);
     instance_methods.insert("keys".to_string(), dict_keys);
     instance_methods.insert("values".to_string(), dict_values);
     instance_methods.insert("entries".to_string(), dict_entries);
-    instance_methods.insert("get".to_string(), dict_get);
-    instance_methods.insert("set".to_string(), dict_set);
-    instance_methods.insert("has".to_string(), dict_has);
-    instance_methods.insert("remove".to_string(), dict_remove);
+    callable_methods.insert("get".to_string(), dict_get);
+    callable_methods.insert("set".to_string(), dict_set);
+    callable_methods.insert("has".to_string(), dict_has);
+    callable_methods.insert("remove".to_string(), dict_remove);
     instance_methods.insert("clear".to_string(), dict_clear);
     instance_methods.insert("isEmpty".to_string(), dict_is_empty);
     instance_methods.insert("toString".to_string(), dict_to_string);
+    instance_methods.insert("merge".to_string(), dict_merge);
+    instance_methods.insert("defaults".to_string(), dict_defaults);
+    instance_methods.insert("invert".to_string(), dict_invert);
+
+    callable_methods.insert("mapValues".to_string(), dict_map_values);
+    callable_methods.insert("filter".to_string(), dict_filter);
+    callable_methods.insert("getOrInsert".to_string(), dict_get_or_insert);
+
+    let mut class = Class::new_with_instance("Dict", instance_methods, Some(dict_constructor));
+    class.native_static_methods = static_methods;
+    class.callable_native_instance_methods = callable_methods;
+    class.named_arg_methods.insert("merge".to_string());
+    class
+}
+
+/// API documentation for the `Dict` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Dict", "Dictionary operations")
+        .method("length", "length()", "Get number of keys")
+        .method("keys", "keys()", "Get all keys as array")
+        .method("values", "values()", "Get all values as array")
+        .method("entries", "entries()", "Get [key, value] pairs")
+        .method("get", "get(key, default?)", "Get value for key")
+        .method("set", "set(key, value)", "Set key-value pair")
+        .method("has", "has(key)", "Check if key exists")
+        .method("remove", "remove(key)", "Remove key")
+        .method("clear", "clear()", "Remove all keys")
+        .method("isEmpty", "isEmpty()", "Check if empty")
+        .method("toString", "toString()", "Convert to string")
+        .method(
+            "fromEntries",
+            "Dict.fromEntries(entries)",
+            "Build a dict from [key, value] pairs, as returned by entries()",
+        )
+        .method(
+            "merge",
+            "merge(other, deep?)",
+            "New dict with other's keys on top; merge(other, deep: true) merges nested dicts",
+        )
+        .method(
+            "defaults",
+            "defaults(other)",
+            "New dict with other's keys filled in where missing",
+        )
+        .method(
+            "mapValues",
+            "mapValues(fn)",
+            "New dict of fn(value) per key",
+        )
+        .method(
+            "filter",
+            "filter(fn)",
+            "New dict of the entries for which fn(key, value) is truthy",
+        )
+        .method(
+            "invert",
+            "invert()",
+            "New dict mapping each value (as a string) to its key",
+        )
+        .method(
+            "getOrInsert",
+            "getOrInsert(key, fn)",
+            "Value for key, storing fn() first if it is missing",
+        )
+}
+
+fn dict(map: Dict) -> Value {
+    Value::Dictionary(Rc::new(RefCell::new(map)))
+}
+
+/// Runs a method bound to a key, such as its `__hash__`
+pub(crate) type CallMethod<'a> = dyn FnMut(&Value, Vec<Value>) -> Result<Value, String> + 'a;
+
+/// Where `key` lives in `dict`, if it can be a key at all. An instance whose
+/// class defines `__hash__` is looked up by what that returns, then told
+/// apart from other keys with the same hash by identity or `__eq__`. `call`
+/// runs those methods.
+pub(crate) fn key_slot(
+    dict: &RefCell<Dict>,
+    key: &Value,
+    call: &mut CallMethod,
+) -> Option<Result<DictSlot, String>> {
+    let hash = match key {
+        Value::String(s) => return Some(Ok(DictSlot::Entry(s.to_string()))),
+        _ => super::bound_user_method(key, "__hash__")?,
+    };
+    let hash = match call(&hash, Vec::new()) {
+        Ok(Value::String(s)) => s.to_string(),
+        Ok(n @ Value::Number(_)) => n.to_string(),
+        Ok(other) => {
+            return Some(Err(format!(
+                "__hash__() must return a String or Number to be a dictionary key, got {}",
+                other.type_name()
+            )))
+        }
+        Err(e) => return Some(Err(e)),
+    };
+    let eq = super::bound_user_method(key, "__eq__");
+    let bucket = dict.borrow().bucket(&hash).to_vec();
+    for (entry, other) in bucket {
+        let same = match (key, &other, &eq) {
+            (Value::Instance(a), Value::Instance(b), _) if Rc::ptr_eq(a, b) => true,
+            (_, _, Some(eq)) => match call(eq, vec![other]) {
+                Ok(result) => result.is_truthy(),
+                Err(e) => return Some(Err(e)),
+            },
+            (_, _, None) => false,
+        };
+        if same {
+            return Some(Ok(DictSlot::Entry(entry)));
+        }
+    }
+    Some(Ok(DictSlot::Vacant(hash)))
+}
 
-    Class::new_with_instance("Dict", instance_methods, Some(dict_constructor))
+fn slot_arg(
+    dict: &RefCell<Dict>,
+    key: &Value,
+    caller: &mut dyn ValueCaller,
+) -> Result<DictSlot, String> {
+    key_slot(dict, key, &mut |method, args| caller.call(method, args)).unwrap_or_else(|| {
+        Err(format!(
+            "Argument 'key' must be a string or define __hash__, got {}",
+            key.type_name()
+        ))
+    })
+}
+
+fn dict_arg(value: &Value, name: &str) -> Result<Rc<RefCell<Dict>>, String> {
+    match value {
+        Value::Dictionary(dict) => Ok(dict.clone()),
+        other => Err(format!(
+            "Argument '{}' must be a dictionary, got {}",
+            name,
+            other.type_name()
+        )),
+    }
+}
+
+/// Copies `source` onto `target`, recursing into dicts present on both sides
+/// when `deep` is set. Nested dicts are copied, never shared with the inputs.
+fn merge_into(target: &mut Dict, source: &Dict, deep: bool) {
+    for (entry, value) in source.iter() {
+        let value = match (target.get(entry), value) {
+            (Some(Value::Dictionary(existing)), Value::Dictionary(incoming)) if deep => {
+                let mut merged = existing.borrow().clone();
+                merge_into(&mut merged, &incoming.borrow(), true);
+                dict(merged)
+            }
+            _ => value.clone(),
+        };
+        target.insert_entry(entry, &source.key(entry), value);
+    }
 }
 
 fn dict_constructor(args: &[Value]) -> Result<Value, String> {
     check_arity_range(0, 1, args.len())?;
 
     if args.is_empty() {
-        Ok(Value::Dictionary(Rc::new(RefCell::new(
-            FxHashMap::default(),
-        ))))
+        Ok(dict(Dict::new()))
     } else {
         match &args[0] {
             Value::Dictionary(source) => {
@@ -43,6 +198,28 @@ fn dict_constructor(args: &[Value]) -> Result<Value, String> {
     }
 }
 
+fn dict_from_entries(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let Value::Array(entries) = &args[0] else {
+        return Err(format!(
+            "Argument 'entries' must be an array, got {}",
+            args[0].type_name()
+        ));
+    };
+
+    let mut map = Dict::new();
+    for entry in entries.borrow().iter() {
+        match entry {
+            Value::Array(pair) if pair.borrow().len() == 2 => {
+                let pair = pair.borrow();
+                map.insert(get_string_arg(&pair[0], "key")?, pair[1].clone());
+            }
+            _ => return Err("Each entry must be a [key, value] array".to_string()),
+        }
+    }
+    Ok(dict(map))
+}
+
 fn dict_length(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     match recv {
@@ -55,12 +232,9 @@ fn dict_keys(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     match recv {
         Value::Dictionary(dict) => {
-            let keys: Vec<Value> = dict
-                .borrow()
-                .keys()
-                .map(|k| Value::String(Rc::from(k.clone())))
-                .collect();
-            Ok(Value::Array(Rc::new(RefCell::new(keys))))
+            let dict = dict.borrow();
+            let keys: Vec<Value> = dict.keys().map(|entry| dict.key(entry)).collect();
+            Ok(Value::Array(Rc::new(RefCell::new(keys.into()))))
         }
         _ => Err("Receiver must be a dictionary".to_string()),
     }
@@ -71,7 +245,7 @@ fn dict_values(recv: &Value, args: &[Value]) -> Result<Value, String> {
     match recv {
         Value::Dictionary(dict) => {
             let values: Vec<Value> = dict.borrow().values().cloned().collect();
-            Ok(Value::Array(Rc::new(RefCell::new(values))))
+            Ok(Value::Array(Rc::new(RefCell::new(values.into()))))
         }
         _ => Err("Receiver must be a dictionary".to_string()),
     }
@@ -81,29 +255,26 @@ fn dict_entries(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     match recv {
         Value::Dictionary(dict) => {
+            let dict = dict.borrow();
             let entries: Vec<Value> = dict
-                .borrow()
                 .iter()
                 .map(|(k, v)| {
-                    Value::Array(Rc::new(RefCell::new(vec![
-                        Value::String(Rc::from(k.clone())),
-                        v.clone(),
-                    ])))
+                    Value::Array(Rc::new(RefCell::new(vec![dict.key(k), v.clone()].into())))
                 })
                 .collect();
-            Ok(Value::Array(Rc::new(RefCell::new(entries))))
+            Ok(Value::Array(Rc::new(RefCell::new(entries.into()))))
         }
         _ => Err("Receiver must be a dictionary".to_string()),
     }
 }
 
-fn dict_get(recv: &Value, args: &[Value]) -> Result<Value, String> {
+fn dict_get(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
     check_arity_range(1, 2, args.len())?;
     match recv {
         Value::Dictionary(dict) => {
-            let key = get_string_arg(&args[0], "key")?;
+            let slot = slot_arg(dict, &args[0], caller)?;
             let dict_ref = dict.borrow();
-            match dict_ref.get(&key) {
+            match dict_ref.get_slot(&slot) {
                 Some(value) => Ok(value.clone()),
                 None => {
                     if args.len() == 2 {
@@ -118,35 +289,42 @@ fn dict_get(recv: &Value, args: &[Value]) -> Result<Value, String> {
     }
 }
 
-fn dict_set(recv: &Value, args: &[Value]) -> Result<Value, String> {
+fn dict_set(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
     check_arity(2, args.len())?;
     match recv {
         Value::Dictionary(dict) => {
-            let key = get_string_arg(&args[0], "key")?;
-            dict.borrow_mut().insert(key, args[1].clone());
+            let slot = slot_arg(dict, &args[0], caller)?;
+            dict_mut(dict)?.insert_slot(slot, &args[0], args[1].clone());
             Ok(Value::Null)
         }
         _ => Err("Receiver must be a dictionary".to_string()),
     }
 }
 
-fn dict_has(recv: &Value, args: &[Value]) -> Result<Value, String> {
+fn dict_has(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
     check_arity(1, args.len())?;
     match recv {
         Value::Dictionary(dict) => {
-            let key = get_string_arg(&args[0], "key")?;
-            Ok(Value::Boolean(dict.borrow().contains_key(&key)))
+            let slot = slot_arg(dict, &args[0], caller)?;
+            Ok(Value::Boolean(dict.borrow().get_slot(&slot).is_some()))
         }
         _ => Err("Receiver must be a dictionary".to_string()),
     }
 }
 
-fn dict_remove(recv: &Value, args: &[Value]) -> Result<Value, String> {
+fn dict_remove(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
     check_arity(1, args.len())?;
     match recv {
         Value::Dictionary(dict) => {
-            let key = get_string_arg(&args[0], "key")?;
-            Ok(dict.borrow_mut().remove(&key).unwrap_or(Value::Null))
+            let removed = match slot_arg(dict, &args[0], caller)? {
+                DictSlot::Entry(entry) => dict_mut(dict)?.remove(&entry),
+                DictSlot::Vacant(_) => None,
+            };
+            Ok(removed.unwrap_or(Value::Null))
         }
         _ => Err("Receiver must be a dictionary".to_string()),
     }
@@ -156,7 +334,7 @@ fn dict_clear(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     match recv {
         Value::Dictionary(dict) => {
-            dict.borrow_mut().clear();
+            dict_mut(dict)?.clear();
             Ok(Value::Null)
         }
         _ => Err("Receiver must be a dictionary".to_string()),
@@ -174,14 +352,257 @@ fn dict_is_empty(recv: &Value, args: &[Value]) -> Result<Value, String> {
 fn dict_to_string(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(0, args.len())?;
     match recv {
-        Value::Dictionary(dict) => {
-            let dict_ref = dict.borrow();
-            let items: Vec<String> = dict_ref
-                .iter()
-                .map(|(k, v)| format!("\"{}\": {}", k, v))
-                .collect();
-            Ok(Value::String(Rc::from(format!("{{{}}}", items.join(", ")))))
+        Value::Dictionary(_) => Ok(Value::String(Rc::from(recv.to_string()))),
+        _ => Err("Receiver must be a dictionary".to_string()),
+    }
+}
+
+fn dict_merge(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let (args, named) = NamedArgs::split(args, &["deep"])?;
+    check_arity_range(1, 2, args.len())?;
+    match recv {
+        Value::Dictionary(dict_ref) => {
+            let other = dict_arg(&args[0], "other")?;
+            let deep = match named.get(args, 1, "deep")? {
+                None | Some(Value::Null) => false,
+                Some(Value::Boolean(b)) => b,
+                Some(Value::Dictionary(options)) => options
+                    .borrow()
+                    .get("deep")
+                    .is_some_and(|deep| deep.is_truthy()),
+                Some(other) => {
+                    return Err(format!(
+                        "Argument 'deep' must be a boolean, got {}",
+                        other.type_name()
+                    ))
+                }
+            };
+
+            let mut merged = dict_ref.borrow().clone();
+            merge_into(&mut merged, &other.borrow(), deep);
+            Ok(dict(merged))
         }
         _ => Err("Receiver must be a dictionary".to_string()),
     }
 }
+
+fn dict_defaults(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    match recv {
+        Value::Dictionary(dict_ref) => {
+            let mut result = dict_ref.borrow().clone();
+            let other = dict_arg(&args[0], "other")?;
+            let other = other.borrow();
+            for (entry, value) in other.iter() {
+                if !result.contains_key(entry) {
+                    result.insert_entry(entry, &other.key(entry), value.clone());
+                }
+            }
+            Ok(dict(result))
+        }
+        _ => Err("Receiver must be a dictionary".to_string()),
+    }
+}
+
+fn dict_invert(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    match recv {
+        Value::Dictionary(dict_ref) => {
+            let mut inverted = Dict::new();
+            let dict_ref = dict_ref.borrow();
+            for (key, value) in dict_ref.iter() {
+                let new_key = match value {
+                    Value::String(_) | Value::Number(_) | Value::Boolean(_) | Value::Null => {
+                        value.to_string()
+                    }
+                    other => {
+                        return Err(format!("Cannot use a {} value as a key", other.type_name()))
+                    }
+                };
+                inverted.insert(new_key, dict_ref.key(key));
+            }
+            Ok(dict(inverted))
+        }
+        _ => Err("Receiver must be a dictionary".to_string()),
+    }
+}
+
+/// Entry keys, keys and values of `dict`
+fn entries_of(dict: &Dict) -> Vec<(String, Value, Value)> {
+    dict.iter()
+        .map(|(entry, value)| (entry.clone(), dict.key(entry), value.clone()))
+        .collect()
+}
+
+fn dict_map_values(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    match recv {
+        Value::Dictionary(dict_ref) => {
+            // Snapshot first so the callback may read or modify the receiver
+            let entries = entries_of(&dict_ref.borrow());
+            let mut result = Dict::new();
+            for (entry, key, value) in entries {
+                result.insert_entry(&entry, &key, caller.call(&args[0], vec![value])?);
+            }
+            Ok(dict(result))
+        }
+        _ => Err("Receiver must be a dictionary".to_string()),
+    }
+}
+
+fn dict_filter(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    match recv {
+        Value::Dictionary(dict_ref) => {
+            let entries = entries_of(&dict_ref.borrow());
+            let mut result = Dict::new();
+            for (entry, key, value) in entries {
+                let keep = caller
+                    .call(&args[0], vec![key.clone(), value.clone()])?
+                    .is_truthy();
+                if keep {
+                    result.insert_entry(&entry, &key, value);
+                }
+            }
+            Ok(dict(result))
+        }
+        _ => Err("Receiver must be a dictionary".to_string()),
+    }
+}
+
+fn dict_get_or_insert(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    match recv {
+        Value::Dictionary(dict_ref) => {
+            let slot = slot_arg(dict_ref, &args[0], caller)?;
+            if let Some(value) = dict_ref.borrow().get_slot(&slot) {
+                return Ok(value.clone());
+            }
+            let value = caller.call(&args[1], Vec::new())?;
+            // The callback may have changed the dict, so look the key up again
+            let slot = slot_arg(dict_ref, &args[0], caller)?;
+            dict_mut(dict_ref)?.insert_slot(slot, &args[0], value.clone());
+            Ok(value)
+        }
+        _ => Err("Receiver must be a dictionary".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::eval;
+
+    /// `Pt(1, 0)` and `Pt(0, 31)` hash alike but aren't equal
+    const PT: &str = r#"
+        class Pt {
+            fun init(self, x, y) {
+                self.x = x
+                self.y = y
+            }
+            fun __hash__(self) { return self.x * 31 + self.y }
+            fun __eq__(self, other) {
+                return Reflect.classOf(other) == Pt && self.x == other.x && self.y == other.y
+            }
+        }
+        let d = {Pt(1, 0): "a"}
+        d[Pt(0, 31)] = "b"
+    "#;
+
+    fn eval_pt(source: &str) -> String {
+        eval(&format!("{PT}\n{source}"))
+    }
+
+    #[test]
+    fn test_colliding_hashes_are_separate_keys() {
+        assert_eq!(eval_pt("d.length()"), "2");
+        assert_eq!(eval_pt("d[Pt(1, 0)]"), "a");
+        assert_eq!(eval_pt("d[Pt(0, 31)]"), "b");
+        assert_eq!(eval_pt("d[Pt(1, 0)] = \"c\"\nd.length()"), "2");
+        assert_eq!(eval_pt("d[Pt(1, 0)] = \"c\"\nd[Pt(0, 31)]"), "b");
+    }
+
+    #[test]
+    fn test_instance_keys_do_not_collide_with_strings() {
+        assert_eq!(eval_pt("d[\"31\"] = \"s\"\nd.length()"), "3");
+        assert_eq!(eval_pt("d[\"31\"] = \"s\"\nd[Pt(1, 0)]"), "a");
+        assert_eq!(eval_pt("d[\"31\"]"), "null");
+        assert_eq!(eval_pt("d.has(\"31\")"), "false");
+    }
+
+    #[test]
+    fn test_keys_are_the_instances() {
+        assert_eq!(
+            eval_pt("d.keys().map(|k| k.x + k.y).sort().join(\",\")"),
+            "1,31"
+        );
+        assert_eq!(
+            eval_pt("d.entries().map(|e| e[0].x).sort().join(\",\")"),
+            "0,1"
+        );
+    }
+
+    #[test]
+    fn test_dict_methods_take_instance_keys() {
+        assert_eq!(eval_pt("d.get(Pt(0, 31))"), "b");
+        assert_eq!(eval_pt("d.get(Pt(3, 3), \"none\")"), "none");
+        assert_eq!(eval_pt("d.has(Pt(1, 0))"), "true");
+        assert_eq!(eval_pt("d.remove(Pt(1, 0))\nd.length()"), "1");
+        assert_eq!(eval_pt("d.remove(Pt(1, 0))\nd[Pt(0, 31)]"), "b");
+        assert_eq!(eval_pt("d.remove(Pt(1, 0))\nd[Pt(1, 0)]"), "null");
+        assert_eq!(eval_pt("d.set(Pt(3, 3), \"e\")\nd[Pt(3, 3)]"), "e");
+    }
+
+    #[test]
+    fn test_copies_keep_instance_keys() {
+        assert_eq!(eval_pt("let c = {**d}\nc[Pt(0, 31)]"), "b");
+        assert_eq!(eval_pt("d.merge({\"k\": 1}).keys().length()"), "3");
+        assert_eq!(eval_pt("Type.deepClone(d)[Pt(1, 0)]"), "a");
+        assert_eq!(eval_pt("d.filter(|k, v| v == \"b\").keys()[0].y"), "31");
+    }
+
+    #[test]
+    fn test_dict_transformations() {
+        let mut engine = Engine::new();
+        let merged: serde_json::Value = engine
+            .eval_as("{\"a\": {\"x\": 1}, \"b\": 1}.merge({\"a\": {\"y\": 2}}, {\"deep\": true})")
+            .unwrap();
+        assert_eq!(merged, serde_json::json!({"a": {"x": 1, "y": 2}, "b": 1}));
+        let shallow: serde_json::Value = engine
+            .eval_as("{\"a\": {\"x\": 1}}.merge({\"a\": {\"y\": 2}}).defaults({\"c\": 3})")
+            .unwrap();
+        assert_eq!(shallow, serde_json::json!({"a": {"y": 2}, "c": 3}));
+        let named: serde_json::Value = engine
+            .eval_as("{\"a\": {\"x\": 1}}.merge({\"a\": {\"y\": 2}}, deep: true)")
+            .unwrap();
+        assert_eq!(named, serde_json::json!({"a": {"x": 1, "y": 2}}));
+        let named_shallow: serde_json::Value = engine
+            .eval_as("{\"a\": {\"x\": 1}}.merge({\"a\": {\"y\": 2}}, deep: false)")
+            .unwrap();
+        assert_eq!(named_shallow, serde_json::json!({"a": {"y": 2}}));
+        let mapped: serde_json::Value = engine
+            .eval_as("{\"a\": 1, \"b\": 2}.mapValues(|v| v * 10).filter(|k, v| k != \"a\")")
+            .unwrap();
+        assert_eq!(mapped, serde_json::json!({"b": 20}));
+        let inverted: serde_json::Value = engine
+            .eval_as("Dict.fromEntries({\"x\": 1, \"y\": \"z\"}.entries()).invert()")
+            .unwrap();
+        assert_eq!(inverted, serde_json::json!({"1": "x", "z": "y"}));
+        let cached: f64 = engine
+            .eval_as("let d = {}\nd.getOrInsert(\"n\", || 5) + d.getOrInsert(\"n\", || 7)")
+            .unwrap();
+        assert_eq!(cached, 10.0);
+    }
+}
@@ -0,0 +1,140 @@
+//! API documentation for builtin classes
+//! Each builtin module describes itself with a `docs()` function; hosts and
+//! native modules can add their own classes with `register`
+
+use parking_lot::Mutex;
+
+static REGISTERED: Mutex<Vec<ClassDoc>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassDoc {
+    pub name: String,
+    pub doc: String,
+    pub methods: Vec<MemberDoc>,
+    pub properties: Vec<MemberDoc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberDoc {
+    pub name: String,
+    /// Call signature for methods, empty for properties
+    pub signature: String,
+    pub doc: String,
+}
+
+impl ClassDoc {
+    pub fn new(name: impl Into<String>, doc: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            doc: doc.into(),
+            methods: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    pub fn method(
+        mut self,
+        name: impl Into<String>,
+        signature: impl Into<String>,
+        doc: impl Into<String>,
+    ) -> Self {
+        self.methods.push(MemberDoc {
+            name: name.into(),
+            signature: signature.into(),
+            doc: doc.into(),
+        });
+        self
+    }
+
+    pub fn property(mut self, name: impl Into<String>, doc: impl Into<String>) -> Self {
+        self.properties.push(MemberDoc {
+            name: name.into(),
+            signature: String::new(),
+            doc: doc.into(),
+        });
+        self
+    }
+}
+
+/// Adds documentation for a class defined outside the core builtins.
+/// A later registration with the same name replaces the earlier one.
+pub fn register(doc: ClassDoc) {
+    let mut registered = REGISTERED.lock();
+    registered.retain(|existing| existing.name != doc.name);
+    registered.push(doc);
+}
+
+/// Documentation for every builtin class followed by registered classes.
+pub fn builtin_docs() -> Vec<ClassDoc> {
+    let mut docs = vec![
+        super::console::docs(),
+        super::math::docs(),
+        super::json::docs(),
+        super::yaml::docs(),
+        super::toml::docs(),
+        super::msgpack::docs(),
+        super::random::docs(),
+        super::types::docs(),
+        super::array::docs(),
+        super::dict::docs(),
+        super::function::docs(),
+        super::string::docs(),
+        super::regex::docs(),
+    ];
+    docs.extend(super::collections::docs());
+    docs.push(super::deque::docs());
+    docs.push(super::heap::docs());
+    docs.push(super::iter::docs());
+    docs.push(super::matrix::docs());
+    docs.push(super::warning::docs());
+    docs.push(super::module::docs());
+    docs.push(super::code::docs());
+    docs.push(super::reflect::docs());
+
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend([
+        super::file::docs(),
+        super::date::docs(),
+        super::path::docs(),
+        super::process::docs(),
+        super::child::docs(),
+        super::system::docs(),
+        super::env::docs(),
+        super::ffi::docs(),
+        super::channel::docs(),
+        super::promise::docs(),
+        super::crypto::docs(),
+        super::profiler::docs(),
+        super::kv::docs(),
+        super::archive::docs(),
+        super::db::docs(),
+        super::encoding::docs(),
+        super::uuid::docs(),
+        super::cron::docs(),
+        super::temp::docs(),
+        super::readline::docs(),
+    ]);
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend(super::test::docs());
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend(super::timer::docs());
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend(super::json_stream::docs());
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend(super::file_stream::docs());
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend(super::compress::docs());
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend(super::term::docs());
+    #[cfg(not(target_arch = "wasm32"))]
+    docs.extend(super::args::docs());
+    #[cfg(target_arch = "wasm32")]
+    docs.extend(super::vfs::docs());
+    #[cfg(target_arch = "wasm32")]
+    docs.extend(super::web::docs());
+    #[cfg(target_arch = "wasm32")]
+    docs.extend(super::js::docs());
+
+    docs.extend(REGISTERED.lock().iter().cloned());
+    docs
+}
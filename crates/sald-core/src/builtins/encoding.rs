@@ -0,0 +1,253 @@
+//! Plain encodings
+//! Encoders take a string or a byte array; decoders of binary encodings return
+//! byte arrays, which `utf8Decode` turns back into text
+
+use super::docs::ClassDoc;
+use super::{bytes_to_value, check_arity, get_bytes_arg, get_string_arg};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use base64::{engine::general_purpose, Engine};
+use percent_encoding::{percent_decode_str, AsciiSet, NON_ALPHANUMERIC};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+/// Everything but the URI unreserved characters
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+pub fn create_encoding_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("base64Encode".to_string(), encoding_base64_encode);
+    static_methods.insert("base64Decode".to_string(), encoding_base64_decode);
+    static_methods.insert("base64UrlEncode".to_string(), encoding_base64_url_encode);
+    static_methods.insert("base64UrlDecode".to_string(), encoding_base64_url_decode);
+    static_methods.insert("hexEncode".to_string(), encoding_hex_encode);
+    static_methods.insert("hexDecode".to_string(), encoding_hex_decode);
+    static_methods.insert("urlEncode".to_string(), encoding_url_encode);
+    static_methods.insert("urlDecode".to_string(), encoding_url_decode);
+    static_methods.insert("punycodeEncode".to_string(), encoding_punycode_encode);
+    static_methods.insert("punycodeDecode".to_string(), encoding_punycode_decode);
+    static_methods.insert("domainToAscii".to_string(), encoding_domain_to_ascii);
+    static_methods.insert("domainToUnicode".to_string(), encoding_domain_to_unicode);
+    static_methods.insert("utf8Encode".to_string(), encoding_utf8_encode);
+    static_methods.insert("utf8Decode".to_string(), encoding_utf8_decode);
+
+    Class::new_with_static("Encoding", static_methods)
+}
+
+/// API documentation for the `Encoding` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Encoding", "Base64, hex, percent and punycode encodings")
+        .method("base64Encode", "base64Encode(data)", "Encode to base64")
+        .method(
+            "base64Decode",
+            "base64Decode(text)",
+            "Decode base64 to a byte array",
+        )
+        .method(
+            "base64UrlEncode",
+            "base64UrlEncode(data)",
+            "Encode to unpadded URL-safe base64",
+        )
+        .method(
+            "base64UrlDecode",
+            "base64UrlDecode(text)",
+            "Decode URL-safe base64, padded or not, to a byte array",
+        )
+        .method("hexEncode", "hexEncode(data)", "Encode to lowercase hex")
+        .method("hexDecode", "hexDecode(text)", "Decode hex to a byte array")
+        .method(
+            "urlEncode",
+            "urlEncode(data)",
+            "Percent-encode for use in a URL component",
+        )
+        .method(
+            "urlDecode",
+            "urlDecode(text)",
+            "Decode percent-encoded text",
+        )
+        .method(
+            "punycodeEncode",
+            "punycodeEncode(label)",
+            "Encode one label to punycode",
+        )
+        .method(
+            "punycodeDecode",
+            "punycodeDecode(label)",
+            "Decode one punycode label",
+        )
+        .method(
+            "domainToAscii",
+            "domainToAscii(domain)",
+            "Convert an internationalized domain to its xn-- form",
+        )
+        .method(
+            "domainToUnicode",
+            "domainToUnicode(domain)",
+            "Convert an xn-- domain back to Unicode",
+        )
+        .method(
+            "utf8Encode",
+            "utf8Encode(text)",
+            "Get the UTF-8 bytes of text",
+        )
+        .method(
+            "utf8Decode",
+            "utf8Decode(bytes)",
+            "Decode UTF-8 bytes to text",
+        )
+}
+
+fn string(s: String) -> Value {
+    Value::String(Rc::from(s))
+}
+
+fn encoding_base64_encode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let data = get_bytes_arg(&args[0], "data")?;
+    Ok(string(general_purpose::STANDARD.encode(data)))
+}
+
+fn encoding_base64_decode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    general_purpose::STANDARD
+        .decode(text.trim())
+        .map(|bytes| bytes_to_value(&bytes))
+        .map_err(|e| format!("Base64 decode error: {}", e))
+}
+
+fn encoding_base64_url_encode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let data = get_bytes_arg(&args[0], "data")?;
+    Ok(string(general_purpose::URL_SAFE_NO_PAD.encode(data)))
+}
+
+fn encoding_base64_url_decode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    general_purpose::URL_SAFE_NO_PAD
+        .decode(text.trim().trim_end_matches('='))
+        .map(|bytes| bytes_to_value(&bytes))
+        .map_err(|e| format!("Base64 decode error: {}", e))
+}
+
+fn encoding_hex_encode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let data = get_bytes_arg(&args[0], "data")?;
+    Ok(string(hex::encode(data)))
+}
+
+fn encoding_hex_decode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    hex::decode(text.trim())
+        .map(|bytes| bytes_to_value(&bytes))
+        .map_err(|e| format!("Hex decode error: {}", e))
+}
+
+fn encoding_url_encode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let data = get_bytes_arg(&args[0], "data")?;
+    let encoded = percent_encoding::percent_encode(&data, COMPONENT).to_string();
+    Ok(string(encoded))
+}
+
+fn encoding_url_decode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    percent_decode_str(&text)
+        .decode_utf8()
+        .map(|decoded| string(decoded.into_owned()))
+        .map_err(|_| "Decoded text is not valid UTF-8".to_string())
+}
+
+fn encoding_punycode_encode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let label = get_string_arg(&args[0], "label")?;
+    idna::punycode::encode_str(&label)
+        .map(string)
+        .ok_or_else(|| format!("Cannot encode '{}' to punycode", label))
+}
+
+fn encoding_punycode_decode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let label = get_string_arg(&args[0], "label")?;
+    idna::punycode::decode_to_string(&label)
+        .map(string)
+        .ok_or_else(|| format!("Invalid punycode '{}'", label))
+}
+
+fn encoding_domain_to_ascii(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let domain = get_string_arg(&args[0], "domain")?;
+    idna::domain_to_ascii(&domain)
+        .map(string)
+        .map_err(|_| format!("Invalid domain '{}'", domain))
+}
+
+fn encoding_domain_to_unicode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let domain = get_string_arg(&args[0], "domain")?;
+    match idna::domain_to_unicode(&domain) {
+        (unicode, Ok(())) => Ok(string(unicode)),
+        (_, Err(_)) => Err(format!("Invalid domain '{}'", domain)),
+    }
+}
+
+fn encoding_utf8_encode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    Ok(bytes_to_value(text.as_bytes()))
+}
+
+fn encoding_utf8_decode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let bytes = get_bytes_arg(&args[0], "bytes")?;
+    String::from_utf8(bytes)
+        .map(string)
+        .map_err(|e| format!("UTF-8 decode error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_encoding_builtin() {
+        let mut engine = Engine::new();
+        let result: Vec<String> = engine
+            .eval_as(
+                "[Encoding.base64Encode(\"héllo?\"),\n\
+                  Encoding.base64UrlEncode([251, 255]),\n\
+                  Encoding.hexEncode(Encoding.base64UrlDecode(\"-_8=\")),\n\
+                  Encoding.utf8Decode(Encoding.base64Decode(\"aMOpbGxvPw==\")),\n\
+                  Encoding.utf8Decode(Encoding.hexDecode(\"6869\")),\n\
+                  Encoding.urlEncode(\"a b&c/é~\"),\n\
+                  Encoding.urlDecode(\"a%20b%26c\"),\n\
+                  Encoding.punycodeEncode(\"bücher\"),\n\
+                  Encoding.domainToAscii(\"bücher.example\"),\n\
+                  Encoding.domainToUnicode(\"xn--bcher-kva.example\")]",
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            [
+                "aMOpbGxvPw==",
+                "-_8",
+                "fbff",
+                "héllo?",
+                "hi",
+                "a%20b%26c%2F%C3%A9~",
+                "a b&c",
+                "bcher-kva",
+                "xn--bcher-kva.example",
+                "bücher.example"
+            ]
+        );
+        assert!(engine.eval("Encoding.hexDecode(\"zz\")").is_err());
+    }
+}
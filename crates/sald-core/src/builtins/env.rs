@@ -0,0 +1,298 @@
+//! Environment variables
+//! `System.env` reads and writes the live process environment, and
+//! `System.loadDotenv` fills it from a `.env` file. Dotenv values may be
+//! single quoted (literal), double quoted (escapes, `$VAR` expansion, may span
+//! lines) or bare (expansion, trailing `# comment` removed)
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_string_arg};
+use crate::vm::value::{Class, Instance, NativeInstanceFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn create_env_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("get".to_string(), env_get);
+    instance_methods.insert("set".to_string(), env_set);
+    instance_methods.insert("delete".to_string(), env_delete);
+    instance_methods.insert("has".to_string(), env_has);
+    instance_methods.insert("keys".to_string(), env_keys);
+    instance_methods.insert("toDict".to_string(), env_to_dict);
+
+    Class::new_with_instance("Env", instance_methods, None)
+}
+
+/// The object behind `System.env`.
+pub(crate) fn create_env() -> Value {
+    let instance = Instance::new(Rc::new(create_env_class()));
+    Value::Instance(Rc::new(RefCell::new(instance)))
+}
+
+/// API documentation for the `Env` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Env", "Live process environment, available as System.env")
+        .method(
+            "get",
+            "get(name, default?)",
+            "Value of a variable, or default (null) when unset",
+        )
+        .method("set", "set(name, value)", "Set a variable")
+        .method(
+            "delete",
+            "delete(name)",
+            "Unset a variable, returns whether it was set",
+        )
+        .method("has", "has(name)", "Check if a variable is set")
+        .method("keys", "keys()", "Names of all variables")
+        .method("toDict", "toDict()", "Snapshot of all variables")
+}
+
+fn string(s: String) -> Value {
+    Value::String(Rc::from(s))
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.contains(['=', '\0']) {
+        return Err(format!("Invalid environment variable name '{}'", name));
+    }
+    Ok(())
+}
+
+fn env_get(_recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    match std::env::var(&name) {
+        Ok(value) => Ok(string(value)),
+        Err(_) => Ok(args.get(1).cloned().unwrap_or(Value::Null)),
+    }
+}
+
+fn env_set(_recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    check_name(&name)?;
+    let value = args[1].to_string();
+    if value.contains('\0') {
+        return Err("Environment values cannot contain NUL".to_string());
+    }
+    unsafe {
+        std::env::set_var(&name, value);
+    }
+    Ok(Value::Null)
+}
+
+fn env_delete(_recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    check_name(&name)?;
+    let existed = std::env::var_os(&name).is_some();
+    unsafe {
+        std::env::remove_var(&name);
+    }
+    Ok(Value::Boolean(existed))
+}
+
+fn env_has(_recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    Ok(Value::Boolean(std::env::var_os(name).is_some()))
+}
+
+fn env_keys(_recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let mut keys: Vec<String> = std::env::vars().map(|(key, _)| key).collect();
+    keys.sort();
+    let keys = keys.into_iter().map(string).collect();
+    Ok(Value::Array(Rc::new(RefCell::new(keys))))
+}
+
+fn env_to_dict(_recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let vars = std::env::vars()
+        .map(|(key, value)| (key, string(value)))
+        .collect();
+    Ok(Value::Dictionary(Rc::new(RefCell::new(vars))))
+}
+
+/// Expands `$NAME`, `${NAME}` and `${NAME:-default}`, preferring variables
+/// defined earlier in the same file.
+fn expand(text: &str, defined: &[(String, String)]) -> String {
+    let lookup = |name: &str| {
+        defined
+            .iter()
+            .rev()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| std::env::var(name).ok())
+            .filter(|value| !value.is_empty())
+    };
+    let is_name = |c: char| c.is_ascii_alphanumeric() || c == '_';
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(braced) = after.strip_prefix('{') {
+            if let Some(end) = braced.find('}') {
+                let inner = &braced[..end];
+                let (name, default) = match inner.split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (inner, None),
+                };
+                match lookup(name) {
+                    Some(value) => out.push_str(&value),
+                    None => out.push_str(&expand(default.unwrap_or(""), defined)),
+                }
+                rest = &braced[end + 1..];
+                continue;
+            }
+        }
+        let len = after.find(|c| !is_name(c)).unwrap_or(after.len());
+        if len == 0 {
+            out.push('$');
+        } else {
+            out.push_str(&lookup(&after[..len]).unwrap_or_default());
+        }
+        rest = &after[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Unescapes a double quoted value that starts after the opening quote, or
+/// returns `None` if the closing quote is not in `text` yet.
+fn double_quoted(text: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(value),
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                // Keep an escaped dollar away from expansion
+                Some('$') => value.push('\u{0}'),
+                Some(c) => value.push(c),
+                None => value.push('\\'),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Parses `.env` text into variables in file order.
+pub(crate) fn parse_dotenv(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("Line {}: expected KEY=VALUE", index + 1));
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("Line {}: invalid name '{}'", index + 1, key));
+        }
+        let value = value.trim_start();
+
+        let value = if let Some(quoted) = value.strip_prefix('\'') {
+            let end = quoted
+                .find('\'')
+                .ok_or_else(|| format!("Line {}: unterminated single quote", index + 1))?;
+            quoted[..end].to_string()
+        } else if let Some(quoted) = value.strip_prefix('"') {
+            // Double quoted values may continue over the following lines
+            let mut raw = quoted.to_string();
+            let parsed = loop {
+                if let Some(parsed) = double_quoted(&raw) {
+                    break parsed;
+                }
+                match lines.next() {
+                    Some((_, next)) => {
+                        raw.push('\n');
+                        raw.push_str(next);
+                    }
+                    None => return Err(format!("Line {}: unterminated double quote", index + 1)),
+                }
+            };
+            expand(&parsed, &vars).replace('\u{0}', "$")
+        } else {
+            let value = match value.find(" #") {
+                Some(comment) => &value[..comment],
+                None => value,
+            };
+            expand(value.trim_end(), &vars)
+        };
+        vars.push((key.to_string(), value));
+    }
+    Ok(vars)
+}
+
+pub(crate) fn system_load_dotenv(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let path = match args.first() {
+        None | Some(Value::Null) => ".env".to_string(),
+        Some(value) => get_string_arg(value, "path")?,
+    };
+    let overwrite = matches!(args.get(1), Some(Value::Boolean(true)));
+    let path = crate::workspace::resolve(&path);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let vars =
+        parse_dotenv(&text).map_err(|e| format!("Invalid dotenv '{}': {}", path.display(), e))?;
+
+    let mut loaded = FxHashMap::default();
+    for (key, value) in vars {
+        if overwrite || std::env::var_os(&key).is_none() {
+            unsafe {
+                std::env::set_var(&key, &value);
+            }
+        }
+        loaded.insert(key, string(value));
+    }
+    Ok(Value::Dictionary(Rc::new(RefCell::new(loaded.into()))))
+}
+
+pub(crate) fn system_parse_dotenv(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    let vars = parse_dotenv(&text)?
+        .into_iter()
+        .map(|(key, value)| (key, string(value)))
+        .collect();
+    Ok(Value::Dictionary(Rc::new(RefCell::new(vars))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_env_and_dotenv() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "System.env.set(\"SALD_TEST_ENV\", 42)\n\
+                 let parsed = System.parseDotenv(\"export A=$SALD_TEST_ENV # c\\nB=\\\"${A}\\\\n\\\"\\nC='$A'\\nD=${SALD_TEST_UNSET:-x}\")",
+            )
+            .unwrap();
+        let parsed: Vec<String> = engine
+            .eval_as("[parsed[\"A\"], parsed[\"B\"], parsed[\"C\"], parsed[\"D\"]]")
+            .unwrap();
+        assert_eq!(parsed, ["42", "42\n", "$A", "x"]);
+        let deleted: bool = engine
+            .eval_as("System.env.delete(\"SALD_TEST_ENV\")")
+            .unwrap();
+        assert!(deleted);
+        assert!(std::env::var("SALD_TEST_ENV").is_err());
+    }
+}
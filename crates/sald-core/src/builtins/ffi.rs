@@ -1,3 +1,5 @@
+use super::docs::ClassDoc;
+use super::{native_instance, native_state};
 use crate::vm::caller::ValueCaller;
 use crate::vm::value::{Class, Instance, NativeInstanceFn, Value};
 use libffi::middle::{Arg, Cif, CodePtr, Type as FfiType};
@@ -5,7 +7,7 @@ use libloading::{Library, Symbol};
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 use std::alloc::{alloc, Layout};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::ffi::{c_void, CStr, CString};
 use std::ptr;
 use std::rc::Rc;
@@ -25,6 +27,56 @@ enum CType {
     F64,
     Pointer,
     CString,
+    Struct(Rc<StructLayout>),
+}
+
+/// Field layout of a C struct, computed with the platform's natural alignment
+#[derive(Debug, PartialEq)]
+struct StructLayout {
+    fields: Vec<StructField>,
+    size: usize,
+    align: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct StructField {
+    name: String,
+    ctype: CType,
+    offset: usize,
+}
+
+impl StructLayout {
+    fn new(fields: Vec<(String, CType)>) -> Result<Self, String> {
+        if fields.is_empty() {
+            return Err("A struct needs at least one field".to_string());
+        }
+        let mut offset = 0usize;
+        let mut align = 1;
+        let mut laid_out = Vec::with_capacity(fields.len());
+        for (name, ctype) in fields {
+            if ctype == CType::Void {
+                return Err(format!("Field '{}' cannot be void", name));
+            }
+            if laid_out.iter().any(|f: &StructField| f.name == name) {
+                return Err(format!("Duplicate struct field '{}'", name));
+            }
+            let field_align = ctype.align();
+            offset = offset.next_multiple_of(field_align);
+            align = align.max(field_align);
+            let size = ctype.size();
+            laid_out.push(StructField {
+                name,
+                ctype,
+                offset,
+            });
+            offset += size;
+        }
+        Ok(Self {
+            fields: laid_out,
+            size: offset.next_multiple_of(align),
+            align,
+        })
+    }
 }
 
 impl CType {
@@ -43,10 +95,28 @@ impl CType {
             "f64" | "double" => Ok(CType::F64),
             "ptr" | "pointer" | "void*" | "voidptr" => Ok(CType::Pointer),
             "string" | "cstring" | "char*" | "str" => Ok(CType::CString),
+            "callback" | "fnptr" => Ok(CType::Pointer),
             _ => Err(format!("Unknown C type: {}", s)),
         }
     }
 
+    /// Accepts either a type name or an `Ffi.Struct` descriptor
+    fn from_value(value: &Value) -> Result<Self, String> {
+        match value {
+            Value::String(s) => Self::from_str(s),
+            _ => match handle_id(value, "Struct") {
+                Some(id) => STRUCT_LAYOUTS
+                    .with(|layouts| layouts.borrow().get(&id).cloned())
+                    .map(CType::Struct)
+                    .ok_or_else(|| "Struct descriptor is no longer alive".to_string()),
+                None => Err(format!(
+                    "Expected a type name or Ffi.Struct, got {}",
+                    value.type_name()
+                )),
+            },
+        }
+    }
+
     fn to_ffi_type(&self) -> FfiType {
         match self {
             CType::Void => FfiType::void(),
@@ -61,6 +131,9 @@ impl CType {
             CType::F32 => FfiType::f32(),
             CType::F64 => FfiType::f64(),
             CType::Pointer | CType::CString => FfiType::pointer(),
+            CType::Struct(layout) => {
+                FfiType::structure(layout.fields.iter().map(|f| f.ctype.to_ffi_type()))
+            }
         }
     }
 
@@ -72,6 +145,20 @@ impl CType {
             CType::I32 | CType::U32 | CType::F32 => 4,
             CType::I64 | CType::U64 | CType::F64 => 8,
             CType::Pointer | CType::CString => std::mem::size_of::<usize>(),
+            CType::Struct(layout) => layout.size,
+        }
+    }
+
+    fn align(&self) -> usize {
+        match self {
+            CType::Void | CType::I8 | CType::U8 => 1,
+            CType::I16 | CType::U16 => std::mem::align_of::<i16>(),
+            CType::I32 | CType::U32 => std::mem::align_of::<i32>(),
+            CType::F32 => std::mem::align_of::<f32>(),
+            CType::I64 | CType::U64 => std::mem::align_of::<i64>(),
+            CType::F64 => std::mem::align_of::<f64>(),
+            CType::Pointer | CType::CString => std::mem::align_of::<usize>(),
+            CType::Struct(layout) => layout.align,
         }
     }
 }
@@ -104,6 +191,211 @@ thread_local! {
     static GLOBAL_CALLER_VTABLE: RefCell<Vec<SendConstPtr>> = RefCell::new(Vec::new());
     static ALLOCATION_SIZES: RefCell<FxHashMap<usize, usize>> = RefCell::new(FxHashMap::default());
     static CLOSURE_REGISTRY: RefCell<FxHashMap<i64, ClosureData>> = RefCell::new(FxHashMap::default());
+    static STRUCT_LAYOUTS: RefCell<FxHashMap<usize, Rc<StructLayout>>> = RefCell::new(FxHashMap::default());
+    static OWNED: RefCell<FxHashMap<usize, OwnedPtr>> = RefCell::new(FxHashMap::default());
+    static NEXT_HANDLE: Cell<usize> = const { Cell::new(1) };
+}
+
+extern "C" {
+    fn malloc(size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+/// A native pointer Sald is responsible for releasing with `free_fn`
+struct OwnedPtr {
+    ptr: usize,
+    free_fn: usize,
+}
+
+impl OwnedPtr {
+    fn release(self) {
+        if self.ptr != 0 {
+            unsafe {
+                let free_fn: unsafe extern "C" fn(*mut c_void) = std::mem::transmute(self.free_fn);
+                free_fn(self.ptr as *mut c_void);
+            }
+        }
+    }
+}
+
+struct StructGuard(usize);
+
+impl Drop for StructGuard {
+    fn drop(&mut self) {
+        let _ = STRUCT_LAYOUTS.try_with(|layouts| layouts.borrow_mut().remove(&self.0));
+    }
+}
+
+struct OwnedGuard(usize);
+
+impl Drop for OwnedGuard {
+    fn drop(&mut self) {
+        if let Ok(Some(owned)) = OWNED.try_with(|owned| owned.borrow_mut().remove(&self.0)) {
+            owned.release();
+        }
+    }
+}
+
+fn next_handle() -> usize {
+    NEXT_HANDLE.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    })
+}
+
+/// State of `Ffi.Struct` and `Ffi.Owned` handles: the registry id and the
+/// guard that drops the registry entry along with the last handle
+struct Handle {
+    id: usize,
+    _guard: Box<dyn std::any::Any>,
+}
+
+/// State of an `Ffi.Callback`: its registry id and the closure's code pointer
+struct CallbackHandle {
+    id: i64,
+    code_ptr: usize,
+    released: bool,
+}
+
+fn handle_id(value: &Value, class_name: &str) -> Option<usize> {
+    let Value::Instance(inst) = value else {
+        return None;
+    };
+    let inst = inst.borrow();
+    if inst.class.name != class_name {
+        return None;
+    }
+    inst.native::<Handle>().map(|handle| handle.borrow().id)
+}
+
+fn guarded_instance(class: Class, id: usize, guard: Box<dyn std::any::Any>) -> Value {
+    native_instance(class, Handle { id, _guard: guard })
+}
+
+/// Resolves anything usable where C expects a pointer: numbers, null,
+/// callbacks (their code pointer) and owned pointers.
+fn pointer_of(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(n) => Some(*n as usize),
+        Value::Null => Some(0),
+        Value::Instance(inst) => {
+            let inst = inst.borrow();
+            match inst.class.name.as_str() {
+                "Callback" => inst.native::<CallbackHandle>().and_then(|handle| {
+                    let handle = handle.borrow();
+                    (!handle.released).then_some(handle.code_ptr)
+                }),
+                "Owned" => inst.native::<Handle>().and_then(|handle| {
+                    let id = handle.borrow().id;
+                    OWNED.with(|owned| owned.borrow().get(&id).map(|o| o.ptr))
+                }),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Reads a value of `ctype` stored at `ptr`
+unsafe fn read_ctype(ptr: *const u8, ctype: &CType) -> Value {
+    match ctype {
+        CType::Void => Value::Null,
+        CType::I8 => Value::Number(*(ptr as *const i8) as f64),
+        CType::U8 => Value::Number(*ptr as f64),
+        CType::I16 => Value::Number((ptr as *const i16).read_unaligned() as f64),
+        CType::U16 => Value::Number((ptr as *const u16).read_unaligned() as f64),
+        CType::I32 => Value::Number((ptr as *const i32).read_unaligned() as f64),
+        CType::U32 => Value::Number((ptr as *const u32).read_unaligned() as f64),
+        CType::I64 => Value::Number((ptr as *const i64).read_unaligned() as f64),
+        CType::U64 => Value::Number((ptr as *const u64).read_unaligned() as f64),
+        CType::F32 => Value::Number((ptr as *const f32).read_unaligned() as f64),
+        CType::F64 => Value::Number((ptr as *const f64).read_unaligned()),
+        CType::Pointer => Value::Number((ptr as *const usize).read_unaligned() as f64),
+        CType::CString => {
+            let s = (ptr as *const *const std::os::raw::c_char).read_unaligned();
+            if s.is_null() {
+                Value::Null
+            } else {
+                Value::String(Rc::from(CStr::from_ptr(s).to_string_lossy().to_string()))
+            }
+        }
+        CType::Struct(layout) => {
+            let mut fields = FxHashMap::default();
+            for field in &layout.fields {
+                fields.insert(
+                    field.name.clone(),
+                    read_ctype(ptr.add(field.offset), &field.ctype),
+                );
+            }
+            Value::Dictionary(Rc::new(RefCell::new(fields.into())))
+        }
+    }
+}
+
+/// Writes `value` as `ctype` at `ptr`. Strings are copied into `keep`, so
+/// they stay valid only as long as the caller holds on to it.
+unsafe fn write_ctype(
+    ptr: *mut u8,
+    ctype: &CType,
+    value: &Value,
+    keep: &mut Vec<CString>,
+) -> Result<(), String> {
+    let number = |v: &Value| match v {
+        Value::Number(n) => Ok(*n),
+        Value::Boolean(b) => Ok(if *b { 1.0 } else { 0.0 }),
+        _ => Err(format!("Cannot convert {} to {:?}", v.type_name(), ctype)),
+    };
+    match ctype {
+        CType::Void => {}
+        CType::I8 => *(ptr as *mut i8) = number(value)? as i8,
+        CType::U8 => *ptr = number(value)? as u8,
+        CType::I16 => (ptr as *mut i16).write_unaligned(number(value)? as i16),
+        CType::U16 => (ptr as *mut u16).write_unaligned(number(value)? as u16),
+        CType::I32 => (ptr as *mut i32).write_unaligned(number(value)? as i32),
+        CType::U32 => (ptr as *mut u32).write_unaligned(number(value)? as u32),
+        CType::I64 => (ptr as *mut i64).write_unaligned(number(value)? as i64),
+        CType::U64 => (ptr as *mut u64).write_unaligned(number(value)? as u64),
+        CType::F32 => (ptr as *mut f32).write_unaligned(number(value)? as f32),
+        CType::F64 => (ptr as *mut f64).write_unaligned(number(value)?),
+        CType::Pointer | CType::CString => {
+            let address = match value {
+                Value::String(s) if *ctype == CType::CString => {
+                    let c_str =
+                        CString::new(&**s).map_err(|_| "Invalid C string (contains null byte)")?;
+                    let address = c_str.as_ptr() as usize;
+                    keep.push(c_str);
+                    address
+                }
+                _ => pointer_of(value).ok_or_else(|| {
+                    format!("Cannot convert {} to {:?}", value.type_name(), ctype)
+                })?,
+            };
+            (ptr as *mut usize).write_unaligned(address);
+        }
+        CType::Struct(layout) => {
+            let Value::Dictionary(dict) = value else {
+                return Err(format!(
+                    "Struct value must be a dictionary, got {}",
+                    value.type_name()
+                ));
+            };
+            let dict = dict.borrow();
+            ptr::write_bytes(ptr, 0, layout.size);
+            for field in &layout.fields {
+                if let Some(v) = dict.get(&field.name) {
+                    write_ctype(ptr.add(field.offset), &field.ctype, v, keep)
+                        .map_err(|e| format!("Field '{}': {}", field.name, e))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Zeroed, 8-byte aligned scratch space for a by-value struct
+fn struct_buffer(size: usize) -> Vec<u64> {
+    vec![0u64; size.max(8).div_ceil(8)]
 }
 
 #[allow(dead_code)]
@@ -184,33 +476,24 @@ extern "C" fn closure_handler(
     let mut sald_args = Vec::with_capacity(arg_types.len());
     for (i, arg_type) in arg_types.iter().enumerate() {
         let arg_ptr = unsafe { *args.add(i) };
-        let value = match arg_type {
-            CType::I8 => Value::Number(unsafe { *(arg_ptr as *const i8) } as f64),
-            CType::U8 => Value::Number(unsafe { *(arg_ptr as *const u8) } as f64),
-            CType::I16 => Value::Number(unsafe { *(arg_ptr as *const i16) } as f64),
-            CType::U16 => Value::Number(unsafe { *(arg_ptr as *const u16) } as f64),
-            CType::I32 => Value::Number(unsafe { *(arg_ptr as *const i32) } as f64),
-            CType::U32 => Value::Number(unsafe { *(arg_ptr as *const u32) } as f64),
-            CType::I64 => Value::Number(unsafe { *(arg_ptr as *const i64) } as f64),
-            CType::U64 => Value::Number(unsafe { *(arg_ptr as *const u64) } as f64),
-            CType::F32 => Value::Number(unsafe { *(arg_ptr as *const f32) } as f64),
-            CType::F64 => Value::Number(unsafe { *(arg_ptr as *const f64) }),
-            CType::Pointer => Value::Number(unsafe { *(arg_ptr as *const usize) } as f64),
-            CType::CString => {
-                let ptr = unsafe { *(arg_ptr as *const *const i8) };
-                if ptr.is_null() {
-                    Value::Null
-                } else {
-                    let cstr = unsafe { CStr::from_ptr(ptr) };
-                    Value::String(Rc::from(cstr.to_string_lossy().to_string()))
-                }
-            }
-            CType::Void => Value::Null,
-        };
-        sald_args.push(value);
+        sald_args.push(unsafe { read_ctype(arg_ptr as *const u8, arg_type) });
     }
 
     match caller.call(&callback_fn, sald_args) {
+        Ok(ret_val) if matches!(return_type, CType::Struct(_)) => {
+            let mut keep = Vec::new();
+            let written = unsafe {
+                write_ctype(
+                    result as *mut u64 as *mut u8,
+                    &return_type,
+                    &ret_val,
+                    &mut keep,
+                )
+            };
+            if let Err(e) = written {
+                eprintln!("[FFI] Callback error: {}", e);
+            }
+        }
         Ok(ret_val) => {
             *result = match (&return_type, &ret_val) {
                 (CType::Void, _) => 0,
@@ -389,6 +672,7 @@ enum ConvertedData {
     F32(f32),
     F64(f64),
     Ptr(usize),
+    Struct(Vec<u64>),
 }
 
 fn convert_value_to_arg(value: &Value, ctype: &CType) -> Result<ConvertedArg, String> {
@@ -648,6 +932,14 @@ pub fn create_ffi_namespace() -> Value {
         },
     );
 
+    members.insert(
+        "alignof".to_string(),
+        Value::NativeFunction {
+            func: ffi_alignof,
+            class_name: "Ffi".into(),
+        },
+    );
+
     members.insert("NULL".to_string(), Value::Number(0.0));
 
     members.insert(
@@ -658,14 +950,134 @@ pub fn create_ffi_namespace() -> Value {
         "Callback".to_string(),
         Value::Class(Rc::new(create_callback_class())),
     );
+    members.insert(
+        "Struct".to_string(),
+        Value::Class(Rc::new(create_struct_class())),
+    );
+    members.insert(
+        "Owned".to_string(),
+        Value::Class(Rc::new(create_owned_class())),
+    );
 
     Value::Namespace {
         name: "Ffi".to_string(),
-        members: Rc::new(RefCell::new(members)),
+        members: Rc::new(RefCell::new(members.into())),
         module_globals: None,
     }
 }
 
+/// API documentation for the `Ffi` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "Ffi",
+        "Foreign Function Interface for calling native C libraries",
+    )
+    .method(
+        "open",
+        "open(path)",
+        "Load a dynamic library (.dll/.so/.dylib)",
+    )
+    .method("alloc", "alloc(size)", "Allocate memory")
+    .method("free", "free(ptr)", "Free allocated memory")
+    .method("memcpy", "memcpy(dest, src, size)", "Copy memory")
+    .method(
+        "memset",
+        "memset(ptr, value, size)",
+        "Fill memory with value",
+    )
+    .method("readI8", "readI8(ptr)", "Read signed 8-bit integer")
+    .method("readU8", "readU8(ptr)", "Read unsigned 8-bit integer")
+    .method("readI16", "readI16(ptr)", "Read signed 16-bit integer")
+    .method("readU16", "readU16(ptr)", "Read unsigned 16-bit integer")
+    .method("readI32", "readI32(ptr)", "Read signed 32-bit integer")
+    .method("readU32", "readU32(ptr)", "Read unsigned 32-bit integer")
+    .method("readI64", "readI64(ptr)", "Read signed 64-bit integer")
+    .method("readU64", "readU64(ptr)", "Read unsigned 64-bit integer")
+    .method("readF32", "readF32(ptr)", "Read 32-bit float")
+    .method("readF64", "readF64(ptr)", "Read 64-bit double")
+    .method("readPtr", "readPtr(ptr)", "Read pointer")
+    .method(
+        "readString",
+        "readString(ptr)",
+        "Read null-terminated C string",
+    )
+    .method(
+        "writeI8",
+        "writeI8(ptr, value)",
+        "Write signed 8-bit integer",
+    )
+    .method(
+        "writeU8",
+        "writeU8(ptr, value)",
+        "Write unsigned 8-bit integer",
+    )
+    .method(
+        "writeI16",
+        "writeI16(ptr, value)",
+        "Write signed 16-bit integer",
+    )
+    .method(
+        "writeU16",
+        "writeU16(ptr, value)",
+        "Write unsigned 16-bit integer",
+    )
+    .method(
+        "writeI32",
+        "writeI32(ptr, value)",
+        "Write signed 32-bit integer",
+    )
+    .method(
+        "writeU32",
+        "writeU32(ptr, value)",
+        "Write unsigned 32-bit integer",
+    )
+    .method(
+        "writeI64",
+        "writeI64(ptr, value)",
+        "Write signed 64-bit integer",
+    )
+    .method(
+        "writeU64",
+        "writeU64(ptr, value)",
+        "Write unsigned 64-bit integer",
+    )
+    .method("writeF32", "writeF32(ptr, value)", "Write 32-bit float")
+    .method("writeF64", "writeF64(ptr, value)", "Write 64-bit double")
+    .method("writePtr", "writePtr(ptr, value)", "Write pointer")
+    .method(
+        "writeString",
+        "writeString(ptr, value)",
+        "Write null-terminated C string",
+    )
+    .method("offset", "offset(ptr, bytes)", "Add byte offset to pointer")
+    .method(
+        "sizeof",
+        "sizeof(type)",
+        "Get size of type or Struct in bytes",
+    )
+    .method(
+        "alignof",
+        "alignof(type)",
+        "Get alignment of type or Struct in bytes",
+    )
+    .method(
+        "Callback",
+        "Callback({ args, returns, fn })",
+        "Create callback for C functions",
+    )
+    .method(
+        "Struct",
+        "Struct([[name, type], ...])",
+        "Describe a C struct; usable as a type in calls and callbacks",
+    )
+    .method(
+        "Owned",
+        "Owned(ptr, freeFn?)",
+        "Pointer freed with freeFn (default C free) when collected",
+    )
+    .property("NULL", "Null pointer (0)")
+}
+
 fn ffi_alloc(args: &[Value]) -> Result<Value, String> {
     if args.is_empty() {
         return Err("Ffi.alloc expects 1 argument (size)".to_string());
@@ -905,14 +1317,18 @@ fn ffi_sizeof(args: &[Value]) -> Result<Value, String> {
     if args.is_empty() {
         return Err("Ffi.sizeof expects 1 argument (type_name)".to_string());
     }
-    let type_name = match &args[0] {
-        Value::String(s) => &**s,
-        _ => return Err("Type name must be a string".to_string()),
-    };
-    let ctype = CType::from_str(type_name)?;
+    let ctype = CType::from_value(&args[0])?;
     Ok(Value::Number(ctype.size() as f64))
 }
 
+fn ffi_alignof(args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() {
+        return Err("Ffi.alignof expects 1 argument (type_name)".to_string());
+    }
+    let ctype = CType::from_value(&args[0])?;
+    Ok(Value::Number(ctype.align() as f64))
+}
+
 fn ffi_open(args: &[Value]) -> Result<Value, String> {
     if args.is_empty() {
         return Err("Ffi.open expects 1 argument (path)".to_string());
@@ -927,8 +1343,13 @@ fn ffi_open(args: &[Value]) -> Result<Value, String> {
         }
     };
 
-    let resolved_path = crate::resolve_script_path(&path);
-    let full_path = resolved_path.to_string_lossy().to_string();
+    let resolved_path = crate::workspace::resolve(&path);
+    // Bare names like "libsqlite3.so" fall back to the system search path
+    let full_path = if resolved_path.exists() || path.contains(['/', '\\']) {
+        resolved_path.to_string_lossy().to_string()
+    } else {
+        path
+    };
 
     let library = unsafe {
         Library::new(&full_path)
@@ -1001,37 +1422,48 @@ fn library_call(
         return Err("lib.call requires options dictionary with 'args' and 'returns'".to_string());
     };
 
-    let return_type_str = match options.get("returns") {
-        Some(Value::String(s)) => s.to_string(),
-        Some(_) => return Err("'returns' must be a string".to_string()),
-        None => "void".to_string(),
+    let return_type = match options.get("returns") {
+        Some(v) => CType::from_value(v)?,
+        None => CType::Void,
+    };
+    let caller_owns_result = parse_owner(options.get("owner"))?;
+    if caller_owns_result && !matches!(return_type, CType::Pointer | CType::CString) {
+        return Err("'owner: \"caller\"' only applies to pointer and string returns".to_string());
+    }
+    let free_symbol = match options.get("free") {
+        Some(Value::String(s)) => Some(s.to_string()),
+        Some(_) => return Err("'free' must be a symbol name".to_string()),
+        None => None,
     };
-    let return_type = CType::from_str(&return_type_str)?;
 
-    let (call_values, arg_types) = match options.get("args") {
+    let (call_values, arg_types, transfers) = match options.get("args") {
         Some(Value::Array(arr)) => {
             let arr_guard = arr.borrow();
             let mut values = Vec::new();
             let mut types = Vec::new();
+            let mut transfers = Vec::new();
 
             for (idx, arg_dict) in arr_guard.iter().enumerate() {
                 match arg_dict {
                     Value::Dictionary(d) => {
                         let d_guard = d.borrow();
 
-                        let type_str = match d_guard.get("type") {
-                            Some(Value::String(s)) => s.to_string(),
-                            Some(_) => return Err(format!("args[{}].type must be a string", idx)),
+                        let ctype = match d_guard.get("type") {
+                            Some(t) => CType::from_value(t)
+                                .map_err(|e| format!("args[{}].type: {}", idx, e))?,
                             None => return Err(format!("args[{}] missing 'type' field", idx)),
                         };
+                        let transfer =
+                            d_guard.contains_key("owner") && !parse_owner(d_guard.get("owner"))?;
 
                         let value = match d_guard.get("value") {
                             Some(v) => v.clone(),
                             None => return Err(format!("args[{}] missing 'value' field", idx)),
                         };
 
-                        types.push(CType::from_str(&type_str)?);
+                        types.push(ctype);
                         values.push(value);
+                        transfers.push(transfer);
                     }
                     _ => {
                         return Err(format!(
@@ -1042,10 +1474,10 @@ fn library_call(
                 }
             }
 
-            (values, types)
+            (values, types, transfers)
         }
         Some(_) => return Err("'args' must be an array".to_string()),
-        None => (Vec::new(), Vec::new()),
+        None => (Vec::new(), Vec::new(), Vec::new()),
     };
 
     set_global_caller(caller);
@@ -1067,21 +1499,32 @@ fn library_call(
         unsafe {
             let lib_mutex = &*lib_ptr;
 
-            let func_ptr = {
+            let lookup = |name: &str| -> Result<*const c_void, String> {
                 let lib_guard = lib_mutex.lock();
 
-                let fn_name_c =
-                    CString::new(fn_name.as_str()).map_err(|_| "Invalid function name")?;
+                let name_c = CString::new(name).map_err(|_| "Invalid function name")?;
 
                 let func_ptr: Symbol<*const c_void> = lib_guard
                     .library
-                    .get(fn_name_c.as_bytes_with_nul())
-                    .map_err(|e| format!("Function '{}' not found: {}", fn_name, e))?;
+                    .get(name_c.as_bytes_with_nul())
+                    .map_err(|e| format!("Function '{}' not found: {}", name, e))?;
 
-                *func_ptr
+                Ok(*func_ptr)
+            };
+            let func_ptr = lookup(&fn_name)?;
+            let free_fn = match &free_symbol {
+                Some(name) => lookup(name)? as usize,
+                None => free as *const () as usize,
             };
 
-            call_with_types(func_ptr, &call_values, &arg_types, &return_type)
+            call_with_types(func_ptr, &call_values, &arg_types, &transfers, &return_type).map(
+                |value| match value {
+                    Value::Number(p) if caller_owns_result => {
+                        take_ownership(p as usize, free_fn, &return_type)
+                    }
+                    other => other,
+                },
+            )
         }
     } else {
         Err("Invalid library instance".to_string())
@@ -1192,25 +1635,28 @@ fn callback_new(args: &[Value]) -> Result<Value, String> {
             let arr_guard = arr.borrow();
             let mut types = Vec::new();
             for (idx, t) in arr_guard.iter().enumerate() {
-                match t {
-                    Value::String(s) => types.push(CType::from_str(s)?),
-                    _ => return Err(format!("args[{}] must be a type string", idx)),
-                }
+                types.push(CType::from_value(t).map_err(|e| format!("args[{}]: {}", idx, e))?);
             }
             types
         }
-        Some(_) => return Err("'args' must be an array of type strings".to_string()),
+        Some(_) => return Err("'args' must be an array of types".to_string()),
         None => Vec::new(),
     };
 
     let return_type = match options.get("returns") {
-        Some(Value::String(s)) => CType::from_str(s)?,
-        Some(_) => return Err("'returns' must be a type string".to_string()),
+        Some(v) => CType::from_value(v)?,
         None => CType::Void,
     };
 
     let func = match options.get("fn") {
-        Some(f @ Value::Function(_)) => f.clone(),
+        Some(
+            f @ (Value::Function(_)
+            | Value::BoundMethod { .. }
+            | Value::Partial { .. }
+            | Value::NativeFunction { .. }
+            | Value::HostFunction { .. }
+            | Value::InstanceMethod { .. }),
+        ) => f.clone(),
         Some(_) => return Err("'fn' must be a function".to_string()),
         None => return Err("Missing 'fn' field in Callback options".to_string()),
     };
@@ -1245,83 +1691,252 @@ fn callback_new(args: &[Value]) -> Result<Value, String> {
         );
     });
 
-    let callback_class = Rc::new(create_callback_class());
-    let mut instance = Instance::new(callback_class);
-
-    instance
-        .fields
-        .insert("_id".to_string(), Value::Number(callback_id as f64));
-    instance.fields.insert(
-        "_code_ptr".to_string(),
-        Value::Number(code_ptr_value as f64),
-    );
-    instance
-        .fields
-        .insert("_released".to_string(), Value::Boolean(false));
+    Ok(native_instance(
+        create_callback_class(),
+        CallbackHandle {
+            id: callback_id,
+            code_ptr: code_ptr_value,
+            released: false,
+        },
+    ))
+}
 
-    Ok(Value::Instance(Rc::new(RefCell::new(instance))))
+/// The callback behind `recv`, unless it has been released
+fn live_callback(recv: &Value) -> Result<Rc<RefCell<CallbackHandle>>, String> {
+    let handle = native_state::<CallbackHandle>(recv, "Callback")?;
+    if handle.borrow().released {
+        return Err("Callback has been released".to_string());
+    }
+    Ok(handle)
 }
 
 fn callback_ptr(recv: &Value, _args: &[Value]) -> Result<Value, String> {
-    if let Value::Instance(inst) = recv {
-        let inst_guard = inst.borrow();
+    let handle = live_callback(recv)?;
+    let code_ptr = handle.borrow().code_ptr;
+    Ok(Value::Number(code_ptr as f64))
+}
 
-        if let Some(Value::Boolean(true)) = inst_guard.fields.get("_released") {
-            return Err("Callback has been released".to_string());
-        }
+fn callback_id(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    let handle = live_callback(recv)?;
+    let id = handle.borrow().id;
+    Ok(Value::Number(id as f64))
+}
 
-        if let Some(Value::Number(code_ptr)) = inst_guard.fields.get("_code_ptr") {
-            return Ok(Value::Number(*code_ptr));
-        }
+fn callback_release(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    let handle = native_state::<CallbackHandle>(recv, "Callback")?;
+    let mut handle = handle.borrow_mut();
+    if !handle.released {
+        unregister_callback(handle.id);
+        CLOSURE_REGISTRY.with(|reg_cell| {
+            let mut map = reg_cell.borrow_mut();
+            map.remove(&handle.id);
+        });
+        handle.released = true;
     }
-    Err("Invalid callback instance".to_string())
+    Ok(Value::Null)
 }
 
-fn callback_id(recv: &Value, _args: &[Value]) -> Result<Value, String> {
-    if let Value::Instance(inst) = recv {
-        let inst_guard = inst.borrow();
+fn create_struct_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
 
-        if let Some(Value::Boolean(true)) = inst_guard.fields.get("_released") {
-            return Err("Callback has been released".to_string());
-        }
+    instance_methods.insert("size".to_string(), struct_size);
+    instance_methods.insert("align".to_string(), struct_align);
+    instance_methods.insert("offsetOf".to_string(), struct_offset_of);
+    instance_methods.insert("fields".to_string(), struct_fields);
+    instance_methods.insert("alloc".to_string(), struct_alloc);
+    instance_methods.insert("read".to_string(), struct_read);
+    instance_methods.insert("write".to_string(), struct_write);
 
-        if let Some(id) = inst_guard.fields.get("_id") {
-            return Ok(id.clone());
-        }
+    let mut class = Class::new_with_instance("Struct", instance_methods, Some(struct_new));
+
+    class
+        .native_static_methods
+        .insert("new".to_string(), struct_new);
+
+    class
+}
+
+fn struct_new(args: &[Value]) -> Result<Value, String> {
+    let fields = match args.first() {
+        Some(Value::Array(arr)) => arr.borrow().clone(),
+        _ => return Err("Ffi.Struct expects an array of [name, type] fields".to_string()),
+    };
+
+    let mut parsed = Vec::with_capacity(fields.len());
+    for (idx, field) in fields.iter().enumerate() {
+        let (name, ctype) = match field {
+            Value::Array(pair) => {
+                let pair = pair.borrow();
+                match pair.as_slice() {
+                    [Value::String(name), t] => (name.to_string(), t.clone()),
+                    _ => return Err(format!("fields[{}] must be [name, type]", idx)),
+                }
+            }
+            Value::Dictionary(d) => {
+                let d = d.borrow();
+                match (d.get("name"), d.get("type")) {
+                    (Some(Value::String(name)), Some(t)) => (name.to_string(), t.clone()),
+                    _ => return Err(format!("fields[{}] must have 'name' and 'type'", idx)),
+                }
+            }
+            _ => return Err(format!("fields[{}] must be [name, type]", idx)),
+        };
+        let ctype = CType::from_value(&ctype).map_err(|e| format!("fields[{}]: {}", idx, e))?;
+        parsed.push((name, ctype));
     }
-    Err("Invalid callback instance".to_string())
+
+    let layout = Rc::new(StructLayout::new(parsed)?);
+    let id = next_handle();
+    STRUCT_LAYOUTS.with(|layouts| layouts.borrow_mut().insert(id, layout));
+    Ok(guarded_instance(
+        create_struct_class(),
+        id,
+        Box::new(StructGuard(id)),
+    ))
 }
 
-fn callback_release(recv: &Value, _args: &[Value]) -> Result<Value, String> {
-    if let Value::Instance(inst) = recv {
-        let mut inst_guard = inst.borrow_mut();
+fn struct_layout(recv: &Value) -> Result<Rc<StructLayout>, String> {
+    match CType::from_value(recv) {
+        Ok(CType::Struct(layout)) => Ok(layout),
+        _ => Err("Receiver must be a Struct".to_string()),
+    }
+}
 
-        if let Some(Value::Boolean(true)) = inst_guard.fields.get("_released") {
-            return Ok(Value::Null);
-        }
+fn struct_pointer(args: &[Value], method: &str) -> Result<*mut u8, String> {
+    match args.first().and_then(pointer_of) {
+        Some(0) => Err(format!("Struct.{}: null pointer", method)),
+        Some(ptr) => Ok(ptr as *mut u8),
+        None => Err(format!("Struct.{} expects a pointer", method)),
+    }
+}
 
-        if let Some(Value::Number(id)) = inst_guard.fields.get("_id") {
-            let callback_id = *id as i64;
-            unregister_callback(callback_id);
+fn struct_size(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(struct_layout(recv)?.size as f64))
+}
 
-            CLOSURE_REGISTRY.with(|reg_cell| {
-                let mut map = reg_cell.borrow_mut();
-                map.remove(&callback_id);
-            });
-        }
+fn struct_align(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(struct_layout(recv)?.align as f64))
+}
 
-        inst_guard
-            .fields
-            .insert("_released".to_string(), Value::Boolean(true));
-        return Ok(Value::Null);
+fn struct_offset_of(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let layout = struct_layout(recv)?;
+    let name = match args.first() {
+        Some(Value::String(s)) => s,
+        _ => return Err("Struct.offsetOf expects a field name".to_string()),
+    };
+    layout
+        .fields
+        .iter()
+        .find(|f| f.name == **name)
+        .map(|f| Value::Number(f.offset as f64))
+        .ok_or_else(|| format!("Struct has no field '{}'", name))
+}
+
+fn struct_fields(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    let names = struct_layout(recv)?
+        .fields
+        .iter()
+        .map(|f| Value::String(Rc::from(f.name.as_str())))
+        .collect();
+    Ok(Value::Array(Rc::new(RefCell::new(names))))
+}
+
+fn struct_alloc(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    let layout = struct_layout(recv)?;
+    let ptr = ffi_alloc(&[Value::Number(layout.size as f64)])?;
+    ffi_memset(&[
+        ptr.clone(),
+        Value::Number(0.0),
+        Value::Number(layout.size as f64),
+    ])?;
+    Ok(ptr)
+}
+
+fn struct_read(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let layout = struct_layout(recv)?;
+    let ptr = struct_pointer(args, "read")?;
+    Ok(unsafe { read_ctype(ptr, &CType::Struct(layout)) })
+}
+
+fn struct_write(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let layout = struct_layout(recv)?;
+    let ptr = struct_pointer(args, "write")?;
+    let value = args
+        .get(1)
+        .ok_or("Struct.write expects 2 arguments (pointer, dict)")?;
+    let mut keep = Vec::new();
+    unsafe { write_ctype(ptr, &CType::Struct(layout), value, &mut keep)? };
+    if !keep.is_empty() {
+        return Err(
+            "Struct.write cannot store strings; write them with Ffi.alloc + writeString"
+                .to_string(),
+        );
+    }
+    Ok(Value::Null)
+}
+
+fn create_owned_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("ptr".to_string(), owned_ptr);
+    instance_methods.insert("free".to_string(), owned_free);
+    instance_methods.insert("release".to_string(), owned_release);
+
+    let mut class = Class::new_with_instance("Owned", instance_methods, Some(owned_new));
+
+    class
+        .native_static_methods
+        .insert("new".to_string(), owned_new);
+
+    class
+}
+
+fn owned_new(args: &[Value]) -> Result<Value, String> {
+    let ptr = match args.first() {
+        Some(Value::Number(n)) => *n as usize,
+        Some(Value::Null) => 0,
+        _ => return Err("Ffi.Owned expects a pointer".to_string()),
+    };
+    let free_fn = match args.get(1) {
+        Some(Value::Number(n)) if *n != 0.0 => *n as usize,
+        Some(Value::Number(_)) => return Err("Free function cannot be null".to_string()),
+        Some(Value::Null) | None => free as *const () as usize,
+        Some(_) => return Err("Free function must be a symbol pointer".to_string()),
+    };
+    Ok(own_pointer(OwnedPtr { ptr, free_fn }))
+}
+
+fn take_owned(recv: &Value) -> Result<Option<OwnedPtr>, String> {
+    let id = handle_id(recv, "Owned").ok_or("Receiver must be an Owned pointer")?;
+    Ok(OWNED.with(|owned| owned.borrow_mut().remove(&id)))
+}
+
+fn owned_ptr(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    match pointer_of(recv) {
+        Some(ptr) => Ok(Value::Number(ptr as f64)),
+        None => Err("Owned pointer has been freed or released".to_string()),
+    }
+}
+
+fn owned_free(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    if let Some(owned) = take_owned(recv)? {
+        owned.release();
+    }
+    Ok(Value::Null)
+}
+
+fn owned_release(recv: &Value, _args: &[Value]) -> Result<Value, String> {
+    match take_owned(recv)? {
+        Some(owned) => Ok(Value::Number(owned.ptr as f64)),
+        None => Err("Owned pointer has been freed or released".to_string()),
     }
-    Err("Invalid callback instance".to_string())
 }
 
 unsafe fn call_with_types(
     func_ptr: *const c_void,
     values: &[Value],
     arg_types: &[CType],
+    transfers: &[bool],
     return_type: &CType,
 ) -> Result<Value, String> {
     if values.len() != arg_types.len() {
@@ -1332,40 +1947,61 @@ unsafe fn call_with_types(
         ));
     }
 
-    let mut cstrings: Vec<CString> = Vec::new();
-    for (val, typ) in values.iter().zip(arg_types.iter()) {
-        if *typ == CType::CString {
-            if let Value::String(s) = val {
-                let c_str =
-                    CString::new(&**s).map_err(|_| "Invalid C string (contains null byte)")?;
-                cstrings.push(c_str);
-            }
-        }
-    }
-
-    let mut cstring_idx = 0;
+    let mut keep: Vec<CString> = Vec::new();
     let mut converted_args: Vec<ConvertedArg> = Vec::new();
     for (idx, (val, typ)) in values.iter().zip(arg_types.iter()).enumerate() {
-        let converted = match val {
-            Value::Instance(inst) => {
-                let inst_guard = inst.borrow();
-                if let Some(Value::Number(id)) = inst_guard.fields.get("_id") {
-                    ConvertedArg {
-                        ffi_type: FfiType::u64(),
-                        data: ConvertedData::I64(*id as i64),
-                    }
-                } else {
-                    return Err(format!("args[{}]: Invalid callback instance", idx));
+        let transfer = transfers.get(idx).copied().unwrap_or(false);
+        let converted = match (val, typ) {
+            (_, CType::Struct(layout)) => {
+                let mut buffer = struct_buffer(layout.size);
+                write_ctype(buffer.as_mut_ptr() as *mut u8, typ, val, &mut keep)
+                    .map_err(|e| format!("args[{}]: {}", idx, e))?;
+                ConvertedArg {
+                    ffi_type: typ.to_ffi_type(),
+                    data: ConvertedData::Struct(buffer),
                 }
             }
-            Value::String(_) if *typ == CType::CString => {
-                let ptr = cstrings[cstring_idx].as_ptr() as usize;
-                cstring_idx += 1;
+            (Value::String(s), CType::CString) => {
+                let c_str =
+                    CString::new(&**s).map_err(|_| "Invalid C string (contains null byte)")?;
+                let ptr = if transfer {
+                    // The callee frees it, so it must come from the C allocator
+                    let bytes = c_str.as_bytes_with_nul();
+                    let copy = malloc(bytes.len()) as *mut u8;
+                    if copy.is_null() {
+                        return Err("Memory allocation failed".to_string());
+                    }
+                    ptr::copy_nonoverlapping(bytes.as_ptr(), copy, bytes.len());
+                    copy as usize
+                } else {
+                    let ptr = c_str.as_ptr() as usize;
+                    keep.push(c_str);
+                    ptr
+                };
                 ConvertedArg {
                     ffi_type: typ.to_ffi_type(),
                     data: ConvertedData::Ptr(ptr),
                 }
             }
+            (Value::Instance(_), CType::Pointer | CType::CString) => ConvertedArg {
+                ffi_type: typ.to_ffi_type(),
+                data: ConvertedData::Ptr(pointer_of(val).ok_or_else(|| {
+                    format!("args[{}]: Invalid or released pointer instance", idx)
+                })?),
+            },
+            (Value::Instance(inst), _) => {
+                let id = inst
+                    .borrow()
+                    .native::<CallbackHandle>()
+                    .map(|handle| handle.borrow().id);
+                match id {
+                    Some(id) => ConvertedArg {
+                        ffi_type: FfiType::u64(),
+                        data: ConvertedData::I64(id),
+                    },
+                    None => return Err(format!("args[{}]: Invalid callback instance", idx)),
+                }
+            }
             _ => convert_value_to_arg(val, typ)?,
         };
         converted_args.push(converted);
@@ -1388,6 +2024,7 @@ unsafe fn call_with_types(
             ConvertedData::F32(v) => Arg::new(v),
             ConvertedData::F64(v) => Arg::new(v),
             ConvertedData::Ptr(v) => Arg::new(v),
+            ConvertedData::Struct(buffer) => Arg::new(&buffer[0]),
         };
         ffi_args.push(arg_ref);
     }
@@ -1444,7 +2081,128 @@ unsafe fn call_with_types(
             let r: usize = cif.call(code_ptr, &ffi_args);
             Value::Number(r as f64)
         }
+        CType::Struct(layout) => {
+            let mut buffer = struct_buffer(layout.size);
+            libffi::raw::ffi_call(
+                cif.as_raw_ptr(),
+                Some(*code_ptr.as_fun()),
+                buffer.as_mut_ptr() as *mut c_void,
+                ffi_args.as_ptr() as *mut *mut c_void,
+            );
+            read_ctype(buffer.as_ptr() as *const u8, return_type)
+        }
     };
 
+    // Ownership moved to the callee, so these must not be freed by us
+    for (idx, val) in values.iter().enumerate() {
+        if transfers.get(idx).copied().unwrap_or(false) {
+            if let Some(id) = handle_id(val, "Owned") {
+                OWNED.with(|owned| owned.borrow_mut().remove(&id));
+            }
+        }
+    }
+
     Ok(result)
 }
+
+/// Applies `owner: "caller"` to a returned pointer: strings are copied out
+/// and freed immediately, other pointers become an `Ffi.Owned`.
+fn take_ownership(ptr: usize, free_fn: usize, return_type: &CType) -> Value {
+    if ptr == 0 {
+        return Value::Null;
+    }
+    let owned = OwnedPtr { ptr, free_fn };
+    if *return_type == CType::CString {
+        let s = unsafe { CStr::from_ptr(ptr as *const std::os::raw::c_char) }
+            .to_string_lossy()
+            .to_string();
+        owned.release();
+        return Value::String(Rc::from(s));
+    }
+    own_pointer(owned)
+}
+
+fn own_pointer(owned: OwnedPtr) -> Value {
+    let id = next_handle();
+    OWNED.with(|map| map.borrow_mut().insert(id, owned));
+    guarded_instance(create_owned_class(), id, Box::new(OwnedGuard(id)))
+}
+
+/// True when `owner` says the caller (Sald) is responsible for freeing
+fn parse_owner(value: Option<&Value>) -> Result<bool, String> {
+    match value {
+        None => Ok(false),
+        Some(Value::String(s)) if &**s == "caller" => Ok(true),
+        Some(Value::String(s)) if &**s == "callee" => Ok(false),
+        Some(_) => Err("'owner' must be \"caller\" or \"callee\"".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_ffi_structs_callbacks_and_ownership() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let libc = Ffi.open(\"libc.so.6\")\n\
+                 let Div = Ffi.Struct([[\"quot\", \"int\"], [\"rem\", \"int\"]])\n\
+                 let Mixed = Ffi.Struct([[\"a\", \"u8\"], [\"b\", \"double\"], [\"c\", Div]])",
+            )
+            .unwrap();
+        engine
+            .eval(
+                "let r = libc.call(\"div\", {\"args\": [{\"type\": \"int\", \"value\": 17}, \
+                 {\"type\": \"int\", \"value\": 5}], \"returns\": Div})",
+            )
+            .unwrap();
+        let div: Vec<f64> = engine
+            .eval_as("[r[\"quot\"], r[\"rem\"], Mixed.size(), Mixed.offsetOf(\"c\")]")
+            .unwrap();
+        assert_eq!(div, [3.0, 2.0, 24.0, 16.0]);
+
+        engine
+            .eval(
+                "let buf = Ffi.alloc(12)\n\
+                 Ffi.writeI32(buf, 3)\n\
+                 Ffi.writeI32(Ffi.offset(buf, 4), 1)\n\
+                 Ffi.writeI32(Ffi.offset(buf, 8), 2)\n\
+                 let cmp = Ffi.Callback({\"args\": [\"pointer\", \"pointer\"], \"returns\": \"int\", \
+                 \"fn\": |a, b| Ffi.readI32(a) - Ffi.readI32(b)})\n\
+                 libc.call(\"qsort\", {\"args\": [{\"type\": \"pointer\", \"value\": buf}, \
+                 {\"type\": \"size_t\", \"value\": 3}, {\"type\": \"size_t\", \"value\": 4}, \
+                 {\"type\": \"callback\", \"value\": cmp}]})",
+            )
+            .unwrap();
+        let sorted: Vec<f64> = engine
+            .eval_as(
+                "[Ffi.readI32(buf), Ffi.readI32(Ffi.offset(buf, 4)), Ffi.readI32(Ffi.offset(buf, 8))]",
+            )
+            .unwrap();
+        assert_eq!(sorted, [1.0, 2.0, 3.0]);
+        let cmp_id: f64 = engine.eval_as("cmp.id()").unwrap();
+        assert!(cmp_id >= 0.0);
+        engine.eval("cmp.release()\ncmp.release()").unwrap();
+        assert!(engine.eval("cmp.ptr()").is_err());
+        assert!(engine.eval("cmp._released = false\ncmp.ptr()").is_err());
+
+        let copied: String = engine
+            .eval_as(
+                "libc.call(\"strdup\", {\"args\": [{\"type\": \"string\", \"value\": \"hello\"}], \
+                 \"returns\": \"string\", \"owner\": \"caller\"})",
+            )
+            .unwrap();
+        assert_eq!(copied, "hello");
+        engine
+            .eval(
+                "let m = libc.call(\"malloc\", {\"args\": [{\"type\": \"size_t\", \"value\": 8}], \
+                 \"returns\": \"pointer\", \"owner\": \"caller\"})\n\
+                 m.free()",
+            )
+            .unwrap();
+        assert!(engine.eval("m.ptr()").is_err());
+    }
+}
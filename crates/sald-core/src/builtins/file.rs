@@ -1,12 +1,15 @@
-use super::{check_arity, get_string_arg};
+use super::date::{make_date, DateKind};
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, get_string_arg};
 use crate::vm::value::{Class, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 use std::path::Path;
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn resolve_path(path: &str) -> String {
-    crate::resolve_script_path(path)
+    crate::workspace::resolve(path)
         .to_string_lossy()
         .to_string()
 }
@@ -18,16 +21,26 @@ pub fn create_file_class() -> Class {
     static_methods.insert("write".to_string(), file_write);
     static_methods.insert("append".to_string(), file_append);
     static_methods.insert("readDir".to_string(), file_read_dir);
+    static_methods.insert("openRead".to_string(), super::file_stream::file_open_read);
+    static_methods.insert("openWrite".to_string(), super::file_stream::file_open_write);
 
     static_methods.insert("exists".to_string(), file_exists);
     static_methods.insert("isFile".to_string(), file_is_file);
     static_methods.insert("isDir".to_string(), file_is_dir);
     static_methods.insert("size".to_string(), file_size);
+    static_methods.insert("stat".to_string(), file_stat);
+    static_methods.insert("chmod".to_string(), file_chmod);
 
     static_methods.insert("delete".to_string(), file_delete);
     static_methods.insert("copy".to_string(), file_copy);
     static_methods.insert("rename".to_string(), file_rename);
+    static_methods.insert("replace".to_string(), file_replace);
+    static_methods.insert("symlink".to_string(), file_symlink);
+    static_methods.insert("readlink".to_string(), file_readlink);
+    static_methods.insert("hardlink".to_string(), file_hardlink);
     static_methods.insert("mkdir".to_string(), file_mkdir);
+    static_methods.insert("tempFile".to_string(), super::temp::file_temp_file);
+    static_methods.insert("tempDir".to_string(), super::temp::file_temp_dir);
 
     static_methods.insert("join".to_string(), file_join);
     static_methods.insert("dirname".to_string(), file_dirname);
@@ -36,9 +49,81 @@ pub fn create_file_class() -> Class {
 
     let mut class = Class::new("File");
     class.native_static_methods = static_methods;
+    class.named_arg_methods.insert("openWrite".to_string());
     class
 }
 
+/// API documentation for the `File` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("File", "File system operations (async)")
+        .method("read", "await read(path)", "Read file contents")
+        .method("write", "await write(path, content)", "Write to file")
+        .method("append", "await append(path, content)", "Append to file")
+        .method(
+            "openRead",
+            "openRead(path)",
+            "Open a FileStream for chunked reading",
+        )
+        .method(
+            "openWrite",
+            "openWrite(path, append?)",
+            "Open a FileStream for writing, truncating unless append is true, as in openWrite(path, append: true)",
+        )
+        .method("exists", "await exists(path)", "Check if path exists")
+        .method("isFile", "await isFile(path)", "Check if path is file")
+        .method("isDir", "await isDir(path)", "Check if path is directory")
+        .method("size", "await size(path)", "Get file size in bytes")
+        .method(
+            "stat",
+            "stat(path)",
+            "Get a dict with size, mtime, atime, created, mode, isFile, isDir and isSymlink",
+        )
+        .method(
+            "chmod",
+            "chmod(path, mode)",
+            "Set permission bits, e.g. 0o644",
+        )
+        .method("delete", "await delete(path)", "Delete file or empty dir")
+        .method(
+            "copy",
+            "await copy(src, dst, keepTimes?)",
+            "Copy file with its permissions, and its timestamps if keepTimes",
+        )
+        .method("rename", "await rename(old, new)", "Rename/move file")
+        .method(
+            "replace",
+            "replace(path, content)",
+            "Atomically replace a file's contents through a temporary file",
+        )
+        .method(
+            "symlink",
+            "symlink(target, link)",
+            "Create a symbolic link at link pointing to target",
+        )
+        .method(
+            "readlink",
+            "readlink(path)",
+            "Get the target of a symbolic link",
+        )
+        .method("hardlink", "hardlink(src, dst)", "Create a hard link")
+        .method("mkdir", "await mkdir(path)", "Create directory")
+        .method(
+            "tempFile",
+            "tempFile(prefix?, suffix?)",
+            "Create an empty TempPath file in the system temp directory",
+        )
+        .method(
+            "tempDir",
+            "tempDir(prefix?)",
+            "Create a TempPath directory in the system temp directory",
+        )
+        .method("readDir", "await readDir(path)", "List directory contents")
+        .method("join", "join(...parts)", "Join path components")
+        .method("dirname", "dirname(path)", "Get directory name")
+        .method("basename", "basename(path)", "Get file name")
+        .method("ext", "ext(path)", "Get file extension")
+}
+
 fn file_read(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     let path = resolve_path(&get_string_arg(&args[0], "path")?);
@@ -124,15 +209,113 @@ fn file_delete(args: &[Value]) -> Result<Value, String> {
     Ok(Value::Boolean(true))
 }
 
-fn file_copy(args: &[Value]) -> Result<Value, String> {
+fn time_value(time: std::io::Result<SystemTime>) -> Value {
+    match time.map(|time| time.duration_since(UNIX_EPOCH)) {
+        Ok(Ok(since)) => make_date(since.as_secs_f64(), DateKind::Date),
+        _ => Value::Null,
+    }
+}
+
+#[cfg(unix)]
+fn mode_of(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+/// Windows only tracks read-only, so report the usual equivalent modes.
+#[cfg(not(unix))]
+fn mode_of(meta: &std::fs::Metadata) -> u32 {
+    match (meta.permissions().readonly(), meta.is_dir()) {
+        (true, true) => 0o555,
+        (true, false) => 0o444,
+        (false, true) => 0o755,
+        (false, false) => 0o644,
+    }
+}
+
+fn file_stat(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = resolve_path(&get_string_arg(&args[0], "path")?);
+
+    let link = std::fs::symlink_metadata(&path)
+        .map_err(|e| format!("Failed to stat '{}': {}", path, e))?;
+    // A dangling link still has metadata of its own
+    let meta = std::fs::metadata(&path).unwrap_or_else(|_| link.clone());
+
+    let mut dict = FxHashMap::default();
+    dict.insert("size".to_string(), Value::Number(meta.len() as f64));
+    dict.insert("mtime".to_string(), time_value(meta.modified()));
+    dict.insert("atime".to_string(), time_value(meta.accessed()));
+    dict.insert("created".to_string(), time_value(meta.created()));
+    dict.insert("mode".to_string(), Value::Number(mode_of(&meta) as f64));
+    dict.insert("isFile".to_string(), Value::Boolean(meta.is_file()));
+    dict.insert("isDir".to_string(), Value::Boolean(meta.is_dir()));
+    dict.insert(
+        "isSymlink".to_string(),
+        Value::Boolean(link.file_type().is_symlink()),
+    );
+    dict.insert(
+        "readonly".to_string(),
+        Value::Boolean(meta.permissions().readonly()),
+    );
+    Ok(Value::Dictionary(Rc::new(RefCell::new(dict.into()))))
+}
+
+fn file_chmod(args: &[Value]) -> Result<Value, String> {
     check_arity(2, args.len())?;
+    let path = resolve_path(&get_string_arg(&args[0], "path")?);
+    let mode = get_number_arg(&args[1], "mode")?;
+    if !(0.0..=4095.0).contains(&mode) || mode.fract() != 0.0 {
+        return Err(format!("Invalid file mode {}", mode));
+    }
+
+    #[cfg(unix)]
+    let permissions = {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::Permissions::from_mode(mode as u32)
+    };
+    #[cfg(not(unix))]
+    let permissions = {
+        let mut permissions = std::fs::metadata(&path)
+            .map_err(|e| format!("Failed to chmod '{}': {}", path, e))?
+            .permissions();
+        permissions.set_readonly(mode as u32 & 0o200 == 0);
+        permissions
+    };
+
+    std::fs::set_permissions(&path, permissions)
+        .map_err(|e| format!("Failed to chmod '{}': {}", path, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_copy(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
     let src = resolve_path(&get_string_arg(&args[0], "src")?);
     let dst = resolve_path(&get_string_arg(&args[1], "dst")?);
-
-    match std::fs::copy(&src, &dst) {
-        Ok(bytes) => Ok(Value::Number(bytes as f64)),
-        Err(e) => Err(format!("Failed to copy '{}' to '{}': {}", src, dst, e)),
+    let keep_times = matches!(args.get(2), Some(Value::Boolean(true)));
+
+    // std::fs::copy carries the permission bits over itself
+    let bytes = std::fs::copy(&src, &dst)
+        .map_err(|e| format!("Failed to copy '{}' to '{}': {}", src, dst, e))?;
+
+    if keep_times {
+        let meta =
+            std::fs::metadata(&src).map_err(|e| format!("Failed to stat '{}': {}", src, e))?;
+        let mut times = std::fs::FileTimes::new();
+        if let Ok(modified) = meta.modified() {
+            times = times.set_modified(modified);
+        }
+        if let Ok(accessed) = meta.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        std::fs::File::options()
+            .write(true)
+            .open(&dst)
+            .and_then(|file| file.set_times(times))
+            .map_err(|e| format!("Failed to set times on '{}': {}", dst, e))?;
     }
+
+    Ok(Value::Number(bytes as f64))
 }
 
 fn file_rename(args: &[Value]) -> Result<Value, String> {
@@ -146,6 +329,89 @@ fn file_rename(args: &[Value]) -> Result<Value, String> {
     }
 }
 
+fn file_replace(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let path = resolve_path(&get_string_arg(&args[0], "path")?);
+    let content = format!("{}", args[1]);
+
+    use std::io::Write;
+
+    // The temporary file sits next to the target so the rename never
+    // crosses file systems, which is what makes it atomic
+    let target = Path::new(&path);
+    let name = target
+        .file_name()
+        .ok_or_else(|| format!("Cannot replace '{}': not a file path", path))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = target.with_file_name(temp_name);
+
+    let result = std::fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            if let Ok(meta) = std::fs::metadata(target) {
+                file.set_permissions(meta.permissions())?;
+            }
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp, target));
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to replace '{}': {}", path, e));
+    }
+
+    Ok(Value::Boolean(true))
+}
+
+fn file_symlink(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    // The target is stored as given, relative to the link's directory
+    let target = get_string_arg(&args[0], "target")?;
+    let link = resolve_path(&get_string_arg(&args[1], "link")?);
+
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(&target, &link);
+    #[cfg(windows)]
+    let result = {
+        let resolved = Path::new(&link)
+            .parent()
+            .map(|dir| dir.join(&target))
+            .unwrap_or_else(|| target.clone().into());
+        if resolved.is_dir() {
+            std::os::windows::fs::symlink_dir(&target, &link)
+        } else {
+            std::os::windows::fs::symlink_file(&target, &link)
+        }
+    };
+
+    result.map_err(|e| format!("Failed to link '{}' to '{}': {}", link, target, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_readlink(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = resolve_path(&get_string_arg(&args[0], "path")?);
+
+    match std::fs::read_link(&path) {
+        Ok(target) => Ok(Value::String(Rc::from(
+            target.to_string_lossy().to_string(),
+        ))),
+        Err(e) => Err(format!("Failed to read link '{}': {}", path, e)),
+    }
+}
+
+fn file_hardlink(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let src = resolve_path(&get_string_arg(&args[0], "src")?);
+    let dst = resolve_path(&get_string_arg(&args[1], "dst")?);
+
+    match std::fs::hard_link(&src, &dst) {
+        Ok(_) => Ok(Value::Boolean(true)),
+        Err(e) => Err(format!("Failed to link '{}' to '{}': {}", dst, src, e)),
+    }
+}
+
 fn file_mkdir(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     let path = resolve_path(&get_string_arg(&args[0], "path")?);
@@ -170,7 +436,7 @@ fn file_read_dir(args: &[Value]) -> Result<Value, String> {
         }
     }
 
-    Ok(Value::Array(Rc::new(RefCell::new(items))))
+    Ok(Value::Array(Rc::new(RefCell::new(items.into()))))
 }
 
 fn file_join(args: &[Value]) -> Result<Value, String> {
@@ -218,3 +484,49 @@ fn file_ext(args: &[Value]) -> Result<Value, String> {
         None => Ok(Value::String(Rc::from(String::new()))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_metadata_and_links() {
+        let dir = std::env::temp_dir().join(format!("sald-meta-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let root = dir.to_str().unwrap();
+
+        let mut engine = Engine::new();
+        engine
+            .eval(&format!(
+                "let root = \"{}\"\n\
+                 File.write(root + \"/a.txt\", \"hello\")\n\
+                 File.chmod(root + \"/a.txt\", 0o640)\n\
+                 File.copy(root + \"/a.txt\", root + \"/b.txt\", true)\n\
+                 File.symlink(\"a.txt\", root + \"/link\")\n\
+                 File.replace(root + \"/a.txt\", \"replaced\")\n\
+                 let link = File.stat(root + \"/link\")\n\
+                 let copy = File.stat(root + \"/b.txt\")",
+                root
+            ))
+            .unwrap();
+        let result: (f64, bool, String, f64, f64, String) = engine
+            .eval_as(
+                "[link[\"size\"], link[\"isSymlink\"], File.readlink(root + \"/link\"),\n\
+                  link[\"mode\"], copy[\"mode\"], File.read(root + \"/link\")]",
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            (
+                8.0,
+                true,
+                "a.txt".to_string(),
+                0o640 as f64,
+                0o640 as f64,
+                "replaced".to_string()
+            )
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
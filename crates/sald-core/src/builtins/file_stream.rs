@@ -0,0 +1,442 @@
+//! File streams
+//! `File.openRead` and `File.openWrite` return a `FileStream` that reads and
+//! writes in chunks, so large files never have to fit in memory. An open file
+//! lives in the native handle of its instance and is closed with it
+
+use super::docs::ClassDoc;
+use super::{
+    bytes_to_value, check_arity, check_arity_range, get_bytes_arg, get_number_arg, get_string_arg,
+    native_instance, native_state, NamedArgs,
+};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{Class, Instance, NativeInstanceFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+
+/// Read size used when `read` is called without one
+const CHUNK_SIZE: usize = 64 * 1024;
+
+enum Stream {
+    Reader(BufReader<File>),
+    Writer(BufWriter<File>),
+}
+
+/// State of a `FileStream` and its `FileLines`, `None` once closed
+type OpenStream = Option<Stream>;
+
+fn create_file_stream_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("read".to_string(), stream_read);
+    instance_methods.insert("readBytes".to_string(), stream_read_bytes);
+    instance_methods.insert("readLine".to_string(), stream_read_line);
+    instance_methods.insert("readLines".to_string(), stream_read_lines);
+    instance_methods.insert("write".to_string(), stream_write);
+    instance_methods.insert("flush".to_string(), stream_flush);
+    instance_methods.insert("seek".to_string(), stream_seek);
+    instance_methods.insert("position".to_string(), stream_position);
+    instance_methods.insert("truncate".to_string(), stream_truncate);
+    instance_methods.insert("size".to_string(), stream_size);
+    instance_methods.insert("close".to_string(), stream_close);
+
+    Class::new_with_instance("FileStream", instance_methods, None)
+}
+
+fn create_file_lines_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("next".to_string(), lines_next);
+    instance_methods.insert("close".to_string(), stream_close);
+    callable_methods.insert("forEach".to_string(), lines_for_each);
+
+    let mut class = Class::new_with_instance("FileLines", instance_methods, None);
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `FileStream` and `FileLines` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new(
+            "FileStream",
+            "Open file returned by File.openRead and File.openWrite",
+        )
+        .method(
+            "read",
+            "read(size?)",
+            "Read up to size bytes as text, or null at the end",
+        )
+        .method(
+            "readBytes",
+            "readBytes(size?)",
+            "Read up to size bytes as a byte array, or null at the end",
+        )
+        .method(
+            "readLine",
+            "readLine()",
+            "Next line without its ending, or null at the end",
+        )
+        .method(
+            "readLines",
+            "readLines()",
+            "FileLines reader over the remaining lines",
+        )
+        .method(
+            "write",
+            "write(data)",
+            "Write a string or byte array, returns the bytes written",
+        )
+        .method("flush", "flush()", "Flush buffered writes")
+        .method(
+            "seek",
+            "seek(offset, from?)",
+            "Move to offset from \"start\", \"current\" or \"end\", returns the position",
+        )
+        .method("position", "position()", "Current byte offset")
+        .method(
+            "truncate",
+            "truncate(size?)",
+            "Cut a writable file to size bytes, 0 by default",
+        )
+        .method("size", "size()", "File size in bytes")
+        .method("close", "close()", "Flush and close the file"),
+        ClassDoc::new("FileLines", "Line reader returned by FileStream.readLines")
+            .method("next", "next()", "Next line, or null at the end")
+            .method("forEach", "forEach(fn)", "Call fn with each remaining line")
+            .method("close", "close()", "Close the file"),
+    ]
+}
+
+fn register(stream: Stream) -> Value {
+    native_instance(create_file_stream_class(), Some(stream) as OpenStream)
+}
+
+pub(crate) fn file_open_read(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = crate::workspace::resolve(&get_string_arg(&args[0], "path")?);
+    let file = File::open(&path)
+        .map_err(|e| format!("Failed to open file '{}': {}", path.display(), e))?;
+    Ok(register(Stream::Reader(BufReader::new(file))))
+}
+
+pub(crate) fn file_open_write(args: &[Value]) -> Result<Value, String> {
+    let (args, named) = NamedArgs::split(args, &["append"])?;
+    check_arity_range(1, 2, args.len())?;
+    let path = crate::workspace::resolve(&get_string_arg(&args[0], "path")?);
+    let append = match named.get(args, 1, "append")? {
+        None | Some(Value::Null) => false,
+        Some(Value::Boolean(append)) => append,
+        Some(other) => {
+            return Err(format!(
+                "Argument 'append' must be a boolean, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .map_err(|e| format!("Failed to open file '{}': {}", path.display(), e))?;
+    Ok(register(Stream::Writer(BufWriter::new(file))))
+}
+
+fn stream_state(recv: &Value) -> Result<Rc<RefCell<OpenStream>>, String> {
+    native_state(recv, "FileStream")
+}
+
+fn with_stream<T>(
+    recv: &Value,
+    f: impl FnOnce(&mut Stream) -> Result<T, String>,
+) -> Result<T, String> {
+    match stream_state(recv)?.borrow_mut().as_mut() {
+        Some(stream) => f(stream),
+        None => Err("FileStream is closed".to_string()),
+    }
+}
+
+fn with_reader<T>(
+    recv: &Value,
+    f: impl FnOnce(&mut BufReader<File>) -> std::io::Result<T>,
+) -> Result<T, String> {
+    with_stream(recv, |stream| match stream {
+        Stream::Reader(reader) => f(reader).map_err(|e| format!("Read error: {}", e)),
+        Stream::Writer(_) => Err("FileStream was opened for writing".to_string()),
+    })
+}
+
+fn chunk_size(args: &[Value]) -> Result<usize, String> {
+    check_arity_range(0, 1, args.len())?;
+    match args.first() {
+        None | Some(Value::Null) => Ok(CHUNK_SIZE),
+        Some(value) => {
+            let size = get_number_arg(value, "size")?;
+            if size < 1.0 {
+                return Err("Argument 'size' must be at least 1".to_string());
+            }
+            Ok(size as usize)
+        }
+    }
+}
+
+fn read_chunk(reader: &mut BufReader<File>, size: usize) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(size.min(CHUNK_SIZE));
+    reader.by_ref().take(size as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+fn stream_read(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let size = chunk_size(args)?;
+    let bytes = with_reader(recv, |reader| {
+        let mut bytes = read_chunk(reader, size)?;
+        // Finish a character split by the chunk boundary
+        for _ in 0..3 {
+            match std::str::from_utf8(&bytes) {
+                Err(e) if e.error_len().is_none() => {
+                    let mut byte = [0u8];
+                    if reader.read(&mut byte)? == 0 {
+                        break;
+                    }
+                    bytes.push(byte[0]);
+                }
+                _ => break,
+            }
+        }
+        Ok(bytes)
+    })?;
+    if bytes.is_empty() {
+        return Ok(Value::Null);
+    }
+    String::from_utf8(bytes)
+        .map(|text| Value::String(Rc::from(text)))
+        .map_err(|_| "File is not valid UTF-8, use readBytes".to_string())
+}
+
+fn stream_read_bytes(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let size = chunk_size(args)?;
+    let bytes = with_reader(recv, |reader| read_chunk(reader, size))?;
+    if bytes.is_empty() {
+        return Ok(Value::Null);
+    }
+    Ok(bytes_to_value(&bytes))
+}
+
+fn next_line(recv: &Value) -> Result<Value, String> {
+    let mut line = String::new();
+    let read = with_reader(recv, |reader| reader.read_line(&mut line))?;
+    if read == 0 {
+        return Ok(Value::Null);
+    }
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+    Ok(Value::String(Rc::from(line)))
+}
+
+fn stream_read_line(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    next_line(recv)
+}
+
+fn stream_read_lines(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    with_reader(recv, |_| Ok(()))?;
+    // The lines share the stream, so reading either advances both
+    let mut lines = Instance::new(Rc::new(create_file_lines_class()));
+    if let Value::Instance(inst) = recv {
+        lines.native = inst.borrow().native.clone();
+    }
+    Ok(Value::Instance(Rc::new(RefCell::new(lines))))
+}
+
+fn lines_next(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    next_line(recv)
+}
+
+fn lines_for_each(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    loop {
+        let line = next_line(recv)?;
+        if matches!(line, Value::Null) {
+            return Ok(Value::Null);
+        }
+        caller.call(&args[0], vec![line])?;
+    }
+}
+
+fn stream_write(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let data = match &args[0] {
+        Value::Array(_) => get_bytes_arg(&args[0], "data")?,
+        other => other.to_string().into_bytes(),
+    };
+    with_stream(recv, |stream| match stream {
+        Stream::Writer(writer) => writer
+            .write_all(&data)
+            .map_err(|e| format!("Write error: {}", e)),
+        Stream::Reader(_) => Err("FileStream was opened for reading".to_string()),
+    })?;
+    Ok(Value::Number(data.len() as f64))
+}
+
+fn stream_flush(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    with_stream(recv, |stream| match stream {
+        Stream::Writer(writer) => writer.flush().map_err(|e| format!("Write error: {}", e)),
+        Stream::Reader(_) => Ok(()),
+    })?;
+    Ok(Value::Null)
+}
+
+fn stream_seek(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let offset = get_number_arg(&args[0], "offset")? as i64;
+    let from = match args.get(1) {
+        None | Some(Value::Null) => "start".to_string(),
+        Some(value) => get_string_arg(value, "from")?,
+    };
+    let target = match from.as_str() {
+        "start" if offset >= 0 => SeekFrom::Start(offset as u64),
+        "start" => return Err("Cannot seek before the start of the file".to_string()),
+        "current" => SeekFrom::Current(offset),
+        "end" => SeekFrom::End(offset),
+        other => {
+            return Err(format!(
+                "Unknown seek origin '{}', expected start, current or end",
+                other
+            ))
+        }
+    };
+    let position = with_stream(recv, |stream| {
+        match stream {
+            Stream::Reader(reader) => reader.seek(target),
+            Stream::Writer(writer) => writer.seek(target),
+        }
+        .map_err(|e| format!("Seek error: {}", e))
+    })?;
+    Ok(Value::Number(position as f64))
+}
+
+fn stream_position(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let position = with_stream(recv, |stream| {
+        match stream {
+            Stream::Reader(reader) => reader.stream_position(),
+            Stream::Writer(writer) => writer.stream_position(),
+        }
+        .map_err(|e| format!("Seek error: {}", e))
+    })?;
+    Ok(Value::Number(position as f64))
+}
+
+fn stream_truncate(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let size = match args.first() {
+        None | Some(Value::Null) => 0,
+        Some(value) => get_number_arg(value, "size")?.max(0.0) as u64,
+    };
+    with_stream(recv, |stream| match stream {
+        Stream::Writer(writer) => writer
+            .flush()
+            .and_then(|_| writer.get_ref().set_len(size))
+            .map_err(|e| format!("Truncate error: {}", e)),
+        Stream::Reader(_) => Err("FileStream was opened for reading".to_string()),
+    })?;
+    Ok(Value::Null)
+}
+
+fn stream_size(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let size = with_stream(recv, |stream| {
+        match stream {
+            Stream::Reader(reader) => reader.get_ref().metadata(),
+            Stream::Writer(writer) => writer.flush().and_then(|_| writer.get_ref().metadata()),
+        }
+        .map(|meta| meta.len())
+        .map_err(|e| format!("Cannot read file size: {}", e))
+    })?;
+    Ok(Value::Number(size as f64))
+}
+
+fn stream_close(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let stream = stream_state(recv)?.borrow_mut().take();
+    if let Some(Stream::Writer(mut writer)) = stream {
+        writer.flush().map_err(|e| format!("Write error: {}", e))?;
+    }
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_file_streams() {
+        let path = std::env::temp_dir().join(format!("sald-stream-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap().replace('\\', "/");
+
+        let mut engine = Engine::new();
+        engine
+            .eval(&format!(
+                "let path = \"{}\"\n\
+                 let out = File.openWrite(path)\n\
+                 for i in 1..500 {{ out.write(\"line \" + i + \"\\n\") }}\n\
+                 out.close()\n\
+                 let more = File.openWrite(path, true)\n\
+                 more.write(\"h\u{e9}llo\\r\\n\")\n\
+                 more.close()\n\
+                 let input = File.openRead(path)\n\
+                 let first = input.readLine()\n\
+                 let lines = []\n\
+                 input.readLines().forEach(|line| lines.push(line))\n\
+                 input.seek(-7, \"end\")\n\
+                 let tail = input.read()\n\
+                 let done = input.read(4)\n\
+                 input.seek(0)\n\
+                 let head = input.readBytes(4)\n\
+                 input.close()\n\
+                 let cut = File.openWrite(path, true)\n\
+                 cut.truncate(6)\n\
+                 let size = cut.size()\n\
+                 cut.close()",
+                path
+            ))
+            .unwrap();
+        let result: (String, f64, String, String, bool, f64, f64, String) = engine
+            .eval_as(
+                "[first, lines.length(), lines[498], tail, done == null,\n\
+                  head.length(), size, File.read(path)]",
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            (
+                "line 1".to_string(),
+                500.0,
+                "line 500".to_string(),
+                "\u{e9}llo\r\n".to_string(),
+                true,
+                4.0,
+                6.0,
+                "line 1".to_string()
+            )
+        );
+        assert!(engine.eval("input.readLine()").is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,169 @@
+//! Methods and properties of function values
+//! `bind` and `partial` fix the receiver or the leading arguments of a
+//! function, so callbacks can be handed around without wrapping them in
+//! lambdas.
+
+use super::check_arity;
+use super::docs::ClassDoc;
+use crate::vm::value::{Class, NativeInstanceFn, Value};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+pub fn create_function_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("bind".to_string(), function_bind);
+    instance_methods.insert("partial".to_string(), function_partial);
+
+    Class::new_with_instance("Function", instance_methods, None)
+}
+
+/// API documentation for the `Function` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Function", "Methods and properties of functions")
+        .method(
+            "bind",
+            "bind(receiver)",
+            "Get a copy of the function whose self is receiver",
+        )
+        .method(
+            "partial",
+            "partial(...args)",
+            "Get a function that calls this one with args before the ones it is given",
+        )
+        .property(
+            "arity",
+            "Number of parameters, not counting self, ...rest and **named; null for natives",
+        )
+        .property(
+            "name",
+            "Name the function was declared with; null for lambdas and anonymous functions",
+        )
+}
+
+/// Whether `name` is one of the properties every function value has
+pub(crate) fn is_property(name: &str) -> bool {
+    matches!(name, "arity" | "name")
+}
+
+/// `fn.arity` or `fn.name`, null when a native function doesn't know it or
+/// the function has no name
+pub(crate) fn property(value: &Value, name: &str) -> Value {
+    match name {
+        "arity" => arity(value).map_or(Value::Null, |n| Value::Number(n as f64)),
+        _ => function_name(value).map_or(Value::Null, |n| Value::String(Rc::from(n))),
+    }
+}
+
+fn arity(value: &Value) -> Option<usize> {
+    match value {
+        Value::Function(function)
+        | Value::BoundMethod {
+            method: function, ..
+        } => Some(function.arity - function.is_variadic as usize),
+        Value::Partial { callee, args } => arity(callee).map(|n| n.saturating_sub(args.len())),
+        _ => None,
+    }
+}
+
+fn function_name(value: &Value) -> Option<&str> {
+    match value {
+        Value::Function(function)
+        | Value::BoundMethod {
+            method: function, ..
+        } => {
+            // Lambdas and anonymous functions carry a `<...@line:col>` name
+            // that is only meant for stack traces
+            Some(function.name.as_str()).filter(|name| !name.starts_with('<'))
+        }
+        Value::HostFunction { name, .. } => Some(name),
+        Value::InstanceMethod { method_name, .. } => Some(method_name),
+        Value::Partial { callee, .. } => function_name(callee),
+        _ => None,
+    }
+}
+
+fn function_bind(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    match recv {
+        Value::Function(method) | Value::BoundMethod { method, .. } => Ok(Value::BoundMethod {
+            receiver: Box::new(args[0].clone()),
+            method: method.clone(),
+        }),
+        other => Err(format!(
+            "bind() needs a function defined in Sald, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn function_partial(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    // Partials of partials keep one callee with all the arguments so far
+    let (callee, args) = match recv {
+        Value::Partial {
+            callee,
+            args: given,
+        } => {
+            let mut all = given.as_ref().clone();
+            all.extend_from_slice(args);
+            (callee.clone(), all)
+        }
+        other => (Box::new(other.clone()), args.to_vec()),
+    };
+    Ok(Value::Partial {
+        callee,
+        args: Rc::new(args),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::eval;
+
+    #[test]
+    fn test_declared_functions_have_their_name() {
+        assert_eq!(eval("fun add(a, b) { return a + b }\nadd.name"), "add");
+        assert_eq!(eval("fun add(a, b) { return a + b }\nadd.partial(1).name"), "add");
+    }
+
+    #[test]
+    fn test_anonymous_functions_have_no_name() {
+        assert_eq!(eval("let f = |x| x\nf.name"), "null");
+        assert_eq!(eval("let f = fun(x) { return x }\nf.name"), "null");
+        assert_eq!(eval("let f = async |x| x\nf.name == null"), "true");
+    }
+
+    #[test]
+    fn test_function_bind_partial_and_arity() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                fun add(a, b, c = 0) { return a + b + c }
+                fun greet(greeting) { return greeting + ", " + self.name }
+                class Person {
+                    fun init(self, name) { self.name = name }
+                    fun hi(self, mark) { return self.name + mark }
+                }
+                let add5 = add.partial(5)
+                let add56 = add5.partial(6)
+                let errors = []
+                try { "abc".upper.bind(1) } catch e { errors.push(e) }
+                let parts = [
+                    add.arity, add.name, add5(1), add5(1, c: 10), add5.arity,
+                    add56(), greet.bind(Person("Ada"))("Hi"),
+                    Person("A").hi.bind(Person("B"))("!"), Person("A").hi.arity,
+                    [1, 2].map(add.partial(10)), Type.isFunction(add5), errors
+                ]
+                return parts.join("|")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            "3|add|6|16|2|11|Hi, Ada|B!|1|[11, 12]|true|\
+             [bind() needs a function defined in Sald, got InstanceMethod]"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Binary heap
+//! `Heap` is a priority queue that always pops its smallest value. Values are
+//! ordered like `Array.sort` does by default (arrays element by element, so
+//! `[priority, item]` pairs work) or by a compare function that returns a
+//! negative number when its first argument comes first
+
+use super::array::compare_values;
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, native_instance, native_state};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+struct Store {
+    items: Vec<Value>,
+    compare: Option<Value>,
+    /// Initial items are put in heap order on first use, since ordering
+    /// them may need to call the compare function
+    heapified: bool,
+}
+
+pub fn create_heap_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    static_methods.insert("new".to_string(), heap_new);
+
+    instance_methods.insert("length".to_string(), heap_length);
+    instance_methods.insert("isEmpty".to_string(), heap_is_empty);
+    instance_methods.insert("clear".to_string(), heap_clear);
+    callable_methods.insert("push".to_string(), heap_push);
+    callable_methods.insert("pop".to_string(), heap_pop);
+    callable_methods.insert("peek".to_string(), heap_peek);
+    callable_methods.insert("toArray".to_string(), heap_to_array);
+
+    let mut class = Class::new_with_instance("Heap", instance_methods, Some(heap_new));
+    class.native_static_methods = static_methods;
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Heap` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Heap", "Priority queue that pops the smallest value first")
+        .method(
+            "new",
+            "Heap(items?, compare?)",
+            "Create a heap, ordered by compare(a, b) < 0 when given; Heap(compare) also works",
+        )
+        .method("push", "push(value)", "Add a value, returns the new length")
+        .method(
+            "pop",
+            "pop()",
+            "Remove and return the smallest value, null when empty",
+        )
+        .method("peek", "peek()", "Smallest value without removing it")
+        .method("length", "length()", "Number of values")
+        .method("isEmpty", "isEmpty()", "Check if the heap is empty")
+        .method("clear", "clear()", "Remove all values")
+        .method(
+            "toArray",
+            "toArray()",
+            "Values in the order they would be popped",
+        )
+}
+
+fn with_heap<T>(recv: &Value, f: impl FnOnce(&mut Store) -> T) -> Result<T, String> {
+    let store = native_state::<Store>(recv, "Heap")?;
+    let mut store = store.borrow_mut();
+    Ok(f(&mut store))
+}
+
+/// Sifts values around a vector taken out of the store, so the compare
+/// function is free to call back into the interpreter.
+struct Sifter<'a> {
+    items: Vec<Value>,
+    compare: Option<Value>,
+    caller: &'a mut dyn ValueCaller,
+}
+
+impl Sifter<'_> {
+    fn less(&mut self, i: usize, j: usize) -> Result<bool, String> {
+        let (a, b) = (&self.items[i], &self.items[j]);
+        match &self.compare {
+            None => Ok(compare_values(a, b) == Ordering::Less),
+            Some(compare) => match self.caller.call(compare, vec![a.clone(), b.clone()])? {
+                Value::Number(n) => Ok(n < 0.0),
+                other => Err(format!(
+                    "Compare function must return a number, got {}",
+                    other.type_name()
+                )),
+            },
+        }
+    }
+
+    fn sift_up(&mut self, mut index: usize) -> Result<(), String> {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !self.less(index, parent)? {
+                break;
+            }
+            self.items.swap(index, parent);
+            index = parent;
+        }
+        Ok(())
+    }
+
+    fn sift_down(&mut self, mut index: usize) -> Result<(), String> {
+        loop {
+            let mut smallest = index;
+            for child in [2 * index + 1, 2 * index + 2] {
+                if child < self.items.len() && self.less(child, smallest)? {
+                    smallest = child;
+                }
+            }
+            if smallest == index {
+                return Ok(());
+            }
+            self.items.swap(index, smallest);
+            index = smallest;
+        }
+    }
+
+    fn heapify(&mut self) -> Result<(), String> {
+        for index in (0..self.items.len() / 2).rev() {
+            self.sift_down(index)?;
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Option<Value>, String> {
+        if self.items.is_empty() {
+            return Ok(None);
+        }
+        let last = self.items.len() - 1;
+        self.items.swap(0, last);
+        let top = self.items.pop();
+        self.sift_down(0)?;
+        Ok(top)
+    }
+}
+
+/// Runs `f` on the heap's items with the store emptied meanwhile, putting
+/// the items back even when the compare function fails.
+fn sift<T>(
+    recv: &Value,
+    caller: &mut dyn ValueCaller,
+    f: impl FnOnce(&mut Sifter) -> Result<T, String>,
+) -> Result<T, String> {
+    let (items, compare, mut heapified) = with_heap(recv, |store| {
+        (
+            std::mem::take(&mut store.items),
+            store.compare.clone(),
+            store.heapified,
+        )
+    })?;
+    let mut sifter = Sifter {
+        items,
+        compare,
+        caller,
+    };
+    let result = match heapified {
+        true => f(&mut sifter),
+        false => sifter.heapify().and_then(|_| {
+            heapified = true;
+            f(&mut sifter)
+        }),
+    };
+    let mut items = sifter.items;
+    with_heap(recv, |store| {
+        // Keep anything pushed from inside the compare function
+        items.append(&mut store.items);
+        store.items = items;
+        store.heapified = heapified;
+    })?;
+    result
+}
+
+fn heap_new(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let (items, compare) = match (args.first(), args.get(1)) {
+        (None | Some(Value::Null), compare) => (Vec::new(), compare),
+        (Some(Value::Array(items)), compare) => (items.borrow().to_vec(), compare),
+        (Some(compare), None) => (Vec::new(), Some(compare)),
+        (Some(other), Some(_)) => {
+            return Err(format!(
+                "Argument 'items' must be an array, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let compare = compare.filter(|compare| !compare.is_null()).cloned();
+    let heapified = items.len() < 2;
+
+    Ok(native_instance(
+        create_heap_class(),
+        Store {
+            items,
+            compare,
+            heapified,
+        },
+    ))
+}
+
+fn heap_push(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    sift(recv, caller, |heap| {
+        heap.items.push(args[0].clone());
+        heap.sift_up(heap.items.len() - 1)?;
+        Ok(Value::Number(heap.items.len() as f64))
+    })
+}
+
+fn heap_pop(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    sift(recv, caller, |heap| Ok(heap.pop()?.unwrap_or(Value::Null)))
+}
+
+fn heap_peek(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    sift(recv, caller, |heap| {
+        Ok(heap.items.first().cloned().unwrap_or(Value::Null))
+    })
+}
+
+fn heap_to_array(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let (items, compare) = sift(recv, caller, |heap| {
+        Ok((heap.items.clone(), heap.compare.clone()))
+    })?;
+    let mut sorted = Sifter {
+        items,
+        compare,
+        caller,
+    };
+    let mut out = Vec::with_capacity(sorted.items.len());
+    while let Some(value) = sorted.pop()? {
+        out.push(value);
+    }
+    Ok(Value::Array(Rc::new(RefCell::new(out.into()))))
+}
+
+fn heap_length(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(
+        with_heap(recv, |store| store.items.len())? as f64
+    ))
+}
+
+fn heap_is_empty(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Boolean(with_heap(recv, |store| {
+        store.items.is_empty()
+    })?))
+}
+
+fn heap_clear(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let removed = with_heap(recv, |store| std::mem::take(&mut store.items))?;
+    drop(removed);
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::eval_err;
+
+    #[test]
+    fn test_heap_is_not_a_collection() {
+        let error = eval_err("Set(Heap([1]))");
+        assert!(
+            error.contains("TypeError: Cannot create a Set from a Heap"),
+            "{}",
+            error
+        );
+    }
+}
@@ -0,0 +1,840 @@
+//! Lazy iterators
+//! `Iter` wraps an array, string, dict, collection, line reader or generator
+//! function and chains adapters such as `map` and `filter` without building
+//! intermediate arrays. Nothing runs until a consuming method such as
+//! `toArray` or `reduce` pulls values through. Adapters share the iterator
+//! they wrap, so advancing one advances the other, as in most languages
+
+use super::array::size_arg;
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, get_string_arg, native_state};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{
+    Array, Class, Instance, NativeHandle, NativeInstanceFn, NativeStaticFn, Value,
+};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+type Shared = Rc<RefCell<Node>>;
+
+/// One stage of a pipeline; adapters pull from the stage they wrap.
+enum Node {
+    Array {
+        items: Rc<RefCell<Array>>,
+        index: usize,
+    },
+    Chars {
+        text: Rc<str>,
+        offset: usize,
+    },
+    /// Native collections exposing `length()` and `at(i)`
+    Indexed {
+        source: Value,
+        length: NativeInstanceFn,
+        at: NativeInstanceFn,
+        index: usize,
+    },
+    /// Native readers whose `next()` returns null at the end
+    Next {
+        source: Value,
+        next: NativeInstanceFn,
+    },
+    Generate {
+        func: Value,
+    },
+    Range {
+        next: f64,
+        end: Option<f64>,
+        step: f64,
+    },
+    Repeat {
+        value: Value,
+        remaining: Option<usize>,
+    },
+    Map {
+        inner: Shared,
+        func: Value,
+    },
+    Filter {
+        inner: Shared,
+        func: Value,
+    },
+    Take {
+        inner: Shared,
+        remaining: usize,
+    },
+    Skip {
+        inner: Shared,
+        remaining: usize,
+    },
+    TakeWhile {
+        inner: Shared,
+        func: Value,
+        done: bool,
+    },
+    SkipWhile {
+        inner: Shared,
+        func: Value,
+        skipping: bool,
+    },
+    Enumerate {
+        inner: Shared,
+        index: usize,
+    },
+    Zip {
+        inners: Vec<Shared>,
+    },
+    Chain {
+        inners: VecDeque<Shared>,
+    },
+    Chunk {
+        inner: Shared,
+        size: usize,
+    },
+    Window {
+        inner: Shared,
+        size: usize,
+        buffer: VecDeque<Value>,
+    },
+}
+
+pub fn create_iter_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    static_methods.insert("new".to_string(), iter_new);
+    static_methods.insert("from".to_string(), iter_new);
+    static_methods.insert("range".to_string(), iter_range);
+    static_methods.insert("count".to_string(), iter_count_from);
+    static_methods.insert("repeat".to_string(), iter_repeat);
+    static_methods.insert("generate".to_string(), iter_generate);
+
+    instance_methods.insert("map".to_string(), iter_map);
+    instance_methods.insert("filter".to_string(), iter_filter);
+    instance_methods.insert("take".to_string(), iter_take);
+    instance_methods.insert("skip".to_string(), iter_skip);
+    instance_methods.insert("takeWhile".to_string(), iter_take_while);
+    instance_methods.insert("skipWhile".to_string(), iter_skip_while);
+    instance_methods.insert("enumerate".to_string(), iter_enumerate);
+    instance_methods.insert("zip".to_string(), iter_zip);
+    instance_methods.insert("chain".to_string(), iter_chain);
+    instance_methods.insert("chunk".to_string(), iter_chunk);
+    instance_methods.insert("window".to_string(), iter_window);
+
+    callable_methods.insert("next".to_string(), iter_next);
+    callable_methods.insert("toArray".to_string(), iter_to_array);
+    callable_methods.insert("reduce".to_string(), iter_reduce);
+    callable_methods.insert("forEach".to_string(), iter_for_each);
+    callable_methods.insert("count".to_string(), iter_count);
+    callable_methods.insert("find".to_string(), iter_find);
+    callable_methods.insert("any".to_string(), iter_any);
+    callable_methods.insert("all".to_string(), iter_all);
+    callable_methods.insert("sum".to_string(), iter_sum);
+    callable_methods.insert("join".to_string(), iter_join);
+
+    let mut class = Class::new_with_instance("Iter", instance_methods, Some(iter_new));
+    class.native_static_methods = static_methods;
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Iter` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "Iter",
+        "Lazy iterator: adapters run only as a consuming method pulls values",
+    )
+    .method(
+        "new",
+        "Iter(source)",
+        "Iterate an array, string, dict ([key, value] pairs), Set, Map, Deque, FileLines or Iter",
+    )
+    .method("from", "Iter.from(source)", "Same as Iter(source)")
+    .method(
+        "range",
+        "Iter.range(start, end?, step?)",
+        "Numbers from start up to end (exclusive); range(n) counts 0 to n",
+    )
+    .method(
+        "count",
+        "Iter.count(start?, step?)",
+        "Endless numbers from start (0); as a method, consumes and counts values",
+    )
+    .method(
+        "repeat",
+        "Iter.repeat(value, times?)",
+        "The same value, endlessly or a number of times",
+    )
+    .method(
+        "generate",
+        "Iter.generate(fn)",
+        "Values from calling fn until it returns null",
+    )
+    .method("map", "map(fn)", "Transform each value")
+    .method("filter", "filter(fn)", "Keep values for which fn is truthy")
+    .method("take", "take(n)", "Stop after n values")
+    .method("skip", "skip(n)", "Drop the first n values")
+    .method(
+        "takeWhile",
+        "takeWhile(fn)",
+        "Stop at the first value for which fn is falsy",
+    )
+    .method(
+        "skipWhile",
+        "skipWhile(fn)",
+        "Drop values while fn is truthy",
+    )
+    .method("enumerate", "enumerate()", "[index, value] pairs")
+    .method(
+        "zip",
+        "zip(other)",
+        "[value, other value] pairs, ending with the shorter",
+    )
+    .method("chain", "chain(other)", "Values of this, then of other")
+    .method(
+        "chunk",
+        "chunk(size)",
+        "Arrays of size values, the last one possibly shorter",
+    )
+    .method(
+        "window",
+        "window(size)",
+        "Overlapping arrays of size consecutive values",
+    )
+    .method("next", "next()", "Next value, or null at the end")
+    .method("toArray", "toArray()", "Collect the remaining values")
+    .method(
+        "reduce",
+        "reduce(fn, initial?)",
+        "Fold values with fn(acc, value), starting from initial or the first value",
+    )
+    .method("forEach", "forEach(fn)", "Call fn with each value")
+    .method(
+        "find",
+        "find(fn)",
+        "First value for which fn is truthy, or null",
+    )
+    .method("any", "any(fn)", "Check if fn is truthy for some value")
+    .method("all", "all(fn)", "Check if fn is truthy for every value")
+    .method("sum", "sum()", "Add up numeric values")
+    .method("join", "join(separator?)", "Concatenate values as strings")
+}
+
+impl Node {
+    fn next(&mut self, caller: &mut dyn ValueCaller) -> Result<Option<Value>, String> {
+        match self {
+            Node::Array { items, index } => {
+                let item = items.borrow().get(*index).cloned();
+                *index += item.is_some() as usize;
+                Ok(item)
+            }
+            Node::Chars { text, offset } => Ok(text[*offset..].chars().next().map(|c| {
+                *offset += c.len_utf8();
+                Value::String(Rc::from(c.to_string()))
+            })),
+            Node::Indexed {
+                source,
+                length,
+                at,
+                index,
+            } => {
+                let len = match length(source, &[])? {
+                    Value::Number(n) => n as usize,
+                    _ => 0,
+                };
+                if *index >= len {
+                    return Ok(None);
+                }
+                let item = at(source, &[Value::Number(*index as f64)])?;
+                *index += 1;
+                Ok(Some(item))
+            }
+            Node::Next { source, next } => Ok(Some(next(source, &[])?).filter(|v| !v.is_null())),
+            Node::Generate { func } => {
+                Ok(Some(caller.call(func, Vec::new())?).filter(|v| !v.is_null()))
+            }
+            Node::Range { next, end, step } => {
+                let value = *next;
+                let finished = match end {
+                    Some(end) if *step > 0.0 => value >= *end,
+                    Some(end) => value <= *end,
+                    None => false,
+                };
+                if finished {
+                    return Ok(None);
+                }
+                *next += *step;
+                Ok(Some(Value::Number(value)))
+            }
+            Node::Repeat { value, remaining } => match remaining {
+                Some(0) => Ok(None),
+                Some(n) => {
+                    *n -= 1;
+                    Ok(Some(value.clone()))
+                }
+                None => Ok(Some(value.clone())),
+            },
+            Node::Map { inner, func } => match pull(inner, caller)? {
+                Some(value) => Ok(Some(caller.call(func, vec![value])?)),
+                None => Ok(None),
+            },
+            Node::Filter { inner, func } => {
+                while let Some(value) = pull(inner, caller)? {
+                    if caller.call(func, vec![value.clone()])?.is_truthy() {
+                        return Ok(Some(value));
+                    }
+                }
+                Ok(None)
+            }
+            Node::Take { inner, remaining } => {
+                if *remaining == 0 {
+                    return Ok(None);
+                }
+                *remaining -= 1;
+                pull(inner, caller)
+            }
+            Node::Skip { inner, remaining } => {
+                while *remaining > 0 {
+                    *remaining -= 1;
+                    if pull(inner, caller)?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                pull(inner, caller)
+            }
+            Node::TakeWhile { inner, func, done } => {
+                if *done {
+                    return Ok(None);
+                }
+                match pull(inner, caller)? {
+                    Some(value) if caller.call(func, vec![value.clone()])?.is_truthy() => {
+                        Ok(Some(value))
+                    }
+                    _ => {
+                        *done = true;
+                        Ok(None)
+                    }
+                }
+            }
+            Node::SkipWhile {
+                inner,
+                func,
+                skipping,
+            } => {
+                while *skipping {
+                    let Some(value) = pull(inner, caller)? else {
+                        return Ok(None);
+                    };
+                    if !caller.call(func, vec![value.clone()])?.is_truthy() {
+                        *skipping = false;
+                        return Ok(Some(value));
+                    }
+                }
+                pull(inner, caller)
+            }
+            Node::Enumerate { inner, index } => match pull(inner, caller)? {
+                Some(value) => {
+                    let pair = array(vec![Value::Number(*index as f64), value]);
+                    *index += 1;
+                    Ok(Some(pair))
+                }
+                None => Ok(None),
+            },
+            Node::Zip { inners } => {
+                let mut values = Vec::with_capacity(inners.len());
+                for inner in inners.iter() {
+                    match pull(inner, caller)? {
+                        Some(value) => values.push(value),
+                        None => return Ok(None),
+                    }
+                }
+                Ok(Some(array(values)))
+            }
+            Node::Chain { inners } => {
+                while let Some(inner) = inners.front() {
+                    if let Some(value) = pull(inner, caller)? {
+                        return Ok(Some(value));
+                    }
+                    inners.pop_front();
+                }
+                Ok(None)
+            }
+            Node::Chunk { inner, size } => {
+                let mut chunk = Vec::with_capacity(*size);
+                while chunk.len() < *size {
+                    match pull(inner, caller)? {
+                        Some(value) => chunk.push(value),
+                        None => break,
+                    }
+                }
+                Ok(Some(chunk).filter(|chunk| !chunk.is_empty()).map(array))
+            }
+            Node::Window {
+                inner,
+                size,
+                buffer,
+            } => {
+                if buffer.len() == *size {
+                    buffer.pop_front();
+                }
+                while buffer.len() < *size {
+                    match pull(inner, caller)? {
+                        Some(value) => buffer.push_back(value),
+                        None => return Ok(None),
+                    }
+                }
+                Ok(Some(array(buffer.iter().cloned().collect())))
+            }
+        }
+    }
+}
+
+/// Advances a stage, refusing to re-enter one that is already running (for
+/// example from inside its own map function).
+fn pull(node: &Shared, caller: &mut dyn ValueCaller) -> Result<Option<Value>, String> {
+    let mut node = node
+        .try_borrow_mut()
+        .map_err(|_| "Iterator is already being advanced".to_string())?;
+    node.next(caller)
+}
+
+fn array(items: Vec<Value>) -> Value {
+    Value::Array(Rc::new(RefCell::new(items.into())))
+}
+
+fn shared(node: Node) -> Shared {
+    Rc::new(RefCell::new(node))
+}
+
+fn register(node: Shared) -> Value {
+    let mut instance = Instance::new(Rc::new(create_iter_class()));
+    instance.native = Some(NativeHandle::shared(node));
+    Value::Instance(Rc::new(RefCell::new(instance)))
+}
+
+/// The pipeline behind an `Iter` instance, if `value` is one.
+fn node_of(value: &Value) -> Option<Shared> {
+    match value {
+        Value::Instance(inst) => inst.borrow().native::<Node>(),
+        _ => None,
+    }
+}
+
+/// Next value of `value` if it is an `Iter`, for `for` loops, which pull
+/// from a pipeline instead of indexing it
+pub(crate) fn next_of(
+    value: &Value,
+    caller: &mut dyn ValueCaller,
+) -> Option<Result<Option<Value>, String>> {
+    node_of(value).map(|node| pull(&node, caller))
+}
+
+fn receiver(recv: &Value) -> Result<Shared, String> {
+    native_state(recv, "Iter")
+}
+
+/// Turns anything iterable into a stage.
+fn source(value: &Value) -> Result<Shared, String> {
+    if let Some(node) = node_of(value) {
+        return Ok(node);
+    }
+    let node = match value {
+        Value::Array(items) => Node::Array {
+            items: items.clone(),
+            index: 0,
+        },
+        Value::String(text) => Node::Chars {
+            text: text.clone(),
+            offset: 0,
+        },
+        Value::Dictionary(dict) => {
+            let dict = dict.borrow();
+            let entries = dict
+                .iter()
+                .map(|(k, v)| array(vec![dict.key(k), v.clone()]))
+                .collect();
+            Node::Array {
+                items: Rc::new(RefCell::new(entries)),
+                index: 0,
+            }
+        }
+        Value::Instance(inst) => {
+            let inst = inst.borrow();
+            let methods = &inst.class.native_instance_methods;
+            match (
+                methods.get("length"),
+                methods.get("at"),
+                methods.get("next"),
+            ) {
+                (Some(length), Some(at), _) => Node::Indexed {
+                    source: value.clone(),
+                    length: *length,
+                    at: *at,
+                    index: 0,
+                },
+                (_, _, Some(next)) => Node::Next {
+                    source: value.clone(),
+                    next: *next,
+                },
+                _ => return Err(format!("Cannot iterate a {}", inst.class.name)),
+            }
+        }
+        other => return Err(format!("Cannot iterate a {}", other.type_name())),
+    };
+    Ok(shared(node))
+}
+
+fn count_arg(value: &Value, name: &str) -> Result<usize, String> {
+    let n = get_number_arg(value, name)?;
+    if n < 0.0 || n.fract() != 0.0 {
+        return Err(format!(
+            "Argument '{}' must be a non-negative integer",
+            name
+        ));
+    }
+    Ok(n as usize)
+}
+
+/// Wraps an iterable value in an `Iter`, as `Iter(value)` does.
+pub(crate) fn iterate(value: &Value) -> Result<Value, String> {
+    Ok(register(source(value)?))
+}
+
+fn iter_new(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    iterate(&args[0])
+}
+
+fn iter_range(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 3, args.len())?;
+    let (start, end) = match args.get(1) {
+        Some(end) => (
+            get_number_arg(&args[0], "start")?,
+            get_number_arg(end, "end")?,
+        ),
+        None => (0.0, get_number_arg(&args[0], "end")?),
+    };
+    let step = match args.get(2) {
+        Some(step) => get_number_arg(step, "step")?,
+        None => 1.0,
+    };
+    if step == 0.0 {
+        return Err("Argument 'step' cannot be 0".to_string());
+    }
+    Ok(register(shared(Node::Range {
+        next: start,
+        end: Some(end),
+        step,
+    })))
+}
+
+fn iter_count_from(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let start = match args.first() {
+        Some(start) => get_number_arg(start, "start")?,
+        None => 0.0,
+    };
+    let step = match args.get(1) {
+        Some(step) => get_number_arg(step, "step")?,
+        None => 1.0,
+    };
+    Ok(register(shared(Node::Range {
+        next: start,
+        end: None,
+        step,
+    })))
+}
+
+fn iter_repeat(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let remaining = match args.get(1) {
+        None | Some(Value::Null) => None,
+        Some(times) => Some(count_arg(times, "times")?),
+    };
+    Ok(register(shared(Node::Repeat {
+        value: args[0].clone(),
+        remaining,
+    })))
+}
+
+fn iter_generate(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Generate {
+        func: args[0].clone(),
+    })))
+}
+
+fn iter_map(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Map {
+        inner: receiver(recv)?,
+        func: args[0].clone(),
+    })))
+}
+
+fn iter_filter(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Filter {
+        inner: receiver(recv)?,
+        func: args[0].clone(),
+    })))
+}
+
+fn iter_take(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Take {
+        inner: receiver(recv)?,
+        remaining: count_arg(&args[0], "n")?,
+    })))
+}
+
+fn iter_skip(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Skip {
+        inner: receiver(recv)?,
+        remaining: count_arg(&args[0], "n")?,
+    })))
+}
+
+fn iter_take_while(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::TakeWhile {
+        inner: receiver(recv)?,
+        func: args[0].clone(),
+        done: false,
+    })))
+}
+
+fn iter_skip_while(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::SkipWhile {
+        inner: receiver(recv)?,
+        func: args[0].clone(),
+        skipping: true,
+    })))
+}
+
+fn iter_enumerate(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(register(shared(Node::Enumerate {
+        inner: receiver(recv)?,
+        index: 0,
+    })))
+}
+
+fn iter_zip(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Zip {
+        inners: vec![receiver(recv)?, source(&args[0])?],
+    })))
+}
+
+fn iter_chain(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Chain {
+        inners: VecDeque::from([receiver(recv)?, source(&args[0])?]),
+    })))
+}
+
+fn iter_chunk(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(shared(Node::Chunk {
+        inner: receiver(recv)?,
+        size: size_arg(&args[0])?,
+    })))
+}
+
+fn iter_window(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let size = size_arg(&args[0])?;
+    Ok(register(shared(Node::Window {
+        inner: receiver(recv)?,
+        size,
+        buffer: VecDeque::with_capacity(size),
+    })))
+}
+
+fn iter_next(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(pull(&receiver(recv)?, caller)?.unwrap_or(Value::Null))
+}
+
+fn iter_to_array(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let node = receiver(recv)?;
+    let mut items = Vec::new();
+    while let Some(value) = pull(&node, caller)? {
+        items.push(value);
+    }
+    Ok(array(items))
+}
+
+fn iter_reduce(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let node = receiver(recv)?;
+    let mut acc = match args.get(1) {
+        Some(initial) => initial.clone(),
+        None => match pull(&node, caller)? {
+            Some(first) => first,
+            None => {
+                return Err("Cannot reduce an empty iterator without an initial value".to_string())
+            }
+        },
+    };
+    while let Some(value) = pull(&node, caller)? {
+        acc = caller.call(&args[0], vec![acc, value])?;
+    }
+    Ok(acc)
+}
+
+fn iter_for_each(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let node = receiver(recv)?;
+    while let Some(value) = pull(&node, caller)? {
+        caller.call(&args[0], vec![value])?;
+    }
+    Ok(Value::Null)
+}
+
+fn iter_count(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let node = receiver(recv)?;
+    let mut count = 0;
+    while pull(&node, caller)?.is_some() {
+        count += 1;
+    }
+    Ok(Value::Number(count as f64))
+}
+
+fn iter_find(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let node = receiver(recv)?;
+    while let Some(value) = pull(&node, caller)? {
+        if caller.call(&args[0], vec![value.clone()])?.is_truthy() {
+            return Ok(value);
+        }
+    }
+    Ok(Value::Null)
+}
+
+fn iter_any(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let node = receiver(recv)?;
+    while let Some(value) = pull(&node, caller)? {
+        if caller.call(&args[0], vec![value])?.is_truthy() {
+            return Ok(Value::Boolean(true));
+        }
+    }
+    Ok(Value::Boolean(false))
+}
+
+fn iter_all(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let node = receiver(recv)?;
+    while let Some(value) = pull(&node, caller)? {
+        if !caller.call(&args[0], vec![value])?.is_truthy() {
+            return Ok(Value::Boolean(false));
+        }
+    }
+    Ok(Value::Boolean(true))
+}
+
+fn iter_sum(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let node = receiver(recv)?;
+    let mut sum = 0.0;
+    while let Some(value) = pull(&node, caller)? {
+        sum += get_number_arg(&value, "value")?;
+    }
+    Ok(Value::Number(sum))
+}
+
+fn iter_join(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let separator = match args.first() {
+        Some(separator) => get_string_arg(separator, "separator")?,
+        None => String::new(),
+    };
+    let node = receiver(recv)?;
+    let mut parts = Vec::new();
+    while let Some(value) = pull(&node, caller)? {
+        parts.push(value.to_string());
+    }
+    Ok(Value::String(Rc::from(parts.join(&separator))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::{eval, eval_err};
+
+    #[test]
+    fn test_iter_lazy_pipeline() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let calls = 0\n\
+                 let firsts = Iter.count(1).map(|x| { calls = calls + 1\n return x * x }).take(3).toArray()",
+            )
+            .unwrap();
+        let firsts: Vec<f64> = engine.eval_as("firsts").unwrap();
+        assert_eq!(firsts, [1.0, 4.0, 9.0]);
+        let calls: f64 = engine.eval_as("calls").unwrap();
+        assert_eq!(calls, 3.0);
+        let windows: String = engine
+            .eval_as("Json.stringify(Iter([1, 2, 3, 4]).window(3).toArray())")
+            .unwrap();
+        assert_eq!(windows, "[[1,2,3],[2,3,4]]");
+        let total: f64 = engine
+            .eval_as("Iter.range(10).filter(|x| x % 2 == 1).reduce(|a, b| a + b, 0)")
+            .unwrap();
+        assert_eq!(total, 25.0);
+        let zipped: String = engine
+            .eval_as(
+                "Iter(\"ab\").zip(Iter.range(5)).chunk(1).map(|c| c[0].join(\"\")).join(\",\")",
+            )
+            .unwrap();
+        assert_eq!(zipped, "a0,b1");
+    }
+
+    #[test]
+    fn test_for_loops_pull_from_iter() {
+        let doubled =
+            "let out = []\nfor x in Iter.from([1, 2]).map(|v| v * 2) { out.push(x) }\nout";
+        assert_eq!(eval(doubled), "[2, 4]");
+        let endless =
+            "let out = []\nfor x in Iter.count(1) { if x > 3 { break }\n out.push(x) }\nout";
+        assert_eq!(eval(endless), "[1, 2, 3]");
+        let nulls = "let out = []\nfor x in Iter([null, 1]) { out.push(x) }\nout";
+        assert_eq!(eval(nulls), "[null, 1]");
+        let caught = "let r = null\n\
+                      try { for x in Iter([1]).map(|v| v.missing()) {} } catch e { r = \"caught\" }\nr";
+        assert_eq!(eval(caught), "caught");
+    }
+
+    #[test]
+    fn test_chunk_and_window_sizes_match_array() {
+        let expected = "Argument 'size' must be a positive integer";
+        for call in ["chunk(0)", "chunk(-1)", "chunk(1.5)", "window(0)"] {
+            for receiver in ["Iter([1, 2])", "[1, 2]"] {
+                let err = eval_err(&format!("{receiver}.{call}"));
+                assert!(err.contains(expected), "{err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! `Js` for the browser build: reach into the host page's JavaScript.
+//! Values cross over as described in `crate::wasm::interop`.
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_string_arg};
+use crate::vm::caller::{CallableNativeInstanceFn, CallableNativeStaticFn, ValueCaller};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use crate::wasm::{self, from_js, js_error, object_of, to_js};
+use js_sys::{Array, Promise, Reflect};
+use rustc_hash::FxHashMap;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+pub fn create_js_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+    let mut static_fields: FxHashMap<String, Value> = FxHashMap::default();
+
+    static_fields.insert("global".to_string(), from_js(js_sys::global().into()));
+
+    static_methods.insert("typeOf".to_string(), js_type_of);
+    static_methods.insert("future".to_string(), js_future);
+    static_methods.insert("toData".to_string(), js_to_data);
+    callable_methods.insert("get".to_string(), js_get);
+    callable_methods.insert("set".to_string(), js_set);
+    callable_methods.insert("call".to_string(), js_call);
+    callable_methods.insert("invoke".to_string(), js_invoke);
+    callable_methods.insert("new".to_string(), js_new);
+
+    let mut class = Class::new_with_static("Js", static_methods);
+    class.callable_native_static_methods = callable_methods;
+    class.native_static_fields = static_fields;
+    class
+}
+
+/// Class of the handles JS objects and functions are wrapped in
+pub fn create_js_object_class() -> Class {
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    callable_methods.insert("get".to_string(), object_get);
+    callable_methods.insert("set".to_string(), object_set);
+    callable_methods.insert("call".to_string(), object_call);
+
+    let mut class = Class::new_with_instance("JsObject", FxHashMap::default(), None);
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the browser `Js` and `JsObject` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new("Js", "JavaScript interop for the browser build")
+            .method("global", "global", "The page's globalThis")
+            .method("get", "get(obj, key)", "Read a property")
+            .method("set", "set(obj, key, value)", "Write a property")
+            .method(
+                "call",
+                "call(obj, method, args?)",
+                "Call a method with an array of arguments",
+            )
+            .method("invoke", "invoke(fn, args?)", "Call a JS function")
+            .method("new", "new(ctor, args?)", "Construct a JS object")
+            .method("typeOf", "typeOf(value)", "JavaScript typeof of a value")
+            .method(
+                "future",
+                "await future(promise)",
+                "Wait for a JS promise that resolves to plain data",
+            )
+            .method(
+                "toData",
+                "toData(obj)",
+                "Deep-copy plain JS objects into dictionaries",
+            ),
+        ClassDoc::new("JsObject", "Handle to a JavaScript object or function")
+            .method("get", "get(key)", "Read a property")
+            .method("set", "set(key, value)", "Write a property")
+            .method(
+                "call",
+                "call(method, args?)",
+                "Call a method with an array of arguments",
+            ),
+    ]
+}
+
+fn js_object_arg(value: &Value, name: &str) -> Result<JsValue, String> {
+    object_of(value).ok_or_else(|| {
+        format!(
+            "Argument '{}' must be a JsObject, got {}",
+            name,
+            value.type_name()
+        )
+    })
+}
+
+fn args_arg(args: &[Value], index: usize) -> Result<Vec<Value>, String> {
+    match args.get(index) {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => Ok(items.borrow().to_vec()),
+        Some(other) => Err(format!(
+            "Argument 'args' must be an array, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn get(target: &JsValue, key: &Value, caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    wasm::with_caller(caller, |_| {
+        Reflect::get(target, &to_js(key))
+            .map(from_js)
+            .map_err(js_error)
+    })
+}
+
+fn set(
+    target: &JsValue,
+    key: &Value,
+    value: &Value,
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    wasm::with_caller(caller, |_| {
+        Reflect::set(target, &to_js(key), &to_js(value)).map_err(js_error)
+    })?;
+    Ok(Value::Null)
+}
+
+fn call(
+    target: &JsValue,
+    method: &str,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let function = Reflect::get(target, &JsValue::from_str(method)).map_err(js_error)?;
+    if !function.is_function() {
+        return Err(format!("JS object has no method '{}'", method));
+    }
+    wasm::with_caller(caller, |_| wasm::apply(&function, target, args))
+}
+
+fn js_get(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let target = js_object_arg(&args[0], "obj")?;
+    get(&target, &args[1], caller)
+}
+
+fn js_set(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(3, args.len())?;
+    let target = js_object_arg(&args[0], "obj")?;
+    set(&target, &args[1], &args[2], caller)
+}
+
+fn js_call(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let target = js_object_arg(&args[0], "obj")?;
+    let method = get_string_arg(&args[1], "method")?;
+    call(&target, &method, &args_arg(args, 2)?, caller)
+}
+
+fn js_invoke(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let function = js_object_arg(&args[0], "fn")?;
+    let call_args = args_arg(args, 1)?;
+    wasm::with_caller(caller, |_| {
+        wasm::apply(&function, &JsValue::UNDEFINED, &call_args)
+    })
+}
+
+fn js_new(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let ctor = js_object_arg(&args[0], "ctor")?;
+    let ctor = ctor
+        .dyn_ref::<js_sys::Function>()
+        .ok_or_else(|| "Argument 'ctor' must be a JS constructor".to_string())?;
+    let ctor_args: Array = args_arg(args, 1)?.iter().map(to_js).collect();
+    wasm::with_caller(caller, |_| {
+        Reflect::construct(ctor, &ctor_args)
+            .map(from_js)
+            .map_err(js_error)
+    })
+}
+
+fn js_type_of(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let type_name = to_js(&args[0]).js_typeof().as_string().unwrap_or_default();
+    Ok(Value::String(type_name.into()))
+}
+
+fn js_future(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let promise = js_object_arg(&args[0], "promise")?;
+    let promise = Promise::resolve(&promise);
+    Ok(wasm::spawn_future(async move {
+        let value = JsFuture::from(promise).await.map_err(js_error)?;
+        wasm::to_send(&value)
+    }))
+}
+
+fn js_to_data(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(wasm::to_send(&to_js(&args[0]))?.to_value())
+}
+
+fn object_get(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let target = js_object_arg(recv, "self")?;
+    get(&target, &args[0], caller)
+}
+
+fn object_set(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let target = js_object_arg(recv, "self")?;
+    set(&target, &args[0], &args[1], caller)
+}
+
+fn object_call(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let target = js_object_arg(recv, "self")?;
+    let method = get_string_arg(&args[0], "method")?;
+    call(&target, &method, &args_arg(args, 1)?, caller)
+}
@@ -1,51 +1,163 @@
-use super::{check_arity, check_arity_range, get_number_arg, get_string_arg};
+use super::docs::ClassDoc;
+use super::{check_arity_min, check_arity_range, get_number_arg, get_string_arg, NamedArgs};
+use crate::vm::caller::{CallableNativeStaticFn, ValueCaller};
 use crate::vm::value::{Class, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
-use serde::Serialize;
 use std::cell::RefCell;
 use std::rc::Rc;
 
 pub fn create_json_class() -> Class {
-    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
 
-    static_methods.insert("parse".to_string(), json_parse);
-    static_methods.insert("stringify".to_string(), json_stringify);
+    callable_methods.insert("parse".to_string(), json_parse);
+    callable_methods.insert("stringify".to_string(), json_stringify);
+    #[cfg(not(target_arch = "wasm32"))]
+    let static_methods = {
+        let mut static_methods = static_methods;
+        static_methods.insert(
+            "parseLines".to_string(),
+            super::json_stream::json_parse_lines,
+        );
+        static_methods.insert("events".to_string(), super::json_stream::json_events);
+        static_methods
+    };
+
+    let mut class = Class::new_with_static("Json", static_methods);
+    class.callable_native_static_methods = callable_methods;
+    class.named_arg_methods.insert("stringify".to_string());
+    class
+}
 
-    Class::new_with_static("Json", static_methods)
+/// API documentation for the `Json` class
+pub(crate) fn docs() -> ClassDoc {
+    let doc = ClassDoc::new("Json", "JSON parsing and serialization")
+        .method(
+            "parse",
+            "parse(json, reviver?)",
+            "Parse JSON string to value. reviver is a |key, value| function, or a class whose fromJson builds the result",
+        )
+        .method(
+            "stringify",
+            "stringify(value, indent?, sortKeys?, replacer?)",
+            "Convert value to JSON. Options may also be passed by name, as in stringify(value, indent: 2); instances are written through their toJson method",
+        );
+    #[cfg(not(target_arch = "wasm32"))]
+    let doc = doc
+        .method(
+            "parseLines",
+            "parseLines(path)",
+            "Read a JSON Lines file one record at a time",
+        )
+        .method(
+            "events",
+            "events(path)",
+            "Read parse events from a large JSON file",
+        );
+    doc
 }
 
-fn json_parse(args: &[Value]) -> Result<Value, String> {
-    check_arity(1, args.len())?;
+fn json_parse(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
     let json_str = get_string_arg(&args[0], "json")?;
 
-    serde_json::from_str::<serde_json::Value>(&json_str)
+    let value = serde_json::from_str::<serde_json::Value>(&json_str)
         .map_err(|e| e.to_string())
-        .and_then(|json_value| json_to_sald_value(&json_value))
-}
+        .and_then(|json_value| json_to_sald_value(&json_value))?;
 
-fn json_stringify(args: &[Value]) -> Result<Value, String> {
-    check_arity_range(1, 2, args.len())?;
+    match args.get(1) {
+        None | Some(Value::Null) => Ok(value),
+        Some(Value::Class(class)) => match class.user_static_methods.get("fromJson") {
+            Some(from_json) => caller.call(from_json, vec![value]),
+            None => Err(format!(
+                "Class '{}' has no static fromJson method",
+                class.name
+            )),
+        },
+        Some(reviver) => revive(Value::String(Rc::from("")), value, reviver, caller),
+    }
+}
 
-    let json_string = if args.len() == 2 {
-        let json_value = sald_value_to_json(&args[0])?;
-        let indent = get_number_arg(&args[1], "indent")? as usize;
-        let indent_str = " ".repeat(indent);
-
-        let mut buf = Vec::new();
-        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent_str.as_bytes());
-        let mut ser = serde_json::Serializer::with_formatter(&mut buf, formatter);
-        json_value.serialize(&mut ser).map_err(|e| e.to_string())?;
-        String::from_utf8(buf).map_err(|e| e.to_string())?
-    } else {
-        let mut buf = String::with_capacity(256);
-        write_json_value(&args[0], &mut buf)?;
-        buf
+/// Passes every value to the reviver, children before their parents.
+fn revive(
+    key: Value,
+    value: Value,
+    reviver: &Value,
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let value = match value {
+        Value::Array(arr) => {
+            let items = arr.borrow().clone();
+            let mut revived = Vec::with_capacity(items.len());
+            for (i, item) in items.into_iter().enumerate() {
+                revived.push(revive(Value::Number(i as f64), item, reviver, caller)?);
+            }
+            Value::Array(Rc::new(RefCell::new(revived.into())))
+        }
+        Value::Dictionary(dict) => {
+            let entries: Vec<(String, Value)> = dict
+                .borrow()
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            let mut revived = FxHashMap::default();
+            for (k, v) in entries {
+                let v = revive(Value::String(Rc::from(k.as_str())), v, reviver, caller)?;
+                revived.insert(k, v);
+            }
+            Value::Dictionary(Rc::new(RefCell::new(revived.into())))
+        }
+        other => other,
     };
+    caller.call(reviver, vec![key, value])
+}
+
+#[derive(Default)]
+struct StringifyOptions {
+    indent: Option<String>,
+    sort_keys: bool,
+    replacer: Option<Value>,
+}
+
+fn stringify_options(args: &[Value]) -> Result<StringifyOptions, String> {
+    let mut options = StringifyOptions::default();
+    let (args, named) = NamedArgs::split(args, &["indent", "sortKeys", "replacer"])?;
+    check_arity_range(1, 4, args.len())?;
+    let indent = named.get(args, 1, "indent")?;
+    let sort_keys = named.get(args, 2, "sortKeys")?;
+    let replacer = named.get(args, 3, "replacer")?;
+
+    match indent {
+        None | Some(Value::Null) => {}
+        Some(Value::String(s)) => options.indent = Some(s.to_string()),
+        Some(n) => options.indent = Some(" ".repeat(get_number_arg(&n, "indent")? as usize)),
+    }
+    match sort_keys {
+        None | Some(Value::Null) => {}
+        Some(Value::Boolean(b)) => options.sort_keys = b,
+        Some(other) => {
+            return Err(format!(
+                "sortKeys must be a boolean, got {}",
+                other.type_name()
+            ))
+        }
+    }
+    options.replacer = replacer.filter(|r| !matches!(r, Value::Null));
+    Ok(options)
+}
 
-    Ok(Value::String(Rc::from(json_string)))
+fn json_stringify(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_min(1, args.len())?;
+    let mut writer = JsonWriter {
+        options: stringify_options(args)?,
+        caller,
+        buf: String::with_capacity(256),
+    };
+    writer.write(Value::String(Rc::from("")), &args[0], 0)?;
+    Ok(Value::String(Rc::from(writer.buf)))
 }
 
-fn json_to_sald_value(json: &serde_json::Value) -> Result<Value, String> {
+pub(crate) fn json_to_sald_value(json: &serde_json::Value) -> Result<Value, String> {
     match json {
         serde_json::Value::Null => Ok(Value::Null),
         serde_json::Value::Bool(b) => Ok(Value::Boolean(*b)),
@@ -56,19 +168,19 @@ fn json_to_sald_value(json: &serde_json::Value) -> Result<Value, String> {
         serde_json::Value::String(s) => Ok(Value::String(Rc::from(s.clone()))),
         serde_json::Value::Array(arr) => {
             let sald_arr: Result<Vec<Value>, String> = arr.iter().map(json_to_sald_value).collect();
-            Ok(Value::Array(Rc::new(RefCell::new(sald_arr?))))
+            Ok(Value::Array(Rc::new(RefCell::new(sald_arr?.into()))))
         }
         serde_json::Value::Object(obj) => {
             let mut map = FxHashMap::default();
             for (key, value) in obj {
                 map.insert(key.clone(), json_to_sald_value(value)?);
             }
-            Ok(Value::Dictionary(Rc::new(RefCell::new(map))))
+            Ok(Value::Dictionary(Rc::new(RefCell::new(map.into()))))
         }
     }
 }
 
-fn sald_value_to_json(value: &Value) -> Result<serde_json::Value, String> {
+pub(crate) fn sald_value_to_json(value: &Value) -> Result<serde_json::Value, String> {
     match value {
         Value::Null => Ok(serde_json::Value::Null),
         Value::Boolean(b) => Ok(serde_json::Value::Bool(*b)),
@@ -85,6 +197,9 @@ fn sald_value_to_json(value: &Value) -> Result<serde_json::Value, String> {
         Value::Dictionary(dict) => {
             let mut map = serde_json::Map::new();
             let guard = dict.borrow();
+            if guard.has_instance_keys() {
+                return Err("Cannot convert a Dict with instance keys to JSON".to_string());
+            }
             for (key, val) in guard.iter() {
                 map.insert(key.clone(), sald_value_to_json(val)?);
             }
@@ -94,84 +209,189 @@ fn sald_value_to_json(value: &Value) -> Result<serde_json::Value, String> {
     }
 }
 
-fn write_json_value(value: &Value, buf: &mut String) -> Result<(), String> {
-    use std::fmt::Write;
-    match value {
-        Value::Null => {
-            buf.push_str("null");
-            Ok(())
+// Sald numbers are always f64; whole numbers are turned into JSON integers so
+// they deserialize into Rust integer types and print without a fraction.
+pub(crate) fn integralize_numbers(json: &mut serde_json::Value) {
+    match json {
+        serde_json::Value::Number(n) => {
+            if let Some(f) = n.as_f64() {
+                if f.fract() == 0.0 && f.abs() < i64::MAX as f64 {
+                    *n = serde_json::Number::from(f as i64);
+                }
+            }
+        }
+        serde_json::Value::Array(arr) => arr.iter_mut().for_each(integralize_numbers),
+        serde_json::Value::Object(obj) => obj.values_mut().for_each(integralize_numbers),
+        _ => {}
+    }
+}
+
+struct JsonWriter<'a> {
+    options: StringifyOptions,
+    caller: &'a mut dyn ValueCaller,
+    buf: String,
+}
+
+impl JsonWriter<'_> {
+    /// Writes `value`, first resolving `toJson` and the replacer for it.
+    fn write(&mut self, key: Value, value: &Value, depth: usize) -> Result<(), String> {
+        let mut value = value.clone();
+        let native_to_json = match &value {
+            Value::Instance(inst) => inst
+                .borrow()
+                .class
+                .native_instance_methods
+                .get("toJson")
+                .copied(),
+            _ => None,
+        };
+        if let Some(to_json) = native_to_json {
+            value = to_json(&value, &[])?;
+        } else if let Value::Instance(inst) = &value {
+            let to_json = inst.borrow().class.methods.get("toJson").cloned();
+            let Some(Value::Function(method)) = to_json else {
+                return Err(format!(
+                    "Cannot convert instance of '{}' to JSON without a toJson method",
+                    inst.borrow().class.name
+                ));
+            };
+            let bound = Value::BoundMethod {
+                receiver: Box::new(value.clone()),
+                method,
+            };
+            value = self.caller.call(&bound, Vec::new())?;
         }
-        Value::Boolean(b) => {
-            buf.push_str(if *b { "true" } else { "false" });
-            Ok(())
+        if let Some(replacer) = self.options.replacer.clone() {
+            value = self.caller.call(&replacer, vec![key, value])?;
         }
-        Value::Number(n) => {
-            if n.is_nan() || n.is_infinite() {
-                return Err("Cannot convert NaN/Infinity to JSON".to_string());
+
+        match &value {
+            Value::Null => self.buf.push_str("null"),
+            Value::Boolean(b) => self.buf.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => {
+                use std::fmt::Write;
+                if n.is_nan() || n.is_infinite() {
+                    return Err("Cannot convert NaN/Infinity to JSON".to_string());
+                }
+                if n.fract() == 0.0 && n.abs() < 1e15 {
+                    let _ = write!(self.buf, "{}", *n as i64);
+                } else {
+                    let _ = write!(self.buf, "{}", n);
+                }
             }
-            if n.fract() == 0.0 && n.abs() < 1e15 {
-                let _ = write!(buf, "{}", *n as i64);
-            } else {
-                let _ = write!(buf, "{}", n);
+            Value::String(s) => self.write_string(s),
+            Value::Array(arr) => {
+                let items = arr.borrow().clone();
+                self.buf.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.buf.push(',');
+                    }
+                    self.newline(depth + 1);
+                    self.write(Value::Number(i as f64), item, depth + 1)?;
+                }
+                if !items.is_empty() {
+                    self.newline(depth);
+                }
+                self.buf.push(']');
             }
-            Ok(())
-        }
-        Value::String(s) => {
-            buf.push('"');
-            for ch in s.chars() {
-                match ch {
-                    '"' => buf.push_str("\\\""),
-                    '\\' => buf.push_str("\\\\"),
-                    '\n' => buf.push_str("\\n"),
-                    '\r' => buf.push_str("\\r"),
-                    '\t' => buf.push_str("\\t"),
-                    c if c.is_control() => {
-                        let _ = write!(buf, "\\u{:04x}", c as u32);
+            Value::Dictionary(dict) => {
+                if dict.borrow().has_instance_keys() {
+                    return Err("Cannot convert a Dict with instance keys to JSON".to_string());
+                }
+                let mut entries: Vec<(String, Value)> = dict
+                    .borrow()
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                if self.options.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+                self.buf.push('{');
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.buf.push(',');
                     }
-                    c => buf.push(c),
+                    self.newline(depth + 1);
+                    self.write_string(k);
+                    self.buf.push(':');
+                    if self.options.indent.is_some() {
+                        self.buf.push(' ');
+                    }
+                    self.write(Value::String(Rc::from(k.as_str())), v, depth + 1)?;
+                }
+                if !entries.is_empty() {
+                    self.newline(depth);
                 }
+                self.buf.push('}');
             }
-            buf.push('"');
-            Ok(())
+            Value::Instance(_) => return Err("toJson must not return another instance".to_string()),
+            _ => return Err(format!("Cannot convert {} to JSON", value.type_name())),
         }
-        Value::Array(arr) => {
-            buf.push('[');
-            let guard = arr.borrow();
-            let mut first = true;
-            for item in guard.iter() {
-                if !first {
-                    buf.push(',');
-                }
-                first = false;
-                write_json_value(item, buf)?;
+        Ok(())
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if let Some(indent) = &self.options.indent {
+            self.buf.push('\n');
+            for _ in 0..depth {
+                self.buf.push_str(indent);
             }
-            buf.push(']');
-            Ok(())
         }
-        Value::Dictionary(dict) => {
-            buf.push('{');
-            let guard = dict.borrow();
-            let mut first = true;
-            for (key, val) in guard.iter() {
-                if !first {
-                    buf.push(',');
-                }
-                first = false;
-
-                buf.push('"');
-                for ch in key.chars() {
-                    match ch {
-                        '"' => buf.push_str("\\\""),
-                        '\\' => buf.push_str("\\\\"),
-                        c => buf.push(c),
-                    }
+    }
+
+    fn write_string(&mut self, s: &str) {
+        use std::fmt::Write;
+        self.buf.push('"');
+        for ch in s.chars() {
+            match ch {
+                '"' => self.buf.push_str("\\\""),
+                '\\' => self.buf.push_str("\\\\"),
+                '\n' => self.buf.push_str("\\n"),
+                '\r' => self.buf.push_str("\\r"),
+                '\t' => self.buf.push_str("\\t"),
+                c if c.is_control() => {
+                    let _ = write!(self.buf, "\\u{:04x}", c as u32);
                 }
-                buf.push_str("\":");
-                write_json_value(val, buf)?;
+                c => self.buf.push(c),
             }
-            buf.push('}');
-            Ok(())
         }
-        _ => Err(format!("Cannot convert {} to JSON", value.type_name())),
+        self.buf.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_json_stringify_options_and_protocol() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "class Point {\n\
+                     fun init(self, x, y) { self.x = x\n self.y = y }\n\
+                     fun toJson(self) { return [self.x, self.y] }\n\
+                     fun fromJson(data) { return Point(data[0], data[1]) }\n\
+                 }\n\
+                 let pretty = Json.stringify({\"b\": 1, \"a\": [true, null]}, 2, true)\n\
+                 let doubled = Json.stringify({\"a\": 1, \"b\": \"x\"}, sortKeys: true, replacer: |k, v| Type.isNumber(v) ? v * 2 : v)\n\
+                 let text = Json.stringify({\"p\": Point(1, 2)})\n\
+                 let p = Json.parse(Json.stringify(Point(3, 4)), Point)\n\
+                 let revived = Json.parse(\"[1, 2]\", |k, v| Type.isNumber(v) ? v + 1 : v)",
+            )
+            .unwrap();
+
+        let result: (String, String, String, f64, Vec<f64>) = engine
+            .eval_as("[pretty, doubled, text, p.y, revived]")
+            .unwrap();
+        assert_eq!(
+            result.0,
+            "{\n  \"a\": [\n    true,\n    null\n  ],\n  \"b\": 1\n}"
+        );
+        assert_eq!(result.1, r#"{"a":2,"b":"x"}"#);
+        assert_eq!(result.2, r#"{"p":[1,2]}"#);
+        assert_eq!(result.3, 4.0);
+        assert_eq!(result.4, vec![2.0, 3.0]);
     }
 }
@@ -0,0 +1,460 @@
+//! Streaming JSON readers
+//! `Json.parseLines` reads JSON Lines one record at a time and `Json.events`
+//! pulls parse events out of a single large document, so neither keeps more
+//! than the current value in memory. An open reader lives in the native handle
+//! of its instance
+
+use super::docs::ClassDoc;
+use super::{check_arity, get_string_arg, json_to_sald_value, native_instance, native_state};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{Class, NativeInstanceFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::rc::Rc;
+
+enum Reader {
+    Lines {
+        input: Box<dyn BufRead>,
+        line: usize,
+    },
+    Events(EventReader),
+}
+
+/// State of a reader instance, `None` once it is closed or exhausted
+type OpenReader = Option<Reader>;
+
+fn create_json_lines_class() -> Class {
+    reader_class("JsonLines")
+}
+
+fn create_json_events_class() -> Class {
+    reader_class("JsonEvents")
+}
+
+fn reader_class(name: &str) -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("next".to_string(), reader_next);
+    instance_methods.insert("close".to_string(), reader_close);
+    callable_methods.insert("forEach".to_string(), reader_for_each);
+
+    let mut class = Class::new_with_instance(name, instance_methods, None);
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `JsonLines` and `JsonEvents` readers
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new("JsonLines", "JSON Lines reader returned by Json.parseLines")
+            .method("next", "next()", "Next record, or null at the end")
+            .method(
+                "forEach",
+                "forEach(fn)",
+                "Call fn with each remaining record",
+            )
+            .method("close", "close()", "Close the file"),
+        ClassDoc::new("JsonEvents", "JSON event reader returned by Json.events")
+            .method(
+                "next",
+                "next()",
+                "Next event dict with type, key and value, or null at the end",
+            )
+            .method(
+                "forEach",
+                "forEach(fn)",
+                "Call fn with each remaining event",
+            )
+            .method("close", "close()", "Close the file"),
+    ]
+}
+
+fn open(path: &str) -> Result<Box<dyn BufRead>, String> {
+    let file = File::open(crate::workspace::resolve(path))
+        .map_err(|e| format!("Cannot open '{}': {}", path, e))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+fn register(reader: Reader, class: Class) -> Value {
+    native_instance(class, Some(reader) as OpenReader)
+}
+
+pub(crate) fn json_parse_lines(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let input = open(&get_string_arg(&args[0], "path")?)?;
+    Ok(register(
+        Reader::Lines { input, line: 0 },
+        create_json_lines_class(),
+    ))
+}
+
+pub(crate) fn json_events(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let input = open(&get_string_arg(&args[0], "path")?)?;
+    Ok(register(
+        Reader::Events(EventReader::new(input)),
+        create_json_events_class(),
+    ))
+}
+
+fn reader_state(recv: &Value) -> Result<Rc<RefCell<OpenReader>>, String> {
+    native_state(recv, "JSON reader")
+}
+
+/// Reads the next item, closing the reader once it is exhausted or fails.
+fn advance(state: &Rc<RefCell<OpenReader>>) -> Result<Value, String> {
+    let mut state = state.borrow_mut();
+    let Some(reader) = state.as_mut() else {
+        return Ok(Value::Null);
+    };
+    let result = match reader {
+        Reader::Lines { input, line } => next_line(input.as_mut(), line),
+        Reader::Events(events) => events.next_event(),
+    };
+    if !matches!(result, Ok(Some(_))) {
+        *state = None;
+    }
+    result.map(|item| item.unwrap_or(Value::Null))
+}
+
+fn reader_next(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    advance(&reader_state(recv)?)
+}
+
+fn reader_close(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    *reader_state(recv)?.borrow_mut() = None;
+    Ok(Value::Null)
+}
+
+fn reader_for_each(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let state = reader_state(recv)?;
+    loop {
+        let item = advance(&state)?;
+        if matches!(item, Value::Null) {
+            return Ok(Value::Null);
+        }
+        caller.call(&args[0], vec![item])?;
+    }
+}
+
+/// Blank lines are skipped; errors name the line they occurred on.
+fn next_line(input: &mut dyn BufRead, line: &mut usize) -> Result<Option<Value>, String> {
+    let mut text = String::new();
+    loop {
+        text.clear();
+        if input.read_line(&mut text).map_err(|e| e.to_string())? == 0 {
+            return Ok(None);
+        }
+        *line += 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let json: serde_json::Value =
+            serde_json::from_str(&text).map_err(|e| format!("Line {}: {}", line, e))?;
+        return json_to_sald_value(&json).map(Some);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    ArrayStart,
+    ArrayNext,
+    ObjectStart,
+    ObjectValue,
+    ObjectNext,
+}
+
+/// Pull parser producing one event per call.
+struct EventReader {
+    input: Box<dyn BufRead>,
+    stack: Vec<State>,
+    finished: bool,
+}
+
+impl EventReader {
+    fn new(input: Box<dyn BufRead>) -> Self {
+        Self {
+            input,
+            stack: Vec::new(),
+            finished: false,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, String> {
+        let buf = self.input.fill_buf().map_err(|e| e.to_string())?;
+        Ok(buf.first().copied())
+    }
+
+    fn bump(&mut self) -> Result<Option<u8>, String> {
+        let byte = self.peek()?;
+        if byte.is_some() {
+            self.input.consume(1);
+        }
+        Ok(byte)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<Option<u8>, String> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.input.consume(1);
+        }
+        Ok(None)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        match self.skip_whitespace()? {
+            Some(byte) if byte == expected => {
+                self.input.consume(1);
+                Ok(())
+            }
+            Some(byte) => Err(format!(
+                "Expected '{}' but found '{}'",
+                expected as char, byte as char
+            )),
+            None => Err(format!(
+                "Expected '{}' but the input ended",
+                expected as char
+            )),
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Value>, String> {
+        let byte = self.skip_whitespace()?;
+        match self.stack.last().copied() {
+            None if self.finished => match byte {
+                None => Ok(None),
+                Some(byte) => Err(format!("Unexpected '{}' after the document", byte as char)),
+            },
+            None => self.value(),
+            Some(State::ArrayStart) if byte == Some(b']') => self.close("endArray"),
+            Some(State::ArrayStart) => self.value(),
+            Some(State::ArrayNext) => match byte {
+                Some(b']') => self.close("endArray"),
+                Some(b',') => {
+                    self.input.consume(1);
+                    self.value()
+                }
+                _ => Err("Expected ',' or ']' in array".to_string()),
+            },
+            Some(State::ObjectStart) if byte == Some(b'}') => self.close("endObject"),
+            Some(State::ObjectStart) => self.key(),
+            Some(State::ObjectNext) => match byte {
+                Some(b'}') => self.close("endObject"),
+                Some(b',') => {
+                    self.input.consume(1);
+                    self.key()
+                }
+                _ => Err("Expected ',' or '}' in object".to_string()),
+            },
+            Some(State::ObjectValue) => {
+                self.expect(b':')?;
+                self.value()
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<Option<Value>, String> {
+        if self.skip_whitespace()? != Some(b'"') {
+            return Err("Expected a string key in object".to_string());
+        }
+        let key = self.string()?;
+        if let Some(top) = self.stack.last_mut() {
+            *top = State::ObjectValue;
+        }
+        Ok(Some(event(
+            "key",
+            Some(("key", Value::String(Rc::from(key)))),
+        )))
+    }
+
+    /// Marks the value that just ended in the enclosing container.
+    fn value_done(&mut self) {
+        match self.stack.last_mut() {
+            Some(state @ (State::ArrayStart | State::ArrayNext)) => *state = State::ArrayNext,
+            Some(state) => *state = State::ObjectNext,
+            None => self.finished = true,
+        }
+    }
+
+    fn close(&mut self, kind: &str) -> Result<Option<Value>, String> {
+        self.input.consume(1);
+        self.stack.pop();
+        self.value_done();
+        Ok(Some(event(kind, None)))
+    }
+
+    fn value(&mut self) -> Result<Option<Value>, String> {
+        let value = match self.skip_whitespace()? {
+            Some(b'{') => {
+                self.input.consume(1);
+                self.stack.push(State::ObjectStart);
+                return Ok(Some(event("startObject", None)));
+            }
+            Some(b'[') => {
+                self.input.consume(1);
+                self.stack.push(State::ArrayStart);
+                return Ok(Some(event("startArray", None)));
+            }
+            Some(b'"') => Value::String(Rc::from(self.string()?)),
+            Some(b't') => self.literal("true", Value::Boolean(true))?,
+            Some(b'f') => self.literal("false", Value::Boolean(false))?,
+            Some(b'n') => self.literal("null", Value::Null)?,
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            Some(byte) => return Err(format!("Unexpected '{}'", byte as char)),
+            None => return Err("Unexpected end of input".to_string()),
+        };
+        self.value_done();
+        Ok(Some(event("value", Some(("value", value)))))
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        for expected in word.bytes() {
+            if self.bump()? != Some(expected) {
+                return Err(format!("Invalid literal, expected '{}'", word));
+            }
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let mut text = String::new();
+        while let Some(byte) = self.peek()? {
+            if !matches!(byte, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                break;
+            }
+            text.push(byte as char);
+            self.input.consume(1);
+        }
+        text.parse::<f64>()
+            .map(Value::Number)
+            .map_err(|_| format!("Invalid number '{}'", text))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .bump()?
+                .and_then(|b| (b as char).to_digit(16))
+                .ok_or("Invalid \\u escape")?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.input.consume(1);
+        let mut bytes = Vec::new();
+        loop {
+            match self.bump()? {
+                None => return Err("Unterminated string".to_string()),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let escaped = match self.bump()? {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let mut code = self.hex4()?;
+                            // Surrogate pairs arrive as two escapes
+                            if (0xD800..0xDC00).contains(&code) {
+                                if self.bump()? != Some(b'\\') || self.bump()? != Some(b'u') {
+                                    return Err("Unpaired surrogate in string".to_string());
+                                }
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err("Unpaired surrogate in string".to_string());
+                                }
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(code).ok_or("Invalid \\u escape")?
+                        }
+                        _ => return Err("Invalid escape in string".to_string()),
+                    };
+                    let mut buf = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut buf).as_bytes());
+                }
+                Some(byte) => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "String is not valid UTF-8".to_string())
+    }
+}
+
+fn event(kind: &str, field: Option<(&str, Value)>) -> Value {
+    let mut dict = FxHashMap::default();
+    dict.insert("type".to_string(), Value::String(Rc::from(kind)));
+    if let Some((name, value)) = field {
+        dict.insert(name.to_string(), value);
+    }
+    Value::Dictionary(Rc::new(RefCell::new(dict.into())))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_json_streaming_readers() {
+        let dir = std::env::temp_dir().join(format!("sald-json-stream-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lines = dir.join("log.jsonl");
+        let doc = dir.join("doc.json");
+        std::fs::write(&lines, "{\"n\": 1}\n\n{\"n\": 2}\n{\"n\": 3}\n").unwrap();
+        std::fs::write(&doc, r#"{"a": [1, "x\u00e9"], "b": {}}"#).unwrap();
+        let path = |p: &std::path::Path| p.to_str().unwrap().replace('\\', "/");
+
+        // doc.json is named relative to the project root
+        let mut engine = Engine::new();
+        engine.vm().set_project_root(&dir);
+        engine
+            .eval(&format!(
+                "let total = 0\n\
+                 let reader = Json.parseLines(\"{}\")\n\
+                 let first = reader.next()\n\
+                 reader.forEach(|r| total = total + r[\"n\"])\n\
+                 let events = []\n\
+                 Json.events(\"doc.json\").forEach(|e| events.push(e[\"type\"]))",
+                path(&lines)
+            ))
+            .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+
+        let result: (f64, f64, Vec<String>) =
+            engine.eval_as("[first[\"n\"], total, events]").unwrap();
+        assert_eq!(result.0, 1.0);
+        assert_eq!(result.1, 5.0);
+        assert_eq!(
+            result.2,
+            [
+                "startObject",
+                "key",
+                "startArray",
+                "value",
+                "value",
+                "endArray",
+                "key",
+                "startObject",
+                "endObject",
+                "endObject"
+            ]
+        );
+    }
+}
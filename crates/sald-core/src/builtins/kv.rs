@@ -0,0 +1,313 @@
+//! Embedded key-value store
+//! Each store is a JSON file holding its entries and their expiry times. Stores
+//! are shared by path within the process and rewritten on every change
+
+use super::docs::ClassDoc;
+use super::{
+    check_arity, check_arity_range, get_number_arg, get_string_arg, json_to_sald_value,
+    native_instance, native_state, sald_value_to_json,
+};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    value: serde_json::Value,
+    /// Unix time in seconds after which the entry is gone
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<f64>,
+}
+
+impl Entry {
+    fn is_live(&self, now: f64) -> bool {
+        self.expires.is_none_or(|expires| expires > now)
+    }
+}
+
+static STORES: Mutex<Option<FxHashMap<PathBuf, BTreeMap<String, Entry>>>> = Mutex::new(None);
+
+pub fn create_kv_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    static_methods.insert("open".to_string(), kv_open);
+
+    instance_methods.insert("get".to_string(), kv_get);
+    instance_methods.insert("set".to_string(), kv_set);
+    instance_methods.insert("delete".to_string(), kv_delete);
+    instance_methods.insert("has".to_string(), kv_has);
+    instance_methods.insert("scan".to_string(), kv_scan);
+    instance_methods.insert("keys".to_string(), kv_keys);
+    instance_methods.insert("clear".to_string(), kv_clear);
+    instance_methods.insert("length".to_string(), kv_length);
+
+    let mut class = Class::new_with_instance("Kv", instance_methods, Some(kv_open));
+    class.native_static_methods = static_methods;
+    class
+}
+
+/// API documentation for the `Kv` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Kv", "Embedded key-value store persisted to a file")
+        .method("open", "open(path)", "Open or create the store at path")
+        .method("get", "get(key, default?)", "Get the value for key")
+        .method(
+            "set",
+            "set(key, value, ttl?)",
+            "Store a value, expiring after ttl seconds",
+        )
+        .method(
+            "delete",
+            "delete(key)",
+            "Remove key, returns whether it existed",
+        )
+        .method("has", "has(key)", "Check if key exists")
+        .method(
+            "scan",
+            "scan(prefix)",
+            "Get [key, value] pairs with a key prefix",
+        )
+        .method(
+            "keys",
+            "keys(prefix?)",
+            "Get keys, optionally with a prefix",
+        )
+        .method("clear", "clear()", "Remove all keys")
+        .method("length", "length()", "Get number of keys")
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+fn load(path: &Path) -> Result<BTreeMap<String, Entry>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) if text.trim().is_empty() => Ok(BTreeMap::new()),
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|e| format!("Corrupt key-value store '{}': {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Cannot open '{}': {}", path.display(), e)),
+    }
+}
+
+/// Writes to a temporary file first so a crash never leaves half a store.
+fn save(path: &Path, entries: &BTreeMap<String, Entry>) -> Result<(), String> {
+    let text = serde_json::to_string(entries).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, text)
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("Cannot write '{}': {}", path.display(), e))
+}
+
+fn kv_open(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = crate::workspace::resolve(&get_string_arg(&args[0], "path")?);
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Cannot create '{}': {}", parent.display(), e))?;
+    }
+    // Canonicalize the path so every handle to one file shares its entries
+    let path = path.canonicalize().unwrap_or(path);
+
+    let mut stores = STORES.lock();
+    let stores = stores.get_or_insert_with(FxHashMap::default);
+    if !stores.contains_key(&path) {
+        stores.insert(path.clone(), load(&path)?);
+    }
+    Ok(native_instance(create_kv_class(), path))
+}
+
+/// Runs `f` on the entries of the store `recv` was opened on, saving them
+/// afterwards if `f` reports a change.
+fn with_store<T>(
+    recv: &Value,
+    f: impl FnOnce(&mut BTreeMap<String, Entry>, f64) -> Result<(T, bool), String>,
+) -> Result<T, String> {
+    let path = native_state::<PathBuf>(recv, "Kv")?.borrow().clone();
+
+    let mut stores = STORES.lock();
+    let entries = stores
+        .get_or_insert_with(FxHashMap::default)
+        .entry(path.clone())
+        .or_default();
+    let now = now();
+    let (result, changed) = f(entries, now)?;
+    if changed {
+        entries.retain(|_, entry| entry.is_live(now));
+        save(&path, entries)?;
+    }
+    Ok(result)
+}
+
+fn kv_get(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let key = get_string_arg(&args[0], "key")?;
+    let value = with_store(recv, |entries, now| {
+        let value = entries
+            .get(&key)
+            .filter(|e| e.is_live(now))
+            .map(|e| e.value.clone());
+        Ok((value, false))
+    })?;
+    match value {
+        Some(value) => json_to_sald_value(&value),
+        None => Ok(args.get(1).cloned().unwrap_or(Value::Null)),
+    }
+}
+
+fn kv_set(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let key = get_string_arg(&args[0], "key")?;
+    let value = sald_value_to_json(&args[1])?;
+    let ttl = match args.get(2) {
+        None | Some(Value::Null) => None,
+        Some(ttl) => Some(get_number_arg(ttl, "ttl")?),
+    };
+    with_store(recv, |entries, now| {
+        let expires = ttl.map(|ttl| now + ttl);
+        entries.insert(key, Entry { value, expires });
+        Ok(((), true))
+    })?;
+    Ok(Value::Null)
+}
+
+fn kv_delete(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let key = get_string_arg(&args[0], "key")?;
+    let existed = with_store(recv, |entries, now| match entries.remove(&key) {
+        Some(entry) => Ok((entry.is_live(now), true)),
+        None => Ok((false, false)),
+    })?;
+    Ok(Value::Boolean(existed))
+}
+
+fn kv_has(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let key = get_string_arg(&args[0], "key")?;
+    let has = with_store(recv, |entries, now| {
+        Ok((entries.get(&key).is_some_and(|e| e.is_live(now)), false))
+    })?;
+    Ok(Value::Boolean(has))
+}
+
+fn kv_scan(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let prefix = get_string_arg(&args[0], "prefix")?;
+    let pairs = with_store(recv, |entries, now| {
+        let pairs: Vec<(String, serde_json::Value)> = entries
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter(|(_, entry)| entry.is_live(now))
+            .map(|(key, entry)| (key.clone(), entry.value.clone()))
+            .collect();
+        Ok((pairs, false))
+    })?;
+
+    let mut result = Vec::with_capacity(pairs.len());
+    for (key, value) in pairs {
+        let pair = vec![Value::String(Rc::from(key)), json_to_sald_value(&value)?];
+        result.push(Value::Array(Rc::new(RefCell::new(pair.into()))));
+    }
+    Ok(Value::Array(Rc::new(RefCell::new(result.into()))))
+}
+
+fn kv_keys(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let prefix = match args.first() {
+        Some(prefix) => get_string_arg(prefix, "prefix")?,
+        None => String::new(),
+    };
+    let keys = with_store(recv, |entries, now| {
+        let keys: Vec<Value> = entries
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter(|(_, entry)| entry.is_live(now))
+            .map(|(key, _)| Value::String(Rc::from(key.as_str())))
+            .collect();
+        Ok((keys, false))
+    })?;
+    Ok(Value::Array(Rc::new(RefCell::new(keys.into()))))
+}
+
+fn kv_clear(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    with_store(recv, |entries, _| {
+        entries.clear();
+        Ok(((), true))
+    })?;
+    Ok(Value::Null)
+}
+
+fn kv_length(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let count = with_store(recv, |entries, now| {
+        Ok((entries.values().filter(|e| e.is_live(now)).count(), false))
+    })?;
+    Ok(Value::Number(count as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_kv_store_persists_and_expires() {
+        let dir = std::env::temp_dir().join(format!("sald-kv-{}", std::process::id()));
+        let path = dir.join("state.json").to_str().unwrap().replace('\\', "/");
+        let mut engine = Engine::new();
+        engine
+            .eval(&format!(
+                "let kv = Kv.open(\"{}\")\n\
+                 kv.set(\"user:1\", {{\"name\": \"ada\"}})\n\
+                 kv.set(\"user:2\", [1, 2])\n\
+                 kv.set(\"session\", \"x\", -1)\n\
+                 kv.delete(\"user:2\")",
+                path
+            ))
+            .unwrap();
+
+        let stored = std::fs::read_to_string(dir.join("state.json")).unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert!(stored.contains("ada") && !stored.contains("session"));
+
+        let result: (f64, String, bool, String) = engine
+            .eval_as(
+                "[kv.scan(\"user:\").length(), kv.get(\"user:1\")[\"name\"], \
+                 kv.has(\"session\"), kv.get(\"missing\", \"none\")]",
+            )
+            .unwrap();
+        assert_eq!(result, (1.0, "ada".to_string(), false, "none".to_string()));
+    }
+
+    #[test]
+    fn test_kv_store_is_named_relative_to_the_project_and_keeps_its_file() {
+        let dir = std::env::temp_dir().join(format!("sald-kv-root-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut engine = Engine::new();
+        engine.vm().set_project_root(&dir);
+        let keys: Vec<String> = engine
+            .eval_as(
+                "let kv = Kv.open(\"data/kv.json\")\n\
+                 kv.set(\"k\", \"v\")\n\
+                 kv.path = \"elsewhere.json\"\n\
+                 kv.set(\"k2\", \"v2\")\n\
+                 Kv.open(\"data/kv.json\").keys()",
+            )
+            .unwrap();
+        let stored = std::fs::read_to_string(dir.join("data/kv.json")).unwrap();
+        let elsewhere = dir.join("elsewhere.json").exists();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(keys, ["k", "k2"]);
+        assert!(stored.contains("v2") && !elsewhere);
+    }
+}
@@ -1,3 +1,4 @@
+use super::docs::ClassDoc;
 use crate::vm::value::{Class, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
 
@@ -23,6 +24,16 @@ pub fn create_math_class() -> Class {
     static_methods.insert("random".to_string(), math_random);
     static_methods.insert("min".to_string(), math_min);
     static_methods.insert("max".to_string(), math_max);
+    static_methods.insert("clamp".to_string(), math_clamp);
+    static_methods.insert("lerp".to_string(), math_lerp);
+    static_methods.insert("hypot".to_string(), math_hypot);
+    static_methods.insert("atan2".to_string(), math_atan2);
+    static_methods.insert("sign".to_string(), math_sign);
+    static_methods.insert("trunc".to_string(), math_trunc);
+    static_methods.insert("mean".to_string(), math_mean);
+    static_methods.insert("median".to_string(), math_median);
+    static_methods.insert("stddev".to_string(), math_stddev);
+    static_methods.insert("percentile".to_string(), math_percentile);
 
     static_fields.insert("PI".to_string(), Value::Number(std::f64::consts::PI));
     static_fields.insert("E".to_string(), Value::Number(std::f64::consts::E));
@@ -33,6 +44,68 @@ pub fn create_math_class() -> Class {
     Class::new_with_static_and_fields("Math", static_methods, static_fields)
 }
 
+/// API documentation for the `Math` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Math", "Mathematical functions and constants")
+        .method("abs", "abs(n)", "Absolute value")
+        .method("floor", "floor(n)", "Round down")
+        .method("ceil", "ceil(n)", "Round up")
+        .method(
+            "round",
+            "round(n, digits?)",
+            "Round to nearest, or to digits decimal places",
+        )
+        .method("sqrt", "sqrt(n)", "Square root")
+        .method("pow", "pow(base, exp)", "Power")
+        .method("sin", "sin(n)", "Sine (radians)")
+        .method("cos", "cos(n)", "Cosine (radians)")
+        .method("tan", "tan(n)", "Tangent (radians)")
+        .method("asin", "asin(n)", "Arc sine")
+        .method("acos", "acos(n)", "Arc cosine")
+        .method("atan", "atan(n)", "Arc tangent")
+        .method("atan2", "atan2(y, x)", "Angle of the point (x, y)")
+        .method(
+            "hypot",
+            "hypot(...args)",
+            "Square root of the sum of squares",
+        )
+        .method("log", "log(n)", "Natural logarithm")
+        .method("log10", "log10(n)", "Base 10 logarithm")
+        .method("exp", "exp(n)", "e^n")
+        .method("random", "random()", "Random number 0-1")
+        .method("min", "min(...args)", "Minimum value")
+        .method("max", "max(...args)", "Maximum value")
+        .method("clamp", "clamp(n, min, max)", "Limit n to [min, max]")
+        .method(
+            "lerp",
+            "lerp(a, b, t)",
+            "Linear interpolation from a (t = 0) to b (t = 1)",
+        )
+        .method("sign", "sign(n)", "-1, 0 or 1 by the sign of n")
+        .method("trunc", "trunc(n)", "Drop the fractional part")
+        .method("mean", "mean(array)", "Arithmetic mean")
+        .method(
+            "median",
+            "median(array)",
+            "Middle value, or mean of the middle two",
+        )
+        .method(
+            "stddev",
+            "stddev(array, sample?)",
+            "Population standard deviation, or sample when sample is true",
+        )
+        .method(
+            "percentile",
+            "percentile(array, p)",
+            "Value at percentile p (0-100), interpolating between ranks",
+        )
+        .property("PI", "π = 3.14159...")
+        .property("E", "e = 2.71828...")
+        .property("INFINITY", "Positive infinity")
+        .property("NEG_INFINITY", "Negative infinity")
+        .property("NAN", "Not a number")
+}
+
 fn get_number(args: &[Value], idx: usize, name: &str) -> Result<f64, String> {
     if idx >= args.len() {
         return Err(format!("Expected at least {} argument(s)", idx + 1));
@@ -64,7 +137,21 @@ fn math_ceil(args: &[Value]) -> Result<Value, String> {
 
 fn math_round(args: &[Value]) -> Result<Value, String> {
     let n = get_number(args, 0, "n")?;
-    Ok(Value::Number(n.round()))
+    if args.len() < 2 || matches!(args[1], Value::Null) {
+        return Ok(Value::Number(n.round()));
+    }
+    let digits = get_number(args, 1, "digits")?;
+    if digits.fract() != 0.0 {
+        return Err("Argument 'digits' must be an integer".to_string());
+    }
+    // Past ±308 the scale is no longer a finite, non-zero f64
+    let scale = 10f64.powi(digits.clamp(-308.0, 308.0) as i32);
+    let scaled = n * scale;
+    if !scaled.is_finite() {
+        // n already has fewer digits than asked for
+        return Ok(Value::Number(n));
+    }
+    Ok(Value::Number(scaled.round() / scale))
 }
 
 fn math_sqrt(args: &[Value]) -> Result<Value, String> {
@@ -187,3 +274,157 @@ fn math_max(args: &[Value]) -> Result<Value, String> {
     }
     Ok(Value::Number(max_val))
 }
+
+fn math_clamp(args: &[Value]) -> Result<Value, String> {
+    let n = get_number(args, 0, "n")?;
+    let min = get_number(args, 1, "min")?;
+    let max = get_number(args, 2, "max")?;
+    if min > max {
+        return Err(format!(
+            "min ({}) must not be greater than max ({})",
+            min, max
+        ));
+    }
+    Ok(Value::Number(n.clamp(min, max)))
+}
+
+fn math_lerp(args: &[Value]) -> Result<Value, String> {
+    let a = get_number(args, 0, "a")?;
+    let b = get_number(args, 1, "b")?;
+    let t = get_number(args, 2, "t")?;
+    Ok(Value::Number(a + (b - a) * t))
+}
+
+fn math_hypot(args: &[Value]) -> Result<Value, String> {
+    let mut sum = 0.0;
+    for i in 0..args.len() {
+        let n = get_number(args, i, "n")?;
+        sum += n * n;
+    }
+    Ok(Value::Number(sum.sqrt()))
+}
+
+fn math_atan2(args: &[Value]) -> Result<Value, String> {
+    let y = get_number(args, 0, "y")?;
+    let x = get_number(args, 1, "x")?;
+    Ok(Value::Number(y.atan2(x)))
+}
+
+fn math_sign(args: &[Value]) -> Result<Value, String> {
+    let n = get_number(args, 0, "n")?;
+    // f64::signum maps 0 to 1, unlike Math.sign elsewhere
+    let sign = if n > 0.0 {
+        1.0
+    } else if n < 0.0 {
+        -1.0
+    } else {
+        n
+    };
+    Ok(Value::Number(sign))
+}
+
+fn math_trunc(args: &[Value]) -> Result<Value, String> {
+    let n = get_number(args, 0, "n")?;
+    Ok(Value::Number(n.trunc()))
+}
+
+/// The numbers in a non-empty array argument.
+fn get_numbers(args: &[Value], idx: usize) -> Result<Vec<f64>, String> {
+    let Some(Value::Array(arr)) = args.get(idx) else {
+        return Err(format!(
+            "Argument 'array' must be an array, got {}",
+            args.get(idx).map_or("nothing", |v| v.type_name())
+        ));
+    };
+    let numbers = arr
+        .borrow()
+        .iter()
+        .map(|item| match item {
+            Value::Number(n) => Ok(*n),
+            other => Err(format!(
+                "Array elements must be numbers, got {}",
+                other.type_name()
+            )),
+        })
+        .collect::<Result<Vec<f64>, String>>()?;
+    if numbers.is_empty() {
+        return Err("Array must not be empty".to_string());
+    }
+    Ok(numbers)
+}
+
+fn mean(numbers: &[f64]) -> f64 {
+    numbers.iter().sum::<f64>() / numbers.len() as f64
+}
+
+/// Linear interpolation between closest ranks; `p` is a fraction in [0, 1].
+fn quantile(numbers: &mut [f64], p: f64) -> f64 {
+    numbers.sort_by(f64::total_cmp);
+    let rank = p * (numbers.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    numbers[lower] + (numbers[upper] - numbers[lower]) * (rank - lower as f64)
+}
+
+fn math_mean(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(mean(&get_numbers(args, 0)?)))
+}
+
+fn math_median(args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(quantile(&mut get_numbers(args, 0)?, 0.5)))
+}
+
+fn math_stddev(args: &[Value]) -> Result<Value, String> {
+    let numbers = get_numbers(args, 0)?;
+    let sample = args.get(1).is_some_and(|v| v.is_truthy());
+    if sample && numbers.len() < 2 {
+        return Err("Sample standard deviation needs at least 2 values".to_string());
+    }
+    let avg = mean(&numbers);
+    let squares: f64 = numbers.iter().map(|n| (n - avg).powi(2)).sum();
+    let count = numbers.len() - sample as usize;
+    Ok(Value::Number((squares / count as f64).sqrt()))
+}
+
+fn math_percentile(args: &[Value]) -> Result<Value, String> {
+    let mut numbers = get_numbers(args, 0)?;
+    let p = get_number(args, 1, "p")?;
+    if !(0.0..=100.0).contains(&p) {
+        return Err(format!("Percentile must be between 0 and 100, got {}", p));
+    }
+    Ok(Value::Number(quantile(&mut numbers, p / 100.0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_math_helpers_and_stats() {
+        let mut engine = Engine::new();
+        let scalars: Vec<f64> = engine
+            .eval_as(
+                "[Math.clamp(12, 0, 10), Math.lerp(2, 4, 0.25), Math.hypot(3, 4), \
+                 Math.sign(-3), Math.trunc(-2.7), Math.round(1.23456, 2)]",
+            )
+            .unwrap();
+        assert_eq!(scalars, [10.0, 2.5, 5.0, -1.0, -2.0, 1.23]);
+        engine.eval("let xs = [2, 4, 4, 4, 5, 5, 7, 9]").unwrap();
+        let stats: Vec<f64> = engine
+            .eval_as("[Math.mean(xs), Math.median(xs), Math.stddev(xs), Math.percentile(xs, 25)]")
+            .unwrap();
+        assert_eq!(stats, [5.0, 4.5, 2.0, 4.0]);
+        assert!(engine.eval("Math.mean([])").is_err());
+    }
+
+    #[test]
+    fn test_math_round_with_extreme_digits() {
+        let mut engine = Engine::new();
+        let rounded: Vec<f64> = engine
+            .eval_as(
+                "[Math.round(1.5, 400), Math.round(Math.pow(10, 300), 20), Math.round(1234.5, -2), \
+                 Math.round(1234.5, -400), Math.round(-1.25, 1)]",
+            )
+            .unwrap();
+        assert_eq!(rounded, [1.5, 1e300, 1200.0, 0.0, -1.3]);
+    }
+}
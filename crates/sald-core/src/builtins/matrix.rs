@@ -0,0 +1,682 @@
+//! Numeric matrices
+//! `Matrix` holds an n-dimensional array of numbers backed by ndarray, so
+//! elementwise arithmetic, matmul and aggregates run natively instead of as
+//! interpreted loops over nested arrays. Binary operations broadcast like
+//! NumPy: trailing dimensions must match or be 1. Contents live in the
+//! instance's native handle and are freed with it
+
+use super::docs::ClassDoc;
+use super::{
+    check_arity, check_arity_min, check_arity_range, get_number_arg, native_instance, native_state,
+};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use ndarray::{ArrayD, Axis, Ix1, Ix2, IxDyn, SliceInfo, SliceInfoElem, Zip};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn create_matrix_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    static_methods.insert("new".to_string(), matrix_new);
+    static_methods.insert("zeros".to_string(), matrix_zeros);
+    static_methods.insert("ones".to_string(), matrix_ones);
+    static_methods.insert("full".to_string(), matrix_full);
+    static_methods.insert("identity".to_string(), matrix_identity);
+    static_methods.insert("range".to_string(), matrix_range);
+
+    instance_methods.insert("shape".to_string(), matrix_shape);
+    instance_methods.insert("ndim".to_string(), matrix_ndim);
+    instance_methods.insert("size".to_string(), matrix_size);
+    instance_methods.insert("get".to_string(), matrix_get);
+    instance_methods.insert("set".to_string(), matrix_set);
+    instance_methods.insert("fill".to_string(), matrix_fill);
+    instance_methods.insert("add".to_string(), matrix_add);
+    instance_methods.insert("sub".to_string(), matrix_sub);
+    instance_methods.insert("mul".to_string(), matrix_mul);
+    instance_methods.insert("div".to_string(), matrix_div);
+    instance_methods.insert("pow".to_string(), matrix_pow);
+    instance_methods.insert("matmul".to_string(), matrix_matmul);
+    instance_methods.insert("transpose".to_string(), matrix_transpose);
+    instance_methods.insert("reshape".to_string(), matrix_reshape);
+    instance_methods.insert("slice".to_string(), matrix_slice);
+    instance_methods.insert("sum".to_string(), matrix_sum);
+    instance_methods.insert("mean".to_string(), matrix_mean);
+    instance_methods.insert("min".to_string(), matrix_min);
+    instance_methods.insert("max".to_string(), matrix_max);
+    instance_methods.insert("toArray".to_string(), matrix_to_array);
+    instance_methods.insert("toJson".to_string(), matrix_to_array);
+    instance_methods.insert("toString".to_string(), matrix_to_string);
+    callable_methods.insert("map".to_string(), matrix_map);
+
+    let mut class = Class::new_with_instance("Matrix", instance_methods, Some(matrix_new));
+    class.native_static_methods = static_methods;
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Matrix` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "Matrix",
+        "N-dimensional numeric arrays with native elementwise and linear algebra operations",
+    )
+    .method(
+        "new",
+        "Matrix(values)",
+        "Create from a number, nested arrays of equal length, or another Matrix",
+    )
+    .method("zeros", "Matrix.zeros(shape)", "Matrix of zeros")
+    .method("ones", "Matrix.ones(shape)", "Matrix of ones")
+    .method(
+        "full",
+        "Matrix.full(shape, value)",
+        "Matrix with every element set to value",
+    )
+    .method("identity", "Matrix.identity(n)", "n by n identity matrix")
+    .method(
+        "range",
+        "Matrix.range(start, end?, step?)",
+        "1-D matrix of numbers from start up to end",
+    )
+    .method("shape", "shape()", "Length of each dimension")
+    .method("ndim", "ndim()", "Number of dimensions")
+    .method("size", "size()", "Number of elements")
+    .method(
+        "get",
+        "get(...indices)",
+        "Element at one index per dimension, negative from the end",
+    )
+    .method(
+        "set",
+        "set(...indices, value)",
+        "Replace the element at one index per dimension",
+    )
+    .method(
+        "fill",
+        "fill(value)",
+        "Set every element, returns the matrix",
+    )
+    .method(
+        "add",
+        "add(other)",
+        "Elementwise sum with a number, array or Matrix",
+    )
+    .method("sub", "sub(other)", "Elementwise difference")
+    .method("mul", "mul(other)", "Elementwise product")
+    .method("div", "div(other)", "Elementwise quotient")
+    .method("pow", "pow(other)", "Elementwise power")
+    .method(
+        "matmul",
+        "matmul(other)",
+        "Matrix product of 1-D or 2-D operands; two vectors give a number",
+    )
+    .method("transpose", "transpose()", "Dimensions in reverse order")
+    .method(
+        "reshape",
+        "reshape(shape)",
+        "Same elements in row-major order with a new shape",
+    )
+    .method(
+        "slice",
+        "slice(ranges)",
+        "Sub-matrix; per dimension an index, [start, end?, step?] or null for all",
+    )
+    .method(
+        "sum",
+        "sum(axis?)",
+        "Sum of all elements, or a Matrix summed along axis",
+    )
+    .method(
+        "mean",
+        "mean(axis?)",
+        "Mean of all elements, or a Matrix averaged along axis",
+    )
+    .method("min", "min()", "Smallest element, null when empty")
+    .method("max", "max()", "Largest element, null when empty")
+    .method("map", "map(fn)", "New matrix of fn(element)")
+    .method("toArray", "toArray()", "Nested arrays of numbers")
+}
+
+fn register(data: ArrayD<f64>) -> Value {
+    native_instance(create_matrix_class(), data)
+}
+
+fn matrix_data(value: &Value) -> Option<Rc<RefCell<ArrayD<f64>>>> {
+    match value {
+        Value::Instance(inst) => inst.borrow().native::<ArrayD<f64>>(),
+        _ => None,
+    }
+}
+
+fn with_matrix<T>(recv: &Value, f: impl FnOnce(&mut ArrayD<f64>) -> T) -> Result<T, String> {
+    let data = native_state::<ArrayD<f64>>(recv, "Matrix")?;
+    let mut data = data.borrow_mut();
+    Ok(f(&mut data))
+}
+
+/// Converts a number, nested arrays or a Matrix into an ndarray.
+fn to_ndarray(value: &Value) -> Result<ArrayD<f64>, String> {
+    if let Some(data) = matrix_data(value) {
+        return Ok(data.borrow().clone());
+    }
+
+    // The shape follows the first element at each depth; every other
+    // element must agree with it.
+    let mut shape = Vec::new();
+    let mut probe = value.clone();
+    while let Value::Array(items) = probe {
+        let items = items.borrow();
+        shape.push(items.len());
+        probe = match items.first() {
+            Some(first) => first.clone(),
+            None => break,
+        };
+    }
+
+    let mut data = Vec::with_capacity(shape.iter().product());
+    flatten(value, &shape, &mut data)?;
+    ArrayD::from_shape_vec(IxDyn(&shape), data).map_err(|e| e.to_string())
+}
+
+fn flatten(value: &Value, shape: &[usize], data: &mut Vec<f64>) -> Result<(), String> {
+    match (value, shape.split_first()) {
+        (Value::Number(n), None) => {
+            data.push(*n);
+            Ok(())
+        }
+        (Value::Array(items), Some((len, rest))) if items.borrow().len() == *len => {
+            for item in items.borrow().iter() {
+                flatten(item, rest, data)?;
+            }
+            Ok(())
+        }
+        (Value::Array(_), _) => Err("Nested arrays must all have the same length".to_string()),
+        (other, _) => Err(format!(
+            "Matrix elements must be numbers, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn to_nested(data: &ArrayD<f64>) -> Value {
+    if data.ndim() == 0 {
+        return Value::Number(data.iter().next().copied().unwrap_or(0.0));
+    }
+    let items = data
+        .axis_iter(Axis(0))
+        .map(|row| to_nested(&row.to_owned()))
+        .collect();
+    Value::Array(Rc::new(RefCell::new(items)))
+}
+
+fn shape_arg(value: &Value) -> Result<Vec<usize>, String> {
+    let dims = match value {
+        Value::Number(_) => vec![value.clone()],
+        Value::Array(items) => items.borrow().to_vec(),
+        other => {
+            return Err(format!(
+                "Argument 'shape' must be a number or array, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    let shape = dims
+        .iter()
+        .map(|dim| match dim {
+            Value::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Ok(*n as usize),
+            _ => Err("Shape dimensions must be non-negative integers".to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    check_size(&shape)?;
+    Ok(shape)
+}
+
+/// Errors when a matrix of `shape` could not be addressed, which ndarray
+/// would otherwise panic on
+fn check_size(shape: &[usize]) -> Result<(), String> {
+    shape
+        .iter()
+        .try_fold(std::mem::size_of::<f64>(), |bytes, &dim| bytes.checked_mul(dim))
+        .filter(|&bytes| bytes <= isize::MAX as usize)
+        .map(|_| ())
+        .ok_or_else(|| format!("Shape {:?} is too large", shape))
+}
+
+fn axis_arg(value: &Value, ndim: usize) -> Result<Axis, String> {
+    let axis = get_number_arg(value, "axis")?;
+    if axis < 0.0 || axis.fract() != 0.0 || axis as usize >= ndim {
+        return Err(format!(
+            "Axis {} is out of range for a {}-dimensional matrix",
+            axis, ndim
+        ));
+    }
+    Ok(Axis(axis as usize))
+}
+
+/// Resolves a possibly negative index against `len`.
+fn index_arg(value: &Value, len: usize) -> Result<usize, String> {
+    let index = get_number_arg(value, "index")?;
+    let resolved = if index < 0.0 {
+        index + len as f64
+    } else {
+        index
+    };
+    if resolved < 0.0 || resolved >= len as f64 || index.fract() != 0.0 {
+        return Err(format!(
+            "Index {} out of bounds for dimension of length {}",
+            index, len
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+fn indices(args: &[Value], shape: &[usize]) -> Result<Vec<usize>, String> {
+    if args.len() != shape.len() {
+        return Err(format!(
+            "Expected {} indices for a {}-dimensional matrix but got {}",
+            shape.len(),
+            shape.len(),
+            args.len()
+        ));
+    }
+    args.iter()
+        .zip(shape)
+        .map(|(index, len)| index_arg(index, *len))
+        .collect()
+}
+
+/// The shape both operands broadcast to, if they are compatible.
+fn broadcast_shape(a: &[usize], b: &[usize]) -> Result<Vec<usize>, String> {
+    let ndim = a.len().max(b.len());
+    let dim =
+        |shape: &[usize], i: usize| (i + shape.len()).checked_sub(ndim).map_or(1, |i| shape[i]);
+    (0..ndim)
+        .map(|i| match (dim(a, i), dim(b, i)) {
+            (x, y) if x == y || y == 1 => Ok(x),
+            (1, y) => Ok(y),
+            _ => Err(format!("Cannot broadcast shapes {:?} and {:?}", a, b)),
+        })
+        .collect()
+}
+
+fn elementwise(recv: &Value, args: &[Value], op: fn(f64, f64) -> f64) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let a = with_matrix(recv, |data| data.clone())?;
+    let result = match &args[0] {
+        Value::Number(n) => a.mapv(|x| op(x, *n)),
+        other => {
+            let b = to_ndarray(other)?;
+            let shape = IxDyn(&broadcast_shape(a.shape(), b.shape())?);
+            // Both views exist: the shape was checked against each operand
+            let (a, b) = (
+                a.broadcast(shape.clone()).expect("broadcastable"),
+                b.broadcast(shape).expect("broadcastable"),
+            );
+            Zip::from(&a).and(&b).map_collect(|&x, &y| op(x, y))
+        }
+    };
+    Ok(register(result))
+}
+
+fn matrix_new(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(to_ndarray(&args[0])?))
+}
+
+fn matrix_zeros(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(ArrayD::zeros(IxDyn(&shape_arg(&args[0])?))))
+}
+
+fn matrix_ones(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(register(ArrayD::ones(IxDyn(&shape_arg(&args[0])?))))
+}
+
+fn matrix_full(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let value = get_number_arg(&args[1], "value")?;
+    Ok(register(ArrayD::from_elem(
+        IxDyn(&shape_arg(&args[0])?),
+        value,
+    )))
+}
+
+fn matrix_identity(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let n = match shape_arg(&args[0])?.as_slice() {
+        [n] => *n,
+        _ => return Err("Argument 'n' must be a non-negative integer".to_string()),
+    };
+    check_size(&[n, n])?;
+    Ok(register(ndarray::Array2::eye(n).into_dyn()))
+}
+
+fn matrix_range(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 3, args.len())?;
+    let (start, end) = match args.get(1) {
+        Some(end) => (
+            get_number_arg(&args[0], "start")?,
+            get_number_arg(end, "end")?,
+        ),
+        None => (0.0, get_number_arg(&args[0], "end")?),
+    };
+    let step = match args.get(2) {
+        Some(step) => get_number_arg(step, "step")?,
+        None => 1.0,
+    };
+    if step == 0.0 {
+        return Err("Argument 'step' cannot be 0".to_string());
+    }
+    Ok(register(
+        ndarray::Array1::range(start, end, step).into_dyn(),
+    ))
+}
+
+fn matrix_shape(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let shape = with_matrix(recv, |data| {
+        data.shape()
+            .iter()
+            .map(|len| Value::Number(*len as f64))
+            .collect()
+    })?;
+    Ok(Value::Array(Rc::new(RefCell::new(shape))))
+}
+
+fn matrix_ndim(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(with_matrix(recv, |data| data.ndim())? as f64))
+}
+
+fn matrix_size(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(with_matrix(recv, |data| data.len())? as f64))
+}
+
+fn matrix_get(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    with_matrix(recv, |data| {
+        let index = indices(args, data.shape())?;
+        Ok(Value::Number(data[IxDyn(&index)]))
+    })?
+}
+
+fn matrix_set(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_min(1, args.len())?;
+    let (value, index) = args.split_last().expect("at least one argument");
+    let value = get_number_arg(value, "value")?;
+    with_matrix(recv, |data| {
+        let index = indices(index, data.shape())?;
+        data[IxDyn(&index)] = value;
+        Ok(Value::Null)
+    })?
+}
+
+fn matrix_fill(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let value = get_number_arg(&args[0], "value")?;
+    with_matrix(recv, |data| data.fill(value))?;
+    Ok(recv.clone())
+}
+
+fn matrix_add(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    elementwise(recv, args, |a, b| a + b)
+}
+
+fn matrix_sub(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    elementwise(recv, args, |a, b| a - b)
+}
+
+fn matrix_mul(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    elementwise(recv, args, |a, b| a * b)
+}
+
+fn matrix_div(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    elementwise(recv, args, |a, b| a / b)
+}
+
+fn matrix_pow(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    elementwise(recv, args, f64::powf)
+}
+
+fn matrix_matmul(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let a = with_matrix(recv, |data| data.clone())?;
+    let b = to_ndarray(&args[0])?;
+    let mismatch = || {
+        format!(
+            "Cannot multiply matrices of shapes {:?} and {:?}",
+            a.shape(),
+            b.shape()
+        )
+    };
+    if a.shape().last() != b.shape().first() {
+        return Err(mismatch());
+    }
+    let result = match (a.ndim(), b.ndim()) {
+        (1, 1) => {
+            let (a, b) = (vector(a)?, vector(b)?);
+            return Ok(Value::Number(a.dot(&b)));
+        }
+        (2, 1) => matrix2(a)?.dot(&vector(b)?).into_dyn(),
+        (1, 2) => vector(a)?.dot(&matrix2(b)?).into_dyn(),
+        (2, 2) => matrix2(a)?.dot(&matrix2(b)?).into_dyn(),
+        _ => return Err("matmul() supports 1-D and 2-D matrices".to_string()),
+    };
+    Ok(register(result))
+}
+
+fn vector(data: ArrayD<f64>) -> Result<ndarray::Array1<f64>, String> {
+    data.into_dimensionality::<Ix1>().map_err(|e| e.to_string())
+}
+
+fn matrix2(data: ArrayD<f64>) -> Result<ndarray::Array2<f64>, String> {
+    data.into_dimensionality::<Ix2>().map_err(|e| e.to_string())
+}
+
+fn matrix_transpose(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let transposed = with_matrix(recv, |data| data.t().as_standard_layout().into_owned())?;
+    Ok(register(transposed))
+}
+
+fn matrix_reshape(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let shape = shape_arg(&args[0])?;
+    let reshaped = with_matrix(recv, |data| {
+        if shape.iter().product::<usize>() != data.len() {
+            return Err(format!(
+                "Cannot reshape {} elements into shape {:?}",
+                data.len(),
+                shape
+            ));
+        }
+        data.to_shape(IxDyn(&shape))
+            .map(|view| view.into_owned())
+            .map_err(|e| e.to_string())
+    })??;
+    Ok(register(reshaped))
+}
+
+/// One `slice()` entry, clamped to the dimension so ndarray never panics.
+fn slice_elem(value: &Value, len: usize) -> Result<SliceInfoElem, String> {
+    let bound = |value: Option<&Value>, default: usize| -> Result<usize, String> {
+        match value {
+            None | Some(Value::Null) => Ok(default),
+            Some(value) => {
+                let n = get_number_arg(value, "bound")?;
+                let n = if n < 0.0 { n + len as f64 } else { n };
+                Ok(n.clamp(0.0, len as f64) as usize)
+            }
+        }
+    };
+    match value {
+        Value::Null => Ok(SliceInfoElem::Slice {
+            start: 0,
+            end: None,
+            step: 1,
+        }),
+        Value::Number(_) => Ok(SliceInfoElem::Index(index_arg(value, len)? as isize)),
+        Value::Array(range) => {
+            let range = range.borrow();
+            if range.is_empty() || range.len() > 3 {
+                return Err("Slice ranges must be [start, end?, step?]".to_string());
+            }
+            let start = bound(range.first(), 0)?;
+            let end = bound(range.get(1), len)?.max(start);
+            let step = match range.get(2) {
+                None | Some(Value::Null) => 1,
+                Some(step) => get_number_arg(step, "step")? as isize,
+            };
+            if step == 0 {
+                return Err("Slice step cannot be 0".to_string());
+            }
+            Ok(SliceInfoElem::Slice {
+                start: start as isize,
+                end: Some(end as isize),
+                step,
+            })
+        }
+        other => Err(format!(
+            "Slice ranges must be numbers, arrays or null, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn matrix_slice(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let Value::Array(ranges) = &args[0] else {
+        return Err(format!(
+            "Argument 'ranges' must be an array, got {}",
+            args[0].type_name()
+        ));
+    };
+    let ranges = ranges.borrow().clone();
+    let sliced = with_matrix(recv, |data| {
+        if ranges.len() > data.ndim() {
+            return Err(format!(
+                "Got {} ranges for a {}-dimensional matrix",
+                ranges.len(),
+                data.ndim()
+            ));
+        }
+        let mut elems = Vec::with_capacity(data.ndim());
+        for (axis, len) in data.shape().iter().enumerate() {
+            elems.push(slice_elem(ranges.get(axis).unwrap_or(&Value::Null), *len)?);
+        }
+        let info = SliceInfo::<Vec<SliceInfoElem>, IxDyn, IxDyn>::try_from(elems)
+            .map_err(|e| e.to_string())?;
+        Ok(data.slice(info).to_owned())
+    })??;
+    Ok(register(sliced))
+}
+
+fn matrix_sum(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let Some(axis) = args.first().filter(|axis| !axis.is_null()) else {
+        return Ok(Value::Number(with_matrix(recv, |data| data.sum())?));
+    };
+    let summed = with_matrix(recv, |data| {
+        Ok::<_, String>(data.sum_axis(axis_arg(axis, data.ndim())?))
+    })??;
+    Ok(register(summed))
+}
+
+fn matrix_mean(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let Some(axis) = args.first().filter(|axis| !axis.is_null()) else {
+        let mean = with_matrix(recv, |data| data.mean())?;
+        return Ok(mean.map_or(Value::Null, Value::Number));
+    };
+    let averaged = with_matrix(recv, |data| {
+        data.mean_axis(axis_arg(axis, data.ndim())?)
+            .ok_or_else(|| "Cannot take the mean along an empty axis".to_string())
+    })??;
+    Ok(register(averaged))
+}
+
+fn matrix_min(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let min = with_matrix(recv, |data| data.iter().copied().reduce(f64::min))?;
+    Ok(min.map_or(Value::Null, Value::Number))
+}
+
+fn matrix_max(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let max = with_matrix(recv, |data| data.iter().copied().reduce(f64::max))?;
+    Ok(max.map_or(Value::Null, Value::Number))
+}
+
+fn matrix_to_array(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    with_matrix(recv, |data| to_nested(data))
+}
+
+fn matrix_to_string(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let nested = with_matrix(recv, |data| to_nested(data))?;
+    Ok(Value::String(Rc::from(format!("Matrix {}", nested))))
+}
+
+fn matrix_map(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    // Copied out so the callback may use the matrix itself
+    let mut data = with_matrix(recv, |data| data.clone())?;
+    for element in data.iter_mut() {
+        *element = match caller.call(&args[0], vec![Value::Number(*element)])? {
+            Value::Number(n) => n,
+            other => {
+                return Err(format!(
+                    "map() function must return a number, got {}",
+                    other.type_name()
+                ))
+            }
+        };
+    }
+    Ok(register(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_matrix_operations() {
+        let mut engine = Engine::new();
+        engine.eval("let m = Matrix([[1, 2], [3, 4]])").unwrap();
+        let product: Vec<Vec<f64>> = engine
+            .eval_as("m.matmul(Matrix.identity(2).mul(2)).add([10, 20]).toArray()")
+            .unwrap();
+        assert_eq!(product, [[12.0, 24.0], [16.0, 28.0]]);
+        let reshaped: Vec<Vec<f64>> = engine
+            .eval_as("Matrix.range(6).reshape([2, 3]).slice([null, [1]]).transpose().toArray()")
+            .unwrap();
+        assert_eq!(reshaped, [[1.0, 4.0], [2.0, 5.0]]);
+        let aggregates: Vec<f64> = engine
+            .eval_as("[m.sum(), m.mean(), m.max(), m.get(-1, 0), m.sum(0).get(1), m.map(|x| x * x).sum()]")
+            .unwrap();
+        assert_eq!(aggregates, [10.0, 2.5, 4.0, 3.0, 6.0, 30.0]);
+        assert!(engine.eval("m.add(Matrix.zeros([3]))").is_err());
+        assert!(engine.eval("Matrix([[1, 2], [3]])").is_err());
+    }
+
+    #[test]
+    fn test_matrix_shape_too_large() {
+        let mut engine = Engine::new();
+        for source in [
+            "Matrix.zeros([10000000000, 10000000000])",
+            "Matrix.ones([4294967296, 4294967296, 2])",
+            "Matrix.full([10000000000, 10000000000], 1)",
+            "Matrix.identity(10000000000)",
+            "Matrix.range(4).reshape([10000000000, 10000000000])",
+        ] {
+            let err = engine.eval(source).unwrap_err();
+            assert!(err.message().contains("is too large"), "{}: {}", source, err);
+        }
+    }
+}
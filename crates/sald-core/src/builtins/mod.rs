@@ -1,77 +1,179 @@
 mod array;
 mod boolean;
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(crate) mod clock;
+mod code;
+pub(crate) mod collections;
 mod console;
-mod dict;
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub(crate) mod date;
+mod deque;
+pub(crate) mod dict;
+pub mod docs;
+pub(crate) mod function;
+mod heap;
+pub(crate) mod iter;
 mod json;
 mod math;
+mod matrix;
+pub(crate) mod module;
+mod msgpack;
 mod null;
 mod number;
+mod random;
+mod reflect;
 mod regex;
 mod string;
+mod toml;
 mod types;
+mod warning;
+mod yaml;
 
+#[cfg(not(target_arch = "wasm32"))]
+mod archive;
+#[cfg(not(target_arch = "wasm32"))]
+mod args;
 #[cfg(not(target_arch = "wasm32"))]
 mod channel;
 #[cfg(not(target_arch = "wasm32"))]
+mod child;
+#[cfg(not(target_arch = "wasm32"))]
+mod compress;
+#[cfg(not(target_arch = "wasm32"))]
+mod cron;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod db;
+#[cfg(not(target_arch = "wasm32"))]
 mod crypto;
 #[cfg(not(target_arch = "wasm32"))]
-mod date;
+mod encoding;
+#[cfg(not(target_arch = "wasm32"))]
+mod env;
 #[cfg(not(target_arch = "wasm32"))]
 mod ffi;
 #[cfg(not(target_arch = "wasm32"))]
 mod file;
 #[cfg(not(target_arch = "wasm32"))]
+mod file_stream;
+#[cfg(target_arch = "wasm32")]
+mod js;
+#[cfg(not(target_arch = "wasm32"))]
+mod json_stream;
+#[cfg(not(target_arch = "wasm32"))]
+mod kv;
+#[cfg(not(target_arch = "wasm32"))]
 mod path;
 #[cfg(not(target_arch = "wasm32"))]
 mod process;
 #[cfg(not(target_arch = "wasm32"))]
+mod profiler;
+#[cfg(not(target_arch = "wasm32"))]
 mod promise;
 #[cfg(not(target_arch = "wasm32"))]
+mod readline;
+#[cfg(not(target_arch = "wasm32"))]
 mod system;
 #[cfg(not(target_arch = "wasm32"))]
+mod temp;
+#[cfg(not(target_arch = "wasm32"))]
+mod term;
+#[cfg(not(target_arch = "wasm32"))]
 mod test;
 #[cfg(not(target_arch = "wasm32"))]
 mod timer;
+#[cfg(not(target_arch = "wasm32"))]
+mod uuid;
+#[cfg(target_arch = "wasm32")]
+mod vfs;
+#[cfg(target_arch = "wasm32")]
+mod web;
 
-use crate::vm::value::Value;
-use rustc_hash::FxHashMap;
+use crate::vm::value::{Class, Dict, DisplayText, Instance, Value};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::RefCell;
 use std::rc::Rc;
 
 pub use array::create_array_class;
 pub use boolean::create_boolean_class;
-pub use console::create_console_class;
+pub use code::create_code_class;
+pub use collections::{create_counter_class, create_map_class, create_set_class};
+pub(crate) use console::write_stderr;
+pub use console::{create_console_class, ConsoleStream};
+pub use deque::create_deque_class;
 pub use dict::create_dict_class;
+pub use function::create_function_class;
+pub use heap::create_heap_class;
+pub use iter::create_iter_class;
 pub use json::create_json_class;
+pub(crate) use json::{integralize_numbers, json_to_sald_value, sald_value_to_json};
 pub use math::create_math_class;
+pub use matrix::create_matrix_class;
+pub use module::create_module_class;
+pub use msgpack::create_msgpack_class;
 pub use null::create_null_class;
 pub use number::create_number_class;
+pub use random::create_random_class;
+pub use reflect::create_reflect_class;
 pub use regex::create_regex_class;
 pub use string::create_string_class;
+pub use toml::create_toml_class;
 pub use types::create_type_class;
+pub(crate) use types::{array_mut, dict_mut, is_frozen};
+pub use warning::create_warning_class;
+pub use yaml::create_yaml_class;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use archive::create_archive_class;
+#[cfg(not(target_arch = "wasm32"))]
+pub use args::{args_exit, create_args_class, ArgsExit};
 #[cfg(not(target_arch = "wasm32"))]
 pub use channel::create_channel_class;
 #[cfg(not(target_arch = "wasm32"))]
+pub use compress::create_compress_class;
+#[cfg(not(target_arch = "wasm32"))]
+pub use console::set_console_writer;
+#[cfg(not(target_arch = "wasm32"))]
+pub use cron::create_cron_class;
+#[cfg(not(target_arch = "wasm32"))]
 pub use crypto::create_crypto_class;
 #[cfg(not(target_arch = "wasm32"))]
 pub use date::create_date_class;
 #[cfg(not(target_arch = "wasm32"))]
+pub use db::create_db_class;
+#[cfg(not(target_arch = "wasm32"))]
+pub use encoding::create_encoding_class;
+#[cfg(not(target_arch = "wasm32"))]
 pub use ffi::create_ffi_namespace;
 #[cfg(not(target_arch = "wasm32"))]
 pub use file::create_file_class;
+#[cfg(target_arch = "wasm32")]
+pub use js::{create_js_class, create_js_object_class};
 #[cfg(not(target_arch = "wasm32"))]
+pub use kv::create_kv_class;
 #[cfg(not(target_arch = "wasm32"))]
 pub use path::create_path_class;
 #[cfg(not(target_arch = "wasm32"))]
-pub use process::create_process_class;
+pub use process::{create_process_class, set_script_args};
+#[cfg(not(target_arch = "wasm32"))]
+pub use profiler::create_profiler_class;
 #[cfg(not(target_arch = "wasm32"))]
 pub use promise::create_promise_class;
 #[cfg(not(target_arch = "wasm32"))]
+pub use readline::create_readline_class;
+#[cfg(not(target_arch = "wasm32"))]
 pub use system::create_system_class;
 #[cfg(not(target_arch = "wasm32"))]
+pub use term::create_term_class;
+#[cfg(not(target_arch = "wasm32"))]
 pub use test::create_test_class;
 #[cfg(not(target_arch = "wasm32"))]
-pub use timer::create_timer_class;
+pub use timer::{create_timer_class, run_pending_timers};
+#[cfg(not(target_arch = "wasm32"))]
+pub use uuid::create_uuid_class;
+#[cfg(target_arch = "wasm32")]
+pub use vfs::{create_file_class, create_process_class, create_system_class};
+#[cfg(target_arch = "wasm32")]
+pub use web::{create_http_class, create_timer_class};
 
 pub type NativeStaticFn = fn(&[Value]) -> Result<Value, String>;
 
@@ -104,6 +206,10 @@ pub fn create_builtin_classes() -> FxHashMap<String, Value> {
         "Dict".to_string(),
         Value::Class(Rc::new(create_dict_class())),
     );
+    classes.insert(
+        "Function".to_string(),
+        Value::Class(Rc::new(create_function_class())),
+    );
     classes.insert(
         "Console".to_string(),
         Value::Class(Rc::new(create_console_class())),
@@ -124,6 +230,60 @@ pub fn create_builtin_classes() -> FxHashMap<String, Value> {
         "Regex".to_string(),
         Value::Class(Rc::new(create_regex_class())),
     );
+    classes.insert(
+        "Yaml".to_string(),
+        Value::Class(Rc::new(create_yaml_class())),
+    );
+    classes.insert(
+        "Toml".to_string(),
+        Value::Class(Rc::new(create_toml_class())),
+    );
+    classes.insert(
+        "MsgPack".to_string(),
+        Value::Class(Rc::new(create_msgpack_class())),
+    );
+    classes.insert(
+        "Random".to_string(),
+        Value::Class(Rc::new(create_random_class())),
+    );
+    classes.insert("Set".to_string(), Value::Class(Rc::new(create_set_class())));
+    classes.insert("Map".to_string(), Value::Class(Rc::new(create_map_class())));
+    classes.insert(
+        "Counter".to_string(),
+        Value::Class(Rc::new(create_counter_class())),
+    );
+    classes.insert(
+        "Deque".to_string(),
+        Value::Class(Rc::new(create_deque_class())),
+    );
+    classes.insert(
+        "Heap".to_string(),
+        Value::Class(Rc::new(create_heap_class())),
+    );
+    classes.insert(
+        "Iter".to_string(),
+        Value::Class(Rc::new(create_iter_class())),
+    );
+    classes.insert(
+        "Matrix".to_string(),
+        Value::Class(Rc::new(create_matrix_class())),
+    );
+    classes.insert(
+        "Warning".to_string(),
+        Value::Class(Rc::new(create_warning_class())),
+    );
+    classes.insert(
+        "Module".to_string(),
+        Value::Class(Rc::new(create_module_class())),
+    );
+    classes.insert(
+        "Code".to_string(),
+        Value::Class(Rc::new(create_code_class())),
+    );
+    classes.insert(
+        "Reflect".to_string(),
+        Value::Class(Rc::new(create_reflect_class())),
+    );
 
     #[cfg(not(target_arch = "wasm32"))]
     {
@@ -139,6 +299,10 @@ pub fn create_builtin_classes() -> FxHashMap<String, Value> {
             "Timer".to_string(),
             Value::Class(Rc::new(create_timer_class())),
         );
+        classes.insert(
+            "Cron".to_string(),
+            Value::Class(Rc::new(create_cron_class())),
+        );
         classes.insert(
             "Path".to_string(),
             Value::Class(Rc::new(create_path_class())),
@@ -169,6 +333,65 @@ pub fn create_builtin_classes() -> FxHashMap<String, Value> {
             "Test".to_string(),
             Value::Class(Rc::new(create_test_class())),
         );
+        classes.insert(
+            "Profiler".to_string(),
+            Value::Class(Rc::new(create_profiler_class())),
+        );
+        classes.insert("Kv".to_string(), Value::Class(Rc::new(create_kv_class())));
+        classes.insert(
+            "Compress".to_string(),
+            Value::Class(Rc::new(create_compress_class())),
+        );
+        classes.insert(
+            "Archive".to_string(),
+            Value::Class(Rc::new(create_archive_class())),
+        );
+        classes.insert("Db".to_string(), Value::Class(Rc::new(create_db_class())));
+        classes.insert(
+            "Encoding".to_string(),
+            Value::Class(Rc::new(create_encoding_class())),
+        );
+        classes.insert(
+            "Uuid".to_string(),
+            Value::Class(Rc::new(create_uuid_class())),
+        );
+        classes.insert(
+            "Term".to_string(),
+            Value::Class(Rc::new(create_term_class())),
+        );
+        classes.insert(
+            "Readline".to_string(),
+            Value::Class(Rc::new(create_readline_class())),
+        );
+        classes.insert(
+            "Args".to_string(),
+            Value::Class(Rc::new(create_args_class())),
+        );
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        classes.insert(
+            "File".to_string(),
+            Value::Class(Rc::new(create_file_class())),
+        );
+        classes.insert(
+            "Process".to_string(),
+            Value::Class(Rc::new(create_process_class())),
+        );
+        classes.insert(
+            "Timer".to_string(),
+            Value::Class(Rc::new(create_timer_class())),
+        );
+        classes.insert(
+            "System".to_string(),
+            Value::Class(Rc::new(create_system_class())),
+        );
+        classes.insert(
+            "Http".to_string(),
+            Value::Class(Rc::new(create_http_class())),
+        );
+        classes.insert("Js".to_string(), Value::Class(Rc::new(create_js_class())));
     }
 
     classes
@@ -184,8 +407,10 @@ pub fn get_builtin_class_name(value: &Value) -> &'static str {
         Value::Dictionary(_) => "Dict",
         Value::Function(_) => "Function",
         Value::NativeFunction { .. } => "Function",
+        Value::HostFunction { .. } => "Function",
         Value::InstanceMethod { .. } => "Function",
         Value::BoundMethod { .. } => "Function",
+        Value::Partial { .. } => "Function",
         Value::Class(_) => "Class",
         Value::Instance(inst) => {
             let _ = inst;
@@ -195,9 +420,117 @@ pub fn get_builtin_class_name(value: &Value) -> &'static str {
         Value::Namespace { .. } => "Namespace",
         Value::Enum { .. } => "Enum",
         Value::SpreadMarker(_) => "SpreadMarker",
+        Value::NamedArgs(_) => "NamedArgs",
     }
 }
 
+/// A method the value's class defines in Sald, such as `toString` or
+/// `__hash__`, bound to the value
+pub(crate) fn bound_user_method(value: &Value, name: &str) -> Option<Value> {
+    let Value::Instance(inst) = value else {
+        return None;
+    };
+    match inst.borrow().class.methods.get(name) {
+        Some(Value::Function(method)) => Some(Value::BoundMethod {
+            receiver: Box::new(value.clone()),
+            method: method.clone(),
+        }),
+        _ => None,
+    }
+}
+
+/// What the classes' own `toString` methods return for the instances in
+/// `value`, found through arrays, dictionaries and the fields of instances
+/// without one. `call` runs the methods.
+pub(crate) fn user_display_text(
+    value: &Value,
+    call: &mut dict::CallMethod,
+) -> Result<DisplayText, String> {
+    let mut text = DisplayText::default();
+    collect_display_text(value, call, &mut text, &mut FxHashSet::default())?;
+    Ok(text)
+}
+
+fn collect_display_text(
+    value: &Value,
+    call: &mut dict::CallMethod,
+    text: &mut DisplayText,
+    seen: &mut FxHashSet<usize>,
+) -> Result<(), String> {
+    let nested: Vec<Value> = match value {
+        Value::Array(arr) if seen.insert(arr.as_ptr() as usize) => arr.borrow().to_vec(),
+        Value::Dictionary(dict) if seen.insert(dict.as_ptr() as usize) => {
+            let dict = dict.borrow();
+            dict.iter()
+                .flat_map(|(k, v)| [dict.key(k), v.clone()])
+                .collect()
+        }
+        Value::Instance(inst) if seen.insert(inst.as_ptr() as usize) => {
+            match bound_user_method(value, "toString") {
+                Some(to_string) => {
+                    let shown = match call(&to_string, Vec::new())? {
+                        Value::String(s) => s.to_string(),
+                        other => {
+                            return Err(format!(
+                                "toString() must return a String, got {}",
+                                other.type_name()
+                            ))
+                        }
+                    };
+                    text.insert(inst.as_ptr() as usize, shown);
+                    return Ok(());
+                }
+                None => inst.borrow().fields.values().cloned().collect(),
+            }
+        }
+        _ => return Ok(()),
+    };
+    nested
+        .iter()
+        .try_for_each(|item| collect_display_text(item, call, text, seen))
+}
+
+/// New instance of a builtin class whose state lives in a native handle
+pub(crate) fn native_instance<T: 'static>(class: Class, state: T) -> Value {
+    Value::Instance(Rc::new(RefCell::new(Instance::with_native(
+        Rc::new(class),
+        state,
+    ))))
+}
+
+/// State of `recv`, which must be an instance of the builtin `class`. Any
+/// other value, including instances of other builtins, is a TypeError.
+pub(crate) fn native_state<T: 'static>(
+    recv: &Value,
+    class: &str,
+) -> Result<Rc<RefCell<T>>, String> {
+    let got = match recv {
+        Value::Instance(inst) => {
+            let inst = inst.borrow();
+            if let Some(state) = inst.native::<T>() {
+                return Ok(state);
+            }
+            inst.class_name.clone()
+        }
+        other => other.type_name().to_string(),
+    };
+    Err(format!(
+        "TypeError: Receiver must be a {}, got {}",
+        class, got
+    ))
+}
+
+/// Longest string, in bytes, or array that repetition may build
+const MAX_REPEAT_LEN: usize = 1 << 27;
+
+/// Length of something `len` long repeated `count` times, refused when it
+/// would overflow or pass `MAX_REPEAT_LEN`
+pub(crate) fn repeat_len(len: usize, count: f64) -> Result<usize, String> {
+    len.checked_mul(count as usize)
+        .filter(|total| *total <= MAX_REPEAT_LEN)
+        .ok_or_else(|| format!("Repeat count {} is too large", count))
+}
+
 pub fn check_arity(expected: usize, got: usize) -> Result<(), String> {
     if expected != got {
         Err(format!(
@@ -235,6 +568,45 @@ pub fn check_arity_min(min: usize, got: usize) -> Result<(), String> {
     }
 }
 
+/// Named arguments of a native call, which the VM passes as a trailing
+/// `Value::NamedArgs` to methods listed in `Class::named_arg_methods`
+pub struct NamedArgs(Dict);
+
+impl NamedArgs {
+    /// Splits `args` into the positional arguments and the named ones. A
+    /// trailing dictionary is always positional. Names other than `known`
+    /// are rejected.
+    pub fn split<'a>(
+        args: &'a [Value],
+        known: &[&str],
+    ) -> Result<(&'a [Value], NamedArgs), String> {
+        let (args, named) = match args.split_last() {
+            Some((Value::NamedArgs(named), rest)) => (rest, named.borrow().clone()),
+            _ => (args, Dict::new()),
+        };
+        let mut unknown: Vec<&String> = named
+            .keys()
+            .filter(|name| !known.contains(&name.as_str()))
+            .collect();
+        unknown.sort();
+        if let Some(name) = unknown.first() {
+            return Err(format!("Unexpected named argument '{}'", name));
+        }
+        Ok((args, NamedArgs(named)))
+    }
+
+    /// The argument `name`, given either by name or at position `index`
+    pub fn get(&self, args: &[Value], index: usize, name: &str) -> Result<Option<Value>, String> {
+        match (args.get(index), self.0.get(name)) {
+            (Some(_), Some(_)) => Err(format!(
+                "Argument '{}' was given both by position and by name",
+                name
+            )),
+            (positional, named) => Ok(positional.or(named).cloned()),
+        }
+    }
+}
+
 pub fn get_string_arg(value: &Value, arg_name: &str) -> Result<String, String> {
     match value {
         Value::String(s) => Ok(s.to_string()),
@@ -267,3 +639,31 @@ pub fn get_bool_arg(value: &Value, arg_name: &str) -> Result<bool, String> {
         )),
     }
 }
+
+/// Bytes are arrays of numbers from 0 to 255; a string stands for its UTF-8 bytes.
+pub fn get_bytes_arg(value: &Value, arg_name: &str) -> Result<Vec<u8>, String> {
+    match value {
+        Value::String(s) => Ok(s.as_bytes().to_vec()),
+        Value::Array(items) => items
+            .borrow()
+            .iter()
+            .map(|b| match b {
+                Value::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => Ok(*n as u8),
+                _ => Err(format!(
+                    "Argument '{}' must hold whole numbers from 0 to 255",
+                    arg_name
+                )),
+            })
+            .collect(),
+        _ => Err(format!(
+            "Argument '{}' must be a string or byte array, got {}",
+            arg_name,
+            value.type_name()
+        )),
+    }
+}
+
+pub fn bytes_to_value(bytes: &[u8]) -> Value {
+    let bytes = bytes.iter().map(|b| Value::Number(*b as f64)).collect();
+    Value::Array(Rc::new(RefCell::new(bytes)))
+}
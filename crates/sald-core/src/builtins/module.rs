@@ -0,0 +1,206 @@
+//! Imported modules as values
+//! A `Module` describes one imported file: its name, its resolved path and the
+//! members it exports, so scripts can enumerate plugins and call into them by
+//! name.
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_string_arg};
+use crate::vm::caller::{CallableNativeStaticFn, ModuleMembers, ValueCaller};
+use crate::vm::value::{Class, Globals, Instance, NativeHandle, NativeInstanceFn, Value};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+pub fn create_module_class() -> Class {
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    callable_methods.insert("all".to_string(), module_all);
+    callable_methods.insert("of".to_string(), module_of);
+
+    instance_methods.insert("exports".to_string(), module_exports);
+    instance_methods.insert("get".to_string(), module_get);
+    instance_methods.insert("has".to_string(), module_has);
+
+    let mut class = Class::new_with_instance("Module", instance_methods, None);
+    class.callable_native_static_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Module` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Module", "Imported files and the members they export")
+        .method("all", "all()", "Get every module imported so far")
+        .method(
+            "of",
+            "of(namespace)",
+            "Get the module an 'import ... as' namespace came from, or null",
+        )
+        .method("exports", "exports()", "Get the names the module exports")
+        .method(
+            "get",
+            "get(name, default?)",
+            "Get the exported member called name",
+        )
+        .method("has", "has(name)", "Check if the module exports name")
+}
+
+/// `Module` object for the module at `path` with the given members
+pub(crate) fn make_module(path: &str, members: ModuleMembers) -> Value {
+    let name = std::path::Path::new(path).file_stem().map_or_else(
+        || path.to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+    let mut instance = Instance::new(Rc::new(create_module_class()));
+    instance
+        .fields
+        .insert("name".to_string(), Value::String(Rc::from(name.as_str())));
+    instance
+        .fields
+        .insert("path".to_string(), Value::String(Rc::from(path)));
+    // Shares the module's table, so members it defines later show up too
+    instance.native = Some(NativeHandle::shared(members));
+    Value::Instance(Rc::new(RefCell::new(instance)))
+}
+
+fn module_all(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let modules: Vec<Value> = caller
+        .loaded_modules()
+        .into_iter()
+        .map(|(path, members)| make_module(&path, members))
+        .collect();
+    Ok(Value::Array(Rc::new(RefCell::new(modules.into()))))
+}
+
+fn module_of(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let Value::Namespace { members, .. } = &args[0] else {
+        return Err(format!(
+            "Module.of() expects a Namespace, got {}",
+            args[0].type_name()
+        ));
+    };
+    Ok(caller
+        .loaded_modules()
+        .into_iter()
+        .find(|(_, module_members)| Rc::ptr_eq(module_members, members))
+        .map_or(Value::Null, |(path, members)| make_module(&path, members)))
+}
+
+fn members_of(recv: &Value, method: &str) -> Result<ModuleMembers, String> {
+    if let Value::Instance(inst) = recv {
+        if let Some(members) = inst.borrow().native::<Globals>() {
+            return Ok(members);
+        }
+    }
+    Err(format!("{}() must be called on a Module", method))
+}
+
+/// Builtin classes every module sees, which it doesn't export
+fn is_builtin(name: &str, value: &Value) -> bool {
+    static NAMES: OnceLock<FxHashSet<String>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| super::create_builtin_classes().into_keys().collect());
+    names.contains(name)
+        && match value {
+            Value::Class(class) => class.name == name,
+            Value::Namespace { name: ns_name, .. } => ns_name == name,
+            _ => false,
+        }
+}
+
+fn module_exports(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let members = members_of(recv, "exports")?;
+    let mut names: Vec<String> = members
+        .borrow()
+        .iter()
+        .filter(|(name, value)| !is_builtin(name, value))
+        .map(|(name, _)| name.clone())
+        .collect();
+    names.sort();
+    Ok(Value::Array(Rc::new(RefCell::new(
+        names
+            .into_iter()
+            .map(|name| Value::String(Rc::from(name.as_str())))
+            .collect(),
+    ))))
+}
+
+fn module_get(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    let members = members_of(recv, "get")?;
+    let members = members.borrow();
+    Ok(members
+        .get(&name)
+        .filter(|value| !is_builtin(&name, value))
+        .cloned()
+        .unwrap_or_else(|| args.get(1).cloned().unwrap_or(Value::Null)))
+}
+
+fn module_has(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    let members = members_of(recv, "has")?;
+    let members = members.borrow();
+    Ok(Value::Boolean(
+        members
+            .get(&name)
+            .is_some_and(|value| !is_builtin(&name, value)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_module_objects() {
+        use crate::vm::MemoryLoader;
+
+        let loader = MemoryLoader::new()
+            .with_module("plugins/greet.sald", "fun run(x) { return \"hi \" + x }")
+            .with_module("plugins/shout.sald", "fun run(x) { return x.upper() }");
+        let mut engine = Engine::new();
+        engine.set_module_loader(loader);
+        engine
+            .eval(
+                r#"
+import "plugins/greet" as greet
+import "plugins/shout"
+let names = Module.all().map(|m| m.name)
+let results = Module.all().map(|m| m.get("run")("sald"))
+let info = Module.of(greet)
+"#,
+            )
+            .unwrap();
+        assert_eq!(
+            engine.eval_as::<Vec<String>>("names").unwrap(),
+            ["greet", "shout"]
+        );
+        assert_eq!(
+            engine.eval_as::<Vec<String>>("results").unwrap(),
+            ["hi sald", "SALD"]
+        );
+        assert_eq!(
+            engine.eval_as::<String>("info.path").unwrap(),
+            "plugins/greet.sald"
+        );
+        assert_eq!(
+            engine.eval_as::<Vec<String>>("info.exports()").unwrap(),
+            ["run"]
+        );
+        assert!(!engine.eval_as::<bool>("info.has(\"Console\")").unwrap());
+        assert!(engine
+            .eval_as::<bool>("info.get(\"missing\") == null")
+            .unwrap());
+        assert_eq!(
+            engine
+                .eval_as::<Vec<String>>("Reflect.fields(info).toSorted()")
+                .unwrap(),
+            ["name", "path"]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! MessagePack encoding
+//! Values are encoded to and decoded from byte arrays, the same representation
+//! `Crypto.randomBytes` uses
+
+use super::check_arity;
+use super::docs::ClassDoc;
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use rmpv::Value as MsgValue;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn create_msgpack_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("encode".to_string(), msgpack_encode);
+    static_methods.insert("decode".to_string(), msgpack_decode);
+
+    Class::new_with_static("MsgPack", static_methods)
+}
+
+/// API documentation for the `MsgPack` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("MsgPack", "MessagePack binary serialization")
+        .method("encode", "encode(value)", "Encode value to a byte array")
+        .method("decode", "decode(bytes)", "Decode a byte array to a value")
+}
+
+fn msgpack_encode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &to_msgpack(&args[0])?).map_err(|e| e.to_string())?;
+
+    let bytes = buf.into_iter().map(|b| Value::Number(b as f64)).collect();
+    Ok(Value::Array(Rc::new(RefCell::new(bytes))))
+}
+
+fn msgpack_decode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let bytes = byte_array(&args[0])?;
+    let mut reader = bytes.as_slice();
+    let value = rmpv::decode::read_value(&mut reader)
+        .map_err(|e| format!("Invalid MessagePack data: {}", e))?;
+    if !reader.is_empty() {
+        return Err(format!(
+            "Invalid MessagePack data: {} trailing bytes",
+            reader.len()
+        ));
+    }
+    from_msgpack(value)
+}
+
+fn byte_array(value: &Value) -> Result<Vec<u8>, String> {
+    let Value::Array(items) = value else {
+        return Err(format!(
+            "Expected a byte array but got {}",
+            value.type_name()
+        ));
+    };
+    items
+        .borrow()
+        .iter()
+        .map(|b| match b {
+            Value::Number(n) if n.fract() == 0.0 && (0.0..=255.0).contains(n) => Ok(*n as u8),
+            _ => Err("Bytes must be whole numbers from 0 to 255".to_string()),
+        })
+        .collect()
+}
+
+fn to_msgpack(value: &Value) -> Result<MsgValue, String> {
+    Ok(match value {
+        Value::Null => MsgValue::Nil,
+        Value::Boolean(b) => MsgValue::Boolean(*b),
+        // Whole numbers use the compact integer encodings
+        Value::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+            MsgValue::from(*n as i64)
+        }
+        Value::Number(n) => MsgValue::F64(*n),
+        Value::String(s) => MsgValue::from(s.as_ref()),
+        Value::Array(items) => MsgValue::Array(
+            items
+                .borrow()
+                .iter()
+                .map(to_msgpack)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Dictionary(dict) if dict.borrow().has_instance_keys() => {
+            return Err("Cannot encode a Dict with instance keys to MessagePack".to_string())
+        }
+        Value::Dictionary(dict) => MsgValue::Map(
+            dict.borrow()
+                .iter()
+                .map(|(key, value)| Ok((MsgValue::from(key.as_str()), to_msgpack(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        _ => {
+            return Err(format!(
+                "Cannot encode {} to MessagePack",
+                value.type_name()
+            ))
+        }
+    })
+}
+
+/// Binary and extension payloads become byte arrays; non-string map keys are
+/// converted to their text form.
+fn from_msgpack(value: MsgValue) -> Result<Value, String> {
+    let array = |items: Vec<Value>| Value::Array(Rc::new(RefCell::new(items.into())));
+    let bytes = |data: Vec<u8>| array(data.into_iter().map(|b| Value::Number(b as f64)).collect());
+
+    Ok(match value {
+        MsgValue::Nil => Value::Null,
+        MsgValue::Boolean(b) => Value::Boolean(b),
+        MsgValue::Integer(i) => Value::Number(i.as_f64().unwrap_or(0.0)),
+        MsgValue::F32(f) => Value::Number(f as f64),
+        MsgValue::F64(f) => Value::Number(f),
+        MsgValue::String(s) => match s.into_str() {
+            Some(s) => Value::String(Rc::from(s)),
+            None => return Err("Invalid MessagePack data: string is not UTF-8".to_string()),
+        },
+        MsgValue::Binary(data) | MsgValue::Ext(_, data) => bytes(data),
+        MsgValue::Array(items) => array(
+            items
+                .into_iter()
+                .map(from_msgpack)
+                .collect::<Result<_, _>>()?,
+        ),
+        MsgValue::Map(entries) => {
+            let mut dict = FxHashMap::default();
+            for (key, value) in entries {
+                let key = match key {
+                    MsgValue::String(s) => s.into_str().unwrap_or_default(),
+                    other => other.to_string(),
+                };
+                dict.insert(key, from_msgpack(value)?);
+            }
+            Value::Dictionary(Rc::new(RefCell::new(dict.into())))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let mut engine = Engine::new();
+        let encoded: Vec<f64> = engine.eval_as("MsgPack.encode([1, -2.5, \"hi\"])").unwrap();
+        assert_eq!(
+            encoded,
+            [0x93, 0x01, 0xcb, 0xc0, 0x04, 0, 0, 0, 0, 0, 0, 0xa2, b'h', b'i'].map(f64::from)
+        );
+
+        engine
+            .eval(
+                "let d = MsgPack.decode(MsgPack.encode({\"name\": \"ada\", \"tags\": [1, null]}))",
+            )
+            .unwrap();
+        let result: (String, f64, bool) = engine
+            .eval_as("[d[\"name\"], d[\"tags\"][0], d[\"tags\"][1] == null]")
+            .unwrap();
+        assert_eq!(result, ("ada".to_string(), 1.0, true));
+    }
+}
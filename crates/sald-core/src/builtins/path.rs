@@ -1,3 +1,4 @@
+use super::docs::ClassDoc;
 use crate::vm::value::{Class, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
 use std::path::Path;
@@ -17,6 +18,18 @@ pub fn create_path_class() -> Class {
     Class::new_with_static("Path", static_methods)
 }
 
+/// API documentation for the `Path` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Path", "Path utilities")
+        .method("join", "join(...parts)", "Join path components")
+        .method("dirname", "dirname(path)", "Get directory name")
+        .method("basename", "basename(path)", "Get file name")
+        .method("extname", "extname(path)", "Get extension with dot")
+        .method("isAbsolute", "isAbsolute(path)", "Check if absolute path")
+        .method("exists", "exists(path)", "Check if path exists")
+        .method("normalize", "normalize(path)", "Normalize path")
+}
+
 fn get_string(args: &[Value], idx: usize, name: &str) -> Result<String, String> {
     if idx >= args.len() {
         return Err(format!("Expected at least {} argument(s)", idx + 1));
@@ -1,8 +1,28 @@
+use super::docs::ClassDoc;
 use crate::vm::value::{Class, NativeStaticFn, Value};
+use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Arguments given to the script itself, when the host separates them from
+/// its own command line
+static SCRIPT_ARGS: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+/// Sets the arguments `Process.args()` and `Args.parse()` see.
+pub fn set_script_args(args: Vec<String>) {
+    *SCRIPT_ARGS.lock() = Some(args);
+}
+
+/// The script's arguments, or the whole command line after the executable
+/// when the host never set them.
+pub(super) fn script_args() -> Vec<String> {
+    match SCRIPT_ARGS.lock().as_ref() {
+        Some(args) => args.clone(),
+        None => std::env::args().skip(1).collect(),
+    }
+}
+
 pub fn create_process_class() -> Class {
     let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
 
@@ -12,17 +32,40 @@ pub fn create_process_class() -> Class {
     static_methods.insert("exec".to_string(), process_exec);
     static_methods.insert("cwd".to_string(), process_cwd);
     static_methods.insert("chdir".to_string(), process_chdir);
+    static_methods.insert("spawn".to_string(), super::child::process_spawn);
+    static_methods.insert("pipeline".to_string(), super::child::process_pipeline);
 
     Class::new_with_static("Process", static_methods)
 }
 
+/// API documentation for the `Process` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Process", "Process and environment operations")
+        .method("args", "args()", "Command line arguments")
+        .method("env", "env(name)", "Get environment variable")
+        .method("cwd", "cwd()", "Current working directory")
+        .method("chdir", "chdir(path)", "Change working directory")
+        .method("exit", "exit(code?)", "Exit process")
+        .method("exec", "exec(command)", "Execute shell command")
+        .method(
+            "spawn",
+            "spawn(command, args?, options?)",
+            "Start a ChildProcess; options are cwd, env, clearEnv, stdin, stdout and stderr",
+        )
+        .method(
+            "pipeline",
+            "pipeline(commands, options?)",
+            "Run commands piped together, returns stdout, stderr, code and codes",
+        )
+}
+
 fn process_args(_args: &[Value]) -> Result<Value, String> {
-    let args: Vec<Value> = std::env::args()
-        .skip(1)
+    let args: Vec<Value> = script_args()
+        .into_iter()
         .map(|s| Value::String(Rc::from(s)))
         .collect();
 
-    Ok(Value::Array(Rc::new(RefCell::new(args))))
+    Ok(Value::Array(Rc::new(RefCell::new(args.into()))))
 }
 
 fn process_env(args: &[Value]) -> Result<Value, String> {
@@ -133,3 +176,44 @@ fn process_chdir(args: &[Value]) -> Result<Value, String> {
         Err(e) => Err(e.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_process_spawn_and_pipeline() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let child = Process.spawn(\"sh\", [\"-c\", \"read x; echo got $x; echo $FOO; echo oops >&2; exit 3\"],\n\
+                     {\"env\": {\"FOO\": \"bar\"}})\n\
+                 child.write(\"hello\\n\")\n\
+                 let first = child.readLine()\n\
+                 let second = child.readLine()\n\
+                 let errors = child.readErrAll()\n\
+                 let code = await child.wait()\n\
+                 let sleeper = Process.spawn(\"sleep\", [\"10\"])\n\
+                 sleeper.kill(\"SIGTERM\")\n\
+                 let killed = await sleeper.wait()\n\
+                 let piped = Process.pipeline([[\"printf\", \"b\\na\\nc\\n\"], \"sort\", [\"head\", \"-n\", \"2\"]])",
+            )
+            .unwrap();
+        let result: (String, String, String, f64, f64, String, Vec<f64>) = engine
+            .eval_as("[first, second, errors, code, killed, piped[\"stdout\"], piped[\"codes\"]]")
+            .unwrap();
+        assert_eq!(
+            result,
+            (
+                "got hello".to_string(),
+                "bar".to_string(),
+                "oops\n".to_string(),
+                3.0,
+                143.0,
+                "a\nb\n".to_string(),
+                vec![0.0, 0.0, 0.0]
+            )
+        );
+    }
+}
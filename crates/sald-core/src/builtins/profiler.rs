@@ -0,0 +1,120 @@
+use super::docs::ClassDoc;
+use super::{check_arity, get_string_arg};
+use crate::vm::profiler::{self, ProfileReport};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn create_profiler_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("start".to_string(), profiler_start);
+    static_methods.insert("stop".to_string(), profiler_stop);
+    static_methods.insert("report".to_string(), profiler_report);
+    static_methods.insert("isActive".to_string(), profiler_is_active);
+    static_methods.insert("table".to_string(), profiler_table);
+    static_methods.insert("save".to_string(), profiler_save);
+
+    Class::new_with_static("Profiler", static_methods)
+}
+
+/// API documentation for the `Profiler` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Profiler", "Function call profiling")
+        .method(
+            "start",
+            "start()",
+            "Start collecting call timings; raises under --profile",
+        )
+        .method(
+            "stop",
+            "stop()",
+            "Stop profiling and return the report; raises under --profile",
+        )
+        .method("report", "report()", "Report collected so far")
+        .method("isActive", "isActive()", "Check if profiling is running")
+        .method("table", "table()", "Report as a text table")
+        .method("save", "save(path)", "Write a flamegraph profile")
+}
+
+fn profiler_start(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    host_session_error()?;
+    profiler::start();
+    Ok(Value::Null)
+}
+
+fn profiler_stop(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    host_session_error()?;
+    Ok(report_to_value(&profiler::stop()))
+}
+
+/// `start` and `stop` would discard or end what `--profile` is collecting
+fn host_session_error() -> Result<(), String> {
+    if profiler::in_session() {
+        return Err("Profiler is already running for --profile".to_string());
+    }
+    Ok(())
+}
+
+fn profiler_report(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(report_to_value(&profiler::report()))
+}
+
+fn profiler_is_active(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Boolean(profiler::is_active()))
+}
+
+fn profiler_table(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::String(Rc::from(profiler::report().to_table())))
+}
+
+/// Writes collapsed stacks for flamegraph tools
+fn profiler_save(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    std::fs::write(&path, profiler::report().to_collapsed())
+        .map_err(|e| format!("Cannot write profile to '{}': {}", path, e))?;
+    Ok(Value::Null)
+}
+
+fn report_to_value(report: &ProfileReport) -> Value {
+    let functions: Vec<Value> = report
+        .functions
+        .iter()
+        .map(|(name, stats)| {
+            let mut entry = FxHashMap::default();
+            entry.insert("name".to_string(), Value::String(Rc::from(name.as_str())));
+            entry.insert("calls".to_string(), Value::Number(stats.calls as f64));
+            entry.insert(
+                "selfMs".to_string(),
+                Value::Number(stats.self_time.as_secs_f64() * 1000.0),
+            );
+            entry.insert(
+                "totalMs".to_string(),
+                Value::Number(stats.total_time.as_secs_f64() * 1000.0),
+            );
+            entry.insert(
+                "allocations".to_string(),
+                Value::Number(stats.allocations as f64),
+            );
+            Value::Dictionary(Rc::new(RefCell::new(entry.into())))
+        })
+        .collect();
+
+    let mut result = FxHashMap::default();
+    result.insert(
+        "functions".to_string(),
+        Value::Array(Rc::new(RefCell::new(functions.into()))),
+    );
+    result.insert(
+        "collapsed".to_string(),
+        Value::String(Rc::from(report.to_collapsed())),
+    );
+    Value::Dictionary(Rc::new(RefCell::new(result.into())))
+}
@@ -1,4 +1,5 @@
 use super::check_arity;
+use super::docs::ClassDoc;
 use crate::vm::value::{Class, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
@@ -15,6 +16,23 @@ pub fn create_promise_class() -> Class {
     Class::new_with_static("Promise", static_methods)
 }
 
+/// API documentation for the `Promise` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Promise", "Async promise utilities")
+        .method(
+            "all",
+            "await all(futures)",
+            "Wait for all futures to complete",
+        )
+        .method(
+            "race",
+            "await race(futures)",
+            "Wait for first future to complete",
+        )
+        .method("resolve", "resolve(value)", "Create a resolved future")
+        .method("reject", "reject(error)", "Create a rejected future")
+}
+
 fn promise_all(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
 
@@ -22,8 +40,8 @@ fn promise_all(args: &[Value]) -> Result<Value, String> {
         Value::Array(arr) => {
             let arr_ref = arr.borrow();
 
-            let results: Vec<Value> = arr_ref.clone();
-            Ok(Value::Array(Rc::new(RefCell::new(results))))
+            let results: Vec<Value> = arr_ref.to_vec();
+            Ok(Value::Array(Rc::new(RefCell::new(results.into()))))
         }
         _ => Err("Promise.all() expects an array".to_string()),
     }
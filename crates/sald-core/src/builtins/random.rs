@@ -0,0 +1,354 @@
+//! Seedable random numbers
+//! Generators are xoshiro256** seeded through splitmix64, implemented here so a
+//! seed gives the same sequence on every platform and release. `Random(seed?)`
+//! makes a generator; the same methods called on the class use a shared one
+//! that `Random.seed` resets
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, native_instance, native_state};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+struct Xoshiro256([u64; 4]);
+
+impl Xoshiro256 {
+    fn from_seed(seed: u64) -> Self {
+        let mut state = seed;
+        let mut next = || {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Self([next(), next(), next(), next()])
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.0;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Uniform in [0, 1) with 53 bits of precision.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in [0, bound) without modulo bias.
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let n = self.next_u64();
+            if n < zone {
+                return n % bound;
+            }
+        }
+    }
+}
+
+thread_local! {
+    /// Generator used by the static methods, seeded on first use
+    static SHARED: RefCell<Option<Xoshiro256>> = const { RefCell::new(None) };
+}
+
+macro_rules! random_methods {
+    ($($name:literal => $method:ident),* $(,)?) => {
+        fn register_methods(
+            static_methods: &mut FxHashMap<String, NativeStaticFn>,
+            instance_methods: &mut FxHashMap<String, NativeInstanceFn>,
+        ) {
+            $(
+                static_methods.insert($name.to_string(), |args| {
+                    with_shared(|rng| $method(rng, args))
+                });
+                instance_methods.insert($name.to_string(), |recv, args| {
+                    let rng = native_state::<Xoshiro256>(recv, "Random")?;
+                    let mut rng = rng.borrow_mut();
+                    $method(&mut rng, args)
+                });
+            )*
+        }
+    };
+}
+
+random_methods! {
+    "next" => random_next,
+    "int" => random_int,
+    "float" => random_float,
+    "bool" => random_bool,
+    "choice" => random_choice,
+    "shuffle" => random_shuffle,
+    "sample" => random_sample,
+    "normal" => random_normal,
+    "exponential" => random_exponential,
+}
+
+pub fn create_random_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    register_methods(&mut static_methods, &mut instance_methods);
+    static_methods.insert("seed".to_string(), random_seed);
+
+    let mut class = Class::new_with_instance("Random", instance_methods, Some(random_new));
+    class.native_static_methods = static_methods;
+    class
+}
+
+/// API documentation for the `Random` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "Random",
+        "Seedable random numbers, on a Random(seed?) instance or the class itself",
+    )
+    .method(
+        "seed",
+        "seed(seed)",
+        "Reseed the generator used by the static methods",
+    )
+    .method("next", "next()", "Random number from 0 up to 1")
+    .method(
+        "int",
+        "int(min, max)",
+        "Random integer from min to max inclusive",
+    )
+    .method(
+        "float",
+        "float(min, max)",
+        "Random number from min up to max",
+    )
+    .method(
+        "bool",
+        "bool(p?)",
+        "true with probability p, 0.5 by default",
+    )
+    .method("choice", "choice(array)", "Random element of array")
+    .method("shuffle", "shuffle(array)", "Shuffled copy of array")
+    .method(
+        "sample",
+        "sample(array, k)",
+        "k distinct elements of array in random order",
+    )
+    .method(
+        "normal",
+        "normal(mean?, stddev?)",
+        "Normally distributed number, standard by default",
+    )
+    .method(
+        "exponential",
+        "exponential(rate?)",
+        "Exponentially distributed number, rate 1 by default",
+    )
+}
+
+fn entropy_seed() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        rand::random()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let mut buf = [0u8; 8];
+        let _ = getrandom::getrandom(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+}
+
+/// Numbers seed directly; strings are hashed so any text can name a run.
+fn seed_of(value: &Value) -> Result<u64, String> {
+    match value {
+        Value::Number(n) if n.fract() == 0.0 => Ok(*n as i64 as u64),
+        Value::Number(n) => Ok(n.to_bits()),
+        Value::String(s) => Ok(s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        })),
+        other => Err(format!(
+            "Seed must be a number or string, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn with_shared<T>(f: impl FnOnce(&mut Xoshiro256) -> T) -> T {
+    SHARED.with(|shared| {
+        let mut shared = shared.borrow_mut();
+        f(shared.get_or_insert_with(|| Xoshiro256::from_seed(entropy_seed())))
+    })
+}
+
+fn random_new(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let seed = match args.first() {
+        None | Some(Value::Null) => entropy_seed(),
+        Some(seed) => seed_of(seed)?,
+    };
+    Ok(native_instance(
+        create_random_class(),
+        Xoshiro256::from_seed(seed),
+    ))
+}
+
+fn random_seed(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let seed = seed_of(&args[0])?;
+    SHARED.with(|shared| *shared.borrow_mut() = Some(Xoshiro256::from_seed(seed)));
+    Ok(Value::Null)
+}
+
+fn optional_number(args: &[Value], index: usize, name: &str, default: f64) -> Result<f64, String> {
+    match args.get(index) {
+        None | Some(Value::Null) => Ok(default),
+        Some(value) => get_number_arg(value, name),
+    }
+}
+
+fn array_items(value: &Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(items) => Ok(items.borrow().to_vec()),
+        other => Err(format!("Expected an array but got {}", other.type_name())),
+    }
+}
+
+fn random_next(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(rng.next_f64()))
+}
+
+fn random_int(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let min = get_number_arg(&args[0], "min")?.ceil() as i64;
+    let max = get_number_arg(&args[1], "max")?.floor() as i64;
+    if min > max {
+        return Err("min cannot be greater than max".to_string());
+    }
+    let span = max.wrapping_sub(min) as u64;
+    let offset = if span == u64::MAX {
+        rng.next_u64()
+    } else {
+        rng.below(span + 1)
+    };
+    Ok(Value::Number(min.wrapping_add(offset as i64) as f64))
+}
+
+fn random_float(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let min = get_number_arg(&args[0], "min")?;
+    let max = get_number_arg(&args[1], "max")?;
+    if min > max {
+        return Err("min cannot be greater than max".to_string());
+    }
+    Ok(Value::Number(min + rng.next_f64() * (max - min)))
+}
+
+fn random_bool(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let p = optional_number(args, 0, "p", 0.5)?;
+    Ok(Value::Boolean(rng.next_f64() < p))
+}
+
+fn random_choice(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let items = array_items(&args[0])?;
+    if items.is_empty() {
+        return Err("Cannot choose from an empty array".to_string());
+    }
+    Ok(items[rng.below(items.len() as u64) as usize].clone())
+}
+
+/// Fisher-Yates over the first `k` positions.
+fn partial_shuffle(rng: &mut Xoshiro256, items: &mut [Value], k: usize) {
+    for i in 0..k.min(items.len().saturating_sub(1)) {
+        let j = i + rng.below((items.len() - i) as u64) as usize;
+        items.swap(i, j);
+    }
+}
+
+fn random_shuffle(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let mut items = array_items(&args[0])?;
+    let len = items.len();
+    partial_shuffle(rng, &mut items, len);
+    Ok(Value::Array(Rc::new(RefCell::new(items.into()))))
+}
+
+fn random_sample(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let mut items = array_items(&args[0])?;
+    let k = get_number_arg(&args[1], "k")?;
+    if k < 0.0 || k as usize > items.len() {
+        return Err(format!(
+            "Cannot sample {} elements from an array of {}",
+            k,
+            items.len()
+        ));
+    }
+    let k = k as usize;
+    partial_shuffle(rng, &mut items, k);
+    items.truncate(k);
+    Ok(Value::Array(Rc::new(RefCell::new(items.into()))))
+}
+
+fn random_normal(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let mean = optional_number(args, 0, "mean", 0.0)?;
+    let stddev = optional_number(args, 1, "stddev", 1.0)?;
+    // Box-Muller; 1 - u keeps the logarithm finite
+    let u = 1.0 - rng.next_f64();
+    let v = rng.next_f64();
+    let z = (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos();
+    Ok(Value::Number(mean + stddev * z))
+}
+
+fn random_exponential(rng: &mut Xoshiro256, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let rate = optional_number(args, 0, "rate", 1.0)?;
+    if rate <= 0.0 {
+        return Err("rate must be positive".to_string());
+    }
+    Ok(Value::Number(-(1.0 - rng.next_f64()).ln() / rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_random_is_reproducible() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let a = Random(42)\n\
+                 let b = Random(42)\n\
+                 let same = true\n\
+                 for i in 0..100 { if a.int(1, 6) != b.int(1, 6) { same = false } }\n\
+                 Random.seed(\"run-1\")\n\
+                 let first = Random.shuffle([1, 2, 3, 4, 5])\n\
+                 Random.seed(\"run-1\")\n\
+                 let again = Random.shuffle([1, 2, 3, 4, 5])\n\
+                 let sum = 0\n\
+                 for i in 0..2000 { sum = sum + a.normal(10, 2) }",
+            )
+            .unwrap();
+        let result: (bool, Vec<f64>, Vec<f64>, f64, f64) = engine
+            .eval_as("[same, first, again, sum / 2000, a.sample([1, 2, 3], 3).length()]")
+            .unwrap();
+        assert!(result.0);
+        assert_eq!(result.1, result.2);
+        let mut sorted = result.1.clone();
+        sorted.sort_by(f64::total_cmp);
+        assert_eq!(sorted, [1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert!((result.3 - 10.0).abs() < 0.3);
+        assert_eq!(result.4, 3.0);
+    }
+}
@@ -0,0 +1,483 @@
+//! Interactive prompts
+//! `Readline` asks questions on the terminal with a small line editor (cursor
+//! keys, Home/End, history on Up/Down) built on `Term`'s raw-mode key reader.
+//! When stdin is not a terminal every prompt falls back to reading plain
+//! lines, so scripts can still be driven through a pipe
+
+use super::docs::ClassDoc;
+use super::string::display_width;
+use super::term::{read_key, set_raw};
+use super::{check_arity, check_arity_range, get_bool_arg, get_string_arg};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use crossterm::event::KeyModifiers;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::io::{BufRead, IsTerminal, Write};
+use std::rc::Rc;
+
+/// Oldest entries are dropped past this many
+const MAX_HISTORY: usize = 1000;
+
+thread_local! {
+    static HISTORY: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+pub fn create_readline_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("ask".to_string(), readline_ask);
+    static_methods.insert("password".to_string(), readline_password);
+    static_methods.insert("confirm".to_string(), readline_confirm);
+    static_methods.insert("select".to_string(), readline_select);
+    static_methods.insert("history".to_string(), readline_history);
+    static_methods.insert("addHistory".to_string(), readline_add_history);
+    static_methods.insert("clearHistory".to_string(), readline_clear_history);
+    static_methods.insert("loadHistory".to_string(), readline_load_history);
+    static_methods.insert("saveHistory".to_string(), readline_save_history);
+
+    Class::new_with_static("Readline", static_methods)
+}
+
+/// API documentation for the `Readline` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "Readline",
+        "Interactive prompts with line editing and history",
+    )
+    .method(
+        "ask",
+        "ask(prompt?, default?)",
+        "Read a line, or default when it is empty; null at end of input",
+    )
+    .method(
+        "password",
+        "password(prompt?, mask?)",
+        "Read a line without echoing it, optionally showing mask per character",
+    )
+    .method(
+        "confirm",
+        "confirm(prompt, default?)",
+        "Ask a yes/no question, returns a boolean",
+    )
+    .method(
+        "select",
+        "select(prompt, options, default?)",
+        "Pick one option with the arrow keys, returns it",
+    )
+    .method("history", "history()", "Lines entered so far, oldest first")
+    .method(
+        "addHistory",
+        "addHistory(line)",
+        "Add a line to the history",
+    )
+    .method("clearHistory", "clearHistory()", "Forget the history")
+    .method(
+        "loadHistory",
+        "loadHistory(path)",
+        "Append lines from a file to the history",
+    )
+    .method(
+        "saveHistory",
+        "saveHistory(path)",
+        "Write the history to a file, one line each",
+    )
+}
+
+fn string(s: String) -> Value {
+    Value::String(Rc::from(s))
+}
+
+fn optional_string(args: &[Value], index: usize, name: &str) -> Result<String, String> {
+    match args.get(index) {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(value) => get_string_arg(value, name),
+    }
+}
+
+fn interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+fn write_out(text: &str) {
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(text.as_bytes());
+    let _ = stdout.flush();
+}
+
+/// Reads a line from a non-terminal stdin, `None` at end of input.
+fn read_plain_line(prompt: &str) -> Result<Option<String>, String> {
+    write_out(prompt);
+    let mut line = String::new();
+    let read = std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .map_err(|e| format!("Read error: {}", e))?;
+    if read == 0 {
+        return Ok(None);
+    }
+    let line = line.trim_end_matches('\n').trim_end_matches('\r');
+    Ok(Some(line.to_string()))
+}
+
+/// Keeps the terminal in raw mode while alive.
+struct RawGuard(bool);
+
+impl RawGuard {
+    fn new() -> Result<Self, String> {
+        set_raw(true).map(RawGuard)
+    }
+}
+
+impl Drop for RawGuard {
+    fn drop(&mut self) {
+        if !self.0 {
+            let _ = set_raw(false);
+        }
+    }
+}
+
+/// How typed text is shown while editing.
+enum Echo {
+    Plain,
+    Mask(String),
+    Hidden,
+}
+
+/// Edits one line in raw mode. `None` means Ctrl-D on an empty line.
+fn edit_line(prompt: &str, echo: Echo, history: bool) -> Result<Option<String>, String> {
+    let entries = if history {
+        HISTORY.with(|history| history.borrow().clone())
+    } else {
+        Vec::new()
+    };
+    let prompt_width = display_width(prompt);
+    let mut line: Vec<char> = Vec::new();
+    let mut cursor = 0;
+    let mut browsing = entries.len();
+    let mut draft = Vec::new();
+
+    let _raw = RawGuard::new()?;
+    let result = loop {
+        let (shown, column): (String, usize) = match &echo {
+            Echo::Plain => {
+                let before: String = line[..cursor].iter().collect();
+                (line.iter().collect(), prompt_width + display_width(&before))
+            }
+            Echo::Mask(mask) => (
+                mask.repeat(line.len()),
+                prompt_width + display_width(mask) * cursor,
+            ),
+            Echo::Hidden => (String::new(), prompt_width),
+        };
+        write_out(&format!("\r\x1b[2K{}{}\r", prompt, shown));
+        if column > 0 {
+            write_out(&format!("\x1b[{}C", column));
+        }
+
+        let Some((key, modifiers)) = read_key(None)? else {
+            continue;
+        };
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        match (key.as_str(), ctrl) {
+            ("c", true) => break Err("Interrupted".to_string()),
+            ("d", true) if line.is_empty() => break Ok(None),
+            ("enter", _) => break Ok(Some(line.iter().collect())),
+            ("a", true) | ("home", _) => cursor = 0,
+            ("e", true) | ("end", _) => cursor = line.len(),
+            ("u", true) => {
+                line.drain(..cursor);
+                cursor = 0;
+            }
+            ("left", _) => cursor = cursor.saturating_sub(1),
+            ("right", _) => cursor = (cursor + 1).min(line.len()),
+            ("backspace", _) if cursor > 0 => {
+                cursor -= 1;
+                line.remove(cursor);
+            }
+            ("delete", _) if cursor < line.len() => {
+                line.remove(cursor);
+            }
+            ("up", _) if browsing > 0 => {
+                if browsing == entries.len() {
+                    draft = line.clone();
+                }
+                browsing -= 1;
+                line = entries[browsing].chars().collect();
+                cursor = line.len();
+            }
+            ("down", _) if browsing < entries.len() => {
+                browsing += 1;
+                line = match entries.get(browsing) {
+                    Some(entry) => entry.chars().collect(),
+                    None => draft.clone(),
+                };
+                cursor = line.len();
+            }
+            (key, false) if key.chars().count() == 1 => {
+                line.insert(cursor, key.chars().next().unwrap_or(' '));
+                cursor += 1;
+            }
+            _ => {}
+        }
+    };
+    write_out("\r\n");
+    result
+}
+
+fn add_history(line: &str) {
+    if line.trim().is_empty() {
+        return;
+    }
+    HISTORY.with(|history| {
+        let mut history = history.borrow_mut();
+        if history.last().map(String::as_str) != Some(line) {
+            history.push(line.to_string());
+        }
+        let excess = history.len().saturating_sub(MAX_HISTORY);
+        history.drain(..excess);
+    });
+}
+
+fn readline_ask(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let prompt = optional_string(args, 0, "prompt")?;
+    let default = args.get(1).filter(|value| !value.is_null());
+    let prompt = match default {
+        Some(default) => format!("{}[{}] ", prompt, default),
+        None => prompt,
+    };
+    let line = if interactive() {
+        edit_line(&prompt, Echo::Plain, true)?
+    } else {
+        read_plain_line(&prompt)?
+    };
+    match line {
+        Some(line) if line.is_empty() => Ok(default.cloned().unwrap_or(string(line))),
+        Some(line) => {
+            add_history(&line);
+            Ok(string(line))
+        }
+        None => Ok(Value::Null),
+    }
+}
+
+fn readline_password(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let prompt = optional_string(args, 0, "prompt")?;
+    let mask = optional_string(args, 1, "mask")?;
+    let line = if interactive() {
+        let echo = if mask.is_empty() {
+            Echo::Hidden
+        } else {
+            Echo::Mask(mask)
+        };
+        edit_line(&prompt, echo, false)?
+    } else {
+        read_plain_line(&prompt)?
+    };
+    Ok(line.map(string).unwrap_or(Value::Null))
+}
+
+fn readline_confirm(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let question = get_string_arg(&args[0], "prompt")?;
+    let default = match args.get(1) {
+        None | Some(Value::Null) => None,
+        Some(value) => Some(get_bool_arg(value, "default")?),
+    };
+    let hint = match default {
+        Some(true) => "[Y/n]",
+        Some(false) => "[y/N]",
+        None => "[y/n]",
+    };
+    let prompt = format!("{} {} ", question, hint);
+    loop {
+        let line = if interactive() {
+            edit_line(&prompt, Echo::Plain, false)?
+        } else {
+            read_plain_line(&prompt)?
+        };
+        let Some(line) = line else {
+            return default
+                .map(Value::Boolean)
+                .ok_or_else(|| "Input ended before an answer was given".to_string());
+        };
+        match line.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => return Ok(Value::Boolean(true)),
+            "n" | "no" => return Ok(Value::Boolean(false)),
+            "" if default.is_some() => return Ok(Value::Boolean(default.unwrap_or(false))),
+            _ => {}
+        }
+    }
+}
+
+fn select_interactive(question: &str, labels: &[String], start: usize) -> Result<usize, String> {
+    let mut selected = start;
+    let _raw = RawGuard::new()?;
+    write_out(&format!("{}\r\n\x1b[?25l", question));
+    let result = loop {
+        for (index, label) in labels.iter().enumerate() {
+            let marker = if index == selected {
+                "\x1b[36m❯"
+            } else {
+                " "
+            };
+            write_out(&format!("\r\x1b[2K{} {}\x1b[0m\r\n", marker, label));
+        }
+        let Some((key, modifiers)) = read_key(None)? else {
+            continue;
+        };
+        write_out(&format!("\x1b[{}A", labels.len()));
+        match key.as_str() {
+            "c" if modifiers.contains(KeyModifiers::CONTROL) => {
+                break Err("Interrupted".to_string())
+            }
+            "enter" => break Ok(selected),
+            "up" | "k" => selected = (selected + labels.len() - 1) % labels.len(),
+            "down" | "j" | "tab" => selected = (selected + 1) % labels.len(),
+            "home" => selected = 0,
+            "end" => selected = labels.len() - 1,
+            _ => {}
+        }
+    };
+    // Collapse the menu into the question line followed by the answer
+    write_out(&format!("\x1b[1A\r\x1b[J\x1b[?25h{}", question));
+    if let Ok(selected) = result {
+        write_out(&format!(" {}", labels[selected]));
+    }
+    write_out("\r\n");
+    result
+}
+
+fn select_plain(question: &str, labels: &[String], start: usize) -> Result<Option<usize>, String> {
+    let mut menu = format!("{}\n", question);
+    for (index, label) in labels.iter().enumerate() {
+        menu.push_str(&format!("  {}) {}\n", index + 1, label));
+    }
+    write_out(&menu);
+    let prompt = format!("Choose 1-{} [{}]: ", labels.len(), start + 1);
+    loop {
+        let Some(line) = read_plain_line(&prompt)? else {
+            return Ok(None);
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Some(start));
+        }
+        match line.parse::<usize>() {
+            Ok(choice) if (1..=labels.len()).contains(&choice) => return Ok(Some(choice - 1)),
+            _ => {
+                // Also accept the option text itself
+                if let Some(index) = labels.iter().position(|label| label == line) {
+                    return Ok(Some(index));
+                }
+            }
+        }
+    }
+}
+
+fn readline_select(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let question = get_string_arg(&args[0], "prompt")?;
+    let options = match &args[1] {
+        Value::Array(options) => options.borrow().clone(),
+        other => {
+            return Err(format!(
+                "Argument 'options' must be an array, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    if options.is_empty() {
+        return Err("Argument 'options' cannot be empty".to_string());
+    }
+    let labels: Vec<String> = options.iter().map(|option| option.to_string()).collect();
+    let start = match args.get(2) {
+        None | Some(Value::Null) => 0,
+        Some(default) => options
+            .iter()
+            .position(|option| option == default)
+            .ok_or_else(|| format!("Default '{}' is not one of the options", default))?,
+    };
+
+    let selected = if interactive() {
+        Some(select_interactive(&question, &labels, start)?)
+    } else {
+        select_plain(&question, &labels, start)?
+    };
+    Ok(selected
+        .map(|index| options[index].clone())
+        .unwrap_or(Value::Null))
+}
+
+fn readline_history(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let lines = HISTORY.with(|history| history.borrow().iter().cloned().map(string).collect());
+    Ok(Value::Array(Rc::new(RefCell::new(lines))))
+}
+
+fn readline_add_history(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    add_history(&get_string_arg(&args[0], "line")?);
+    Ok(Value::Null)
+}
+
+fn readline_clear_history(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    HISTORY.with(|history| history.borrow_mut().clear());
+    Ok(Value::Null)
+}
+
+fn readline_load_history(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = crate::workspace::resolve(&get_string_arg(&args[0], "path")?);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        // A missing file is just an empty history
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read '{}': {}", path.display(), e)),
+    };
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    for line in &lines {
+        add_history(line);
+    }
+    Ok(Value::Number(lines.len() as f64))
+}
+
+fn readline_save_history(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = crate::workspace::resolve(&get_string_arg(&args[0], "path")?);
+    let mut text = HISTORY.with(|history| history.borrow().join("\n"));
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    std::fs::write(&path, text)
+        .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_readline_history_round_trip() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "Readline.addHistory(\"first\")\n\
+                 Readline.addHistory(\"first\")\n\
+                 Readline.addHistory(\"second\")\n\
+                 let file = File.tempFile()\n\
+                 Readline.saveHistory(file.path)\n\
+                 Readline.clearHistory()\n\
+                 Readline.loadHistory(file.path)",
+            )
+            .unwrap();
+        let history: Vec<String> = engine.eval_as("Readline.history()").unwrap();
+        assert_eq!(history, ["first", "second"]);
+    }
+}
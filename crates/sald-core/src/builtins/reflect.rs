@@ -0,0 +1,308 @@
+//! Reflection over classes and instances
+//! Lists and reaches members by name for code that works on any object, such
+//! as serializers and dependency injection. Private members, whose names start
+//! with `_`, stay hidden as they are from other code outside the class.
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_string_arg};
+use crate::vm::caller::{CallableNativeStaticFn, ValueCaller};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn create_reflect_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("fields".to_string(), reflect_fields);
+    static_methods.insert("set".to_string(), reflect_set);
+    callable_methods.insert("classOf".to_string(), reflect_class_of);
+    callable_methods.insert("methods".to_string(), reflect_methods);
+    callable_methods.insert("get".to_string(), reflect_get);
+    callable_methods.insert("has".to_string(), reflect_has);
+    callable_methods.insert("call".to_string(), reflect_call);
+
+    let mut class = Class::new_with_static("Reflect", static_methods);
+    class.callable_native_static_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Reflect` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Reflect", "Inspect and use members of values by name")
+        .method("classOf", "classOf(value)", "Get the class of a value")
+        .method(
+            "methods",
+            "methods(classOrValue)",
+            "Get the names of the public methods of a class",
+        )
+        .method(
+            "fields",
+            "fields(instance)",
+            "Get the names of the public fields of an instance",
+        )
+        .method(
+            "get",
+            "get(value, name, default?)",
+            "Get a field or method of an instance, class, dict or namespace",
+        )
+        .method("has", "has(value, name)", "Check if a value has a member")
+        .method("set", "set(instance, name, value)", "Set a field")
+        .method(
+            "call",
+            "call(value, name, args?)",
+            "Call a method by name with an array of arguments",
+        )
+}
+
+fn is_private(name: &str) -> bool {
+    name.starts_with('_') && name.len() > 1
+}
+
+fn check_public(name: &str) -> Result<(), String> {
+    if is_private(name) {
+        return Err(format!(
+            "Cannot access private member '{}' through Reflect",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn strings(mut names: Vec<String>) -> Value {
+    names.sort();
+    names.dedup();
+    Value::Array(Rc::new(RefCell::new(
+        names
+            .into_iter()
+            .map(|name| Value::String(Rc::from(name.as_str())))
+            .collect(),
+    )))
+}
+
+/// The class of an instance, a builtin class for other values, or a class
+/// itself
+fn class_of(value: &Value, caller: &dyn ValueCaller) -> Option<Rc<Class>> {
+    match value {
+        Value::Instance(inst) => Some(inst.borrow().class.clone()),
+        Value::Class(class) => Some(class.clone()),
+        other => match caller.get_shared_globals().borrow().get(other.type_name()) {
+            Some(Value::Class(class)) => Some(class.clone()),
+            _ => None,
+        },
+    }
+}
+
+fn reflect_class_of(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(match &args[0] {
+        Value::Class(_) => Value::Null,
+        value => class_of(value, caller).map_or(Value::Null, Value::Class),
+    })
+}
+
+fn reflect_methods(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let Some(class) = class_of(&args[0], caller) else {
+        return Ok(strings(Vec::new()));
+    };
+    let names = class
+        .methods
+        .keys()
+        .chain(class.native_instance_methods.keys())
+        .chain(class.callable_native_instance_methods.keys())
+        .filter(|name| !is_private(name) && !name.starts_with("__"))
+        .cloned()
+        .collect();
+    Ok(strings(names))
+}
+
+fn reflect_fields(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let names = match &args[0] {
+        Value::Instance(inst) => inst
+            .borrow()
+            .fields
+            .keys()
+            .filter(|name| !is_private(name))
+            .cloned()
+            .collect(),
+        other => {
+            return Err(format!(
+                "Reflect.fields() expects an Instance, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    Ok(strings(names))
+}
+
+/// Looks up `name` on `target` the way property access does, returning
+/// a callable for methods.
+fn member(target: &Value, name: &str, caller: &dyn ValueCaller) -> Option<Value> {
+    match target {
+        Value::Instance(inst) => {
+            if let Some(value) = inst.borrow().fields.get(name) {
+                return Some(value.clone());
+            }
+        }
+        Value::Dictionary(dict) => return dict.borrow().get(name).cloned(),
+        Value::Namespace { members, .. } => return members.borrow().get(name).cloned(),
+        Value::Class(class) => {
+            if let Some(value) = class
+                .native_static_fields
+                .get(name)
+                .or_else(|| class.user_static_methods.get(name))
+            {
+                return Some(value.clone());
+            }
+            return class
+                .native_static_methods
+                .get(name)
+                .map(|func| Value::NativeFunction {
+                    func: *func,
+                    class_name: class.name.clone(),
+                });
+        }
+        _ => {}
+    }
+    let class = class_of(target, caller)?;
+    if let Some(Value::Function(method)) = class.methods.get(name) {
+        return Some(Value::BoundMethod {
+            receiver: Box::new(target.clone()),
+            method: method.clone(),
+        });
+    }
+    class
+        .native_instance_methods
+        .get(name)
+        .map(|method| Value::InstanceMethod {
+            receiver: Box::new(target.clone()),
+            method: *method,
+            method_name: name.to_string(),
+        })
+}
+
+fn reflect_get(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let name = get_string_arg(&args[1], "name")?;
+    check_public(&name)?;
+    Ok(member(&args[0], &name, caller)
+        .unwrap_or_else(|| args.get(2).cloned().unwrap_or(Value::Null)))
+}
+
+fn reflect_has(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let name = get_string_arg(&args[1], "name")?;
+    if is_private(&name) {
+        return Ok(Value::Boolean(false));
+    }
+    let callable_native = match (&args[0], class_of(&args[0], caller)) {
+        (Value::Class(class), _) => class.callable_native_static_methods.contains_key(&name),
+        (_, Some(class)) => class.callable_native_instance_methods.contains_key(&name),
+        _ => false,
+    };
+    Ok(Value::Boolean(
+        callable_native || member(&args[0], &name, caller).is_some(),
+    ))
+}
+
+fn reflect_set(args: &[Value]) -> Result<Value, String> {
+    check_arity(3, args.len())?;
+    let name = get_string_arg(&args[1], "name")?;
+    check_public(&name)?;
+    let Value::Instance(inst) = &args[0] else {
+        return Err(format!(
+            "Reflect.set() expects an Instance, got {}",
+            args[0].type_name()
+        ));
+    };
+    if super::types::is_frozen(&args[0]) {
+        return Err(format!("Cannot set '{}' on a frozen instance", name));
+    }
+    inst.borrow_mut().fields.insert(name, args[2].clone());
+    Ok(args[2].clone())
+}
+
+fn reflect_call(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let target = &args[0];
+    let name = get_string_arg(&args[1], "name")?;
+    check_public(&name)?;
+    let call_args = match args.get(2) {
+        None | Some(Value::Null) => Vec::new(),
+        Some(Value::Array(arr)) => arr.borrow().to_vec(),
+        Some(other) => {
+            return Err(format!(
+                "Reflect.call() expects an Array of arguments, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    if let Some(callee) = member(target, &name, caller) {
+        return caller.call(&callee, call_args);
+    }
+    // Natives that need the VM can't be turned into a value, so call them here
+    let class = class_of(target, caller);
+    match (target, &class) {
+        (Value::Class(class), _) => {
+            if let Some(method) = class.callable_native_static_methods.get(&name) {
+                return method(&call_args, caller);
+            }
+        }
+        (_, Some(class)) => {
+            if let Some(method) = class.callable_native_instance_methods.get(&name) {
+                return method(target, &call_args, caller);
+            }
+        }
+        _ => {}
+    }
+    let owner = match (target, class) {
+        (Value::Instance(_), Some(class)) => class.name.clone(),
+        _ => target.type_name().to_string(),
+    };
+    Err(format!("'{}' has no method '{}'", owner, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_reflection() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                class Point {
+                    fun init(self, x, y) {
+                        self.x = x
+                        self.y = y
+                        self._id = 1
+                    }
+                    fun sum(self, extra) { return self.x + self.y + extra }
+                }
+                let p = Point(1, 2)
+                Reflect.set(p, "y", 10)
+                let parts = [
+                    Reflect.classOf(p) == Point,
+                    Reflect.fields(p).join(","),
+                    Reflect.methods(Point).join(","),
+                    Reflect.get(p, "y"),
+                    Reflect.call(p, "sum", [100]),
+                    Reflect.call("abc", "upper"),
+                    Reflect.has(p, "_id")
+                ]
+                parts.map(|v| "" + v).join(" ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result, "true x,y init,sum 10 111 ABC false");
+
+        let err = engine
+            .eval("Reflect.get(Point(1, 2), \"_id\")")
+            .unwrap_err();
+        assert!(err.message().contains("private member '_id'"));
+    }
+}
@@ -1,30 +1,95 @@
-use super::{check_arity, check_arity_range, get_string_arg};
+use super::docs::ClassDoc;
+use super::iter::iterate;
+use super::{
+    check_arity, check_arity_range, get_number_arg, get_string_arg, native_instance, native_state,
+};
+use crate::vm::caller::{CallableNativeInstanceFn, ValueCaller};
 use crate::vm::value::{Class, Instance, NativeInstanceFn, NativeStaticFn, Value};
-use regex::Regex as RustRegex;
+use regex::{Captures, Regex as RustRegex};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Compiled patterns are reused across instances; the cache is cleared
+/// rather than evicted piecemeal once it grows past this many entries.
+const CACHE_LIMIT: usize = 256;
+
+thread_local! {
+    static CACHE: RefCell<FxHashMap<String, RustRegex>> = RefCell::new(FxHashMap::default());
+}
+
 pub fn create_regex_class() -> Class {
     let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
     let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
 
     static_methods.insert("new".to_string(), regex_new);
 
     instance_methods.insert("test".to_string(), regex_test);
     instance_methods.insert("match".to_string(), regex_match);
     instance_methods.insert("matchAll".to_string(), regex_match_all);
-    instance_methods.insert("replace".to_string(), regex_replace);
-    instance_methods.insert("replaceAll".to_string(), regex_replace_all);
+    instance_methods.insert("exec".to_string(), regex_exec);
+    instance_methods.insert("matches".to_string(), regex_matches);
     instance_methods.insert("split".to_string(), regex_split);
     instance_methods.insert("pattern".to_string(), regex_pattern);
     instance_methods.insert("flags".to_string(), regex_flags);
 
+    callable_methods.insert("replace".to_string(), regex_replace);
+    callable_methods.insert("replaceAll".to_string(), regex_replace_all);
+
     let mut class = Class::new_with_instance("Regex", instance_methods, None);
     class.native_static_methods = static_methods;
+    class.callable_native_instance_methods = callable_methods;
     class
 }
 
+/// Lazy cursor over the matches of a pattern, wrapped by `Regex.matches()`
+/// Position of a `RegexMatches` cursor in its input
+struct MatchCursor {
+    regex: RustRegex,
+    input: Rc<str>,
+    /// Byte offset of the next search; `None` once exhausted
+    offset: Option<usize>,
+}
+
+fn create_regex_matches_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    instance_methods.insert("next".to_string(), regex_matches_next);
+    Class::new_with_instance("RegexMatches", instance_methods, None)
+}
+
+/// API documentation for the `Regex` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Regex", "Regular expression operations")
+        .method("new", "new(pattern, flags?)", "Create a new regex")
+        .method("test", "test(string)", "Test if pattern matches")
+        .method("match", "match(string)", "Find first match with groups")
+        .method("matchAll", "matchAll(string)", "Find all matches")
+        .method(
+            "exec",
+            "exec(string, from?)",
+            "First match object {match, index, end, groups, named} at or after from, or null",
+        )
+        .method("matches", "matches(string)", "Lazy Iter over match objects")
+        .method(
+            "replace",
+            "replace(string, replacement)",
+            "Replace first match; replacement may be fn(match object)",
+        )
+        .method(
+            "replaceAll",
+            "replaceAll(string, replacement)",
+            "Replace all matches; replacement may be fn(match object)",
+        )
+        .method(
+            "split",
+            "split(string, limit?)",
+            "Split string by pattern into at most limit parts",
+        )
+        .method("pattern", "pattern()", "Get the pattern string")
+        .method("flags", "flags()", "Get the flags string")
+}
+
 fn get_regex_from_instance(inst: &Instance) -> Result<RustRegex, String> {
     let pattern = inst
         .fields
@@ -53,6 +118,7 @@ fn get_regex_from_instance(inst: &Instance) -> Result<RustRegex, String> {
     build_regex(&pattern, &flags)
 }
 
+/// Compiles a pattern, reusing an earlier compilation of the same source.
 fn build_regex(pattern: &str, flags: &str) -> Result<RustRegex, String> {
     let case_insensitive = flags.contains('i');
     let multiline = flags.contains('m');
@@ -76,7 +142,101 @@ fn build_regex(pattern: &str, flags: &str) -> Result<RustRegex, String> {
 
     regex_pattern.push_str(pattern);
 
-    RustRegex::new(&regex_pattern).map_err(|e| format!("Invalid regex pattern: {}", e))
+    if let Some(regex) = CACHE.with(|cache| cache.borrow().get(&regex_pattern).cloned()) {
+        return Ok(regex);
+    }
+    let regex =
+        RustRegex::new(&regex_pattern).map_err(|e| format!("Invalid regex pattern: {}", e))?;
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(regex_pattern, regex.clone());
+    });
+    Ok(regex)
+}
+
+/// Builds `{match, index, end, groups, named}`, with character offsets and
+/// null for groups that did not participate.
+fn match_object(regex: &RustRegex, input: &str, captures: &Captures) -> Value {
+    let whole = captures.get(0).expect("group 0 always matches");
+    let index = input[..whole.start()].chars().count();
+    let end = index + whole.as_str().chars().count();
+
+    let group = |m: Option<regex::Match>| match m {
+        Some(m) => Value::String(Rc::from(m.as_str())),
+        None => Value::Null,
+    };
+    let groups: Vec<Value> = captures.iter().skip(1).map(group).collect();
+    let named: FxHashMap<String, Value> = regex
+        .capture_names()
+        .flatten()
+        .map(|name| (name.to_string(), group(captures.name(name))))
+        .collect();
+
+    let mut object = FxHashMap::default();
+    object.insert("match".to_string(), Value::String(Rc::from(whole.as_str())));
+    object.insert("index".to_string(), Value::Number(index as f64));
+    object.insert("end".to_string(), Value::Number(end as f64));
+    object.insert(
+        "groups".to_string(),
+        Value::Array(Rc::new(RefCell::new(groups.into()))),
+    );
+    object.insert(
+        "named".to_string(),
+        Value::Dictionary(Rc::new(RefCell::new(named.into()))),
+    );
+    Value::Dictionary(Rc::new(RefCell::new(object.into())))
+}
+
+/// Converts a character offset into a byte offset, clamped to the end.
+fn byte_offset(input: &str, chars: usize) -> usize {
+    input
+        .char_indices()
+        .nth(chars)
+        .map_or(input.len(), |(i, _)| i)
+}
+
+/// Replaces up to `limit` matches (all when `None`), calling `replacement`
+/// with the match object when it is a function.
+fn replace_matches(
+    recv: &Value,
+    args: &[Value],
+    limit: Option<usize>,
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    let Value::Instance(inst) = recv else {
+        return Err("Receiver must be a Regex instance".to_string());
+    };
+    let regex = get_regex_from_instance(&inst.borrow())?;
+    let input = get_string_arg(&args[0], "string")?;
+
+    let func = match &args[1] {
+        Value::String(replacement) => {
+            let result = match limit {
+                Some(n) => regex.replacen(&input, n, replacement.as_ref()),
+                None => regex.replace_all(&input, replacement.as_ref()),
+            };
+            return Ok(Value::String(Rc::from(result.into_owned())));
+        }
+        func => func.clone(),
+    };
+
+    let mut result = String::with_capacity(input.len());
+    let mut last = 0;
+    for captures in regex
+        .captures_iter(&input)
+        .take(limit.unwrap_or(usize::MAX))
+    {
+        let whole = captures.get(0).expect("group 0 always matches");
+        let replaced = caller.call(&func, vec![match_object(&regex, &input, &captures)])?;
+        result.push_str(&input[last..whole.start()]);
+        result.push_str(&replaced.to_string());
+        last = whole.end();
+    }
+    result.push_str(&input[last..]);
+    Ok(Value::String(Rc::from(result)))
 }
 
 fn regex_new(args: &[Value]) -> Result<Value, String> {
@@ -133,7 +293,7 @@ fn regex_match(recv: &Value, args: &[Value]) -> Result<Value, String> {
                     matches.push(Value::Null);
                 }
             }
-            Ok(Value::Array(Rc::new(RefCell::new(matches))))
+            Ok(Value::Array(Rc::new(RefCell::new(matches.into()))))
         } else {
             Ok(Value::Null)
         }
@@ -161,61 +321,119 @@ fn regex_match_all(recv: &Value, args: &[Value]) -> Result<Value, String> {
                     match_group.push(Value::Null);
                 }
             }
-            all_matches.push(Value::Array(Rc::new(RefCell::new(match_group))));
+            all_matches.push(Value::Array(Rc::new(RefCell::new(match_group.into()))));
         }
 
-        Ok(Value::Array(Rc::new(RefCell::new(all_matches))))
+        Ok(Value::Array(Rc::new(RefCell::new(all_matches.into()))))
     } else {
         Err("matchAll() must be called on a Regex instance".to_string())
     }
 }
 
-fn regex_replace(recv: &Value, args: &[Value]) -> Result<Value, String> {
-    check_arity(2, args.len())?;
+fn regex_exec(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
 
     if let Value::Instance(inst) = recv {
         let inst = inst.borrow();
         let regex = get_regex_from_instance(&inst)?;
         let input = get_string_arg(&args[0], "string")?;
-        let replacement = get_string_arg(&args[1], "replacement")?;
-
-        let result = regex.replace(&input, replacement.as_str());
-        Ok(Value::String(Rc::from(result.into_owned())))
+        let from = match args.get(1) {
+            Some(from) => byte_offset(&input, get_number_arg(from, "from")?.max(0.0) as usize),
+            None => 0,
+        };
+
+        Ok(regex
+            .captures_at(&input, from)
+            .map_or(Value::Null, |captures| {
+                match_object(&regex, &input, &captures)
+            }))
     } else {
-        Err("replace() must be called on a Regex instance".to_string())
+        Err("exec() must be called on a Regex instance".to_string())
     }
 }
 
-fn regex_replace_all(recv: &Value, args: &[Value]) -> Result<Value, String> {
-    check_arity(2, args.len())?;
+fn regex_matches(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
 
     if let Value::Instance(inst) = recv {
-        let inst = inst.borrow();
-        let regex = get_regex_from_instance(&inst)?;
+        let regex = get_regex_from_instance(&inst.borrow())?;
         let input = get_string_arg(&args[0], "string")?;
-        let replacement = get_string_arg(&args[1], "replacement")?;
-
-        let result = regex.replace_all(&input, replacement.as_str());
-        Ok(Value::String(Rc::from(result.into_owned())))
+        let cursor = MatchCursor {
+            regex,
+            input: Rc::from(input),
+            offset: Some(0),
+        };
+        iterate(&native_instance(create_regex_matches_class(), cursor))
     } else {
-        Err("replaceAll() must be called on a Regex instance".to_string())
+        Err("matches() must be called on a Regex instance".to_string())
     }
 }
 
+fn regex_matches_next(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+
+    let cursor = native_state::<MatchCursor>(recv, "RegexMatches")?;
+    let mut cursor = cursor.borrow_mut();
+    let Some(offset) = cursor.offset else {
+        return Ok(Value::Null);
+    };
+    let input = cursor.input.clone();
+    let Some(captures) = cursor.regex.captures_at(&input, offset) else {
+        cursor.offset = None;
+        return Ok(Value::Null);
+    };
+    let whole = captures.get(0).expect("group 0 always matches");
+    // Step over empty matches so the cursor always advances
+    cursor.offset = if whole.is_empty() {
+        input[whole.end()..]
+            .chars()
+            .next()
+            .map(|c| whole.end() + c.len_utf8())
+    } else {
+        Some(whole.end())
+    };
+    Ok(match_object(&cursor.regex, &input, &captures))
+}
+
+fn regex_replace(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    replace_matches(recv, args, Some(1), caller)
+}
+
+fn regex_replace_all(
+    recv: &Value,
+    args: &[Value],
+    caller: &mut dyn ValueCaller,
+) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    replace_matches(recv, args, None, caller)
+}
+
 fn regex_split(recv: &Value, args: &[Value]) -> Result<Value, String> {
-    check_arity(1, args.len())?;
+    check_arity_range(1, 2, args.len())?;
 
     if let Value::Instance(inst) = recv {
         let inst = inst.borrow();
         let regex = get_regex_from_instance(&inst)?;
         let input = get_string_arg(&args[0], "string")?;
+        let to_value = |s: &str| Value::String(Rc::from(s));
+
+        let parts: Vec<Value> = match args.get(1) {
+            None | Some(Value::Null) => regex.split(&input).map(to_value).collect(),
+            Some(limit) => {
+                let limit = get_number_arg(limit, "limit")?;
+                if limit < 1.0 || limit.fract() != 0.0 {
+                    return Err("Argument 'limit' must be a positive integer".to_string());
+                }
+                regex.splitn(&input, limit as usize).map(to_value).collect()
+            }
+        };
 
-        let parts: Vec<Value> = regex
-            .split(&input)
-            .map(|s| Value::String(Rc::from(s.to_string())))
-            .collect();
-
-        Ok(Value::Array(Rc::new(RefCell::new(parts))))
+        Ok(Value::Array(Rc::new(RefCell::new(parts.into()))))
     } else {
         Err("split() must be called on a Regex instance".to_string())
     }
@@ -250,3 +468,40 @@ fn regex_flags(recv: &Value, args: &[Value]) -> Result<Value, String> {
         Err("flags() must be called on a Regex instance".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_regex_named_groups_and_callbacks() {
+        let mut engine = Engine::new();
+        engine
+            .eval("let m = Regex.new(\"(?P<key>\\\\w+)=(?P<value>\\\\d+)\").exec(\"x a=1\")")
+            .unwrap();
+        let named: Vec<String> = engine
+            .eval_as("[m[\"named\"][\"key\"], m[\"named\"][\"value\"], \"\" + m[\"index\"]]")
+            .unwrap();
+        assert_eq!(named, ["a", "1", "2"]);
+        let replaced: String = engine
+            .eval_as(
+                "Regex.new(\"\\\\d+\").replaceAll(\"a1b22\", |m| \"<\" + m[\"match\"] + \">\")",
+            )
+            .unwrap();
+        assert_eq!(replaced, "a<1>b<22>");
+        let lazy: Vec<String> = engine
+            .eval_as(
+                "Regex.new(\"\\\\d\").matches(\"1a2b3\").map(|m| m[\"match\"]).take(2).toArray()",
+            )
+            .unwrap();
+        assert_eq!(lazy, ["1", "2"]);
+        let empty: Vec<f64> = engine
+            .eval_as("Regex.new(\"\").matches(\"aé\").map(|m| m[\"index\"]).toArray()")
+            .unwrap();
+        assert_eq!(empty.len(), 3);
+        let parts: Vec<String> = engine
+            .eval_as("Regex.new(\",\\\\s*\").split(\"a, b,c\", 2)")
+            .unwrap();
+        assert_eq!(parts, ["a", "b,c"]);
+    }
+}
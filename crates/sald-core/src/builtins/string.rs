@@ -1,8 +1,12 @@
-use super::{check_arity, get_number_arg, get_string_arg};
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, get_string_arg, repeat_len};
 use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
 
 pub fn create_string_class() -> Class {
     let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
@@ -38,12 +42,157 @@ pub fn create_string_class() -> Class {
     instance_methods.insert("lastIndexOf".to_string(), string_last_index_of);
     instance_methods.insert("replaceAll".to_string(), string_replace_all);
     instance_methods.insert("includes".to_string(), string_contains);
+    instance_methods.insert("splitLines".to_string(), string_split_lines);
+    instance_methods.insert("caseFold".to_string(), string_case_fold);
+    instance_methods.insert("count".to_string(), string_count);
+    instance_methods.insert("chars".to_string(), string_chars);
+    instance_methods.insert("graphemes".to_string(), string_graphemes);
+    instance_methods.insert("graphemeLength".to_string(), string_grapheme_length);
+    instance_methods.insert("normalize".to_string(), string_normalize);
+    instance_methods.insert("displayWidth".to_string(), string_display_width);
+    instance_methods.insert("isAlphabetic".to_string(), string_is_alphabetic);
+    instance_methods.insert("isAlpha".to_string(), string_is_alphabetic);
+    instance_methods.insert("isNumeric".to_string(), string_is_numeric);
+    instance_methods.insert("isAlphanumeric".to_string(), string_is_alphanumeric);
+    instance_methods.insert("isWhitespace".to_string(), string_is_whitespace);
 
     let mut class = Class::new_with_instance("String", instance_methods, Some(string_constructor));
     class.native_static_methods = static_methods;
     class
 }
 
+/// API documentation for the `String` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("String", "String operations")
+        .method("length", "length()", "Get string length")
+        .method("upper", "upper()", "Convert to uppercase")
+        .method("lower", "lower()", "Convert to lowercase")
+        .method(
+            "trim",
+            "trim(chars?)",
+            "Remove whitespace, or any of chars, from both ends",
+        )
+        .method(
+            "trimStart",
+            "trimStart(chars?)",
+            "Remove leading whitespace, or any of chars",
+        )
+        .method(
+            "trimEnd",
+            "trimEnd(chars?)",
+            "Remove trailing whitespace, or any of chars",
+        )
+        .method(
+            "contains",
+            "contains(substr)",
+            "Check if contains substring",
+        )
+        .method(
+            "includes",
+            "includes(substr)",
+            "Check if contains substring",
+        )
+        .method("startsWith", "startsWith(prefix)", "Check prefix")
+        .method("endsWith", "endsWith(suffix)", "Check suffix")
+        .method("charAt", "charAt(index)", "Get character at index")
+        .method("charCodeAt", "charCodeAt(index)", "Get char code at index")
+        .method("substring", "substring(start, end?)", "Get substring")
+        .method(
+            "slice",
+            "slice(start, end?)",
+            "Get slice (negative allowed)",
+        )
+        .method(
+            "indexOf",
+            "indexOf(substr)",
+            "Find first index of substring",
+        )
+        .method(
+            "lastIndexOf",
+            "lastIndexOf(substr)",
+            "Find last index of substring",
+        )
+        .method("replace", "replace(old, new)", "Replace first occurrence")
+        .method(
+            "replaceAll",
+            "replaceAll(old, new)",
+            "Replace all occurrences",
+        )
+        .method("split", "split(separator)", "Split to array")
+        .method("repeat", "repeat(count)", "Repeat string n times")
+        .method(
+            "padStart",
+            "padStart(length, pad?)",
+            "Pad start to length, repeating pad (a space)",
+        )
+        .method(
+            "padEnd",
+            "padEnd(length, pad?)",
+            "Pad end to length, repeating pad (a space)",
+        )
+        .method(
+            "splitLines",
+            "splitLines()",
+            "Split on \\n, \\r\\n or \\r line endings",
+        )
+        .method(
+            "caseFold",
+            "caseFold()",
+            "Locale-independent folded form for caseless comparison",
+        )
+        .method(
+            "count",
+            "count(substr)",
+            "Number of non-overlapping occurrences",
+        )
+        .method("chars", "chars()", "Array of code points as strings")
+        .method(
+            "graphemes",
+            "graphemes()",
+            "Array of user-perceived characters (grapheme clusters)",
+        )
+        .method(
+            "graphemeLength",
+            "graphemeLength()",
+            "Number of grapheme clusters",
+        )
+        .method("concat", "concat(...strings)", "Concatenate strings")
+        .method("isDigit", "isDigit()", "Check if single digit")
+        .method(
+            "isAlphabetic",
+            "isAlphabetic()",
+            "Check if every character is a Unicode letter",
+        )
+        .method("isAlpha", "isAlpha()", "Same as isAlphabetic")
+        .method(
+            "isNumeric",
+            "isNumeric()",
+            "Check if every character is a Unicode number",
+        )
+        .method(
+            "isAlphanumeric",
+            "isAlphanumeric()",
+            "Check if every character is a letter or number",
+        )
+        .method(
+            "isWhitespace",
+            "isWhitespace()",
+            "Check if every character is whitespace",
+        )
+        .method(
+            "normalize",
+            "normalize(form?)",
+            "Unicode normal form: \"NFC\" (default), \"NFD\", \"NFKC\" or \"NFKD\"",
+        )
+        .method(
+            "displayWidth",
+            "displayWidth()",
+            "Terminal columns taken, counting wide characters as 2 and ignoring ANSI codes",
+        )
+        .method("toNumber", "toNumber()", "Parse as number")
+        .method("toString", "toString()", "Convert to string")
+}
+
 fn string_from_char_code(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     let code = get_number_arg(&args[0], "code")? as u32;
@@ -103,9 +252,16 @@ fn string_lower(recv: &Value, args: &[Value]) -> Result<Value, String> {
 }
 
 fn string_trim(recv: &Value, args: &[Value]) -> Result<Value, String> {
-    check_arity(0, args.len())?;
+    check_arity_range(0, 1, args.len())?;
     if let Value::String(s) = recv {
-        Ok(Value::String(Rc::from(s.trim().to_string())))
+        let trimmed = match args.first() {
+            Some(chars) => {
+                let chars: Vec<char> = get_string_arg(chars, "chars")?.chars().collect();
+                s.trim_matches(chars.as_slice())
+            }
+            None => s.trim(),
+        };
+        Ok(Value::String(Rc::from(trimmed)))
     } else {
         Err("Receiver must be a string".to_string())
     }
@@ -239,7 +395,7 @@ fn string_split(recv: &Value, args: &[Value]) -> Result<Value, String> {
             .split(&sep)
             .map(|p| Value::String(Rc::from(p.to_string())))
             .collect();
-        Ok(Value::Array(Rc::new(RefCell::new(parts))))
+        Ok(Value::Array(Rc::new(RefCell::new(parts.into()))))
     } else {
         Err("Receiver must be a string".to_string())
     }
@@ -343,19 +499,16 @@ fn string_pad_start(recv: &Value, args: &[Value]) -> Result<Value, String> {
     }
     if let Value::String(s) = recv {
         let target_len = get_number_arg(&args[0], "length")? as usize;
-        let pad_char = if args.len() == 2 {
-            let pad_str = get_string_arg(&args[1], "char")?;
-            pad_str.chars().next().unwrap_or(' ')
+        let pad = if args.len() == 2 {
+            get_string_arg(&args[1], "pad")?
         } else {
-            ' '
+            " ".to_string()
         };
         let current_len = s.chars().count();
-        if current_len >= target_len {
+        if current_len >= target_len || pad.is_empty() {
             return Ok(Value::String(Rc::clone(s)));
         }
-        let padding: String = std::iter::repeat(pad_char)
-            .take(target_len - current_len)
-            .collect();
+        let padding: String = pad.chars().cycle().take(target_len - current_len).collect();
         Ok(Value::String(Rc::from(format!("{}{}", padding, s))))
     } else {
         Err("Receiver must be a string".to_string())
@@ -368,19 +521,16 @@ fn string_pad_end(recv: &Value, args: &[Value]) -> Result<Value, String> {
     }
     if let Value::String(s) = recv {
         let target_len = get_number_arg(&args[0], "length")? as usize;
-        let pad_char = if args.len() == 2 {
-            let pad_str = get_string_arg(&args[1], "char")?;
-            pad_str.chars().next().unwrap_or(' ')
+        let pad = if args.len() == 2 {
+            get_string_arg(&args[1], "pad")?
         } else {
-            ' '
+            " ".to_string()
         };
         let current_len = s.chars().count();
-        if current_len >= target_len {
+        if current_len >= target_len || pad.is_empty() {
             return Ok(Value::String(Rc::clone(s)));
         }
-        let padding: String = std::iter::repeat(pad_char)
-            .take(target_len - current_len)
-            .collect();
+        let padding: String = pad.chars().cycle().take(target_len - current_len).collect();
         Ok(Value::String(Rc::from(format!("{}{}", s, padding))))
     } else {
         Err("Receiver must be a string".to_string())
@@ -390,26 +540,41 @@ fn string_pad_end(recv: &Value, args: &[Value]) -> Result<Value, String> {
 fn string_repeat(recv: &Value, args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     if let Value::String(s) = recv {
-        let count = get_number_arg(&args[0], "count")? as usize;
-        Ok(Value::String(Rc::from(s.repeat(count))))
+        let count = get_number_arg(&args[0], "count")?;
+        repeat_len(s.len(), count)?;
+        Ok(Value::String(Rc::from(s.repeat(count as usize))))
     } else {
         Err("Receiver must be a string".to_string())
     }
 }
 
 fn string_trim_start(recv: &Value, args: &[Value]) -> Result<Value, String> {
-    check_arity(0, args.len())?;
+    check_arity_range(0, 1, args.len())?;
     if let Value::String(s) = recv {
-        Ok(Value::String(Rc::from(s.trim_start().to_string())))
+        let trimmed = match args.first() {
+            Some(chars) => {
+                let chars: Vec<char> = get_string_arg(chars, "chars")?.chars().collect();
+                s.trim_start_matches(chars.as_slice())
+            }
+            None => s.trim_start(),
+        };
+        Ok(Value::String(Rc::from(trimmed)))
     } else {
         Err("Receiver must be a string".to_string())
     }
 }
 
 fn string_trim_end(recv: &Value, args: &[Value]) -> Result<Value, String> {
-    check_arity(0, args.len())?;
+    check_arity_range(0, 1, args.len())?;
     if let Value::String(s) = recv {
-        Ok(Value::String(Rc::from(s.trim_end().to_string())))
+        let trimmed = match args.first() {
+            Some(chars) => {
+                let chars: Vec<char> = get_string_arg(chars, "chars")?.chars().collect();
+                s.trim_end_matches(chars.as_slice())
+            }
+            None => s.trim_end(),
+        };
+        Ok(Value::String(Rc::from(trimmed)))
     } else {
         Err("Receiver must be a string".to_string())
     }
@@ -465,3 +630,202 @@ fn string_replace_all(recv: &Value, args: &[Value]) -> Result<Value, String> {
         Err("Receiver must be a string".to_string())
     }
 }
+
+fn string_array(items: impl Iterator<Item = String>) -> Value {
+    let items = items.map(|item| Value::String(Rc::from(item))).collect();
+    Value::Array(Rc::new(RefCell::new(items)))
+}
+
+fn string_split_lines(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    if let Value::String(s) = recv {
+        let mut lines = Vec::new();
+        let mut rest: &str = s;
+        while let Some(end) = rest.find(['\n', '\r']) {
+            lines.push(rest[..end].to_string());
+            rest = &rest[end..];
+            rest = rest.strip_prefix("\r\n").unwrap_or_else(|| &rest[1..]);
+        }
+        // A final line ending does not start another line
+        if !rest.is_empty() {
+            lines.push(rest.to_string());
+        }
+        Ok(string_array(lines.into_iter()))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+fn string_case_fold(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    if let Value::String(s) = recv {
+        Ok(Value::String(Rc::from(caseless::default_case_fold_str(s))))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+fn string_count(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    if let Value::String(s) = recv {
+        let substr = get_string_arg(&args[0], "substr")?;
+        if substr.is_empty() {
+            return Err("Argument 'substr' cannot be empty".to_string());
+        }
+        Ok(Value::Number(s.matches(substr.as_str()).count() as f64))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+fn string_chars(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    if let Value::String(s) = recv {
+        Ok(string_array(s.chars().map(String::from)))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+fn string_graphemes(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    if let Value::String(s) = recv {
+        Ok(string_array(s.graphemes(true).map(String::from)))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+fn string_grapheme_length(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    if let Value::String(s) = recv {
+        Ok(Value::Number(s.graphemes(true).count() as f64))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+/// Terminal columns `text` takes: East Asian wide characters count as two,
+/// combining marks and ANSI escape sequences as nothing.
+pub(crate) fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip a CSI sequence up to its final byte
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        width += c.width().unwrap_or(0);
+    }
+    width
+}
+
+fn string_normalize(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    if let Value::String(s) = recv {
+        let form = match args.first() {
+            Some(form) => get_string_arg(form, "form")?,
+            None => "NFC".to_string(),
+        };
+        let normalized: String = match form.to_ascii_uppercase().as_str() {
+            "NFC" => s.nfc().collect(),
+            "NFD" => s.nfd().collect(),
+            "NFKC" => s.nfkc().collect(),
+            "NFKD" => s.nfkd().collect(),
+            _ => {
+                return Err(format!(
+                    "Unknown normalization form '{}', expected NFC, NFD, NFKC or NFKD",
+                    form
+                ))
+            }
+        };
+        Ok(Value::String(Rc::from(normalized)))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+fn string_display_width(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    if let Value::String(s) = recv {
+        Ok(Value::Number(display_width(s) as f64))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+/// Whether a non-empty string consists only of characters matching `test`.
+fn all_chars(recv: &Value, args: &[Value], test: fn(char) -> bool) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    if let Value::String(s) = recv {
+        Ok(Value::Boolean(!s.is_empty() && s.chars().all(test)))
+    } else {
+        Err("Receiver must be a string".to_string())
+    }
+}
+
+fn string_is_alphabetic(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    all_chars(recv, args, char::is_alphabetic)
+}
+
+fn string_is_numeric(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    all_chars(recv, args, char::is_numeric)
+}
+
+fn string_is_alphanumeric(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    all_chars(recv, args, char::is_alphanumeric)
+}
+
+fn string_is_whitespace(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    all_chars(recv, args, char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_string_lines_folding_and_graphemes() {
+        let mut engine = Engine::new();
+        let lines: Vec<String> = engine.eval_as("\"a\\r\\nb\\rc\\n\".splitLines()").unwrap();
+        assert_eq!(lines, ["a", "b", "c"]);
+        let folded: bool = engine
+            .eval_as("\"Straße\".caseFold() == \"STRASSE\".caseFold()")
+            .unwrap();
+        assert!(folded);
+        let trimmed: String = engine
+            .eval_as("\"--a--\".trim(\"-\") + \"7\".padStart(3, \"0\")")
+            .unwrap();
+        assert_eq!(trimmed, "a007");
+        let count: f64 = engine.eval_as("\"banana\".count(\"an\")").unwrap();
+        assert_eq!(count, 2.0);
+        let graphemes: f64 = engine
+            .eval_as("\"e\\u{301}\\u{1F1F3}\\u{1F1F1}\".graphemeLength()")
+            .unwrap();
+        assert_eq!(graphemes, 2.0);
+    }
+
+    #[test]
+    fn test_string_normalization_and_width() {
+        let mut engine = Engine::new();
+        let composed: bool = engine
+            .eval_as("\"e\\u{301}\".normalize() == \"\\u{e9}\" && \"\\u{e9}\".normalize(\"NFD\") == \"e\\u{301}\"")
+            .unwrap();
+        assert!(composed);
+        let widths: Vec<f64> = engine
+            .eval_as("[\"日本\".displayWidth(), \"\\u{1b}[1mab\\u{1b}[0m\".displayWidth()]")
+            .unwrap();
+        assert_eq!(widths, [4.0, 2.0]);
+        let classes: Vec<bool> = engine
+            .eval_as("[\"héllo\".isAlphabetic(), \"٣4\".isNumeric(), \"a b\".isAlphanumeric()]")
+            .unwrap();
+        assert_eq!(classes, [true, true, false]);
+    }
+}
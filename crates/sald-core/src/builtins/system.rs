@@ -1,3 +1,4 @@
+use super::docs::ClassDoc;
 use crate::vm::value::{Class, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
@@ -34,7 +35,60 @@ pub fn create_system_class() -> Class {
     static_methods.insert("setenv".to_string(), system_setenv);
     static_methods.insert("envs".to_string(), system_envs);
 
-    Class::new_with_static("System", static_methods)
+    static_methods.insert("onSignal".to_string(), system_on_signal);
+
+    static_methods.insert("loadDotenv".to_string(), super::env::system_load_dotenv);
+    static_methods.insert("parseDotenv".to_string(), super::env::system_parse_dotenv);
+
+    let mut class = Class::new_with_static("System", static_methods);
+    class
+        .native_static_fields
+        .insert("env".to_string(), super::env::create_env());
+    class.native_static_fields.insert(
+        "platform".to_string(),
+        Value::String(Rc::from(crate::target::PLATFORM)),
+    );
+    class
+}
+
+/// API documentation for the `System` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("System", "System information")
+        .method("os", "os()", "Operating system name")
+        .method("arch", "arch()", "CPU architecture")
+        .method("family", "family()", "OS family (unix/windows)")
+        .method("cpuCount", "cpuCount()", "Number of CPU cores")
+        .method("hostname", "hostname()", "Computer hostname")
+        .method("osVersion", "osVersion()", "OS version string")
+        .method("kernelVersion", "kernelVersion()", "Kernel version")
+        .method("totalMemory", "totalMemory()", "Total RAM in bytes")
+        .method("usedMemory", "usedMemory()", "Used RAM in bytes")
+        .method("freeMemory", "freeMemory()", "Free RAM in bytes")
+        .method("cpuName", "cpuName()", "CPU model name")
+        .method("cpuUsage", "cpuUsage()", "CPU usage percentage")
+        .method("uptime", "uptime()", "System uptime in seconds")
+        .method("bootTime", "bootTime()", "Boot time as Unix timestamp")
+        .method("info", "info()", "All system info as dictionary")
+        .method("getenv", "getenv(name)", "Get environment variable")
+        .method("setenv", "setenv(name, value)", "Set environment variable")
+        .method("envs", "envs()", "All environment variables")
+        .property("env", "Live environment as an Env object")
+        .property("platform", "\"native\", or \"wasm\" in the browser build")
+        .method(
+            "loadDotenv",
+            "loadDotenv(path?, override?)",
+            "Load a .env file into the environment without replacing set variables unless override",
+        )
+        .method(
+            "parseDotenv",
+            "parseDotenv(text)",
+            "Parse .env text into a dict",
+        )
+        .method(
+            "onSignal",
+            "onSignal(signal, fn)",
+            "Call fn with the signal name instead of exiting, or restore exiting if fn is null",
+        )
 }
 
 fn system_os(_args: &[Value]) -> Result<Value, String> {
@@ -200,7 +254,7 @@ fn system_info(_args: &[Value]) -> Result<Value, String> {
 
     info.insert("uptime".to_string(), Value::Number(System::uptime() as f64));
 
-    Ok(Value::Dictionary(Rc::new(RefCell::new(info))))
+    Ok(Value::Dictionary(Rc::new(RefCell::new(info.into()))))
 }
 
 fn system_getenv(args: &[Value]) -> Result<Value, String> {
@@ -243,5 +297,34 @@ fn system_envs(_args: &[Value]) -> Result<Value, String> {
         envs.insert(key, Value::String(Rc::from(value)));
     }
 
-    Ok(Value::Dictionary(Rc::new(RefCell::new(envs))))
+    Ok(Value::Dictionary(Rc::new(RefCell::new(envs.into()))))
+}
+
+/// Signal number from a name like "SIGTERM" or a plain number.
+pub(super) fn get_signal_arg(value: &Value) -> Result<i32, String> {
+    match value {
+        Value::Number(n) => Ok(*n as i32),
+        Value::String(name) => crate::vm::signals::signal_number(name)
+            .ok_or_else(|| format!("Unknown signal '{}'", name)),
+        other => Err(format!(
+            "Argument 'signal' must be a string or number, got {}",
+            other.type_name()
+        )),
+    }
+}
+
+fn system_on_signal(args: &[Value]) -> Result<Value, String> {
+    super::check_arity(2, args.len())?;
+    let signal = get_signal_arg(&args[0])?;
+    match &args[1] {
+        Value::Null | Value::Function(_) | Value::BoundMethod { .. } | Value::Partial { .. } => {}
+        other => {
+            return Err(format!(
+                "Argument 'fn' must be a function, got {}",
+                other.type_name()
+            ))
+        }
+    }
+    crate::vm::signals::set_handler(signal, args[1].clone())?;
+    Ok(Value::Null)
 }
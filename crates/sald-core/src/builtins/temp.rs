@@ -0,0 +1,194 @@
+//! Temporary files and directories
+//! `File.tempFile` and `File.tempDir` create uniquely named entries with
+//! exclusive creation, so two scripts can never race for the same name. The
+//! returned handle deletes the entry once the last reference
+//! to the handle is gone, unless `keep()` was called first
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_string_arg, native_instance, native_state};
+use crate::vm::value::{Class, NativeInstanceFn, Value};
+use rand::distr::{Alphanumeric, SampleString};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::fs::OpenOptions;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// Names tried before giving up on a crowded directory
+const ATTEMPTS: usize = 16;
+
+/// Deletes its entry when the handle holding it is dropped, unless the
+/// script already kept or removed it.
+struct TempEntry {
+    path: PathBuf,
+    cleanup: bool,
+}
+
+impl Drop for TempEntry {
+    fn drop(&mut self) {
+        if self.cleanup {
+            remove_entry(&self.path);
+        }
+    }
+}
+
+fn remove_entry(path: &std::path::Path) -> bool {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path).is_ok()
+    } else {
+        std::fs::remove_file(path).is_ok()
+    }
+}
+
+fn create_temp_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("keep".to_string(), temp_keep);
+    instance_methods.insert("remove".to_string(), temp_remove);
+
+    Class::new_with_instance("TempPath", instance_methods, None)
+}
+
+/// API documentation for the `TempPath` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "TempPath",
+        "Temporary file or directory from File.tempFile or File.tempDir, deleted once unreachable",
+    )
+    .property("path", "Absolute path of the entry")
+    .method(
+        "keep",
+        "keep()",
+        "Leave the entry in place, returns its path",
+    )
+    .method(
+        "remove",
+        "remove()",
+        "Delete the entry now, returns whether it existed",
+    )
+}
+
+fn optional_string(args: &[Value], index: usize, name: &str) -> Result<String, String> {
+    match args.get(index) {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(value) => get_string_arg(value, name),
+    }
+}
+
+/// Tries fresh random names until `create` succeeds on one that was free.
+fn create_unique(
+    prefix: &str,
+    suffix: &str,
+    create: impl Fn(&std::path::Path) -> std::io::Result<()>,
+) -> Result<PathBuf, String> {
+    if prefix.contains(['/', '\\']) || suffix.contains(['/', '\\']) {
+        return Err("Prefix and suffix cannot contain path separators".to_string());
+    }
+    let dir = std::env::temp_dir();
+    for _ in 0..ATTEMPTS {
+        let name = Alphanumeric.sample_string(&mut rand::rng(), 12);
+        let path = dir.join(format!("{}{}{}", prefix, name, suffix));
+        match create(&path) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(format!("Failed to create '{}': {}", path.display(), e)),
+        }
+    }
+    Err(format!(
+        "Failed to find a free temporary name in '{}'",
+        dir.display()
+    ))
+}
+
+fn register(path: PathBuf) -> Value {
+    let display = Value::String(Rc::from(path.to_string_lossy().to_string()));
+    let value = native_instance(
+        create_temp_class(),
+        TempEntry {
+            path,
+            cleanup: true,
+        },
+    );
+    if let Value::Instance(inst) = &value {
+        inst.borrow_mut().fields.insert("path".to_string(), display);
+    }
+    value
+}
+
+pub(crate) fn file_temp_file(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 2, args.len())?;
+    let prefix = optional_string(args, 0, "prefix")?;
+    let suffix = optional_string(args, 1, "suffix")?;
+    let path = create_unique(&prefix, &suffix, |path| {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    })?;
+    Ok(register(path))
+}
+
+pub(crate) fn file_temp_dir(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let prefix = optional_string(args, 0, "prefix")?;
+    let path = create_unique(&prefix, "", |path| std::fs::create_dir(path))?;
+    Ok(register(path))
+}
+
+fn temp_entry(recv: &Value) -> Result<Rc<RefCell<TempEntry>>, String> {
+    native_state(recv, "TempPath")
+}
+
+fn temp_keep(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let entry = temp_entry(recv)?;
+    let mut entry = entry.borrow_mut();
+    entry.cleanup = false;
+    Ok(Value::String(Rc::from(
+        entry.path.to_string_lossy().to_string(),
+    )))
+}
+
+fn temp_remove(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let entry = temp_entry(recv)?;
+    let mut entry = entry.borrow_mut();
+    entry.cleanup = false;
+    Ok(Value::Boolean(remove_entry(&entry.path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_temp_paths_clean_up() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "fun scratch() {\n\
+                     let tmp = File.tempFile(\"sald-\", \".txt\")\n\
+                     File.write(tmp.path, \"x\")\n\
+                     return tmp.path\n\
+                 }\n\
+                 let dropped = scratch()\n\
+                 let dir = File.tempDir()\n\
+                 File.write(dir.path + \"/inner.txt\", \"y\")\n\
+                 let kept = File.tempFile()\n\
+                 let keptPath = kept.keep()\n\
+                 kept = null",
+            )
+            .unwrap();
+        let result: (bool, bool, bool, bool) = engine
+            .eval_as(
+                "[File.exists(dropped), File.exists(keptPath), File.exists(dir.path + \"/inner.txt\"),\n\
+                  dir.remove()]",
+            )
+            .unwrap();
+        assert_eq!(result, (false, true, true, true));
+        let kept: String = engine.eval_as("keptPath").unwrap();
+        std::fs::remove_file(kept).unwrap();
+    }
+}
@@ -0,0 +1,869 @@
+//! Terminal control
+//! `Term` styles text with ANSI colors, moves the cursor, reads single keys in
+//! raw mode and draws progress bars and spinners. Styling turns itself off
+//! when stdout is not a terminal or `NO_COLOR` is set, and progress output
+//! goes to stderr so it never mixes with piped stdout
+
+use super::docs::ClassDoc;
+use super::string::display_width;
+use super::{
+    check_arity, check_arity_range, get_bool_arg, get_number_arg, get_string_arg, native_instance,
+    native_state,
+};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, terminal, QueueableCommand};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::io::{IsTerminal, Write};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+/// Bar width used when the label leaves room for it
+const BAR_WIDTH: usize = 30;
+
+/// 0 follows the environment, 1 forces colors on and 2 forces them off
+static COLOR_OVERRIDE: AtomicU8 = AtomicU8::new(0);
+
+struct Progress {
+    total: f64,
+    current: f64,
+    label: String,
+    started: Instant,
+    drawn: String,
+}
+
+struct Spinner {
+    label: Arc<Mutex<String>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Stops the animation of a spinner that is dropped without `finish()`
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn create_term_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("style".to_string(), term_style);
+    static_methods.insert("red".to_string(), term_red);
+    static_methods.insert("green".to_string(), term_green);
+    static_methods.insert("yellow".to_string(), term_yellow);
+    static_methods.insert("blue".to_string(), term_blue);
+    static_methods.insert("magenta".to_string(), term_magenta);
+    static_methods.insert("cyan".to_string(), term_cyan);
+    static_methods.insert("gray".to_string(), term_gray);
+    static_methods.insert("bold".to_string(), term_bold);
+    static_methods.insert("dim".to_string(), term_dim);
+    static_methods.insert("italic".to_string(), term_italic);
+    static_methods.insert("underline".to_string(), term_underline);
+    static_methods.insert("strip".to_string(), term_strip);
+    static_methods.insert("colors".to_string(), term_colors);
+    static_methods.insert("setColors".to_string(), term_set_colors);
+    static_methods.insert("isTTY".to_string(), term_is_tty);
+    static_methods.insert("width".to_string(), term_width);
+    static_methods.insert("height".to_string(), term_height);
+    static_methods.insert("moveTo".to_string(), term_move_to);
+    static_methods.insert("up".to_string(), term_up);
+    static_methods.insert("down".to_string(), term_down);
+    static_methods.insert("left".to_string(), term_left);
+    static_methods.insert("right".to_string(), term_right);
+    static_methods.insert("hideCursor".to_string(), term_hide_cursor);
+    static_methods.insert("showCursor".to_string(), term_show_cursor);
+    static_methods.insert("clearLine".to_string(), term_clear_line);
+    static_methods.insert("clear".to_string(), term_clear);
+    static_methods.insert("rawMode".to_string(), term_raw_mode);
+    static_methods.insert("readKey".to_string(), term_read_key);
+    static_methods.insert("progress".to_string(), term_progress);
+    static_methods.insert("spinner".to_string(), term_spinner);
+
+    Class::new_with_static("Term", static_methods)
+}
+
+fn create_progress_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("tick".to_string(), progress_tick);
+    instance_methods.insert("set".to_string(), progress_set);
+    instance_methods.insert("setLabel".to_string(), progress_set_label);
+    instance_methods.insert("value".to_string(), progress_value);
+    instance_methods.insert("finish".to_string(), progress_finish);
+
+    Class::new_with_instance("ProgressBar", instance_methods, None)
+}
+
+fn create_spinner_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("setLabel".to_string(), spinner_set_label);
+    instance_methods.insert("finish".to_string(), spinner_finish);
+
+    Class::new_with_instance("Spinner", instance_methods, None)
+}
+
+/// API documentation for the `Term`, `ProgressBar` and `Spinner` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new(
+            "Term",
+            "Terminal colors, cursor control, key input and progress",
+        )
+        .method(
+            "style",
+            "style(text, ...styles)",
+            "Apply styles such as \"bold\", \"red\", \"bgBlue\", \"brightGreen\" or \"#ff8800\"",
+        )
+        .method("red", "red(text)", "Red text")
+        .method("green", "green(text)", "Green text")
+        .method("yellow", "yellow(text)", "Yellow text")
+        .method("blue", "blue(text)", "Blue text")
+        .method("magenta", "magenta(text)", "Magenta text")
+        .method("cyan", "cyan(text)", "Cyan text")
+        .method("gray", "gray(text)", "Gray text")
+        .method("bold", "bold(text)", "Bold text")
+        .method("dim", "dim(text)", "Dimmed text")
+        .method("italic", "italic(text)", "Italic text")
+        .method("underline", "underline(text)", "Underlined text")
+        .method("strip", "strip(text)", "Remove ANSI escape sequences")
+        .method("colors", "colors()", "Check if styles produce colors")
+        .method(
+            "setColors",
+            "setColors(enabled)",
+            "Force colors on or off, null to follow the terminal again",
+        )
+        .method(
+            "isTTY",
+            "isTTY(stream?)",
+            "Check if \"stdout\" (default), \"stderr\" or \"stdin\" is a terminal",
+        )
+        .method("width", "width()", "Terminal width in columns")
+        .method("height", "height()", "Terminal height in rows")
+        .method("moveTo", "moveTo(column, row)", "Move the cursor, 0-based")
+        .method("up", "up(n?)", "Move the cursor up")
+        .method("down", "down(n?)", "Move the cursor down")
+        .method("left", "left(n?)", "Move the cursor left")
+        .method("right", "right(n?)", "Move the cursor right")
+        .method("hideCursor", "hideCursor()", "Hide the cursor")
+        .method("showCursor", "showCursor()", "Show the cursor")
+        .method("clearLine", "clearLine()", "Clear the current line")
+        .method("clear", "clear()", "Clear the screen and move to the top")
+        .method(
+            "rawMode",
+            "rawMode(enabled)",
+            "Switch raw mode, returns the previous state",
+        )
+        .method(
+            "readKey",
+            "readKey(timeoutMs?)",
+            "Wait for a key press, returns {key, ctrl, alt, shift} or null on timeout",
+        )
+        .method(
+            "progress",
+            "progress(total, label?)",
+            "Progress bar drawn on stderr",
+        )
+        .method(
+            "spinner",
+            "spinner(label?)",
+            "Animated spinner drawn on stderr",
+        ),
+        ClassDoc::new("ProgressBar", "Progress bar returned by Term.progress")
+            .method("tick", "tick(n?)", "Advance by n, 1 by default")
+            .method("set", "set(value)", "Set the current value")
+            .method("setLabel", "setLabel(label)", "Change the label")
+            .method("value", "value()", "Current value")
+            .method(
+                "finish",
+                "finish(message?)",
+                "Complete the bar, replacing it with message if given",
+            ),
+        ClassDoc::new("Spinner", "Spinner returned by Term.spinner")
+            .method("setLabel", "setLabel(label)", "Change the label")
+            .method(
+                "finish",
+                "finish(message?)",
+                "Stop the spinner, replacing it with message if given",
+            ),
+    ]
+}
+
+fn string(s: String) -> Value {
+    Value::String(Rc::from(s))
+}
+
+/// Whether stdout gets colors: `Term.setColors`, then `NO_COLOR` and
+/// `FORCE_COLOR`, then whether stdout is a terminal.
+pub(crate) fn colors_enabled() -> bool {
+    match COLOR_OVERRIDE.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => {
+            let set = |name| std::env::var_os(name).is_some_and(|value| !value.is_empty());
+            if set("NO_COLOR") {
+                false
+            } else if set("FORCE_COLOR") {
+                std::env::var("FORCE_COLOR").as_deref() != Ok("0")
+            } else {
+                std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// SGR code for a color name such as "red" or "brightBlue", or a "#rrggbb"
+/// hex color. Backgrounds add 10 to the foreground code.
+fn color_code(name: &str, background: bool) -> Option<String> {
+    let base = if background { 40 } else { 30 };
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let rgb = u32::from_str_radix(hex, 16).ok()?;
+        return Some(format!(
+            "{};2;{};{};{}",
+            base + 8,
+            rgb >> 16,
+            (rgb >> 8) & 0xff,
+            rgb & 0xff
+        ));
+    }
+    let (bright, name) = match name.strip_prefix("bright") {
+        Some(rest) => (true, rest),
+        None => (false, name),
+    };
+    let offset = match name.to_ascii_lowercase().as_str() {
+        "black" => 0,
+        "red" => 1,
+        "green" => 2,
+        "yellow" => 3,
+        "blue" => 4,
+        "magenta" => 5,
+        "cyan" => 6,
+        "white" => 7,
+        "gray" | "grey" if !bright => return Some((base + 60).to_string()),
+        _ => return None,
+    };
+    let bright = if bright { 60 } else { 0 };
+    Some((base + bright + offset).to_string())
+}
+
+fn style_code(style: &str) -> Result<String, String> {
+    let code = match style {
+        "bold" => Some("1".to_string()),
+        "dim" => Some("2".to_string()),
+        "italic" => Some("3".to_string()),
+        "underline" => Some("4".to_string()),
+        "inverse" => Some("7".to_string()),
+        "hidden" => Some("8".to_string()),
+        "strikethrough" => Some("9".to_string()),
+        _ => match style.strip_prefix("bg") {
+            Some(color) => color_code(color, true),
+            None => color_code(style, false),
+        },
+    };
+    code.ok_or_else(|| format!("Unknown style '{}'", style))
+}
+
+fn paint(text: &Value, styles: &[&str]) -> Result<Value, String> {
+    let text = text.to_string();
+    let codes = styles
+        .iter()
+        .map(|style| style_code(style))
+        .collect::<Result<Vec<_>, _>>()?;
+    if codes.is_empty() || !colors_enabled() {
+        return Ok(string(text));
+    }
+    Ok(string(format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)))
+}
+
+fn term_style(args: &[Value]) -> Result<Value, String> {
+    if args.is_empty() {
+        return Err("Expected at least 1 argument but got 0".to_string());
+    }
+    let styles = args[1..]
+        .iter()
+        .map(|style| get_string_arg(style, "style"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let styles: Vec<&str> = styles.iter().map(String::as_str).collect();
+    paint(&args[0], &styles)
+}
+
+fn term_red(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["red"])
+}
+
+fn term_green(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["green"])
+}
+
+fn term_yellow(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["yellow"])
+}
+
+fn term_blue(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["blue"])
+}
+
+fn term_magenta(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["magenta"])
+}
+
+fn term_cyan(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["cyan"])
+}
+
+fn term_gray(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["gray"])
+}
+
+fn term_bold(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["bold"])
+}
+
+fn term_dim(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["dim"])
+}
+
+fn term_italic(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["italic"])
+}
+
+fn term_underline(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    paint(&args[0], &["underline"])
+}
+
+/// Removes CSI and OSC escape sequences.
+fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+fn term_strip(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(string(strip_ansi(&args[0].to_string())))
+}
+
+fn term_colors(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Boolean(colors_enabled()))
+}
+
+fn term_set_colors(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let setting = match &args[0] {
+        Value::Null => 0,
+        value if get_bool_arg(value, "enabled")? => 1,
+        _ => 2,
+    };
+    COLOR_OVERRIDE.store(setting, Ordering::Relaxed);
+    Ok(Value::Null)
+}
+
+fn term_is_tty(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let stream = match args.first() {
+        None | Some(Value::Null) => "stdout".to_string(),
+        Some(value) => get_string_arg(value, "stream")?,
+    };
+    let tty = match stream.as_str() {
+        "stdout" => std::io::stdout().is_terminal(),
+        "stderr" => std::io::stderr().is_terminal(),
+        "stdin" => std::io::stdin().is_terminal(),
+        other => {
+            return Err(format!(
+                "Unknown stream '{}', expected stdout, stderr or stdin",
+                other
+            ))
+        }
+    };
+    Ok(Value::Boolean(tty))
+}
+
+/// Terminal size, falling back to `COLUMNS`/`LINES` and then 80x24 when
+/// output is redirected.
+fn size() -> (u16, u16) {
+    if let Ok((columns, rows)) = terminal::size() {
+        if columns > 0 && rows > 0 {
+            return (columns, rows);
+        }
+    }
+    let env = |name: &str, default| {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    (env("COLUMNS", 80), env("LINES", 24))
+}
+
+fn term_width(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(size().0 as f64))
+}
+
+fn term_height(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(size().1 as f64))
+}
+
+fn emit(command: impl crossterm::Command) -> Result<Value, String> {
+    let mut stdout = std::io::stdout();
+    stdout
+        .queue(command)
+        .and_then(|stdout| stdout.flush())
+        .map_err(|e| format!("Terminal error: {}", e))?;
+    Ok(Value::Null)
+}
+
+fn count_arg(args: &[Value]) -> Result<u16, String> {
+    check_arity_range(0, 1, args.len())?;
+    match args.first() {
+        None | Some(Value::Null) => Ok(1),
+        Some(value) => Ok(get_number_arg(value, "n")?.clamp(0.0, u16::MAX as f64) as u16),
+    }
+}
+
+fn term_move_to(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let column = get_number_arg(&args[0], "column")?.clamp(0.0, u16::MAX as f64) as u16;
+    let row = get_number_arg(&args[1], "row")?.clamp(0.0, u16::MAX as f64) as u16;
+    emit(cursor::MoveTo(column, row))
+}
+
+fn term_up(args: &[Value]) -> Result<Value, String> {
+    emit(cursor::MoveUp(count_arg(args)?))
+}
+
+fn term_down(args: &[Value]) -> Result<Value, String> {
+    emit(cursor::MoveDown(count_arg(args)?))
+}
+
+fn term_left(args: &[Value]) -> Result<Value, String> {
+    emit(cursor::MoveLeft(count_arg(args)?))
+}
+
+fn term_right(args: &[Value]) -> Result<Value, String> {
+    emit(cursor::MoveRight(count_arg(args)?))
+}
+
+fn term_hide_cursor(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    emit(cursor::Hide)
+}
+
+fn term_show_cursor(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    emit(cursor::Show)
+}
+
+fn term_clear_line(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    emit(terminal::Clear(terminal::ClearType::CurrentLine))?;
+    emit(cursor::MoveToColumn(0))
+}
+
+fn term_clear(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    emit(terminal::Clear(terminal::ClearType::All))?;
+    emit(cursor::MoveTo(0, 0))
+}
+
+pub(super) fn set_raw(enabled: bool) -> Result<bool, String> {
+    let was = terminal::is_raw_mode_enabled().unwrap_or(false);
+    let result = match (was, enabled) {
+        (false, true) => terminal::enable_raw_mode(),
+        (true, false) => terminal::disable_raw_mode(),
+        _ => Ok(()),
+    };
+    result.map_err(|e| format!("Cannot switch raw mode: {}", e))?;
+    Ok(was)
+}
+
+fn term_raw_mode(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let enabled = get_bool_arg(&args[0], "enabled")?;
+    Ok(Value::Boolean(set_raw(enabled)?))
+}
+
+fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Char(c) => return Some(c.to_string()),
+        KeyCode::F(n) => return Some(format!("f{}", n)),
+        KeyCode::Enter => "enter",
+        KeyCode::Esc => "escape",
+        KeyCode::Backspace => "backspace",
+        KeyCode::Tab | KeyCode::BackTab => "tab",
+        KeyCode::Delete => "delete",
+        KeyCode::Insert => "insert",
+        KeyCode::Up => "up",
+        KeyCode::Down => "down",
+        KeyCode::Left => "left",
+        KeyCode::Right => "right",
+        KeyCode::Home => "home",
+        KeyCode::End => "end",
+        KeyCode::PageUp => "pageUp",
+        KeyCode::PageDown => "pageDown",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+/// Waits for the next key press, or `None` once `timeout` has passed.
+pub(super) fn read_key(
+    timeout: Option<Duration>,
+) -> Result<Option<(String, KeyModifiers)>, String> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let was_raw = set_raw(true)?;
+    let result = loop {
+        if let Some(deadline) = deadline {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match event::poll(left) {
+                Ok(true) => {}
+                Ok(false) => break Ok(None),
+                Err(e) => break Err(e),
+            }
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => {
+                let mut modifiers = key.modifiers;
+                if key.code == KeyCode::BackTab {
+                    modifiers |= KeyModifiers::SHIFT;
+                }
+                if let Some(name) = key_name(key.code) {
+                    break Ok(Some((name, modifiers)));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    if !was_raw {
+        set_raw(false)?;
+    }
+    result.map_err(|e| format!("Cannot read key: {}", e))
+}
+
+fn term_read_key(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let timeout = match args.first() {
+        None | Some(Value::Null) => None,
+        Some(value) => Some(Duration::from_millis(
+            get_number_arg(value, "timeoutMs")?.max(0.0) as u64,
+        )),
+    };
+    let Some((key, modifiers)) = read_key(timeout)? else {
+        return Ok(Value::Null);
+    };
+    let mut dict = FxHashMap::default();
+    dict.insert("key".to_string(), string(key));
+    for (name, flag) in [
+        ("ctrl", KeyModifiers::CONTROL),
+        ("alt", KeyModifiers::ALT),
+        ("shift", KeyModifiers::SHIFT),
+    ] {
+        dict.insert(name.to_string(), Value::Boolean(modifiers.contains(flag)));
+    }
+    Ok(Value::Dictionary(Rc::new(RefCell::new(dict.into()))))
+}
+
+/// A bar or spinner stays in its instance until `finish()` takes it out
+fn handle<T: 'static>(recv: &Value, class: &str) -> Result<Rc<RefCell<Option<T>>>, String> {
+    native_state(recv, class)
+}
+
+fn optional_label(args: &[Value], index: usize) -> Result<String, String> {
+    match args.get(index) {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(value) => get_string_arg(value, "label"),
+    }
+}
+
+/// Replaces the current stderr line with `line`, when stderr is a terminal.
+fn draw(line: &str) {
+    let mut stderr = std::io::stderr();
+    if stderr.is_terminal() {
+        let _ = write!(stderr, "\r\x1b[2K{}", line);
+        let _ = stderr.flush();
+    }
+}
+
+/// Clears the drawn line and prints `message` in its place, if any.
+fn finish_line(message: Option<String>) {
+    let mut stderr = std::io::stderr();
+    if stderr.is_terminal() {
+        let _ = write!(stderr, "\r\x1b[2K");
+    }
+    if let Some(message) = message {
+        let _ = writeln!(stderr, "{}", message);
+    }
+    let _ = stderr.flush();
+}
+
+fn finish_message(args: &[Value]) -> Result<Option<String>, String> {
+    check_arity_range(0, 1, args.len())?;
+    match args.first() {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => Ok(Some(value.to_string())),
+    }
+}
+
+fn format_eta(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    if seconds >= 3600 {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn render(progress: &Progress) -> String {
+    let ratio = if progress.total > 0.0 {
+        (progress.current / progress.total).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let elapsed = progress.started.elapsed().as_secs_f64();
+    let eta = if ratio > 0.0 && ratio < 1.0 {
+        format!(" ETA {}", format_eta(elapsed / ratio - elapsed))
+    } else {
+        String::new()
+    };
+    let counts = format!(
+        " {:>3}% {}/{}{}",
+        (ratio * 100.0).floor(),
+        progress.current,
+        progress.total,
+        eta
+    );
+    let label = if progress.label.is_empty() {
+        String::new()
+    } else {
+        format!("{} ", progress.label)
+    };
+    let room = (size().0 as usize).saturating_sub(display_width(&label) + counts.len() + 3);
+    let width = BAR_WIDTH.min(room).max(5);
+    let filled = (ratio * width as f64).round() as usize;
+    format!(
+        "{}[{}{}]{}",
+        label,
+        "█".repeat(filled),
+        "░".repeat(width - filled),
+        counts
+    )
+}
+
+fn term_progress(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let total = get_number_arg(&args[0], "total")?;
+    if total < 0.0 {
+        return Err("Argument 'total' cannot be negative".to_string());
+    }
+    let mut progress = Progress {
+        total,
+        current: 0.0,
+        label: optional_label(args, 1)?,
+        started: Instant::now(),
+        drawn: String::new(),
+    };
+    progress.drawn = render(&progress);
+    draw(&progress.drawn);
+
+    Ok(native_instance(create_progress_class(), Some(progress)))
+}
+
+/// Updates a bar and redraws it when the visible text changed.
+fn update_progress(recv: &Value, f: impl FnOnce(&mut Progress)) -> Result<Value, String> {
+    let bar = handle::<Progress>(recv, "ProgressBar")?;
+    let mut bar = bar.borrow_mut();
+    let progress = bar
+        .as_mut()
+        .ok_or_else(|| "ProgressBar is finished".to_string())?;
+    f(progress);
+    let line = render(progress);
+    if line != progress.drawn {
+        draw(&line);
+        progress.drawn = line;
+    }
+    Ok(Value::Number(progress.current))
+}
+
+fn progress_tick(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let step = match args.first() {
+        None | Some(Value::Null) => 1.0,
+        Some(value) => get_number_arg(value, "n")?,
+    };
+    update_progress(recv, |progress| {
+        progress.current = (progress.current + step).min(progress.total)
+    })
+}
+
+fn progress_set(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let value = get_number_arg(&args[0], "value")?;
+    update_progress(recv, |progress| {
+        progress.current = value.clamp(0.0, progress.total)
+    })
+}
+
+fn progress_set_label(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let label = get_string_arg(&args[0], "label")?;
+    update_progress(recv, |progress| progress.label = label)?;
+    Ok(Value::Null)
+}
+
+fn progress_value(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let bar = handle::<Progress>(recv, "ProgressBar")?;
+    let current = bar.borrow().as_ref().map(|progress| progress.current);
+    current
+        .map(Value::Number)
+        .ok_or_else(|| "ProgressBar is finished".to_string())
+}
+
+fn progress_finish(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let message = finish_message(args)?;
+    let finished = handle::<Progress>(recv, "ProgressBar")?.borrow_mut().take();
+    if let Some(mut progress) = finished {
+        progress.current = progress.total;
+        // Leave the full bar on screen unless a message replaces it
+        match message {
+            Some(message) => finish_line(Some(message)),
+            None if std::io::stderr().is_terminal() => {
+                draw(&render(&progress));
+                eprintln!();
+            }
+            None => {}
+        }
+    }
+    Ok(Value::Null)
+}
+
+fn term_spinner(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let label = Arc::new(Mutex::new(optional_label(args, 0)?));
+    let stop = Arc::new(AtomicBool::new(false));
+
+    // Nothing to animate when stderr is redirected
+    let thread = std::io::stderr().is_terminal().then(|| {
+        let label = Arc::clone(&label);
+        let stop = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            for frame in SPINNER_FRAMES.iter().cycle() {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                let label = label.lock().map(|l| l.clone()).unwrap_or_default();
+                draw(&format!("{} {}", frame, label));
+                std::thread::sleep(SPINNER_INTERVAL);
+            }
+        })
+    });
+
+    let spinner = Spinner {
+        label,
+        stop,
+        thread,
+    };
+    Ok(native_instance(create_spinner_class(), Some(spinner)))
+}
+
+fn spinner_set_label(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "label")?;
+    let spinner = handle::<Spinner>(recv, "Spinner")?;
+    let spinner = spinner.borrow();
+    match spinner.as_ref() {
+        Some(spinner) => {
+            if let Ok(mut label) = spinner.label.lock() {
+                *label = text;
+            }
+            Ok(Value::Null)
+        }
+        None => Err("Spinner is finished".to_string()),
+    }
+}
+
+fn spinner_finish(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let message = finish_message(args)?;
+    let spinner = handle::<Spinner>(recv, "Spinner")?.borrow_mut().take();
+    if let Some(mut spinner) = spinner {
+        spinner.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = spinner.thread.take() {
+            let _ = thread.join();
+        }
+        finish_line(message);
+    }
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_term_styles_and_progress() {
+        let mut engine = Engine::new();
+        engine.eval("Term.setColors(true)").unwrap();
+        let styled: String = engine
+            .eval_as("Term.style(\"ok\", \"bold\", \"brightGreen\", \"bg#000080\")")
+            .unwrap();
+        assert_eq!(styled, "\x1b[1;92;48;2;0;0;128mok\x1b[0m");
+        let stripped: String = engine.eval_as("Term.strip(Term.red(\"ok\"))").unwrap();
+        assert_eq!(stripped, "ok");
+        assert!(engine.eval("Term.style(\"ok\", \"sparkly\")").is_err());
+        engine.eval("Term.setColors(null)").unwrap();
+
+        let value: f64 = engine
+            .eval_as("let bar = Term.progress(10)\nbar.tick(4)\nbar.set(20)")
+            .unwrap();
+        assert_eq!(value, 10.0);
+        engine.eval("bar.finish()").unwrap();
+        assert!(engine.eval("bar.tick()").is_err());
+    }
+}
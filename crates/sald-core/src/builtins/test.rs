@@ -1,28 +1,246 @@
 use super::check_arity;
 use super::check_arity_min;
-use crate::vm::value::{Class, NativeStaticFn, Value};
+use super::clock;
+use super::date::date_parts;
+use super::docs::ClassDoc;
+use super::timer::run_timers;
+use super::{check_arity_range, get_number_arg, get_string_arg, native_state};
+use crate::testing::{self, TestMode};
+use crate::vm::caller::{CallableNativeInstanceFn, CallableNativeStaticFn, ValueCaller};
+use crate::vm::value::{Array, Class, Instance, NativeInstanceFn, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+thread_local! {
+    static MOCK_CLASS: Rc<Class> = Rc::new(create_mock_class());
+}
 
 pub fn create_test_class() -> Class {
     let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
 
     static_methods.insert("assert".to_string(), test_assert);
     static_methods.insert("assert_eq".to_string(), test_assert_eq);
     static_methods.insert("assert_ne".to_string(), test_assert_ne);
     static_methods.insert("fail".to_string(), test_fail);
+    static_methods.insert("matchesSnapshot".to_string(), test_matches_snapshot);
+
+    static_methods.insert("it".to_string(), test_it);
+    static_methods.insert("skip".to_string(), test_skip);
+    static_methods.insert("failing".to_string(), test_failing);
+    static_methods.insert("beforeEach".to_string(), test_before_each);
+    static_methods.insert("afterEach".to_string(), test_after_each);
+    callable_methods.insert("describe".to_string(), test_describe);
+
+    static_methods.insert("mock".to_string(), test_mock);
+    static_methods.insert("stub".to_string(), test_stub);
+    static_methods.insert("spy".to_string(), test_spy);
+    static_methods.insert("restoreAll".to_string(), test_restore_all);
+    static_methods.insert("useFakeTime".to_string(), test_use_fake_time);
+    static_methods.insert("useRealTime".to_string(), test_use_real_time);
+    callable_methods.insert("advanceTime".to_string(), test_advance_time);
 
     let mut class = Class::new_with_static("Test", static_methods);
+    class.callable_native_static_methods = callable_methods;
 
     class.constructor = Some(test_decorator);
 
     class
 }
 
+fn create_mock_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("callCount".to_string(), mock_call_count);
+    instance_methods.insert("calledWith".to_string(), mock_called_with);
+    instance_methods.insert("lastCall".to_string(), mock_last_call);
+    instance_methods.insert("returns".to_string(), mock_returns);
+    instance_methods.insert("implement".to_string(), mock_implement);
+    instance_methods.insert("reset".to_string(), mock_reset);
+    callable_methods.insert("__call__".to_string(), mock_call);
+
+    let mut class = Class::new_with_instance("Mock", instance_methods, None);
+    class.callable_native_instance_methods = callable_methods;
+    class
+}
+
+/// API documentation for the `Test` and `Mock` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![test_docs(), mock_docs()]
+}
+
+fn test_docs() -> ClassDoc {
+    ClassDoc::new("Test", "Built-in Test framework")
+        .method(
+            "assert",
+            "assert(condition, ?message)",
+            "Fails if condition is falsy",
+        )
+        .method(
+            "assert_eq",
+            "assert_eq(actual, expected, ?message)",
+            "Fails if actual != expected",
+        )
+        .method(
+            "assert_ne",
+            "assert_ne(actual, expected, ?message)",
+            "Fails if actual == expected",
+        )
+        .method("fail", "fail(?message)", "Unconditionally fails the test")
+        .method(
+            "matchesSnapshot",
+            "matchesSnapshot(value, ?name)",
+            "Fails if value differs from its stored snapshot; records missing snapshots",
+        )
+        .method(
+            "describe",
+            "describe(name, fn)",
+            "Groups the tests and hooks registered by fn into a suite",
+        )
+        .method("it", "it(name, fn)", "Registers a test; fn may be async")
+        .method(
+            "skip",
+            "skip(name, fn)",
+            "Registers a test that is reported but not run",
+        )
+        .method(
+            "failing",
+            "failing(name, fn)",
+            "Registers a test that is expected to fail",
+        )
+        .method(
+            "beforeEach",
+            "beforeEach(fn)",
+            "Runs fn before every test in the current suite",
+        )
+        .method(
+            "afterEach",
+            "afterEach(fn)",
+            "Runs fn after every test in the current suite, even failed ones",
+        )
+        .method(
+            "mock",
+            "mock(?fn)",
+            "Callable Mock that records its calls and runs fn, if given",
+        )
+        .method(
+            "stub",
+            "stub(target, name, ?fn)",
+            "Replaces a method of an instance or namespace with a Mock until the test ends",
+        )
+        .method(
+            "spy",
+            "spy(target, name)",
+            "Like stub, but the Mock calls through to the original method",
+        )
+        .method(
+            "restoreAll",
+            "restoreAll()",
+            "Puts back every stubbed method",
+        )
+        .method(
+            "useFakeTime",
+            "useFakeTime(?start)",
+            "Freezes Timer and Date at start (a Date or Unix ms) until the test ends",
+        )
+        .method(
+            "advanceTime",
+            "advanceTime(ms)",
+            "Moves the fake clock forward, running timers that come due",
+        )
+        .method(
+            "useRealTime",
+            "useRealTime()",
+            "Goes back to the real clock",
+        )
+}
+
+fn mock_docs() -> ClassDoc {
+    ClassDoc::new(
+        "Mock",
+        "Callable recorder returned by Test.mock, Test.stub and Test.spy",
+    )
+    .property("calls", "Arguments of each call, oldest first")
+    .method("callCount", "callCount()", "Number of calls so far")
+    .method(
+        "calledWith",
+        "calledWith(...args)",
+        "Check if any call had exactly these arguments",
+    )
+    .method(
+        "lastCall",
+        "lastCall()",
+        "Arguments of the latest call, or null",
+    )
+    .method(
+        "returns",
+        "returns(value)",
+        "Returns value from every call instead of running an implementation",
+    )
+    .method("implement", "implement(fn)", "Runs fn for every call")
+    .method("reset", "reset()", "Forgets the recorded calls")
+}
+
+/// `@Test` marks a function as a test; `@Test("skip")` and
+/// `@Test("failing")` also mark how the runner treats it.
 fn test_decorator(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
+    if let Value::String(marker) = &args[0] {
+        if TestMode::from_marker(marker).is_none() {
+            return Err(format!(
+                "Unknown test marker '{}', expected 'skip' or 'failing'",
+                marker
+            ));
+        }
+        return Ok(Value::NativeFunction {
+            func: test_decorator,
+            class_name: "Test".into(),
+        });
+    }
     Ok(args[0].clone())
 }
 
+fn register(args: &[Value], mode: TestMode) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    testing::add_test(&name, args[1].clone(), mode);
+    Ok(Value::Null)
+}
+
+fn test_it(args: &[Value]) -> Result<Value, String> {
+    register(args, TestMode::Run)
+}
+
+fn test_skip(args: &[Value]) -> Result<Value, String> {
+    register(args, TestMode::Skip)
+}
+
+fn test_failing(args: &[Value]) -> Result<Value, String> {
+    register(args, TestMode::Failing)
+}
+
+fn test_describe(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    testing::describe(&name, &args[1], caller)?;
+    Ok(Value::Null)
+}
+
+fn test_before_each(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    testing::add_before_each(args[0].clone());
+    Ok(Value::Null)
+}
+
+fn test_after_each(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    testing::add_after_each(args[0].clone());
+    Ok(Value::Null)
+}
+
 fn test_assert(args: &[Value]) -> Result<Value, String> {
     check_arity_min(1, args.len())?;
 
@@ -97,3 +315,245 @@ fn test_fail(args: &[Value]) -> Result<Value, String> {
 
     Err(format!("AssertionError: {}", message))
 }
+
+fn test_matches_snapshot(args: &[Value]) -> Result<Value, String> {
+    check_arity_min(1, args.len())?;
+    let name = match args.get(1) {
+        Some(Value::String(s)) => Some(s.to_string()),
+        Some(Value::Null) | None => None,
+        Some(_) => return Err("Snapshot name must be a string".to_string()),
+    };
+    testing::match_snapshot(&args[0], name.as_deref())?;
+    Ok(Value::Null)
+}
+
+/// What a Mock does when called: run `implementation` if it is a function,
+/// otherwise return `returns`
+struct MockState {
+    implementation: Value,
+    returns: Value,
+}
+
+/// A Mock that runs `implementation` (a function or null) for every call.
+fn make_mock(implementation: Value) -> Value {
+    let state = MockState {
+        implementation,
+        returns: Value::Null,
+    };
+    let mut instance = Instance::with_native(MOCK_CLASS.with(|c| c.clone()), state);
+    instance.fields.insert(
+        "calls".to_string(),
+        Value::Array(Rc::new(RefCell::new(Vec::new().into()))),
+    );
+    Value::Instance(Rc::new(RefCell::new(instance)))
+}
+
+fn test_mock(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    Ok(make_mock(args.first().cloned().unwrap_or(Value::Null)))
+}
+
+fn test_stub(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let name = get_string_arg(&args[1], "name")?;
+    let mock = make_mock(args.get(2).cloned().unwrap_or(Value::Null));
+    testing::stub(&args[0], &name, mock.clone())?;
+    Ok(mock)
+}
+
+fn test_spy(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let name = get_string_arg(&args[1], "name")?;
+    let mock = make_mock(Value::Null);
+    let original = testing::stub(&args[0], &name, mock.clone())?;
+    mock_state(&mock)?.borrow_mut().implementation = original;
+    Ok(mock)
+}
+
+fn test_restore_all(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    testing::restore_stubs();
+    Ok(Value::Null)
+}
+
+fn test_use_fake_time(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let start = match args.first() {
+        None | Some(Value::Null) => None,
+        Some(Value::Number(ms)) if *ms >= 0.0 => Some(Duration::from_secs_f64(ms / 1000.0)),
+        Some(value) => match date_parts(value) {
+            Some((seconds, _)) if seconds >= 0.0 => Some(Duration::from_secs_f64(seconds)),
+            _ => return Err("Argument 'start' must be a Date or Unix milliseconds".to_string()),
+        },
+    };
+    clock::install_fake(start);
+    Ok(Value::Null)
+}
+
+fn test_use_real_time(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    clock::use_real();
+    Ok(Value::Null)
+}
+
+fn test_advance_time(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let ms = get_number_arg(&args[0], "ms")?;
+    if ms < 0.0 {
+        return Err("Argument 'ms' must not be negative".to_string());
+    }
+    if !clock::is_fake() {
+        return Err(
+            "Test.advanceTime needs the fake clock; call Test.useFakeTime first".to_string(),
+        );
+    }
+    let deadline = clock::now() + Duration::from_secs_f64(ms / 1000.0);
+    run_timers(caller, Some(deadline))?;
+    Ok(Value::Null)
+}
+
+fn mock_state(recv: &Value) -> Result<Rc<RefCell<MockState>>, String> {
+    native_state::<MockState>(recv, "Mock")
+}
+
+fn mock_calls(recv: &Value) -> Result<Rc<RefCell<Array>>, String> {
+    let calls = match recv {
+        Value::Instance(inst) => inst.borrow().fields.get("calls").cloned(),
+        _ => return Err("Receiver must be a Mock".to_string()),
+    };
+    match calls {
+        Some(Value::Array(calls)) => Ok(calls),
+        _ => Err("Mock 'calls' must be an array".to_string()),
+    }
+}
+
+fn mock_call(recv: &Value, args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    mock_calls(recv)?
+        .borrow_mut()
+        .push(Value::Array(Rc::new(RefCell::new(args.to_vec().into()))));
+    let (implementation, returns) = {
+        let state = mock_state(recv)?;
+        let state = state.borrow();
+        (state.implementation.clone(), state.returns.clone())
+    };
+    match implementation {
+        Value::Null => Ok(returns),
+        implementation => caller.call(&implementation, args.to_vec()),
+    }
+}
+
+fn mock_call_count(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Number(mock_calls(recv)?.borrow().len() as f64))
+}
+
+/// Equality that compares arrays and dictionaries by contents
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| same_value(x, y))
+        }
+        (Value::Dictionary(a), Value::Dictionary(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(k, v)| b.get(k).is_some_and(|other| same_value(v, other)))
+        }
+        _ => a == b,
+    }
+}
+
+fn mock_called_with(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    let expected = Value::Array(Rc::new(RefCell::new(args.to_vec().into())));
+    let called = mock_calls(recv)?
+        .borrow()
+        .iter()
+        .any(|call| same_value(call, &expected));
+    Ok(Value::Boolean(called))
+}
+
+fn mock_last_call(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(mock_calls(recv)?
+        .borrow()
+        .last()
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
+fn mock_returns(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let state = mock_state(recv)?;
+    let mut state = state.borrow_mut();
+    state.returns = args[0].clone();
+    state.implementation = Value::Null;
+    Ok(recv.clone())
+}
+
+fn mock_implement(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    mock_state(recv)?.borrow_mut().implementation = args[0].clone();
+    Ok(recv.clone())
+}
+
+fn mock_reset(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    mock_calls(recv)?.borrow_mut().clear();
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::eval;
+
+    #[test]
+    fn test_mock_keeps_its_behaviour_out_of_fields() {
+        let source = "let m = Test.mock().returns(7)\n\
+                      let r = [m(1), Reflect.fields(m), m.implement(|x| x + 1)(1), m.callCount()]\nr";
+        assert_eq!(eval(source), "[7, [calls], 2, 2]");
+    }
+
+    #[test]
+    fn test_mocks_stubs_and_fake_time() {
+        use crate::testing::{self, TestStatus};
+
+        testing::reset();
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                r#"
+namespace Api {
+    fun fetch(url) { return "real " + url }
+}
+let seen = []
+Test.it("mocks and stubs", || {
+    let double = Test.mock(|x| x * 2)
+    Test.assert_eq(double(4), 8)
+    Test.assert(double.calledWith(4))
+    let fetch = Test.stub(Api, "fetch").returns("fake")
+    Test.assert_eq(Api.fetch("a"), "fake")
+    Test.assert_eq(fetch.callCount(), 1)
+})
+Test.it("fake time", || {
+    Test.useFakeTime(5000)
+    Timer.interval(100, || { seen.push(Timer.now()) })
+    Test.advanceTime(250)
+    seen.push(Api.fetch("b"))
+})
+"#,
+            )
+            .unwrap();
+
+        let results = testing::run(engine.vm(), None, |_| {});
+        assert!(results.iter().all(|r| r.status == TestStatus::Passed));
+        assert_eq!(
+            engine
+                .eval_as::<Vec<String>>("seen.map(|s| \"\" + s)")
+                .unwrap(),
+            ["5100", "5200", "real b"]
+        );
+        assert!(engine.eval_as::<f64>("Timer.now()").unwrap() > 1.0e12);
+    }
+}
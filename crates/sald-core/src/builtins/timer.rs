@@ -1,18 +1,247 @@
-use crate::vm::value::{Class, NativeStaticFn, Value};
+//! Timers
+//! Scheduled callbacks wait in a per-thread queue and run on the thread that
+//! scheduled them, while `Timer.run` or `Timer.sleep` is waiting or once the
+//! script itself has finished
+
+use super::clock;
+use super::cron::CronSchedule;
+use super::docs::ClassDoc;
+use super::{check_arity, get_number_arg, native_instance, native_state};
+use crate::vm::caller::{CallableNativeStaticFn, ValueCaller};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, Value};
 use rustc_hash::FxHashMap;
-use std::time::Duration;
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
+
+pub(crate) enum Repeat {
+    Once,
+    Every(Duration),
+    Cron(Box<CronSchedule>),
+}
+
+struct Scheduled {
+    due: Instant,
+    repeat: Repeat,
+    callback: Value,
+}
+
+/// Longest sleep between checks for signals
+const SIGNAL_POLL: Duration = Duration::from_millis(50);
+
+thread_local! {
+    static TIMERS: RefCell<FxHashMap<usize, Scheduled>> = RefCell::new(FxHashMap::default());
+    static NEXT_ID: Cell<usize> = const { Cell::new(1) };
+}
+
+/// Native state of a `TimerHandle`: the key of its entry in `TIMERS`
+struct TimerId(usize);
 
 pub fn create_timer_class() -> Class {
     let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+    let mut callable_methods: FxHashMap<String, CallableNativeStaticFn> = FxHashMap::default();
 
-    static_methods.insert("sleep".to_string(), timer_sleep);
     static_methods.insert("now".to_string(), timer_now);
     static_methods.insert("millis".to_string(), timer_now);
+    static_methods.insert("interval".to_string(), timer_interval);
+    static_methods.insert("timeout".to_string(), timer_timeout);
+    callable_methods.insert("sleep".to_string(), timer_sleep);
+    callable_methods.insert("run".to_string(), timer_run);
 
-    Class::new_with_static("Timer", static_methods)
+    let mut class = Class::new_with_static("Timer", static_methods);
+    class.callable_native_static_methods = callable_methods;
+    class
 }
 
-fn timer_sleep(args: &[Value]) -> Result<Value, String> {
+fn create_handle_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("cancel".to_string(), handle_cancel);
+    instance_methods.insert("isActive".to_string(), handle_is_active);
+
+    Class::new_with_instance("TimerHandle", instance_methods, None)
+}
+
+/// API documentation for the `Timer` class
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new("Timer", "Time utilities")
+            .method(
+                "sleep",
+                "await sleep(ms)",
+                "Sleep for milliseconds, running timers that come due",
+            )
+            .method("now", "now()", "Current timestamp in ms")
+            .method("millis", "millis()", "Alias for now()")
+            .method(
+                "interval",
+                "interval(ms, fn)",
+                "Call fn every ms milliseconds, returns a TimerHandle",
+            )
+            .method(
+                "timeout",
+                "timeout(ms, fn)",
+                "Call fn once after ms milliseconds, returns a TimerHandle",
+            )
+            .method("run", "run()", "Run scheduled timers until none are left"),
+        ClassDoc::new(
+            "TimerHandle",
+            "Scheduled callback returned by Timer.interval, Timer.timeout and Cron.schedule",
+        )
+        .method("cancel", "cancel()", "Stop the callback from running again")
+        .method(
+            "isActive",
+            "isActive()",
+            "Check if the callback is still scheduled",
+        ),
+    ]
+}
+
+/// Queues `callback` and returns a handle that can cancel it.
+pub(crate) fn schedule(due: Instant, repeat: Repeat, callback: Value) -> Value {
+    let id = NEXT_ID.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    TIMERS.with(|timers| {
+        timers.borrow_mut().insert(
+            id,
+            Scheduled {
+                due,
+                repeat,
+                callback,
+            },
+        )
+    });
+
+    native_instance(create_handle_class(), TimerId(id))
+}
+
+fn next_due() -> Option<Instant> {
+    TIMERS.with(|timers| timers.borrow().values().map(|t| t.due).min())
+}
+
+/// Runs every timer that is due, rescheduling repeating ones before their
+/// callback so the callback may cancel them.
+fn run_due(caller: &mut dyn ValueCaller) -> Result<(), String> {
+    loop {
+        let now = clock::now();
+        let callback = TIMERS.with(|timers| {
+            let mut timers = timers.borrow_mut();
+            let id = timers
+                .iter()
+                .filter(|(_, t)| t.due <= now)
+                .min_by_key(|(_, t)| t.due)
+                .map(|(id, _)| *id)?;
+            let timer = timers.get_mut(&id)?;
+            let callback = timer.callback.clone();
+            let next = match &timer.repeat {
+                Repeat::Once => None,
+                // Skip ticks that were missed rather than firing them in a burst
+                Repeat::Every(period) => Some((timer.due + *period).max(now)),
+                Repeat::Cron(cron) => cron.next_instant(),
+            };
+            match next {
+                Some(due) => timer.due = due,
+                None => {
+                    timers.remove(&id);
+                }
+            }
+            Some(callback)
+        });
+        match callback {
+            Some(callback) => {
+                caller.call(&callback, Vec::new())?;
+            }
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Runs timers as they come due until `deadline`, or until none are left.
+pub(crate) fn run_timers(
+    caller: &mut dyn ValueCaller,
+    deadline: Option<Instant>,
+) -> Result<(), String> {
+    loop {
+        crate::vm::signals::dispatch(caller)?;
+        run_due(caller)?;
+        let wake = match (next_due(), deadline) {
+            (Some(due), Some(deadline)) => due.min(deadline),
+            (Some(due), None) => due,
+            (None, Some(deadline)) => deadline,
+            (None, None) => return Ok(()),
+        };
+        // Wake up regularly so signal handlers run while waiting; a fake
+        // clock jumps straight to the next wake-up instead
+        let now = clock::now();
+        if wake > now {
+            if clock::is_fake() {
+                clock::advance(wake - now);
+            } else {
+                std::thread::sleep((wake - now).min(SIGNAL_POLL));
+            }
+        }
+        if deadline.is_some_and(|deadline| clock::now() >= deadline) {
+            return run_due(caller);
+        }
+    }
+}
+
+/// Runs the timers a script left scheduled once its top level has finished.
+pub fn run_pending_timers(caller: &mut dyn ValueCaller) -> Result<(), String> {
+    run_timers(caller, None)
+}
+
+fn timer_run(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    run_timers(caller, None)?;
+    Ok(Value::Null)
+}
+
+fn scheduled(args: &[Value], repeat: bool) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let ms = get_number_arg(&args[0], "ms")?;
+    if ms < 0.0 || (repeat && ms < 1.0) {
+        return Err("Argument 'ms' must be at least 1".to_string());
+    }
+    let period = Duration::from_secs_f64(ms / 1000.0);
+    let repeat = if repeat {
+        Repeat::Every(period)
+    } else {
+        Repeat::Once
+    };
+    Ok(schedule(clock::now() + period, repeat, args[1].clone()))
+}
+
+fn timer_interval(args: &[Value]) -> Result<Value, String> {
+    scheduled(args, true)
+}
+
+fn timer_timeout(args: &[Value]) -> Result<Value, String> {
+    scheduled(args, false)
+}
+
+fn handle_id(recv: &Value) -> Result<usize, String> {
+    let TimerId(id) = *native_state::<TimerId>(recv, "TimerHandle")?.borrow();
+    Ok(id)
+}
+
+fn handle_cancel(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let id = handle_id(recv)?;
+    let removed = TIMERS.with(|timers| timers.borrow_mut().remove(&id));
+    Ok(Value::Boolean(removed.is_some()))
+}
+
+fn handle_is_active(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    let id = handle_id(recv)?;
+    let active = TIMERS.with(|timers| timers.borrow().contains_key(&id));
+    Ok(Value::Boolean(active))
+}
+
+fn timer_sleep(args: &[Value], caller: &mut dyn ValueCaller) -> Result<Value, String> {
     if args.is_empty() {
         return Err("Expected 1 argument but got 0".to_string());
     }
@@ -27,18 +256,51 @@ fn timer_sleep(args: &[Value]) -> Result<Value, String> {
         }
     };
 
-    std::thread::sleep(Duration::from_millis(ms));
+    run_timers(caller, Some(clock::now() + Duration::from_millis(ms)))?;
 
     Ok(Value::Null)
 }
 
 fn timer_now(_args: &[Value]) -> Result<Value, String> {
-    use std::time::{SystemTime, UNIX_EPOCH};
+    let millis = clock::unix_time().as_secs_f64() * 1000.0;
+    Ok(Value::Number(millis))
+}
 
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| e.to_string())?;
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
 
-    let millis = duration.as_secs_f64() * 1000.0;
-    Ok(Value::Number(millis))
+    #[test]
+    fn test_timer_interval_and_cron() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let ticks = []\n\
+                 let handle = null\n\
+                 handle = Timer.interval(1, || {\n\
+                     ticks.push(ticks.length())\n\
+                     if ticks.length() == 3 { handle.cancel() }\n\
+                 })\n\
+                 let fired = []\n\
+                 let once = Timer.timeout(1, || fired.push(true))\n\
+                 Timer.run()\n\
+                 let start = Date.fromTimestamp(1700000123)\n\
+                 let next = Cron.next(\"*/5 * * * *\", start)\n\
+                 let workday = Cron.next(\"0 9 * * mon-fri\", start)",
+            )
+            .unwrap();
+        let result: (Vec<f64>, f64, bool, bool, f64, f64, f64) = engine
+            .eval_as(
+                "[ticks, fired.length(), handle.isActive(), once.isActive(),\n\
+                  next.timestamp() - start.timestamp(), workday.weekday(), workday.hour()]",
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            (vec![0.0, 1.0, 2.0], 1.0, false, false, 277.0, 3.0, 9.0)
+        );
+
+        assert!(engine.eval("Cron.next(\"61 * * * *\")").is_err());
+        assert!(engine.eval("Cron.next(\"* * *\")").is_err());
+    }
 }
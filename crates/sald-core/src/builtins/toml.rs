@@ -0,0 +1,87 @@
+use super::docs::ClassDoc;
+use super::{
+    check_arity, get_string_arg, integralize_numbers, json_to_sald_value, sald_value_to_json,
+};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+pub fn create_toml_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("parse".to_string(), toml_parse);
+    static_methods.insert("stringify".to_string(), toml_stringify);
+
+    Class::new_with_static("Toml", static_methods)
+}
+
+/// API documentation for the `Toml` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Toml", "TOML parsing and serialization")
+        .method("parse", "parse(toml)", "Parse TOML string to a Dict")
+        .method(
+            "stringify",
+            "stringify(dict)",
+            "Convert a Dict to TOML; null values are left out",
+        )
+}
+
+fn toml_parse(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let toml_str = get_string_arg(&args[0], "toml")?;
+
+    let table = toml_str
+        .parse::<::toml::Table>()
+        .map_err(|e| e.message().to_string())?;
+    json_to_sald_value(&toml_to_json(::toml::Value::Table(table)))
+}
+
+fn toml_stringify(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    if !matches!(args[0], Value::Dictionary(_)) {
+        return Err(format!(
+            "TOML documents must be a Dict but got {}",
+            args[0].type_name()
+        ));
+    }
+    let mut json_value = strip_nulls(sald_value_to_json(&args[0])?);
+    integralize_numbers(&mut json_value);
+    let toml_string = ::toml::to_string(&json_value).map_err(|e| e.to_string())?;
+    Ok(Value::String(Rc::from(toml_string)))
+}
+
+/// Datetimes have no Sald equivalent and are kept as their TOML text.
+fn toml_to_json(value: ::toml::Value) -> serde_json::Value {
+    match value {
+        ::toml::Value::String(s) => serde_json::Value::String(s),
+        ::toml::Value::Integer(i) => serde_json::Value::from(i),
+        ::toml::Value::Float(f) => serde_json::Value::from(f),
+        ::toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        ::toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+        ::toml::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(toml_to_json).collect())
+        }
+        ::toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// TOML has no null, so keys holding one are dropped.
+fn strip_nulls(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, strip_nulls(value)))
+                .collect(),
+        ),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(strip_nulls).collect())
+        }
+        other => other,
+    }
+}
@@ -1,8 +1,50 @@
-use super::check_arity;
-use crate::vm::value::{Class, NativeStaticFn, Value};
-use rustc_hash::FxHashMap;
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range};
+use crate::vm::value::{Array, Class, Dict, Instance, NativeStaticFn, Value};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::{RefCell, RefMut};
 use std::rc::Rc;
 
+/// Address identifying a mutable container, if `value` is one.
+fn container_id(value: &Value) -> Option<usize> {
+    match value {
+        Value::Array(arr) => Some(Rc::as_ptr(arr) as *const () as usize),
+        Value::Dictionary(dict) => Some(Rc::as_ptr(dict) as *const () as usize),
+        Value::Instance(inst) => Some(Rc::as_ptr(inst) as *const () as usize),
+        _ => None,
+    }
+}
+
+/// Whether `value` was frozen with `Type.freeze`. Checked by the VM before
+/// index and property assignment, and by the natives that change arrays,
+/// dicts and instances.
+pub(crate) fn is_frozen(value: &Value) -> bool {
+    match value {
+        Value::Array(arr) => arr.borrow().is_frozen(),
+        Value::Dictionary(dict) => dict.borrow().is_frozen(),
+        Value::Instance(inst) => inst.borrow().frozen,
+        _ => false,
+    }
+}
+
+/// Borrows an array for changing, unless it is frozen
+pub(crate) fn array_mut(arr: &RefCell<Array>) -> Result<RefMut<'_, Array>, String> {
+    let arr = arr.borrow_mut();
+    if arr.is_frozen() {
+        return Err("Cannot modify a frozen Array".to_string());
+    }
+    Ok(arr)
+}
+
+/// Borrows a dictionary for changing, unless it is frozen
+pub(crate) fn dict_mut(dict: &RefCell<Dict>) -> Result<RefMut<'_, Dict>, String> {
+    let dict = dict.borrow_mut();
+    if dict.is_frozen() {
+        return Err("Cannot modify a frozen Dict".to_string());
+    }
+    Ok(dict)
+}
+
 pub fn create_type_class() -> Class {
     let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
 
@@ -16,9 +58,44 @@ pub fn create_type_class() -> Class {
     static_methods.insert("isClass".to_string(), type_is_class);
     static_methods.insert("isInstance".to_string(), type_is_instance);
     static_methods.insert("isDict".to_string(), type_is_dictonary);
+    static_methods.insert("deepEquals".to_string(), type_deep_equals);
+    static_methods.insert("deepClone".to_string(), type_deep_clone);
+    static_methods.insert("freeze".to_string(), type_freeze);
+    static_methods.insert("isFrozen".to_string(), type_is_frozen);
     Class::new_with_static("Type", static_methods)
 }
 
+/// API documentation for the `Type` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Type", "Type checking utilities")
+        .method("of", "of(value)", "Get type name as string")
+        .method("isNumber", "isNumber(value)", "Check if number")
+        .method("isString", "isString(value)", "Check if string")
+        .method("isBoolean", "isBoolean(value)", "Check if boolean")
+        .method("isNull", "isNull(value)", "Check if null")
+        .method("isArray", "isArray(value)", "Check if array")
+        .method("isFunction", "isFunction(value)", "Check if function")
+        .method("isClass", "isClass(value)", "Check if class")
+        .method("isInstance", "isInstance(value)", "Check if instance")
+        .method("isDict", "isDict(value)", "Check if dictionary")
+        .method(
+            "deepEquals",
+            "deepEquals(a, b)",
+            "Compare arrays, dicts and instances by contents",
+        )
+        .method(
+            "deepClone",
+            "deepClone(value)",
+            "Copy arrays, dicts and instances recursively, preserving cycles",
+        )
+        .method(
+            "freeze",
+            "freeze(value, deep?)",
+            "Make an array, dict or instance reject assignment; returns value",
+        )
+        .method("isFrozen", "isFrozen(value)", "Check if frozen")
+}
+
 fn type_of(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     Ok(Value::String(Rc::from(args[0].type_name().to_string())))
@@ -53,7 +130,11 @@ fn type_is_function(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     Ok(Value::Boolean(matches!(
         args[0],
-        Value::Function(_) | Value::NativeFunction { .. }
+        Value::Function(_)
+            | Value::NativeFunction { .. }
+            | Value::HostFunction { .. }
+            | Value::BoundMethod { .. }
+            | Value::Partial { .. }
     )))
 }
 
@@ -71,3 +152,297 @@ fn type_is_dictonary(args: &[Value]) -> Result<Value, String> {
     check_arity(1, args.len())?;
     Ok(Value::Boolean(matches!(args[0], Value::Dictionary(_))))
 }
+
+fn type_deep_equals(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    Ok(Value::Boolean(deep_equals(
+        &args[0],
+        &args[1],
+        &mut FxHashSet::default(),
+    )))
+}
+
+/// Structural equality. Pairs already being compared count as equal, so
+/// cyclic structures terminate.
+fn deep_equals(a: &Value, b: &Value, seen: &mut FxHashSet<(usize, usize)>) -> bool {
+    if a == b {
+        return true;
+    }
+    if let (Some(a_id), Some(b_id)) = (container_id(a), container_id(b)) {
+        if !seen.insert((a_id, b_id)) {
+            return true;
+        }
+    }
+    match (a, b) {
+        (Value::Array(a), Value::Array(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| deep_equals(x, y, seen))
+        }
+        (Value::Dictionary(a), Value::Dictionary(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            dicts_equal(&a, &b, seen)
+        }
+        (Value::Instance(a), Value::Instance(b)) => {
+            // Builtins keep their contents in native state, compared by `==` above
+            let (a, b) = (a.borrow(), b.borrow());
+            a.native.is_none()
+                && b.native.is_none()
+                && Rc::ptr_eq(&a.class, &b.class)
+                && dicts_equal(&a.fields, &b.fields, seen)
+        }
+        _ => false,
+    }
+}
+
+fn dicts_equal(
+    a: &FxHashMap<String, Value>,
+    b: &FxHashMap<String, Value>,
+    seen: &mut FxHashSet<(usize, usize)>,
+) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .all(|(key, x)| b.get(key).is_some_and(|y| deep_equals(x, y, seen)))
+}
+
+fn type_deep_clone(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(deep_clone(&args[0], &mut FxHashMap::default()))
+}
+
+/// Copies containers recursively. `copies` maps each original to its copy so
+/// shared and cyclic references keep their shape. Copies are never frozen.
+fn deep_clone(value: &Value, copies: &mut FxHashMap<usize, Value>) -> Value {
+    let Some(id) = container_id(value) else {
+        return value.clone();
+    };
+    if let Some(copy) = copies.get(&id) {
+        return copy.clone();
+    }
+    match value {
+        Value::Array(arr) => {
+            let copy = Rc::new(RefCell::new(Array::new()));
+            copies.insert(id, Value::Array(copy.clone()));
+            let items: Vec<Value> = arr
+                .borrow()
+                .iter()
+                .map(|item| deep_clone(item, copies))
+                .collect();
+            *copy.borrow_mut() = items.into();
+            Value::Array(copy)
+        }
+        Value::Dictionary(dict) => {
+            let copy = Rc::new(RefCell::new(Dict::new()));
+            copies.insert(id, Value::Dictionary(copy.clone()));
+            let dict = dict.borrow();
+            for (entry, value) in dict.iter() {
+                let value = deep_clone(value, copies);
+                copy.borrow_mut()
+                    .insert_entry(entry, &dict.key(entry), value);
+            }
+            Value::Dictionary(copy)
+        }
+        Value::Instance(inst) => {
+            let mut copy = Instance::new(inst.borrow().class.clone());
+            // Native state can't be copied generically, so the copy shares it
+            copy.native = inst.borrow().native.clone();
+            let copy = Rc::new(RefCell::new(copy));
+            copies.insert(id, Value::Instance(copy.clone()));
+            let fields = clone_fields(&inst.borrow().fields, copies);
+            copy.borrow_mut().fields = fields;
+            Value::Instance(copy)
+        }
+        _ => value.clone(),
+    }
+}
+
+fn clone_fields(
+    fields: &FxHashMap<String, Value>,
+    copies: &mut FxHashMap<usize, Value>,
+) -> FxHashMap<String, Value> {
+    fields
+        .iter()
+        .map(|(key, value)| (key.clone(), deep_clone(value, copies)))
+        .collect()
+}
+
+fn type_freeze(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let deep = args.get(1).is_some_and(|deep| deep.is_truthy());
+    freeze(&args[0], deep);
+    Ok(args[0].clone())
+}
+
+fn freeze(value: &Value, deep: bool) {
+    // Anything already frozen was visited before, which also ends cycles
+    if is_frozen(value) {
+        return;
+    }
+    let children: Vec<Value> = match value {
+        Value::Array(arr) => {
+            let mut arr = arr.borrow_mut();
+            arr.freeze();
+            arr.to_vec()
+        }
+        Value::Dictionary(dict) => {
+            let mut dict = dict.borrow_mut();
+            dict.freeze();
+            dict.values().cloned().collect()
+        }
+        Value::Instance(inst) => {
+            let mut inst = inst.borrow_mut();
+            inst.frozen = true;
+            inst.fields.values().cloned().collect()
+        }
+        _ => return,
+    };
+    if deep {
+        for child in &children {
+            freeze(child, true);
+        }
+    }
+}
+
+fn type_is_frozen(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    Ok(Value::Boolean(is_frozen(&args[0])))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::test_util::{eval, eval_err};
+
+    fn assert_refused(setup: &str, call: &str, kind: &str) {
+        let err = eval_err(&format!("{setup}\n{call}"));
+        assert!(
+            err.contains(&format!("Cannot modify a frozen {kind}")),
+            "{call}: {err}"
+        );
+    }
+
+    #[test]
+    fn test_frozen_array_refuses_mutating_methods() {
+        let setup = "let a = Type.freeze([3, 1, 2])";
+        for call in [
+            "a.push(4)",
+            "a.pop()",
+            "a.shift()",
+            "a.unshift(0)",
+            "a.removeAt(0)",
+            "a.splice(0, 1)",
+            "a.set(0, 9)",
+            "a.reverse()",
+            "a.clear()",
+            "a.fill(0)",
+            "a.sortInPlace()",
+            "a.sortBy(|x| x)",
+        ] {
+            assert_refused(setup, call, "Array");
+        }
+        assert_eq!(
+            eval(&format!("{setup}\ntry {{ a.pop() }} catch (e) {{}}\na")),
+            "[3, 1, 2]"
+        );
+    }
+
+    #[test]
+    fn test_frozen_array_still_reads_and_copies() {
+        let setup = "let a = Type.freeze([3, 1, 2])";
+        assert_eq!(eval(&format!("{setup}\na.toSorted()")), "[1, 2, 3]");
+        assert_eq!(eval(&format!("{setup}\na.slice(0, 1)")), "[3]");
+        assert_eq!(
+            eval(&format!("{setup}\nlet b = a.slice(0)\nb.push(4)\nb")),
+            "[3, 1, 2, 4]"
+        );
+        assert_eq!(
+            eval(&format!("{setup}\nType.isFrozen(a.toReversed())")),
+            "false"
+        );
+    }
+
+    #[test]
+    fn test_frozen_dict_refuses_mutating_methods() {
+        let setup = "let d = Type.freeze({\"a\": 1})";
+        for call in [
+            "d.set(\"b\", 2)",
+            "d.remove(\"a\")",
+            "d.clear()",
+            "d.getOrInsert(\"b\", || 2)",
+        ] {
+            assert_refused(setup, call, "Dict");
+        }
+        assert_eq!(eval(&format!("{setup}\nd.getOrInsert(\"a\", || 2)")), "1");
+        assert_eq!(
+            eval(&format!("{setup}\nd.merge({{\"b\": 2}}).length()")),
+            "2"
+        );
+    }
+
+    #[test]
+    fn test_frozen_instance_refuses_reflect_set() {
+        let err = eval_err("class P {}\nlet p = Type.freeze(P())\nReflect.set(p, \"x\", 1)");
+        assert!(err.contains("frozen instance"), "{err}");
+    }
+
+    #[test]
+    fn test_freeze_errors_read_the_same_from_every_path() {
+        let caught = |setup: &str, call: &str| {
+            eval(&format!(
+                "{setup}\nlet r = \"\"\ntry {{ {call} }} catch (e) {{ r = e }}\nr"
+            ))
+        };
+        let setup = "let a = Type.freeze([1])";
+        assert_eq!(caught(setup, "a[0] = 2"), "Cannot modify a frozen Array");
+        assert_eq!(caught(setup, "a.push(2)"), "Cannot modify a frozen Array");
+        let setup = "class P {}\nlet p = Type.freeze(P())";
+        assert_eq!(
+            caught(setup, "p.x = 1"),
+            "Cannot set 'x' on a frozen instance"
+        );
+        assert_eq!(
+            caught(setup, "Reflect.set(p, \"x\", 1)"),
+            "Cannot set 'x' on a frozen instance"
+        );
+    }
+
+    #[test]
+    fn test_shallow_freeze_leaves_children_mutable() {
+        let setup = "let a = Type.freeze([[1]])";
+        assert_eq!(eval(&format!("{setup}\na[0].push(2)\na")), "[[1, 2]]");
+        let err = eval_err("let a = Type.freeze([[1]], true)\na[0].push(2)");
+        assert!(err.contains("Cannot modify a frozen Array"), "{err}");
+    }
+
+    #[test]
+    fn test_deep_freeze_handles_cycles() {
+        assert_eq!(
+            eval("let a = [1]\na.push(a)\nType.freeze(a, true)\nType.isFrozen(a[1])"),
+            "true"
+        );
+    }
+
+    #[test]
+    fn test_deep_equality_clone_and_freeze() {
+        let mut engine = Engine::new();
+        engine
+            .eval("let a = {\"xs\": [1, {\"y\": 2}]}\na[\"self\"] = a\nlet b = Type.deepClone(a)")
+            .unwrap();
+        let cloned: Vec<bool> = engine
+            .eval_as("[Type.deepEquals(a, b), a == b, Type.deepEquals(b[\"self\"], b)]")
+            .unwrap();
+        assert_eq!(cloned, [true, false, true]);
+        engine.eval("b[\"xs\"][1][\"y\"] = 3").unwrap();
+        let changed: bool = engine.eval_as("Type.deepEquals(a, b)").unwrap();
+        assert!(!changed);
+
+        engine
+            .eval("let f = Type.freeze({\"n\": [1]}, true)")
+            .unwrap();
+        let error = engine.eval("f[\"n\"][0] = 2").unwrap_err();
+        assert!(error.message().contains("frozen"), "{}", error);
+        let frozen: Vec<bool> = engine
+            .eval_as("[Type.isFrozen(f), Type.isFrozen(Type.deepClone(f))]")
+            .unwrap();
+        assert_eq!(frozen, [true, false]);
+    }
+}
@@ -0,0 +1,113 @@
+use super::date::{make_date, DateKind};
+use super::docs::ClassDoc;
+use super::{bytes_to_value, check_arity, get_string_arg};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use uuid::Uuid;
+
+pub fn create_uuid_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("v4".to_string(), uuid_v4);
+    static_methods.insert("v7".to_string(), uuid_v7);
+    static_methods.insert("nil".to_string(), uuid_nil);
+    static_methods.insert("parse".to_string(), uuid_parse);
+    static_methods.insert("isValid".to_string(), uuid_is_valid);
+
+    Class::new_with_static("Uuid", static_methods)
+}
+
+/// API documentation for the `Uuid` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Uuid", "UUID generation and parsing")
+        .method("v4", "v4()", "Generate a random UUID")
+        .method("v7", "v7()", "Generate a time-ordered UUID")
+        .method("nil", "nil()", "The all-zero UUID")
+        .method(
+            "parse",
+            "parse(text)",
+            "Get a dict with the canonical form, version, bytes and, for v7, the time",
+        )
+        .method("isValid", "isValid(text)", "Check if text is a UUID")
+}
+
+fn string(id: Uuid) -> Value {
+    Value::String(Rc::from(id.hyphenated().to_string()))
+}
+
+fn uuid_v4(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(string(Uuid::new_v4()))
+}
+
+fn uuid_v7(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(string(Uuid::now_v7()))
+}
+
+fn uuid_nil(args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(string(Uuid::nil()))
+}
+
+fn uuid_parse(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let text = get_string_arg(&args[0], "text")?;
+    let id = Uuid::parse_str(text.trim()).map_err(|e| format!("Invalid UUID '{}': {}", text, e))?;
+
+    let mut dict = FxHashMap::default();
+    dict.insert("uuid".to_string(), string(id));
+    dict.insert(
+        "version".to_string(),
+        Value::Number(id.get_version_num() as f64),
+    );
+    dict.insert("bytes".to_string(), bytes_to_value(id.as_bytes()));
+    if let Some(timestamp) = id.get_timestamp() {
+        let (secs, nanos) = timestamp.to_unix();
+        let seconds = secs as f64 + nanos as f64 / 1e9;
+        dict.insert("time".to_string(), make_date(seconds, DateKind::Date));
+    }
+    Ok(Value::Dictionary(Rc::new(RefCell::new(dict.into()))))
+}
+
+fn uuid_is_valid(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let valid = match &args[0] {
+        Value::String(text) => Uuid::parse_str(text.trim()).is_ok(),
+        _ => false,
+    };
+    Ok(Value::Boolean(valid))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_uuid_and_nanoid() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let a = Uuid.v7()\n\
+                 let b = Uuid.v7()\n\
+                 let info = Uuid.parse(a)\n\
+                 let v4 = Uuid.parse(Uuid.v4())",
+            )
+            .unwrap();
+        let result: (bool, f64, f64, f64, bool, bool, bool) = engine
+            .eval_as(
+                "[a < b, info[\"version\"], v4[\"version\"], info[\"bytes\"].length(),\n\
+                  Uuid.isValid(Uuid.nil()), Uuid.isValid(\"not-a-uuid\"), v4[\"time\"] == null]",
+            )
+            .unwrap();
+        assert_eq!(result, (true, 7.0, 4.0, 16.0, true, false, true));
+
+        let ids: (String, String) = engine
+            .eval_as("[Crypto.nanoid(), Crypto.nanoid(8, \"ab\")]")
+            .unwrap();
+        assert_eq!(ids.0.len(), 21);
+        assert!(ids.1.len() == 8 && ids.1.chars().all(|c| c == 'a' || c == 'b'));
+    }
+}
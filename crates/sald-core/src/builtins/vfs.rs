@@ -0,0 +1,331 @@
+//! `File`, `Process` and `System` for the browser build, backed by the
+//! virtual filesystem and host state in `crate::wasm`
+
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_number_arg, get_string_arg};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use crate::wasm::{self, normalize_path, with_filesystem};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn create_file_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("read".to_string(), file_read);
+    static_methods.insert("write".to_string(), file_write);
+    static_methods.insert("append".to_string(), file_append);
+    static_methods.insert("readDir".to_string(), file_read_dir);
+
+    static_methods.insert("exists".to_string(), file_exists);
+    static_methods.insert("isFile".to_string(), file_is_file);
+    static_methods.insert("isDir".to_string(), file_is_dir);
+    static_methods.insert("size".to_string(), file_size);
+    static_methods.insert("stat".to_string(), file_stat);
+
+    static_methods.insert("delete".to_string(), file_delete);
+    static_methods.insert("copy".to_string(), file_copy);
+    static_methods.insert("rename".to_string(), file_rename);
+    static_methods.insert("replace".to_string(), file_write);
+    static_methods.insert("mkdir".to_string(), file_mkdir);
+
+    static_methods.insert("join".to_string(), file_join);
+    static_methods.insert("dirname".to_string(), file_dirname);
+    static_methods.insert("basename".to_string(), file_basename);
+    static_methods.insert("ext".to_string(), file_ext);
+
+    let mut class = Class::new("File");
+    class.native_static_methods = static_methods;
+    class
+}
+
+pub fn create_process_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("args".to_string(), process_args);
+    static_methods.insert("env".to_string(), process_env);
+    static_methods.insert("exit".to_string(), process_exit);
+    static_methods.insert("cwd".to_string(), process_cwd);
+    static_methods.insert("chdir".to_string(), process_chdir);
+
+    Class::new_with_static("Process", static_methods)
+}
+
+pub fn create_system_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("os".to_string(), system_os);
+    static_methods.insert("arch".to_string(), system_arch);
+    static_methods.insert("family".to_string(), system_family);
+
+    let mut class = Class::new_with_static("System", static_methods);
+    class.native_static_fields.insert(
+        "platform".to_string(),
+        Value::String(Rc::from(crate::target::PLATFORM)),
+    );
+    class
+}
+
+/// API documentation for the browser `File`, `Process` and `System` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new("File", "Virtual filesystem operations")
+            .method("read", "read(path)", "Read file contents")
+            .method("write", "write(path, content)", "Write to file")
+            .method("append", "append(path, content)", "Append to file")
+            .method("readDir", "readDir(path)", "List directory contents")
+            .method("exists", "exists(path)", "Check if path exists")
+            .method("isFile", "isFile(path)", "Check if path is file")
+            .method("isDir", "isDir(path)", "Check if path is directory")
+            .method("size", "size(path)", "Get file size in bytes")
+            .method(
+                "stat",
+                "stat(path)",
+                "Get a dict with size, isFile and isDir",
+            )
+            .method("delete", "delete(path)", "Delete file or empty dir")
+            .method("copy", "copy(src, dst)", "Copy file")
+            .method("rename", "rename(old, new)", "Rename/move file")
+            .method(
+                "replace",
+                "replace(path, content)",
+                "Replace a file's contents",
+            )
+            .method("mkdir", "mkdir(path)", "Create directory and its parents")
+            .method("join", "join(...parts)", "Join path components")
+            .method("dirname", "dirname(path)", "Get directory name")
+            .method("basename", "basename(path)", "Get file name")
+            .method("ext", "ext(path)", "Get file extension"),
+        ClassDoc::new("Process", "Host-provided process state")
+            .method("args", "args()", "Arguments set by the host page")
+            .method("env", "env(name)", "Variable set by the host page")
+            .method("cwd", "cwd()", "Current virtual directory")
+            .method("chdir", "chdir(path)", "Change virtual directory")
+            .method("exit", "exit(code?)", "Stop the script"),
+        ClassDoc::new("System", "Build information")
+            .property("platform", "\"wasm\" in the browser build")
+            .method("os", "os()", "Operating system name")
+            .method("arch", "arch()", "CPU architecture")
+            .method("family", "family()", "OS family"),
+    ]
+}
+
+fn path_arg(args: &[Value], index: usize, name: &str) -> Result<String, String> {
+    Ok(normalize_path(&get_string_arg(&args[index], name)?))
+}
+
+fn file_read(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    let bytes = with_filesystem(|fs| fs.read(&path))
+        .map_err(|e| format!("Failed to read file '{}': {}", path, e))?;
+    Ok(Value::String(Rc::from(
+        String::from_utf8_lossy(&bytes).into_owned(),
+    )))
+}
+
+fn file_write(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    let content = format!("{}", args[1]);
+    with_filesystem(|fs| fs.write(&path, content.as_bytes()))
+        .map_err(|e| format!("Failed to write file '{}': {}", path, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_append(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    let content = format!("{}", args[1]);
+    with_filesystem(|fs| fs.append(&path, content.as_bytes()))
+        .map_err(|e| format!("Failed to append to file '{}': {}", path, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_read_dir(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    let names = with_filesystem(|fs| fs.read_dir(&path))
+        .map_err(|e| format!("Failed to read directory '{}': {}", path, e))?;
+    Ok(Value::Array(Rc::new(RefCell::new(
+        names
+            .into_iter()
+            .map(|name| Value::String(Rc::from(name)))
+            .collect(),
+    ))))
+}
+
+fn file_exists(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    Ok(Value::Boolean(with_filesystem(|fs| {
+        fs.is_file(&path) || fs.is_dir(&path)
+    })))
+}
+
+fn file_is_file(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    Ok(Value::Boolean(with_filesystem(|fs| fs.is_file(&path))))
+}
+
+fn file_is_dir(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    Ok(Value::Boolean(with_filesystem(|fs| fs.is_dir(&path))))
+}
+
+fn file_size(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    let bytes = with_filesystem(|fs| fs.read(&path))
+        .map_err(|e| format!("Failed to get size of '{}': {}", path, e))?;
+    Ok(Value::Number(bytes.len() as f64))
+}
+
+fn file_stat(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    let (is_file, is_dir) = with_filesystem(|fs| (fs.is_file(&path), fs.is_dir(&path)));
+    let size = if is_file {
+        with_filesystem(|fs| fs.read(&path)).map_or(0, |b| b.len())
+    } else if is_dir {
+        0
+    } else {
+        return Err(format!(
+            "Failed to stat '{}': No such file or directory",
+            path
+        ));
+    };
+
+    let mut dict = FxHashMap::default();
+    dict.insert("size".to_string(), Value::Number(size as f64));
+    dict.insert("isFile".to_string(), Value::Boolean(is_file));
+    dict.insert("isDir".to_string(), Value::Boolean(is_dir));
+    Ok(Value::Dictionary(Rc::new(RefCell::new(dict.into()))))
+}
+
+fn file_delete(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    with_filesystem(|fs| fs.remove(&path))
+        .map_err(|e| format!("Failed to delete '{}': {}", path, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_copy(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let src = path_arg(args, 0, "src")?;
+    let dst = path_arg(args, 1, "dst")?;
+    with_filesystem(|fs| fs.read(&src).and_then(|bytes| fs.write(&dst, &bytes)))
+        .map_err(|e| format!("Failed to copy '{}' to '{}': {}", src, dst, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_rename(args: &[Value]) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let from = path_arg(args, 0, "old")?;
+    let to = path_arg(args, 1, "new")?;
+    with_filesystem(|fs| fs.rename(&from, &to))
+        .map_err(|e| format!("Failed to rename '{}' to '{}': {}", from, to, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_mkdir(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = path_arg(args, 0, "path")?;
+    with_filesystem(|fs| fs.mkdir(&path))
+        .map_err(|e| format!("Failed to create directory '{}': {}", path, e))?;
+    Ok(Value::Boolean(true))
+}
+
+fn file_join(args: &[Value]) -> Result<Value, String> {
+    let mut joined = String::new();
+    for (i, arg) in args.iter().enumerate() {
+        let part = get_string_arg(arg, "part")?;
+        if part.starts_with('/') || i == 0 {
+            joined = part;
+        } else if joined.ends_with('/') || joined.is_empty() {
+            joined.push_str(&part);
+        } else {
+            joined = format!("{}/{}", joined, part);
+        }
+    }
+    Ok(Value::String(Rc::from(joined)))
+}
+
+fn file_dirname(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    let dir = match path.trim_end_matches('/').rfind('/') {
+        Some(0) => "/",
+        Some(i) => &path[..i],
+        None => ".",
+    };
+    Ok(Value::String(Rc::from(dir)))
+}
+
+fn file_basename(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    let name = path.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    Ok(Value::String(Rc::from(name)))
+}
+
+fn file_ext(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    let name = path.rsplit('/').next().unwrap_or("");
+    let ext = match name.rfind('.') {
+        Some(i) if i > 0 => &name[i + 1..],
+        _ => "",
+    };
+    Ok(Value::String(Rc::from(ext)))
+}
+
+fn process_args(_args: &[Value]) -> Result<Value, String> {
+    let args: Vec<Value> = wasm::script_args()
+        .into_iter()
+        .map(|s| Value::String(Rc::from(s)))
+        .collect();
+    Ok(Value::Array(Rc::new(RefCell::new(args.into()))))
+}
+
+fn process_env(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let name = get_string_arg(&args[0], "name")?;
+    Ok(wasm::env_var(&name).map_or(Value::Null, |v| Value::String(Rc::from(v))))
+}
+
+/// There is no process to end, so the script is unwound with an error.
+fn process_exit(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(0, 1, args.len())?;
+    let code = match args.first() {
+        Some(arg) => get_number_arg(arg, "code")? as i32,
+        None => 0,
+    };
+    Err(format!("Process exited with code {}", code))
+}
+
+fn system_os(_args: &[Value]) -> Result<Value, String> {
+    Ok(Value::String(Rc::from(std::env::consts::OS)))
+}
+
+fn system_arch(_args: &[Value]) -> Result<Value, String> {
+    Ok(Value::String(Rc::from(std::env::consts::ARCH)))
+}
+
+fn system_family(_args: &[Value]) -> Result<Value, String> {
+    Ok(Value::String(Rc::from(std::env::consts::FAMILY)))
+}
+
+fn process_cwd(_args: &[Value]) -> Result<Value, String> {
+    Ok(Value::String(Rc::from(wasm::current_dir())))
+}
+
+fn process_chdir(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let path = get_string_arg(&args[0], "path")?;
+    wasm::set_current_dir(&path)?;
+    Ok(Value::Null)
+}
@@ -0,0 +1,58 @@
+use super::docs::ClassDoc;
+use super::{check_arity, check_arity_range, get_string_arg};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use crate::warnings::{self, WarningKind};
+use rustc_hash::FxHashMap;
+
+pub fn create_warning_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("emit".to_string(), warning_emit);
+    static_methods.insert("deprecated".to_string(), warning_deprecated);
+
+    Class::new_with_static("Warning", static_methods)
+}
+
+/// API documentation for the `Warning` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new(
+        "Warning",
+        "Non-fatal warnings, printed once each to stderr by default",
+    )
+    .method(
+        "emit",
+        "emit(message, ?kind)",
+        "Report a warning; kind is 'user' (default), 'deprecated', 'coercion' or 'lossy'",
+    )
+    .method(
+        "deprecated",
+        "deprecated(message)",
+        "Report use of a deprecated API",
+    )
+}
+
+fn warning_emit(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let message = get_string_arg(&args[0], "message")?;
+    let kind = match args.get(1) {
+        None | Some(Value::Null) => WarningKind::User,
+        Some(kind) => {
+            let name = get_string_arg(kind, "kind")?;
+            WarningKind::from_name(&name).ok_or_else(|| {
+                format!(
+                    "Unknown warning kind '{}', expected 'user', 'deprecated', 'coercion' or 'lossy'",
+                    name
+                )
+            })?
+        }
+    };
+    warnings::warn(kind, message)?;
+    Ok(Value::Null)
+}
+
+fn warning_deprecated(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let message = get_string_arg(&args[0], "message")?;
+    warnings::warn(WarningKind::Deprecated, message)?;
+    Ok(Value::Null)
+}
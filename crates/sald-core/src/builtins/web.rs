@@ -0,0 +1,297 @@
+//! `Timer` and `Http` for the browser build, bridged to `setTimeout` and
+//! `fetch`. Both hand back Futures that resume the script once JS settles
+//! them, so they need `run_code_async`.
+
+use super::docs::ClassDoc;
+use super::{
+    check_arity, check_arity_range, get_number_arg, get_string_arg, native_instance, native_state,
+};
+use crate::vm::value::{Class, NativeInstanceFn, NativeStaticFn, SendValue, Value};
+use crate::wasm::{self, js_error};
+use js_sys::{Object, Promise, Reflect};
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+pub fn create_timer_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("now".to_string(), timer_now);
+    static_methods.insert("millis".to_string(), timer_now);
+    static_methods.insert("sleep".to_string(), timer_sleep);
+    static_methods.insert("interval".to_string(), timer_interval);
+    static_methods.insert("timeout".to_string(), timer_timeout);
+
+    Class::new_with_static("Timer", static_methods)
+}
+
+fn create_handle_class() -> Class {
+    let mut instance_methods: FxHashMap<String, NativeInstanceFn> = FxHashMap::default();
+
+    instance_methods.insert("cancel".to_string(), handle_cancel);
+    instance_methods.insert("isActive".to_string(), handle_is_active);
+
+    Class::new_with_instance("TimerHandle", instance_methods, None)
+}
+
+pub fn create_http_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("get".to_string(), http_get);
+    static_methods.insert("post".to_string(), http_post);
+    static_methods.insert("request".to_string(), http_request);
+
+    Class::new_with_static("Http", static_methods)
+}
+
+/// API documentation for the browser `Timer` and `Http` classes
+pub(crate) fn docs() -> Vec<ClassDoc> {
+    vec![
+        ClassDoc::new("Timer", "Time utilities backed by setTimeout")
+            .method("sleep", "await sleep(ms)", "Resume after ms milliseconds")
+            .method("now", "now()", "Current timestamp in ms")
+            .method("millis", "millis()", "Alias for now()")
+            .method(
+                "interval",
+                "interval(ms, fn)",
+                "Call fn every ms milliseconds, returns a TimerHandle",
+            )
+            .method(
+                "timeout",
+                "timeout(ms, fn)",
+                "Call fn once after ms milliseconds, returns a TimerHandle",
+            ),
+        ClassDoc::new(
+            "TimerHandle",
+            "Scheduled callback returned by Timer.interval and Timer.timeout",
+        )
+        .method("cancel", "cancel()", "Stop the callback from running again")
+        .method(
+            "isActive",
+            "isActive()",
+            "Check if the callback is still scheduled",
+        ),
+        ClassDoc::new("Http", "HTTP client backed by fetch")
+            .method(
+                "get",
+                "await get(url, options?)",
+                "Fetch url; options may set headers",
+            )
+            .method(
+                "post",
+                "await post(url, body, options?)",
+                "Send body to url with POST",
+            )
+            .method(
+                "request",
+                "await request(method, url, options?)",
+                "Send a request; options may set headers and body",
+            ),
+    ]
+}
+
+fn timer_now(_args: &[Value]) -> Result<Value, String> {
+    Ok(Value::Number(js_sys::Date::now()))
+}
+
+fn timer_sleep(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let ms = get_number_arg(&args[0], "ms")?;
+    let delay = wasm::delay(ms.max(0.0))?;
+    Ok(wasm::spawn_future(async move {
+        delay.await.map_err(js_error)?;
+        Ok(SendValue::Null)
+    }))
+}
+
+fn scheduled(args: &[Value], repeat: bool) -> Result<Value, String> {
+    check_arity(2, args.len())?;
+    let ms = get_number_arg(&args[0], "ms")?;
+    if ms < 0.0 || (repeat && ms < 1.0) {
+        return Err("Argument 'ms' must be at least 1".to_string());
+    }
+    let id = wasm::set_timer(ms, repeat, args[1].clone())?;
+    Ok(native_instance(create_handle_class(), TimerId(id)))
+}
+
+fn timer_interval(args: &[Value]) -> Result<Value, String> {
+    scheduled(args, true)
+}
+
+fn timer_timeout(args: &[Value]) -> Result<Value, String> {
+    scheduled(args, false)
+}
+
+/// Native state of a `TimerHandle`: the id of its JS-side timer
+struct TimerId(usize);
+
+fn handle_id(recv: &Value) -> Result<usize, String> {
+    let TimerId(id) = *native_state::<TimerId>(recv, "TimerHandle")?.borrow();
+    Ok(id)
+}
+
+fn handle_cancel(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Boolean(wasm::clear_timer(handle_id(recv)?)))
+}
+
+fn handle_is_active(recv: &Value, args: &[Value]) -> Result<Value, String> {
+    check_arity(0, args.len())?;
+    Ok(Value::Boolean(wasm::timer_active(handle_id(recv)?)))
+}
+
+struct Request {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+}
+
+impl Request {
+    fn new(method: &str, url: String, options: Option<&Value>) -> Result<Self, String> {
+        let mut request = Request {
+            method: method.to_uppercase(),
+            url,
+            headers: Vec::new(),
+            body: None,
+        };
+        let Some(options) = options else {
+            return Ok(request);
+        };
+        let Value::Dictionary(options) = options else {
+            return Err(format!(
+                "Argument 'options' must be a dictionary, got {}",
+                options.type_name()
+            ));
+        };
+        let options = options.borrow();
+        match options.get("headers") {
+            Some(Value::Dictionary(headers)) => {
+                for (name, value) in headers.borrow().iter() {
+                    request.headers.push((name.clone(), value.to_string()));
+                }
+            }
+            Some(Value::Null) | None => {}
+            Some(other) => {
+                return Err(format!(
+                    "Option 'headers' must be a dictionary, got {}",
+                    other.type_name()
+                ))
+            }
+        }
+        match options.get("body") {
+            Some(Value::Null) | None => {}
+            Some(body) => request.body = Some(body.to_string()),
+        }
+        Ok(request)
+    }
+
+    fn send(self) -> Result<Value, String> {
+        let init = Object::new();
+        let set = |key: &str, value: &JsValue| {
+            Reflect::set(&init, &JsValue::from_str(key), value).map_err(js_error)
+        };
+        set("method", &JsValue::from_str(&self.method))?;
+        let headers = Object::new();
+        for (name, value) in &self.headers {
+            Reflect::set(
+                &headers,
+                &JsValue::from_str(name),
+                &JsValue::from_str(value),
+            )
+            .map_err(js_error)?;
+        }
+        set("headers", &headers)?;
+        if let Some(body) = &self.body {
+            set("body", &JsValue::from_str(body))?;
+        }
+
+        let fetch = Reflect::get(&js_sys::global(), &JsValue::from_str("fetch"))
+            .map_err(js_error)?
+            .dyn_into::<js_sys::Function>()
+            .map_err(|_| "fetch is not available in this environment".to_string())?;
+        let pending = fetch
+            .call2(&JsValue::NULL, &JsValue::from_str(&self.url), &init)
+            .map_err(js_error)?
+            .dyn_into::<Promise>()
+            .map_err(|_| "fetch did not return a Promise".to_string())?;
+
+        let url = self.url;
+        Ok(wasm::spawn_future(async move {
+            let response = JsFuture::from(pending)
+                .await
+                .map_err(|e| format!("Request to '{}' failed: {}", url, js_error(e)))?;
+            read_response(&response).await
+        }))
+    }
+}
+
+fn field(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).unwrap_or(JsValue::UNDEFINED)
+}
+
+/// Turns a fetch `Response` into `{status, ok, statusText, headers, body}`.
+async fn read_response(response: &JsValue) -> Result<SendValue, String> {
+    let mut headers = HashMap::new();
+    if let Ok(Some(entries)) = js_sys::try_iter(&field(response, "headers")) {
+        for entry in entries.flatten() {
+            let pair = js_sys::Array::from(&entry);
+            if let (Some(name), Some(value)) = (pair.get(0).as_string(), pair.get(1).as_string()) {
+                headers.insert(name, SendValue::String(value));
+            }
+        }
+    }
+
+    let text = Reflect::get(response, &JsValue::from_str("text"))
+        .and_then(|f| f.dyn_into::<js_sys::Function>())
+        .and_then(|f| f.call0(response))
+        .and_then(|p| p.dyn_into::<Promise>())
+        .map_err(js_error)?;
+    let body = JsFuture::from(text).await.map_err(js_error)?;
+
+    let mut result = HashMap::new();
+    result.insert(
+        "status".to_string(),
+        SendValue::Number(field(response, "status").as_f64().unwrap_or(0.0)),
+    );
+    result.insert(
+        "ok".to_string(),
+        SendValue::Boolean(field(response, "ok").as_bool().unwrap_or(false)),
+    );
+    result.insert(
+        "statusText".to_string(),
+        SendValue::String(
+            field(response, "statusText")
+                .as_string()
+                .unwrap_or_default(),
+        ),
+    );
+    result.insert("headers".to_string(), SendValue::Dictionary(headers));
+    result.insert(
+        "body".to_string(),
+        SendValue::String(body.as_string().unwrap_or_default()),
+    );
+    Ok(SendValue::Dictionary(result))
+}
+
+fn http_get(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(1, 2, args.len())?;
+    let url = get_string_arg(&args[0], "url")?;
+    Request::new("GET", url, args.get(1))?.send()
+}
+
+fn http_post(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let url = get_string_arg(&args[0], "url")?;
+    let mut request = Request::new("POST", url, args.get(2))?;
+    request.body = Some(args[1].to_string());
+    request.send()
+}
+
+fn http_request(args: &[Value]) -> Result<Value, String> {
+    check_arity_range(2, 3, args.len())?;
+    let method = get_string_arg(&args[0], "method")?;
+    let url = get_string_arg(&args[1], "url")?;
+    Request::new(&method, url, args.get(2))?.send()
+}
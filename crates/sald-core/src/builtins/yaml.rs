@@ -0,0 +1,66 @@
+use super::docs::ClassDoc;
+use super::{
+    check_arity, get_string_arg, integralize_numbers, json_to_sald_value, sald_value_to_json,
+};
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+pub fn create_yaml_class() -> Class {
+    let mut static_methods: FxHashMap<String, NativeStaticFn> = FxHashMap::default();
+
+    static_methods.insert("parse".to_string(), yaml_parse);
+    static_methods.insert("stringify".to_string(), yaml_stringify);
+
+    Class::new_with_static("Yaml", static_methods)
+}
+
+/// API documentation for the `Yaml` class
+pub(crate) fn docs() -> ClassDoc {
+    ClassDoc::new("Yaml", "YAML parsing and serialization")
+        .method("parse", "parse(yaml)", "Parse YAML string to value")
+        .method("stringify", "stringify(value)", "Convert value to YAML")
+}
+
+fn yaml_parse(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let yaml_str = get_string_arg(&args[0], "yaml")?;
+
+    serde_yaml::from_str::<serde_json::Value>(&yaml_str)
+        .map_err(|e| e.to_string())
+        .and_then(|json_value| json_to_sald_value(&json_value))
+}
+
+fn yaml_stringify(args: &[Value]) -> Result<Value, String> {
+    check_arity(1, args.len())?;
+    let mut json_value = sald_value_to_json(&args[0])?;
+    integralize_numbers(&mut json_value);
+    let yaml_string = serde_yaml::to_string(&json_value).map_err(|e| e.to_string())?;
+    Ok(Value::String(Rc::from(yaml_string)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_yaml_and_toml_round_trip() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let y = Yaml.parse(\"name: demo\\nports: [80, 443]\\n\")\n\
+                 let t = Toml.parse(\"[server]\\nhost = \\\"local\\\"\\nport = 8080\\n\")",
+            )
+            .unwrap();
+        let result: (String, f64, String, String) = engine
+            .eval_as(
+                "[y[\"name\"], y[\"ports\"][1], Toml.stringify({\"a\": 1, \"b\": null}), \
+                 Yaml.stringify(t)]",
+            )
+            .unwrap();
+        assert_eq!(result.0, "demo");
+        assert_eq!(result.1, 443.0);
+        assert_eq!(result.2, "a = 1\n");
+        assert_eq!(result.3, "server:\n  host: local\n  port: 8080\n");
+    }
+}
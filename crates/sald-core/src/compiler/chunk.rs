@@ -1,6 +1,7 @@
 use super::opcode::OpCode;
 use crate::error::Span;
-use std::sync::Arc;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Constant {
@@ -8,6 +9,10 @@ pub enum Constant {
     String(Arc<str>),
     Function(FunctionConstant),
     Class(ClassConstant),
+    /// Unix timestamp in seconds, from a `d"..."` literal
+    Date(f64),
+    /// Seconds since midnight, from a `t"..."` literal
+    Time(f64),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -24,8 +29,11 @@ pub struct FunctionConstant {
     pub is_async: bool,
     pub upvalue_count: usize,
     pub upvalues: Vec<UpvalueInfo>,
-    pub chunk: Chunk,
+    pub chunk: FunctionBody,
     pub file: String,
+    /// Whether named arguments with no matching parameter go to a `**`
+    /// parameter, the local after the others
+    pub has_kwargs: bool,
     pub param_names: Vec<String>,
     pub default_count: usize,
     pub decorators: Vec<String>,
@@ -33,6 +41,74 @@ pub struct FunctionConstant {
     pub class_context: Option<String>,
 }
 
+/// Compiled body of a function constant. Bodies read by
+/// `binary::deserialize_lazy` stay encoded until the function is first created.
+#[derive(Clone)]
+pub struct FunctionBody {
+    chunk: Arc<OnceLock<Result<Chunk, String>>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    encoded: Option<Arc<crate::binary::EncodedBody>>,
+}
+
+impl FunctionBody {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn encoded(body: crate::binary::EncodedBody) -> Self {
+        Self {
+            chunk: Arc::new(OnceLock::new()),
+            encoded: Some(Arc::new(body)),
+        }
+    }
+
+    /// Decodes the body on first use. Fails if the encoded bytes are corrupt.
+    pub fn load(&self) -> Result<&Chunk, String> {
+        self.chunk
+            .get_or_init(|| self.decode())
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.chunk.get().is_some()
+    }
+
+    fn decode(&self) -> Result<Chunk, String> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(encoded) = &self.encoded {
+            return crate::binary::decode_body(encoded);
+        }
+        Err("Function body is missing".to_string())
+    }
+}
+
+impl From<Chunk> for FunctionBody {
+    fn from(chunk: Chunk) -> Self {
+        Self {
+            chunk: Arc::new(OnceLock::from(Ok(chunk))),
+            #[cfg(not(target_arch = "wasm32"))]
+            encoded: None,
+        }
+    }
+}
+
+impl fmt::Debug for FunctionBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.chunk.get() {
+            Some(Ok(chunk)) => chunk.fmt(f),
+            Some(Err(e)) => write!(f, "<invalid body: {}>", e),
+            None => write!(f, "<encoded body>"),
+        }
+    }
+}
+
+impl PartialEq for FunctionBody {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.load(), other.load()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ClassConstant {
     pub name: String,
@@ -144,8 +220,9 @@ impl Chunk {
 
         for constant in &self.constants {
             if let Constant::Function(f) = constant {
-                f.chunk
-                    .disassemble_with_indent(&format!("<fn {}>", f.name), indent + 1);
+                if let Ok(chunk) = f.chunk.load() {
+                    chunk.disassemble_with_indent(&format!("<fn {}>", f.name), indent + 1);
+                }
             }
         }
     }
@@ -195,6 +272,8 @@ impl Chunk {
             }
             Some(Constant::Function(f)) => format!("<fn {}>", f.name),
             Some(Constant::Class(c)) => format!("<class {}>", c.name),
+            Some(Constant::Date(n)) => format!("<date {}>", n),
+            Some(Constant::Time(n)) => format!("<time {}>", n),
             None => format!("???[{}]", idx),
         }
     }
@@ -234,6 +313,11 @@ impl Chunk {
                 println!("def_global     {}", self.format_constant(idx));
                 offset + 3
             }
+            OpCode::DefineNamespace => {
+                let idx = self.read_u16(offset + 1) as usize;
+                println!("def_namespace  {}", self.format_constant(idx));
+                offset + 3
+            }
             OpCode::GetGlobal => {
                 let idx = self.read_u16(offset + 1) as usize;
                 println!("get_global     {}", self.format_constant(idx));
@@ -447,6 +531,39 @@ impl Chunk {
                 );
                 offset + 5
             }
+            OpCode::ImportDynamic => {
+                println!("import_dynamic");
+                offset + 1
+            }
+            OpCode::NamedArgs => {
+                println!("named_args");
+                offset + 1
+            }
+            OpCode::Kwargs => {
+                println!("kwargs");
+                offset + 1
+            }
+            OpCode::GetPropertyOrNull => {
+                let idx = self.read_u16(offset + 1) as usize;
+                println!("get_prop_or_null {}", self.format_constant(idx));
+                offset + 3
+            }
+            OpCode::ForIter => {
+                let slot = self.read_u16(offset + 1);
+                let jump = self.read_u16(offset + 3) as usize;
+                println!("for_iter       [{}] -> {}", slot, offset + 5 + jump);
+                offset + 5
+            }
+            OpCode::ImportNamespace => {
+                let path_idx = self.read_u16(offset + 1) as usize;
+                let alias_idx = self.read_u16(offset + 3) as usize;
+                println!(
+                    "import_ns      {} as {}",
+                    self.format_constant(path_idx),
+                    self.format_constant(alias_idx)
+                );
+                offset + 5
+            }
 
             OpCode::TryStart => {
                 let catch_offset = self.read_u16(offset + 1);
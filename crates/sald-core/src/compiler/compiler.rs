@@ -1,9 +1,23 @@
 use super::chunk::{Chunk, Constant, FunctionConstant, UpvalueInfo};
 use super::opcode::OpCode;
 use crate::ast::*;
+use crate::builtins::date::{parse_date_literal, parse_time_literal};
 use crate::error::{SaldError, SaldResult, Span};
 use crate::vm::interner::intern;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Constant operands are u16, so a chunk can address at most this many
+const MAX_CONSTANTS: usize = u16::MAX as usize + 1;
+
+/// Longest string a literal `"s" * n` is folded into at compile time
+const FOLD_REPEAT_LIMIT: usize = 1024;
+
+/// Strict mode for files without the pragma, set by `--strict`
+static STRICT_DEFAULT: AtomicBool = AtomicBool::new(false);
+
+/// A file whose first statement is this string compiles in strict mode
+const STRICT_PRAGMA: &str = "use strict";
 
 #[derive(Debug, Clone)]
 enum FoldedValue {
@@ -18,6 +32,9 @@ struct Local {
     depth: usize,
     initialized: bool,
     is_captured: bool,
+    is_const: bool,
+    /// Where it was declared, for redeclaration errors
+    span: Span,
 }
 
 #[derive(Debug, Clone)]
@@ -60,6 +77,8 @@ impl FunctionScope {
                 depth: 0,
                 initialized: true,
                 is_captured: false,
+                is_const: false,
+                span: Span::default(),
             });
         } else {
             scope.locals.push(Local {
@@ -67,6 +86,8 @@ impl FunctionScope {
                 depth: 0,
                 initialized: true,
                 is_captured: false,
+                is_const: false,
+                span: Span::default(),
             });
         }
 
@@ -83,6 +104,19 @@ pub struct Compiler {
     interfaces: FxHashMap<String, InterfaceDef>,
     current_namespace: Option<String>,
     current_class: Option<String>,
+    strict: bool,
+    /// Top-level names the file declares; only tracked in strict mode
+    file_globals: Option<FxHashSet<String>>,
+    /// Top-level names declared by the statements compiled so far
+    defined_globals: FxHashSet<String>,
+    /// Top-level names the file declares with `const`
+    const_globals: FxHashSet<String>,
+    /// Jumps out of each `?.` chain being compiled, taken when a link is null
+    optional_chains: Vec<Vec<usize>>,
+    /// Set while compiling the object of a chain link that is itself a link
+    continue_chain: bool,
+    /// Whether `import` statements and `import()` are compile errors
+    deny_imports: bool,
 }
 
 impl Compiler {
@@ -96,12 +130,37 @@ impl Compiler {
             interfaces: FxHashMap::default(),
             current_namespace: None,
             current_class: None,
+            strict: STRICT_DEFAULT.load(Ordering::Relaxed),
+            file_globals: None,
+            defined_globals: FxHashSet::default(),
+            const_globals: FxHashSet::default(),
+            optional_chains: Vec::new(),
+            continue_chain: false,
+            deny_imports: false,
         }
     }
 
+    /// Rejects `import` statements and `import()` calls, for code that must
+    /// only reach what it is handed.
+    pub fn deny_imports(&mut self) {
+        self.deny_imports = true;
+    }
+
+    /// Compiles every file in strict mode, as if it started with
+    /// `"use strict"`.
+    pub fn set_strict_default(strict: bool) {
+        STRICT_DEFAULT.store(strict, Ordering::Relaxed);
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
     pub fn compile(&mut self, program: &Program) -> SaldResult<Chunk> {
-        for stmt in &program.statements {
+        self.begin_program(program, false);
+        for stmt in hoisted_order(&program.statements) {
             self.compile_stmt(stmt)?;
+            self.define_globals(stmt);
         }
 
         self.emit_op(OpCode::Null, Span::default());
@@ -113,13 +172,16 @@ impl Compiler {
                 Span::default(),
                 &self.file,
             ))
+        } else if self.current_scope().chunk.constants.len() > MAX_CONSTANTS {
+            Err(self.too_many_constants(Span::default()))
         } else {
             Ok(self.current_scope().chunk.clone())
         }
     }
 
     pub fn compile_repl(&mut self, program: &Program) -> SaldResult<Chunk> {
-        let stmts = &program.statements;
+        self.begin_program(program, true);
+        let stmts = hoisted_order(&program.statements);
 
         for (i, stmt) in stmts.iter().enumerate() {
             let is_last = i == stmts.len() - 1;
@@ -138,6 +200,7 @@ impl Compiler {
             } else {
                 self.compile_stmt(stmt)?;
             }
+            self.define_globals(stmt);
         }
 
         if stmts.is_empty() {
@@ -152,11 +215,164 @@ impl Compiler {
                 Span::default(),
                 &self.file,
             ))
+        } else if self.current_scope().chunk.constants.len() > MAX_CONSTANTS {
+            Err(self.too_many_constants(Span::default()))
         } else {
             Ok(self.current_scope().chunk.clone())
         }
     }
 
+    /// Compiles `program` without producing a chunk and returns every
+    /// compile error instead of stopping at the first. Each top-level
+    /// statement is checked on its own, so one error does not hide the rest.
+    pub fn check(&mut self, program: &Program) -> Vec<SaldError> {
+        self.begin_program(program, false);
+        let mut errors = Vec::new();
+        for stmt in hoisted_order(&program.statements) {
+            let scopes = self.scopes.len();
+            let scope = self.current_scope();
+            let (locals, depth) = (scope.locals.len(), scope.scope_depth);
+            let loops = scope.loop_starts.len();
+            let breaks = scope.break_jumps.len();
+
+            if let Err(e) = self.compile_stmt(stmt) {
+                errors.push(e);
+                // Unwind whatever the failed statement left open
+                self.scopes.truncate(scopes);
+                let scope = self.current_scope_mut();
+                scope.locals.truncate(locals);
+                scope.scope_depth = depth;
+                scope.loop_starts.truncate(loops);
+                scope.break_jumps.truncate(breaks);
+                scope.loop_scope_depths.truncate(loops);
+                self.class_depth = 0;
+                self.current_namespace = None;
+                self.current_class = None;
+            }
+            self.define_globals(stmt);
+        }
+
+        if self.current_scope().chunk.constants.len() > MAX_CONSTANTS {
+            errors.push(self.too_many_constants(Span::default()));
+        }
+        errors
+    }
+
+    /// Turns on strict mode for a file that asks for it and collects the
+    /// globals it declares, so assignments to its constants and uses of undeclared or not yet declared
+    /// globals can be reported. REPL input only collects them when it asks
+    /// for strict mode itself, as `--strict` must not reject globals
+    /// declared by earlier lines.
+    fn begin_program(&mut self, program: &Program, repl: bool) {
+        let pragma = matches!(
+            program.statements.first(),
+            Some(Stmt::Expression {
+                expr: Expr::Literal {
+                    value: Literal::String(text),
+                    ..
+                },
+                ..
+            }) if text == STRICT_PRAGMA
+        );
+        self.strict |= pragma;
+        self.const_globals = program
+            .statements
+            .iter()
+            .filter_map(Stmt::active)
+            .filter_map(|stmt| match stmt {
+                Stmt::Const { name, .. } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        if self.strict && (pragma || !repl) {
+            let mut names = FxHashSet::default();
+            for stmt in &program.statements {
+                names.extend(declared_names(stmt));
+            }
+            self.file_globals = Some(names);
+        }
+    }
+
+    fn define_globals(&mut self, stmt: &Stmt) {
+        if self.file_globals.is_some() {
+            self.defined_globals.extend(declared_names(stmt));
+        }
+    }
+
+    /// In strict mode, rejects top-level code reading a global before the
+    /// file declares it, and assignments to globals the file never declares.
+    fn check_strict_global(&self, name: &str, assigning: bool, span: Span) -> SaldResult<()> {
+        let Some(file_globals) = &self.file_globals else {
+            return Ok(());
+        };
+        let declared = file_globals.contains(name);
+        if assigning && !declared {
+            return Err(SaldError::syntax_error(
+                format!("Assignment to undeclared variable '{}'", name),
+                span,
+                &self.file,
+            )
+            .with_source(&self.source)
+            .with_help(format!(
+                "Declare it first with 'let {} = ...' (strict mode)",
+                name
+            )));
+        }
+        if declared && self.scopes.len() == 1 && !self.defined_globals.contains(name) {
+            return Err(SaldError::syntax_error(
+                format!("Variable '{}' is used before its declaration", name),
+                span,
+                &self.file,
+            )
+            .with_source(&self.source)
+            .with_help("Move the declaration above its first use (strict mode)"));
+        }
+        Ok(())
+    }
+
+    /// In strict mode, rejects a local that hides a variable of an
+    /// enclosing block, function or the file's globals.
+    fn check_strict_shadowing(&self, name: &str, span: Span) -> SaldResult<()> {
+        if !self.strict || name.is_empty() || name == "self" || name.starts_with("__") {
+            return Ok(());
+        }
+        let scope = self.current_scope();
+        let shadows_local = scope
+            .locals
+            .iter()
+            .any(|local| local.name == name && local.depth < scope.scope_depth)
+            || self.scopes[..self.scopes.len() - 1]
+                .iter()
+                .any(|scope| scope.locals.iter().any(|local| local.name == name));
+        let shadows_global = self
+            .file_globals
+            .as_ref()
+            .is_some_and(|globals| globals.contains(name));
+        if shadows_local || shadows_global {
+            return Err(SaldError::syntax_error(
+                format!("Variable '{}' shadows an outer variable", name),
+                span,
+                &self.file,
+            )
+            .with_source(&self.source)
+            .with_help("Pick a different name (strict mode)"));
+        }
+        Ok(())
+    }
+
+    fn too_many_constants(&self, span: Span) -> SaldError {
+        SaldError::syntax_error(
+            format!(
+                "Too many constants in one chunk (limit is {})",
+                MAX_CONSTANTS
+            ),
+            span,
+            &self.file,
+        )
+        .with_source(&self.source)
+        .with_help("Split large functions or data literals into smaller pieces")
+    }
+
     fn current_scope(&self) -> &FunctionScope {
         self.scopes.last().unwrap()
     }
@@ -242,9 +458,20 @@ impl Compiler {
             Stmt::Continue { span } => {
                 self.compile_continue(*span)?;
             }
+            Stmt::Debugger { span } => {
+                self.compile_debugger(*span);
+            }
             Stmt::Import { path, alias, span } => {
+                self.check_import_allowed(*span)?;
                 self.compile_import(path, alias.as_deref(), *span)?;
             }
+            Stmt::Export { span, .. } => {
+                return Err(SaldError::syntax_error(
+                    "'export' outside of namespace",
+                    *span,
+                    &self.file,
+                ));
+            }
             Stmt::TryCatch {
                 try_body,
                 catch_var,
@@ -272,6 +499,11 @@ impl Compiler {
             Stmt::Interface { def } => {
                 self.compile_interface(def)?;
             }
+            Stmt::Cfg { .. } => {
+                if let Some(stmt) = stmt.active() {
+                    self.compile_stmt(stmt)?;
+                }
+            }
         }
         Ok(())
     }
@@ -511,39 +743,12 @@ impl Compiler {
             .loop_scope_depths
             .push(entry_scope_depth);
 
-        if let Some(idx_slot) = self.resolve_local("__idx") {
-            self.emit_op(OpCode::GetLocal, span);
-            self.emit_u16(idx_slot as u16, span);
-        }
-
-        if let Some(iter_slot) = self.resolve_local("__iter") {
-            self.emit_op(OpCode::GetLocal, span);
-            self.emit_u16(iter_slot as u16, span);
-
-            let length_const = self
-                .current_chunk()
-                .add_constant(Constant::String(intern(&"length".to_string())));
-            self.emit_op(OpCode::Invoke, span);
-            self.emit_u16(length_const as u16, span);
-            self.emit_u16(0, span);
-        }
-
-        self.emit_op(OpCode::Less, span);
-
-        let exit_jump = self.emit_jump(OpCode::JumpIfFalse, span);
-        self.emit_op(OpCode::Pop, span);
-
-        if let Some(iter_slot) = self.resolve_local("__iter") {
-            self.emit_op(OpCode::GetLocal, span);
-            self.emit_u16(iter_slot as u16, span);
-        }
-
-        if let Some(idx_slot) = self.resolve_local("__idx") {
-            self.emit_op(OpCode::GetLocal, span);
-            self.emit_u16(idx_slot as u16, span);
-        }
-
-        self.emit_op(OpCode::GetIndex, span);
+        // Each pass starts by taking the next item, so `continue` advances too
+        let iter_slot = self.resolve_local("__iter").unwrap_or_default();
+        self.emit_op(OpCode::ForIter, span);
+        self.emit_u16(iter_slot as u16, span);
+        let exit_jump = self.current_chunk().current_offset();
+        self.emit_u16(0xFFFF, span);
 
         if let Some(var_slot) = self.resolve_local(variable) {
             self.emit_op(OpCode::SetLocal, span);
@@ -553,24 +758,9 @@ impl Compiler {
 
         self.compile_stmt(body)?;
 
-        if let Some(idx_slot) = self.resolve_local("__idx") {
-            self.emit_op(OpCode::GetLocal, span);
-            self.emit_u16(idx_slot as u16, span);
-
-            let one_const = self.current_chunk().add_constant(Constant::Number(1.0));
-            self.emit_op(OpCode::Constant, span);
-            self.emit_u16(one_const as u16, span);
-            self.emit_op(OpCode::Add, span);
-
-            self.emit_op(OpCode::SetLocal, span);
-            self.emit_u16(idx_slot as u16, span);
-            self.emit_op(OpCode::Pop, span);
-        }
-
         self.emit_loop(loop_start, span);
 
         self.patch_jump(exit_jump);
-        self.emit_op(OpCode::Pop, span);
 
         self.current_scope_mut().loop_starts.pop();
         self.current_scope_mut().loop_scope_depths.pop();
@@ -589,11 +779,36 @@ impl Compiler {
     }
 
     fn compile_function(&mut self, def: &FunctionDef, as_method: bool) -> SaldResult<()> {
+        self.compile_function_with(def, as_method, false)
+    }
+
+    /// A `fun name(...) { }` expression leaves the closure on the stack
+    /// instead of declaring it. Its name refers to slot 0, which holds the
+    /// function being called.
+    fn compile_function_expression(&mut self, def: &FunctionDef) -> SaldResult<()> {
+        self.compile_function_with(def, false, true)
+    }
+
+    fn compile_function_with(
+        &mut self,
+        def: &FunctionDef,
+        as_method: bool,
+        as_expression: bool,
+    ) -> SaldResult<()> {
         let func_span = def.span;
 
+        // A nested function is a local, declared first so it can refer to
+        // itself; the closure below lands in its slot
+        if !as_method && !as_expression && self.current_scope().scope_depth > 0 {
+            self.declare_local(&def.name, func_span)?;
+            self.mark_initialized();
+        }
+
         self.scopes.push(FunctionScope::new(as_method));
 
-        if !as_method {
+        if as_expression {
+            self.current_scope_mut().locals[0].name = def.name.clone();
+        } else if !as_method {
             self.current_scope_mut().function_name = Some(def.name.clone());
         }
 
@@ -606,6 +821,10 @@ impl Compiler {
             self.declare_local(&param.name, param.span)?;
             self.mark_initialized();
         }
+        let (params, has_kwargs) = split_kwargs_param(&def.params);
+        if has_kwargs {
+            self.emit_op(OpCode::Kwargs, func_span);
+        }
 
         for param in def.params.iter() {
             if let Some(ref default_expr) = param.default_value {
@@ -644,18 +863,21 @@ impl Compiler {
         self.emit_op(OpCode::Return, func_span);
 
         let func_scope = self.scopes.pop().unwrap();
+        if func_scope.chunk.constants.len() > MAX_CONSTANTS {
+            return Err(self.too_many_constants(func_span));
+        }
 
         let arity = if as_method && !def.is_static {
-            if def.params.first().map(|p| p.name.as_str()) == Some("self") {
-                def.params.len().saturating_sub(1)
+            if params.first().map(|p| p.name.as_str()) == Some("self") {
+                params.len().saturating_sub(1)
             } else {
-                def.params.len()
+                params.len()
             }
         } else {
-            def.params.len()
+            params.len()
         };
 
-        let is_variadic = def.params.last().map(|p| p.is_variadic).unwrap_or(false);
+        let is_variadic = params.last().map(|p| p.is_variadic).unwrap_or(false);
 
         let upvalues: Vec<UpvalueInfo> = func_scope
             .upvalues
@@ -673,14 +895,11 @@ impl Compiler {
             is_async: def.is_async,
             upvalue_count: upvalues.len(),
             upvalues,
-            chunk: func_scope.chunk,
+            chunk: func_scope.chunk.into(),
             file: self.file.clone(),
-            param_names: def.params.iter().map(|p| p.name.clone()).collect(),
-            default_count: def
-                .params
-                .iter()
-                .filter(|p| p.default_value.is_some())
-                .count(),
+            has_kwargs,
+            param_names: params.iter().map(|p| p.name.clone()).collect(),
+            default_count: params.iter().filter(|p| p.default_value.is_some()).count(),
             decorators: def.decorators.iter().map(|d| d.name.clone()).collect(),
             namespace_context: self.current_namespace.clone(),
             class_context: self.current_class.clone(),
@@ -722,7 +941,7 @@ impl Compiler {
                 self.emit_u16(1, func_span);
             }
 
-            if self.current_scope().scope_depth == 0 {
+            if self.current_scope().scope_depth == 0 && !as_expression {
                 let name_const = self
                     .current_chunk()
                     .add_constant(Constant::String(intern(&def.name.clone())));
@@ -896,6 +1115,8 @@ impl Compiler {
             depth,
             initialized: true,
             is_captured: false,
+            is_const: false,
+            span,
         });
 
         self.compile_stmt(catch_body)?;
@@ -918,183 +1139,38 @@ impl Compiler {
     fn compile_const(&mut self, name: &str, value: &Expr, span: Span) -> SaldResult<()> {
         self.compile_expr(value)?;
 
-        let const_idx = self
-            .current_chunk()
-            .add_constant(Constant::String(intern(&name.to_string())));
-        self.emit_op(OpCode::DefineGlobal, span);
-        self.emit_u16(const_idx as u16, span);
+        if self.current_scope().scope_depth > 0 {
+            self.declare_local(name, span)?;
+            self.mark_initialized();
+            self.mark_const();
+        } else {
+            let const_idx = self
+                .current_chunk()
+                .add_constant(Constant::String(intern(name)));
+            self.emit_op(OpCode::DefineConstGlobal, span);
+            self.emit_u16(const_idx as u16, span);
+        }
 
         Ok(())
     }
 
     fn compile_namespace(&mut self, name: &str, body: &[Stmt], span: Span) -> SaldResult<()> {
-        let func_name = format!("<namespace {}>", name);
-
-        let previous_namespace = self.current_namespace.clone();
-        let full_namespace_name = match &previous_namespace {
-            Some(parent) => format!("{}.{}", parent, name),
-            None => name.to_string(),
-        };
-        self.current_namespace = Some(full_namespace_name.clone());
-
-        self.scopes.push(FunctionScope::new(false));
-        self.begin_scope();
-
-        let mut namespace_vars: Vec<(String, Span)> = Vec::new();
-
-        for stmt in body {
-            match stmt {
-                Stmt::Let {
-                    name: var_name,
-                    name_span: _,
-                    initializer,
-                    span: var_span,
-                } => {
-                    if let Some(init) = initializer {
-                        self.compile_expr(init)?;
-                    } else {
-                        self.emit_op(OpCode::Null, *var_span);
-                    }
-                    self.declare_local(var_name, *var_span)?;
-                    self.mark_initialized();
-                    namespace_vars.push((var_name.clone(), *var_span));
-                }
-                Stmt::Const {
-                    name: const_name,
-                    value,
-                    span: const_span,
-                } => {
-                    self.compile_expr(value)?;
-                    self.declare_local(const_name, *const_span)?;
-                    self.mark_initialized();
-                    namespace_vars.push((const_name.clone(), *const_span));
-                }
-                _ => {}
-            }
-        }
-
-        let mut member_count = 0;
-
-        for (var_name, var_span) in &namespace_vars {
-            let key_idx = self
-                .current_chunk()
-                .add_constant(Constant::String(intern(&var_name.clone())));
-            self.emit_op(OpCode::Constant, *var_span);
-            self.emit_u16(key_idx as u16, *var_span);
-
-            if let Some(slot) = self.resolve_local(var_name) {
-                self.emit_op(OpCode::GetLocal, *var_span);
-                self.emit_u16(slot as u16, *var_span);
-            }
-            member_count += 1;
-        }
-
-        for stmt in body {
-            match stmt {
-                Stmt::Function { def } => {
-                    let key_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&def.name.clone())));
-                    self.emit_op(OpCode::Constant, def.span);
-                    self.emit_u16(key_idx as u16, def.span);
-
-                    self.compile_namespace_function(def)?;
-                    member_count += 1;
-                }
-                Stmt::Class { def } => {
-                    let key_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&def.name.clone())));
-                    self.emit_op(OpCode::Constant, def.span);
-                    self.emit_u16(key_idx as u16, def.span);
-
-                    self.compile_namespace_class(def)?;
-                    member_count += 1;
-                }
-                Stmt::Namespace {
-                    name: ns_name,
-                    body: ns_body,
-                    span: ns_span,
-                } => {
-                    let key_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&ns_name.clone())));
-                    self.emit_op(OpCode::Constant, *ns_span);
-                    self.emit_u16(key_idx as u16, *ns_span);
-
-                    self.compile_namespace_inner(ns_name, ns_body, *ns_span)?;
-                    member_count += 1;
-                }
-                Stmt::Enum {
-                    name: enum_name,
-                    variants,
-                    span: enum_span,
-                } => {
-                    let key_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&enum_name.clone())));
-                    self.emit_op(OpCode::Constant, *enum_span);
-                    self.emit_u16(key_idx as u16, *enum_span);
-
-                    self.compile_enum_inner(enum_name, variants, *enum_span)?;
-                    member_count += 1;
-                }
-
-                Stmt::Let { .. } | Stmt::Const { .. } => {}
-                _ => {}
-            }
-        }
-
-        self.emit_op(OpCode::BuildNamespace, span);
-        self.emit_u16(member_count as u16, span);
-
-        self.emit_op(OpCode::Return, span);
-
-        let func_scope = self.scopes.pop().unwrap();
-
-        let upvalues: Vec<UpvalueInfo> = func_scope
-            .upvalues
-            .iter()
-            .map(|u| UpvalueInfo {
-                index: u.index as u8,
-                is_local: u.is_local,
-            })
-            .collect();
-
-        let func_const = Constant::Function(FunctionConstant {
-            name: func_name,
-            arity: 0,
-            is_variadic: false,
-            is_async: false,
-            upvalue_count: upvalues.len(),
-            upvalues,
-            chunk: func_scope.chunk,
-            file: self.file.clone(),
-            param_names: Vec::new(),
-            default_count: 0,
-            decorators: Vec::new(),
-            namespace_context: None,
-            class_context: None,
-        });
-
-        let const_idx = self.current_chunk().add_constant(func_const);
-        self.emit_op(OpCode::Closure, span);
-        self.emit_u16(const_idx as u16, span);
-
-        self.emit_op(OpCode::Call, span);
-        self.emit_u16(0, span);
+        self.compile_namespace_inner(name, body, span)?;
 
         let name_idx = self
             .current_chunk()
             .add_constant(Constant::String(intern(&name.to_string())));
-        self.emit_op(OpCode::DefineGlobal, span);
+        self.emit_op(OpCode::DefineNamespace, span);
         self.emit_u16(name_idx as u16, span);
 
-        self.current_namespace = previous_namespace;
-
         Ok(())
     }
 
+    /// Compiles a namespace body into a function that builds the namespace
+    /// object, and calls it. Every member gets a local slot before any body
+    /// is compiled, so members can refer to siblings declared after them.
+    /// `import ... as` aliases get a slot too but stay private unless named
+    /// by an `export`.
     fn compile_namespace_inner(&mut self, name: &str, body: &[Stmt], span: Span) -> SaldResult<()> {
         let func_name = format!("<namespace {}>", name);
 
@@ -1108,112 +1184,107 @@ impl Compiler {
         self.scopes.push(FunctionScope::new(false));
         self.begin_scope();
 
-        let mut namespace_vars: Vec<(String, Span)> = Vec::new();
-
-        for stmt in body {
-            match stmt {
-                Stmt::Let {
-                    name: var_name,
-                    name_span: _,
-                    initializer,
-                    span: var_span,
-                } => {
-                    if let Some(init) = initializer {
-                        self.compile_expr(init)?;
-                    } else {
-                        self.emit_op(OpCode::Null, *var_span);
+        let mut slots: Vec<&str> = Vec::new();
+        let mut members: Vec<(&str, Span)> = Vec::new();
+        for stmt in body.iter().filter_map(Stmt::active) {
+            if let Stmt::Export { names, span } = stmt {
+                for export in names {
+                    if !members.iter().any(|(seen, _)| seen == export) {
+                        members.push((export, *span));
                     }
-                    self.declare_local(var_name, *var_span)?;
-                    self.mark_initialized();
-                    namespace_vars.push((var_name.clone(), *var_span));
-                }
-                Stmt::Const {
-                    name: const_name,
-                    value,
-                    span: const_span,
-                } => {
-                    self.compile_expr(value)?;
-                    self.declare_local(const_name, *const_span)?;
-                    self.mark_initialized();
-                    namespace_vars.push((const_name.clone(), *const_span));
                 }
-                _ => {}
+                continue;
             }
-        }
-
-        let mut member_count = 0;
-
-        for (var_name, var_span) in &namespace_vars {
-            let key_idx = self
-                .current_chunk()
-                .add_constant(Constant::String(intern(&var_name.clone())));
-            self.emit_op(OpCode::Constant, *var_span);
-            self.emit_u16(key_idx as u16, *var_span);
-            if let Some(slot) = self.resolve_local(var_name) {
-                self.emit_op(OpCode::GetLocal, *var_span);
-                self.emit_u16(slot as u16, *var_span);
+            let Some((member_name, member_span)) = namespace_member(stmt) else {
+                continue;
+            };
+            if !slots.contains(&member_name) {
+                self.emit_op(OpCode::Null, member_span);
+                self.declare_local(member_name, member_span)?;
+                self.mark_initialized();
+                slots.push(member_name);
+            }
+            let exported = !matches!(stmt, Stmt::Import { .. });
+            if exported && !members.iter().any(|(seen, _)| *seen == member_name) {
+                members.push((member_name, member_span));
             }
-            member_count += 1;
         }
 
-        for stmt in body {
+        for stmt in hoisted_order(body).into_iter().filter_map(Stmt::active) {
+            if let Stmt::Import {
+                path,
+                alias: None,
+                span,
+            } = stmt
+            {
+                self.compile_import(path, None, *span)?;
+                continue;
+            }
+            let Some((member_name, member_span)) = namespace_member(stmt) else {
+                continue;
+            };
             match stmt {
-                Stmt::Function { def } => {
-                    let key_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&def.name.clone())));
-                    self.emit_op(OpCode::Constant, def.span);
-                    self.emit_u16(key_idx as u16, def.span);
-                    self.compile_namespace_function(def)?;
-                    member_count += 1;
-                }
-                Stmt::Class { def } => {
-                    let key_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&def.name.clone())));
-                    self.emit_op(OpCode::Constant, def.span);
-                    self.emit_u16(key_idx as u16, def.span);
-                    self.compile_namespace_class(def)?;
-                    member_count += 1;
-                }
+                Stmt::Let { initializer, .. } => match initializer {
+                    Some(init) => self.compile_expr(init)?,
+                    None => self.emit_op(OpCode::Null, member_span),
+                },
+                Stmt::Const { value, .. } => self.compile_expr(value)?,
+                Stmt::Function { def } => self.compile_namespace_function(def)?,
+                Stmt::Class { def } => self.compile_namespace_class(def)?,
                 Stmt::Namespace {
                     name: ns_name,
                     body: ns_body,
                     span: ns_span,
-                } => {
-                    let key_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&ns_name.clone())));
-                    self.emit_op(OpCode::Constant, *ns_span);
-                    self.emit_u16(key_idx as u16, *ns_span);
-                    self.compile_namespace_inner(ns_name, ns_body, *ns_span)?;
-                    member_count += 1;
-                }
+                } => self.compile_namespace_inner(ns_name, ns_body, *ns_span)?,
                 Stmt::Enum {
                     name: enum_name,
                     variants,
                     span: enum_span,
-                } => {
-                    let key_idx = self
+                } => self.compile_enum_inner(enum_name, variants, *enum_span)?,
+                Stmt::Import { path, .. } => {
+                    let path_const = self
+                        .current_chunk()
+                        .add_constant(Constant::String(intern(path)));
+                    let alias_const = self
                         .current_chunk()
-                        .add_constant(Constant::String(intern(&enum_name.clone())));
-                    self.emit_op(OpCode::Constant, *enum_span);
-                    self.emit_u16(key_idx as u16, *enum_span);
-                    self.compile_enum_inner(enum_name, variants, *enum_span)?;
-                    member_count += 1;
+                        .add_constant(Constant::String(intern(member_name)));
+                    self.emit_op(OpCode::ImportNamespace, member_span);
+                    self.emit_u16(path_const as u16, member_span);
+                    self.emit_u16(alias_const as u16, member_span);
                 }
-
-                Stmt::Let { .. } | Stmt::Const { .. } => {}
-                _ => {}
+                _ => continue,
             }
+            let slot = self
+                .resolve_local(member_name)
+                .expect("Namespace member should be declared as local");
+            self.emit_op(OpCode::SetLocal, member_span);
+            self.emit_u16(slot as u16, member_span);
+            self.emit_op(OpCode::Pop, member_span);
+            if let Stmt::Const { .. } = stmt {
+                self.current_scope_mut().locals[slot].is_const = true;
+            }
+        }
+
+        for (member_name, member_span) in &members {
+            let key_idx = self
+                .current_chunk()
+                .add_constant(Constant::String(intern(member_name)));
+            self.emit_op(OpCode::Constant, *member_span);
+            self.emit_u16(key_idx as u16, *member_span);
+
+            // An export can also name something from outside the namespace
+            self.compile_identifier(member_name, *member_span)?;
         }
 
         self.emit_op(OpCode::BuildNamespace, span);
-        self.emit_u16(member_count as u16, span);
+        self.emit_u16(members.len() as u16, span);
 
         self.emit_op(OpCode::Return, span);
 
         let func_scope = self.scopes.pop().unwrap();
+        if func_scope.chunk.constants.len() > MAX_CONSTANTS {
+            return Err(self.too_many_constants(span));
+        }
 
         let upvalues: Vec<UpvalueInfo> = func_scope
             .upvalues
@@ -1231,8 +1302,9 @@ impl Compiler {
             is_async: false,
             upvalue_count: upvalues.len(),
             upvalues,
-            chunk: func_scope.chunk,
+            chunk: func_scope.chunk.into(),
             file: self.file.clone(),
+            has_kwargs: false,
             param_names: Vec::new(),
             default_count: 0,
             decorators: Vec::new(),
@@ -1262,6 +1334,10 @@ impl Compiler {
             self.declare_local(&param.name, param.span)?;
             self.mark_initialized();
         }
+        let (params, has_kwargs) = split_kwargs_param(&def.params);
+        if has_kwargs {
+            self.emit_op(OpCode::Kwargs, func_span);
+        }
 
         for param in def.params.iter() {
             if let Some(ref default_expr) = param.default_value {
@@ -1300,8 +1376,11 @@ impl Compiler {
         self.emit_op(OpCode::Return, func_span);
 
         let func_scope = self.scopes.pop().unwrap();
-        let arity = def.params.len();
-        let is_variadic = def.params.last().map(|p| p.is_variadic).unwrap_or(false);
+        if func_scope.chunk.constants.len() > MAX_CONSTANTS {
+            return Err(self.too_many_constants(func_span));
+        }
+        let arity = params.len();
+        let is_variadic = params.last().map(|p| p.is_variadic).unwrap_or(false);
 
         let upvalues: Vec<UpvalueInfo> = func_scope
             .upvalues
@@ -1319,14 +1398,11 @@ impl Compiler {
             is_async: def.is_async,
             upvalue_count: upvalues.len(),
             upvalues,
-            chunk: func_scope.chunk,
+            chunk: func_scope.chunk.into(),
             file: self.file.clone(),
-            param_names: def.params.iter().map(|p| p.name.clone()).collect(),
-            default_count: def
-                .params
-                .iter()
-                .filter(|p| p.default_value.is_some())
-                .count(),
+            has_kwargs,
+            param_names: params.iter().map(|p| p.name.clone()).collect(),
+            default_count: params.iter().filter(|p| p.default_value.is_some()).count(),
             decorators: def.decorators.iter().map(|d| d.name.clone()).collect(),
             namespace_context: self.current_namespace.clone(),
             class_context: self.current_class.clone(),
@@ -1353,11 +1429,8 @@ impl Compiler {
         self.emit_u16(name_const as u16, class_span);
 
         if let Some(superclass) = &def.superclass {
-            let super_const = self
-                .current_chunk()
-                .add_constant(Constant::String(intern(&superclass.clone())));
-            self.emit_op(OpCode::GetGlobal, class_span);
-            self.emit_u16(super_const as u16, class_span);
+            // A sibling class lives in a local slot of the namespace
+            self.compile_identifier(superclass, class_span)?;
             self.emit_op(OpCode::Inherit, class_span);
             self.emit_u16(0, class_span);
         }
@@ -1445,51 +1518,34 @@ impl Compiler {
             Expr::Call {
                 callee,
                 args,
-                is_optional: _,
+                is_optional,
                 span,
             } => {
-                if let Expr::Get {
+                let starts_chain = self.begin_chain();
+                if *is_optional {
+                    self.compile_chain_object(callee)?;
+                    self.emit_chain_skip(*span);
+                    let arg_count = self.compile_call_args(args, *span)?;
+                    self.emit_op(OpCode::Call, *span);
+                    self.emit_u16(arg_count, *span);
+                } else if let Expr::Get {
                     object,
                     property,
                     is_optional,
                     ..
                 } = callee.as_ref()
                 {
-                    self.compile_expr(object)?;
-
+                    self.compile_chain_object(object)?;
                     if *is_optional {
-                        self.emit_op(OpCode::Dup, *span);
-                        let normal_jump = self.emit_jump(OpCode::JumpIfNotNull, *span);
-
-                        self.emit_op(OpCode::Pop, *span);
-                        let end_jump = self.emit_jump(OpCode::Jump, *span);
-
-                        self.patch_jump(normal_jump);
-                        self.emit_op(OpCode::Pop, *span);
-
-                        self.compile_expr(object)?;
-
-                        for arg in args {
-                            self.compile_expr(&arg.value)?;
-                        }
-                        let const_idx = self
-                            .current_chunk()
-                            .add_constant(Constant::String(intern(&property.clone())));
-                        self.emit_op(OpCode::Invoke, *span);
-                        self.emit_u16(const_idx as u16, *span);
-                        self.emit_u16(args.len() as u16, *span);
-                        self.patch_jump(end_jump);
-                    } else {
-                        for arg in args {
-                            self.compile_expr(&arg.value)?;
-                        }
-                        let const_idx = self
-                            .current_chunk()
-                            .add_constant(Constant::String(intern(&property.clone())));
-                        self.emit_op(OpCode::Invoke, *span);
-                        self.emit_u16(const_idx as u16, *span);
-                        self.emit_u16(args.len() as u16, *span);
+                        self.emit_chain_skip(*span);
                     }
+                    let arg_count = self.compile_call_args(args, *span)?;
+                    let const_idx = self
+                        .current_chunk()
+                        .add_constant(Constant::String(intern(&property.clone())));
+                    self.emit_op(OpCode::Invoke, *span);
+                    self.emit_u16(const_idx as u16, *span);
+                    self.emit_u16(arg_count, *span);
                 } else if let Expr::Identifier { name, .. } = callee.as_ref() {
                     let is_self_recursive = self
                         .current_scope()
@@ -1498,7 +1554,11 @@ impl Compiler {
                         .map(|fn_name| fn_name == name)
                         .unwrap_or(false);
 
-                    if is_self_recursive {
+                    // Named arguments are bound by the regular call path
+                    let all_positional = args
+                        .iter()
+                        .all(|arg| arg.name.is_none() && !arg.is_dict_spread);
+                    if is_self_recursive && all_positional {
                         self.emit_op(OpCode::Null, *span);
                         for arg in args {
                             self.compile_expr(&arg.value)?;
@@ -1507,20 +1567,17 @@ impl Compiler {
                         self.emit_u16(args.len() as u16, *span);
                     } else {
                         self.compile_expr(callee)?;
-                        for arg in args {
-                            self.compile_expr(&arg.value)?;
-                        }
+                        let arg_count = self.compile_call_args(args, *span)?;
                         self.emit_op(OpCode::Call, *span);
-                        self.emit_u16(args.len() as u16, *span);
+                        self.emit_u16(arg_count, *span);
                     }
                 } else {
-                    self.compile_expr(callee)?;
-                    for arg in args {
-                        self.compile_expr(&arg.value)?;
-                    }
+                    self.compile_chain_object(callee)?;
+                    let arg_count = self.compile_call_args(args, *span)?;
                     self.emit_op(OpCode::Call, *span);
-                    self.emit_u16(args.len() as u16, *span);
+                    self.emit_u16(arg_count, *span);
                 }
+                self.end_chain(starts_chain);
             }
             Expr::Get {
                 object,
@@ -1528,34 +1585,17 @@ impl Compiler {
                 is_optional,
                 span,
             } => {
-                self.compile_expr(object)?;
-
+                let starts_chain = self.begin_chain();
+                self.compile_chain_object(object)?;
                 if *is_optional {
-                    self.emit_op(OpCode::Dup, *span);
-
-                    let normal_jump = self.emit_jump(OpCode::JumpIfNotNull, *span);
-
-                    self.emit_op(OpCode::Pop, *span);
-
-                    let end_jump = self.emit_jump(OpCode::Jump, *span);
-
-                    self.patch_jump(normal_jump);
-                    self.emit_op(OpCode::Pop, *span);
-
-                    let const_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&property.clone())));
-                    self.emit_op(OpCode::GetProperty, *span);
-                    self.emit_u16(const_idx as u16, *span);
-
-                    self.patch_jump(end_jump);
-                } else {
-                    let const_idx = self
-                        .current_chunk()
-                        .add_constant(Constant::String(intern(&property.clone())));
-                    self.emit_op(OpCode::GetProperty, *span);
-                    self.emit_u16(const_idx as u16, *span);
+                    self.emit_chain_skip(*span);
                 }
+                let const_idx = self
+                    .current_chunk()
+                    .add_constant(Constant::String(intern(&property.clone())));
+                self.emit_op(OpCode::GetProperty, *span);
+                self.emit_u16(const_idx as u16, *span);
+                self.end_chain(starts_chain);
             }
             Expr::Set {
                 object,
@@ -1588,12 +1628,17 @@ impl Compiler {
             Expr::Index {
                 object,
                 index,
-                is_optional: _,
+                is_optional,
                 span,
             } => {
-                self.compile_expr(object)?;
+                let starts_chain = self.begin_chain();
+                self.compile_chain_object(object)?;
+                if *is_optional {
+                    self.emit_chain_skip(*span);
+                }
                 self.compile_expr(index)?;
                 self.emit_op(OpCode::GetIndex, *span);
+                self.end_chain(starts_chain);
             }
             Expr::IndexSet {
                 object,
@@ -1638,6 +1683,9 @@ impl Compiler {
                 expr,
                 span,
             } => {
+                if let Some(def) = block_function(statements, expr.as_deref()) {
+                    return self.compile_function_expression(def);
+                }
                 for stmt in statements {
                     self.compile_stmt(stmt)?;
                 }
@@ -1662,6 +1710,11 @@ impl Compiler {
 
                 self.emit_op(OpCode::Await, *span);
             }
+            Expr::Import { path, span } => {
+                self.check_import_allowed(*span)?;
+                self.compile_expr(path)?;
+                self.emit_op(OpCode::ImportDynamic, *span);
+            }
             Expr::Return { value, span } => {
                 if let Some(v) = value {
                     self.compile_expr(v)?;
@@ -2025,6 +2078,8 @@ impl Compiler {
             depth: scope.scope_depth,
             initialized: true,
             is_captured: false,
+            is_const: false,
+            span: Span::default(),
         });
         slot
     }
@@ -2049,6 +2104,25 @@ impl Compiler {
             Literal::Null => {
                 self.emit_op(OpCode::Null, span);
             }
+            Literal::Date(text) => {
+                let seconds = parse_date_literal(text).map_err(|e| {
+                    SaldError::syntax_error(&e, span, &self.file).with_help(
+                        "Date literals look like d\"2024-06-01\" or d\"2024-06-01 12:30:00\"",
+                    )
+                })?;
+                let const_idx = self.current_chunk().add_constant(Constant::Date(seconds));
+                self.emit_op(OpCode::Constant, span);
+                self.emit_u16(const_idx as u16, span);
+            }
+            Literal::Time(text) => {
+                let seconds = parse_time_literal(text).map_err(|e| {
+                    SaldError::syntax_error(&e, span, &self.file)
+                        .with_help("Time literals look like t\"12:30\" or t\"12:30:00\"")
+                })?;
+                let const_idx = self.current_chunk().add_constant(Constant::Time(seconds));
+                self.emit_op(OpCode::Constant, span);
+                self.emit_u16(const_idx as u16, span);
+            }
         }
         Ok(())
     }
@@ -2061,6 +2135,7 @@ impl Compiler {
             self.emit_op(OpCode::GetUpvalue, span);
             self.emit_u16(upvalue as u16, span);
         } else {
+            self.check_strict_global(name, false, span)?;
             let const_idx = self
                 .current_chunk()
                 .add_constant(Constant::String(intern(&name.to_string())));
@@ -2138,6 +2213,8 @@ impl Compiler {
             BinaryOp::Mul => self.emit_op(OpCode::Mul, span),
             BinaryOp::Div => self.emit_op(OpCode::Div, span),
             BinaryOp::Mod => self.emit_op(OpCode::Mod, span),
+            BinaryOp::Equal if self.strict => self.emit_op(OpCode::StrictEqual, span),
+            BinaryOp::NotEqual if self.strict => self.emit_op(OpCode::StrictNotEqual, span),
             BinaryOp::Equal => self.emit_op(OpCode::Equal, span),
             BinaryOp::NotEqual => self.emit_op(OpCode::NotEqual, span),
             BinaryOp::Less => self.emit_op(OpCode::Less, span),
@@ -2196,22 +2273,31 @@ impl Compiler {
         value: &Expr,
         span: Span,
     ) -> SaldResult<()> {
+        if matches!(op, AssignOp::NullCoalesceAssign) {
+            return self.compile_coalesce_assignment(target, value, span);
+        }
         match target {
             Expr::Identifier { name, .. } => {
                 if op.is_compound() {
                     self.compile_identifier(name, span)?;
                 }
 
+                if self.is_const(name) {
+                    return Err(SaldError::syntax_error(
+                        format!("Cannot assign to constant '{}'", name),
+                        span,
+                        &self.file,
+                    )
+                    .with_source(&self.source)
+                    .with_help(format!(
+                        "Declare '{}' with 'let' if it needs to change",
+                        name
+                    )));
+                }
+
                 self.compile_expr(value)?;
 
-                match op {
-                    AssignOp::AddAssign => self.emit_op(OpCode::Add, span),
-                    AssignOp::SubAssign => self.emit_op(OpCode::Sub, span),
-                    AssignOp::MulAssign => self.emit_op(OpCode::Mul, span),
-                    AssignOp::DivAssign => self.emit_op(OpCode::Div, span),
-                    AssignOp::ModAssign => self.emit_op(OpCode::Mod, span),
-                    AssignOp::Assign => {}
-                }
+                self.emit_compound_op(op, span);
 
                 if let Some(slot) = self.resolve_local(name) {
                     self.emit_op(OpCode::SetLocal, span);
@@ -2220,6 +2306,7 @@ impl Compiler {
                     self.emit_op(OpCode::SetUpvalue, span);
                     self.emit_u16(upvalue as u16, span);
                 } else {
+                    self.check_strict_global(name, true, span)?;
                     let const_idx = self
                         .current_chunk()
                         .add_constant(Constant::String(intern(&name.clone())));
@@ -2243,14 +2330,7 @@ impl Compiler {
 
                 self.compile_expr(value)?;
 
-                match op {
-                    AssignOp::AddAssign => self.emit_op(OpCode::Add, span),
-                    AssignOp::SubAssign => self.emit_op(OpCode::Sub, span),
-                    AssignOp::MulAssign => self.emit_op(OpCode::Mul, span),
-                    AssignOp::DivAssign => self.emit_op(OpCode::Div, span),
-                    AssignOp::ModAssign => self.emit_op(OpCode::Mod, span),
-                    AssignOp::Assign => {}
-                }
+                self.emit_compound_op(op, span);
 
                 let const_idx = self
                     .current_chunk()
@@ -2269,14 +2349,7 @@ impl Compiler {
 
                 self.compile_expr(value)?;
 
-                match op {
-                    AssignOp::AddAssign => self.emit_op(OpCode::Add, span),
-                    AssignOp::SubAssign => self.emit_op(OpCode::Sub, span),
-                    AssignOp::MulAssign => self.emit_op(OpCode::Mul, span),
-                    AssignOp::DivAssign => self.emit_op(OpCode::Div, span),
-                    AssignOp::ModAssign => self.emit_op(OpCode::Mod, span),
-                    AssignOp::Assign => {}
-                }
+                self.emit_compound_op(op, span);
 
                 self.emit_op(OpCode::SetIndex, span);
             }
@@ -2291,6 +2364,85 @@ impl Compiler {
         Ok(())
     }
 
+    fn emit_compound_op(&mut self, op: &AssignOp, span: Span) {
+        let opcode = match op {
+            AssignOp::AddAssign => OpCode::Add,
+            AssignOp::SubAssign => OpCode::Sub,
+            AssignOp::MulAssign => OpCode::Mul,
+            AssignOp::DivAssign => OpCode::Div,
+            AssignOp::ModAssign => OpCode::Mod,
+            AssignOp::Assign | AssignOp::NullCoalesceAssign => return,
+        };
+        self.emit_op(opcode, span);
+    }
+
+    /// `target ??= value`: assigns only when the target is null, and is the
+    /// target's value otherwise. The target's object and index are evaluated
+    /// once.
+    fn compile_coalesce_assignment(
+        &mut self,
+        target: &Expr,
+        value: &Expr,
+        span: Span,
+    ) -> SaldResult<()> {
+        match target {
+            Expr::Identifier { name, .. } => {
+                self.compile_identifier(name, span)?;
+                let end_jump = self.emit_jump(OpCode::JumpIfNotNull, span);
+                self.emit_op(OpCode::Pop, span);
+                self.compile_assignment(target, &AssignOp::Assign, value, span)?;
+                self.patch_jump(end_jump);
+            }
+            Expr::Get {
+                object, property, ..
+            } => {
+                let const_idx = self
+                    .current_chunk()
+                    .add_constant(Constant::String(intern(property)));
+                self.compile_expr(object)?;
+                self.emit_op(OpCode::Dup, span);
+                self.emit_op(OpCode::GetPropertyOrNull, span);
+                self.emit_u16(const_idx as u16, span);
+                let present_jump = self.emit_jump(OpCode::JumpIfNotNull, span);
+                self.emit_op(OpCode::Pop, span);
+                self.compile_expr(value)?;
+                self.emit_op(OpCode::SetProperty, span);
+                self.emit_u16(const_idx as u16, span);
+                let end_jump = self.emit_jump(OpCode::Jump, span);
+                // Drop the object from under the present value
+                self.patch_jump(present_jump);
+                self.emit_op(OpCode::Swap, span);
+                self.emit_op(OpCode::Pop, span);
+                self.patch_jump(end_jump);
+            }
+            Expr::Index { object, index, .. } => {
+                self.compile_expr(object)?;
+                self.compile_expr(index)?;
+                self.emit_op(OpCode::DupTwo, span);
+                self.emit_op(OpCode::GetIndex, span);
+                let present_jump = self.emit_jump(OpCode::JumpIfNotNull, span);
+                self.emit_op(OpCode::Pop, span);
+                self.compile_expr(value)?;
+                self.emit_op(OpCode::SetIndex, span);
+                let end_jump = self.emit_jump(OpCode::Jump, span);
+                // Drop the object and index from under the present value
+                self.patch_jump(present_jump);
+                for _ in 0..2 {
+                    self.emit_op(OpCode::Swap, span);
+                    self.emit_op(OpCode::Pop, span);
+                }
+                self.patch_jump(end_jump);
+            }
+            _ => {
+                return Err(
+                    SaldError::syntax_error("Invalid assignment target", span, &self.file)
+                        .with_source(&self.source),
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn compile_set(
         &mut self,
         object: &Expr,
@@ -2367,14 +2519,27 @@ impl Compiler {
                 break;
             }
             if local.name == name {
+                let mut help = format!(
+                    "Use '{} = ...' to change it, or declare the new variable in a nested \
+                     block to shadow it",
+                    name
+                );
+                if local.span != Span::default() {
+                    help = format!(
+                        "'{}' was declared on line {}. {}",
+                        name, local.span.start.line, help
+                    );
+                }
                 return Err(SaldError::syntax_error(
                     &format!("Variable '{}' already declared in this scope", name),
                     span,
                     &self.file,
                 )
-                .with_source(&self.source));
+                .with_source(&self.source)
+                .with_help(help));
             }
         }
+        self.check_strict_shadowing(name, span)?;
 
         let depth = self.current_scope().scope_depth;
         self.current_scope_mut().locals.push(Local {
@@ -2382,6 +2547,8 @@ impl Compiler {
             depth,
             initialized: false,
             is_captured: false,
+            is_const: false,
+            span,
         });
 
         Ok(())
@@ -2393,6 +2560,23 @@ impl Compiler {
         }
     }
 
+    fn mark_const(&mut self) {
+        if let Some(local) = self.current_scope_mut().locals.last_mut() {
+            local.is_const = true;
+        }
+    }
+
+    /// Whether `name` resolves to a constant, looking through locals and
+    /// enclosing functions the way variable lookup does before falling back
+    /// to the file's globals.
+    fn is_const(&self, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.locals.iter().rev().find(|local| local.name == name))
+            .map_or_else(|| self.const_globals.contains(name), |local| local.is_const)
+    }
+
     fn resolve_local(&self, name: &str) -> Option<usize> {
         let scope = self.current_scope();
         for (i, local) in scope.locals.iter().enumerate().rev() {
@@ -2481,6 +2665,21 @@ impl Compiler {
         Ok(())
     }
 
+    fn compile_debugger(&mut self, span: Span) {
+        let names: Vec<&str> = self
+            .current_scope()
+            .locals
+            .iter()
+            .map(|local| local.name.as_str())
+            .collect();
+        let names = names.join(",");
+        let names_const = self
+            .current_chunk()
+            .add_constant(Constant::String(intern(&names)));
+        self.emit_op(OpCode::Debugger, span);
+        self.emit_u16(names_const as u16, span);
+    }
+
     fn compile_continue(&mut self, span: Span) -> SaldResult<()> {
         let (loop_start, target_depth) = {
             let scope = self.current_scope();
@@ -2515,6 +2714,17 @@ impl Compiler {
         Ok(())
     }
 
+    fn check_import_allowed(&self, span: Span) -> SaldResult<()> {
+        if self.deny_imports {
+            return Err(SaldError::syntax_error(
+                "Imports are not allowed here; pass modules in through globals",
+                span,
+                &self.file,
+            ));
+        }
+        Ok(())
+    }
+
     fn compile_import(&mut self, path: &str, alias: Option<&str>, span: Span) -> SaldResult<()> {
         let path_const = self
             .current_chunk()
@@ -2578,6 +2788,10 @@ impl Compiler {
             self.declare_local(&param.name, param.span)?;
             self.mark_initialized();
         }
+        let (params, has_kwargs) = split_kwargs_param(params);
+        if has_kwargs {
+            self.emit_op(OpCode::Kwargs, span);
+        }
 
         match body {
             LambdaBody::Block(stmts) => {
@@ -2597,6 +2811,9 @@ impl Compiler {
         self.end_scope();
 
         let func_scope = self.scopes.pop().unwrap();
+        if func_scope.chunk.constants.len() > MAX_CONSTANTS {
+            return Err(self.too_many_constants(span));
+        }
         let arity = params.len();
 
         let is_variadic = params.last().map(|p| p.is_variadic).unwrap_or(false);
@@ -2617,8 +2834,9 @@ impl Compiler {
             is_async,
             upvalue_count: upvalues.len(),
             upvalues,
-            chunk: func_scope.chunk,
+            chunk: func_scope.chunk.into(),
             file: self.file.clone(),
+            has_kwargs,
             param_names: params.iter().map(|p| p.name.clone()).collect(),
             default_count: params.iter().filter(|p| p.default_value.is_some()).count(),
             decorators: Vec::new(),
@@ -2672,6 +2890,76 @@ impl Compiler {
         self.current_chunk().patch_jump(offset);
     }
 
+    /// Starts a chain of property, index and call links, unless this link is
+    /// the object of an enclosing one. Returns whether it started one.
+    fn begin_chain(&mut self) -> bool {
+        if std::mem::take(&mut self.continue_chain) {
+            return false;
+        }
+        self.optional_chains.push(Vec::new());
+        true
+    }
+
+    /// Compiles the object of a chain link, continuing the chain through it
+    /// when it is a link itself, so `a?.b.c` is null as a whole if `a` is
+    fn compile_chain_object(&mut self, object: &Expr) -> SaldResult<()> {
+        self.continue_chain = matches!(
+            object,
+            Expr::Get { .. } | Expr::Index { .. } | Expr::Call { .. }
+        );
+        self.compile_expr(object)
+    }
+
+    /// With the object of a `?.`, `?[` or `?(` on the stack, jumps to the end
+    /// of the chain when it is null, leaving null as the result
+    fn emit_chain_skip(&mut self, span: Span) {
+        self.emit_op(OpCode::Dup, span);
+        let normal_jump = self.emit_jump(OpCode::JumpIfNotNull, span);
+        self.emit_op(OpCode::Pop, span);
+        let end_jump = self.emit_jump(OpCode::Jump, span);
+        self.patch_jump(normal_jump);
+        self.emit_op(OpCode::Pop, span);
+        if let Some(chain) = self.optional_chains.last_mut() {
+            chain.push(end_jump);
+        }
+    }
+
+    fn end_chain(&mut self, started: bool) {
+        if started {
+            for jump in self.optional_chains.pop().unwrap_or_default() {
+                self.patch_jump(jump);
+            }
+        }
+    }
+
+    /// Compiles call arguments and returns the argument count for the call.
+    /// Named arguments go into one dictionary passed as the last argument,
+    /// with `**dict` arguments merged in like `{**dict}` entries.
+    fn compile_call_args(&mut self, args: &[CallArg], span: Span) -> SaldResult<u16> {
+        let mut named = 0;
+        for arg in args {
+            if let Some(name) = &arg.name {
+                let const_idx = self
+                    .current_chunk()
+                    .add_constant(Constant::String(intern(name)));
+                self.emit_op(OpCode::Constant, arg.span);
+                self.emit_u16(const_idx as u16, arg.span);
+                named += 1;
+            } else if arg.is_dict_spread {
+                self.emit_op(OpCode::Null, arg.span);
+                named += 1;
+            }
+            self.compile_expr(&arg.value)?;
+        }
+        if named == 0 {
+            return Ok(args.len() as u16);
+        }
+        self.emit_op(OpCode::BuildDict, span);
+        self.emit_u16(named as u16, span);
+        self.emit_op(OpCode::NamedArgs, span);
+        Ok((args.len() - named + 1) as u16)
+    }
+
     fn emit_loop(&mut self, loop_start: usize, span: Span) {
         self.emit_op(OpCode::Loop, span);
         let offset = self.current_chunk().current_offset() - loop_start + 2;
@@ -2711,6 +2999,17 @@ impl Compiler {
                 Some(FoldedValue::String(format!("{}{}", a, b)))
             }
 
+            // Small repeats only, so constants stay small; the VM reports bad counts
+            (FoldedValue::String(s), FoldedValue::Number(n))
+            | (FoldedValue::Number(n), FoldedValue::String(s))
+                if matches!(op, BinaryOp::Mul)
+                    && n >= 0.0
+                    && n.fract() == 0.0
+                    && s.len() as f64 * n <= FOLD_REPEAT_LIMIT as f64 =>
+            {
+                Some(FoldedValue::String(s.repeat(n as usize)))
+            }
+
             (FoldedValue::Boolean(a), FoldedValue::Boolean(b)) => match op {
                 BinaryOp::Equal => Some(FoldedValue::Boolean(a == b)),
                 BinaryOp::NotEqual => Some(FoldedValue::Boolean(a != b)),
@@ -2745,7 +3044,7 @@ impl Compiler {
                 Literal::Number(n) => Some(FoldedValue::Number(*n)),
                 Literal::Boolean(b) => Some(FoldedValue::Boolean(*b)),
                 Literal::String(s) => Some(FoldedValue::String(s.clone())),
-                Literal::Null => None,
+                Literal::Null | Literal::Date(_) | Literal::Time(_) => None,
             },
             Expr::Grouping { expr, .. } => self.extract_literal(expr),
             Expr::Unary { op, operand, .. } => self.try_fold_unary(op, operand),
@@ -2756,3 +3055,345 @@ impl Compiler {
         }
     }
 }
+
+/// The parameters that take arguments, without a trailing `**` parameter.
+/// That one is an extra local after them, not counted in the arity.
+fn split_kwargs_param(params: &[FunctionParam]) -> (&[FunctionParam], bool) {
+    match params.split_last() {
+        Some((last, rest)) if last.is_kwargs => (rest, true),
+        _ => (params, false),
+    }
+}
+
+/// Name and span of the namespace member `stmt` declares, if any
+fn namespace_member(stmt: &Stmt) -> Option<(&str, Span)> {
+    match stmt {
+        Stmt::Let { name, span, .. } | Stmt::Const { name, span, .. } => Some((name, *span)),
+        Stmt::Function { def } => Some((&def.name, def.span)),
+        Stmt::Class { def } => Some((&def.name, def.span)),
+        Stmt::Namespace { name, span, .. } | Stmt::Enum { name, span, .. } => Some((name, *span)),
+        Stmt::Import {
+            alias: Some(alias),
+            span,
+            ..
+        } => Some((alias, *span)),
+        _ => None,
+    }
+}
+
+/// The top-level statements in the order they are compiled: interfaces,
+/// functions and classes first, so code can use them above their
+/// declaration, then everything else in source order.
+///
+/// Declarations that evaluate something when defined stay in place:
+/// decorated functions and classes, and classes extending anything but a
+/// hoisted class.
+fn hoisted_order(statements: &[Stmt]) -> Vec<&Stmt> {
+    let mut hoisted = vec![false; statements.len()];
+    let mut order = Vec::new();
+    for (i, stmt) in statements.iter().enumerate() {
+        if let Some(Stmt::Interface { .. }) = stmt.active() {
+            hoisted[i] = true;
+            order.push(stmt);
+        }
+    }
+    for (i, stmt) in statements.iter().enumerate() {
+        if let Some(Stmt::Function { def }) = stmt.active() {
+            if def.decorators.is_empty() {
+                hoisted[i] = true;
+                order.push(stmt);
+            }
+        }
+    }
+    // Classes after the classes they extend
+    let mut classes: FxHashSet<&str> = FxHashSet::default();
+    loop {
+        let before = order.len();
+        for (i, stmt) in statements.iter().enumerate() {
+            let Some(Stmt::Class { def }) = stmt.active() else {
+                continue;
+            };
+            let base_ready = def
+                .superclass
+                .as_deref()
+                .is_none_or(|base| classes.contains(base));
+            if !hoisted[i] && def.decorators.is_empty() && base_ready {
+                hoisted[i] = true;
+                classes.insert(&def.name);
+                order.push(stmt);
+            }
+        }
+        if order.len() == before {
+            break;
+        }
+    }
+    order.extend(
+        statements
+            .iter()
+            .zip(&hoisted)
+            .filter(|(_, hoisted)| !**hoisted)
+            .map(|(stmt, _)| stmt),
+    );
+    order
+}
+
+/// Top-level names a statement declares
+fn declared_names(stmt: &Stmt) -> Vec<String> {
+    match stmt {
+        Stmt::Let { name, .. } if !name.starts_with("self.") => vec![name.clone()],
+        Stmt::LetDestructure { pattern, .. } => pattern
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                ArrayPatternElement::Variable { name, .. }
+                | ArrayPatternElement::Rest { name, .. } => Some(name.clone()),
+                ArrayPatternElement::Hole => None,
+            })
+            .collect(),
+        Stmt::Const { name, .. } | Stmt::Namespace { name, .. } | Stmt::Enum { name, .. } => {
+            vec![name.clone()]
+        }
+        Stmt::Function { def } => vec![def.name.clone()],
+        Stmt::Class { def } => vec![def.name.clone()],
+        Stmt::Interface { def } => vec![def.name.clone()],
+        Stmt::Import {
+            alias: Some(alias), ..
+        } => vec![alias.clone()],
+        Stmt::Cfg { .. } => stmt.active().map(declared_names).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{Engine, EngineError};
+
+    #[test]
+    fn test_compiler_check_reports_every_error() {
+        use crate::compiler::Compiler;
+        use crate::lexer::Scanner;
+        use crate::parser::Parser;
+
+        let source = "fun f() {\n    let a = 1\n    let a = 2\n}\nbreak\nfun g() { return 1 }\n";
+        let tokens = Scanner::new(source, "check.sald").scan_tokens().unwrap();
+        let program = Parser::new(tokens, "check.sald", source).parse().unwrap();
+        let errors = Compiler::new("check.sald", source).check(&program);
+
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Variable 'a' already declared in this scope",
+                "'break' outside of loop"
+            ]
+        );
+        assert_eq!(errors[1].span.start.line, 5);
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let mut engine = Engine::new();
+        let strict = |body: &str| format!("\"use strict\"\n{}", body);
+        let compile_error = |engine: &mut Engine, body: &str| {
+            engine
+                .eval(&strict(body))
+                .unwrap_err()
+                .message()
+                .to_string()
+        };
+
+        assert_eq!(
+            compile_error(&mut engine, "undeclared = 1"),
+            "Assignment to undeclared variable 'undeclared'"
+        );
+        assert_eq!(
+            compile_error(&mut engine, "let early = later\nlet later = 1"),
+            "Variable 'later' is used before its declaration"
+        );
+        assert_eq!(
+            compile_error(&mut engine, "let top = 1\nfun f(top) { return top }"),
+            "Variable 'top' shadows an outer variable"
+        );
+        let err = engine
+            .eval(&strict("let n = 1\nlet same = n == \"1\""))
+            .unwrap_err();
+        assert!(err
+            .message()
+            .contains("Cannot compare 'Number' with 'String'"));
+
+        engine
+            .eval(&strict(
+                "fun get() { return value }\nlet value = 2\nlet ok = get() == 2 && value != null",
+            ))
+            .unwrap();
+        assert!(engine.eval_as::<bool>("ok").unwrap());
+        assert!(!engine.eval_as::<bool>("1 == \"1\"").unwrap());
+    }
+
+    #[test]
+    fn test_const_immutability() {
+        let mut engine = Engine::new();
+        let err = engine.eval("const LIMIT = 1\nLIMIT = 2").unwrap_err();
+        assert_eq!(err.message(), "Cannot assign to constant 'LIMIT'");
+        let err = engine
+            .eval("fun f() {\n    const step = 1\n    let bump = || { step += 1 }\n}")
+            .unwrap_err();
+        assert_eq!(err.message(), "Cannot assign to constant 'step'");
+
+        engine
+            .eval("const MAX = 10\nfun local() { const inner = MAX * 2\n return inner }")
+            .unwrap();
+        assert_eq!(engine.eval_as::<i64>("local()").unwrap(), 20);
+        assert!(engine.eval("inner").is_err());
+
+        // Separate evaluations only see the constant at runtime
+        let err = engine.eval("MAX = 11").unwrap_err();
+        assert_eq!(err.message(), "Cannot assign to constant 'MAX'");
+        let err = engine.eval("let MAX = 12").unwrap_err();
+        assert_eq!(err.message(), "Cannot redeclare constant 'MAX'");
+        assert_eq!(engine.eval_as::<i64>("MAX").unwrap(), 10);
+    }
+
+    #[test]
+    fn test_redeclaration_and_shadowing() {
+        let mut engine = Engine::new();
+        let err = engine
+            .eval("fun f() {\n    let total = 1\n    let total = 2\n}")
+            .unwrap_err();
+        let EngineError::Script(err) = err else {
+            panic!("expected a script error");
+        };
+        assert_eq!(
+            err.message,
+            "Variable 'total' already declared in this scope"
+        );
+        assert!(err.help.as_deref().is_some_and(
+            |help| help.starts_with("'total' was declared on line 2. Use 'total = ...'")
+        ));
+
+        // Shadowing in a nested block is allowed outside strict mode
+        engine
+            .eval("fun g() {\n    let n = 1\n    if (true) { let n = 2 }\n    return n\n}")
+            .unwrap();
+        assert_eq!(engine.eval_as::<i64>("g()").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_hoisting_and_function_expressions() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                r#"
+let parity = [isEven(10), isOdd(7)]
+let pet = Dog("rex").speak()
+
+fun isEven(n) { return n == 0 ? true : isOdd(n - 1) }
+fun isOdd(n) { return n == 0 ? false : isEven(n - 1) }
+
+class Dog extends Animal {
+    fun speak(self) { return self.name + " barks" }
+}
+class Animal {
+    fun init(self, name) { self.name = name }
+}
+
+let fact = fun factorial(n) { return n <= 1 ? 1 : n * factorial(n - 1) }
+let doubled = [1, 2].map(fun (x) { return x * 2 })
+
+fun outer() {
+    fun sum(n) { return n <= 0 ? 0 : n + sum(n - 1) }
+    let extra = 5
+    return sum(3) + extra
+}
+"#,
+            )
+            .unwrap();
+        assert_eq!(engine.eval_as::<Vec<bool>>("parity").unwrap(), [true, true]);
+        assert_eq!(engine.eval_as::<String>("pet").unwrap(), "rex barks");
+        assert_eq!(engine.eval_as::<i64>("fact(5)").unwrap(), 120);
+        assert_eq!(engine.eval_as::<Vec<i64>>("doubled").unwrap(), [2, 4]);
+        assert_eq!(engine.eval_as::<i64>("outer()").unwrap(), 11);
+        // The expression's name is only visible inside it
+        assert!(engine.eval("factorial").is_err());
+    }
+
+    #[test]
+    fn test_namespace_forward_references() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                r#"
+namespace Parity {
+    fun isEven(n) { return n == 0 ? true : isOdd(n - 1) }
+    fun isOdd(n) { return n == 0 ? false : isEven(n - 1) }
+}
+
+namespace Geo {
+    let unit = 2
+    class Square extends Shape {
+        fun area(self) { return unit * unit * Inner.scale() }
+    }
+    class Shape {
+        fun kind(self) { return "shape" }
+    }
+    namespace Inner {
+        fun scale() { return FACTOR }
+        const FACTOR = 10
+    }
+}
+"#,
+            )
+            .unwrap();
+        assert!(engine.eval_as::<bool>("Parity.isEven(10)").unwrap());
+        assert!(engine.eval_as::<bool>("Parity.isOdd(7)").unwrap());
+        assert_eq!(engine.eval_as::<i64>("Geo.Square().area()").unwrap(), 40);
+        assert_eq!(
+            engine.eval_as::<String>("Geo.Square().kind()").unwrap(),
+            "shape"
+        );
+    }
+
+    #[test]
+    fn test_namespace_reexport_and_partial_namespaces() {
+        use crate::vm::MemoryLoader;
+
+        let loader = MemoryLoader::new()
+            .with_module("text.sald", "fun upper(s) { return s.upper() }")
+            .with_module("std/a.sald", "namespace Std { fun one() { return 1 } }")
+            .with_module(
+                "std/b.sald",
+                "namespace Std { fun two() { return Std.one() + 1 } }",
+            );
+        let mut engine = Engine::new();
+        engine.set_module_loader(loader);
+        engine
+            .eval(
+                r#"
+import "std/a"
+import "std/b"
+
+namespace Lib {
+    import "text" as Text
+    import "text" as Private
+    export Text
+    fun shout(s) { return Private.upper(s) + "!" }
+}
+namespace Lib {
+    const VERSION = 2
+}
+"#,
+            )
+            .unwrap();
+        assert_eq!(engine.eval_as::<i64>("Std.two()").unwrap(), 2);
+        assert_eq!(
+            engine.eval_as::<String>("Lib.shout(\"hi\")").unwrap(),
+            "HI!"
+        );
+        assert_eq!(
+            engine.eval_as::<String>("Lib.Text.upper(\"a\")").unwrap(),
+            "A"
+        );
+        assert_eq!(engine.eval_as::<i64>("Lib.VERSION").unwrap(), 2);
+        assert!(engine.eval("Lib.Private").is_err());
+    }
+}
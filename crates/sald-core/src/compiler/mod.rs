@@ -1,6 +1,8 @@
 pub mod chunk;
 mod compiler;
 pub mod opcode;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pipeline;
 
 pub use chunk::{Chunk, Constant};
 pub use compiler::Compiler;
@@ -89,6 +89,43 @@ pub enum OpCode {
     BuildRangeExclusive,
 
     RecursiveCall,
+
+    /// Operand: string constant with the names of the current locals by slot
+    Debugger,
+
+    /// `==` and `!=` in strict mode, which reject operands of different types
+    StrictEqual,
+    StrictNotEqual,
+
+    /// Like `DefineGlobal`, but the global can't be assigned or redeclared
+    DefineConstGlobal,
+
+    /// Like `ImportAs`, but pushes the module's namespace instead of
+    /// defining a global
+    ImportNamespace,
+    /// Like `DefineGlobal` for a namespace, merging it into an existing
+    /// namespace of the same name
+    DefineNamespace,
+
+    /// `import(path)`: pops the path and pushes the module's namespace
+    ImportDynamic,
+
+    /// Pops a dictionary of named call arguments and pushes it as the
+    /// call's last argument
+    NamedArgs,
+
+    /// Pushes the named arguments the current call had no parameter for,
+    /// as the value of the function's `**` parameter
+    Kwargs,
+
+    /// Like `GetProperty`, but pushes null when the property is missing,
+    /// for `obj.name ??= value`
+    GetPropertyOrNull,
+
+    /// Operands: slot of a `for` loop's iterable, followed by its position,
+    /// and the jump past the loop. Pushes the iterable's next item, or jumps
+    /// when there is none
+    ForIter,
 }
 
 impl OpCode {
@@ -96,6 +133,8 @@ impl OpCode {
         match self {
             OpCode::Constant
             | OpCode::DefineGlobal
+            | OpCode::DefineConstGlobal
+            | OpCode::DefineNamespace
             | OpCode::GetGlobal
             | OpCode::SetGlobal
             | OpCode::GetLocal
@@ -111,6 +150,7 @@ impl OpCode {
             | OpCode::Method
             | OpCode::StaticMethod
             | OpCode::GetProperty
+            | OpCode::GetPropertyOrNull
             | OpCode::SetProperty
             | OpCode::Invoke
             | OpCode::BuildArray
@@ -121,10 +161,12 @@ impl OpCode {
             | OpCode::GetSuper
             | OpCode::Import
             | OpCode::ImportAs
+            | OpCode::ImportNamespace
             | OpCode::GetUpvalue
             | OpCode::SetUpvalue
             | OpCode::TryStart
-            | OpCode::RecursiveCall => 2,
+            | OpCode::RecursiveCall
+            | OpCode::Debugger => 2,
 
             _ => 0,
         }
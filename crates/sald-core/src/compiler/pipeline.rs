@@ -0,0 +1,151 @@
+//! Parallel front-end for the import graph
+//! Lexes, parses and compiles every module reachable from an entry file on the
+//! rayon pool so the VM only has to execute them when the import runs
+
+use super::{Chunk, Compiler};
+use crate::ast::{Program, Stmt};
+use crate::lexer::Scanner;
+use crate::parser::Parser;
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::path::{Path, PathBuf};
+
+/// Compiled chunks keyed by canonical module path.
+pub type PrecompiledModules = FxHashMap<String, Chunk>;
+
+struct CompiledModule {
+    path: PathBuf,
+    chunk: Option<Chunk>,
+    imports: Vec<PathBuf>,
+}
+
+/// Compiles all modules imported (directly or transitively) by `entry`,
+/// resolving package imports against `project_root`.
+///
+/// Modules that fail to compile are left out; the VM compiles them again
+/// when the import executes and reports the error at that point, so error
+/// behavior is the same as without precompilation.
+pub fn precompile_imports(entry: &Path, project_root: Option<&Path>) -> PrecompiledModules {
+    let mut modules = PrecompiledModules::default();
+    let mut seen: FxHashSet<PathBuf> = FxHashSet::default();
+
+    let entry = match entry.canonicalize() {
+        Ok(p) => p,
+        Err(_) => return modules,
+    };
+    seen.insert(entry.clone());
+
+    let mut frontier = match parse_file(&entry) {
+        Some((_, program)) => collect_imports(&entry, &program.statements, project_root),
+        None => return modules,
+    };
+    frontier.retain(|p| seen.insert(p.clone()));
+
+    while !frontier.is_empty() {
+        let level: Vec<CompiledModule> = frontier
+            .par_iter()
+            .map(|p| compile_module(p, project_root))
+            .collect();
+
+        frontier = Vec::new();
+        for module in level {
+            for import in module.imports {
+                if seen.insert(import.clone()) {
+                    frontier.push(import);
+                }
+            }
+            if let Some(chunk) = module.chunk {
+                modules.insert(module.path.to_string_lossy().to_string(), chunk);
+            }
+        }
+    }
+
+    modules
+}
+
+fn parse_file(path: &Path) -> Option<(String, Program)> {
+    let source = std::fs::read_to_string(path).ok()?;
+    let file = path.to_string_lossy().to_string();
+    let tokens = Scanner::new(&source, &file).scan_tokens().ok()?;
+    let program = Parser::new(tokens, &file, &source).parse().ok()?;
+    Some((source, program))
+}
+
+fn compile_module(path: &Path, project_root: Option<&Path>) -> CompiledModule {
+    let mut module = CompiledModule {
+        path: path.to_path_buf(),
+        chunk: None,
+        imports: Vec::new(),
+    };
+    if let Some((source, program)) = parse_file(path) {
+        module.imports = collect_imports(path, &program.statements, project_root);
+        let file = path.to_string_lossy().to_string();
+        module.chunk = Compiler::new(&file, &source).compile(&program).ok();
+    }
+    module
+}
+
+fn collect_imports(
+    importer: &Path,
+    statements: &[Stmt],
+    project_root: Option<&Path>,
+) -> Vec<PathBuf> {
+    let mut imports = Vec::new();
+    for stmt in statements.iter().filter_map(Stmt::active) {
+        match stmt {
+            Stmt::Import { path, .. } => {
+                if let Some(resolved) = resolve_import(importer, path, project_root) {
+                    imports.push(resolved);
+                }
+            }
+            Stmt::Namespace { body, .. } => {
+                imports.extend(collect_imports(importer, body, project_root))
+            }
+            _ => {}
+        }
+    }
+    imports
+}
+
+/// Mirrors the VM's import resolution for source modules. Native and
+/// precompiled `.saldc` imports are skipped.
+fn resolve_import(
+    importer: &Path,
+    import_path: &str,
+    project_root: Option<&Path>,
+) -> Option<PathBuf> {
+    if import_path.starts_with(crate::native_module::NATIVE_IMPORT_PREFIX)
+        || import_path.ends_with(".saldc")
+    {
+        return None;
+    }
+
+    let dir = importer.parent().unwrap_or_else(|| Path::new("."));
+    if crate::package::is_bare_specifier(import_path) && !import_path.contains(['/', '\\']) {
+        let module = crate::package::resolve_module(project_root, dir, import_path).ok()?;
+        return module.entry.canonicalize().ok();
+    }
+
+    let with_ext = if import_path.ends_with(".sald") {
+        import_path.to_string()
+    } else {
+        format!("{}.sald", import_path)
+    };
+    let candidate = PathBuf::from(&with_ext);
+    if candidate.is_absolute() {
+        return candidate.canonicalize().ok();
+    }
+    if let Ok(found) = dir.join(&with_ext).canonicalize() {
+        return Some(found);
+    }
+    if let Ok(module_path) = std::env::var("SALD_MODULE") {
+        if let Ok(found) = PathBuf::from(module_path).join(&with_ext).canonicalize() {
+            return Some(found);
+        }
+    }
+    if !crate::package::is_bare_specifier(import_path) {
+        return None;
+    }
+    let module = crate::package::resolve_module(project_root, dir, import_path).ok()?;
+    module.entry.canonicalize().ok()
+}
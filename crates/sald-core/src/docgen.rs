@@ -0,0 +1,278 @@
+//! API documentation generator
+//! Collects the functions and classes of parsed modules together with their
+//! `///` comments and renders them, along with the builtin classes, as a
+//! Markdown or HTML reference
+
+use crate::ast::{FunctionDef, Program, Stmt};
+use crate::builtins::docs::{ClassDoc, MemberDoc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+/// Documented items of one source file.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleDoc {
+    pub path: String,
+    pub functions: Vec<MemberDoc>,
+    pub classes: Vec<ClassDoc>,
+}
+
+impl ModuleDoc {
+    /// Collects top-level functions and classes; namespace members are
+    /// qualified with the namespace name.
+    pub fn from_program(path: impl Into<String>, program: &Program) -> Self {
+        let mut module = Self {
+            path: path.into(),
+            ..Self::default()
+        };
+        module.collect(&program.statements, "");
+        module
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty() && self.classes.is_empty()
+    }
+
+    fn collect(&mut self, statements: &[Stmt], prefix: &str) {
+        for stmt in statements {
+            match stmt.without_cfg() {
+                Stmt::Function { def } => self.functions.push(function_doc(def, prefix)),
+                Stmt::Class { def } => {
+                    let mut class = ClassDoc::new(
+                        format!("{}{}", prefix, def.name),
+                        def.doc.clone().unwrap_or_default(),
+                    );
+                    class.methods = def.methods.iter().map(|m| function_doc(m, "")).collect();
+                    self.classes.push(class);
+                }
+                Stmt::Namespace { name, body, .. } => {
+                    self.collect(body, &format!("{}{}.", prefix, name));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn function_doc(def: &FunctionDef, prefix: &str) -> MemberDoc {
+    let params: Vec<String> = def
+        .params
+        .iter()
+        .filter(|p| p.name != "self")
+        .map(|p| {
+            if p.is_variadic {
+                format!("...{}", p.name)
+            } else if p.is_kwargs {
+                format!("**{}", p.name)
+            } else if p.default_value.is_some() {
+                format!("{}?", p.name)
+            } else {
+                p.name.clone()
+            }
+        })
+        .collect();
+    let name = format!("{}{}", prefix, def.name);
+    MemberDoc {
+        signature: format!(
+            "{}{}({})",
+            if def.is_async { "async " } else { "" },
+            name,
+            params.join(", ")
+        ),
+        name,
+        doc: def.doc.clone().unwrap_or_default(),
+    }
+}
+
+/// Renders the project modules followed by the builtin classes.
+pub fn render(
+    title: &str,
+    modules: &[ModuleDoc],
+    builtins: &[ClassDoc],
+    format: DocFormat,
+) -> String {
+    match format {
+        DocFormat::Markdown => render_markdown(title, modules, builtins),
+        DocFormat::Html => render_html(title, modules, builtins),
+    }
+}
+
+fn render_markdown(title: &str, modules: &[ModuleDoc], builtins: &[ClassDoc]) -> String {
+    let mut out = format!("# {}\n", title);
+
+    for module in modules.iter().filter(|m| !m.is_empty()) {
+        out.push_str(&format!("\n## {}\n", module.path));
+        for function in &module.functions {
+            out.push_str(&format!("\n### `{}`\n", function.signature));
+            if !function.doc.is_empty() {
+                out.push_str(&format!("\n{}\n", function.doc));
+            }
+        }
+        for class in &module.classes {
+            markdown_class(&mut out, class);
+        }
+    }
+
+    if !builtins.is_empty() {
+        out.push_str("\n## Builtins\n");
+        for class in builtins {
+            markdown_class(&mut out, class);
+        }
+    }
+
+    out
+}
+
+fn markdown_class(out: &mut String, class: &ClassDoc) {
+    out.push_str(&format!("\n### class `{}`\n", class.name));
+    if !class.doc.is_empty() {
+        out.push_str(&format!("\n{}\n", class.doc));
+    }
+    if !class.methods.is_empty() || !class.properties.is_empty() {
+        out.push('\n');
+    }
+    for property in &class.properties {
+        out.push_str(&format!("- `{}`", property.name));
+        markdown_summary(out, &property.doc);
+    }
+    for method in &class.methods {
+        out.push_str(&format!("- `{}`", method.signature));
+        markdown_summary(out, &method.doc);
+    }
+}
+
+/// Continuation lines are indented so multi-line docs stay in the list item.
+fn markdown_summary(out: &mut String, doc: &str) {
+    if !doc.is_empty() {
+        out.push_str(" — ");
+        out.push_str(&doc.replace('\n', "\n  "));
+    }
+    out.push('\n');
+}
+
+fn render_html(title: &str, modules: &[ModuleDoc], builtins: &[ClassDoc]) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape(title)
+    );
+
+    for module in modules.iter().filter(|m| !m.is_empty()) {
+        out.push_str(&format!("<h2>{}</h2>\n", escape(&module.path)));
+        for function in &module.functions {
+            out.push_str(&format!(
+                "<h3><code>{}</code></h3>\n",
+                escape(&function.signature)
+            ));
+            html_doc(&mut out, &function.doc);
+        }
+        for class in &module.classes {
+            html_class(&mut out, class);
+        }
+    }
+
+    if !builtins.is_empty() {
+        out.push_str("<h2>Builtins</h2>\n");
+        for class in builtins {
+            html_class(&mut out, class);
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn html_class(out: &mut String, class: &ClassDoc) {
+    out.push_str(&format!(
+        "<h3 id=\"{0}\">class <code>{0}</code></h3>\n",
+        escape(&class.name)
+    ));
+    html_doc(out, &class.doc);
+    if class.methods.is_empty() && class.properties.is_empty() {
+        return;
+    }
+    out.push_str("<dl>\n");
+    for property in &class.properties {
+        out.push_str(&format!(
+            "<dt><code>{}</code></dt>\n",
+            escape(&property.name)
+        ));
+        out.push_str(&format!("<dd>{}</dd>\n", escape(&property.doc)));
+    }
+    for method in &class.methods {
+        out.push_str(&format!(
+            "<dt><code>{}</code></dt>\n",
+            escape(&method.signature)
+        ));
+        out.push_str(&format!("<dd>{}</dd>\n", escape(&method.doc)));
+    }
+    out.push_str("</dl>\n");
+}
+
+fn html_doc(out: &mut String, doc: &str) {
+    if !doc.is_empty() {
+        out.push_str(&format!("<p>{}</p>\n", escape(doc).replace('\n', "<br>\n")));
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Scanner;
+    use crate::parser::Parser;
+
+    fn module(source: &str) -> ModuleDoc {
+        let tokens = Scanner::new(source, "lib.sald").scan_tokens().unwrap();
+        let program = Parser::new(tokens, "lib.sald", source).parse().unwrap();
+        ModuleDoc::from_program("lib.sald", &program)
+    }
+
+    #[test]
+    fn test_doc_comments_are_collected() {
+        let module = module(
+            "/// Adds two numbers\n/// Returns their sum\nfun add(a, b = 1) { return a + b }\n\n\
+             // not a doc comment\nfun plain() {}\n\n\
+             /// A point\n@Serializable\nclass Point {\n    /// Distance from origin\n    \
+             fun length(self) { return 0 }\n    fun origin() { return null }\n}\n",
+        );
+
+        assert_eq!(module.functions[0].signature, "add(a, b?)");
+        assert_eq!(
+            module.functions[0].doc,
+            "Adds two numbers\nReturns their sum"
+        );
+        assert_eq!(module.functions[1].doc, "");
+        assert_eq!(module.classes[0].doc, "A point");
+        assert_eq!(module.classes[0].methods[0].signature, "length()");
+        assert_eq!(module.classes[0].methods[0].doc, "Distance from origin");
+        assert_eq!(module.classes[0].methods[1].doc, "");
+    }
+
+    #[test]
+    fn test_render_includes_builtins() {
+        let modules = [module("/// Says <hi>\nfun greet(...names) {}\n")];
+        let builtins = [ClassDoc::new("Console", "Console I/O operations").method(
+            "println",
+            "println(...args)",
+            "Print with newline",
+        )];
+
+        let markdown = render("API", &modules, &builtins, DocFormat::Markdown);
+        assert!(markdown.contains("### `greet(...names)`\n\nSays <hi>\n"));
+        assert!(markdown.contains("### class `Console`"));
+        assert!(markdown.contains("- `println(...args)` — Print with newline"));
+
+        let html = render("API", &modules, &builtins, DocFormat::Html);
+        assert!(html.contains("<p>Says &lt;hi&gt;</p>"));
+        assert!(html.contains("<dt><code>println(...args)</code></dt>"));
+    }
+}
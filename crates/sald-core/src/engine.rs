@@ -0,0 +1,519 @@
+//! Embedding API for Rust hosts
+//! Evaluates Sald source and exchanges values with scripts
+
+use crate::builtins::{integralize_numbers, json_to_sald_value, sald_value_to_json, ConsoleStream};
+use crate::compiler::Compiler;
+use crate::error::SaldError;
+use crate::lexer::Scanner;
+use crate::native_module::{register_native_module, NativeModule};
+use crate::parser::Parser;
+use crate::vm::value::{HostFn, SendValue};
+use crate::vm::ModuleLoader;
+use crate::vm::{Value, VM};
+use crate::warnings::Warning;
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Error returned to host applications by [`Engine`].
+#[derive(Debug, Clone)]
+pub enum EngineError {
+    /// Scanning, parsing, compiling or running the script failed.
+    Script(Box<SaldError>),
+    /// A value could not be converted between Sald and Rust.
+    Conversion(String),
+    /// A snapshot could not be written or restored.
+    Snapshot(String),
+}
+
+impl EngineError {
+    /// The error message without source context or colors.
+    pub fn message(&self) -> &str {
+        match self {
+            EngineError::Script(e) => &e.message,
+            EngineError::Conversion(msg) | EngineError::Snapshot(msg) => msg,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::Script(e) => {
+                write!(f, "{}: {}", e.kind, crate::locale::translate(&e.message))
+            }
+            EngineError::Conversion(msg) => write!(f, "ConversionError: {}", msg),
+            EngineError::Snapshot(msg) => write!(f, "SnapshotError: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {}
+
+impl From<SaldError> for EngineError {
+    fn from(error: SaldError) -> Self {
+        EngineError::Script(Box::new(error))
+    }
+}
+
+pub type EngineResult<T> = Result<T, EngineError>;
+
+/// A Sald interpreter owned by a host program.
+///
+/// Globals persist across calls, so definitions from one `eval` are visible
+/// to the next one and to `call_function`.
+pub struct Engine {
+    vm: VM,
+    file: String,
+    spawner: SharedSpawner,
+}
+
+/// Drives the futures returned by async host functions to completion.
+pub type AsyncSpawner = Arc<dyn Fn(BoxFuture<'static, ()>) + Send + Sync>;
+
+/// The engine's current spawner, read by async host functions on each call
+/// so they follow later `set_async_spawner` calls.
+pub type SharedSpawner = Rc<RefCell<AsyncSpawner>>;
+
+impl Engine {
+    pub fn new() -> Self {
+        Self {
+            vm: VM::new(),
+            file: "<engine>".to_string(),
+            spawner: Rc::new(RefCell::new(Arc::new(|future| {
+                std::thread::spawn(move || futures::executor::block_on(future));
+            }))),
+        }
+    }
+
+    /// Sets how async host functions are executed.
+    ///
+    /// By default each call runs on its own thread with a minimal executor.
+    /// Hosts that use tokio should hand the futures to their runtime so host
+    /// IO works as usual:
+    ///
+    /// ```ignore
+    /// let handle = tokio::runtime::Handle::current();
+    /// engine.set_async_spawner(move |future| {
+    ///     handle.spawn(future);
+    /// });
+    /// ```
+    pub fn set_async_spawner(
+        &mut self,
+        spawner: impl Fn(BoxFuture<'static, ()>) + Send + Sync + 'static,
+    ) {
+        *self.spawner.borrow_mut() = Arc::new(spawner);
+    }
+
+    /// Sets the file name reported in error messages and stack traces.
+    pub fn with_file_name(mut self, file: impl Into<String>) -> Self {
+        self.file = file.into();
+        self
+    }
+
+    /// Evaluates source code and returns the value of its last expression.
+    pub fn eval(&mut self, source: &str) -> EngineResult<Value> {
+        let mut scanner = Scanner::new(source, &self.file);
+        let tokens = scanner.scan_tokens()?;
+
+        let mut parser = Parser::new(tokens, &self.file, source);
+        let program = parser.parse()?;
+
+        let mut compiler = Compiler::new(&self.file, source);
+        let chunk = compiler.compile_repl(&program)?;
+
+        let result = self.vm.run(chunk, &self.file, source);
+        self.vm.reset();
+        Ok(result?)
+    }
+
+    /// Evaluates source code and converts the result into a Rust type.
+    pub fn eval_as<T: DeserializeOwned>(&mut self, source: &str) -> EngineResult<T> {
+        let value = self.eval(source)?;
+        from_value(&value)
+    }
+
+    /// Calls a global script function by name.
+    pub fn call_function(&mut self, name: &str, args: Vec<Value>) -> EngineResult<Value> {
+        let result = self.vm.call_global(name, args);
+        self.vm.reset();
+        Ok(result?)
+    }
+
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.vm.get_global(name)
+    }
+
+    pub fn set_global(&mut self, name: impl Into<String>, value: Value) {
+        self.vm.set_global(name, value);
+    }
+
+    /// Converts a serializable Rust value and stores it as a global.
+    pub fn set_global_from<T: Serialize>(
+        &mut self,
+        name: impl Into<String>,
+        value: &T,
+    ) -> EngineResult<()> {
+        let value = to_value(value)?;
+        self.vm.set_global(name, value);
+        Ok(())
+    }
+
+    /// Exposes a Rust closure to scripts as a global function.
+    ///
+    /// Arguments and the return value are converted with serde, so any
+    /// parameter type implementing `DeserializeOwned` and any return type
+    /// implementing `Serialize` can be used. Missing trailing arguments are
+    /// passed as `null`, which lets `Option` parameters be omitted. An `Err`
+    /// returned by the closure is thrown as a Sald exception.
+    ///
+    /// ```ignore
+    /// engine.register_fn("repeat", |s: String, n: f64| -> Result<String, String> {
+    ///     Ok(s.repeat(n as usize))
+    /// });
+    /// ```
+    pub fn register_fn<Args>(&mut self, name: impl Into<String>, func: impl IntoHostFn<Args>) {
+        let name = name.into();
+        let value = Value::HostFunction {
+            func: func.into_host_fn(),
+            name: name.clone(),
+        };
+        self.vm.set_global(name, value);
+    }
+
+    /// Exposes an async Rust closure to scripts as a global function.
+    ///
+    /// Calling it returns a Future that scripts `await`, exactly like calling
+    /// an `async fun`. Argument and result conversion follow
+    /// [`Engine::register_fn`]; the future runs on the engine's spawner.
+    /// Like other globals, it is not visible inside `async fun` bodies, which
+    /// run in isolated worker VMs.
+    ///
+    /// ```ignore
+    /// engine.register_async_fn("fetch", |url: String| async move {
+    ///     reqwest::get(url).await?.text().await
+    /// });
+    /// ```
+    pub fn register_async_fn<Args>(
+        &mut self,
+        name: impl Into<String>,
+        func: impl IntoAsyncHostFn<Args>,
+    ) {
+        let name = name.into();
+        let value = Value::HostFunction {
+            func: func.into_async_host_fn(self.spawner.clone()),
+            name: name.clone(),
+        };
+        self.vm.set_global(name, value);
+    }
+
+    /// Makes a Rust module importable with `import "native:<name>"`.
+    pub fn register_native_module(&mut self, module: impl NativeModule + 'static) {
+        register_native_module(Arc::new(module));
+    }
+
+    /// Sends `Console` output for `stream` to `writer` instead of the
+    /// process's stdout or stderr. Applies to every engine on this thread.
+    pub fn set_console_output(&mut self, stream: ConsoleStream, writer: impl Write + 'static) {
+        crate::builtins::set_console_writer(stream, Some(Box::new(writer)));
+    }
+
+    /// Passes warnings, such as uses of deprecated APIs, to `handler` instead
+    /// of printing them. Applies to every engine in the process.
+    pub fn set_warning_handler(&mut self, handler: impl Fn(&Warning) + Send + Sync + 'static) {
+        crate::warnings::set_handler(Some(Arc::new(handler)));
+    }
+
+    /// Serves `import` from `loader` instead of the filesystem, for scripts
+    /// kept in memory, archives, databases or behind a network.
+    pub fn set_module_loader(&mut self, loader: impl ModuleLoader + 'static) {
+        self.vm.set_module_loader(loader);
+    }
+
+    /// Serializes the script globals defined so far.
+    ///
+    /// Run a prelude once, store the snapshot, and load it with
+    /// [`Engine::from_snapshot`] to skip parsing and executing the prelude on
+    /// later startups. Globals holding host functions, native functions or
+    /// Futures cannot be snapshotted; register host functions again after
+    /// restoring.
+    pub fn snapshot(&self) -> EngineResult<Vec<u8>> {
+        self.vm.snapshot().map_err(EngineError::Snapshot)
+    }
+
+    /// Creates an engine whose globals are restored from [`Engine::snapshot`].
+    pub fn from_snapshot(data: &[u8]) -> EngineResult<Self> {
+        let mut engine = Self::new();
+        engine
+            .vm
+            .restore_snapshot(data)
+            .map_err(EngineError::Snapshot)?;
+        Ok(engine)
+    }
+
+    /// Direct access to the underlying VM for advanced use.
+    pub fn vm(&mut self) -> &mut VM {
+        &mut self.vm
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts any serde-serializable Rust value into a Sald value.
+pub fn to_value<T: Serialize>(value: &T) -> EngineResult<Value> {
+    let json = serde_json::to_value(value).map_err(|e| EngineError::Conversion(e.to_string()))?;
+    json_to_sald_value(&json).map_err(EngineError::Conversion)
+}
+
+/// Converts a Sald value into any serde-deserializable Rust type.
+///
+/// Only data values (null, booleans, numbers, strings, arrays and
+/// dictionaries) can be converted.
+pub fn from_value<T: DeserializeOwned>(value: &Value) -> EngineResult<T> {
+    let mut json = sald_value_to_json(value).map_err(EngineError::Conversion)?;
+    integralize_numbers(&mut json);
+    serde_json::from_value(json).map_err(|e| EngineError::Conversion(e.to_string()))
+}
+
+/// Rust closures that can be registered with [`Engine::register_fn`].
+///
+/// Implemented for `Fn(A, B, ..) -> Result<R, E>` with up to eight arguments.
+pub trait IntoHostFn<Args> {
+    fn into_host_fn(self) -> HostFn;
+}
+
+/// Async Rust closures that can be registered with [`Engine::register_async_fn`].
+///
+/// Implemented for `Fn(A, B, ..) -> impl Future<Output = Result<R, E>>` with
+/// up to eight arguments. The future must be `Send` because it completes off
+/// the VM thread.
+pub trait IntoAsyncHostFn<Args> {
+    fn into_async_host_fn(self, spawner: SharedSpawner) -> HostFn;
+}
+
+fn check_host_arity(args: &[Value], arity: usize) -> Result<(), String> {
+    if args.len() > arity {
+        return Err(arity_message(arity, args.len()));
+    }
+    Ok(())
+}
+
+fn arity_message(expected: usize, got: usize) -> String {
+    format!(
+        "Expected {} argument{} but got {}",
+        expected,
+        if expected == 1 { "" } else { "s" },
+        got
+    )
+}
+
+fn host_arg<T: DeserializeOwned>(args: &[Value], index: usize, arity: usize) -> Result<T, String> {
+    match args.get(index) {
+        Some(value) => from_value(value)
+            .map_err(|e| format!("Invalid argument {}: {}", index + 1, e.message())),
+        None => from_value(&Value::Null).map_err(|_| arity_message(arity, args.len())),
+    }
+}
+
+fn send_value_from_json(json: serde_json::Value) -> SendValue {
+    match json {
+        serde_json::Value::Null => SendValue::Null,
+        serde_json::Value::Bool(b) => SendValue::Boolean(b),
+        serde_json::Value::Number(n) => SendValue::Number(n.as_f64().unwrap_or(0.0)),
+        serde_json::Value::String(s) => SendValue::String(s),
+        serde_json::Value::Array(arr) => {
+            SendValue::Array(arr.into_iter().map(send_value_from_json).collect())
+        }
+        serde_json::Value::Object(obj) => SendValue::Dictionary(
+            obj.into_iter()
+                .map(|(k, v)| (k, send_value_from_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+macro_rules! impl_host_fn {
+    ($($arg:ident),*) => {
+        impl<F, R, E, $($arg),*> IntoHostFn<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Result<R, E> + 'static,
+            R: Serialize,
+            E: fmt::Display,
+            $($arg: DeserializeOwned,)*
+        {
+            #[allow(non_snake_case, unused_variables, unused_mut, unused_assignments)]
+            fn into_host_fn(self) -> HostFn {
+                Rc::new(move |args: &[Value]| {
+                    let arity = <[&str]>::len(&[$(stringify!($arg)),*]);
+                    check_host_arity(args, arity)?;
+                    let mut index = 0;
+                    $(
+                        let $arg: $arg = host_arg(args, index, arity)?;
+                        index += 1;
+                    )*
+                    let result = self($($arg),*).map_err(|e| e.to_string())?;
+                    to_value(&result).map_err(|e| e.message().to_string())
+                })
+            }
+        }
+
+        impl<F, Fut, R, E, $($arg),*> IntoAsyncHostFn<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> Fut + 'static,
+            Fut: Future<Output = Result<R, E>> + Send + 'static,
+            R: Serialize,
+            E: fmt::Display,
+            $($arg: DeserializeOwned,)*
+        {
+            #[allow(non_snake_case, unused_variables, unused_mut, unused_assignments)]
+            fn into_async_host_fn(self, spawner: SharedSpawner) -> HostFn {
+                Rc::new(move |args: &[Value]| {
+                    let arity = <[&str]>::len(&[$(stringify!($arg)),*]);
+                    check_host_arity(args, arity)?;
+                    let mut index = 0;
+                    $(
+                        let $arg: $arg = host_arg(args, index, arity)?;
+                        index += 1;
+                    )*
+                    let future = self($($arg),*);
+
+                    // Same one-shot channel that `async fun` calls resolve through
+                    let (tx, rx) = crossbeam_channel::bounded(1);
+                    let spawn = spawner.borrow().clone();
+                    spawn(Box::pin(async move {
+                        let result = match future.await {
+                            Ok(value) => serde_json::to_value(&value)
+                                .map(send_value_from_json)
+                                .map_err(|e| e.to_string()),
+                            Err(e) => Err(e.to_string()),
+                        };
+                        let _ = tx.send(result);
+                    }));
+                    Ok(Value::Future(Rc::new(RefCell::new(Some(rx)))))
+                })
+            }
+        }
+    };
+}
+
+impl_host_fn!();
+impl_host_fn!(A1);
+impl_host_fn!(A1, A2);
+impl_host_fn!(A1, A2, A3);
+impl_host_fn!(A1, A2, A3, A4);
+impl_host_fn!(A1, A2, A3, A4, A5);
+impl_host_fn!(A1, A2, A3, A4, A5, A6);
+impl_host_fn!(A1, A2, A3, A4, A5, A6, A7);
+impl_host_fn!(A1, A2, A3, A4, A5, A6, A7, A8);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eval_returns_last_expression() {
+        let mut engine = Engine::new();
+        let n: f64 = engine.eval_as("1 + 2").unwrap();
+        assert_eq!(n, 3.0);
+    }
+
+    #[test]
+    fn test_globals_persist_between_calls() {
+        let mut engine = Engine::new();
+        engine.eval("fun add(a, b) { return a + b }").unwrap();
+        engine.set_global("x", Value::Number(40.0));
+        let result = engine
+            .call_function(
+                "add",
+                vec![Value::Number(2.0), engine.get_global("x").unwrap()],
+            )
+            .unwrap();
+        assert_eq!(result, Value::Number(42.0));
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mut engine = Engine::new();
+        engine.set_global_from("items", &vec![1, 2, 3]).unwrap();
+        let items: Vec<i64> = engine.eval_as("items").unwrap();
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_register_fn_marshals_arguments() {
+        let mut engine = Engine::new();
+        engine.register_fn("repeat", |s: String, n: f64| -> Result<String, String> {
+            Ok(s.repeat(n as usize))
+        });
+        engine.register_fn(
+            "total",
+            |items: Vec<f64>, scale: Option<f64>| -> Result<f64, String> {
+                Ok(items.iter().sum::<f64>() * scale.unwrap_or(1.0))
+            },
+        );
+        engine.register_fn("fail", || -> Result<(), String> { Err("nope".into()) });
+
+        let repeated: String = engine.eval_as("repeat(\"ab\", 3)").unwrap();
+        let total: f64 = engine.eval_as("total([1, 2, 3])").unwrap();
+        let scaled: f64 = engine.eval_as("total([1, 2, 3], 2)").unwrap();
+        let caught: String = engine
+            .eval_as("let r = \"\"\ntry { fail() } catch (e) { r = e }\nr")
+            .unwrap();
+        assert_eq!(repeated, "ababab");
+        assert_eq!(total, 6.0);
+        assert_eq!(scaled, 12.0);
+        assert_eq!(caught, "nope");
+        assert!(engine.eval("repeat(1, 2)").is_err());
+    }
+
+    #[test]
+    fn test_register_async_fn_is_awaitable() {
+        let mut engine = Engine::new();
+        engine.register_async_fn("double", |n: f64| async move { Ok::<_, String>(n * 2.0) });
+        engine.register_async_fn("fail", || async { Err::<(), _>("io error") });
+
+        let doubled: f64 = engine
+            .eval("fun run() { return await double(21) }\nrun()")
+            .and_then(|v| from_value(&v))
+            .unwrap();
+        let err = engine.eval("await fail()").unwrap_err();
+        assert_eq!(doubled, 42.0);
+        assert!(err.message().contains("io error"));
+    }
+
+    #[test]
+    fn test_async_fn_uses_spawner_set_after_registration() {
+        let mut engine = Engine::new();
+        engine.register_async_fn("double", |n: f64| async move { Ok::<_, String>(n * 2.0) });
+        let spawned = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let count = spawned.clone();
+        engine.set_async_spawner(move |future| {
+            count.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            futures::executor::block_on(future);
+        });
+
+        let doubled: f64 = engine
+            .eval("await double(4)")
+            .and_then(|v| from_value(&v))
+            .unwrap();
+        assert_eq!(doubled, 8.0);
+        assert_eq!(spawned.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_script_error_is_reported() {
+        let mut engine = Engine::new();
+        let err = engine.eval("undefined_name").unwrap_err();
+        assert!(matches!(err, EngineError::Script(_)));
+    }
+}
@@ -1,5 +1,6 @@
 #[cfg(not(target_arch = "wasm32"))]
 use colored::*;
+use crate::locale::translate;
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -86,6 +87,26 @@ pub enum ErrorKind {
     InterfaceError,
 }
 
+impl ErrorKind {
+    /// Stable identifier for the kind, unaffected by the diagnostic locale.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::SyntaxError => "E0001",
+            ErrorKind::TypeError => "E0002",
+            ErrorKind::NameError => "E0003",
+            ErrorKind::ValueError => "E0004",
+            ErrorKind::RuntimeError => "E0005",
+            ErrorKind::AttributeError => "E0006",
+            ErrorKind::IndexError => "E0007",
+            ErrorKind::ArgumentError => "E0008",
+            ErrorKind::DivisionByZero => "E0009",
+            ErrorKind::ImportError => "E0010",
+            ErrorKind::AccessError => "E0011",
+            ErrorKind::InterfaceError => "E0012",
+        }
+    }
+}
+
 impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -197,7 +218,7 @@ impl SaldError {
         let header = format!(
             "{}: {} at {}:{}:{}",
             self.kind.to_string().red().bold(),
-            self.message.white().bold(),
+            translate(&self.message).white().bold(),
             self.file.trim_start_matches(r"\\?\"),
             self.span.start.line,
             self.span.start.column
@@ -236,11 +257,11 @@ impl SaldError {
         }
 
         if let Some(ref help) = self.help {
-            output.push_str(&format!("\n      {}: {}\n", "Help".cyan().bold(), help));
+            output.push_str(&format!("\n      {}: {}\n", translate("Help").cyan().bold(), translate(help)));
         }
 
         if !self.stack_trace.is_empty() {
-            output.push_str(&format!("\n{}:\n", "Stack trace".yellow().bold()));
+            output.push_str(&format!("\n{}:\n", translate("Stack trace").yellow().bold()));
 
             for frame in self.stack_trace.iter() {
                 output.push_str(&format!("{}\n", frame));
@@ -257,7 +278,7 @@ impl SaldError {
         let header = format!(
             "{}: {} at {}:{}:{}",
             self.kind,
-            self.message,
+            translate(&self.message),
             self.file.trim_start_matches(r"\\?\"),
             self.span.start.line,
             self.span.start.column
@@ -294,11 +315,11 @@ impl SaldError {
         }
 
         if let Some(ref help) = self.help {
-            output.push_str(&format!("\n      Help: {}\n", help));
+            output.push_str(&format!("\n      {}: {}\n", translate("Help"), translate(help)));
         }
 
         if !self.stack_trace.is_empty() {
-            output.push_str("\nStack trace:\n");
+            output.push_str(&format!("\n{}:\n", translate("Stack trace")));
             for frame in self.stack_trace.iter() {
                 output.push_str(&format!("{}\n", frame));
             }
@@ -315,7 +336,7 @@ impl SaldError {
             let header = format!(
                 "{}: {} at {}:{}:{}",
                 self.kind.to_string().red().bold(),
-                self.message.white().bold(),
+                translate(&self.message).white().bold(),
                 self.file.trim_start_matches(r"\\?\"),
                 self.span.start.line,
                 self.span.start.column
@@ -355,11 +376,11 @@ impl SaldError {
             }
 
             if let Some(ref help) = self.help {
-                output.push_str(&format!("\n      {}: {}\n", "Help".cyan().bold(), help));
+                output.push_str(&format!("\n      {}: {}\n", translate("Help").cyan().bold(), translate(help)));
             }
 
             if !self.stack_trace.is_empty() {
-                output.push_str(&format!("\n{}:\n", "Stack trace".yellow().bold()));
+                output.push_str(&format!("\n{}:\n", translate("Stack trace").yellow().bold()));
                 for frame in &self.stack_trace {
                     output.push_str(&format!("{}\n", frame));
                 }
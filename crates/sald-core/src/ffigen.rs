@@ -0,0 +1,1228 @@
+//! FFI binding generator
+//! Reads a C header, or a JSON description of one, and renders a Sald module
+//! that declares its constants and structs and wraps every function in an
+//! `Ffi` call, so bindings don't have to be transcribed by hand
+
+use crate::lexer::KEYWORDS;
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+
+/// Type names understood by `Ffi` calls, besides declared struct names.
+const FFI_TYPES: &[&str] = &[
+    "void",
+    "i8",
+    "int8",
+    "char",
+    "u8",
+    "uint8",
+    "uchar",
+    "byte",
+    "i16",
+    "int16",
+    "short",
+    "u16",
+    "uint16",
+    "ushort",
+    "i32",
+    "int32",
+    "int",
+    "u32",
+    "uint32",
+    "uint",
+    "i64",
+    "int64",
+    "long",
+    "longlong",
+    "u64",
+    "uint64",
+    "ulong",
+    "ulonglong",
+    "size_t",
+    "f32",
+    "float",
+    "f64",
+    "double",
+    "ptr",
+    "pointer",
+    "void*",
+    "voidptr",
+    "string",
+    "cstring",
+    "char*",
+    "str",
+    "callback",
+    "fnptr",
+];
+
+/// Everything a generated module declares.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Bindings {
+    /// Library to open; the CLI falls back to one named after the input
+    #[serde(default)]
+    pub library: Option<String>,
+    #[serde(default)]
+    pub constants: Vec<ConstantDecl>,
+    #[serde(default)]
+    pub structs: Vec<StructDecl>,
+    #[serde(default)]
+    pub functions: Vec<FunctionDecl>,
+    /// Declarations that could not be bound, with the reason
+    #[serde(skip)]
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ConstantDecl {
+    pub name: String,
+    pub value: ConstValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ConstValue {
+    Number(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StructDecl {
+    pub name: String,
+    pub fields: Vec<Param>,
+}
+
+/// A named value of an `Ffi` type or declared struct.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Param {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FunctionDecl {
+    pub name: String,
+    #[serde(default)]
+    pub params: Vec<Param>,
+    #[serde(default = "void_type")]
+    pub returns: String,
+    /// `"caller"` when the returned pointer must be freed by Sald
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Library symbol that frees an owned return value
+    #[serde(default)]
+    pub free: Option<String>,
+    /// C prototype, emitted as the doc comment
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+fn void_type() -> String {
+    "void".to_string()
+}
+
+impl Bindings {
+    /// Parses a JSON description and checks that every type is known.
+    pub fn from_json(source: &str) -> Result<Self, String> {
+        let bindings: Bindings = serde_json::from_str(source)
+            .map_err(|e| format!("Invalid binding description: {}", e))?;
+
+        let mut structs: Vec<&str> = Vec::new();
+        for decl in &bindings.structs {
+            for field in &decl.fields {
+                check_type(&field.ty, &structs)
+                    .map_err(|e| format!("{}.{}: {}", decl.name, field.name, e))?;
+            }
+            structs.push(&decl.name);
+        }
+        for func in &bindings.functions {
+            check_type(&func.returns, &structs).map_err(|e| format!("{}: {}", func.name, e))?;
+            for param in &func.params {
+                check_type(&param.ty, &structs)
+                    .map_err(|e| format!("{}({}): {}", func.name, param.name, e))?;
+            }
+            match func.owner.as_deref() {
+                None | Some("caller") | Some("callee") => {}
+                Some(other) => {
+                    return Err(format!(
+                        "{}: owner must be \"caller\" or \"callee\", got \"{}\"",
+                        func.name, other
+                    ))
+                }
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// Extracts `#define` constants, enums, structs and function prototypes
+    /// from a C header. Anything that has no `Ffi` equivalent (unions,
+    /// bitfields, variadic functions) is listed in `skipped`.
+    pub fn from_header(source: &str) -> Self {
+        let mut parser = HeaderParser::default();
+        parser.parse(source);
+        parser.bindings
+    }
+}
+
+fn check_type(ty: &str, structs: &[&str]) -> Result<(), String> {
+    if FFI_TYPES.contains(&ty.to_lowercase().as_str()) || structs.contains(&ty) {
+        Ok(())
+    } else {
+        Err(format!("unknown type '{}'", ty))
+    }
+}
+
+/// What a C type name stands for once typedefs are resolved.
+#[derive(Debug, Clone, PartialEq)]
+enum Alias {
+    Type(String),
+    Opaque,
+}
+
+#[derive(Default)]
+struct HeaderParser {
+    bindings: Bindings,
+    aliases: FxHashMap<String, Alias>,
+    values: FxHashMap<String, f64>,
+}
+
+impl HeaderParser {
+    fn parse(&mut self, source: &str) {
+        let source = strip_comments(&source.replace("\\\r\n", " ").replace("\\\n", " "));
+        let mut code = String::new();
+        for line in source.lines() {
+            let trimmed = line.trim_start();
+            match trimmed.strip_prefix('#') {
+                Some(directive) => self.directive(directive.trim_start()),
+                None => {
+                    code.push_str(line);
+                    code.push('\n');
+                }
+            }
+        }
+
+        for statement in split_statements(&tokenize(&code)) {
+            self.statement(&statement);
+        }
+    }
+
+    fn directive(&mut self, directive: &str) {
+        let Some(rest) = directive.strip_prefix("define") else {
+            return;
+        };
+        let rest = rest.trim_start();
+        let name_len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (name, body) = rest.split_at(name_len);
+        // Function-like macros and empty defines have no value to bind
+        if name.is_empty() || body.starts_with('(') || body.trim().is_empty() {
+            return;
+        }
+        let tokens = tokenize(body);
+        let value = match tokens.as_slice() {
+            [s] if s.starts_with('"') => ConstValue::String(unquote(s)),
+            _ => match self.eval(&tokens) {
+                Some(n) => {
+                    self.values.insert(name.to_string(), n);
+                    ConstValue::Number(n)
+                }
+                None => return,
+            },
+        };
+        self.bindings.constants.push(ConstantDecl {
+            name: name.to_string(),
+            value,
+        });
+    }
+
+    fn statement(&mut self, tokens: &[String]) {
+        let tokens = strip_attributes(tokens);
+        let Some(first) = tokens.first() else {
+            return;
+        };
+        match first.as_str() {
+            "typedef" => self.typedef(&tokens[1..]),
+            "struct" | "union" | "enum"
+                if tokens.len() <= 2 || tokens[1..3].contains(&"{".to_string()) =>
+            {
+                self.tagged(&tokens)
+            }
+            _ if tokens.contains(&"(".to_string()) => self.function(&tokens),
+            _ => {}
+        }
+    }
+
+    /// `struct Tag { ... };`, `struct Tag;` and `enum Tag { ... };`
+    fn tagged(&mut self, tokens: &[String]) {
+        let kind = tokens[0].as_str();
+        let tag = tokens.get(1).filter(|t| is_ident(t)).cloned();
+        let Some(open) = tokens.iter().position(|t| t == "{") else {
+            if kind == "struct" {
+                if let Some(tag) = tag {
+                    self.aliases.entry(tag).or_insert(Alias::Opaque);
+                }
+            }
+            return;
+        };
+        let body = &tokens[open + 1..matching(tokens, open)];
+        match (kind, tag) {
+            ("enum", _) => self.enumeration(body),
+            ("struct", Some(tag)) => self.structure(&tag, body),
+            (_, tag) => self.bindings.skipped.push(format!(
+                "{} {}: unions have no Ffi equivalent",
+                kind,
+                tag.unwrap_or_default()
+            )),
+        }
+    }
+
+    fn typedef(&mut self, tokens: &[String]) {
+        let Some(name) = typedef_name(tokens) else {
+            return;
+        };
+        let kind = tokens.first().map(String::as_str).unwrap_or("");
+
+        if let Some(open) = tokens.iter().position(|t| t == "{") {
+            let body = &tokens[open + 1..matching(tokens, open)];
+            match kind {
+                "enum" => {
+                    self.enumeration(body);
+                    self.aliases.insert(name, Alias::Type("int".to_string()));
+                }
+                "struct" => {
+                    self.structure(&name, body);
+                    if let Some(tag) = tokens.get(1).filter(|t| is_ident(t)) {
+                        if let Some(alias) = self.aliases.get(&name).cloned() {
+                            self.aliases.insert(tag.clone(), alias);
+                        }
+                    }
+                }
+                _ => self
+                    .bindings
+                    .skipped
+                    .push(format!("{}: unions have no Ffi equivalent", name)),
+            }
+            return;
+        }
+
+        if tokens.contains(&"(".to_string()) {
+            self.aliases
+                .insert(name, Alias::Type("callback".to_string()));
+            return;
+        }
+
+        let target = &tokens[..tokens.len() - 1];
+        // `typedef struct sqlite3 sqlite3;` declares a handle type
+        if kind == "struct" && target.len() == 2 {
+            let alias = self
+                .aliases
+                .get(&target[1])
+                .cloned()
+                .unwrap_or(Alias::Opaque);
+            self.aliases.insert(name, alias);
+            return;
+        }
+        match self.resolve(target) {
+            Ok(ty) => {
+                self.aliases.insert(name, Alias::Type(ty));
+            }
+            Err(e) => self.bindings.skipped.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    fn enumeration(&mut self, body: &[String]) {
+        let mut next = 0.0;
+        for item in split_top_level(body, ",") {
+            let Some(name) = item.first().filter(|t| is_ident(t)) else {
+                continue;
+            };
+            let value = match item.iter().position(|t| t == "=") {
+                Some(eq) => match self.eval(&item[eq + 1..]) {
+                    Some(n) => n,
+                    None => continue,
+                },
+                None => next,
+            };
+            self.values.insert(name.clone(), value);
+            self.bindings.constants.push(ConstantDecl {
+                name: name.clone(),
+                value: ConstValue::Number(value),
+            });
+            next = value + 1.0;
+        }
+    }
+
+    fn structure(&mut self, name: &str, body: &[String]) {
+        match self.fields(body) {
+            Ok(fields) => {
+                self.aliases
+                    .insert(name.to_string(), Alias::Type(name.to_string()));
+                self.bindings.structs.push(StructDecl {
+                    name: name.to_string(),
+                    fields,
+                });
+            }
+            Err(e) => {
+                self.aliases.insert(name.to_string(), Alias::Opaque);
+                self.bindings
+                    .skipped
+                    .push(format!("struct {}: {}", name, e));
+            }
+        }
+    }
+
+    fn fields(&self, body: &[String]) -> Result<Vec<Param>, String> {
+        let mut fields = Vec::new();
+        for decl in split_top_level(body, ";") {
+            if decl.is_empty() {
+                continue;
+            }
+            if decl.contains(&"{".to_string()) {
+                return Err("nested struct or union bodies are not supported".to_string());
+            }
+            if decl.contains(&":".to_string()) {
+                return Err("bitfields are not supported".to_string());
+            }
+            if decl.contains(&"(".to_string()) {
+                fields.push(Param {
+                    name: fn_pointer_name(&decl).unwrap_or_default(),
+                    ty: "callback".to_string(),
+                });
+                continue;
+            }
+            // `int x, *y, z[4];` share the leading base type
+            let declarators = split_top_level(&decl, ",");
+            let base_len = declarators[0]
+                .iter()
+                .rposition(|t| is_ident(t))
+                .ok_or("field without a name")?;
+            let base = &declarators[0][..base_len];
+            for (i, declarator) in declarators.iter().enumerate() {
+                let declarator = if i == 0 {
+                    &declarator[base_len..]
+                } else {
+                    &declarator[..]
+                };
+                let stars = declarator.iter().take_while(|t| *t == "*").count();
+                let name = declarator
+                    .get(stars)
+                    .filter(|t| is_ident(t))
+                    .ok_or("field without a name")?;
+                let mut ty_tokens = base.to_vec();
+                ty_tokens.extend(std::iter::repeat_n("*".to_string(), stars));
+                let ty = self.resolve(&ty_tokens)?;
+                match declarator.iter().position(|t| t == "[") {
+                    Some(open) => {
+                        let count = self
+                            .eval(&declarator[open + 1..matching(declarator, open)])
+                            .ok_or_else(|| format!("array length of '{}' is not constant", name))?;
+                        // Arrays are laid out as consecutive fields
+                        for index in 0..count as usize {
+                            fields.push(Param {
+                                name: format!("{}_{}", name, index),
+                                ty: ty.clone(),
+                            });
+                        }
+                    }
+                    None => fields.push(Param {
+                        name: name.clone(),
+                        ty,
+                    }),
+                }
+            }
+        }
+        if fields.is_empty() {
+            return Err("struct has no fields".to_string());
+        }
+        Ok(fields)
+    }
+
+    fn function(&mut self, tokens: &[String]) {
+        let Some(open) = tokens.iter().position(|t| t == "(") else {
+            return;
+        };
+        // `int (*handler)(int);` is a variable holding a function pointer
+        if open == 0 || tokens.get(open + 1).map(String::as_str) == Some("*") {
+            return;
+        }
+        let name = tokens[open - 1].clone();
+        if !is_ident(&name) {
+            return;
+        }
+        let signature = join_tokens(tokens);
+        let ret_tokens: Vec<String> = tokens[..open - 1]
+            .iter()
+            .filter(|t| !matches!(t.as_str(), "extern" | "static" | "inline"))
+            .filter(|t| !self.is_api_macro(t))
+            .cloned()
+            .collect();
+
+        let result = self.resolve(&ret_tokens).and_then(|returns| {
+            let params = self.params(&tokens[open + 1..matching(tokens, open)])?;
+            Ok((returns, params))
+        });
+        match result {
+            Ok((returns, params)) => self.bindings.functions.push(FunctionDecl {
+                name,
+                params,
+                returns,
+                owner: None,
+                free: None,
+                signature: Some(signature),
+            }),
+            Err(e) => self.bindings.skipped.push(format!("{}: {}", name, e)),
+        }
+    }
+
+    fn params(&self, tokens: &[String]) -> Result<Vec<Param>, String> {
+        if tokens.is_empty() || (tokens.len() == 1 && tokens[0] == "void") {
+            return Ok(Vec::new());
+        }
+        let mut params = Vec::new();
+        for (index, param) in split_top_level(tokens, ",").into_iter().enumerate() {
+            if param.iter().any(|t| t == "...") {
+                return Err("variadic functions are not supported".to_string());
+            }
+            let (name, ty) = if param.contains(&"(".to_string()) {
+                (fn_pointer_name(&param), "callback".to_string())
+            } else {
+                let mut param = param;
+                // `int values[]` decays to a pointer
+                if let Some(open) = param.iter().position(|t| t == "[") {
+                    param.truncate(open);
+                    param.push("*".to_string());
+                    if let Some(last) = param.len().checked_sub(2) {
+                        param.swap(last, last + 1);
+                    }
+                }
+                let has_name = param.len() > 1
+                    && param
+                        .last()
+                        .is_some_and(|t| is_ident(t) && !self.is_type_word(t));
+                if has_name {
+                    let name = param.pop();
+                    (name, self.resolve(&param)?)
+                } else {
+                    (None, self.resolve(&param)?)
+                }
+            };
+            params.push(Param {
+                name: name.unwrap_or_else(|| format!("arg{}", index)),
+                ty,
+            });
+        }
+        Ok(params)
+    }
+
+    /// Maps C type tokens onto an `Ffi` type name or a declared struct.
+    fn resolve(&self, tokens: &[String]) -> Result<String, String> {
+        let pointers = tokens.iter().filter(|t| *t == "*").count();
+        let mut unsigned = false;
+        let mut signed = false;
+        let mut longs = 0;
+        let mut short = false;
+        let mut is_enum = false;
+        let mut words = Vec::new();
+        for token in tokens {
+            match token.as_str() {
+                "*" | "const" | "volatile" | "restrict" | "__restrict" | "struct" | "register" => {}
+                "enum" => is_enum = true,
+                "unsigned" => unsigned = true,
+                "signed" => signed = true,
+                "long" => longs += 1,
+                "short" => short = true,
+                "union" => return Err("unions have no Ffi equivalent".to_string()),
+                word => words.push(word),
+            }
+        }
+
+        let base = match words.as_slice() {
+            _ if is_enum => "int".to_string(),
+            [] | ["int"] if short => if unsigned { "ushort" } else { "short" }.to_string(),
+            [] | ["int"] if longs >= 2 => {
+                if unsigned { "ulonglong" } else { "longlong" }.to_string()
+            }
+            [] | ["int"] if longs == 1 => if unsigned { "ulong" } else { "long" }.to_string(),
+            [] | ["int"] if unsigned || signed || !words.is_empty() => {
+                if unsigned { "uint" } else { "int" }.to_string()
+            }
+            ["char"] if unsigned => "uchar".to_string(),
+            ["char"] if signed => "i8".to_string(),
+            ["double"] if longs > 0 => return Err("long double is not supported".to_string()),
+            [word] => match builtin_type(word) {
+                Some(ty) => ty.to_string(),
+                None => match self.aliases.get(*word) {
+                    Some(Alias::Type(ty)) => ty.clone(),
+                    Some(Alias::Opaque) if pointers > 0 => "pointer".to_string(),
+                    Some(Alias::Opaque) => {
+                        return Err(format!("opaque type '{}' cannot be passed by value", word))
+                    }
+                    None if pointers > 0 => "pointer".to_string(),
+                    None => return Err(format!("unknown type '{}'", word)),
+                },
+            },
+            _ => return Err(format!("unknown type '{}'", join_tokens(tokens))),
+        };
+
+        Ok(match pointers {
+            0 => base,
+            1 if base == "char" => "string".to_string(),
+            _ => "pointer".to_string(),
+        })
+    }
+
+    fn is_type_word(&self, word: &str) -> bool {
+        matches!(
+            word,
+            "const" | "volatile" | "unsigned" | "signed" | "long" | "short" | "struct" | "enum"
+        ) || builtin_type(word).is_some()
+            || self.aliases.contains_key(word)
+    }
+
+    /// Export macros like `SQLITE_API` or `DECLSPEC` in front of a prototype
+    fn is_api_macro(&self, word: &str) -> bool {
+        is_ident(word)
+            && word.len() > 1
+            && word
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+            && !self.aliases.contains_key(word)
+    }
+
+    /// Evaluates an integer constant expression over known constants.
+    fn eval(&self, tokens: &[String]) -> Option<f64> {
+        let mut pos = 0;
+        let value = self.eval_binary(tokens, &mut pos, 0)?;
+        (pos == tokens.len()).then_some(value)
+    }
+
+    fn eval_binary(&self, tokens: &[String], pos: &mut usize, min_prec: u8) -> Option<f64> {
+        let mut lhs = self.eval_unary(tokens, pos)?;
+        while let Some(op) = tokens.get(*pos) {
+            let prec = match op.as_str() {
+                "|" => 1,
+                "^" => 2,
+                "&" => 3,
+                "<" | ">" => 4,
+                "+" | "-" => 5,
+                "*" | "/" | "%" => 6,
+                _ => break,
+            };
+            if prec < min_prec {
+                break;
+            }
+            let op = op.clone();
+            *pos += 1;
+            // `<<` and `>>` arrive as two tokens
+            if op == "<" || op == ">" {
+                if tokens.get(*pos) != Some(&op) {
+                    return None;
+                }
+                *pos += 1;
+            }
+            let rhs = self.eval_binary(tokens, pos, prec + 1)?;
+            let (l, r) = (lhs as i64, rhs as i64);
+            lhs = match op.as_str() {
+                "|" => (l | r) as f64,
+                "^" => (l ^ r) as f64,
+                "&" => (l & r) as f64,
+                "<" => (l << r) as f64,
+                ">" => (l >> r) as f64,
+                "+" => lhs + rhs,
+                "-" => lhs - rhs,
+                "*" => lhs * rhs,
+                "/" if rhs != 0.0 => lhs / rhs,
+                "%" if r != 0 => (l % r) as f64,
+                _ => return None,
+            };
+        }
+        Some(lhs)
+    }
+
+    fn eval_unary(&self, tokens: &[String], pos: &mut usize) -> Option<f64> {
+        let token = tokens.get(*pos)?.clone();
+        *pos += 1;
+        match token.as_str() {
+            "-" => self.eval_unary(tokens, pos).map(|n| -n),
+            "+" => self.eval_unary(tokens, pos),
+            "~" => self.eval_unary(tokens, pos).map(|n| !(n as i64) as f64),
+            "(" => {
+                // Casts like `(int)4` are skipped
+                if tokens.get(*pos).is_some_and(|t| self.is_type_word(t)) {
+                    *pos = matching(tokens, *pos - 1) + 1;
+                    return self.eval_unary(tokens, pos);
+                }
+                let value = self.eval_binary(tokens, pos, 0)?;
+                (tokens.get(*pos).map(String::as_str) == Some(")")).then(|| {
+                    *pos += 1;
+                    value
+                })
+            }
+            _ => parse_number(&token).or_else(|| self.values.get(&token).copied()),
+        }
+    }
+}
+
+fn builtin_type(word: &str) -> Option<&'static str> {
+    Some(match word {
+        "void" => "void",
+        "char" => "char",
+        "int" => "int",
+        "float" => "float",
+        "double" => "double",
+        "bool" | "_Bool" => "u8",
+        "size_t" => "size_t",
+        "ssize_t" | "ptrdiff_t" | "intptr_t" | "off_t" => "i64",
+        "uintptr_t" => "u64",
+        "int8_t" => "i8",
+        "uint8_t" => "u8",
+        "int16_t" => "i16",
+        "uint16_t" => "u16",
+        "int32_t" => "i32",
+        "uint32_t" => "u32",
+        "int64_t" => "i64",
+        "uint64_t" => "u64",
+        _ => return None,
+    })
+}
+
+fn parse_number(token: &str) -> Option<f64> {
+    let lower = token.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        return i64::from_str_radix(hex.trim_end_matches(['u', 'l']), 16)
+            .ok()
+            .map(|n| n as f64);
+    }
+    if !lower.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    if lower.contains(['.', 'e']) {
+        return lower.trim_end_matches('f').parse().ok();
+    }
+    let digits = lower.trim_end_matches(['u', 'l']);
+    if digits.len() > 1 && digits.starts_with('0') {
+        return i64::from_str_radix(&digits[1..], 8).ok().map(|n| n as f64);
+    }
+    digits.parse().ok()
+}
+
+fn is_ident(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+}
+
+fn unquote(literal: &str) -> String {
+    literal
+        .trim_matches('"')
+        .replace("\\n", "\n")
+        .replace("\\t", "\t")
+        .replace("\\\"", "\"")
+        .replace("\\\\", "\\")
+}
+
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '\'' => {
+                out.push(c);
+                while let Some(inner) = chars.next() {
+                    out.push(inner);
+                    if inner == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            out.push(escaped);
+                        }
+                    } else if inner == c || inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        out.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        out.push('\n');
+                    }
+                    if prev == '*' && inner == '/' {
+                        break;
+                    }
+                    prev = inner;
+                }
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn tokenize(source: &str) -> Vec<String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_alphanumeric()
+            || c == '_'
+            || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()))
+        {
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(chars.len());
+        } else if chars[i..].starts_with(&['.', '.', '.']) {
+            i += 3;
+        } else {
+            i += 1;
+        }
+        tokens.push(chars[start..i].iter().collect());
+    }
+    tokens
+}
+
+/// Splits top-level declarations at `;`, dropping `extern "C"` wrappers and
+/// the bodies of inline function definitions.
+fn split_statements(tokens: &[String]) -> Vec<Vec<String>> {
+    let mut statements = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let mut open_externs = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        if depth == 0 && token == "extern" && tokens.get(i + 1).is_some_and(|t| t.starts_with('"'))
+        {
+            if tokens.get(i + 2).map(String::as_str) == Some("{") {
+                open_externs += 1;
+                i += 3;
+            } else {
+                i += 2;
+            }
+            continue;
+        }
+        if depth == 0 && token == "}" && open_externs > 0 {
+            open_externs -= 1;
+            i += 1;
+            continue;
+        }
+        match token.as_str() {
+            "{" => {
+                // A body right after `)` belongs to a function definition
+                if depth == 0 && current.last().map(String::as_str) == Some(")") {
+                    i = matching(tokens, i) + 1;
+                    current.clear();
+                    continue;
+                }
+                depth += 1;
+            }
+            "}" => depth -= 1,
+            ";" if depth == 0 => {
+                statements.push(std::mem::take(&mut current));
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        current.push(token.clone());
+        i += 1;
+    }
+    statements
+}
+
+/// Removes `__attribute__((...))` and `__declspec(...)` annotations.
+fn strip_attributes(tokens: &[String]) -> Vec<String> {
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        if matches!(
+            tokens[i].as_str(),
+            "__attribute__" | "__declspec" | "__asm__" | "__asm"
+        ) && tokens.get(i + 1).map(String::as_str) == Some("(")
+        {
+            i = matching(tokens, i + 1) + 1;
+            continue;
+        }
+        if !matches!(
+            tokens[i].as_str(),
+            "__extension__" | "__inline" | "__cdecl" | "__stdcall"
+        ) {
+            out.push(tokens[i].clone());
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Index of the bracket closing the one at `open`, or the end of `tokens`.
+fn matching(tokens: &[String], open: usize) -> usize {
+    let (opener, closer) = match tokens[open].as_str() {
+        "(" => ("(", ")"),
+        "[" => ("[", "]"),
+        _ => ("{", "}"),
+    };
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token == opener {
+            depth += 1;
+        } else if token == closer {
+            depth -= 1;
+            if depth == 0 {
+                return i;
+            }
+        }
+    }
+    tokens.len()
+}
+
+fn split_top_level(tokens: &[String], separator: &str) -> Vec<Vec<String>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0;
+    for token in tokens {
+        match token.as_str() {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" => depth -= 1,
+            t if t == separator && depth == 0 => {
+                parts.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(token.clone());
+    }
+    if parts.last().is_some_and(|p| p.is_empty()) {
+        parts.pop();
+    }
+    parts
+}
+
+/// The name declared by a typedef: the last identifier outside any braces,
+/// or the `(*name)` of a function pointer typedef.
+fn typedef_name(tokens: &[String]) -> Option<String> {
+    if !tokens.contains(&"{".to_string()) {
+        if let Some(name) = fn_pointer_name(tokens) {
+            return Some(name);
+        }
+    }
+    tokens
+        .iter()
+        .rev()
+        .find(|t| is_ident(t))
+        .filter(|_| tokens.last().is_some_and(|t| is_ident(t) || t == "]"))
+        .cloned()
+}
+
+fn fn_pointer_name(tokens: &[String]) -> Option<String> {
+    tokens
+        .windows(3)
+        .find(|w| w[0] == "(" && w[1] == "*" && is_ident(&w[2]))
+        .map(|w| w[2].clone())
+}
+
+/// Rebuilds C source from tokens with conventional spacing.
+fn join_tokens(tokens: &[String]) -> String {
+    let mut out = String::new();
+    let mut prev = "";
+    for token in tokens {
+        let word = is_ident(token) || token.starts_with(|c: char| c.is_ascii_digit());
+        let prev_word = is_ident(prev) || prev.starts_with(|c: char| c.is_ascii_digit());
+        if (word && (prev_word || prev == "," || prev == ")"))
+            || (token == "*" && prev_word)
+            || (token == "..." && prev == ",")
+        {
+            out.push(' ');
+        }
+        out.push_str(token);
+        prev = token;
+    }
+    out
+}
+
+/// Renders the bindings as a Sald module that opens `library`.
+pub fn render(bindings: &Bindings, library: &str, source_name: &str) -> String {
+    let structs: Vec<&str> = bindings.structs.iter().map(|s| s.name.as_str()).collect();
+    let type_ref = |ty: &str| {
+        if structs.contains(&ty) {
+            identifier(ty)
+        } else {
+            quote(ty)
+        }
+    };
+
+    let mut out = format!(
+        "// Generated by `sald bindgen` from {}\n\nlet _lib = Ffi.open({})\n",
+        source_name,
+        quote(library)
+    );
+
+    if !bindings.skipped.is_empty() {
+        out.push_str("\n// Not bound:\n");
+        for skipped in &bindings.skipped {
+            out.push_str(&format!("//   {}\n", skipped));
+        }
+    }
+
+    if !bindings.constants.is_empty() {
+        out.push('\n');
+        for constant in &bindings.constants {
+            let value = match &constant.value {
+                ConstValue::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => {
+                    format!("{}", *n as i64)
+                }
+                ConstValue::Number(n) => n.to_string(),
+                ConstValue::String(s) => quote(s),
+            };
+            out.push_str(&format!(
+                "const {} = {}\n",
+                identifier(&constant.name),
+                value
+            ));
+        }
+    }
+
+    for decl in &bindings.structs {
+        let fields: Vec<String> = decl
+            .fields
+            .iter()
+            .map(|f| format!("[{}, {}]", quote(&f.name), type_ref(&f.ty)))
+            .collect();
+        out.push_str(&format!(
+            "\nconst {} = Ffi.Struct([{}])\n",
+            identifier(&decl.name),
+            fields.join(", ")
+        ));
+    }
+
+    for func in &bindings.functions {
+        let names: Vec<String> = func.params.iter().map(|p| identifier(&p.name)).collect();
+        let args: Vec<String> = func
+            .params
+            .iter()
+            .zip(&names)
+            .map(|(p, name)| format!("{{\"type\": {}, \"value\": {}}}", type_ref(&p.ty), name))
+            .collect();
+        let mut options = format!(
+            "\"args\": [{}], \"returns\": {}",
+            args.join(", "),
+            type_ref(&func.returns)
+        );
+        if let Some(owner) = &func.owner {
+            options.push_str(&format!(", \"owner\": {}", quote(owner)));
+        }
+        if let Some(free) = &func.free {
+            options.push_str(&format!(", \"free\": {}", quote(free)));
+        }
+
+        out.push('\n');
+        if let Some(signature) = &func.signature {
+            out.push_str(&format!("/// {}\n", signature));
+        }
+        out.push_str(&format!(
+            "fun {}({}) {{\n    return _lib.call({}, {{{}}})\n}}\n",
+            identifier(&func.name),
+            names.join(", "),
+            quote(&func.name),
+            options
+        ));
+    }
+
+    // Long calls are wrapped the same way `sald fmt` would
+    crate::fmt::format_source(&out, source_name).unwrap_or(out)
+}
+
+/// C names that collide with Sald keywords get a trailing underscore.
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) || name == "self" {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Name, `(name, type)` parameters and return type of a bound function
+    type Signature<'a> = (&'a str, Vec<(&'a str, &'a str)>, &'a str);
+
+    const HEADER: &str = r#"
+#ifndef DEMO_H
+#define DEMO_H
+#define DEMO_VERSION "1.2"
+#define DEMO_MAX (16 * 4)
+#define DEMO_FLAG (1 << 3)
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct demo_db demo_db;
+typedef int (*demo_cb)(void *ctx, int code);
+
+/* A point */
+typedef struct demo_point {
+    int x, y;
+    double weights[2];
+} demo_point;
+
+typedef enum { DEMO_OK, DEMO_ERR = -1, DEMO_BUSY = DEMO_FLAG | 1 } demo_status;
+
+DEMO_API int demo_open(const char *path, demo_db **out);
+demo_point demo_mid(demo_point a, struct demo_point b);
+unsigned long long demo_count(demo_db *, demo_cb cb);
+void demo_log(const char *fmt, ...);
+static inline int demo_twice(int x) { return x * 2; }
+union demo_value { int i; float f; };
+
+#ifdef __cplusplus
+}
+#endif
+#endif
+"#;
+
+    #[test]
+    fn test_header_declarations_are_extracted() {
+        let bindings = Bindings::from_header(HEADER);
+
+        let constants: Vec<(&str, &ConstValue)> = bindings
+            .constants
+            .iter()
+            .map(|c| (c.name.as_str(), &c.value))
+            .collect();
+        assert_eq!(
+            constants,
+            [
+                ("DEMO_VERSION", &ConstValue::String("1.2".to_string())),
+                ("DEMO_MAX", &ConstValue::Number(64.0)),
+                ("DEMO_FLAG", &ConstValue::Number(8.0)),
+                ("DEMO_OK", &ConstValue::Number(0.0)),
+                ("DEMO_ERR", &ConstValue::Number(-1.0)),
+                ("DEMO_BUSY", &ConstValue::Number(9.0)),
+            ]
+        );
+
+        let point = &bindings.structs[0];
+        assert_eq!(point.name, "demo_point");
+        let fields: Vec<(&str, &str)> = point
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.ty.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("x", "int"),
+                ("y", "int"),
+                ("weights_0", "double"),
+                ("weights_1", "double")
+            ]
+        );
+
+        let functions: Vec<Signature> = bindings
+            .functions
+            .iter()
+            .map(|f| {
+                (
+                    f.name.as_str(),
+                    f.params
+                        .iter()
+                        .map(|p| (p.name.as_str(), p.ty.as_str()))
+                        .collect(),
+                    f.returns.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            functions,
+            [
+                (
+                    "demo_open",
+                    vec![("path", "string"), ("out", "pointer")],
+                    "int"
+                ),
+                (
+                    "demo_mid",
+                    vec![("a", "demo_point"), ("b", "demo_point")],
+                    "demo_point"
+                ),
+                (
+                    "demo_count",
+                    vec![("arg0", "pointer"), ("cb", "callback")],
+                    "ulonglong"
+                ),
+            ]
+        );
+        assert_eq!(
+            bindings.functions[0].signature.as_deref(),
+            Some("DEMO_API int demo_open(const char *path, demo_db **out)")
+        );
+        assert!(bindings.skipped.iter().any(|s| s.starts_with("demo_log:")));
+        assert!(bindings.skipped.iter().any(|s| s.contains("demo_value")));
+    }
+
+    #[test]
+    fn test_json_description_renders_module() {
+        let bindings = Bindings::from_json(
+            r#"{
+                "library": "libdemo.so",
+                "constants": [{"name": "LIMIT", "value": 3}],
+                "structs": [{"name": "Pair", "fields": [{"name": "a", "type": "int"}, {"name": "b", "type": "int"}]}],
+                "functions": [
+                    {"name": "pair_sum", "params": [{"name": "pair", "type": "Pair"}], "returns": "int"},
+                    {"name": "name_of", "params": [{"name": "fun", "type": "int"}], "returns": "string", "owner": "caller", "free": "demo_free"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let module = render(&bindings, "libdemo.so", "demo.json");
+
+        assert!(module.contains("let _lib = Ffi.open(\"libdemo.so\")\n"));
+        assert!(module.contains("const LIMIT = 3\n"));
+        assert!(module.contains("const Pair = Ffi.Struct([[\"a\", \"int\"], [\"b\", \"int\"]])\n"));
+        assert!(module.contains(
+            "fun pair_sum(pair) {\n    return _lib.call(\"pair_sum\", {\"args\": [{\"type\": Pair, \"value\": pair}], \"returns\": \"int\"})\n}\n"
+        ));
+        assert!(module.contains("fun name_of(fun_) {"));
+        assert!(
+            module.contains("        \"owner\": \"caller\",\n        \"free\": \"demo_free\"\n")
+        );
+
+        let unknown = Bindings::from_json(
+            r#"{"functions": [{"name": "f", "params": [{"name": "x", "type": "quad"}]}]}"#,
+        );
+        assert_eq!(unknown.unwrap_err(), "f(x): unknown type 'quad'");
+    }
+}
@@ -0,0 +1,1209 @@
+//! Source formatter
+//! Prints a parsed program back with canonical spacing and four-space
+//! indentation. Literals keep their original spelling, and comments, which the
+//! scanner drops, are recovered from the gaps between tokens
+
+use crate::ast::{
+    block_function, ArrayPatternElement, AssignOp, BinaryOp, CallArg, Expr, FunctionDef,
+    FunctionParam, InterfaceMethodDef, LambdaBody, Literal, Pattern, Stmt, SwitchArm,
+    SwitchArrayElement, UnaryOp,
+};
+use crate::error::{SaldResult, Span};
+use crate::lexer::{Scanner, Token, TokenKind};
+use crate::parser::Parser;
+use rustc_hash::FxHashMap;
+
+const INDENT: &str = "    ";
+const MAX_WIDTH: usize = 100;
+
+/// Formats a whole file.
+pub fn format_source(source: &str, file: &str) -> SaldResult<String> {
+    let tokens = Scanner::new(source, file).scan_tokens()?;
+    let program = Parser::new(tokens.clone(), file, source).parse()?;
+    let mut printer = Printer::new(source, tokens);
+    Ok(printer.statements(&program.statements, 0, usize::MAX))
+}
+
+/// Formats the top-level statements overlapping `start_line..=end_line`
+/// (1-based). Returns the first and last source line covered together with
+/// their replacement, or `None` if the file does not parse or the range holds
+/// no statement.
+pub fn format_range(
+    source: &str,
+    file: &str,
+    start_line: usize,
+    end_line: usize,
+) -> Option<(usize, usize, String)> {
+    let tokens = Scanner::new(source, file).scan_tokens().ok()?;
+    let program = Parser::new(tokens.clone(), file, source).parse().ok()?;
+    let statements = &program.statements;
+
+    let first = statements
+        .iter()
+        .position(|s| s.span().end.line >= start_line)?;
+    let last = statements
+        .iter()
+        .rposition(|s| start_line_of(s) <= end_line)?;
+    if first > last {
+        return None;
+    }
+
+    let first_line = start_line_of(&statements[first]);
+    let last_line = statements[last].span().end.line;
+
+    let mut printer = Printer::new(source, tokens);
+    printer
+        .comments
+        .retain(|c| c.line >= first_line && c.end_line <= last_line);
+    let text = printer.statements(&statements[first..=last], 0, last_line + 1);
+    Some((first_line, last_line, text))
+}
+
+struct Comment {
+    line: usize,
+    end_line: usize,
+    text: String,
+    /// Token the comment follows on the same line
+    after: Option<usize>,
+}
+
+enum Item<'a> {
+    Expr(&'a Expr),
+    Arg(&'a CallArg),
+    Entry(&'a Expr, &'a Expr),
+}
+
+impl Item<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Item::Expr(expr) => expr.span(),
+            Item::Arg(arg) => arg.span,
+            Item::Entry(key, value) => Span::from_positions(
+                key.span().start.line,
+                key.span().start.column,
+                value.span().end.line,
+                value.span().end.column,
+            ),
+        }
+    }
+}
+
+type SpanKey = (usize, usize, usize, usize);
+
+/// End of a span as (line, column).
+fn position(span: Span) -> (usize, usize) {
+    (span.end.line, span.end.column)
+}
+
+fn key(span: Span) -> SpanKey {
+    (
+        span.start.line,
+        span.start.column,
+        span.end.line,
+        span.end.column,
+    )
+}
+
+struct Printer {
+    chars: Vec<char>,
+    tokens: Vec<Token>,
+    /// Char range of each token in the source
+    offsets: Vec<(usize, usize)>,
+    /// Literal tokens by span, to print them as written
+    literals: FxHashMap<SpanKey, usize>,
+    /// Index of the closing token of each format string
+    format_ends: FxHashMap<usize, usize>,
+    /// `enum` keywords by position, to find the lines of the variants
+    enums: FxHashMap<(usize, usize), usize>,
+    /// Tokens by end position, to attach trailing comments
+    ends: FxHashMap<(usize, usize), usize>,
+    comments: Vec<Comment>,
+    next_comment: usize,
+}
+
+impl Printer {
+    fn new(source: &str, tokens: Vec<Token>) -> Self {
+        let chars: Vec<char> = source.chars().collect();
+        let mut offsets = Vec::with_capacity(tokens.len());
+        let mut comments = Vec::new();
+        let mut pos = 0;
+        let mut line = 1;
+        let mut last_token_line = 0;
+
+        for (index, token) in tokens.iter().enumerate() {
+            let after = index.checked_sub(1);
+            loop {
+                match (chars.get(pos), chars.get(pos + 1)) {
+                    (Some('\n'), _) => {
+                        line += 1;
+                        pos += 1;
+                    }
+                    (Some(c), _) if c.is_whitespace() => pos += 1,
+                    (Some('/'), Some('/')) => {
+                        let start = pos;
+                        while pos < chars.len() && chars[pos] != '\n' {
+                            pos += 1;
+                        }
+                        let text: String = chars[start..pos].iter().collect();
+                        comments.push(Comment {
+                            line,
+                            end_line: line,
+                            text: text.trim_end().to_string(),
+                            after: after.filter(|_| last_token_line == line),
+                        });
+                    }
+                    (Some('/'), Some('*')) => {
+                        let start = pos;
+                        let start_line = line;
+                        let mut depth = 0;
+                        while pos < chars.len() {
+                            if chars[pos] == '/' && chars.get(pos + 1) == Some(&'*') {
+                                depth += 1;
+                                pos += 2;
+                            } else if chars[pos] == '*' && chars.get(pos + 1) == Some(&'/') {
+                                depth -= 1;
+                                pos += 2;
+                                if depth == 0 {
+                                    break;
+                                }
+                            } else {
+                                if chars[pos] == '\n' {
+                                    line += 1;
+                                }
+                                pos += 1;
+                            }
+                        }
+                        comments.push(Comment {
+                            line: start_line,
+                            end_line: line,
+                            text: chars[start..pos].iter().collect(),
+                            after: after.filter(|_| last_token_line == start_line),
+                        });
+                    }
+                    _ => break,
+                }
+            }
+
+            let len = token.lexeme.chars().count();
+            offsets.push((pos, pos + len));
+            pos += len;
+            line += token.lexeme.matches('\n').count();
+            last_token_line = line;
+        }
+
+        let mut literals = FxHashMap::default();
+        let mut format_ends = FxHashMap::default();
+        let mut enums = FxHashMap::default();
+        let mut ends = FxHashMap::default();
+        let mut open_formats = Vec::new();
+        for (index, token) in tokens.iter().enumerate() {
+            ends.entry(position(token.span)).or_insert(index);
+            match token.kind {
+                TokenKind::Number(_)
+                | TokenKind::String(_)
+                | TokenKind::RawString(_)
+                | TokenKind::DateLiteral(_)
+                | TokenKind::TimeLiteral(_) => {
+                    literals.entry(key(token.span)).or_insert(index);
+                }
+                TokenKind::FormatStringStart(_) => {
+                    literals.entry(key(token.span)).or_insert(index);
+                    open_formats.push(index);
+                }
+                TokenKind::FormatStringEnd(_) => {
+                    if let Some(start) = open_formats.pop() {
+                        format_ends.insert(start, index);
+                    }
+                }
+                TokenKind::Enum => {
+                    enums.insert((token.span.start.line, token.span.start.column), index);
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            chars,
+            tokens,
+            offsets,
+            literals,
+            format_ends,
+            enums,
+            ends,
+            comments,
+            next_comment: 0,
+        }
+    }
+
+    // Comments
+
+    /// Emits the pending comments that start before `line` on their own lines.
+    fn comments_before(
+        &mut self,
+        line: usize,
+        indent: usize,
+        prev_end: &mut Option<usize>,
+    ) -> String {
+        let mut out = String::new();
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.line >= line {
+                break;
+            }
+            if prev_end.is_some_and(|end| comment.line > end + 1) {
+                out.push('\n');
+            }
+            out.push_str(&pad(indent));
+            out.push_str(&comment.text);
+            out.push('\n');
+            *prev_end = Some(comment.end_line);
+            self.next_comment += 1;
+        }
+        out
+    }
+
+    fn has_comment_before(&self, line: usize) -> bool {
+        self.comments
+            .get(self.next_comment)
+            .is_some_and(|c| c.line < line)
+    }
+
+    /// Takes the next comment if it directly follows the code ending at
+    /// `end`, or the comma after it, on the same line.
+    fn trailing_comment(&mut self, end: (usize, usize)) -> Option<String> {
+        let comment = self.comments.get(self.next_comment)?;
+        let after = comment.after?;
+        let &last = self.ends.get(&end)?;
+        let follows =
+            after == last || (after == last + 1 && self.tokens[after].kind == TokenKind::Comma);
+        if !follows {
+            return None;
+        }
+        self.next_comment += 1;
+        Some(comment.text.clone())
+    }
+
+    // Statements
+
+    /// Prints `items` one per line followed by any comments before
+    /// `close_line`, keeping single blank lines from the source.
+    /// `separator` goes after every item but the last.
+    fn lines<T>(
+        &mut self,
+        items: &[T],
+        indent: usize,
+        close_line: usize,
+        separator: &str,
+        lines_of: fn(&T) -> (usize, (usize, usize)),
+        print: fn(&mut Self, &T, usize) -> String,
+    ) -> String {
+        let mut out = String::new();
+        let mut prev_end = None;
+        for (index, item) in items.iter().enumerate() {
+            let (start, end) = lines_of(item);
+            out.push_str(&self.comments_before(start, indent, &mut prev_end));
+            if prev_end.is_some_and(|prev| start > prev + 1) {
+                out.push('\n');
+            }
+            out.push_str(&pad(indent));
+            out.push_str(&print(self, item, indent));
+            if index + 1 < items.len() {
+                out.push_str(separator);
+            }
+            if let Some(comment) = self.trailing_comment(end) {
+                out.push(' ');
+                out.push_str(&comment);
+            }
+            out.push('\n');
+            prev_end = Some(end.0);
+        }
+        out.push_str(&self.comments_before(close_line, indent, &mut prev_end));
+        out
+    }
+
+    fn statements(&mut self, statements: &[Stmt], indent: usize, close_line: usize) -> String {
+        self.lines(
+            statements,
+            indent,
+            close_line,
+            "",
+            |s| (start_line_of(s), position(s.span())),
+            Self::stmt,
+        )
+    }
+
+    /// A braced block of statements ending on `close_line`.
+    fn body(&mut self, statements: &[Stmt], indent: usize, close_line: usize) -> String {
+        let inner = self.statements(statements, indent + 1, close_line);
+        braced(inner, indent)
+    }
+
+    fn branch(&mut self, stmt: &Stmt, indent: usize) -> String {
+        match stmt {
+            Stmt::Block { statements, span } => self.body(statements, indent, span.end.line),
+            _ => self.stmt(stmt, indent),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt, indent: usize) -> String {
+        match stmt {
+            Stmt::Let {
+                name, initializer, ..
+            } => match initializer {
+                Some(value) => format!("let {} = {}", name, self.expr(value, indent)),
+                None => format!("let {}", name),
+            },
+            Stmt::LetDestructure {
+                pattern,
+                initializer,
+                ..
+            } => {
+                let elements: Vec<String> = pattern
+                    .elements
+                    .iter()
+                    .map(|element| match element {
+                        ArrayPatternElement::Variable { name, .. } => name.clone(),
+                        ArrayPatternElement::Rest { name, .. } => format!("...{}", name),
+                        ArrayPatternElement::Hole => String::new(),
+                    })
+                    .collect();
+                format!(
+                    "let [{}] = {}",
+                    elements.join(", "),
+                    self.expr(initializer, indent)
+                )
+            }
+            Stmt::Expression { expr, .. } => self.expr(expr, indent),
+            Stmt::Block { statements, span } => self.body(statements, indent, span.end.line),
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                let mut out = format!(
+                    "if {} {}",
+                    self.expr(condition, indent),
+                    self.branch(then_branch, indent)
+                );
+                if let Some(else_branch) = else_branch {
+                    out.push_str(" else ");
+                    out.push_str(&self.branch(else_branch, indent));
+                }
+                out
+            }
+            Stmt::While {
+                condition, body, ..
+            } => format!(
+                "while {} {}",
+                self.expr(condition, indent),
+                self.branch(body, indent)
+            ),
+            Stmt::DoWhile {
+                body, condition, ..
+            } => {
+                let body = self.branch(body, indent);
+                format!("do {} while {}", body, self.expr(condition, indent))
+            }
+            Stmt::Function { def } => self.function(def, indent),
+            Stmt::Return { value, .. } => match value {
+                Some(value) => format!("return {}", self.expr(value, indent)),
+                None => "return".to_string(),
+            },
+            Stmt::Class { def } => {
+                let mut out = self.decorators(&def.decorators, indent);
+                out.push_str("class ");
+                out.push_str(&def.name);
+                if let Some(superclass) = &def.superclass {
+                    out.push_str(" extends ");
+                    out.push_str(superclass);
+                }
+                if !def.implements.is_empty() {
+                    out.push_str(" implements ");
+                    out.push_str(&def.implements.join(", "));
+                }
+                out.push(' ');
+                let inner = self.lines(
+                    &def.methods,
+                    indent + 1,
+                    def.span.end.line,
+                    "",
+                    |m| (function_start_line(m), position(m.span)),
+                    Self::function,
+                );
+                out.push_str(&braced(inner, indent));
+                out
+            }
+            Stmt::For {
+                variable,
+                iterable,
+                body,
+                ..
+            } => format!(
+                "for {} in {} {}",
+                variable,
+                self.expr(iterable, indent),
+                self.branch(body, indent)
+            ),
+            Stmt::Break { .. } => "break".to_string(),
+            Stmt::Continue { .. } => "continue".to_string(),
+            Stmt::Debugger { .. } => "debugger".to_string(),
+            Stmt::Cfg {
+                predicates, body, ..
+            } => {
+                let predicates: Vec<String> = predicates
+                    .iter()
+                    .map(|p| format!("{}: {}", p.key, quote(&p.value)))
+                    .collect();
+                format!(
+                    "@cfg({})\n{}{}",
+                    predicates.join(", "),
+                    pad(indent),
+                    self.stmt(body, indent)
+                )
+            }
+            Stmt::Import { path, alias, .. } => match alias {
+                Some(alias) => format!("import {} as {}", quote(path), alias),
+                None => format!("import {}", quote(path)),
+            },
+            Stmt::Export { names, .. } => format!("export {}", names.join(", ")),
+            Stmt::TryCatch {
+                try_body,
+                catch_var,
+                catch_body,
+                ..
+            } => {
+                let try_body = self.branch(try_body, indent);
+                format!(
+                    "try {} catch {} {}",
+                    try_body,
+                    catch_var,
+                    self.branch(catch_body, indent)
+                )
+            }
+            Stmt::Throw { value, .. } => format!("throw {}", self.expr(value, indent)),
+            Stmt::Namespace { name, body, span } => {
+                let inner = self.statements(body, indent + 1, span.end.line);
+                format!("namespace {} {}", name, braced(inner, indent))
+            }
+            Stmt::Const { name, value, .. } => {
+                format!("const {} = {}", name, self.expr(value, indent))
+            }
+            Stmt::Enum {
+                name,
+                variants,
+                span,
+            } => {
+                let flat = format!("enum {} {{ {} }}", name, variants.join(", "));
+                if variants.is_empty() {
+                    format!("enum {} {{}}", name)
+                } else if span.start.line == span.end.line
+                    && !self.has_comment_before(span.end.line)
+                    && fits(&flat, indent)
+                {
+                    flat
+                } else {
+                    let ends = self.variant_ends(*span, variants.len());
+                    let variants: Vec<(&String, (usize, usize))> =
+                        variants.iter().zip(ends).collect();
+                    let inner = self.lines(
+                        &variants,
+                        indent + 1,
+                        span.end.line,
+                        ",",
+                        |(_, end)| (end.0, *end),
+                        |_, (name, _), _| name.to_string(),
+                    );
+                    format!("enum {} {}", name, braced(inner, indent))
+                }
+            }
+            Stmt::Interface { def } => {
+                let inner = self.lines(
+                    &def.methods,
+                    indent + 1,
+                    def.span.end.line,
+                    "",
+                    |m| (m.span.start.line, position(m.span)),
+                    Self::interface_method,
+                );
+                format!("interface {} {}", def.name, braced(inner, indent))
+            }
+        }
+    }
+
+    /// End position of each variant of the enum declared at `span`.
+    fn variant_ends(&self, span: Span, count: usize) -> Vec<(usize, usize)> {
+        let ends: Vec<(usize, usize)> = self
+            .enums
+            .get(&(span.start.line, span.start.column))
+            .map(|&index| {
+                self.tokens[index..]
+                    .iter()
+                    .skip(3)
+                    .take_while(|t| t.kind != TokenKind::RightBrace)
+                    .filter(|t| matches!(t.kind, TokenKind::Identifier(_)))
+                    .map(|t| position(t.span))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if ends.len() == count {
+            ends
+        } else {
+            vec![position(span); count]
+        }
+    }
+
+    fn decorators(&mut self, decorators: &[crate::ast::Decorator], indent: usize) -> String {
+        let mut out = String::new();
+        for decorator in decorators {
+            out.push('@');
+            out.push_str(&decorator.name);
+            if !decorator.args.is_empty() {
+                let args: Vec<String> = decorator
+                    .args
+                    .iter()
+                    .map(|arg| self.expr(arg, indent))
+                    .collect();
+                out.push_str(&format!("({})", args.join(", ")));
+            }
+            out.push('\n');
+            out.push_str(&pad(indent));
+        }
+        out
+    }
+
+    fn function(&mut self, def: &FunctionDef, indent: usize) -> String {
+        let mut out = self.decorators(&def.decorators, indent);
+        if def.is_async {
+            out.push_str("async ");
+        }
+        let params = self.params(&def.params, indent);
+        out.push_str(&format!("fun {}({}) ", def.name, params));
+        out.push_str(&self.body(&def.body, indent, def.span.end.line));
+        out
+    }
+
+    fn interface_method(&mut self, method: &InterfaceMethodDef, indent: usize) -> String {
+        format!(
+            "fun {}({})",
+            method.name,
+            self.params(&method.params, indent)
+        )
+    }
+
+    fn params(&mut self, params: &[FunctionParam], indent: usize) -> String {
+        let params: Vec<String> = params
+            .iter()
+            .map(|param| {
+                let prefix = if param.is_variadic {
+                    "..."
+                } else if param.is_kwargs {
+                    "**"
+                } else {
+                    ""
+                };
+                match &param.default_value {
+                    Some(value) => {
+                        format!("{}{} = {}", prefix, param.name, self.expr(value, indent))
+                    }
+                    None => format!("{}{}", prefix, param.name),
+                }
+            })
+            .collect();
+        params.join(", ")
+    }
+
+    // Expressions
+
+    fn expr(&mut self, expr: &Expr, indent: usize) -> String {
+        match expr {
+            Expr::Literal { value, span } => self.literal(value, *span),
+            Expr::Identifier { name, .. } => name.clone(),
+            Expr::Binary {
+                left, op, right, ..
+            } => {
+                if let Some(text) = self.format_string(expr) {
+                    return text;
+                }
+                if matches!(op, BinaryOp::And | BinaryOp::Or) {
+                    return self.logical_chain(expr, op, indent);
+                }
+                let left = self.expr(left, indent);
+                format!("{} {} {}", left, binary_op(op), self.expr(right, indent))
+            }
+            Expr::Unary { op, operand, .. } => {
+                let op = match op {
+                    UnaryOp::Negate => "-",
+                    UnaryOp::Not => "!",
+                    UnaryOp::BitNot => "~",
+                };
+                format!("{}{}", op, self.expr(operand, indent))
+            }
+            Expr::Grouping { expr, .. } => format!("({})", self.expr(expr, indent)),
+            Expr::Assignment {
+                target, op, value, ..
+            } => {
+                let target = self.expr(target, indent);
+                format!("{} {} {}", target, assign_op(op), self.expr(value, indent))
+            }
+            Expr::Call {
+                callee,
+                args,
+                is_optional,
+                span,
+            } => {
+                let callee = self.expr(callee, indent);
+                let items: Vec<Item> = args.iter().map(Item::Arg).collect();
+                let args = self.list("(", ")", &items, *span, false, indent);
+                let question = if *is_optional { "?" } else { "" };
+                format!("{}{}{}", callee, question, args)
+            }
+            Expr::Get {
+                object,
+                property,
+                is_optional,
+                ..
+            } => {
+                let dot = if *is_optional { "?." } else { "." };
+                format!("{}{}{}", self.expr(object, indent), dot, property)
+            }
+            Expr::Set {
+                object,
+                property,
+                value,
+                ..
+            } => {
+                let object = self.expr(object, indent);
+                format!("{}.{} = {}", object, property, self.expr(value, indent))
+            }
+            Expr::SelfExpr { .. } => "self".to_string(),
+            Expr::Array { elements, span } => {
+                let items: Vec<Item> = elements.iter().map(Item::Expr).collect();
+                let multiline = elements
+                    .first()
+                    .is_some_and(|e| e.span().start.line > span.start.line);
+                self.list("[", "]", &items, *span, multiline, indent)
+            }
+            Expr::Index {
+                object,
+                index,
+                is_optional,
+                ..
+            } => {
+                let object = self.expr(object, indent);
+                let question = if *is_optional { "?" } else { "" };
+                format!("{}{}[{}]", object, question, self.expr(index, indent))
+            }
+            Expr::IndexSet {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                let object = self.expr(object, indent);
+                let index = self.expr(index, indent);
+                format!("{}[{}] = {}", object, index, self.expr(value, indent))
+            }
+            Expr::Ternary {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => {
+                let condition = self.expr(condition, indent);
+                let then_expr = self.expr(then_expr, indent);
+                format!(
+                    "{} ? {} : {}",
+                    condition,
+                    then_expr,
+                    self.expr(else_expr, indent)
+                )
+            }
+            Expr::Lambda {
+                params,
+                body,
+                is_async,
+                span,
+            } => {
+                let mut out = String::new();
+                if *is_async {
+                    out.push_str("async ");
+                }
+                if params.is_empty() {
+                    out.push_str("|| ");
+                } else {
+                    out.push_str(&format!("|{}| ", self.params(params, indent)));
+                }
+                match body {
+                    LambdaBody::Expr(body) => out.push_str(&self.expr(body, indent)),
+                    LambdaBody::Block(statements) => {
+                        out.push_str(&self.body(statements, indent, span.end.line))
+                    }
+                }
+                out
+            }
+            Expr::Super { method, .. } => format!("super.{}", method),
+            Expr::Switch {
+                value,
+                arms,
+                default,
+                span,
+            } => self.switch(value, arms, default.as_deref(), *span, indent),
+            Expr::Block {
+                statements,
+                expr,
+                span,
+            } => {
+                if let Some(def) = block_function(statements, expr.as_deref()) {
+                    let params = self.params(&def.params, indent);
+                    let name = if def.name.starts_with('<') {
+                        ""
+                    } else {
+                        &def.name
+                    };
+                    let body = self.body(&def.body, indent, def.span.end.line);
+                    return format!("fun {}({}) {}", name, params, body);
+                }
+                let mut statements = statements.clone();
+                if let Some(expr) = expr {
+                    statements.push(Stmt::Expression {
+                        expr: (**expr).clone(),
+                        span: expr.span(),
+                    });
+                }
+                self.body(&statements, indent, span.end.line)
+            }
+            Expr::Dictionary { entries, span } => {
+                let items: Vec<Item> = entries.iter().map(|(k, v)| Item::Entry(k, v)).collect();
+                let multiline = entries
+                    .first()
+                    .is_some_and(|(k, _)| k.span().start.line > span.start.line);
+                self.list("{", "}", &items, *span, multiline, indent)
+            }
+            Expr::Await { expr, .. } => format!("await {}", self.expr(expr, indent)),
+            Expr::Import { path, .. } => format!("import({})", self.expr(path, indent)),
+            Expr::Return { value, .. } => match value {
+                Some(value) => format!("return {}", self.expr(value, indent)),
+                None => "return".to_string(),
+            },
+            Expr::Throw { value, .. } => format!("throw {}", self.expr(value, indent)),
+            Expr::Break { .. } => "break".to_string(),
+            Expr::Continue { .. } => "continue".to_string(),
+            Expr::Spread { expr, .. } => format!("...{}", self.expr(expr, indent)),
+            Expr::Range {
+                start,
+                end,
+                inclusive,
+                ..
+            } => {
+                let start = self.expr(start, indent);
+                let op = if *inclusive { ".." } else { "..<" };
+                format!("{}{}{}", start, op, self.expr(end, indent))
+            }
+        }
+    }
+
+    /// `a && b && c`, broken after each operator when it does not fit.
+    fn logical_chain(&mut self, expr: &Expr, op: &BinaryOp, indent: usize) -> String {
+        let mut operands = Vec::new();
+        let mut current = expr;
+        while let Expr::Binary {
+            left,
+            op: current_op,
+            right,
+            ..
+        } = current
+        {
+            if current_op != op {
+                break;
+            }
+            operands.push(&**right);
+            current = left;
+        }
+        operands.push(current);
+        operands.reverse();
+
+        let saved = self.next_comment;
+        let parts: Vec<String> = operands.iter().map(|o| self.expr(o, indent)).collect();
+        let flat = parts.join(&format!(" {} ", binary_op(op)));
+        if fits(&flat, indent) {
+            return flat;
+        }
+        self.next_comment = saved;
+        let parts: Vec<String> = operands.iter().map(|o| self.expr(o, indent + 1)).collect();
+        parts.join(&format!(" {}\n{}", binary_op(op), pad(indent + 1)))
+    }
+
+    fn literal(&self, value: &Literal, span: Span) -> String {
+        match self.literals.get(&key(span)) {
+            Some(&index) => self.tokens[index].lexeme.clone(),
+            None => match value {
+                Literal::Number(n) => n.to_string(),
+                Literal::String(s) => quote(s),
+                Literal::Boolean(b) => b.to_string(),
+                Literal::Null => "null".to_string(),
+                Literal::Date(s) => format!("d\"{}\"", s),
+                Literal::Time(s) => format!("t\"{}\"", s),
+            },
+        }
+    }
+
+    /// The parser lowers `$"..."` into string concatenation; a chain starting
+    /// at a format string token and ending inside it is printed as written.
+    fn format_string(&self, expr: &Expr) -> Option<String> {
+        let mut leftmost = expr;
+        while let Expr::Binary {
+            left,
+            op: BinaryOp::Add,
+            ..
+        } = leftmost
+        {
+            leftmost = left;
+        }
+        let Expr::Literal { span, .. } = leftmost else {
+            return None;
+        };
+        let start = *self.literals.get(&key(*span))?;
+        let end = *self.format_ends.get(&start)?;
+
+        let close = self.tokens[end].span.end;
+        let expr_end = expr.span().end;
+        if (expr_end.line, expr_end.column) > (close.line, close.column) {
+            return None;
+        }
+        Some(
+            self.chars[self.offsets[start].0..self.offsets[end].1]
+                .iter()
+                .collect(),
+        )
+    }
+
+    fn item(&mut self, item: &Item, indent: usize) -> String {
+        match item {
+            Item::Expr(expr) => self.expr(expr, indent),
+            Item::Arg(CallArg {
+                is_dict_spread: true,
+                value: Expr::Spread { expr, .. },
+                ..
+            }) => format!("**{}", self.expr(expr, indent)),
+            Item::Arg(arg) => {
+                let value = self.expr(&arg.value, indent);
+                match &arg.name {
+                    Some(name) => format!("{}: {}", name, value),
+                    None => value,
+                }
+            }
+            Item::Entry(_, Expr::Spread { expr, .. }) => format!("**{}", self.expr(expr, indent)),
+            Item::Entry(key, value) => {
+                let key = self.expr(key, indent);
+                format!("{}: {}", key, self.expr(value, indent))
+            }
+        }
+    }
+
+    /// Prints a bracketed list on one line when it fits, otherwise one item
+    /// per line. Lists written across lines in the source stay broken.
+    fn list(
+        &mut self,
+        open: &str,
+        close: &str,
+        items: &[Item],
+        span: Span,
+        multiline: bool,
+        indent: usize,
+    ) -> String {
+        let saved = self.next_comment;
+        if !multiline {
+            let parts: Vec<String> = items.iter().map(|item| self.item(item, indent)).collect();
+            let flat = format!("{}{}{}", open, parts.join(", "), close);
+            // Comments left over inside the list need lines of their own
+            if fits(&flat, indent) && !self.has_comment_before(span.end.line) {
+                return flat;
+            }
+            self.next_comment = saved;
+        }
+        if items.is_empty() {
+            return format!("{}{}", open, close);
+        }
+
+        let inner = self.lines(
+            items,
+            indent + 1,
+            span.end.line,
+            ",",
+            |item| (item.span().start.line, position(item.span())),
+            Self::item,
+        );
+        format!("{}\n{}{}{}", open, inner, pad(indent), close)
+    }
+
+    fn switch(
+        &mut self,
+        value: &Expr,
+        arms: &[SwitchArm],
+        default: Option<&Expr>,
+        span: Span,
+        indent: usize,
+    ) -> String {
+        let mut out = format!("switch {} {{\n", self.expr(value, indent));
+        let mut prev_end = None;
+
+        for (index, arm) in arms.iter().enumerate() {
+            out.push_str(&self.comments_before(arm.span.start.line, indent + 1, &mut prev_end));
+            let patterns: Vec<String> = arm
+                .patterns
+                .iter()
+                .map(|p| self.pattern(p, indent + 1))
+                .collect();
+            out.push_str(&pad(indent + 1));
+            out.push_str(&patterns.join(", "));
+            out.push_str(" -> ");
+            out.push_str(&self.expr(&arm.body, indent + 1));
+
+            // Arms are only separated by newlines, which is ambiguous before
+            // an array pattern or after a bare `return`
+            let next_is_array = arms
+                .get(index + 1)
+                .and_then(|next| next.patterns.first())
+                .is_some_and(|p| matches!(p, Pattern::Array { .. }));
+            let bare_return = matches!(arm.body, Expr::Return { value: None, .. });
+            if next_is_array || (bare_return && index + 1 < arms.len()) {
+                out.push(',');
+            }
+
+            if let Some(comment) = self.trailing_comment(position(arm.span)) {
+                out.push(' ');
+                out.push_str(&comment);
+            }
+            out.push('\n');
+            prev_end = Some(arm.span.end.line);
+        }
+
+        if let Some(default) = default {
+            let default_span = default.span();
+            out.push_str(&self.comments_before(default_span.start.line, indent + 1, &mut prev_end));
+            out.push_str(&pad(indent + 1));
+            out.push_str("default -> ");
+            out.push_str(&self.expr(default, indent + 1));
+            if let Some(comment) = self.trailing_comment(position(default_span)) {
+                out.push(' ');
+                out.push_str(&comment);
+            }
+            out.push('\n');
+            prev_end = Some(default_span.end.line);
+        }
+
+        out.push_str(&self.comments_before(span.end.line, indent + 1, &mut prev_end));
+        out.push_str(&pad(indent));
+        out.push('}');
+        out
+    }
+
+    fn pattern(&mut self, pattern: &Pattern, indent: usize) -> String {
+        match pattern {
+            Pattern::Literal { value, span } => self.literal(value, *span),
+            Pattern::Binding { name, guard, .. } => match guard {
+                Some(guard) => format!("{} if {}", name, self.expr(guard, indent)),
+                None => name.clone(),
+            },
+            Pattern::Array { elements, .. } => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| match element {
+                        SwitchArrayElement::Single(p) => self.pattern(p, indent),
+                        SwitchArrayElement::Rest { name, .. } => format!("...{}", name),
+                    })
+                    .collect();
+                format!("[{}]", elements.join(", "))
+            }
+            Pattern::Dict { entries, .. } => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, p)| format!("{}: {}", quote(key), self.pattern(p, indent)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Pattern::Range {
+                start,
+                end,
+                inclusive,
+                ..
+            } => {
+                let start = self.expr(start, indent);
+                let op = if *inclusive { ".." } else { "..<" };
+                format!("{}{}{}", start, op, self.expr(end, indent))
+            }
+            Pattern::Expression { expr, .. } => self.expr(expr, indent),
+        }
+    }
+}
+
+fn function_start_line(def: &FunctionDef) -> usize {
+    def.decorators
+        .first()
+        .map_or(def.span.start.line, |d| d.span.start.line)
+}
+
+/// First source line of a statement, including its decorators.
+fn start_line_of(stmt: &Stmt) -> usize {
+    match stmt {
+        Stmt::Function { def } => function_start_line(def),
+        Stmt::Class { def } => def
+            .decorators
+            .first()
+            .map_or(def.span.start.line, |d| d.span.start.line),
+        _ => stmt.span().start.line,
+    }
+}
+
+fn braced(inner: String, indent: usize) -> String {
+    if inner.is_empty() {
+        "{}".to_string()
+    } else {
+        format!("{{\n{}{}}}", inner, pad(indent))
+    }
+}
+
+fn pad(indent: usize) -> String {
+    INDENT.repeat(indent)
+}
+
+/// Whether every line of `text` stays within the width when the first one
+/// starts at `indent`.
+fn fits(text: &str, indent: usize) -> bool {
+    text.lines().enumerate().all(|(index, line)| {
+        let offset = if index == 0 { indent * INDENT.len() } else { 0 };
+        offset + line.chars().count() <= MAX_WIDTH
+    })
+}
+
+fn quote(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn binary_op(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Equal => "==",
+        BinaryOp::NotEqual => "!=",
+        BinaryOp::Less => "<",
+        BinaryOp::LessEqual => "<=",
+        BinaryOp::Greater => ">",
+        BinaryOp::GreaterEqual => ">=",
+        BinaryOp::And => "&&",
+        BinaryOp::Or => "||",
+        BinaryOp::NullCoalesce => "??",
+        BinaryOp::BitAnd => "&",
+        BinaryOp::BitOr => "|",
+        BinaryOp::BitXor => "^",
+        BinaryOp::LeftShift => "<<",
+        BinaryOp::RightShift => ">>",
+    }
+}
+
+fn assign_op(op: &AssignOp) -> &'static str {
+    match op {
+        AssignOp::Assign => "=",
+        AssignOp::AddAssign => "+=",
+        AssignOp::SubAssign => "-=",
+        AssignOp::MulAssign => "*=",
+        AssignOp::DivAssign => "/=",
+        AssignOp::ModAssign => "%=",
+        AssignOp::NullCoalesceAssign => "??=",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn sources() -> Vec<std::path::PathBuf> {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut files = Vec::new();
+        for dir in [
+            root.join("tests/corpus/v4"),
+            root.join("tests/corpus/v5"),
+            root.join("../../bench"),
+            root.join("../../examples/pong"),
+            root.join("../../examples/sald-lua"),
+            root.join("../../examples/sald-python"),
+        ] {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.extension().is_some_and(|ext| ext == "sald") {
+                    files.push(path);
+                }
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        for path in sources() {
+            let source = std::fs::read_to_string(&path).unwrap();
+            let file = path.to_string_lossy();
+            let once =
+                format_source(&source, &file).unwrap_or_else(|e| panic!("{}: {}", file, e.message));
+            let twice = format_source(&once, &file)
+                .unwrap_or_else(|e| panic!("{} after formatting: {}", file, e.message));
+            assert_eq!(once, twice, "{} is not stable", file);
+
+            let comments = |s: &str| {
+                let tokens = Scanner::new(s, "").scan_tokens().unwrap();
+                Printer::new(s, tokens).comments.len()
+            };
+            assert_eq!(comments(&source), comments(&once), "{} lost comments", file);
+        }
+    }
+
+    #[test]
+    fn test_formatted_corpus_evaluates_the_same() {
+        let eval = |source: &str| {
+            let mut engine = crate::engine::Engine::new();
+            engine
+                .eval(&format!("{}\nresult", source))
+                .unwrap()
+                .to_string()
+        };
+        for path in sources() {
+            if !path.to_string_lossy().contains("corpus") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let formatted = format_source(&source, "corpus.sald").unwrap();
+            assert_eq!(eval(&source), eval(&formatted), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn test_format_layout() {
+        let source = "let  x=[1,2 ,3]// items\nfun add(a,b){return a+b}\n\n\n\nif x { Console.println($\"{x[0]}  ok\") } else { y = 0x1F }\n";
+        let expected = "let x = [1, 2, 3] // items\nfun add(a, b) {\n    return a + b\n}\n\nif x {\n    Console.println($\"{x[0]}  ok\")\n} else {\n    y = 0x1F\n}\n";
+        assert_eq!(format_source(source, "test.sald").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_format_range() {
+        let source = "let a = 1\nfun f(){\nreturn  a}\nlet b=2\n";
+        let (first, last, text) = format_range(source, "test.sald", 2, 2).unwrap();
+        assert_eq!((first, last), (2, 3));
+        assert_eq!(text, "fun f() {\n    return a\n}\n");
+    }
+}
@@ -0,0 +1,329 @@
+//! Readable rendering of values for `Console.log`, `Console.inspect` and the
+//! REPL. Nested structures are printed on one line when they fit and broken
+//! over indented lines when they don't; nesting below `depth`, cycles and
+//! oversized arrays, dictionaries and strings are abbreviated.
+
+use crate::vm::value::DisplayText;
+use crate::vm::Value;
+
+#[derive(Debug, Clone)]
+pub struct InspectOptions {
+    /// Levels of nesting to expand below the top value; `None` expands all
+    pub depth: Option<usize>,
+    /// Color with ANSI escapes
+    pub colors: bool,
+    /// Items shown per array, dictionary or instance
+    pub max_items: usize,
+    /// Characters shown per string
+    pub max_string_length: usize,
+    /// Line width a structure must fit in to stay on one line
+    pub width: usize,
+}
+
+impl Default for InspectOptions {
+    fn default() -> Self {
+        Self {
+            depth: Some(2),
+            colors: false,
+            max_items: 100,
+            max_string_length: 10_000,
+            width: 80,
+        }
+    }
+}
+
+pub fn inspect(value: &Value, options: &InspectOptions) -> String {
+    inspect_with(value, options, &DisplayText::default())
+}
+
+/// `inspect`, with the instances listed in `text` shown as that text
+pub fn inspect_with(value: &Value, options: &InspectOptions, text: &DisplayText) -> String {
+    Inspector {
+        options,
+        text,
+        seen: Vec::new(),
+    }
+    .value(value, 0)
+}
+
+const RESET: &str = "\x1b[0m";
+const GRAY: &str = "\x1b[90m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const MAGENTA: &str = "\x1b[35m";
+
+struct Inspector<'a> {
+    options: &'a InspectOptions,
+    /// What classes' own `toString` gave for instances
+    text: &'a DisplayText,
+    /// Containers being printed, to spot cycles
+    seen: Vec<usize>,
+}
+
+impl Inspector<'_> {
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.options.colors {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn value(&mut self, value: &Value, level: usize) -> String {
+        match value {
+            Value::Null => self.paint(GRAY, "null"),
+            Value::Boolean(_) | Value::Number(_) => self.paint(YELLOW, &value.to_string()),
+            Value::String(s) => self.string(s),
+            Value::Array(arr) => {
+                let items: Vec<Value> = arr.borrow().to_vec();
+                let id = arr.as_ptr() as usize;
+                self.container(
+                    id,
+                    level,
+                    "[Array]",
+                    ("[", "]"),
+                    items.len(),
+                    |this, level, limit| {
+                        items
+                            .iter()
+                            .take(limit)
+                            .map(|item| this.value(item, level))
+                            .collect()
+                    },
+                )
+            }
+            Value::Dictionary(dict) => {
+                let dict_ref = dict.borrow();
+                let mut entries: Vec<(String, Value)> = dict_ref
+                    .iter()
+                    .map(|(k, v)| (dict_ref.key(k).to_string(), v.clone()))
+                    .collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                let id = dict.as_ptr() as usize;
+                self.entries(id, level, "[Object]", ("{", "}"), &entries)
+            }
+            Value::Instance(inst) if self.text.contains_key(&(inst.as_ptr() as usize)) => {
+                self.text[&(inst.as_ptr() as usize)].clone()
+            }
+            Value::Instance(inst) => {
+                let inst_ref = inst.borrow();
+                if let Some(text) = crate::builtins::date::format_date_instance(&inst_ref)
+                    .or_else(|| crate::builtins::collections::format_collection(&inst_ref))
+                {
+                    return self.paint(MAGENTA, &text);
+                }
+                let mut entries: Vec<(String, Value)> = inst_ref
+                    .fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                let class_name = inst_ref.class_name.clone();
+                drop(inst_ref);
+                let name = self.paint(MAGENTA, &class_name);
+                let open = format!("{} {{", name);
+                let id = inst.as_ptr() as usize;
+                self.entries(
+                    id,
+                    level,
+                    &format!("[{}]", class_name),
+                    (&open, "}"),
+                    &entries,
+                )
+            }
+            Value::Function(f) => self.paint(CYAN, &format!("[Function: {}]", f.name)),
+            Value::NativeFunction { class_name, .. } => {
+                self.paint(CYAN, &format!("[NativeFunction: {}]", class_name))
+            }
+            Value::HostFunction { name, .. } => {
+                self.paint(CYAN, &format!("[NativeFunction: {}]", name))
+            }
+            Value::InstanceMethod { method_name, .. } => {
+                self.paint(CYAN, &format!("[Method: {}]", method_name))
+            }
+            Value::BoundMethod { method, .. } => {
+                self.paint(CYAN, &format!("[BoundMethod: {}]", method.name))
+            }
+            Value::Partial { callee, .. } => self.paint(CYAN, &format!("[Partial: {}]", callee)),
+            Value::Class(c) => self.paint(MAGENTA, &format!("[Class: {}]", c.name)),
+            Value::Future(_) => self.paint(GRAY, "[Future]"),
+            Value::Namespace { name, .. } => self.paint(MAGENTA, &format!("[Namespace: {}]", name)),
+            Value::Enum { name, .. } => self.paint(MAGENTA, &format!("[Enum: {}]", name)),
+            Value::SpreadMarker(v) => self.paint(GRAY, &format!("[Spread: {:?}]", v)),
+            Value::NamedArgs(_) => self.paint(GRAY, "[NamedArgs]"),
+        }
+    }
+
+    fn string(&self, s: &str) -> String {
+        let length = s.chars().count();
+        let shown: String = s.chars().take(self.options.max_string_length).collect();
+        let escaped = shown
+            .replace('\\', "\\\\")
+            .replace('\'', "\\'")
+            .replace('\n', "\\n")
+            .replace('\t', "\\t");
+        let mut out = self.paint(GREEN, &format!("'{}'", escaped));
+        if length > self.options.max_string_length {
+            out.push_str(&format!(
+                "... {} more characters",
+                length - self.options.max_string_length
+            ));
+        }
+        out
+    }
+
+    fn entries(
+        &mut self,
+        id: usize,
+        level: usize,
+        collapsed: &str,
+        brackets: (&str, &str),
+        entries: &[(String, Value)],
+    ) -> String {
+        self.container(
+            id,
+            level,
+            collapsed,
+            brackets,
+            entries.len(),
+            |this, level, limit| {
+                entries
+                    .iter()
+                    .take(limit)
+                    .map(|(key, value)| {
+                        let key = if key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                            this.paint(CYAN, key)
+                        } else {
+                            this.string(key)
+                        };
+                        format!("{}: {}", key, this.value(value, level))
+                    })
+                    .collect()
+            },
+        )
+    }
+
+    /// Lays out the `total` items of one container, of which `render` returns
+    /// at most `max_items`: on one line if they fit, else one per line.
+    /// Nested containers indent their own lines for their level.
+    fn container(
+        &mut self,
+        id: usize,
+        level: usize,
+        collapsed: &str,
+        (open, close): (&str, &str),
+        total: usize,
+        render: impl FnOnce(&mut Self, usize, usize) -> Vec<String>,
+    ) -> String {
+        if self.seen.contains(&id) {
+            return self.paint(CYAN, "[Circular]");
+        }
+        if total == 0 {
+            return format!("{}{}", open, close);
+        }
+        if self.options.depth.is_some_and(|depth| level > depth) {
+            return self.paint(CYAN, collapsed);
+        }
+        self.seen.push(id);
+        let mut items = render(self, level + 1, self.options.max_items);
+        self.seen.pop();
+        if total > items.len() {
+            let hidden = total - items.len();
+            items.push(format!(
+                "... {} more item{}",
+                hidden,
+                if hidden == 1 { "" } else { "s" }
+            ));
+        }
+
+        let line_length: usize = visible_len(open)
+            + items
+                .iter()
+                .map(|item| visible_len(item) + 2)
+                .sum::<usize>()
+            + visible_len(close)
+            + level * 2;
+        if line_length <= self.options.width && !items.iter().any(|item| item.contains('\n')) {
+            return format!("{} {} {}", open, items.join(", "), close);
+        }
+        let indent = "  ".repeat(level + 1);
+        let body: Vec<String> = items
+            .iter()
+            .map(|item| format!("{}{}", indent, item))
+            .collect();
+        format!(
+            "{}\n{}\n{}{}",
+            open,
+            body.join(",\n"),
+            "  ".repeat(level),
+            close
+        )
+    }
+}
+
+/// Length of `text` without its ANSI escapes
+fn visible_len(text: &str) -> usize {
+    let mut length = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => length += 1,
+        }
+    }
+    length
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_inspect() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                r#"
+class Point {
+    fun init(self, x) { self.x = x }
+}
+let nested = {"b": [1, [2, [3, [4]]]], "a": Point("p")}
+nested["self"] = nested
+"#,
+            )
+            .unwrap();
+        assert_eq!(
+            engine.eval_as::<String>("Console.inspect(nested)").unwrap(),
+            "{ a: Point { x: 'p' }, b: [ 1, [ 2, [Array] ] ], self: [Circular] }"
+        );
+        assert_eq!(
+            engine
+                .eval_as::<String>("Console.inspect(nested, 0)")
+                .unwrap(),
+            "{ a: [Point], b: [Array], self: [Circular] }"
+        );
+        assert_eq!(
+            engine
+                .eval_as::<String>("Console.inspect(nested, depth: 0)")
+                .unwrap(),
+            "{ a: [Point], b: [Array], self: [Circular] }"
+        );
+        assert_eq!(
+            engine
+                .eval_as::<String>("Console.inspect([[[1]]], depth: null)")
+                .unwrap(),
+            "[ [ [ 1 ] ] ]"
+        );
+        assert!(engine
+            .eval_as::<String>("Console.inspect(1, colors: true)")
+            .unwrap()
+            .contains('\x1b'));
+        let long = engine
+            .eval_as::<String>("let xs = []\nfor i in 0..200 { xs.push(i) }\nConsole.inspect(xs)")
+            .unwrap();
+        assert!(long.starts_with("[\n  0,\n") && long.ends_with("  ... 101 more items\n]"));
+    }
+}
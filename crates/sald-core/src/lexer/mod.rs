@@ -1,5 +1,5 @@
 mod scanner;
 mod token;
 
-pub use scanner::Scanner;
+pub use scanner::{Scanner, KEYWORDS};
 pub use token::{Token, TokenKind};
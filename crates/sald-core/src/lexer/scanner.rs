@@ -1,6 +1,43 @@
 use crate::error::{SaldError, SaldResult, Span};
 use crate::lexer::token::{Token, TokenKind};
 
+/// Reserved words, as recognized by `Scanner`.
+pub const KEYWORDS: &[&str] = &[
+    "let",
+    "if",
+    "else",
+    "while",
+    "do",
+    "for",
+    "in",
+    "break",
+    "continue",
+    "debugger",
+    "fun",
+    "return",
+    "class",
+    "extends",
+    "super",
+    "self",
+    "import",
+    "as",
+    "try",
+    "catch",
+    "throw",
+    "switch",
+    "default",
+    "async",
+    "await",
+    "namespace",
+    "const",
+    "enum",
+    "interface",
+    "implements",
+    "true",
+    "false",
+    "null",
+];
+
 pub struct Scanner {
     source: Vec<char>,
     tokens: Vec<Token>,
@@ -70,7 +107,11 @@ impl Scanner {
             ':' => self.add_token(TokenKind::Colon),
             '?' => {
                 if self.match_char('?') {
-                    self.add_token(TokenKind::QuestionQuestion);
+                    if self.match_char('=') {
+                        self.add_token(TokenKind::QuestionQuestionEqual);
+                    } else {
+                        self.add_token(TokenKind::QuestionQuestion);
+                    }
                 } else if self.match_char('.') {
                     self.add_token(TokenKind::QuestionDot);
                 } else {
@@ -246,6 +287,18 @@ impl Scanner {
                     } else {
                         self.raw_string_single(quote_char)?;
                     }
+                } else if (c == 'd' || c == 't') && self.peek() == '"' {
+                    self.advance();
+                    let kind = if c == 'd' { "date" } else { "time" };
+                    match self.date_literal() {
+                        Some(value) if c == 'd' => self.add_token(TokenKind::DateLiteral(value)),
+                        Some(value) => self.add_token(TokenKind::TimeLiteral(value)),
+                        None => {
+                            return Err(self
+                                .error(&format!("Unterminated {} literal", kind))
+                                .with_help(format!("Add a closing double quote: {}\"...\"", c)));
+                        }
+                    }
                 } else {
                     self.identifier();
                 }
@@ -495,6 +548,20 @@ impl Scanner {
         Ok(())
     }
 
+    /// Reads the body of a `d"..."` or `t"..."` literal up to the closing
+    /// quote. Returns `None` if the line ends first.
+    fn date_literal(&mut self) -> Option<String> {
+        let mut value = String::new();
+        while !self.is_at_end() && self.peek() != '"' && self.peek() != '\n' {
+            value.push(self.advance());
+        }
+        if self.peek() != '"' {
+            return None;
+        }
+        self.advance();
+        Some(value)
+    }
+
     fn multiline_string(&mut self, quote_char: char) -> SaldResult<()> {
         let start_line = self.line;
         let start_col = self.start_column;
@@ -912,6 +979,7 @@ impl Scanner {
             "in" => TokenKind::In,
             "break" => TokenKind::Break,
             "continue" => TokenKind::Continue,
+            "debugger" => TokenKind::Debugger,
             "fun" => TokenKind::Fun,
             "return" => TokenKind::Return,
             "class" => TokenKind::Class,
@@ -11,6 +11,8 @@ pub enum TokenKind {
     FormatStringEnd(String),
 
     RawString(String),
+    DateLiteral(String),
+    TimeLiteral(String),
     True,
     False,
     Null,
@@ -30,6 +32,7 @@ pub enum TokenKind {
     SelfKeyword,
     Break,
     Continue,
+    Debugger,
     Extends,
     Super,
     Import,
@@ -87,6 +90,7 @@ pub enum TokenKind {
     Colon,
     Question,
     QuestionQuestion,
+    QuestionQuestionEqual,
     QuestionDot,
     Pipe,
     Arrow,
@@ -111,6 +115,8 @@ impl fmt::Display for TokenKind {
             TokenKind::FormatStringPart(s) => write!(f, "}}{{{}", s),
             TokenKind::FormatStringEnd(s) => write!(f, "}}{}\"", s),
             TokenKind::RawString(s) => write!(f, "r\"{}\"", s),
+            TokenKind::DateLiteral(s) => write!(f, "d\"{}\"", s),
+            TokenKind::TimeLiteral(s) => write!(f, "t\"{}\"", s),
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
             TokenKind::Null => write!(f, "null"),
@@ -128,6 +134,7 @@ impl fmt::Display for TokenKind {
             TokenKind::SelfKeyword => write!(f, "self"),
             TokenKind::Break => write!(f, "break"),
             TokenKind::Continue => write!(f, "continue"),
+            TokenKind::Debugger => write!(f, "debugger"),
             TokenKind::Extends => write!(f, "extends"),
             TokenKind::Super => write!(f, "super"),
             TokenKind::Import => write!(f, "import"),
@@ -180,6 +187,7 @@ impl fmt::Display for TokenKind {
             TokenKind::Colon => write!(f, ":"),
             TokenKind::Question => write!(f, "?"),
             TokenKind::QuestionQuestion => write!(f, "??"),
+            TokenKind::QuestionQuestionEqual => write!(f, "??="),
             TokenKind::QuestionDot => write!(f, "?."),
             TokenKind::Pipe => write!(f, "|"),
             TokenKind::Arrow => write!(f, "=>"),
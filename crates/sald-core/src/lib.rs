@@ -1,85 +1,80 @@
 pub mod ast;
 pub mod builtins;
 pub mod compiler;
+pub mod docgen;
+pub mod ffigen;
 pub mod error;
+pub mod fmt;
+pub mod inspect;
 pub mod lexer;
+pub mod locale;
 pub mod parser;
+pub mod target;
 pub mod vm;
+pub mod warnings;
+pub mod workspace;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod binary;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod engine;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native_module;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod package;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod snapshot;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod test_util;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
-use parking_lot::RwLock;
 use std::path::PathBuf;
 
-static PROJECT_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
-
-static MODULE_WORKSPACE_STACK: RwLock<Vec<PathBuf>> = RwLock::new(Vec::new());
-
+#[deprecated(note = "use `workspace::set_default_project_root` or `VM::set_project_root`")]
 pub fn set_project_root(path: &std::path::Path) {
-    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-    *PROJECT_ROOT.write() = Some(canonical);
+    workspace::set_default_project_root(path);
 }
 
+#[deprecated(note = "use `workspace::current().project_root()`")]
 pub fn get_project_root() -> Option<PathBuf> {
-    PROJECT_ROOT.read().clone()
+    workspace::current().project_root().map(|p| p.to_path_buf())
 }
 
 #[cfg(not(target_arch = "wasm32"))]
+#[deprecated(note = "use `Workspace::push_module` on the VM's workspace")]
 pub fn push_module_workspace(path: &std::path::Path) {
-    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
-    MODULE_WORKSPACE_STACK.write().push(canonical);
+    workspace::with_current(|w| w.push_module(path));
 }
 
 #[cfg(not(target_arch = "wasm32"))]
+#[deprecated(note = "use `Workspace::pop_module` on the VM's workspace")]
 pub fn pop_module_workspace() {
-    MODULE_WORKSPACE_STACK.write().pop();
+    workspace::with_current(|w| w.pop_module());
 }
 
+#[deprecated(note = "use `workspace::current().current_dir()`")]
 pub fn get_current_workspace() -> PathBuf {
-    let stack = MODULE_WORKSPACE_STACK.read();
-    if let Some(workspace) = stack.last() {
-        return workspace.clone();
-    }
-    drop(stack);
-
-    if let Some(root) = get_project_root() {
-        return root;
-    }
-
-    #[cfg(not(target_arch = "wasm32"))]
-    {
-        std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
-    }
-    #[cfg(target_arch = "wasm32")]
-    {
-        PathBuf::from("/virtual")
-    }
+    workspace::current().current_dir()
 }
 
+#[deprecated(note = "use `workspace::resolve`")]
 pub fn resolve_script_path(path: &str) -> PathBuf {
-    let path_buf = PathBuf::from(path);
-
-    if path_buf.is_absolute() {
-        return path_buf;
-    }
-
-    get_current_workspace().join(path)
+    workspace::resolve(path)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
+#[deprecated(note = "use `workspace::set_default_project_root`")]
 pub fn set_script_dir(path: &str) {
     let dir = PathBuf::from(path)
         .parent()
         .map(|p| p.to_path_buf())
         .unwrap_or_else(|| PathBuf::from("."));
-
-    let mut root = PROJECT_ROOT.write();
-    if root.is_none() {
-        *root = Some(dir.canonicalize().unwrap_or(dir));
+    if workspace::default_project_root().is_none() {
+        workspace::set_default_project_root(&dir);
     }
 }
 
@@ -90,11 +85,16 @@ pub fn push_script_dir(_path: &str) {}
 pub fn pop_script_dir() {}
 
 #[cfg(not(target_arch = "wasm32"))]
+#[deprecated(note = "use `workspace::current().project_root()`")]
 pub fn get_script_dir() -> PathBuf {
-    get_project_root().unwrap_or_else(|| PathBuf::from("."))
+    workspace::current()
+        .project_root()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("."))
 }
 
 #[cfg(target_arch = "wasm32")]
+#[deprecated(note = "use `workspace::set_default_project_root`")]
 pub fn set_script_dir(_path: &str) {}
 
 #[cfg(target_arch = "wasm32")]
@@ -104,7 +104,9 @@ pub fn push_script_dir(_path: &str) {}
 pub fn pop_script_dir() {}
 
 #[cfg(target_arch = "wasm32")]
+#[deprecated(note = "use `Workspace::push_module` on the VM's workspace")]
 pub fn push_module_workspace(_path: &std::path::Path) {}
 
 #[cfg(target_arch = "wasm32")]
+#[deprecated(note = "use `Workspace::pop_module` on the VM's workspace")]
 pub fn pop_module_workspace() {}
@@ -0,0 +1,264 @@
+//! Message catalogs for diagnostics
+//! Errors are always built with English messages; when a locale other than
+//! `en` is active they are mapped through that locale's catalog at display time
+
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+/// Translations for one locale. Keys are English message templates where
+/// `{}` stands for an argument, e.g. `"Undefined variable '{}'"`.
+/// Translations use `{}` for arguments in order or `{0}`, `{1}`, ... to
+/// reorder them.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    entries: Vec<(String, String)>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a translation, replacing any existing one for the same template.
+    pub fn insert(&mut self, template: impl Into<String>, translation: impl Into<String>) {
+        let template = template.into();
+        let translation = translation.into();
+        match self.entries.iter_mut().find(|(t, _)| *t == template) {
+            Some(entry) => entry.1 = translation,
+            None => self.entries.push((template, translation)),
+        }
+    }
+
+    /// Translates `message`, or returns `None` when no template matches.
+    pub fn translate(&self, message: &str) -> Option<String> {
+        self.entries.iter().find_map(|(template, translation)| {
+            match_template(template, message).map(|args| fill_template(translation, &args))
+        })
+    }
+}
+
+struct LocaleState {
+    current: String,
+    catalogs: FxHashMap<String, Catalog>,
+}
+
+static LOCALE: RwLock<Option<LocaleState>> = RwLock::new(None);
+
+fn with_state<R>(f: impl FnOnce(&mut LocaleState) -> R) -> R {
+    let mut guard = LOCALE.write();
+    let state = guard.get_or_insert_with(|| {
+        let mut catalogs = FxHashMap::default();
+        catalogs.insert("id".to_string(), indonesian());
+        LocaleState {
+            current: normalize(&default_locale()),
+            catalogs,
+        }
+    });
+    f(state)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_locale() -> String {
+    std::env::var("SALD_LANG").unwrap_or_else(|_| "en".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_locale() -> String {
+    "en".to_string()
+}
+
+/// `id_ID.UTF-8` -> `id_id`
+fn normalize(lang: &str) -> String {
+    lang.split('.')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .replace('-', "_")
+        .to_lowercase()
+}
+
+/// Selects the locale used for diagnostics. Defaults to `SALD_LANG`, or `en`.
+pub fn set_locale(lang: &str) {
+    with_state(|state| state.current = normalize(lang));
+}
+
+pub fn current_locale() -> String {
+    with_state(|state| state.current.clone())
+}
+
+/// Adds translations for `lang`, on top of any catalog already registered
+/// for it (including the built-in ones).
+pub fn register_translations<K, V>(lang: &str, entries: impl IntoIterator<Item = (K, V)>)
+where
+    K: Into<String>,
+    V: Into<String>,
+{
+    with_state(|state| {
+        let catalog = state.catalogs.entry(normalize(lang)).or_default();
+        for (template, translation) in entries {
+            catalog.insert(template, translation);
+        }
+    });
+}
+
+/// Translates an English message into the current locale. Messages without
+/// a translation are returned unchanged.
+pub fn translate(message: &str) -> String {
+    with_state(|state| {
+        if state.current.is_empty() || state.current == "en" {
+            return None;
+        }
+        // Fall back from a region-specific locale (`pt_br`) to its language (`pt`)
+        let language = state.current.split('_').next().unwrap_or("");
+        [state.current.as_str(), language]
+            .iter()
+            .filter_map(|lang| state.catalogs.get(*lang))
+            .find_map(|catalog| catalog.translate(message))
+    })
+    .unwrap_or_else(|| message.to_string())
+}
+
+fn match_template(template: &str, message: &str) -> Option<Vec<String>> {
+    let parts: Vec<&str> = template.split("{}").collect();
+    if parts.len() == 1 {
+        return (template == message).then(Vec::new);
+    }
+
+    let mut rest = message.strip_prefix(parts[0])?;
+    let last = parts[parts.len() - 1];
+    let mut args = Vec::with_capacity(parts.len() - 1);
+    for part in &parts[1..parts.len() - 1] {
+        if part.is_empty() {
+            return None;
+        }
+        let end = rest.find(part)?;
+        args.push(rest[..end].to_string());
+        rest = &rest[end + part.len()..];
+    }
+    args.push(rest.strip_suffix(last)?.to_string());
+    Some(args)
+}
+
+fn fill_template(translation: &str, args: &[String]) -> String {
+    let mut output = String::with_capacity(translation.len());
+    let mut next = 0;
+    let mut rest = translation;
+    while let Some(open) = rest.find('{') {
+        output.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let close = after.find('}');
+        let index = match close {
+            Some(0) => {
+                next += 1;
+                Some(next - 1)
+            }
+            Some(end) => after[..end].parse::<usize>().ok(),
+            None => None,
+        };
+        match (index.and_then(|i| args.get(i)), close) {
+            (Some(arg), Some(end)) => {
+                output.push_str(arg);
+                rest = &after[end + 1..];
+            }
+            _ => {
+                output.push('{');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn indonesian() -> Catalog {
+    let mut catalog = Catalog::new();
+    for (template, translation) in [
+        ("Help", "Bantuan"),
+        ("Stack trace", "Jejak pemanggilan"),
+        (
+            "Undefined variable '{}'",
+            "Variabel '{}' belum didefinisikan",
+        ),
+        (
+            "Undefined property '{}'",
+            "Properti '{}' belum didefinisikan",
+        ),
+        ("Division by zero", "Pembagian dengan nol"),
+        (
+            "Stack overflow (too many call frames)",
+            "Stack overflow (terlalu banyak pemanggilan fungsi)",
+        ),
+        (
+            "Expected at least {} arguments but got {}",
+            "Membutuhkan minimal {} argumen tetapi mendapat {}",
+        ),
+        (
+            "Expected at most {} arguments but got {}",
+            "Membutuhkan maksimal {} argumen tetapi mendapat {}",
+        ),
+        (
+            "Expected {} arguments but got {}",
+            "Membutuhkan {} argumen tetapi mendapat {}",
+        ),
+        (
+            "Index {} out of bounds for array of length {}",
+            "Indeks {} di luar batas untuk array dengan panjang {}",
+        ),
+        (
+            "Index {} out of bounds for string of length {}",
+            "Indeks {} di luar batas untuk string dengan panjang {}",
+        ),
+        ("'{}' is not callable", "'{}' tidak dapat dipanggil"),
+        ("'{}' has no method '{}'", "'{}' tidak memiliki method '{}'"),
+        (
+            "Cannot add '{}' and '{}'",
+            "Tidak dapat menjumlahkan '{}' dan '{}'",
+        ),
+        (
+            "Cannot compare '{}' and '{}'",
+            "Tidak dapat membandingkan '{}' dan '{}'",
+        ),
+        (
+            "Cannot divide '{}' by '{}'",
+            "Tidak dapat membagi '{}' dengan '{}'",
+        ),
+        (
+            "Cannot index '{}' with '{}'",
+            "Tidak dapat mengindeks '{}' dengan '{}'",
+        ),
+        ("Cannot negate '{}'", "Tidak dapat menegasikan '{}'"),
+        ("Uncaught exception: {}", "Exception tidak tertangani: {}"),
+    ] {
+        catalog.insert(template, translation);
+    }
+    catalog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_translates_templates() {
+        let mut catalog = Catalog::new();
+        catalog.insert("Cannot add '{}' and '{}'", "'{1}' + '{0}' nicht möglich");
+        assert_eq!(
+            catalog
+                .translate("Cannot add 'Number' and 'Null'")
+                .as_deref(),
+            Some("'Null' + 'Number' nicht möglich")
+        );
+        assert_eq!(catalog.translate("Division by zero"), None);
+
+        let id = indonesian();
+        assert_eq!(
+            id.translate("Expected at least 2 arguments but got 1")
+                .as_deref(),
+            Some("Membutuhkan minimal 2 argumen tetapi mendapat 1")
+        );
+        assert_eq!(
+            id.translate("Expected 0-1 arguments but got 3").as_deref(),
+            Some("Membutuhkan 0-1 argumen tetapi mendapat 3")
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Native module plugins written in Rust
+//! Modules are registered by the host or loaded from a cdylib and imported with
+//! `import "native:<name>"`
+
+use crate::vm::value::{Class, NativeStaticFn, Value};
+use libloading::{Library, Symbol};
+use parking_lot::{Mutex, RwLock};
+use rustc_hash::FxHashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Bumped whenever `NativeModule` or the exported symbols change shape.
+/// Libraries built against another version are refused at load time.
+pub const NATIVE_MODULE_ABI_VERSION: u32 = 1;
+
+pub const NATIVE_IMPORT_PREFIX: &str = "native:";
+
+/// A Rust extension that contributes globals to importing scripts.
+pub trait NativeModule: Send + Sync {
+    /// Name used in `import "native:<name>"`.
+    fn name(&self) -> &str;
+
+    /// Called once per import to build the module's exports.
+    fn register(&self, exports: &mut NativeExports);
+}
+
+/// Collects the globals a native module exposes to scripts.
+pub struct NativeExports {
+    module_name: String,
+    members: FxHashMap<String, Value>,
+}
+
+impl NativeExports {
+    fn new(module_name: &str) -> Self {
+        Self {
+            module_name: module_name.to_string(),
+            members: FxHashMap::default(),
+        }
+    }
+
+    pub fn function(&mut self, name: impl Into<String>, func: NativeStaticFn) -> &mut Self {
+        self.members.insert(
+            name.into(),
+            Value::NativeFunction {
+                func,
+                class_name: self.module_name.clone(),
+            },
+        );
+        self
+    }
+
+    pub fn class(&mut self, class: Class) -> &mut Self {
+        self.members
+            .insert(class.name.clone(), Value::Class(Rc::new(class)));
+        self
+    }
+
+    pub fn value(&mut self, name: impl Into<String>, value: Value) -> &mut Self {
+        self.members.insert(name.into(), value);
+        self
+    }
+
+    pub fn into_members(self) -> FxHashMap<String, Value> {
+        self.members
+    }
+}
+
+static REGISTRY: RwLock<Option<FxHashMap<String, Arc<dyn NativeModule>>>> = RwLock::new(None);
+
+static LOADED_LIBRARIES: Mutex<Vec<Library>> = Mutex::new(Vec::new());
+
+/// Makes a module available to `import "native:<name>"` in every VM.
+pub fn register_native_module(module: Arc<dyn NativeModule>) {
+    let mut registry = REGISTRY.write();
+    registry
+        .get_or_insert_with(FxHashMap::default)
+        .insert(module.name().to_string(), module);
+}
+
+pub fn get_native_module(name: &str) -> Option<Arc<dyn NativeModule>> {
+    REGISTRY.read().as_ref().and_then(|r| r.get(name).cloned())
+}
+
+/// Loads a cdylib built with `sald_native_module!` and registers its module.
+pub fn load_native_library(path: &Path) -> Result<Arc<dyn NativeModule>, String> {
+    let library = unsafe { Library::new(path) }
+        .map_err(|e| format!("Cannot load native module '{}': {}", path.display(), e))?;
+
+    let module = unsafe {
+        let abi: Symbol<extern "C" fn() -> u32> = library
+            .get(b"sald_native_module_abi_version")
+            .map_err(|_| {
+                format!(
+                    "'{}' is not a Sald native module (missing sald_native_module_abi_version)",
+                    path.display()
+                )
+            })?;
+        let version = abi();
+        if version != NATIVE_MODULE_ABI_VERSION {
+            return Err(format!(
+                "Native module '{}' was built for ABI version {}, expected {}",
+                path.display(),
+                version,
+                NATIVE_MODULE_ABI_VERSION
+            ));
+        }
+
+        let create: Symbol<extern "C" fn() -> *mut Box<dyn NativeModule>> = library
+            .get(b"sald_native_module_create")
+            .map_err(|_| format!("'{}' is missing sald_native_module_create", path.display()))?;
+        let raw = create();
+        if raw.is_null() {
+            return Err(format!(
+                "Native module '{}' failed to initialize",
+                path.display()
+            ));
+        }
+        Box::from_raw(raw)
+    };
+
+    let module: Arc<dyn NativeModule> = Arc::from(*module);
+    register_native_module(module.clone());
+
+    // Code from the library is referenced by the module's function pointers,
+    // so the library stays loaded for the rest of the process.
+    LOADED_LIBRARIES.lock().push(library);
+    Ok(module)
+}
+
+fn library_file_name(name: &str) -> String {
+    format!(
+        "{}{}{}",
+        std::env::consts::DLL_PREFIX,
+        name,
+        std::env::consts::DLL_SUFFIX
+    )
+}
+
+/// Looks for `sald_modules/<name>/<lib><name><ext>` under the project root and
+/// in each directory of `SALD_NATIVE_PATH`.
+fn find_native_library(name: &str) -> Option<PathBuf> {
+    let file_name = library_file_name(name);
+    let mut candidates = Vec::new();
+
+    if let Some(root) = crate::workspace::current().project_root() {
+        candidates.push(root.join("sald_modules").join(name).join(&file_name));
+    }
+    if let Ok(paths) = std::env::var("SALD_NATIVE_PATH") {
+        for dir in std::env::split_paths(&paths) {
+            candidates.push(dir.join(&file_name));
+        }
+    }
+
+    candidates.into_iter().find(|p| p.exists())
+}
+
+/// Resolves `native:<name>` to the module's exports: a registered module, a
+/// built-in database driver, or else a library loaded from disk.
+pub fn import_native_module(name: &str) -> Result<FxHashMap<String, Value>, String> {
+    let module = get_native_module(name).or_else(|| crate::builtins::db::native_module(name));
+    let module = match module {
+        Some(module) => module,
+        None => {
+            let path = find_native_library(name)
+                .ok_or_else(|| format!("Native module '{}' not found", name))?;
+            load_native_library(&path)?
+        }
+    };
+
+    let mut exports = NativeExports::new(module.name());
+    module.register(&mut exports);
+    Ok(exports.into_members())
+}
+
+/// Exports a `NativeModule` from a cdylib so `import "native:<name>"` can load it.
+///
+/// ```ignore
+/// struct Greeter;
+///
+/// impl NativeModule for Greeter {
+///     fn name(&self) -> &str { "greeter" }
+///     fn register(&self, exports: &mut NativeExports) {
+///         exports.function("hello", |_| Ok(Value::String("hi".into())));
+///     }
+/// }
+///
+/// sald_native_module!(Greeter);
+/// ```
+#[macro_export]
+macro_rules! sald_native_module {
+    ($module:expr) => {
+        #[no_mangle]
+        pub extern "C" fn sald_native_module_abi_version() -> u32 {
+            $crate::native_module::NATIVE_MODULE_ABI_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn sald_native_module_create(
+        ) -> *mut Box<dyn $crate::native_module::NativeModule> {
+            let module: Box<dyn $crate::native_module::NativeModule> = Box::new($module);
+            Box::into_raw(Box::new(module))
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::vm::value::Value;
+
+    struct Greeter;
+
+    impl NativeModule for Greeter {
+        fn name(&self) -> &str {
+            "greeter"
+        }
+
+        fn register(&self, exports: &mut crate::native_module::NativeExports) {
+            exports.function("hello", |args| {
+                Ok(Value::String(format!("hello {}", args[0]).into()))
+            });
+        }
+    }
+
+    #[test]
+    fn test_native_module_import() {
+        let mut engine = Engine::new();
+        engine.register_native_module(Greeter);
+        let plain: String = engine
+            .eval("import \"native:greeter\"\nhello(\"a\")")
+            .map(|v| v.to_string())
+            .unwrap();
+        let aliased: String = engine
+            .eval("import \"native:greeter\" as g\ng.hello(\"b\")")
+            .map(|v| v.to_string())
+            .unwrap();
+        assert_eq!(plain, "hello a");
+        assert_eq!(aliased, "hello b");
+    }
+}
@@ -0,0 +1,652 @@
+//! Project dependencies
+//! The `modules` section of `salad.json` names each dependency with a source:
+//! a registry version string, `{"path": ...}` or `{"git": ..., "rev": ...}`.
+//! Resolution walks the installed packages in name order, so the same tree
+//! always produces the same `salad.lock`, and imports find packages through
+//! the lockfile or the project's `sald_modules/` cache.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "salad.json";
+pub const LOCK_FILE: &str = "salad.lock";
+pub const MODULES_DIR: &str = "sald_modules";
+
+const LOCK_VERSION: u32 = 1;
+
+/// Where a dependency comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Source {
+    /// Exact version from the package registry
+    Registry(String),
+    /// Directory relative to the package that depends on it
+    Path { path: String },
+    /// Git repository, optionally pinned to a branch, tag or commit
+    Git {
+        git: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+    },
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Registry(version) => write!(f, "{}", version),
+            Source::Path { path } => write!(f, "path:{}", path),
+            Source::Git {
+                git,
+                rev: Some(rev),
+            } => write!(f, "git:{}#{}", git, rev),
+            Source::Git { git, rev: None } => write!(f, "git:{}", git),
+        }
+    }
+}
+
+/// The parts of `salad.json` resolution needs
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default = "default_main")]
+    pub main: String,
+    #[serde(default)]
+    pub modules: BTreeMap<String, Source>,
+}
+
+fn default_main() -> String {
+    "main.sald".to_string()
+}
+
+impl Manifest {
+    /// Reads `salad.json` from a project or package directory.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_FILE);
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+    }
+}
+
+/// One resolved package in `salad.lock`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: String,
+    /// `registry`, `path+<dir relative to the project>` or `git+<url>#<commit>`
+    pub source: String,
+    /// `sha256:` digest of the installed files
+    pub checksum: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<String>,
+}
+
+impl LockedPackage {
+    /// Directory the package is loaded from.
+    pub fn dir(&self, project_root: &Path) -> PathBuf {
+        match self.source.strip_prefix("path+") {
+            Some(path) => project_root.join(path),
+            None => project_root.join(MODULES_DIR).join(&self.name),
+        }
+    }
+
+    /// Commit a git package was locked at.
+    pub fn git_commit(&self) -> Option<&str> {
+        let (_, commit) = self.source.strip_prefix("git+")?.rsplit_once('#')?;
+        Some(commit)
+    }
+}
+
+/// Contents of `salad.lock`, sorted by package name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub version: u32,
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    /// Reads `salad.lock`, or `None` if the project has none yet.
+    pub fn load(project_root: &Path) -> Result<Option<Self>, String> {
+        let path = project_root.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", LOCK_FILE, e))?;
+        let lock: Lockfile =
+            serde_json::from_str(&content).map_err(|e| format!("Invalid {}: {}", LOCK_FILE, e))?;
+        if lock.version != LOCK_VERSION {
+            return Err(format!(
+                "Unsupported {} version {}",
+                LOCK_FILE, lock.version
+            ));
+        }
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, project_root: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(project_root.join(LOCK_FILE), content + "\n")
+            .map_err(|e| format!("Failed to write {}: {}", LOCK_FILE, e))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages
+            .binary_search_by(|p| p.name.as_str().cmp(name))
+            .ok()
+            .map(|i| &self.packages[i])
+    }
+
+    /// Names of packages whose installed files no longer match their checksum.
+    pub fn verify(&self, project_root: &Path) -> Vec<String> {
+        self.packages
+            .iter()
+            .filter(|p| checksum(&p.dir(project_root)).ok().as_ref() != Some(&p.checksum))
+            .map(|p| p.name.clone())
+            .collect()
+    }
+}
+
+/// Directory a dependency is installed in, given the directory of the package
+/// that declares it.
+pub fn source_dir(project_root: &Path, declared_in: &Path, name: &str, source: &Source) -> PathBuf {
+    match source {
+        Source::Path { path } => normalize(&declared_in.join(path)),
+        _ => project_root.join(MODULES_DIR).join(name),
+    }
+}
+
+/// Finds an installed package by name, preferring the lockfile's location.
+pub fn locate(project_root: &Path, name: &str) -> Option<PathBuf> {
+    if let Ok(Some(lock)) = Lockfile::load(project_root) {
+        if let Some(package) = lock.get(name) {
+            return Some(package.dir(project_root));
+        }
+    }
+    let dir = project_root.join(MODULES_DIR).join(name);
+    dir.is_dir().then_some(dir)
+}
+
+/// A bare import resolved to a file inside a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedModule {
+    pub name: String,
+    /// From the package's `salad.json`, if it has one
+    pub version: Option<String>,
+    pub dir: PathBuf,
+    pub entry: PathBuf,
+}
+
+/// Why a bare import could not be resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// No installed package has this name
+    NotFound {
+        name: String,
+        searched: Vec<PathBuf>,
+    },
+    InvalidManifest {
+        name: String,
+        message: String,
+    },
+    /// The package was found but the file the import names is missing
+    MissingEntry {
+        name: String,
+        entry: PathBuf,
+    },
+}
+
+impl ResolveError {
+    /// Suggested fix, shown alongside the message.
+    pub fn help(&self) -> String {
+        match self {
+            ResolveError::NotFound { name, searched } => {
+                let searched: Vec<_> = searched.iter().map(|p| p.display().to_string()).collect();
+                format!(
+                    "Run 'salad add {}' or 'salad install'. Searched: {}",
+                    name,
+                    searched.join(", ")
+                )
+            }
+            ResolveError::InvalidManifest { name, .. } => {
+                format!("Fix {}/salad.json or reinstall the package", name)
+            }
+            ResolveError::MissingEntry { name, .. } => format!(
+                "Check the 'main' field of {}'s salad.json, or reinstall it with 'salad install'",
+                name
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::NotFound { name, .. } => {
+                write!(f, "Module '{}' not found in {}/", name, MODULES_DIR)
+            }
+            ResolveError::InvalidManifest { name, message } => {
+                write!(
+                    f,
+                    "Module '{}' has an invalid salad.json: {}",
+                    name, message
+                )
+            }
+            ResolveError::MissingEntry { name, entry } => write!(
+                f,
+                "Module '{}' entry file '{}' not found",
+                name,
+                entry.display()
+            ),
+        }
+    }
+}
+
+/// Whether an import names a package (`"http"`, `"http/client"`) rather
+/// than a file.
+pub fn is_bare_specifier(specifier: &str) -> bool {
+    !specifier.is_empty()
+        && !specifier.starts_with(['.', '/', '\\'])
+        && !specifier.contains(':')
+        && !specifier.ends_with(".sald")
+        && !specifier.ends_with(".saldc")
+}
+
+/// Resolves a bare import from a file in `from_dir`. The lockfile is
+/// consulted first, then `sald_modules/` in `from_dir` and each of its
+/// parents, then the project's own `sald_modules/`. `"pkg/sub/file"` names
+/// `sub/file.sald` inside `pkg`; a bare `"pkg"` loads the `main` of its
+/// `salad.json`, falling back to `pkg.sald` or `main.sald`.
+pub fn resolve_module(
+    project_root: Option<&Path>,
+    from_dir: &Path,
+    specifier: &str,
+) -> Result<ResolvedModule, ResolveError> {
+    let (name, subpath) = match specifier.split_once(['/', '\\']) {
+        Some((name, subpath)) => (name, Some(subpath)),
+        None => (specifier, None),
+    };
+
+    let mut searched = Vec::new();
+    if let Some(root) = project_root {
+        if let Ok(Some(lock)) = Lockfile::load(root) {
+            if let Some(package) = lock.get(name) {
+                searched.push(package.dir(root));
+            }
+        }
+    }
+    for dir in from_dir.ancestors() {
+        searched.push(dir.join(MODULES_DIR).join(name));
+    }
+    if let Some(root) = project_root {
+        searched.push(root.join(MODULES_DIR).join(name));
+    }
+    let mut seen = Vec::new();
+    searched.retain(|dir| {
+        let new = !seen.contains(dir);
+        seen.push(dir.clone());
+        new
+    });
+
+    let Some(dir) = searched.iter().find(|dir| dir.is_dir()).cloned() else {
+        // A single-file package: sald_modules/<name>.sald
+        if subpath.is_none() {
+            if let Some(root) = project_root {
+                let file = root.join(MODULES_DIR).join(format!("{}.sald", name));
+                if file.is_file() {
+                    return Ok(ResolvedModule {
+                        name: name.to_string(),
+                        version: None,
+                        dir: root.join(MODULES_DIR),
+                        entry: file,
+                    });
+                }
+            }
+        }
+        return Err(ResolveError::NotFound {
+            name: name.to_string(),
+            searched,
+        });
+    };
+
+    let manifest = if dir.join(MANIFEST_FILE).exists() {
+        let manifest = Manifest::load(&dir).map_err(|message| ResolveError::InvalidManifest {
+            name: name.to_string(),
+            message,
+        })?;
+        Some(manifest)
+    } else {
+        None
+    };
+
+    let entry = match (subpath, &manifest) {
+        (Some(subpath), _) => {
+            let file = dir.join(subpath);
+            if file.extension().is_some() {
+                file
+            } else {
+                file.with_extension("sald")
+            }
+        }
+        (None, Some(manifest)) => dir.join(&manifest.main),
+        (None, None) => {
+            let own = dir.join(format!("{}.sald", name));
+            if own.exists() {
+                own
+            } else {
+                dir.join("main.sald")
+            }
+        }
+    };
+    if !entry.is_file() {
+        return Err(ResolveError::MissingEntry {
+            name: name.to_string(),
+            entry: entry.strip_prefix(&dir).unwrap_or(&entry).to_path_buf(),
+        });
+    }
+
+    Ok(ResolvedModule {
+        name: name.to_string(),
+        version: manifest.map(|m| m.version),
+        dir,
+        entry,
+    })
+}
+
+/// Resolves the project's dependency tree from the installed packages.
+/// Every package must already be installed; two different sources for the
+/// same name are a conflict.
+pub fn resolve(project_root: &Path) -> Result<Lockfile, String> {
+    let manifest = Manifest::load(project_root)?;
+    let mut resolved: BTreeMap<String, (Source, String, LockedPackage)> = BTreeMap::new();
+    let mut queue: VecDeque<(String, Source, PathBuf, String)> = manifest
+        .modules
+        .into_iter()
+        .map(|(name, source)| {
+            (
+                name,
+                source,
+                project_root.to_path_buf(),
+                manifest.name.clone(),
+            )
+        })
+        .collect();
+
+    while let Some((name, source, declared_in, requested_by)) = queue.pop_front() {
+        let dir = source_dir(project_root, &declared_in, &name, &source);
+        if let Some((existing, first_by, _)) = resolved.get(&name) {
+            if !same_source(existing, &source) {
+                return Err(format!(
+                    "Conflicting requirements for '{}': {} (from {}) and {} (from {})",
+                    name, existing, first_by, source, requested_by
+                ));
+            }
+            continue;
+        }
+
+        let package = Manifest::load(&dir).map_err(|_| {
+            format!(
+                "Package '{}' ({}) is not installed. Run 'salad install'",
+                name, source
+            )
+        })?;
+        if let Source::Registry(version) = &source {
+            if &package.version != version {
+                return Err(format!(
+                    "Package '{}' is installed at {} but {} wants {}",
+                    name, package.version, requested_by, version
+                ));
+            }
+        }
+
+        let locked_source = match &source {
+            Source::Registry(_) => "registry".to_string(),
+            Source::Path { .. } => {
+                format!("path+{}", relative_to(project_root, &dir).display())
+            }
+            Source::Git { git, rev } => {
+                let commit = git_head(&dir)
+                    .or_else(|| rev.clone())
+                    .unwrap_or_else(|| "HEAD".to_string());
+                format!("git+{}#{}", git, commit)
+            }
+        };
+        let locked = LockedPackage {
+            name: name.clone(),
+            version: package.version.clone(),
+            source: locked_source,
+            checksum: checksum(&dir)?,
+            dependencies: package.modules.keys().cloned().collect(),
+        };
+        for (dep, dep_source) in package.modules {
+            queue.push_back((dep, dep_source, dir.clone(), name.clone()));
+        }
+        resolved.insert(name, (source, requested_by, locked));
+    }
+
+    Ok(Lockfile {
+        version: LOCK_VERSION,
+        packages: resolved.into_values().map(|(_, _, p)| p).collect(),
+    })
+}
+
+/// Path sources declared from different packages name the same directory in
+/// different ways, so only the kind of source has to agree for them.
+fn same_source(a: &Source, b: &Source) -> bool {
+    match (a, b) {
+        (Source::Path { .. }, Source::Path { .. }) => true,
+        _ => a == b,
+    }
+}
+
+/// `sha256:` digest over every file's relative path and contents, skipping
+/// `.git` and nested `sald_modules`.
+pub fn checksum(dir: &Path) -> Result<String, String> {
+    let mut files = Vec::new();
+    collect_files(dir, dir, &mut files)
+        .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for relative in files {
+        let contents = fs::read(dir.join(&relative))
+            .map_err(|e| format!("Failed to read {}: {}", relative, e))?;
+        hasher.update(relative.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    Ok(format!("sha256:{}", hex::encode(hasher.finalize())))
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            let name = entry.file_name();
+            if name != ".git" && name != MODULES_DIR {
+                collect_files(root, &path, files)?;
+            }
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            // Forward slashes keep checksums the same across platforms
+            let parts: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+/// Commit checked out in a git working copy, read without running git.
+fn git_head(dir: &Path) -> Option<String> {
+    let git_dir = dir.join(".git");
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    let Some(reference) = head.strip_prefix("ref: ") else {
+        return Some(head.to_string());
+    };
+    if let Ok(commit) = fs::read_to_string(git_dir.join(reference)) {
+        return Some(commit.trim().to_string());
+    }
+    let packed = fs::read_to_string(git_dir.join("packed-refs")).ok()?;
+    packed.lines().find_map(|line| {
+        let (commit, name) = line.split_once(' ')?;
+        (name == reference).then(|| commit.to_string())
+    })
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// `path` relative to `base`, both taken lexically.
+fn relative_to(base: &Path, path: &Path) -> PathBuf {
+    let base = normalize(base);
+    let path = normalize(path);
+    let common = base
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push("..");
+    }
+    for component in path.components().skip(common) {
+        relative.push(component.as_os_str());
+    }
+    relative
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_and_lock() {
+        let root = std::env::temp_dir().join(format!("sald_package_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        write(
+            &root.join(MANIFEST_FILE),
+            r#"{"name": "app", "version": "1.0.0",
+                "modules": {"util": "1.2.0", "local": {"path": "libs/local"}}}"#,
+        );
+        write(
+            &root.join("sald_modules/util/salad.json"),
+            r#"{"name": "util", "version": "1.2.0", "main": "util.sald",
+                "modules": {"strings": "0.3.0"}}"#,
+        );
+        write(&root.join("sald_modules/util/util.sald"), "let x = 1\n");
+        write(
+            &root.join("sald_modules/strings/salad.json"),
+            r#"{"name": "strings", "version": "0.3.0"}"#,
+        );
+        write(
+            &root.join("libs/local/salad.json"),
+            r#"{"name": "local", "version": "0.1.0", "modules": {"strings": "0.3.0"}}"#,
+        );
+
+        let lock = resolve(&root).unwrap();
+        let names: Vec<_> = lock.packages.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["local", "strings", "util"]);
+        assert_eq!(lock.get("local").unwrap().source, "path+libs/local");
+        assert_eq!(lock.get("util").unwrap().dependencies, ["strings"]);
+        assert_eq!(resolve(&root).unwrap(), lock);
+
+        lock.save(&root).unwrap();
+        assert_eq!(Lockfile::load(&root).unwrap(), Some(lock.clone()));
+        assert_eq!(locate(&root, "local"), Some(root.join("libs/local")));
+        assert!(lock.verify(&root).is_empty());
+
+        write(&root.join("sald_modules/util/util.sald"), "let x = 2\n");
+        assert_eq!(lock.verify(&root), ["util"]);
+
+        write(
+            &root.join("sald_modules/util/salad.json"),
+            r#"{"name": "util", "version": "1.2.0", "modules": {"strings": "0.4.0"}}"#,
+        );
+        let err = resolve(&root).unwrap_err();
+        assert!(
+            err.contains("Conflicting requirements for 'strings'"),
+            "{}",
+            err
+        );
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_resolve_bare_specifiers() {
+        let root = std::env::temp_dir().join(format!("sald_resolve_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        write(
+            &root.join("sald_modules/http/salad.json"),
+            r#"{"name": "http", "version": "2.0.0", "main": "src/index.sald"}"#,
+        );
+        write(&root.join("sald_modules/http/src/index.sald"), "");
+        write(&root.join("sald_modules/http/client.sald"), "");
+        write(&root.join("sald_modules/tiny/tiny.sald"), "");
+        write(&root.join("app/sald_modules/local/main.sald"), "");
+        write(
+            &root.join("sald_modules/broken/salad.json"),
+            r#"{"name": "broken", "version": "1.0.0", "main": "gone.sald"}"#,
+        );
+        let from = root.join("app");
+
+        let http = resolve_module(Some(&root), &from, "http").unwrap();
+        assert_eq!(http.entry, root.join("sald_modules/http/src/index.sald"));
+        assert_eq!(http.version.as_deref(), Some("2.0.0"));
+        let client = resolve_module(Some(&root), &from, "http/client").unwrap();
+        assert_eq!(client.entry, root.join("sald_modules/http/client.sald"));
+        let tiny = resolve_module(Some(&root), &from, "tiny").unwrap();
+        assert_eq!(tiny.entry, root.join("sald_modules/tiny/tiny.sald"));
+        let local = resolve_module(Some(&root), &from, "local").unwrap();
+        assert_eq!(local.entry, from.join("sald_modules/local/main.sald"));
+
+        let missing = resolve_module(Some(&root), &from, "nope").unwrap_err();
+        assert!(matches!(missing, ResolveError::NotFound { .. }));
+        assert_eq!(
+            missing.to_string(),
+            "Module 'nope' not found in sald_modules/"
+        );
+        assert!(missing.help().contains("salad add nope"));
+        let broken = resolve_module(Some(&root), &from, "broken").unwrap_err();
+        assert_eq!(
+            broken.to_string(),
+            "Module 'broken' entry file 'gone.sald' not found"
+        );
+
+        assert!(is_bare_specifier("http/client"));
+        assert!(!is_bare_specifier("./util"));
+        assert!(!is_bare_specifier("util.sald"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -7,15 +7,21 @@ pub struct Parser {
     current: usize,
     file: String,
     source: String,
+    has_doc_comments: bool,
+    /// Conditionals whose first branch is being parsed, still owed a `:`
+    open_ternaries: usize,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>, file: impl Into<String>, source: impl Into<String>) -> Self {
+        let source = source.into();
         Self {
             tokens,
             current: 0,
             file: file.into(),
-            source: source.into(),
+            has_doc_comments: source.contains("///"),
+            source,
+            open_ternaries: 0,
         }
     }
 
@@ -34,6 +40,14 @@ impl Parser {
     }
 
     fn declaration(&mut self) -> SaldResult<Stmt> {
+        let doc = self.doc_comment();
+        self.declaration_with_doc(doc)
+    }
+
+    fn declaration_with_doc(&mut self, doc: Option<String>) -> SaldResult<Stmt> {
+        if self.check_cfg() {
+            return self.cfg_declaration(doc);
+        }
         let decorators = self.parse_decorators()?;
 
         if self.check(&TokenKind::Let) {
@@ -49,16 +63,16 @@ impl Parser {
         } else if self.check(&TokenKind::Async) {
             self.advance();
             if self.check(&TokenKind::Fun) {
-                self.function_declaration(false, true, decorators)
+                self.function_declaration(false, true, decorators, doc)
             } else {
                 Err(self
                     .error("Expected 'fun' after 'async'")
                     .with_help("Use 'async fun name() { }' to declare an async function"))
             }
         } else if self.check(&TokenKind::Fun) {
-            self.function_declaration(false, false, decorators)
+            self.function_declaration(false, false, decorators, doc)
         } else if self.check(&TokenKind::Class) {
-            self.class_declaration(decorators)
+            self.class_declaration(decorators, doc)
         } else if self.check(&TokenKind::Namespace) {
             if !decorators.is_empty() {
                 return Err(self.error("Decorators cannot be applied to namespace declarations"));
@@ -74,7 +88,7 @@ impl Parser {
                 return Err(self.error("Decorators cannot be applied to interface declarations"));
             }
             self.interface_declaration()
-        } else if self.check(&TokenKind::Import) {
+        } else if self.check(&TokenKind::Import) && !self.check_ahead(1, &TokenKind::LeftParen) {
             if !decorators.is_empty() {
                 return Err(self.error("Decorators cannot be applied to import statements"));
             }
@@ -87,6 +101,87 @@ impl Parser {
         }
     }
 
+    /// `///` lines directly above the next token, with the markers stripped.
+    fn doc_comment(&self) -> Option<String> {
+        if !self.has_doc_comments {
+            return None;
+        }
+        let line = self.peek().span.start.line;
+        let above: Vec<&str> = self.source.lines().take(line.saturating_sub(1)).collect();
+        let mut lines: Vec<&str> = above
+            .iter()
+            .rev()
+            .map(|l| l.trim_start())
+            .take_while(|l| l.starts_with("///") && !l.starts_with("////"))
+            .map(|l| l[3..].strip_prefix(' ').unwrap_or(&l[3..]).trim_end())
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+
+    fn check_cfg(&self) -> bool {
+        self.check(&TokenKind::At)
+            && matches!(
+                self.tokens.get(self.current + 1).map(|t| &t.kind),
+                Some(TokenKind::Identifier(name)) if name == "cfg"
+            )
+    }
+
+    /// `@cfg(key: "value", ...)` followed by the declaration it guards.
+    fn cfg_declaration(&mut self, doc: Option<String>) -> SaldResult<Stmt> {
+        let start = self.advance().span;
+        self.advance();
+        self.consume(&TokenKind::LeftParen, "Expected '(' after '@cfg'")?;
+        let mut predicates = Vec::new();
+        loop {
+            let key_token = self.consume_identifier("Expected a key in '@cfg'")?;
+            let key = key_token.lexeme.clone();
+            let key_span = key_token.span;
+            if !crate::target::CFG_KEYS.contains(&key.as_str()) {
+                return Err(SaldError::syntax_error(
+                    format!("Unknown '@cfg' key '{}'", key),
+                    key_span,
+                    &self.file,
+                )
+                .with_source(&self.source)
+                .with_help(format!(
+                    "Available keys: {}",
+                    crate::target::CFG_KEYS.join(", ")
+                )));
+            }
+            self.consume(&TokenKind::Colon, "Expected ':' after '@cfg' key")?;
+            let TokenKind::String(value) = &self.peek().kind else {
+                return Err(self
+                    .error("Expected a string value in '@cfg'")
+                    .with_help("Write predicates like '@cfg(target: \"wasm\")'"));
+            };
+            let value = value.clone();
+            self.advance();
+            predicates.push(CfgPredicate {
+                key,
+                value,
+                span: key_span,
+            });
+            if !self.match_token(&TokenKind::Comma) || self.check(&TokenKind::RightParen) {
+                break;
+            }
+        }
+        self.consume(
+            &TokenKind::RightParen,
+            "Expected ')' after '@cfg' predicates",
+        )?;
+        let body = self.declaration_with_doc(doc)?;
+        let span = Span::new(start.start, body.span().end);
+        Ok(Stmt::Cfg {
+            predicates,
+            body: Box::new(body),
+            span,
+        })
+    }
+
     fn parse_decorators(&mut self) -> SaldResult<Vec<Decorator>> {
         let mut decorators = Vec::new();
 
@@ -94,6 +189,11 @@ impl Parser {
             let start = self.advance().span;
             let name_token = self.consume_identifier("Expected decorator name after '@'")?;
             let name = name_token.lexeme.clone();
+            if name == "cfg" {
+                return Err(self
+                    .error("'@cfg' must come before other decorators")
+                    .with_help("Move '@cfg(...)' to the first line of the declaration"));
+            }
 
             let args = if self.match_token(&TokenKind::LeftParen) {
                 let mut args = Vec::new();
@@ -251,6 +351,7 @@ impl Parser {
         is_static: bool,
         is_async: bool,
         decorators: Vec<Decorator>,
+        doc: Option<String>,
     ) -> SaldResult<Stmt> {
         let start_span = self.advance().span;
 
@@ -275,6 +376,7 @@ impl Parser {
                 is_static,
                 is_async,
                 decorators,
+                doc,
                 span: Span::from_positions(
                     start_span.start.line,
                     start_span.start.column,
@@ -285,26 +387,115 @@ impl Parser {
         })
     }
 
+    /// `fun name(params) { body }` in expression position, parsed as a block
+    /// that declares the function and evaluates to it. The name is only
+    /// bound inside the function, so it can call itself. Anonymous ones get
+    /// a name no identifier can spell.
+    fn function_expression(&mut self) -> SaldResult<Expr> {
+        let start_span = self.advance().span;
+
+        let (name, name_span) = if self.check_identifier() {
+            let token = self.advance();
+            (token.lexeme.clone(), token.span)
+        } else {
+            (
+                format!(
+                    "<fun@{}:{}>",
+                    start_span.start.line, start_span.start.column
+                ),
+                start_span,
+            )
+        };
+
+        self.consume(&TokenKind::LeftParen, "Expected '(' after 'fun'")?;
+        let params = self.parse_parameters()?;
+        self.consume(&TokenKind::RightParen, "Expected ')' after parameters")?;
+        self.consume(&TokenKind::LeftBrace, "Expected '{' before function body")?;
+        let body = self.block_statements()?;
+        let end_span = self.previous().span;
+        let span = Span::from_positions(
+            start_span.start.line,
+            start_span.start.column,
+            end_span.end.line,
+            end_span.end.column,
+        );
+
+        Ok(Expr::Block {
+            statements: vec![Stmt::Function {
+                def: FunctionDef {
+                    name: name.clone(),
+                    params,
+                    body,
+                    is_static: false,
+                    is_async: false,
+                    decorators: Vec::new(),
+                    doc: None,
+                    span,
+                },
+            }],
+            expr: Some(Box::new(Expr::Identifier {
+                name,
+                span: name_span,
+            })),
+            span,
+        })
+    }
+
     fn parse_parameters(&mut self) -> SaldResult<Vec<FunctionParam>> {
+        self.parameter_list(&TokenKind::RightParen, false)
+    }
+
+    /// Parameters of a lambda, up to and including the closing `|`
+    fn lambda_parameters(&mut self, message: &str) -> SaldResult<Vec<FunctionParam>> {
+        let params = self.parameter_list(&TokenKind::Pipe, true)?;
+        self.consume(&TokenKind::Pipe, message)?;
+        Ok(params)
+    }
+
+    /// Parameters up to `end`, which is left unconsumed. Lambdas take
+    /// neither `self` nor default values, since a default would run into
+    /// the closing `|`.
+    fn parameter_list(
+        &mut self,
+        end: &TokenKind,
+        in_lambda: bool,
+    ) -> SaldResult<Vec<FunctionParam>> {
         let mut params = Vec::new();
         let mut found_variadic = false;
         let mut found_default = false;
+        let mut found_kwargs = false;
 
-        if !self.check(&TokenKind::RightParen) {
+        if !self.check(end) {
             loop {
-                let is_variadic = self.match_token(&TokenKind::DotDotDot);
+                if found_kwargs {
+                    return Err(self
+                        .error("Parameter cannot follow a '**' parameter")
+                        .with_help("The '**' parameter must be the last parameter"));
+                }
+
+                let is_kwargs =
+                    self.check(&TokenKind::Star) && self.check_ahead(1, &TokenKind::Star);
+                if is_kwargs {
+                    self.advance();
+                    self.advance();
+                    found_kwargs = true;
+                }
+                let is_variadic = !is_kwargs && self.match_token(&TokenKind::DotDotDot);
 
-                if found_variadic {
+                if found_variadic && !is_kwargs {
                     return Err(self
                         .error("Variadic parameter must be the last parameter")
-                        .with_help("Only one variadic parameter is allowed, and it must be last"));
+                        .with_help(
+                            "Only one variadic parameter is allowed, and only '**' may follow it",
+                        ));
                 }
 
                 if is_variadic {
                     found_variadic = true;
                 }
 
-                let (param_name, param_span) = if self.check(&TokenKind::SelfKeyword) {
+                let (param_name, param_span) = if !in_lambda && self.check(&TokenKind::SelfKeyword)
+                {
                     let tok = self.advance();
                     (tok.lexeme.clone(), tok.span)
                 } else {
@@ -312,11 +503,14 @@ impl Parser {
                     (tok.lexeme.clone(), tok.span)
                 };
 
-                let default_value = if self.match_token(&TokenKind::Equal) {
+                let default_value = if !in_lambda && self.match_token(&TokenKind::Equal) {
+                    if is_kwargs {
+                        return Err(self.error("A '**' parameter cannot have a default value"));
+                    }
                     found_default = true;
                     Some(self.expression()?)
                 } else {
-                    if found_default && !is_variadic {
+                    if found_default && !is_variadic && !is_kwargs {
                         return Err(self
                             .error("Required parameter cannot follow optional parameter")
                             .with_help("Move parameters with default values to the end"));
@@ -327,6 +521,7 @@ impl Parser {
                 params.push(FunctionParam {
                     name: param_name,
                     is_variadic,
+                    is_kwargs,
                     default_value,
                     span: param_span,
                 });
@@ -340,7 +535,11 @@ impl Parser {
         Ok(params)
     }
 
-    fn class_declaration(&mut self, decorators: Vec<Decorator>) -> SaldResult<Stmt> {
+    fn class_declaration(
+        &mut self,
+        decorators: Vec<Decorator>,
+        doc: Option<String>,
+    ) -> SaldResult<Stmt> {
         let start_span = self.advance().span;
 
         let name_token = self.consume_identifier("Expected class name")?;
@@ -373,6 +572,7 @@ impl Parser {
         let mut methods = Vec::new();
 
         while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            let method_doc = self.doc_comment();
             let method_decorators = self.parse_decorators()?;
 
             let is_async = self.match_token(&TokenKind::Async);
@@ -384,7 +584,7 @@ impl Parser {
             }
 
             if let Stmt::Function { def } =
-                self.function_declaration(false, is_async, method_decorators)?
+                self.function_declaration(false, is_async, method_decorators, method_doc)?
             {
                 let is_static = def.params.first().map(|p| p.name != "self").unwrap_or(true);
                 methods.push(FunctionDef { is_static, ..def });
@@ -401,6 +601,7 @@ impl Parser {
                 implements,
                 methods,
                 decorators,
+                doc,
                 span: Span::from_positions(
                     start_span.start.line,
                     start_span.start.column,
@@ -474,7 +675,11 @@ impl Parser {
 
         let mut body = Vec::new();
         while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
-            body.push(self.declaration()?);
+            if self.check_export() {
+                body.push(self.export_statement()?);
+            } else {
+                body.push(self.declaration()?);
+            }
         }
 
         self.consume(&TokenKind::RightBrace, "Expected '}' after namespace body")?;
@@ -492,6 +697,40 @@ impl Parser {
         })
     }
 
+    /// `export` is only a keyword at the start of a namespace member, so it
+    /// stays usable as a name elsewhere.
+    fn check_export(&self) -> bool {
+        matches!(&self.peek().kind, TokenKind::Identifier(name) if name == "export")
+            && matches!(
+                self.tokens.get(self.current + 1).map(|t| &t.kind),
+                Some(TokenKind::Identifier(_))
+            )
+    }
+
+    fn export_statement(&mut self) -> SaldResult<Stmt> {
+        let start_span = self.advance().span;
+
+        let mut names = Vec::new();
+        loop {
+            let name_token = self.consume_identifier("Expected name to export")?;
+            names.push(name_token.lexeme.clone());
+            if !self.match_token(&TokenKind::Comma) {
+                break;
+            }
+        }
+        let end_span = self.previous().span;
+
+        Ok(Stmt::Export {
+            names,
+            span: Span::from_positions(
+                start_span.start.line,
+                start_span.start.column,
+                end_span.end.line,
+                end_span.end.column,
+            ),
+        })
+    }
+
     fn enum_declaration(&mut self) -> SaldResult<Stmt> {
         let start_span = self.advance().span;
 
@@ -596,6 +835,9 @@ impl Parser {
             self.break_statement()
         } else if self.check(&TokenKind::Continue) {
             self.continue_statement()
+        } else if self.check(&TokenKind::Debugger) {
+            let token = self.advance();
+            Ok(Stmt::Debugger { span: token.span })
         } else if self.check(&TokenKind::Try) {
             self.try_catch_statement()
         } else if self.check(&TokenKind::Throw) {
@@ -896,7 +1138,10 @@ impl Parser {
         let expr = self.null_coalesce()?;
 
         if self.match_token(&TokenKind::Question) {
-            let then_expr = self.ternary()?;
+            self.open_ternaries += 1;
+            let then_expr = self.ternary();
+            self.open_ternaries -= 1;
+            let then_expr = then_expr?;
             self.consume(&TokenKind::Colon, "Expected ':' in ternary expression")?;
             let else_expr = self.ternary()?;
             let span = Span::from_positions(
@@ -1284,6 +1529,15 @@ impl Parser {
         loop {
             if self.match_token(&TokenKind::LeftParen) {
                 expr = self.finish_call(expr, false)?;
+            } else if self.check_optional_bracket() && !self.starts_conditional() {
+                // `a?[i]`, `f?()` and their `?.` spellings
+                self.advance();
+                if self.match_token(&TokenKind::LeftParen) {
+                    expr = self.finish_call(expr, true)?;
+                } else {
+                    self.advance();
+                    expr = self.finish_index(expr, true)?;
+                }
             } else if self.match_token(&TokenKind::Dot) {
                 let name_token = self.consume_identifier("Expected property name after '.'")?;
                 let span = Span::from_positions(
@@ -1313,20 +1567,7 @@ impl Parser {
                     span,
                 };
             } else if self.match_token(&TokenKind::LeftBracket) {
-                let index = self.expression()?;
-                let bracket = self.consume(&TokenKind::RightBracket, "Expected ']' after index")?;
-                let span = Span::from_positions(
-                    expr.span().start.line,
-                    expr.span().start.column,
-                    bracket.span.end.line,
-                    bracket.span.end.column,
-                );
-                expr = Expr::Index {
-                    object: Box::new(expr),
-                    index: Box::new(index),
-                    is_optional: false,
-                    span,
-                };
+                expr = self.finish_index(expr, false)?;
             } else {
                 break;
             }
@@ -1335,6 +1576,67 @@ impl Parser {
         Ok(expr)
     }
 
+    /// Whether the next tokens start an optional index or call: `?.` followed
+    /// by `[` or `(`, or a `?` written against both its operand and the
+    /// bracket, as `a ? [x] : y` is a conditional
+    fn check_optional_bracket(&self) -> bool {
+        let Some(next) = self.tokens.get(self.current + 1) else {
+            return false;
+        };
+        if !matches!(next.kind, TokenKind::LeftBracket | TokenKind::LeftParen) {
+            return false;
+        }
+        let question = self.peek();
+        match question.kind {
+            TokenKind::QuestionDot => true,
+            TokenKind::Question => {
+                let touches = |a: &Span, b: &Span| {
+                    a.end.line == b.start.line && a.end.column + 1 == b.start.column
+                };
+                touches(&self.previous().span, &question.span)
+                    && touches(&question.span, &next.span)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a bare `?` before `[` or `(` begins a conditional, as in
+    /// `c?[1]:[2]`: both branches parse, and inside the first branch of another
+    /// conditional the `:` that one needs still follows
+    fn starts_conditional(&mut self) -> bool {
+        if !self.check(&TokenKind::Question) {
+            return false;
+        }
+        let start = self.current;
+        self.advance();
+        self.open_ternaries += 1;
+        let then_branch = self.ternary().is_ok();
+        self.open_ternaries -= 1;
+        let parsed = then_branch
+            && self.match_token(&TokenKind::Colon)
+            && self.ternary().is_ok()
+            && (self.open_ternaries == 0 || self.check(&TokenKind::Colon));
+        self.current = start;
+        parsed
+    }
+
+    fn finish_index(&mut self, object: Expr, is_optional: bool) -> SaldResult<Expr> {
+        let index = self.expression()?;
+        let bracket = self.consume(&TokenKind::RightBracket, "Expected ']' after index")?;
+        let span = Span::from_positions(
+            object.span().start.line,
+            object.span().start.column,
+            bracket.span.end.line,
+            bracket.span.end.column,
+        );
+        Ok(Expr::Index {
+            object: Box::new(object),
+            index: Box::new(index),
+            is_optional,
+            span,
+        })
+    }
+
     fn finish_call(&mut self, callee: Expr, is_optional: bool) -> SaldResult<Expr> {
         let mut args: Vec<CallArg> = Vec::new();
         let mut seen_named = false;
@@ -1343,7 +1645,15 @@ impl Parser {
             loop {
                 let arg_start_span = self.peek().span;
 
-                if self.match_token(&TokenKind::DotDotDot) {
+                if self.check(&TokenKind::DotDotDot) {
+                    if seen_named {
+                        return Err(self
+                            .error("Spread argument cannot follow named argument")
+                            .with_help(
+                                "Named arguments must come after all positional arguments",
+                            ));
+                    }
+                    self.advance();
                     let start_span = self.previous().span;
                     let expr = self.expression()?;
                     let end_span = expr.span();
@@ -1355,6 +1665,28 @@ impl Parser {
                     );
                     args.push(CallArg {
                         name: None,
+                        is_dict_spread: false,
+                        value: Expr::Spread {
+                            expr: Box::new(expr),
+                            span,
+                        },
+                        span,
+                    });
+                } else if self.check(&TokenKind::Star) && self.check_ahead(1, &TokenKind::Star) {
+                    seen_named = true;
+                    self.advance();
+                    self.advance();
+                    let expr = self.expression()?;
+                    let end_span = expr.span();
+                    let span = Span::from_positions(
+                        arg_start_span.start.line,
+                        arg_start_span.start.column,
+                        end_span.end.line,
+                        end_span.end.column,
+                    );
+                    args.push(CallArg {
+                        name: None,
+                        is_dict_spread: true,
                         value: Expr::Spread {
                             expr: Box::new(expr),
                             span,
@@ -1363,6 +1695,13 @@ impl Parser {
                     });
                 } else if self.check_identifier() && self.check_ahead(1, &TokenKind::Colon) {
                     seen_named = true;
+                    if args
+                        .iter()
+                        .any(|a| a.name.as_ref() == Some(&self.peek().lexeme))
+                    {
+                        let message = format!("Duplicate named argument '{}'", self.peek().lexeme);
+                        return Err(self.error(&message));
+                    }
                     let name_token = self.advance();
                     let arg_name = name_token.lexeme.clone();
                     self.advance();
@@ -1376,6 +1715,7 @@ impl Parser {
                     );
                     args.push(CallArg {
                         name: Some(arg_name),
+                        is_dict_spread: false,
                         value,
                         span,
                     });
@@ -1391,6 +1731,7 @@ impl Parser {
                     let span = value.span();
                     args.push(CallArg {
                         name: None,
+                        is_dict_spread: false,
                         value,
                         span,
                     });
@@ -1448,6 +1789,22 @@ impl Parser {
                     span: token.span,
                 })
             }
+            TokenKind::DateLiteral(s) => {
+                let s = s.clone();
+                self.advance();
+                Ok(Expr::Literal {
+                    value: Literal::Date(s),
+                    span: token.span,
+                })
+            }
+            TokenKind::TimeLiteral(s) => {
+                let s = s.clone();
+                self.advance();
+                Ok(Expr::Literal {
+                    value: Literal::Time(s),
+                    span: token.span,
+                })
+            }
             TokenKind::True => {
                 self.advance();
                 Ok(Expr::Literal {
@@ -1526,33 +1883,7 @@ impl Parser {
                 self.advance();
                 let start_span = token.span;
 
-                let mut params = Vec::new();
-                let mut found_variadic = false;
-                if !self.check(&TokenKind::Pipe) {
-                    loop {
-                        let is_variadic = self.match_token(&TokenKind::DotDotDot);
-
-                        if found_variadic {
-                            return Err(self.error("Variadic parameter must be the last parameter"));
-                        }
-
-                        if is_variadic {
-                            found_variadic = true;
-                        }
-
-                        let param_token = self.consume_identifier("Expected parameter name")?;
-                        params.push(FunctionParam {
-                            name: param_token.lexeme.clone(),
-                            is_variadic,
-                            default_value: None,
-                            span: param_token.span,
-                        });
-                        if !self.match_token(&TokenKind::Comma) {
-                            break;
-                        }
-                    }
-                }
-                self.consume(&TokenKind::Pipe, "Expected '|' after lambda parameters")?;
+                let params = self.lambda_parameters("Expected '|' after lambda parameters")?;
 
                 let body = if self.check(&TokenKind::LeftBrace) {
                     self.advance();
@@ -1602,6 +1933,23 @@ impl Parser {
                     ),
                 })
             }
+            TokenKind::Fun => self.function_expression(),
+            TokenKind::Import => {
+                let start_span = self.advance().span;
+                self.consume(&TokenKind::LeftParen, "Expected '(' after 'import'")?;
+                let path = self.expression()?;
+                self.consume(&TokenKind::RightParen, "Expected ')' after import path")?;
+                let end_span = self.previous().span;
+                Ok(Expr::Import {
+                    path: Box::new(path),
+                    span: Span::from_positions(
+                        start_span.start.line,
+                        start_span.start.column,
+                        end_span.end.line,
+                        end_span.end.column,
+                    ),
+                })
+            }
             TokenKind::Async => {
                 let async_token = self.advance().clone();
 
@@ -1610,37 +1958,7 @@ impl Parser {
                     Vec::new()
                 } else if self.check(&TokenKind::Pipe) {
                     self.advance();
-
-                    let mut params = Vec::new();
-                    let mut found_variadic = false;
-                    if !self.check(&TokenKind::Pipe) {
-                        loop {
-                            let is_variadic = self.match_token(&TokenKind::DotDotDot);
-                            if found_variadic {
-                                return Err(
-                                    self.error("Variadic parameter must be the last parameter")
-                                );
-                            }
-                            if is_variadic {
-                                found_variadic = true;
-                            }
-                            let param_token = self.consume_identifier("Expected parameter name")?;
-                            params.push(FunctionParam {
-                                name: param_token.lexeme.clone(),
-                                is_variadic,
-                                default_value: None,
-                                span: param_token.span,
-                            });
-                            if !self.match_token(&TokenKind::Comma) {
-                                break;
-                            }
-                        }
-                    }
-                    self.consume(
-                        &TokenKind::Pipe,
-                        "Expected '|' after async lambda parameters",
-                    )?;
-                    params
+                    self.lambda_parameters("Expected '|' after async lambda parameters")?
                 } else {
                     return Err(self.error("Expected '|' or '||' after 'async' for async lambda")
                         .with_help("Use 'async |params| { body }' or 'async || { body }' for async lambdas"));
@@ -1820,6 +2138,7 @@ impl Parser {
                 || self.check(&TokenKind::Return)
                 || self.check(&TokenKind::Break)
                 || self.check(&TokenKind::Continue)
+                || self.check(&TokenKind::Debugger)
                 || self.check(&TokenKind::Try)
                 || self.check(&TokenKind::Throw)
             {
@@ -2236,6 +2555,7 @@ impl Parser {
                 || self.check(&TokenKind::Return)
                 || self.check(&TokenKind::Break)
                 || self.check(&TokenKind::Continue)
+                || self.check(&TokenKind::Debugger)
                 || self.check(&TokenKind::Try)
                 || self.check(&TokenKind::Throw)
             {
@@ -2424,3 +2744,107 @@ impl Parser {
         SaldError::syntax_error(message, token.span, &self.file).with_source(&self.source)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_optional_index_and_call() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                let missing = null
+                let items = [10, 20]
+                let double = |x| x * 2
+                let calls = 0
+                fun next() {
+                    calls += 1
+                    return "abc"
+                }
+                let picked = next()?.upper()
+                let yes = true
+                let parts = [
+                    missing?[0],
+                    items?[1],
+                    missing?(1),
+                    double?(4),
+                    items?.[0],
+                    double?.(5),
+                    picked,
+                    calls,
+                    yes ? [1] : [2]
+                ]
+                parts.map(|v| "" + v).join(" ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result, "null 20 null 8 10 10 ABC 1 [1]");
+    }
+
+    #[test]
+    fn test_unspaced_conditionals_are_not_optional_links() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                let yes = true
+                let no = false
+                let items = [10, 20]
+                let parts = [
+                    yes?[1]:[2],
+                    no?[1]:[2],
+                    yes?(1):(2),
+                    no?(1):(2),
+                    yes?[3].length():0,
+                    yes ? items?[1] : 0,
+                    no ? 0 : items?[0]
+                ]
+                parts.map(|v| "" + v).join(" ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result, "[1] [2] 1 2 1 20 10");
+    }
+
+    #[test]
+    fn test_optional_chains_and_coalesce_assignment() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                let lookups = 0
+                fun find() {
+                    lookups += 1
+                    return null
+                }
+                class Box {
+                    fun init(self) { self.items = null }
+                    fun get(self) { return self }
+                }
+                let box = Box()
+                box.get().items ??= []
+                box.items.push(1)
+                box.items ??= "unused"
+                let config = {"port": null}
+                config["port"] ??= 80
+                config["port"] ??= 81
+                let name = null
+                name ??= "anon"
+                let parts = [
+                    find()?.user().name.first ?? "nobody",
+                    find()?.user()?.name ?? "none",
+                    lookups,
+                    box?.get().items.length(),
+                    box.items,
+                    config["port"],
+                    name
+                ]
+                parts.map(|v| "" + v).join(" ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result, "nobody none 2 1 [1] 80 anon");
+    }
+}
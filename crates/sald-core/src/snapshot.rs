@@ -0,0 +1,1118 @@
+//! VM snapshots
+//! Serializes the globals a prelude defined (functions, classes, data) so a new
+//! VM can restore them without running the prelude again
+
+use crate::binary::{
+    self, read_f64, read_optional_string, read_string, read_u32, write_optional_string,
+    write_string, write_u32,
+};
+use crate::builtins;
+use crate::builtins::date::{self, DateKind};
+use crate::vm::value::{Array, Class, Dict, Function, Globals, Instance, UpvalueObj, Value};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const MAGIC: &[u8; 8] = b"SALDSNAP";
+const VERSION: u8 = 2;
+
+/// A value as stored in the snapshot. Heap objects are referenced by index so
+/// sharing and cycles survive a round trip.
+enum Encoded {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Object(u32),
+    Builtin(String),
+    Namespace {
+        name: String,
+        members: u32,
+        module_globals: Option<u32>,
+    },
+    Enum {
+        name: String,
+        variants: Vec<(String, Encoded)>,
+    },
+    BoundMethod {
+        receiver: Box<Encoded>,
+        method: u32,
+    },
+    Date {
+        seconds: f64,
+        kind: DateKind,
+    },
+}
+
+enum Record {
+    Array {
+        items: Vec<Encoded>,
+        frozen: bool,
+    },
+    Dict {
+        entries: Vec<(String, Encoded)>,
+        frozen: bool,
+        consts: Vec<String>,
+    },
+    Upvalue(Encoded),
+    Function {
+        function: Box<Function>,
+        upvalues: Vec<u32>,
+    },
+    Class {
+        name: String,
+        superclass: Option<Encoded>,
+        methods: Vec<(String, u32)>,
+        static_methods: Vec<(String, u32)>,
+    },
+    Instance {
+        class: Encoded,
+        fields: Vec<(String, Encoded)>,
+        frozen: bool,
+    },
+}
+
+/// Serializes every global that is not a builtin.
+///
+/// Fails if a global holds something that cannot outlive the process, such as
+/// a native or host function, a pending Future, or a closure whose captured
+/// variable is still on the stack.
+pub fn snapshot_globals(globals: &Globals) -> Result<Vec<u8>, String> {
+    let builtin_names: Vec<String> = builtins::create_builtin_classes().into_keys().collect();
+    let mut encoder = Encoder {
+        builtin_names,
+        ids: FxHashMap::default(),
+        records: Vec::new(),
+    };
+
+    let mut names: Vec<&String> = globals.keys().collect();
+    names.sort();
+    let mut entries = Vec::new();
+    for name in names {
+        let encoded = encoder
+            .value(&globals[name])
+            .map_err(|e| format!("Cannot snapshot global '{}': {}", name, e))?;
+        if matches!(&encoded, Encoded::Builtin(b) if b == name) {
+            continue;
+        }
+        entries.push((name.clone(), encoded));
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    write_u32(&mut out, encoder.records.len() as u32);
+    for record in &encoder.records {
+        match record {
+            Some(record) => write_record(&mut out, record),
+            None => return Err("Snapshot encoder left an object unfinished".to_string()),
+        }
+    }
+    write_entries(&mut out, &entries);
+    write_strings(&mut out, &sorted_consts(globals));
+    Ok(out)
+}
+
+/// Restores globals from `snapshot_globals` output. Builtins are taken from a
+/// fresh set so native classes work as usual.
+pub fn restore_globals(data: &[u8]) -> Result<Globals, String> {
+    if data.len() < MAGIC.len() + 1 || &data[..MAGIC.len()] != MAGIC {
+        return Err("Invalid snapshot".to_string());
+    }
+    let mut cursor = MAGIC.len();
+    let version = data[cursor];
+    if version != VERSION {
+        return Err(format!("Unsupported snapshot version: {}", version));
+    }
+    cursor += 1;
+
+    let count = read_u32(data, &mut cursor)? as usize;
+    let mut records = Vec::with_capacity(count.min(data.len()));
+    for _ in 0..count {
+        records.push(read_record(data, &mut cursor)?);
+    }
+    let entries = read_entries(data, &mut cursor)?;
+    let consts = read_strings(data, &mut cursor)?;
+
+    let mut decoder = Decoder {
+        builtins: builtins::create_builtin_classes(),
+        objects: Vec::with_capacity(records.len()),
+    };
+    decoder.build(&records)?;
+
+    let mut globals: Globals = builtins::create_builtin_classes().into();
+    for (name, encoded) in &entries {
+        globals.insert(name.clone(), decoder.value(encoded)?);
+    }
+    for name in consts {
+        globals.set_const(name);
+    }
+    Ok(globals)
+}
+
+/// The names a globals table declared `const`, in a stable order
+fn sorted_consts(globals: &Globals) -> Vec<String> {
+    let mut consts: Vec<String> = globals.consts().iter().cloned().collect();
+    consts.sort();
+    consts
+}
+
+struct Encoder {
+    builtin_names: Vec<String>,
+    ids: FxHashMap<usize, u32>,
+    records: Vec<Option<Record>>,
+}
+
+impl Encoder {
+    fn reserve(&mut self, ptr: usize) -> u32 {
+        let id = self.records.len() as u32;
+        self.records.push(None);
+        self.ids.insert(ptr, id);
+        id
+    }
+
+    fn is_builtin(&self, name: &str) -> bool {
+        self.builtin_names.iter().any(|b| b == name)
+    }
+
+    fn value(&mut self, value: &Value) -> Result<Encoded, String> {
+        Ok(match value {
+            Value::Null => Encoded::Null,
+            Value::Boolean(b) => Encoded::Boolean(*b),
+            Value::Number(n) => Encoded::Number(*n),
+            Value::String(s) => Encoded::String(s.to_string()),
+            Value::Array(arr) => {
+                let ptr = Rc::as_ptr(arr) as *const () as usize;
+                if let Some(id) = self.ids.get(&ptr) {
+                    return Ok(Encoded::Object(*id));
+                }
+                let id = self.reserve(ptr);
+                let items = arr
+                    .borrow()
+                    .iter()
+                    .map(|v| self.value(v))
+                    .collect::<Result<_, _>>()?;
+                let frozen = arr.borrow().is_frozen();
+                self.records[id as usize] = Some(Record::Array { items, frozen });
+                Encoded::Object(id)
+            }
+            Value::Dictionary(dict) => {
+                if dict.borrow().has_instance_keys() {
+                    return Err("Dicts with instance keys cannot be snapshotted".to_string());
+                }
+                let ptr = Rc::as_ptr(dict) as *const () as usize;
+                let frozen = dict.borrow().is_frozen();
+                Encoded::Object(self.entries(ptr, &dict.borrow(), frozen, Vec::new())?)
+            }
+            Value::Function(func) => Encoded::Object(self.function(func)?),
+            Value::Class(class) => self.class(class)?,
+            Value::Instance(inst) => {
+                let ptr = Rc::as_ptr(inst) as *const () as usize;
+                if let Some(id) = self.ids.get(&ptr) {
+                    return Ok(Encoded::Object(*id));
+                }
+                if let Some((seconds, kind)) = date::date_parts(value) {
+                    return Ok(Encoded::Date { seconds, kind });
+                }
+                if inst.borrow().native.is_some() {
+                    return Err(format!(
+                        "{} instances cannot be snapshotted",
+                        inst.borrow().class_name
+                    ));
+                }
+                let class = self.class(&inst.borrow().class)?;
+                let id = self.reserve(ptr);
+                let fields = self.fields(&inst.borrow().fields)?;
+                let frozen = inst.borrow().frozen;
+                self.records[id as usize] = Some(Record::Instance {
+                    class,
+                    fields,
+                    frozen,
+                });
+                Encoded::Object(id)
+            }
+            Value::BoundMethod { receiver, method } => Encoded::BoundMethod {
+                receiver: Box::new(self.value(receiver)?),
+                method: self.function(method)?,
+            },
+            Value::Namespace {
+                name,
+                members,
+                module_globals,
+            } => {
+                let native = !members
+                    .borrow()
+                    .values()
+                    .any(|v| matches!(v, Value::Function(_)));
+                if module_globals.is_none() && native && self.is_builtin(name) {
+                    return Ok(Encoded::Builtin(name.clone()));
+                }
+                Encoded::Namespace {
+                    name: name.clone(),
+                    members: self.map(members)?,
+                    module_globals: match module_globals {
+                        Some(globals) => Some(self.map(globals)?),
+                        None => None,
+                    },
+                }
+            }
+            Value::Enum { name, variants } => {
+                let mut keys: Vec<&String> = variants.keys().collect();
+                keys.sort();
+                let mut encoded = Vec::with_capacity(keys.len());
+                for key in keys {
+                    encoded.push((key.clone(), self.value(&variants[key])?));
+                }
+                Encoded::Enum {
+                    name: name.clone(),
+                    variants: encoded,
+                }
+            }
+            Value::NativeFunction { .. }
+            | Value::HostFunction { .. }
+            | Value::InstanceMethod { .. }
+            | Value::Future(_)
+            | Value::Partial { .. }
+            | Value::SpreadMarker(_)
+            | Value::NamedArgs(_) => {
+                return Err(format!(
+                    "{} values cannot be snapshotted",
+                    value.type_name()
+                ))
+            }
+        })
+    }
+
+    fn map(&mut self, map: &Rc<RefCell<Globals>>) -> Result<u32, String> {
+        let ptr = Rc::as_ptr(map) as *const () as usize;
+        let consts = sorted_consts(&map.borrow());
+        self.entries(ptr, &map.borrow(), false, consts)
+    }
+
+    /// Records a dict or module's members, the object at `ptr`
+    fn entries(
+        &mut self,
+        ptr: usize,
+        entries: &FxHashMap<String, Value>,
+        frozen: bool,
+        consts: Vec<String>,
+    ) -> Result<u32, String> {
+        if let Some(id) = self.ids.get(&ptr) {
+            return Ok(*id);
+        }
+        let id = self.reserve(ptr);
+        let entries = self.fields(entries)?;
+        self.records[id as usize] = Some(Record::Dict {
+            entries,
+            frozen,
+            consts,
+        });
+        Ok(id)
+    }
+
+    fn fields(
+        &mut self,
+        fields: &FxHashMap<String, Value>,
+    ) -> Result<Vec<(String, Encoded)>, String> {
+        let mut keys: Vec<&String> = fields.keys().collect();
+        keys.sort();
+        keys.into_iter()
+            .map(|k| Ok((k.clone(), self.value(&fields[k])?)))
+            .collect()
+    }
+
+    fn function(&mut self, func: &Rc<Function>) -> Result<u32, String> {
+        let ptr = Rc::as_ptr(func) as *const () as usize;
+        if let Some(id) = self.ids.get(&ptr) {
+            return Ok(*id);
+        }
+        let id = self.reserve(ptr);
+        let mut upvalues = Vec::with_capacity(func.upvalues.len());
+        for upvalue in &func.upvalues {
+            let ptr = Rc::as_ptr(upvalue) as *const () as usize;
+            if let Some(id) = self.ids.get(&ptr) {
+                upvalues.push(*id);
+                continue;
+            }
+            let upvalue_id = self.reserve(ptr);
+            let closed = match &upvalue.borrow().closed {
+                Some(value) => self.value(value)?,
+                None => {
+                    return Err(format!(
+                        "function '{}' captures a variable that is still in scope",
+                        func.name
+                    ))
+                }
+            };
+            self.records[upvalue_id as usize] = Some(Record::Upvalue(closed));
+            upvalues.push(upvalue_id);
+        }
+
+        let mut function = (**func).clone();
+        function.upvalues.clear();
+        self.records[id as usize] = Some(Record::Function {
+            function: Box::new(function),
+            upvalues,
+        });
+        Ok(id)
+    }
+
+    fn class(&mut self, class: &Rc<Class>) -> Result<Encoded, String> {
+        let ptr = Rc::as_ptr(class) as *const () as usize;
+        if let Some(id) = self.ids.get(&ptr) {
+            return Ok(Encoded::Object(*id));
+        }
+
+        let native = !class.native_static_methods.is_empty()
+            || !class.callable_native_static_methods.is_empty()
+            || !class.native_instance_methods.is_empty()
+            || !class.callable_native_instance_methods.is_empty()
+            || !class.native_static_fields.is_empty()
+            || class.constructor.is_some()
+            || class.callable_constructor.is_some();
+        if native {
+            if self.is_builtin(&class.name) {
+                return Ok(Encoded::Builtin(class.name.clone()));
+            }
+            return Err(format!(
+                "native class '{}' cannot be snapshotted",
+                class.name
+            ));
+        }
+
+        // Superclasses get lower ids so they can be rebuilt first
+        let superclass = match &class.superclass {
+            Some(superclass) => Some(self.class(superclass)?),
+            None => None,
+        };
+        let id = self.reserve(ptr);
+        let methods = self.methods(&class.methods)?;
+        let static_methods = self.methods(&class.user_static_methods)?;
+        self.records[id as usize] = Some(Record::Class {
+            name: class.name.clone(),
+            superclass,
+            methods,
+            static_methods,
+        });
+        Ok(Encoded::Object(id))
+    }
+
+    fn methods(
+        &mut self,
+        methods: &FxHashMap<String, Value>,
+    ) -> Result<Vec<(String, u32)>, String> {
+        let mut names: Vec<&String> = methods.keys().collect();
+        names.sort();
+        let mut encoded = Vec::with_capacity(names.len());
+        for name in names {
+            match &methods[name] {
+                Value::Function(func) => encoded.push((name.clone(), self.function(func)?)),
+                other => {
+                    return Err(format!(
+                        "method '{}' is a {}, not a function",
+                        name,
+                        other.type_name()
+                    ))
+                }
+            }
+        }
+        Ok(encoded)
+    }
+}
+
+enum Object {
+    Array(Rc<RefCell<Array>>),
+    /// Either can stand for a dict record: values use the first, namespaces
+    /// the second
+    Dict(Rc<RefCell<Dict>>, Rc<RefCell<Globals>>),
+    Upvalue(Rc<RefCell<UpvalueObj>>),
+    Function(Rc<Function>),
+    Class(Rc<Class>),
+    Instance(Rc<RefCell<Instance>>),
+}
+
+struct Decoder {
+    builtins: FxHashMap<String, Value>,
+    objects: Vec<Object>,
+}
+
+impl Decoder {
+    /// Creates objects in dependency order, then fills in their contents.
+    fn build(&mut self, records: &[Record]) -> Result<(), String> {
+        let mut pending: Vec<Option<Object>> = records
+            .iter()
+            .map(|record| match record {
+                Record::Array { .. } => Some(Object::Array(Rc::default())),
+                Record::Dict { .. } => Some(Object::Dict(Rc::default(), Rc::default())),
+                Record::Upvalue(_) => {
+                    let mut upvalue = UpvalueObj::new(0);
+                    upvalue.closed = Some(Box::new(Value::Null));
+                    Some(Object::Upvalue(Rc::new(RefCell::new(upvalue))))
+                }
+                _ => None,
+            })
+            .collect();
+
+        for (id, record) in records.iter().enumerate() {
+            if let Record::Function { function, upvalues } = record {
+                let mut function = (**function).clone();
+                for upvalue in upvalues {
+                    match pending.get(*upvalue as usize) {
+                        Some(Some(Object::Upvalue(u))) => function.upvalues.push(u.clone()),
+                        _ => return Err("Invalid snapshot: bad upvalue reference".to_string()),
+                    }
+                }
+                pending[id] = Some(Object::Function(Rc::new(function)));
+            }
+        }
+
+        for (id, record) in records.iter().enumerate() {
+            if let Record::Class {
+                name,
+                superclass,
+                methods,
+                static_methods,
+            } = record
+            {
+                let mut class = Class::new(name.clone());
+                if let Some(superclass) = superclass {
+                    class.superclass = Some(self.class_ref(&pending, superclass)?);
+                }
+                for (method, func) in methods {
+                    class
+                        .methods
+                        .insert(method.clone(), self.function_ref(&pending, *func)?);
+                }
+                for (method, func) in static_methods {
+                    class
+                        .user_static_methods
+                        .insert(method.clone(), self.function_ref(&pending, *func)?);
+                }
+                pending[id] = Some(Object::Class(Rc::new(class)));
+            }
+        }
+
+        for (id, record) in records.iter().enumerate() {
+            if let Record::Instance { class, .. } = record {
+                let class = self.class_ref(&pending, class)?;
+                let instance = Instance::new(class);
+                pending[id] = Some(Object::Instance(Rc::new(RefCell::new(instance))));
+            }
+        }
+
+        self.objects = pending
+            .into_iter()
+            .map(|o| o.ok_or_else(|| "Invalid snapshot: missing object".to_string()))
+            .collect::<Result<_, _>>()?;
+
+        for (id, record) in records.iter().enumerate() {
+            match (record, &self.objects[id]) {
+                (Record::Array { items, frozen }, Object::Array(arr)) => {
+                    let items = items
+                        .iter()
+                        .map(|v| self.value(v))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    *arr.borrow_mut() = items.into();
+                    if *frozen {
+                        arr.borrow_mut().freeze();
+                    }
+                }
+                (
+                    Record::Dict {
+                        entries,
+                        frozen,
+                        consts,
+                    },
+                    Object::Dict(dict, members),
+                ) => {
+                    let entries = self.entries(entries)?;
+                    *dict.borrow_mut() = entries.clone().into();
+                    if *frozen {
+                        dict.borrow_mut().freeze();
+                    }
+                    let mut members = members.borrow_mut();
+                    *members = entries.into();
+                    for name in consts {
+                        members.set_const(name.clone());
+                    }
+                }
+                (Record::Upvalue(value), Object::Upvalue(upvalue)) => {
+                    let value = self.value(value)?;
+                    upvalue.borrow_mut().closed = Some(Box::new(value));
+                }
+                (Record::Instance { fields, frozen, .. }, Object::Instance(inst)) => {
+                    let fields = self.entries(fields)?;
+                    let mut inst = inst.borrow_mut();
+                    inst.fields = fields;
+                    inst.frozen = *frozen;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn class_ref(
+        &self,
+        pending: &[Option<Object>],
+        encoded: &Encoded,
+    ) -> Result<Rc<Class>, String> {
+        match encoded {
+            Encoded::Object(id) => match pending.get(*id as usize) {
+                Some(Some(Object::Class(class))) => Ok(class.clone()),
+                _ => Err("Invalid snapshot: bad class reference".to_string()),
+            },
+            Encoded::Builtin(name) => match self.builtins.get(name) {
+                Some(Value::Class(class)) => Ok(class.clone()),
+                _ => Err(format!("Unknown builtin class '{}'", name)),
+            },
+            _ => Err("Invalid snapshot: bad class reference".to_string()),
+        }
+    }
+
+    fn function_ref(&self, pending: &[Option<Object>], id: u32) -> Result<Value, String> {
+        match pending.get(id as usize) {
+            Some(Some(Object::Function(func))) => Ok(Value::Function(func.clone())),
+            _ => Err("Invalid snapshot: bad function reference".to_string()),
+        }
+    }
+
+    fn map_ref(&self, id: u32) -> Result<Rc<RefCell<Globals>>, String> {
+        match self.objects.get(id as usize) {
+            Some(Object::Dict(_, members)) => Ok(members.clone()),
+            _ => Err("Invalid snapshot: bad map reference".to_string()),
+        }
+    }
+
+    fn entries(&self, entries: &[(String, Encoded)]) -> Result<FxHashMap<String, Value>, String> {
+        entries
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), self.value(v)?)))
+            .collect()
+    }
+
+    fn value(&self, encoded: &Encoded) -> Result<Value, String> {
+        Ok(match encoded {
+            Encoded::Null => Value::Null,
+            Encoded::Boolean(b) => Value::Boolean(*b),
+            Encoded::Number(n) => Value::Number(*n),
+            Encoded::String(s) => Value::String(Rc::from(s.as_str())),
+            Encoded::Object(id) => match self.objects.get(*id as usize) {
+                Some(Object::Array(arr)) => Value::Array(arr.clone()),
+                Some(Object::Dict(dict, _)) => Value::Dictionary(dict.clone()),
+                Some(Object::Function(func)) => Value::Function(func.clone()),
+                Some(Object::Class(class)) => Value::Class(class.clone()),
+                Some(Object::Instance(inst)) => Value::Instance(inst.clone()),
+                _ => return Err("Invalid snapshot: bad object reference".to_string()),
+            },
+            Encoded::Builtin(name) => self
+                .builtins
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Unknown builtin '{}'", name))?,
+            Encoded::Namespace {
+                name,
+                members,
+                module_globals,
+            } => Value::Namespace {
+                name: name.clone(),
+                members: self.map_ref(*members)?,
+                module_globals: match module_globals {
+                    Some(id) => Some(self.map_ref(*id)?),
+                    None => None,
+                },
+            },
+            Encoded::Enum { name, variants } => Value::Enum {
+                name: name.clone(),
+                variants: Rc::new(self.entries(variants)?),
+            },
+            Encoded::BoundMethod { receiver, method } => match self.objects.get(*method as usize) {
+                Some(Object::Function(func)) => Value::BoundMethod {
+                    receiver: Box::new(self.value(receiver)?),
+                    method: func.clone(),
+                },
+                _ => return Err("Invalid snapshot: bad method reference".to_string()),
+            },
+            Encoded::Date { seconds, kind } => date::make_date(*seconds, *kind),
+        })
+    }
+}
+
+fn write_entries(out: &mut Vec<u8>, entries: &[(String, Encoded)]) {
+    write_u32(out, entries.len() as u32);
+    for (name, value) in entries {
+        write_string(out, name);
+        write_encoded(out, value);
+    }
+}
+
+fn read_entries(data: &[u8], cursor: &mut usize) -> Result<Vec<(String, Encoded)>, String> {
+    let count = read_u32(data, cursor)? as usize;
+    let mut entries = Vec::with_capacity(count.min(data.len()));
+    for _ in 0..count {
+        let name = read_string(data, cursor)?;
+        entries.push((name, read_encoded(data, cursor)?));
+    }
+    Ok(entries)
+}
+
+fn write_ids(out: &mut Vec<u8>, ids: &[(String, u32)]) {
+    write_u32(out, ids.len() as u32);
+    for (name, id) in ids {
+        write_string(out, name);
+        write_u32(out, *id);
+    }
+}
+
+fn read_ids(data: &[u8], cursor: &mut usize) -> Result<Vec<(String, u32)>, String> {
+    let count = read_u32(data, cursor)? as usize;
+    let mut ids = Vec::with_capacity(count.min(data.len()));
+    for _ in 0..count {
+        let name = read_string(data, cursor)?;
+        ids.push((name, read_u32(data, cursor)?));
+    }
+    Ok(ids)
+}
+
+fn read_u8(data: &[u8], cursor: &mut usize) -> Result<u8, String> {
+    let byte = *data.get(*cursor).ok_or("Unexpected end of file")?;
+    *cursor += 1;
+    Ok(byte)
+}
+
+fn write_encoded(out: &mut Vec<u8>, value: &Encoded) {
+    match value {
+        Encoded::Null => out.push(0),
+        Encoded::Boolean(b) => {
+            out.push(1);
+            out.push(*b as u8);
+        }
+        Encoded::Number(n) => {
+            out.push(2);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+        Encoded::String(s) => {
+            out.push(3);
+            write_string(out, s);
+        }
+        Encoded::Object(id) => {
+            out.push(4);
+            write_u32(out, *id);
+        }
+        Encoded::Builtin(name) => {
+            out.push(5);
+            write_string(out, name);
+        }
+        Encoded::Namespace {
+            name,
+            members,
+            module_globals,
+        } => {
+            out.push(6);
+            write_string(out, name);
+            write_u32(out, *members);
+            match module_globals {
+                Some(id) => {
+                    out.push(1);
+                    write_u32(out, *id);
+                }
+                None => out.push(0),
+            }
+        }
+        Encoded::Enum { name, variants } => {
+            out.push(7);
+            write_string(out, name);
+            write_entries(out, variants);
+        }
+        Encoded::BoundMethod { receiver, method } => {
+            out.push(8);
+            write_encoded(out, receiver);
+            write_u32(out, *method);
+        }
+        Encoded::Date { seconds, kind } => {
+            out.push(9);
+            out.extend_from_slice(&seconds.to_le_bytes());
+            out.push((*kind == DateKind::Time) as u8);
+        }
+    }
+}
+
+fn read_encoded(data: &[u8], cursor: &mut usize) -> Result<Encoded, String> {
+    Ok(match read_u8(data, cursor)? {
+        0 => Encoded::Null,
+        1 => Encoded::Boolean(read_u8(data, cursor)? != 0),
+        2 => Encoded::Number(read_f64(data, cursor)?),
+        3 => Encoded::String(read_string(data, cursor)?),
+        4 => Encoded::Object(read_u32(data, cursor)?),
+        5 => Encoded::Builtin(read_string(data, cursor)?),
+        6 => {
+            let name = read_string(data, cursor)?;
+            let members = read_u32(data, cursor)?;
+            let module_globals = match read_u8(data, cursor)? {
+                0 => None,
+                _ => Some(read_u32(data, cursor)?),
+            };
+            Encoded::Namespace {
+                name,
+                members,
+                module_globals,
+            }
+        }
+        7 => {
+            let name = read_string(data, cursor)?;
+            let variants = read_entries(data, cursor)?;
+            Encoded::Enum { name, variants }
+        }
+        8 => {
+            let receiver = Box::new(read_encoded(data, cursor)?);
+            let method = read_u32(data, cursor)?;
+            Encoded::BoundMethod { receiver, method }
+        }
+        9 => {
+            let seconds = read_f64(data, cursor)?;
+            let kind = match read_u8(data, cursor)? {
+                0 => DateKind::Date,
+                _ => DateKind::Time,
+            };
+            Encoded::Date { seconds, kind }
+        }
+        tag => return Err(format!("Unknown snapshot value type: {}", tag)),
+    })
+}
+
+fn write_record(out: &mut Vec<u8>, record: &Record) {
+    match record {
+        Record::Array { items, frozen } => {
+            out.push(0);
+            out.push(*frozen as u8);
+            write_u32(out, items.len() as u32);
+            for item in items {
+                write_encoded(out, item);
+            }
+        }
+        Record::Dict {
+            entries,
+            frozen,
+            consts,
+        } => {
+            out.push(1);
+            out.push(*frozen as u8);
+            write_entries(out, entries);
+            write_strings(out, consts);
+        }
+        Record::Upvalue(value) => {
+            out.push(2);
+            write_encoded(out, value);
+        }
+        Record::Function { function, upvalues } => {
+            out.push(3);
+            write_string(out, &function.name);
+            write_u32(out, function.arity as u32);
+            out.push(function.is_variadic as u8 | (function.has_kwargs as u8) << 1);
+            out.push(function.is_async as u8);
+            write_u32(out, function.upvalue_count as u32);
+            write_string(out, &function.file);
+            write_u32(out, function.param_names.len() as u32);
+            for name in &function.param_names {
+                write_string(out, name);
+            }
+            write_u32(out, function.default_count as u32);
+            write_u32(out, function.decorators.len() as u32);
+            for decorator in &function.decorators {
+                write_string(out, decorator);
+            }
+            write_optional_string(out, &function.namespace_context);
+            write_optional_string(out, &function.class_context);
+            let chunk = binary::serialize(&function.chunk);
+            write_u32(out, chunk.len() as u32);
+            out.extend_from_slice(&chunk);
+            write_u32(out, upvalues.len() as u32);
+            for id in upvalues {
+                write_u32(out, *id);
+            }
+        }
+        Record::Class {
+            name,
+            superclass,
+            methods,
+            static_methods,
+        } => {
+            out.push(4);
+            write_string(out, name);
+            match superclass {
+                Some(superclass) => {
+                    out.push(1);
+                    write_encoded(out, superclass);
+                }
+                None => out.push(0),
+            }
+            write_ids(out, methods);
+            write_ids(out, static_methods);
+        }
+        Record::Instance {
+            class,
+            fields,
+            frozen,
+        } => {
+            out.push(5);
+            out.push(*frozen as u8);
+            write_encoded(out, class);
+            write_entries(out, fields);
+        }
+    }
+}
+
+fn write_strings(out: &mut Vec<u8>, strings: &[String]) {
+    write_u32(out, strings.len() as u32);
+    for string in strings {
+        write_string(out, string);
+    }
+}
+
+fn read_strings(data: &[u8], cursor: &mut usize) -> Result<Vec<String>, String> {
+    let count = read_u32(data, cursor)? as usize;
+    let mut strings = Vec::with_capacity(count.min(data.len()));
+    for _ in 0..count {
+        strings.push(read_string(data, cursor)?);
+    }
+    Ok(strings)
+}
+
+fn read_record(data: &[u8], cursor: &mut usize) -> Result<Record, String> {
+    Ok(match read_u8(data, cursor)? {
+        0 => {
+            let frozen = read_u8(data, cursor)? != 0;
+            let count = read_u32(data, cursor)? as usize;
+            let mut items = Vec::with_capacity(count.min(data.len()));
+            for _ in 0..count {
+                items.push(read_encoded(data, cursor)?);
+            }
+            Record::Array { items, frozen }
+        }
+        1 => Record::Dict {
+            frozen: read_u8(data, cursor)? != 0,
+            entries: read_entries(data, cursor)?,
+            consts: read_strings(data, cursor)?,
+        },
+        2 => Record::Upvalue(read_encoded(data, cursor)?),
+        3 => {
+            let name = read_string(data, cursor)?;
+            let arity = read_u32(data, cursor)? as usize;
+            let flags = read_u8(data, cursor)?;
+            let is_async = read_u8(data, cursor)? != 0;
+            let upvalue_count = read_u32(data, cursor)? as usize;
+            let file = read_string(data, cursor)?;
+            let param_names = read_strings(data, cursor)?;
+            let default_count = read_u32(data, cursor)? as usize;
+            let decorators = read_strings(data, cursor)?;
+            let namespace_context = read_optional_string(data, cursor)?;
+            let class_context = read_optional_string(data, cursor)?;
+            let chunk_len = read_u32(data, cursor)? as usize;
+            if *cursor + chunk_len > data.len() {
+                return Err("Unexpected end of file".to_string());
+            }
+            let chunk = binary::deserialize(&data[*cursor..*cursor + chunk_len])?;
+            *cursor += chunk_len;
+            let upvalue_ids = read_u32(data, cursor)? as usize;
+            let mut upvalues = Vec::with_capacity(upvalue_ids.min(data.len()));
+            for _ in 0..upvalue_ids {
+                upvalues.push(read_u32(data, cursor)?);
+            }
+
+            let mut function = Function::new(name, arity, chunk);
+            function.is_variadic = flags & 1 != 0;
+            function.has_kwargs = flags & 2 != 0;
+            function.is_async = is_async;
+            function.upvalue_count = upvalue_count;
+            function.file = file;
+            function.param_names = param_names;
+            function.default_count = default_count;
+            function.decorators = decorators;
+            function.namespace_context = namespace_context;
+            function.class_context = class_context;
+            Record::Function {
+                function: Box::new(function),
+                upvalues,
+            }
+        }
+        4 => {
+            let name = read_string(data, cursor)?;
+            let superclass = match read_u8(data, cursor)? {
+                0 => None,
+                _ => Some(read_encoded(data, cursor)?),
+            };
+            let methods = read_ids(data, cursor)?;
+            let static_methods = read_ids(data, cursor)?;
+            Record::Class {
+                name,
+                superclass,
+                methods,
+                static_methods,
+            }
+        }
+        5 => {
+            let frozen = read_u8(data, cursor)? != 0;
+            let class = read_encoded(data, cursor)?;
+            let fields = read_entries(data, cursor)?;
+            Record::Instance {
+                class,
+                fields,
+                frozen,
+            }
+        }
+        tag => return Err(format!("Unknown snapshot object type: {}", tag)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use crate::vm::value::Value;
+
+    /// An engine restored from a snapshot taken after running `prelude`
+    fn restored(prelude: &str) -> Engine {
+        let mut engine = Engine::new();
+        engine.eval(prelude).unwrap();
+        Engine::from_snapshot(&engine.snapshot().unwrap()).unwrap()
+    }
+
+    /// Why taking a snapshot after running `prelude` fails
+    fn snapshot_err(prelude: &str) -> String {
+        let mut engine = Engine::new();
+        engine.eval(prelude).unwrap();
+        engine.snapshot().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_restores_classes_and_inheritance() {
+        let mut engine = restored(
+            "class Animal { fun init(self, name) { self.name = name } fun speak(self) { return self.name + \"!\" } }\n\
+             class Dog extends Animal { fun speak(self) { return super.speak() + \"!\" } }\n\
+             let pet = Dog(\"Rex\")",
+        );
+        assert_eq!(engine.eval_as::<String>("pet.speak()").unwrap(), "Rex!!");
+        assert_eq!(engine.eval_as::<String>("Dog(\"Fido\").speak()").unwrap(), "Fido!!");
+    }
+
+    #[test]
+    fn test_restores_closure_state() {
+        let mut engine = restored(
+            "fun counter() { let n = 0\n return || { n = n + 1\n return n } }\n\
+             let next = counter()\n\
+             next()",
+        );
+        assert_eq!(engine.eval_as::<f64>("next()").unwrap(), 2.0);
+        assert_eq!(engine.eval_as::<f64>("next()").unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_keeps_shared_containers_shared() {
+        let mut engine = restored("let shared = [1, 2]\nlet config = {\"a\": shared, \"b\": shared}");
+        engine.eval("config[\"a\"].push(3)").unwrap();
+        assert_eq!(engine.eval_as::<f64>("config[\"b\"].length()").unwrap(), 3.0);
+    }
+
+    #[test]
+    fn test_restores_cycles() {
+        let mut engine = restored("let xs = [1]\nxs.push(xs)");
+        assert!(engine.eval_as::<bool>("xs[1][1][0] == 1").unwrap());
+    }
+
+    #[test]
+    fn test_restores_dates_and_builtins() {
+        let mut engine = restored("let when = d\"2024-06-01\"");
+        assert_eq!(engine.eval_as::<f64>("when.year()").unwrap(), 2024.0);
+        assert_eq!(engine.eval_as::<f64>("Math.max(1, 2)").unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_keeps_frozen_values_frozen() {
+        let mut engine = restored(
+            "class P {}\n\
+             let xs = Type.freeze([1, [2]])\n\
+             let d = Type.freeze({\"a\": 1})\n\
+             let p = Type.freeze(P())",
+        );
+        for name in ["xs", "d", "p"] {
+            assert!(engine
+                .eval_as::<bool>(&format!("Type.isFrozen({name})"))
+                .unwrap());
+        }
+        assert!(!engine.eval_as::<bool>("Type.isFrozen(xs[1])").unwrap());
+        assert!(engine.eval("xs.push(3)").is_err());
+        assert!(engine.eval("p.x = 1").is_err());
+    }
+
+    #[test]
+    fn test_keeps_constants_constant() {
+        let mut engine = restored("const LIMIT = 1\nlet step = 2");
+        let err = engine.eval("LIMIT = 2").unwrap_err();
+        assert_eq!(err.message(), "Cannot assign to constant 'LIMIT'");
+        engine.eval("step = 3").unwrap();
+
+        let loader = crate::vm::MemoryLoader::new().with_module("ca.sald", "const MAX = 1");
+        let mut engine = Engine::new();
+        engine.set_module_loader(loader);
+        engine.eval("import \"ca.sald\" as ca").unwrap();
+        let globals = super::restore_globals(&engine.snapshot().unwrap()).unwrap();
+        let Some(Value::Namespace {
+            module_globals: Some(module_globals),
+            ..
+        }) = globals.get("ca")
+        else {
+            panic!("expected a module namespace");
+        };
+        assert!(module_globals.borrow().is_const("MAX"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_data() {
+        let err = Engine::from_snapshot(b"not a snapshot").err().unwrap();
+        assert_eq!(err.to_string(), "SnapshotError: Invalid snapshot");
+        assert!(Engine::from_snapshot(b"").is_err());
+    }
+
+    #[test]
+    fn test_rejects_truncated_data() {
+        let mut engine = Engine::new();
+        engine.eval("let xs = [1, \"two\", {\"three\": 3}]").unwrap();
+        let data = engine.snapshot().unwrap();
+        assert!(Engine::from_snapshot(&data[..data.len() - 4]).is_err());
+    }
+
+    #[test]
+    fn test_refuses_values_that_cannot_be_restored() {
+        let err = snapshot_err("class K { fun __hash__(self) { return 1 } }\nlet d = {}\nd[K()] = 1");
+        assert!(err.contains("Dicts with instance keys cannot be snapshotted"), "{err}");
+        let err = snapshot_err("fun add(a, b) { return a + b }\nlet inc = add.partial(1)");
+        assert!(err.contains("values cannot be snapshotted"), "{err}");
+    }
+
+    #[test]
+    fn test_snapshot_restores_prelude() {
+        let mut prelude = Engine::new();
+        prelude
+            .eval(
+                "class Animal { fun init(self, name) { self.name = name } fun speak(self) { return self.name + \"!\" } }\n\
+                 class Dog extends Animal { fun speak(self) { return super.speak() + \"!\" } }\n\
+                 fun counter() { let n = 0\n return || { n = n + 1\n return n } }\n\
+                 let next = counter()\n\
+                 let pet = Dog(\"Rex\")\n\
+                 let shared = [1, 2]\n\
+                 let config = {\"a\": shared, \"b\": shared, \"when\": d\"2024-06-01\"}",
+            )
+            .unwrap();
+        prelude.eval("next()").unwrap();
+        let data = prelude.snapshot().unwrap();
+
+        let mut engine = Engine::from_snapshot(&data).unwrap();
+        let result: String = engine
+            .eval_as(
+                "config[\"a\"].push(3)\n\
+                 $\"{pet.speak()}|{next()}|{config[\"b\"].length()}|{config[\"when\"].year()}|{Math.max(1, 2)}\"",
+            )
+            .unwrap();
+        assert_eq!(result, "Rex!!|2|3|2024|2");
+        assert!(Engine::from_snapshot(b"not a snapshot").is_err());
+    }
+}
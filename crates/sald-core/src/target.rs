@@ -0,0 +1,62 @@
+//! The build scripts are compiled for
+//! `@cfg(...)` declarations are kept or dropped by comparing their predicates
+//! with these values, and `System.platform` reports the same target.
+
+/// `"wasm"` in the browser build, `"native"` everywhere else
+pub const PLATFORM: &str = if cfg!(target_arch = "wasm32") {
+    "wasm"
+} else {
+    "native"
+};
+
+/// Keys an `@cfg` predicate can test
+pub const CFG_KEYS: &[&str] = &["target", "os", "arch", "family"];
+
+/// Value of an `@cfg` key for this build.
+pub fn cfg_value(key: &str) -> Option<&'static str> {
+    match key {
+        "target" => Some(PLATFORM),
+        "os" => Some(std::env::consts::OS),
+        "arch" => Some(std::env::consts::ARCH),
+        "family" => Some(std::env::consts::FAMILY),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_cfg_declarations() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                r#"
+@cfg(target: "wasm")
+fun storage() { return "memory" }
+@cfg(target: "native")
+fun storage() { return "disk" }
+
+namespace Paths {
+    @cfg(family: "no-such-family")
+    import "./does-not-exist"
+    @cfg(target: "native", os: "no-such-os")
+    const sep = "?"
+    @cfg(target: "native")
+    const sep = "/"
+}
+"#,
+            )
+            .unwrap();
+        assert_eq!(
+            engine
+                .eval_as::<Vec<String>>("[storage(), Paths.sep, System.platform]")
+                .unwrap(),
+            ["disk", "/", "native"]
+        );
+
+        let err = engine.eval("@cfg(cpu: \"x86\")\nfun f() {}").unwrap_err();
+        assert!(err.to_string().contains("Unknown '@cfg' key 'cpu'"));
+    }
+}
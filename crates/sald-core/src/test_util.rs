@@ -0,0 +1,19 @@
+//! Helpers for unit tests that run Sald source
+
+use crate::engine::Engine;
+
+/// Runs `source` in a fresh engine and returns its last expression as text
+pub(crate) fn eval(source: &str) -> String {
+    match Engine::new().eval(source) {
+        Ok(value) => value.to_string(),
+        Err(e) => panic!("script failed: {}", e),
+    }
+}
+
+/// Runs `source` in a fresh engine and returns the error it fails with
+pub(crate) fn eval_err(source: &str) -> String {
+    match Engine::new().eval(source) {
+        Ok(value) => panic!("script returned {} instead of failing", value),
+        Err(e) => e.to_string(),
+    }
+}
@@ -0,0 +1,795 @@
+//! Test runner behind `sald --test`
+//! Tests come from `@Test` functions and from `Test.describe`/`Test.it`
+//! calls made while the file runs. Suites carry `beforeEach`/`afterEach`
+//! hooks that wrap every test nested in them, outermost first. Results can
+//! be rendered as JSON or JUnit XML for CI.
+//! `Test.matchesSnapshot` compares values against snapshots stored in a
+//! `__snapshots__` directory next to the test file. Stubs and the fake clock
+//! a test installs are undone when it finishes.
+
+use crate::builtins::clock;
+use crate::vm::caller::ValueCaller;
+use crate::vm::Value;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How a test is run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestMode {
+    Run,
+    Skip,
+    /// Passes only if the body fails
+    Failing,
+}
+
+impl TestMode {
+    /// Mode named by `@Test("skip")` or `@Test("failing")`.
+    pub fn from_marker(marker: &str) -> Option<Self> {
+        match marker {
+            "skip" => Some(Self::Skip),
+            "failing" => Some(Self::Failing),
+            _ => None,
+        }
+    }
+}
+
+struct Suite {
+    name: String,
+    parent: Option<usize>,
+    before_each: Vec<Value>,
+    after_each: Vec<Value>,
+}
+
+/// A test collected from the script
+#[derive(Clone)]
+struct TestCase {
+    /// Suite names and the test name, joined with " > "
+    name: String,
+    mode: TestMode,
+    func: Value,
+    suite: usize,
+}
+
+struct Registry {
+    /// Index 0 is the file itself, holding top-level hooks and `@Test`s
+    suites: Vec<Suite>,
+    tests: Vec<TestCase>,
+    current: usize,
+}
+
+impl Registry {
+    fn new() -> Self {
+        Self {
+            suites: vec![Suite {
+                name: String::new(),
+                parent: None,
+                before_each: Vec::new(),
+                after_each: Vec::new(),
+            }],
+            tests: Vec::new(),
+            current: 0,
+        }
+    }
+
+    fn qualified(&self, suite: usize, name: &str) -> String {
+        let mut parts = vec![name.to_string()];
+        let mut current = Some(suite);
+        while let Some(index) = current {
+            let suite = &self.suites[index];
+            if !suite.name.is_empty() {
+                parts.push(suite.name.clone());
+            }
+            current = suite.parent;
+        }
+        parts.reverse();
+        parts.join(" > ")
+    }
+
+    /// Suites from the root down to `suite`
+    fn chain(&self, suite: usize) -> Vec<usize> {
+        let mut chain = Vec::new();
+        let mut current = Some(suite);
+        while let Some(index) = current {
+            chain.push(index);
+            current = self.suites[index].parent;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::new());
+}
+
+/// Forgets every suite, hook and test registered so far.
+pub fn reset() {
+    REGISTRY.with(|r| *r.borrow_mut() = Registry::new());
+    restore_stubs();
+}
+
+/// A member replaced by `Test.stub` or `Test.spy`
+struct Stub {
+    target: Value,
+    name: String,
+    /// Field the instance had before, if any
+    original: Option<Value>,
+}
+
+thread_local! {
+    static STUBS: RefCell<Vec<Stub>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Replaces the method `name` of an instance, or the member `name` of a
+/// namespace, until `restore_stubs`. Returns the callable it replaced.
+pub(crate) fn stub(target: &Value, name: &str, replacement: Value) -> Result<Value, String> {
+    let (current, original) = match target {
+        Value::Instance(inst) => {
+            let mut inst = inst.borrow_mut();
+            let original = inst.fields.get(name).cloned();
+            let current = match &original {
+                Some(field) => field.clone(),
+                None => match inst.class.methods.get(name) {
+                    Some(Value::Function(method)) => Value::BoundMethod {
+                        receiver: Box::new(target.clone()),
+                        method: method.clone(),
+                    },
+                    _ => match inst.class.native_instance_methods.get(name) {
+                        Some(method) => Value::InstanceMethod {
+                            receiver: Box::new(target.clone()),
+                            method: *method,
+                            method_name: name.to_string(),
+                        },
+                        None => {
+                            return Err(format!(
+                                "'{}' instance has no method '{}'",
+                                inst.class_name, name
+                            ))
+                        }
+                    },
+                },
+            };
+            inst.fields.insert(name.to_string(), replacement);
+            (current, original)
+        }
+        Value::Namespace {
+            name: ns_name,
+            members,
+            ..
+        } => {
+            let mut members = members.borrow_mut();
+            let current = members
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("Namespace '{}' has no member '{}'", ns_name, name))?;
+            members.insert(name.to_string(), replacement);
+            (current.clone(), Some(current))
+        }
+        other => {
+            return Err(format!(
+                "Only instances and namespaces can be stubbed, got {}",
+                other.type_name()
+            ))
+        }
+    };
+    STUBS.with(|stubs| {
+        stubs.borrow_mut().push(Stub {
+            target: target.clone(),
+            name: name.to_string(),
+            original,
+        })
+    });
+    Ok(current)
+}
+
+/// Puts back every stubbed member, most recent first.
+pub fn restore_stubs() {
+    let stubs = STUBS.with(|stubs| std::mem::take(&mut *stubs.borrow_mut()));
+    for stub in stubs.into_iter().rev() {
+        match (&stub.target, stub.original) {
+            (Value::Instance(inst), Some(original)) => {
+                inst.borrow_mut().fields.insert(stub.name, original);
+            }
+            (Value::Instance(inst), None) => {
+                inst.borrow_mut().fields.remove(&stub.name);
+            }
+            (Value::Namespace { members, .. }, Some(original)) => {
+                members.borrow_mut().insert(stub.name, original);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Runs `body` with a new suite as the current one, so the tests and hooks
+/// it registers belong to that suite.
+pub(crate) fn describe(
+    name: &str,
+    body: &Value,
+    caller: &mut dyn ValueCaller,
+) -> Result<(), String> {
+    let (suite, parent) = REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        let parent = r.current;
+        r.suites.push(Suite {
+            name: name.to_string(),
+            parent: Some(parent),
+            before_each: Vec::new(),
+            after_each: Vec::new(),
+        });
+        (r.suites.len() - 1, parent)
+    });
+    REGISTRY.with(|r| r.borrow_mut().current = suite);
+    let result = caller.call(body, Vec::new());
+    REGISTRY.with(|r| r.borrow_mut().current = parent);
+    result.map(|_| ())
+}
+
+pub(crate) fn add_test(name: &str, func: Value, mode: TestMode) {
+    REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        let suite = r.current;
+        let name = r.qualified(suite, name);
+        r.tests.push(TestCase {
+            name,
+            mode,
+            func,
+            suite,
+        });
+    });
+}
+
+pub(crate) fn add_before_each(hook: Value) {
+    REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        let suite = r.current;
+        r.suites[suite].before_each.push(hook);
+    });
+}
+
+pub(crate) fn add_after_each(hook: Value) {
+    REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        let suite = r.current;
+        r.suites[suite].after_each.push(hook);
+    });
+}
+
+/// Outcome of one test
+#[derive(Debug, Clone, PartialEq)]
+pub enum TestStatus {
+    Passed,
+    Failed(String),
+    Skipped,
+    /// A `failing` test whose body failed as expected
+    ExpectedFailure(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub name: String,
+    pub status: TestStatus,
+    pub duration: Duration,
+}
+
+impl TestResult {
+    pub fn is_failure(&self) -> bool {
+        matches!(self.status, TestStatus::Failed(_))
+    }
+}
+
+/// Registers `@Test` functions, which `sald --test` finds in the source,
+/// as top-level tests ahead of the ones registered with `Test.it`.
+pub fn add_decorated(tests: Vec<(String, Value, TestMode)>) {
+    REGISTRY.with(|r| {
+        let mut r = r.borrow_mut();
+        let mut decorated: Vec<TestCase> = tests
+            .into_iter()
+            .map(|(name, func, mode)| TestCase {
+                name,
+                mode,
+                func,
+                suite: 0,
+            })
+            .collect();
+        decorated.append(&mut r.tests);
+        r.tests = decorated;
+    });
+}
+
+/// Names and modes of the registered tests whose name contains `filter`.
+pub fn collected(filter: Option<&str>) -> Vec<(String, TestMode)> {
+    REGISTRY.with(|r| {
+        r.borrow()
+            .tests
+            .iter()
+            .filter(|t| filter.is_none_or(|f| t.name.contains(f)))
+            .map(|t| (t.name.clone(), t.mode))
+            .collect()
+    })
+}
+
+/// Runs the registered tests whose name contains `filter`, calling
+/// `on_result` as each one finishes.
+pub fn run(
+    caller: &mut dyn ValueCaller,
+    filter: Option<&str>,
+    mut on_result: impl FnMut(&TestResult),
+) -> Vec<TestResult> {
+    let plan: Vec<(TestCase, Vec<Value>, Vec<Value>)> = REGISTRY.with(|r| {
+        let r = r.borrow();
+        r.tests
+            .iter()
+            .filter(|t| filter.is_none_or(|f| t.name.contains(f)))
+            .map(|t| {
+                let chain = r.chain(t.suite);
+                let before = chain
+                    .iter()
+                    .flat_map(|&s| r.suites[s].before_each.iter().cloned())
+                    .collect();
+                let after = chain
+                    .iter()
+                    .rev()
+                    .flat_map(|&s| r.suites[s].after_each.iter().cloned())
+                    .collect();
+                (t.clone(), before, after)
+            })
+            .collect()
+    });
+
+    let mut results = Vec::new();
+    for (test, before, after) in plan {
+        let start = Instant::now();
+        let status = match test.mode {
+            TestMode::Skip => TestStatus::Skipped,
+            TestMode::Run | TestMode::Failing => {
+                SNAPSHOTS.with(|s| {
+                    let mut s = s.borrow_mut();
+                    s.test = Some(test.name.clone());
+                    s.counter = 0;
+                });
+                let outcome = run_one(caller, &test.func, &before, &after);
+                SNAPSHOTS.with(|s| s.borrow_mut().test = None);
+                restore_stubs();
+                clock::use_real();
+                match (test.mode, outcome) {
+                    (TestMode::Failing, Err(e)) => TestStatus::ExpectedFailure(e),
+                    (TestMode::Failing, Ok(())) => {
+                        TestStatus::Failed("Expected the test to fail, but it passed".to_string())
+                    }
+                    (_, Err(e)) => TestStatus::Failed(e),
+                    (_, Ok(())) => TestStatus::Passed,
+                }
+            }
+        };
+        let result = TestResult {
+            name: test.name,
+            status,
+            duration: start.elapsed(),
+        };
+        on_result(&result);
+        results.push(result);
+    }
+    results
+}
+
+/// Runs the hooks and the body. After-hooks run even when the body fails;
+/// the first error wins.
+fn run_one(
+    caller: &mut dyn ValueCaller,
+    func: &Value,
+    before: &[Value],
+    after: &[Value],
+) -> Result<(), String> {
+    let mut outcome = before
+        .iter()
+        .try_for_each(|hook| call_awaited(caller, hook))
+        .and_then(|_| call_awaited(caller, func));
+    for hook in after {
+        let result = call_awaited(caller, hook);
+        if outcome.is_ok() {
+            outcome = result;
+        }
+    }
+    outcome
+}
+
+/// Calls `func` and, if it is async, waits for the Future it returns.
+fn call_awaited(caller: &mut dyn ValueCaller, func: &Value) -> Result<(), String> {
+    match caller.call(func, Vec::new())? {
+        Value::Future(future) => match future.borrow_mut().take() {
+            Some(receiver) => receiver
+                .recv()
+                .map_err(|_| "Async task failed: channel closed".to_string())?
+                .map(|_| ()),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+struct Snapshots {
+    file: Option<PathBuf>,
+    update: bool,
+    /// Name of the running test
+    test: Option<String>,
+    /// Unnamed snapshots taken by the running test so far
+    counter: usize,
+    summary: SnapshotSummary,
+}
+
+/// Snapshots written during a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SnapshotSummary {
+    pub added: usize,
+    pub updated: usize,
+}
+
+thread_local! {
+    static SNAPSHOTS: RefCell<Snapshots> = RefCell::new(Snapshots {
+        file: None,
+        update: false,
+        test: None,
+        counter: 0,
+        summary: SnapshotSummary::default(),
+    });
+}
+
+/// Where the snapshots of `test_file` are stored:
+/// `__snapshots__/<file name>.snap` next to it.
+pub fn snapshot_file(test_file: &Path) -> PathBuf {
+    let name = test_file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    test_file
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join("__snapshots__")
+        .join(format!("{}.snap", name))
+}
+
+/// Stores snapshots for the tests of `test_file`. With `update`, snapshots
+/// that no longer match are overwritten instead of failing the test.
+pub fn configure_snapshots(test_file: &Path, update: bool) {
+    SNAPSHOTS.with(|s| {
+        let mut s = s.borrow_mut();
+        s.file = Some(snapshot_file(test_file));
+        s.update = update;
+        s.summary = SnapshotSummary::default();
+    });
+}
+
+pub fn snapshot_summary() -> SnapshotSummary {
+    SNAPSHOTS.with(|s| s.borrow().summary)
+}
+
+/// Compares `value` with the running test's next snapshot, or the one called
+/// `name`. Missing snapshots are recorded.
+pub(crate) fn match_snapshot(value: &Value, name: Option<&str>) -> Result<(), String> {
+    let (file, key, update) = SNAPSHOTS.with(|s| {
+        let mut s = s.borrow_mut();
+        let (Some(file), Some(test)) = (s.file.clone(), s.test.clone()) else {
+            return Err(
+                "Test.matchesSnapshot can only be used in tests run by 'sald --test'".to_string(),
+            );
+        };
+        let key = match name {
+            Some(name) => format!("{} > {}", test, name),
+            None => {
+                s.counter += 1;
+                format!("{} {}", test, s.counter)
+            }
+        };
+        Ok((file, key, s.update))
+    })?;
+
+    let mut snapshots: BTreeMap<String, String> = match std::fs::read_to_string(&file) {
+        Ok(text) => serde_json::from_str(&text)
+            .map_err(|e| format!("Invalid snapshot file '{}': {}", file.display(), e))?,
+        Err(_) => BTreeMap::new(),
+    };
+    let actual = snapshot_repr(value);
+    let existed = match snapshots.get(&key) {
+        Some(expected) if *expected == actual => return Ok(()),
+        Some(expected) if !update => {
+            return Err(format!(
+                "AssertionError: Snapshot '{}' does not match\n{}\nRun with --update-snapshots to accept the new value",
+                key,
+                line_diff(expected, &actual)
+            ))
+        }
+        Some(_) => true,
+        None => false,
+    };
+
+    snapshots.insert(key, actual);
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    }
+    let text = serde_json::to_string_pretty(&snapshots).unwrap_or_default();
+    std::fs::write(&file, text + "\n")
+        .map_err(|e| format!("Failed to write '{}': {}", file.display(), e))?;
+    SNAPSHOTS.with(|s| {
+        let summary = &mut s.borrow_mut().summary;
+        if existed {
+            summary.updated += 1;
+        } else {
+            summary.added += 1;
+        }
+    });
+    Ok(())
+}
+
+/// Deterministic text for a snapshot. Strings are stored as they are so
+/// multi-line output stays readable; other values are printed one item per
+/// line with dictionary keys and instance fields sorted.
+pub fn snapshot_repr(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        other => {
+            let mut out = String::new();
+            write_repr(other, 0, &mut Vec::new(), &mut out);
+            out
+        }
+    }
+}
+
+fn write_repr(value: &Value, indent: usize, seen: &mut Vec<usize>, out: &mut String) {
+    let entries: Vec<(String, Value)> = match value {
+        Value::String(s) => {
+            out.push_str(&format!("{:?}", s));
+            return;
+        }
+        Value::Array(arr) => arr
+            .borrow()
+            .iter()
+            .map(|v| (String::new(), v.clone()))
+            .collect(),
+        Value::Dictionary(dict) => {
+            let mut entries: Vec<(String, Value)> = dict
+                .borrow()
+                .iter()
+                .map(|(k, v)| (format!("{:?}: ", k), v.clone()))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            entries
+        }
+        Value::Instance(inst) => {
+            let inst = inst.borrow();
+            if let Some(text) = crate::builtins::date::format_date_instance(&inst)
+                .or_else(|| crate::builtins::collections::format_collection(&inst))
+            {
+                out.push_str(&text);
+                return;
+            }
+            let mut entries: Vec<(String, Value)> = inst
+                .fields
+                .iter()
+                .map(|(k, v)| (format!("{}: ", k), v.clone()))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            entries
+        }
+        other => {
+            out.push_str(&other.to_string());
+            return;
+        }
+    };
+
+    let (open, close) = match value {
+        Value::Array(_) => ("[".to_string(), "]"),
+        Value::Instance(inst) => (format!("{} {{", inst.borrow().class_name), "}"),
+        _ => ("{".to_string(), "}"),
+    };
+    let id = match value {
+        Value::Array(rc) => rc.as_ptr() as usize,
+        Value::Dictionary(rc) => rc.as_ptr() as usize,
+        Value::Instance(rc) => rc.as_ptr() as usize,
+        _ => 0,
+    };
+    if seen.contains(&id) {
+        out.push_str("[Circular]");
+        return;
+    }
+    if entries.is_empty() {
+        out.push_str(&open);
+        out.push_str(close);
+        return;
+    }
+    seen.push(id);
+    out.push_str(&open);
+    out.push('\n');
+    for (prefix, item) in &entries {
+        out.push_str(&"  ".repeat(indent + 1));
+        out.push_str(prefix);
+        write_repr(item, indent + 1, seen, out);
+        out.push_str(",\n");
+    }
+    out.push_str(&"  ".repeat(indent));
+    out.push_str(close);
+    seen.pop();
+}
+
+/// Line-by-line comparison: `-` lines are the snapshot, `+` lines the value.
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => out.push(format!("  {}", e)),
+            (e, a) => {
+                if let Some(e) = e {
+                    out.push(format!("- {}", e));
+                }
+                if let Some(a) = a {
+                    out.push(format!("+ {}", a));
+                }
+            }
+        }
+    }
+    out.join("\n")
+}
+
+/// Results as a JSON document with a summary and one entry per test.
+pub fn to_json(results: &[TestResult]) -> String {
+    let count = |f: fn(&TestStatus) -> bool| results.iter().filter(|r| f(&r.status)).count();
+    let tests: Vec<serde_json::Value> = results
+        .iter()
+        .map(|r| {
+            let (status, message) = match &r.status {
+                TestStatus::Passed => ("passed", None),
+                TestStatus::Failed(m) => ("failed", Some(m)),
+                TestStatus::Skipped => ("skipped", None),
+                TestStatus::ExpectedFailure(m) => ("expected_failure", Some(m)),
+            };
+            serde_json::json!({
+                "name": r.name,
+                "status": status,
+                "duration_ms": r.duration.as_secs_f64() * 1000.0,
+                "message": message,
+            })
+        })
+        .collect();
+    let report = serde_json::json!({
+        "passed": count(|s| matches!(s, TestStatus::Passed | TestStatus::ExpectedFailure(_))),
+        "failed": count(|s| matches!(s, TestStatus::Failed(_))),
+        "skipped": count(|s| matches!(s, TestStatus::Skipped)),
+        "tests": tests,
+    });
+    serde_json::to_string_pretty(&report).unwrap_or_default()
+}
+
+/// Results as a JUnit XML report; `suite` names the single `<testsuite>`.
+pub fn to_junit(suite: &str, results: &[TestResult]) -> String {
+    let failures = results.iter().filter(|r| r.is_failure()).count();
+    let skipped = results
+        .iter()
+        .filter(|r| r.status == TestStatus::Skipped)
+        .count();
+    let total: f64 = results.iter().map(|r| r.duration.as_secs_f64()).sum();
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        xml_escape(suite),
+        results.len(),
+        failures,
+        skipped,
+        total
+    ));
+    for r in results {
+        out.push_str(&format!(
+            "  <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            xml_escape(&r.name),
+            xml_escape(suite),
+            r.duration.as_secs_f64()
+        ));
+        match &r.status {
+            TestStatus::Passed | TestStatus::ExpectedFailure(_) => out.push_str("/>\n"),
+            TestStatus::Skipped => out.push_str(">\n    <skipped/>\n  </testcase>\n"),
+            TestStatus::Failed(message) => out.push_str(&format!(
+                ">\n    <failure message=\"{}\">{}</failure>\n  </testcase>\n",
+                xml_escape(message.lines().next().unwrap_or_default()),
+                xml_escape(message)
+            )),
+        }
+    }
+    out.push_str("</testsuite>\n");
+    out
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_suites_hooks_and_markers() {
+        use crate::testing::{self, TestStatus};
+
+        testing::reset();
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                r#"
+let log = []
+Test.describe("outer", || {
+    Test.beforeEach(|| { log.push("before") })
+    Test.afterEach(|| { log.push("after") })
+    Test.it("passes", || { log.push("body") })
+    Test.describe("inner", || {
+        Test.it("fails", || { Test.assert_eq(1, 2) })
+    })
+    Test.skip("skipped", || { log.push("never") })
+    Test.failing("known bug", || { Test.fail("still broken") })
+})
+"#,
+            )
+            .unwrap();
+
+        let results = testing::run(engine.vm(), None, |_| {});
+        let statuses: Vec<(&str, &TestStatus)> = results
+            .iter()
+            .map(|r| (r.name.as_str(), &r.status))
+            .collect();
+        assert!(matches!(
+            statuses.as_slice(),
+            [
+                ("outer > passes", TestStatus::Passed),
+                ("outer > inner > fails", TestStatus::Failed(_)),
+                ("outer > skipped", TestStatus::Skipped),
+                ("outer > known bug", TestStatus::ExpectedFailure(_)),
+            ]
+        ));
+        assert_eq!(
+            engine.eval_as::<Vec<String>>("log").unwrap(),
+            ["before", "body", "after", "before", "after", "before", "after"]
+        );
+        assert!(testing::to_junit("suite", &results).contains("failures=\"1\" skipped=\"1\""));
+        assert_eq!(testing::collected(Some("inner")).len(), 1);
+    }
+
+    #[test]
+    fn test_snapshots() {
+        use crate::testing::{self, TestStatus};
+
+        let dir = std::env::temp_dir().join(format!("sald-snapshots-{}", std::process::id()));
+        let file = dir.join("format.sald");
+        let _ = std::fs::remove_dir_all(&dir);
+        let run = |value: &str, update: bool| {
+            testing::reset();
+            testing::configure_snapshots(&file, update);
+            let mut engine = Engine::new();
+            engine
+                .eval(&format!(
+                    "Test.it(\"formats\", || {{ Test.matchesSnapshot({}) }})",
+                    value
+                ))
+                .unwrap();
+            testing::run(engine.vm(), None, |_| {}).remove(0).status
+        };
+
+        assert_eq!(run(r#"{"b": [1, 2], "a": "x"}"#, false), TestStatus::Passed);
+        let stored = std::fs::read_to_string(testing::snapshot_file(&file)).unwrap();
+        assert!(stored.contains(r#""formats 1": "{\n  \"a\": \"x\",\n  \"b\": ["#));
+        assert_eq!(run(r#"{"a": "x", "b": [1, 2]}"#, false), TestStatus::Passed);
+        assert!(matches!(
+            run(r#"{"a": "y", "b": [1, 2]}"#, false),
+            TestStatus::Failed(e) if e.contains("-   \"a\": \"x\",")
+        ));
+        assert_eq!(run(r#"{"a": "y", "b": [1, 2]}"#, true), TestStatus::Passed);
+        assert_eq!(testing::snapshot_summary().updated, 1);
+        assert_eq!(run(r#"{"a": "y", "b": [1, 2]}"#, false), TestStatus::Passed);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -1,14 +1,32 @@
+use crate::vm::value::{Function, Globals};
 use crate::vm::Value;
 use rustc_hash::FxHashMap;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+/// Members of an imported module, shared with its `import ... as` namespaces
+pub type ModuleMembers = Rc<RefCell<Globals>>;
+
 pub trait ValueCaller {
     fn call(&mut self, callee: &Value, args: Vec<Value>) -> Result<Value, String>;
 
     fn get_globals(&self) -> FxHashMap<String, Value>;
 
-    fn get_shared_globals(&self) -> Rc<RefCell<FxHashMap<String, Value>>>;
+    fn get_shared_globals(&self) -> Rc<RefCell<Globals>>;
+
+    /// Runs a compiled script with only `globals` as its global variables
+    /// and returns the value it ends with.
+    fn run_script(
+        &mut self,
+        script: Rc<Function>,
+        source: String,
+        globals: FxHashMap<String, Value>,
+    ) -> Result<Value, String>;
+
+    /// Path and members of every module imported so far, by path
+    fn loaded_modules(&self) -> Vec<(String, ModuleMembers)> {
+        Vec::new()
+    }
 }
 
 pub type CallableNativeStaticFn = fn(&[Value], &mut dyn ValueCaller) -> Result<Value, String>;
@@ -0,0 +1,77 @@
+//! Support for the `debugger` statement
+//! The statement does nothing unless the host installs a hook on the VM; the
+//! hook is then called with a snapshot of the paused program
+
+use crate::error::StackFrame;
+use crate::vm::Value;
+use rustc_hash::FxHashMap;
+use std::rc::Rc;
+
+/// Called each time a `debugger` statement is reached.
+pub type DebuggerHook = Rc<dyn Fn(&DebugContext)>;
+
+/// State of the program at a `debugger` statement.
+#[derive(Debug, Clone)]
+pub struct DebugContext {
+    /// Innermost frame first, as in error stack traces
+    pub frames: Vec<StackFrame>,
+    /// Locals of the current frame in declaration order
+    pub locals: Vec<(String, Value)>,
+    pub globals: FxHashMap<String, Value>,
+}
+
+impl DebugContext {
+    pub fn current_frame(&self) -> Option<&StackFrame> {
+        self.frames.first()
+    }
+
+    /// Looks a name up the way the script would: locals shadow globals.
+    pub fn lookup(&self, name: &str) -> Option<&Value> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| local == name)
+            .map(|(_, value)| value)
+            .or_else(|| self.globals.get(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_debugger_statement_calls_hook() {
+        let mut engine = Engine::new();
+        engine
+            .eval("fun area(w, h) { let a = w * h\n debugger\n return a }\n area(2, 3)")
+            .unwrap();
+
+        let paused = Rc::new(RefCell::new(Vec::new()));
+        let seen = paused.clone();
+        engine
+            .vm()
+            .set_debugger_hook(Some(Rc::new(move |ctx: &crate::vm::DebugContext| {
+                let frame = ctx.current_frame().unwrap().function_name.clone();
+                let locals: Vec<String> = ctx
+                    .locals
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                let w = ctx.lookup("w").map(|v| v.to_string());
+                seen.borrow_mut().push((frame, locals.join(" "), w));
+            })));
+        let area: f64 = engine.eval_as("area(4, 5)").unwrap();
+        assert_eq!(area, 20.0);
+        assert_eq!(
+            *paused.borrow(),
+            vec![(
+                "area".to_string(),
+                "w=4 h=5 a=20".to_string(),
+                Some("4".to_string())
+            )]
+        );
+    }
+}
@@ -53,8 +53,8 @@ enum GcPhase {
 
 #[derive(Clone)]
 pub enum TrackedObject {
-    Array(Weak<RefCell<Vec<super::Value>>>),
-    Dictionary(Weak<RefCell<FxHashMap<String, super::Value>>>),
+    Array(Weak<RefCell<super::Array>>),
+    Dictionary(Weak<RefCell<super::Dict>>),
     Instance(Weak<RefCell<super::Instance>>),
 }
 
@@ -75,14 +75,14 @@ impl TrackedObject {
         }
     }
 
-    pub fn upgrade_array(&self) -> Option<Rc<RefCell<Vec<super::Value>>>> {
+    pub fn upgrade_array(&self) -> Option<Rc<RefCell<super::Array>>> {
         match self {
             TrackedObject::Array(w) => w.upgrade(),
             _ => None,
         }
     }
 
-    pub fn upgrade_dict(&self) -> Option<Rc<RefCell<FxHashMap<String, super::Value>>>> {
+    pub fn upgrade_dict(&self) -> Option<Rc<RefCell<super::Dict>>> {
         match self {
             TrackedObject::Dictionary(w) => w.upgrade(),
             _ => None,
@@ -147,7 +147,7 @@ impl GcHeap {
         }
     }
 
-    pub fn track_array(&mut self, arr: &Rc<RefCell<Vec<super::Value>>>) -> ObjectId {
+    pub fn track_array(&mut self, arr: &Rc<RefCell<super::Array>>) -> ObjectId {
         let id = self.next_id;
         self.next_id += 1;
         self.tracked
@@ -157,7 +157,7 @@ impl GcHeap {
         id
     }
 
-    pub fn track_dict(&mut self, dict: &Rc<RefCell<FxHashMap<String, super::Value>>>) -> ObjectId {
+    pub fn track_dict(&mut self, dict: &Rc<RefCell<super::Dict>>) -> ObjectId {
         let id = self.next_id;
         self.next_id += 1;
         self.tracked
@@ -374,7 +374,7 @@ mod tests {
     #[test]
     fn test_gc_track_array() {
         let mut gc = GcHeap::new();
-        let arr = Rc::new(RefCell::new(Vec::new()));
+        let arr = Rc::new(RefCell::new(crate::vm::Array::new()));
         let id = gc.track_array(&arr);
         assert_eq!(id, 0);
         assert_eq!(gc.stats.total_tracked, 1);
@@ -385,7 +385,7 @@ mod tests {
         let mut gc = GcHeap::new();
 
         {
-            let arr = Rc::new(RefCell::new(Vec::new()));
+            let arr = Rc::new(RefCell::new(crate::vm::Array::new()));
             gc.track_array(&arr);
         }
 
@@ -0,0 +1,129 @@
+//! Where imported modules come from
+//! By default a VM reads imports from the local filesystem. Hosts can install
+//! a `ModuleLoader` to serve them from memory, archives, databases or the
+//! network instead; the browser build uses one to import from its virtual
+//! filesystem.
+
+use crate::compiler::chunk::Chunk;
+use rustc_hash::FxHashMap;
+
+/// The contents of a module returned by a loader
+#[derive(Clone)]
+pub enum ModuleSource {
+    /// Sald source, compiled when the module is first imported
+    Source(String),
+    /// A chunk compiled ahead of time
+    Chunk(Chunk),
+}
+
+pub trait ModuleLoader {
+    /// Turns an import specifier into the id of the module it names.
+    /// `importer` is the id of the importing module, or the running script's
+    /// file name. Modules are evaluated once per id, so equal modules must
+    /// resolve to equal ids.
+    fn resolve(&self, specifier: &str, importer: &str) -> Result<String, String>;
+
+    /// Returns the module with the given id.
+    fn load(&self, id: &str) -> Result<ModuleSource, String>;
+}
+
+/// Serves modules registered up front under `/`-separated paths.
+///
+/// `import "./util"` from `lib/main.sald` resolves to `lib/util.sald`;
+/// other specifiers are looked up from the root.
+#[derive(Clone, Default)]
+pub struct MemoryLoader {
+    modules: FxHashMap<String, ModuleSource>,
+}
+
+impl MemoryLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_module(mut self, path: &str, source: impl Into<String>) -> Self {
+        self.insert(path, source);
+        self
+    }
+
+    pub fn insert(&mut self, path: &str, source: impl Into<String>) {
+        self.modules
+            .insert(normalize(path), ModuleSource::Source(source.into()));
+    }
+
+    pub fn insert_chunk(&mut self, path: &str, chunk: Chunk) {
+        self.modules
+            .insert(normalize(path), ModuleSource::Chunk(chunk));
+    }
+}
+
+impl ModuleLoader for MemoryLoader {
+    fn resolve(&self, specifier: &str, importer: &str) -> Result<String, String> {
+        let path = if specifier.starts_with("./") || specifier.starts_with("../") {
+            let dir = importer.rfind('/').map_or("", |i| &importer[..i]);
+            normalize(&format!("{}/{}", dir, specifier))
+        } else {
+            normalize(specifier)
+        };
+        [path.clone(), format!("{}.sald", path)]
+            .into_iter()
+            .find(|id| self.modules.contains_key(id))
+            .ok_or_else(|| format!("Module '{}' not found", specifier))
+    }
+
+    fn load(&self, id: &str) -> Result<ModuleSource, String> {
+        self.modules
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("Module '{}' not found", id))
+    }
+}
+
+/// Folds `.` and `..` segments out of a `/`-separated path.
+fn normalize(path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_module_loader_serves_imports() {
+        use crate::vm::MemoryLoader;
+
+        let loader = MemoryLoader::new()
+            .with_module(
+                "lib/util.sald",
+                "let seen = []\nfun twice(n) { return n * 2 }",
+            )
+            .with_module(
+                "lib/math.sald",
+                "import \"./util\" as util\n\
+                 fun quad(n) { return util.twice(util.twice(n)) }\n\
+                 fun seen() { return util.seen.length() }",
+            );
+        let mut engine = Engine::new();
+        engine.set_module_loader(loader);
+        engine
+            .eval("import \"lib/math\" as math\nimport \"lib/util.sald\" as util")
+            .unwrap();
+        assert_eq!(engine.eval_as::<i64>("math.quad(3)").unwrap(), 12);
+        // Both imports share one evaluation of lib/util.sald
+        engine.eval("util.seen.push(1)").unwrap();
+        assert_eq!(engine.eval_as::<i64>("math.seen()").unwrap(), 1);
+
+        let err = engine.eval("import \"lib/missing\"").unwrap_err();
+        assert!(err.to_string().contains("Module 'lib/missing' not found"));
+    }
+}
@@ -1,13 +1,24 @@
 pub mod caller;
+pub mod debugger;
 pub mod gc;
 pub mod interner;
+pub mod loader;
 pub mod natives;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod profiler;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reload;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod signals;
 pub mod value;
 pub mod vm;
 
-pub use caller::{CallableNativeInstanceFn, CallableNativeStaticFn, ValueCaller};
+pub use caller::{CallableNativeInstanceFn, CallableNativeStaticFn, ModuleMembers, ValueCaller};
+pub use debugger::{DebugContext, DebuggerHook};
+pub use loader::{MemoryLoader, ModuleLoader, ModuleSource};
 pub use natives::NativeFunction;
 pub use value::{
-    Class, Function, Instance, NativeConstructorFn, NativeInstanceFn, NativeStaticFn, Value,
+    Array, Class, Dict, DictSlot, Function, Globals, Instance, NativeConstructorFn, NativeHandle, NativeInstanceFn, NativeStaticFn,
+    Value,
 };
 pub use vm::VM;
@@ -0,0 +1,303 @@
+//! Instrumenting profiler for the VM
+//! While active, the VM counts calls and allocations per function as they
+//! happen and samples the call stack every few instructions, attributing the
+//! time since the previous sample to it
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cell::{Cell, RefCell};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// Instructions between two stack samples
+const SAMPLE_INTERVAL: u32 = 16;
+
+thread_local! {
+    /// Checked before every instruction, so kept apart from the collected data
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::default());
+    /// Set while the host, such as the CLI's `--profile`, owns the profiler
+    static SESSION: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FunctionStats {
+    pub calls: u64,
+    /// Time with this function on top of the stack
+    pub self_time: Duration,
+    /// Time with this function anywhere on the stack
+    pub total_time: Duration,
+    pub allocations: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Sorted by self time, longest first
+    pub functions: Vec<(String, FunctionStats)>,
+    /// Collapsed stacks (`outer;inner`) with the time spent in each
+    pub stacks: Vec<(String, Duration)>,
+}
+
+impl ProfileReport {
+    /// Collapsed stack format understood by `flamegraph.pl` and inferno,
+    /// weighted in microseconds.
+    pub fn to_collapsed(&self) -> String {
+        let mut out = String::new();
+        for (stack, time) in &self.stacks {
+            let micros = time.as_micros();
+            if micros > 0 {
+                let _ = writeln!(out, "{} {}", stack, micros);
+            }
+        }
+        out
+    }
+
+    /// Human readable table of the per-function statistics.
+    pub fn to_table(&self) -> String {
+        let mut out = format!(
+            "{:<40} {:>10} {:>12} {:>12} {:>12}\n",
+            "Function", "Calls", "Self ms", "Total ms", "Allocs"
+        );
+        for (name, stats) in &self.functions {
+            let _ = writeln!(
+                out,
+                "{:<40} {:>10} {:>12.3} {:>12.3} {:>12}",
+                name,
+                stats.calls,
+                stats.self_time.as_secs_f64() * 1000.0,
+                stats.total_time.as_secs_f64() * 1000.0,
+                stats.allocations
+            );
+        }
+        out
+    }
+}
+
+#[derive(Default)]
+struct Profiler {
+    functions: FxHashMap<String, FunctionStats>,
+    stacks: FxHashMap<String, Duration>,
+    last_sample: Option<Instant>,
+    /// Frame depth at the previous instruction; `None` until the first one
+    depth: Option<usize>,
+    countdown: u32,
+}
+
+/// Starts profiling on this thread, discarding the results of any previous run.
+/// Frames already running when profiling starts are not counted as calls.
+pub fn start() {
+    PROFILER.with(|p| *p.borrow_mut() = Profiler::default());
+    ACTIVE.with(|active| active.set(true));
+}
+
+/// Stops profiling and returns what was collected.
+pub fn stop() -> ProfileReport {
+    ACTIVE.with(|active| active.set(false));
+    report()
+}
+
+/// Starts profiling for the host. Until `stop_session`, scripts can read the
+/// results but not restart or stop the profiler.
+pub fn start_session() {
+    start();
+    SESSION.with(|session| session.set(true));
+}
+
+/// Ends the host's profiling and returns what was collected.
+pub fn stop_session() -> ProfileReport {
+    SESSION.with(|session| session.set(false));
+    stop()
+}
+
+/// Whether the host is profiling, so scripts must leave the profiler alone
+pub fn in_session() -> bool {
+    SESSION.with(Cell::get)
+}
+
+#[inline(always)]
+pub fn is_active() -> bool {
+    ACTIVE.with(Cell::get)
+}
+
+/// Results collected so far; profiling continues if active.
+pub fn report() -> ProfileReport {
+    PROFILER.with(|p| {
+        let p = p.borrow();
+        let mut functions: Vec<_> = p
+            .functions
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.clone()))
+            .collect();
+        functions.sort_by(|a, b| b.1.self_time.cmp(&a.1.self_time).then(a.0.cmp(&b.0)));
+        let mut stacks: Vec<_> = p.stacks.iter().map(|(s, t)| (s.clone(), *t)).collect();
+        stacks.sort();
+        ProfileReport { functions, stacks }
+    })
+}
+
+/// Called by the VM before each instruction while profiling. `frames` lists
+/// the labels of the active call frames, outermost first.
+pub(crate) fn step(depth: usize, frames: impl Fn() -> Vec<String>) {
+    PROFILER.with(|p| {
+        let mut p = p.borrow_mut();
+        if let Some(previous) = p.depth.filter(|previous| depth > *previous) {
+            for label in frames().into_iter().skip(previous) {
+                p.functions.entry(label).or_default().calls += 1;
+            }
+        }
+        p.depth = Some(depth);
+
+        if p.countdown > 0 {
+            p.countdown -= 1;
+            return;
+        }
+        p.countdown = SAMPLE_INTERVAL;
+
+        let now = Instant::now();
+        let elapsed = match p.last_sample.replace(now) {
+            Some(last) => now - last,
+            None => return,
+        };
+        let labels = frames();
+        let Some(top) = labels.last() else {
+            return;
+        };
+
+        p.functions.entry(top.clone()).or_default().self_time += elapsed;
+        let mut seen = FxHashSet::default();
+        for label in &labels {
+            if seen.insert(label) {
+                p.functions.entry(label.clone()).or_default().total_time += elapsed;
+            }
+        }
+        *p.stacks.entry(labels.join(";")).or_default() += elapsed;
+    });
+}
+
+pub(crate) fn record_allocation(label: &str) {
+    PROFILER.with(|p| {
+        p.borrow_mut()
+            .functions
+            .entry(label.to_string())
+            .or_default()
+            .allocations += 1;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::Engine;
+    use crate::test_util::eval;
+
+    /// The statistics for `name` after profiling `source`
+    fn profile(source: &str, name: &str) -> FunctionStats {
+        eval(&format!("Profiler.start()\n{source}\nProfiler.stop()"));
+        assert!(!is_active());
+        report()
+            .functions
+            .into_iter()
+            .find(|(label, _)| label == name)
+            .map(|(_, stats)| stats)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_counts_every_call() {
+        let fib = "fun fib(n) { if n < 2 { return n }\n return fib(n - 1) + fib(n - 2) }\nfib(10)";
+        assert_eq!(profile(fib, "fib").calls, 177);
+    }
+
+    #[test]
+    fn test_counts_allocations_per_function() {
+        let stats = profile("fun boxes() { return [[1], [2]] }\nboxes()", "boxes");
+        assert_eq!((stats.calls, stats.allocations), (1, 3));
+    }
+
+    #[test]
+    fn test_ignores_code_run_before_start() {
+        let source = "fun f() { return 1 }\nf()";
+        start();
+        stop();
+        eval(source);
+        assert!(report().functions.iter().all(|(label, _)| label != "f"));
+    }
+
+    #[test]
+    fn test_profiling_is_per_thread() {
+        start();
+        let elsewhere = std::thread::spawn(is_active).join().unwrap();
+        stop();
+        assert!(!elsewhere);
+    }
+
+    #[test]
+    fn test_collapsed_stacks_skip_sub_microsecond_entries() {
+        let report = ProfileReport {
+            functions: Vec::new(),
+            stacks: vec![
+                ("main;f".to_string(), Duration::from_micros(25)),
+                ("main;g".to_string(), Duration::from_nanos(10)),
+            ],
+        };
+        assert_eq!(report.to_collapsed(), "main;f 25\n");
+    }
+
+    #[test]
+    fn test_table_lists_each_function() {
+        let stats = FunctionStats {
+            calls: 3,
+            allocations: 2,
+            ..FunctionStats::default()
+        };
+        let report = ProfileReport {
+            functions: vec![("work".to_string(), stats)],
+            stacks: Vec::new(),
+        };
+        let table = report.to_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Function"));
+        let row: Vec<&str> = lines[1].split_whitespace().collect();
+        assert_eq!(row, ["work", "3", "0.000", "0.000", "2"]);
+    }
+
+    #[test]
+    fn test_profiler_counts_calls_and_allocations() {
+        let mut engine = Engine::new();
+        let calls: f64 = engine
+            .eval_as(
+                "fun fib(n) { if n < 2 { return n }\n return fib(n - 1) + fib(n - 2) }\n\
+                 fun boxes() { return [[1], [2]] }\n\
+                 Profiler.start()\n\
+                 fib(10)\n\
+                 boxes()\n\
+                 let report = Profiler.stop()\n\
+                 report[\"functions\"].filter(|f| f[\"name\"] == \"fib\")[0][\"calls\"]",
+            )
+            .unwrap();
+        assert_eq!(calls, 177.0);
+
+        let report = crate::vm::profiler::report();
+        let (_, boxes) = report
+            .functions
+            .iter()
+            .find(|(name, _)| name == "boxes")
+            .unwrap();
+        assert_eq!((boxes.calls, boxes.allocations), (1, 3));
+        assert!(!crate::vm::profiler::is_active());
+    }
+
+    #[test]
+    fn test_scripts_leave_a_host_session_alone() {
+        let mut engine = Engine::new();
+        start_session();
+        engine.eval("fun f() { return 1 }\nf()").unwrap();
+        assert!(engine.eval("Profiler.start()").is_err());
+        assert!(engine.eval("Profiler.stop()").is_err());
+        let active: bool = engine.eval_as("Profiler.isActive()").unwrap();
+        let report = stop_session();
+        assert!(active);
+        assert!(report.functions.iter().any(|(label, _)| label == "f"));
+        assert!(!in_session());
+    }
+}
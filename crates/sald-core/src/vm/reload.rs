@@ -0,0 +1,171 @@
+//! Watching imported modules for hot reload
+//! A background thread polls the modification times of tracked files; the VM
+//! checks for changes at calls and loop back-edges and swaps the new module
+//! code in with `VM::reload_module`
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+static WATCHING: AtomicBool = AtomicBool::new(false);
+static PENDING: AtomicBool = AtomicBool::new(false);
+static STATE: Mutex<Option<Watched>> = Mutex::new(None);
+
+#[derive(Default)]
+struct Watched {
+    files: FxHashMap<PathBuf, Option<SystemTime>>,
+    changed: Vec<String>,
+}
+
+/// Starts polling tracked files every `interval`. Calling it again is a no-op.
+pub fn watch(interval: Duration) {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        poll();
+    });
+}
+
+pub fn is_watching() -> bool {
+    WATCHING.load(Ordering::Relaxed)
+}
+
+/// Adds a file to the watch list; ignored unless `watch` was called.
+pub fn track(path: &str) {
+    if !is_watching() {
+        return;
+    }
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let mut state = STATE.lock();
+    let state = state.get_or_insert_with(Watched::default);
+    if let std::collections::hash_map::Entry::Vacant(entry) = state.files.entry(path) {
+        let modified = modified(entry.key());
+        entry.insert(modified);
+    }
+}
+
+#[inline(always)]
+pub fn is_pending() -> bool {
+    PENDING.load(Ordering::Relaxed)
+}
+
+/// Paths changed since the last call, canonicalized.
+pub fn take_changed() -> Vec<String> {
+    PENDING.store(false, Ordering::Relaxed);
+    STATE
+        .lock()
+        .as_mut()
+        .map(|state| std::mem::take(&mut state.changed))
+        .unwrap_or_default()
+}
+
+/// Blocks until a tracked file changes and returns the changed paths.
+pub fn wait_for_change() -> Vec<String> {
+    loop {
+        if is_pending() {
+            let changed = take_changed();
+            if !changed.is_empty() {
+                return changed;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn poll() {
+    let mut state = STATE.lock();
+    let Some(state) = state.as_mut() else {
+        return;
+    };
+    let mut changed = Vec::new();
+    for (path, last) in state.files.iter_mut() {
+        let current = modified(path);
+        if current.is_some() && current != *last {
+            *last = current;
+            changed.push(path.to_string_lossy().into_owned());
+        }
+    }
+    if !changed.is_empty() {
+        for path in changed {
+            if !state.changed.contains(&path) {
+                state.changed.push(path);
+            }
+        }
+        PENDING.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_reload_module_swaps_code_and_keeps_state() {
+        let dir = std::env::temp_dir().join(format!("sald-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("counter.sald");
+        let path = module.to_str().unwrap().replace('\\', "/");
+        let write = |version: u32| {
+            std::fs::write(
+                &module,
+                format!(
+                    "let count = 0\n\
+                     fun bump() {{ count = count + 1\n return count }}\n\
+                     fun version() {{ return {} }}",
+                    version
+                ),
+            )
+            .unwrap();
+        };
+
+        write(1);
+        let mut engine = Engine::new();
+        engine
+            .eval(&format!(
+                "import \"{0}\" as counter\nimport \"{0}\"\ncounter.bump()\ncounter.bump()",
+                path
+            ))
+            .unwrap();
+
+        write(2);
+        assert_eq!(engine.vm().reload_module(&path).unwrap(), 2);
+        let result: (f64, f64, f64) = engine
+            .eval_as("[counter.version(), version(), counter.bump()]")
+            .unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(result, (2.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_reload_module_replaces_precompiled_chunk() {
+        let dir = std::env::temp_dir().join(format!("sald-reload-pre-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("greeting.sald");
+        let entry = dir.join("main.sald");
+        std::fs::write(&module, "fun greet() { return \"old\" }").unwrap();
+        std::fs::write(&entry, "import \"greeting.sald\" as greeting").unwrap();
+        let path = module.to_str().unwrap().replace('\\', "/");
+
+        let mut engine = Engine::new();
+        let modules = crate::compiler::pipeline::precompile_imports(&entry, None);
+        assert_eq!(modules.len(), 1);
+        engine.vm().set_precompiled_modules(modules);
+        engine
+            .eval(&format!("import \"{}\" as greeting", path))
+            .unwrap();
+
+        std::fs::write(&module, "fun greet() { return \"new\" }").unwrap();
+        engine.vm().reload_module(&path).unwrap();
+        let greeting: String = engine.eval_as("greeting.greet()").unwrap();
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(greeting, "new");
+    }
+}
@@ -0,0 +1,149 @@
+//! Signal hooks for `System.onSignal`
+//! The OS handler only raises a flag; the VM checks it at calls and loop
+//! back-edges, like hot reload, and runs the script's handler there, so a
+//! handler never interrupts a half-finished instruction or write
+
+use super::caller::ValueCaller;
+use super::value::Value;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Signals with hooks, by number. Covers every signal `signal_number` knows.
+const MAX_SIGNAL: usize = 32;
+
+static PENDING: AtomicBool = AtomicBool::new(false);
+static RAISED: [AtomicBool; MAX_SIGNAL] = [const { AtomicBool::new(false) }; MAX_SIGNAL];
+static INSTALLED: Mutex<Vec<i32>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// Script handlers of this thread; `Null` restores the default of exiting
+    static HANDLERS: RefCell<FxHashMap<i32, Value>> = RefCell::new(FxHashMap::default());
+}
+
+/// Signal number for a name such as "SIGINT" or "INT".
+pub fn signal_number(name: &str) -> Option<i32> {
+    let name = name.strip_prefix("SIG").unwrap_or(name);
+    #[cfg(unix)]
+    let signal = match name {
+        "HUP" => libc::SIGHUP,
+        "INT" => libc::SIGINT,
+        "QUIT" => libc::SIGQUIT,
+        "KILL" => libc::SIGKILL,
+        "USR1" => libc::SIGUSR1,
+        "USR2" => libc::SIGUSR2,
+        "PIPE" => libc::SIGPIPE,
+        "ALRM" => libc::SIGALRM,
+        "TERM" => libc::SIGTERM,
+        "CHLD" => libc::SIGCHLD,
+        "CONT" => libc::SIGCONT,
+        "STOP" => libc::SIGSTOP,
+        "WINCH" => libc::SIGWINCH,
+        _ => return None,
+    };
+    // The C runtime on Windows only delivers these two
+    #[cfg(not(unix))]
+    let signal = match name {
+        "INT" => 2,
+        "TERM" => 15,
+        _ => return None,
+    };
+    Some(signal)
+}
+
+fn signal_name(signal: i32) -> String {
+    [
+        "HUP", "INT", "QUIT", "USR1", "USR2", "PIPE", "ALRM", "TERM", "CHLD", "WINCH",
+    ]
+    .into_iter()
+    .find(|name| signal_number(name) == Some(signal))
+    .map(|name| format!("SIG{}", name))
+    .unwrap_or_else(|| signal.to_string())
+}
+
+/// Sets the handler run when `signal` arrives; `Null` removes it again.
+pub fn set_handler(signal: i32, handler: Value) -> Result<(), String> {
+    if signal <= 0 || signal as usize >= MAX_SIGNAL {
+        return Err(format!("Signal {} cannot be handled", signal));
+    }
+    #[cfg(unix)]
+    if signal == libc::SIGKILL || signal == libc::SIGSTOP {
+        return Err(format!("{} cannot be handled", signal_name(signal)));
+    }
+
+    let mut installed = INSTALLED.lock();
+    if !installed.contains(&signal) {
+        // SAFETY: the action only stores to atomics, which is signal safe
+        unsafe {
+            signal_hook_registry::register(signal, move || {
+                RAISED[signal as usize].store(true, Ordering::SeqCst);
+                PENDING.store(true, Ordering::SeqCst);
+            })
+        }
+        .map_err(|e| format!("Cannot handle {}: {}", signal_name(signal), e))?;
+        installed.push(signal);
+    }
+    HANDLERS.with(|handlers| handlers.borrow_mut().insert(signal, handler));
+    Ok(())
+}
+
+#[inline(always)]
+pub fn is_pending() -> bool {
+    PENDING.load(Ordering::Relaxed)
+}
+
+/// Runs this thread's handlers for the signals raised since the last call.
+/// Signals this thread has no hook for stay raised for the thread that does.
+pub fn dispatch(caller: &mut dyn ValueCaller) -> Result<(), String> {
+    let handlers: Vec<(i32, Value)> = HANDLERS.with(|handlers| {
+        handlers
+            .borrow()
+            .iter()
+            .map(|(signal, handler)| (*signal, handler.clone()))
+            .collect()
+    });
+    if handlers.is_empty() {
+        return Ok(());
+    }
+    PENDING.store(false, Ordering::SeqCst);
+    for (signal, handler) in handlers {
+        if !RAISED[signal as usize].swap(false, Ordering::SeqCst) {
+            continue;
+        }
+        if handler.is_null() {
+            std::process::exit(128 + signal);
+        }
+        let name = Value::String(Rc::from(signal_name(signal)));
+        caller.call(&handler, vec![name])?;
+    }
+    if RAISED.iter().any(|raised| raised.load(Ordering::SeqCst)) {
+        PENDING.store(true, Ordering::SeqCst);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_handler_runs_at_safe_point() {
+        let mut engine = Engine::new();
+        engine
+            .eval(
+                "let caught = []\n\
+                 System.onSignal(\"SIGUSR2\", |name| caught.push(name))",
+            )
+            .unwrap();
+        unsafe {
+            libc::raise(libc::SIGUSR2);
+        }
+        engine.eval("for i in 0..10 { }").unwrap();
+        let caught: Vec<String> = engine.eval_as("caught").unwrap();
+        assert_eq!(caught, ["SIGUSR2"]);
+        assert!(engine.eval("System.onSignal(\"SIGKILL\", |n| n)").is_err());
+    }
+}
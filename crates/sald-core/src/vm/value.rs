@@ -1,9 +1,13 @@
 use crate::compiler::Chunk;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::any::Any;
 use std::cell::RefCell;
 use std::fmt;
 use std::rc::Rc;
 
+/// Named arguments reach natives as one trailing `Value::NamedArgs`, which
+/// `builtins::NamedArgs::split` separates from the positional ones. Only
+/// methods listed in `Class::named_arg_methods` receive them.
 pub type NativeStaticFn = fn(&[Value]) -> Result<Value, String>;
 
 pub type NativeInstanceFn = fn(&Value, &[Value]) -> Result<Value, String>;
@@ -12,6 +16,9 @@ pub type NativeConstructorFn = fn(&[Value]) -> Result<Value, String>;
 
 pub type NativeFn = fn(&[Value]) -> Value;
 
+/// Native function backed by a closure, used for host-registered functions.
+pub type HostFn = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
 pub struct SaldFuture;
 
 /// Thread-safe value for cross-thread communication  
@@ -51,6 +58,9 @@ impl SendValue {
             }
             Value::Dictionary(dict) => {
                 let dict = dict.borrow();
+                if dict.has_instance_keys() {
+                    return Err("Cannot send a Dict with instance keys to async worker".to_string());
+                }
                 let mut result = std::collections::HashMap::with_capacity(dict.len());
                 for (k, v) in dict.iter() {
                     result.insert(k.clone(), SendValue::from_value(v)?);
@@ -69,14 +79,14 @@ impl SendValue {
             SendValue::String(s) => Value::String(std::rc::Rc::from(s)),
             SendValue::Array(arr) => {
                 let values: Vec<Value> = arr.into_iter().map(|v| v.to_value()).collect();
-                Value::Array(std::rc::Rc::new(std::cell::RefCell::new(values)))
+                Value::Array(std::rc::Rc::new(std::cell::RefCell::new(values.into())))
             }
             SendValue::Dictionary(dict) => {
                 let mut map = FxHashMap::default();
                 for (k, v) in dict {
                     map.insert(k, v.to_value());
                 }
-                Value::Dictionary(std::rc::Rc::new(std::cell::RefCell::new(map)))
+                Value::Dictionary(std::rc::Rc::new(std::cell::RefCell::new(map.into())))
             }
         }
     }
@@ -88,8 +98,8 @@ pub enum Value {
     Boolean(bool),
     Number(f64),
     String(Rc<str>),
-    Array(Rc<RefCell<Vec<Value>>>),
-    Dictionary(Rc<RefCell<FxHashMap<String, Value>>>),
+    Array(Rc<RefCell<Array>>),
+    Dictionary(Rc<RefCell<Dict>>),
     Function(Rc<Function>),
 
     NativeFunction {
@@ -97,6 +107,11 @@ pub enum Value {
         class_name: String,
     },
 
+    HostFunction {
+        func: HostFn,
+        name: String,
+    },
+
     InstanceMethod {
         receiver: Box<Value>,
         method: NativeInstanceFn,
@@ -107,6 +122,12 @@ pub enum Value {
         receiver: Box<Value>,
         method: Rc<Function>,
     },
+
+    /// A callable with its leading arguments already given, from `partial`
+    Partial {
+        callee: Box<Value>,
+        args: Rc<Vec<Value>>,
+    },
     Class(Rc<Class>),
     Instance(Rc<RefCell<Instance>>),
 
@@ -114,9 +135,9 @@ pub enum Value {
 
     Namespace {
         name: String,
-        members: Rc<RefCell<FxHashMap<String, Value>>>,
+        members: Rc<RefCell<Globals>>,
 
-        module_globals: Option<Rc<RefCell<FxHashMap<String, Value>>>>,
+        module_globals: Option<Rc<RefCell<Globals>>>,
     },
 
     Enum {
@@ -125,6 +146,9 @@ pub enum Value {
     },
 
     SpreadMarker(Box<Value>),
+
+    /// Named arguments of a call, passed after the positional ones
+    NamedArgs(Rc<RefCell<Dict>>),
 }
 
 impl Value {
@@ -138,8 +162,10 @@ impl Value {
             Value::Dictionary(_) => "Dict",
             Value::Function(_) => "Function",
             Value::NativeFunction { .. } => "NativeFunction",
+            Value::HostFunction { .. } => "NativeFunction",
             Value::InstanceMethod { .. } => "InstanceMethod",
             Value::BoundMethod { .. } => "BoundMethod",
+            Value::Partial { .. } => "Partial",
             Value::Class(_) => "Class",
             Value::Instance(inst) => {
                 let inst = inst.borrow();
@@ -152,6 +178,7 @@ impl Value {
             Value::Namespace { .. } => "Namespace",
             Value::Enum { .. } => "Enum",
             Value::SpreadMarker(_) => "SpreadMarker",
+            Value::NamedArgs(_) => "NamedArgs",
         }
     }
 
@@ -206,7 +233,14 @@ impl PartialEq for Value {
             (Value::Boolean(a), Value::Boolean(b)) => a == b,
             (Value::Number(a), Value::Number(b)) => a == b,
             (Value::String(a), Value::String(b)) => Rc::ptr_eq(a, b) || a == b,
-            (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+            (Value::Instance(a), Value::Instance(b)) => {
+                Rc::ptr_eq(a, b)
+                    || matches!(
+                        crate::builtins::date::compare_dates(self, other),
+                        Some(Ok(std::cmp::Ordering::Equal))
+                    )
+                    || crate::builtins::collections::collections_equal(self, other)
+            }
             (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
             (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
             (Value::Dictionary(a), Value::Dictionary(b)) => Rc::ptr_eq(a, b),
@@ -215,9 +249,38 @@ impl PartialEq for Value {
     }
 }
 
+/// Text shown for some instances in place of their default rendering, keyed
+/// by the instance's address; filled in from their classes' `toString`
+pub type DisplayText = FxHashMap<usize, String>;
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
+        Shown {
+            value: self,
+            text: &DisplayText::default(),
+        }
+        .fmt(f)
+    }
+}
+
+impl Value {
+    /// The value as `Display` renders it, except that instances listed in
+    /// `text` are shown as that text, however deeply they are nested
+    pub fn display_with(&self, text: &DisplayText) -> String {
+        Shown { value: self, text }.to_string()
+    }
+}
+
+/// `Display` of a value with some instances shown as given text
+struct Shown<'a> {
+    value: &'a Value,
+    text: &'a DisplayText,
+}
+
+impl fmt::Display for Shown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.text;
+        match self.value {
             Value::Null => write!(f, "null"),
             Value::Boolean(b) => write!(f, "{}", b),
             Value::Number(n) => {
@@ -230,30 +293,44 @@ impl fmt::Display for Value {
             Value::String(s) => write!(f, "{}", s),
             Value::Array(arr) => {
                 let arr = arr.borrow();
-                let items: Vec<String> = arr.iter().map(|v| format!("{}", v)).collect();
+                let items: Vec<String> = arr.iter().map(|v| v.display_with(text)).collect();
                 write!(f, "[{}]", items.join(", "))
             }
             Value::Dictionary(dict) => {
                 let dict = dict.borrow();
                 let items: Vec<String> = dict
                     .iter()
-                    .map(|(k, v)| format!("\"{}\": {}", k, v))
+                    .map(|(k, v)| match dict.key(k) {
+                        Value::String(_) => format!("\"{}\": {}", k, v.display_with(text)),
+                        key => format!("{}: {}", key.display_with(text), v.display_with(text)),
+                    })
                     .collect();
                 write!(f, "{{{}}}", items.join(", "))
             }
             Value::Function(func) => write!(f, "<fn {}>", func.name),
             Value::NativeFunction { class_name, .. } => write!(f, "<native fn {}>", class_name),
+            Value::HostFunction { name, .. } => write!(f, "<native fn {}>", name),
             Value::InstanceMethod { method_name, .. } => write!(f, "<method {}>", method_name),
             Value::BoundMethod { method, .. } => write!(f, "<bound method {}>", method.name),
+            Value::Partial { callee, .. } => write!(f, "<partial {}>", callee),
             Value::Class(class) => write!(f, "<class {}>", class.name),
+            Value::Instance(inst) if text.contains_key(&(inst.as_ptr() as usize)) => {
+                write!(f, "{}", text[&(inst.as_ptr() as usize)])
+            }
             Value::Instance(inst) => {
                 let inst = inst.borrow();
-                write!(f, "<{} instance>", inst.class_name)
+                match crate::builtins::date::format_date_instance(&inst)
+                    .or_else(|| crate::builtins::collections::format_collection(&inst))
+                {
+                    Some(text) => write!(f, "{}", text),
+                    None => write!(f, "<{} instance>", inst.class_name),
+                }
             }
             Value::Future(_) => write!(f, "<Future>"),
             Value::Namespace { name, .. } => write!(f, "<namespace {}>", name),
             Value::Enum { name, .. } => write!(f, "<enum {}>", name),
             Value::SpreadMarker(v) => write!(f, "<spread {:?}>", v),
+            Value::NamedArgs(_) => write!(f, "<named args>"),
         }
     }
 }
@@ -298,6 +375,9 @@ pub struct Function {
 
     pub param_names: Vec<String>,
 
+    /// Whether the function has a `**` parameter for extra named arguments
+    pub has_kwargs: bool,
+
     pub default_count: usize,
 
     pub decorators: Vec<String>,
@@ -319,6 +399,7 @@ impl Function {
             file: String::new(),
             upvalues: Vec::new(),
             param_names: Vec::new(),
+            has_kwargs: false,
             default_count: 0,
             decorators: Vec::new(),
             namespace_context: None,
@@ -342,6 +423,7 @@ impl Function {
             file: String::new(),
             upvalues: Vec::new(),
             param_names: Vec::new(),
+            has_kwargs: false,
             default_count: 0,
             decorators: Vec::new(),
             namespace_context: None,
@@ -366,6 +448,7 @@ impl Function {
             file: String::new(),
             upvalues: Vec::with_capacity(upvalue_count),
             param_names: Vec::new(),
+            has_kwargs: false,
             default_count: 0,
             decorators: Vec::new(),
             namespace_context: None,
@@ -373,22 +456,23 @@ impl Function {
         }
     }
 
-    pub fn from_constant(fc: &crate::compiler::chunk::FunctionConstant) -> Self {
-        Self {
+    pub fn from_constant(fc: &crate::compiler::chunk::FunctionConstant) -> Result<Self, String> {
+        Ok(Self {
             name: fc.name.clone(),
             arity: fc.arity,
             is_variadic: fc.is_variadic,
             is_async: fc.is_async,
             upvalue_count: fc.upvalue_count,
-            chunk: fc.chunk.clone(),
+            chunk: fc.chunk.load()?.clone(),
             file: fc.file.clone(),
             upvalues: Vec::with_capacity(fc.upvalue_count),
             param_names: fc.param_names.clone(),
+            has_kwargs: fc.has_kwargs,
             default_count: fc.default_count,
             decorators: fc.decorators.clone(),
             namespace_context: fc.namespace_context.clone(),
             class_context: fc.class_context.clone(),
-        }
+        })
     }
 }
 
@@ -402,6 +486,8 @@ pub struct Class {
 
     pub native_static_methods: FxHashMap<String, NativeStaticFn>,
 
+    pub callable_native_static_methods: FxHashMap<String, super::caller::CallableNativeStaticFn>,
+
     pub native_instance_methods: FxHashMap<String, NativeInstanceFn>,
 
     pub callable_native_instance_methods:
@@ -411,7 +497,14 @@ pub struct Class {
 
     pub constructor: Option<NativeConstructorFn>,
 
+    /// Constructor that can call back into the VM, tried before `constructor`
+    pub callable_constructor: Option<super::caller::CallableNativeStaticFn>,
+
     pub superclass: Option<Rc<Class>>,
+
+    /// Native methods that take named arguments; calls to any other native
+    /// with named arguments are rejected
+    pub named_arg_methods: FxHashSet<String>,
 }
 
 impl Class {
@@ -422,10 +515,13 @@ impl Class {
             user_static_methods: FxHashMap::default(),
             native_static_methods: FxHashMap::default(),
             native_instance_methods: FxHashMap::default(),
+            callable_native_static_methods: FxHashMap::default(),
             callable_native_instance_methods: FxHashMap::default(),
             native_static_fields: FxHashMap::default(),
             constructor: None,
+            callable_constructor: None,
             superclass: None,
+            named_arg_methods: FxHashSet::default(),
         }
     }
 
@@ -439,10 +535,13 @@ impl Class {
             user_static_methods: FxHashMap::default(),
             native_static_methods,
             native_instance_methods: FxHashMap::default(),
+            callable_native_static_methods: FxHashMap::default(),
             callable_native_instance_methods: FxHashMap::default(),
             native_static_fields: FxHashMap::default(),
             constructor: None,
+            callable_constructor: None,
             superclass: None,
+            named_arg_methods: FxHashSet::default(),
         }
     }
 
@@ -457,10 +556,13 @@ impl Class {
             user_static_methods: FxHashMap::default(),
             native_static_methods: FxHashMap::default(),
             native_instance_methods,
+            callable_native_static_methods: FxHashMap::default(),
             callable_native_instance_methods: FxHashMap::default(),
             native_static_fields: FxHashMap::default(),
             constructor,
+            callable_constructor: None,
             superclass: None,
+            named_arg_methods: FxHashSet::default(),
         }
     }
 
@@ -475,12 +577,42 @@ impl Class {
             user_static_methods: FxHashMap::default(),
             native_static_methods,
             native_instance_methods: FxHashMap::default(),
+            callable_native_static_methods: FxHashMap::default(),
             callable_native_instance_methods: FxHashMap::default(),
             native_static_fields,
             constructor: None,
+            callable_constructor: None,
             superclass: None,
+            named_arg_methods: FxHashSet::default(),
         }
     }
+
+    /// Whether the native method `name` takes named arguments
+    pub fn takes_named_args(&self, name: &str) -> bool {
+        self.named_arg_methods.contains(name)
+    }
+}
+
+/// Rust state behind an instance of a builtin class such as `Set` or
+/// `Deque`. Scripts can't see or replace it, it is only handed out as the
+/// type it was created with, and it is dropped along with the instance.
+#[derive(Clone)]
+pub struct NativeHandle(Rc<dyn Any>);
+
+impl NativeHandle {
+    pub fn new<T: 'static>(state: T) -> Self {
+        Self::shared(Rc::new(RefCell::new(state)))
+    }
+
+    /// Handle to state that other handles may share
+    pub fn shared<T: 'static>(state: Rc<RefCell<T>>) -> Self {
+        Self(state)
+    }
+
+    /// The state, if it was created as a `T`
+    pub fn get<T: 'static>(&self) -> Option<Rc<RefCell<T>>> {
+        self.0.clone().downcast::<RefCell<T>>().ok()
+    }
 }
 
 #[derive(Clone)]
@@ -488,6 +620,9 @@ pub struct Instance {
     pub class_name: String,
     pub class: Rc<Class>,
     pub fields: FxHashMap<String, Value>,
+    pub native: Option<NativeHandle>,
+    /// Set by `Type.freeze`; frozen instances refuse new field values
+    pub frozen: bool,
 }
 
 impl Instance {
@@ -496,6 +631,331 @@ impl Instance {
             class_name: class.name.clone(),
             class,
             fields: FxHashMap::default(),
+            native: None,
+            frozen: false,
+        }
+    }
+
+    /// Instance of a builtin class backed by `state`
+    pub fn with_native<T: 'static>(class: Rc<Class>, state: T) -> Self {
+        Self {
+            native: Some(NativeHandle::new(state)),
+            ..Self::new(class)
+        }
+    }
+
+    /// The instance's native state, if it has one of type `T`
+    pub fn native<T: 'static>(&self) -> Option<Rc<RefCell<T>>> {
+        self.native.as_ref().and_then(NativeHandle::get)
+    }
+}
+
+/// Elements of an array, with the flag set by `Type.freeze`. Natives that
+/// change an array check `is_frozen` first.
+#[derive(Default)]
+pub struct Array {
+    items: Vec<Value>,
+    frozen: bool,
+}
+
+impl Array {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_vec(self) -> Vec<Value> {
+        self.items
+    }
+
+    /// Whether `Type.freeze` was called on the array
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+}
+
+/// Copies are never frozen
+impl Clone for Array {
+    fn clone(&self) -> Self {
+        self.items.clone().into()
+    }
+}
+
+impl std::ops::Deref for Array {
+    type Target = Vec<Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.items
+    }
+}
+
+impl std::ops::DerefMut for Array {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.items
+    }
+}
+
+impl From<Vec<Value>> for Array {
+    fn from(items: Vec<Value>) -> Self {
+        Self {
+            items,
+            frozen: false,
         }
     }
 }
+
+impl FromIterator<Value> for Array {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        Vec::from_iter(iter).into()
+    }
+}
+
+impl IntoIterator for Array {
+    type Item = Value;
+    type IntoIter = std::vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+/// Variables of a module, or members of a namespace, with the names among
+/// them declared `const`. Derefs to the variables.
+#[derive(Clone, Default)]
+pub struct Globals {
+    values: FxHashMap<String, Value>,
+    consts: FxHashSet<String>,
+}
+
+impl Globals {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `name` was declared `const`
+    pub fn is_const(&self, name: &str) -> bool {
+        !self.consts.is_empty() && self.consts.contains(name)
+    }
+
+    pub fn set_const(&mut self, name: String) {
+        self.consts.insert(name);
+    }
+
+    /// Names declared `const`
+    pub fn consts(&self) -> &FxHashSet<String> {
+        &self.consts
+    }
+
+    pub fn into_map(self) -> FxHashMap<String, Value> {
+        self.values
+    }
+}
+
+impl std::ops::Deref for Globals {
+    type Target = FxHashMap<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl std::ops::DerefMut for Globals {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.values
+    }
+}
+
+impl From<FxHashMap<String, Value>> for Globals {
+    fn from(values: FxHashMap<String, Value>) -> Self {
+        Self {
+            values,
+            consts: FxHashSet::default(),
+        }
+    }
+}
+
+impl FromIterator<(String, Value)> for Globals {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        FxHashMap::from_iter(iter).into()
+    }
+}
+
+impl IntoIterator for Globals {
+    type Item = (String, Value);
+    type IntoIter = std::collections::hash_map::IntoIter<String, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
+
+/// Contents of a dictionary. Keys are strings, or instances whose class
+/// defines `__hash__`: each of those gets an entry key made of a NUL, its hash,
+/// another NUL and its address, and is kept in a bucket for that hash so that
+/// instances whose hashes collide are told apart with `__eq__`.
+#[derive(Default)]
+pub struct Dict {
+    entries: FxHashMap<String, Value>,
+    /// Instance keys with their entry keys, by hash
+    buckets: FxHashMap<String, Vec<(String, Value)>>,
+    frozen: bool,
+}
+
+/// Where a key lives in a dictionary
+pub enum DictSlot {
+    /// Entry key of a string, or of an instance equal to one already used
+    Entry(String),
+    /// An instance with this hash that no key equals yet
+    Vacant(String),
+}
+
+impl Dict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: FxHashMap::with_capacity_and_hasher(capacity, Default::default()),
+            ..Self::default()
+        }
+    }
+
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        self.entries.insert(key, value)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.entries.get_mut(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let removed = self.entries.remove(key)?;
+        if let Some(hash) = Self::hash_of(key) {
+            if let Some(bucket) = self.buckets.get_mut(hash) {
+                bucket.retain(|(entry, _)| entry != key);
+                if bucket.is_empty() {
+                    self.buckets.remove(hash);
+                }
+            }
+        }
+        Some(removed)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.buckets.clear();
+    }
+
+    /// Instance keys whose hash is `hash`, with their entry keys
+    pub fn bucket(&self, hash: &str) -> &[(String, Value)] {
+        self.buckets.get(hash).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn get_slot(&self, slot: &DictSlot) -> Option<&Value> {
+        match slot {
+            DictSlot::Entry(key) => self.entries.get(key),
+            DictSlot::Vacant(_) => None,
+        }
+    }
+
+    /// Stores `value` at `slot`, which `key` resolved to
+    pub fn insert_slot(&mut self, slot: DictSlot, key: &Value, value: Value) {
+        match slot {
+            DictSlot::Entry(entry) => {
+                self.entries.insert(entry, value);
+            }
+            DictSlot::Vacant(hash) => {
+                let entry = match key {
+                    Value::Instance(inst) => format!("\0{}\0{:x}", hash, Rc::as_ptr(inst) as usize),
+                    _ => format!("\0{}\0", hash),
+                };
+                self.buckets
+                    .entry(hash)
+                    .or_default()
+                    .push((entry.clone(), key.clone()));
+                self.entries.insert(entry, value);
+            }
+        }
+    }
+
+    /// The key an entry was stored under, as scripts see it
+    pub fn key(&self, entry: &str) -> Value {
+        Self::hash_of(entry)
+            .and_then(|hash| self.bucket(hash).iter().find(|(e, _)| e == entry))
+            .map(|(_, key)| key.clone())
+            .unwrap_or_else(|| Value::String(Rc::from(entry)))
+    }
+
+    /// Stores `value` under an entry copied from another dictionary, where
+    /// `key` is what `key(entry)` returned
+    pub fn insert_entry(&mut self, entry: &str, key: &Value, value: Value) {
+        if let (Value::Instance(_), Some(hash)) = (key, Self::hash_of(entry)) {
+            if !self.entries.contains_key(entry) {
+                self.buckets
+                    .entry(hash.to_string())
+                    .or_default()
+                    .push((entry.to_string(), key.clone()));
+            }
+        }
+        self.entries.insert(entry.to_string(), value);
+    }
+
+    /// Whether any key is an instance
+    pub fn has_instance_keys(&self) -> bool {
+        !self.buckets.is_empty()
+    }
+
+    /// Whether `Type.freeze` was called on the dictionary
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    fn hash_of(entry: &str) -> Option<&str> {
+        entry
+            .strip_prefix('\0')?
+            .rsplit_once('\0')
+            .map(|(hash, _)| hash)
+    }
+}
+
+impl std::ops::Deref for Dict {
+    type Target = FxHashMap<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+/// Copies are never frozen
+impl Clone for Dict {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            buckets: self.buckets.clone(),
+            frozen: false,
+        }
+    }
+}
+
+impl From<FxHashMap<String, Value>> for Dict {
+    fn from(entries: FxHashMap<String, Value>) -> Self {
+        Self {
+            entries,
+            ..Self::default()
+        }
+    }
+}
+
+impl FromIterator<(String, Value)> for Dict {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(iter: I) -> Self {
+        FxHashMap::from_iter(iter).into()
+    }
+}
@@ -2,16 +2,24 @@ use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
 use std::cell::RefCell;
 use std::rc::Rc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::rc::Weak;
 
 use crate::builtins;
-use crate::compiler::chunk::{Chunk, Constant};
+use crate::compiler::chunk::{Chunk, Constant, FunctionConstant};
 use crate::compiler::Compiler;
 use crate::error::{ErrorKind, SaldError, SaldResult, Span, StackFrame};
 use crate::lexer::Scanner;
 use crate::parser::Parser;
-use crate::vm::caller::ValueCaller;
+use crate::vm::caller::{ModuleMembers, ValueCaller};
+use crate::vm::debugger::{DebugContext, DebuggerHook};
 use crate::vm::gc::GcHeap;
-use crate::vm::value::{Class, Function, Instance, UpvalueObj, Value};
+use crate::vm::loader::{ModuleLoader, ModuleSource};
+use crate::vm::value::{
+    Array, Class, Dict, DictSlot, DisplayText, Function, Globals, Instance, UpvalueObj, Value,
+};
+use crate::warnings::{self, Warning, WarningKind};
+use crate::workspace::Workspace;
 
 const STACK_MAX: usize = 65536;
 const FRAMES_MAX: usize = 4096;
@@ -33,7 +41,7 @@ struct CallFrame {
 
     class_context: Option<String>,
 
-    saved_globals: Option<Rc<RefCell<FxHashMap<String, Value>>>>,
+    saved_globals: Option<Rc<RefCell<Globals>>>,
 }
 
 impl CallFrame {
@@ -104,10 +112,25 @@ struct ExceptionHandler {
     catch_ip: usize,
 }
 
+/// How a nested run, such as a callback a native called, failed
+enum Raised {
+    /// A value was thrown and nothing caught it
+    Thrown(Value),
+    Error(ErrorKind),
+}
+
+/// The failure of the last nested run and the message its native was handed.
+/// A native that fails with that same message passes the failure on, so it
+/// is raised again as it was.
+struct NestedError {
+    message: String,
+    raised: Raised,
+}
+
 pub struct VM {
     stack: Vec<Value>,
     frames: Vec<CallFrame>,
-    globals: Rc<RefCell<FxHashMap<String, Value>>>,
+    globals: Rc<RefCell<Globals>>,
     file: String,
     source: String,
     exception_handlers: SmallVec<[ExceptionHandler; 4]>,
@@ -119,6 +142,51 @@ pub struct VM {
     args: Vec<String>,
 
     namespace_context: Vec<String>,
+
+    precompiled_modules: FxHashMap<String, Chunk>,
+
+    debugger_hook: Option<DebuggerHook>,
+
+    /// Project root and package directories paths resolve against
+    workspace: Rc<RefCell<Workspace>>,
+    /// Serves imports instead of the filesystem when set
+    module_loader: Option<Rc<dyn ModuleLoader>>,
+
+    #[cfg(not(target_arch = "wasm32"))]
+    modules: Vec<ModuleBinding>,
+    /// Every module evaluated so far, keyed by canonical path or loader id
+    module_registry: FxHashMap<String, LoadedModule>,
+    /// Modules currently being evaluated, outermost first
+    import_stack: Vec<String>,
+    /// Extra named arguments of the call being entered, taken by the
+    /// callee's `Kwargs` instruction
+    pending_kwargs: Option<Rc<RefCell<Dict>>>,
+    /// Value of the last `throw` that no handler caught
+    uncaught: Option<Value>,
+    nested_error: Option<NestedError>,
+}
+
+/// A module shared by every import of the same file
+#[derive(Clone)]
+struct LoadedModule {
+    globals: Rc<RefCell<Globals>>,
+    /// Members of its `import ... as` namespace, filled in when the module
+    /// finishes so a circular import sees them once they exist
+    members: Rc<RefCell<Globals>>,
+    loading: bool,
+}
+
+/// Where an imported module's values ended up, so `reload_module` can
+/// replace them.
+#[cfg(not(target_arch = "wasm32"))]
+struct ModuleBinding {
+    path: String,
+    /// Namespace members for `import ... as`, the importer's globals otherwise
+    target: Weak<RefCell<Globals>>,
+    /// The module's own globals, for namespace imports
+    module_globals: Option<Weak<RefCell<Globals>>>,
+    /// Names a plain import copied into `target`
+    names: Vec<String>,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -133,11 +201,17 @@ enum ControlFlow {
     Continue,
     Return(Value),
     Error(SaldError),
+    /// `await` hit a Future the host has not settled yet
+    Suspend,
 }
 
 type OpHandler = fn(&mut VM) -> ControlFlow;
 
-static DISPATCH: [OpHandler; 69] = [
+/// Members of a freshly run module, and the globals table it ran in
+#[cfg(not(target_arch = "wasm32"))]
+type ImportedModule = (FxHashMap<String, Value>, ModuleMembers);
+
+static DISPATCH: [OpHandler; 79] = [
     op_constant,
     op_pop,
     op_dup,
@@ -206,7 +280,17 @@ static DISPATCH: [OpHandler; 69] = [
     op_build_range_inclusive,
     op_build_range_exclusive,
     op_recursive_call,
-    op_nop,
+    op_debugger,
+    op_strict_equal,
+    op_strict_not_equal,
+    op_define_const_global,
+    op_import_namespace,
+    op_define_namespace,
+    op_import_dynamic,
+    op_named_args,
+    op_kwargs,
+    op_get_property_or_null,
+    op_for_iter,
 ];
 
 #[inline(always)]
@@ -277,6 +361,12 @@ fn op_define_global(vm: &mut VM) -> ControlFlow {
     let idx = vm.read_u16() as usize;
     match vm.read_string_constant(idx) {
         Ok(name) => {
+            if vm.is_const_global(&name) {
+                return ControlFlow::Error(vm.create_error(
+                    ErrorKind::TypeError,
+                    &format!("Cannot redeclare constant '{}'", name),
+                ));
+            }
             if !vm.stack.is_empty() {
                 let value = vm.pop_fast();
                 vm.globals.borrow_mut().insert(name, value);
@@ -287,6 +377,85 @@ fn op_define_global(vm: &mut VM) -> ControlFlow {
     }
 }
 
+fn op_define_const_global(vm: &mut VM) -> ControlFlow {
+    let idx = vm.read_u16() as usize;
+    match vm.read_string_constant(idx) {
+        Ok(name) => {
+            if vm.is_const_global(&name) {
+                return ControlFlow::Error(vm.create_error(
+                    ErrorKind::TypeError,
+                    &format!("Cannot redeclare constant '{}'", name),
+                ));
+            }
+            let value = (!vm.stack.is_empty()).then(|| vm.pop_fast());
+            let mut globals = vm.globals.borrow_mut();
+            if let Some(value) = value {
+                globals.insert(name.clone(), value);
+            }
+            globals.set_const(name);
+            ControlFlow::Continue
+        }
+        Err(e) => ControlFlow::Error(e),
+    }
+}
+
+fn op_define_namespace(vm: &mut VM) -> ControlFlow {
+    let idx = vm.read_u16() as usize;
+    match vm.read_string_constant(idx) {
+        Ok(name) => {
+            if vm.is_const_global(&name) {
+                return ControlFlow::Error(vm.create_error(
+                    ErrorKind::TypeError,
+                    &format!("Cannot redeclare constant '{}'", name),
+                ));
+            }
+            let mut value = vm.stack.pop().unwrap_or(Value::Null);
+            if let Value::Namespace { name: ns_name, .. } = &mut value {
+                if ns_name.is_empty() {
+                    *ns_name = name.clone();
+                }
+            }
+            let mut globals = vm.globals.borrow_mut();
+            let value = match globals.get(&name) {
+                Some(existing) => merge_namespaces(existing, value),
+                None => value,
+            };
+            globals.insert(name, value);
+            ControlFlow::Continue
+        }
+        Err(e) => ControlFlow::Error(e),
+    }
+}
+
+/// Reopening a namespace, in the same file or another one, adds to its
+/// members. The result is a new namespace so the earlier one, which other
+/// modules may hold, is left as it was.
+fn merge_namespaces(existing: &Value, value: Value) -> Value {
+    match (existing, value) {
+        (
+            Value::Namespace {
+                members: old_members,
+                module_globals: old_globals,
+                ..
+            },
+            Value::Namespace {
+                name,
+                members,
+                module_globals,
+            },
+        ) if !Rc::ptr_eq(old_members, &members) => {
+            let mut merged = old_members.borrow().clone();
+            merged.extend(members.borrow().iter().map(|(k, v)| (k.clone(), v.clone())));
+            Value::Namespace {
+                name,
+                members: Rc::new(RefCell::new(merged)),
+                module_globals: module_globals.or_else(|| old_globals.clone()),
+            }
+        }
+        (_, value) => value,
+    }
+}
+
 #[inline(always)]
 fn op_get_global(vm: &mut VM) -> ControlFlow {
     let idx = vm.read_u16() as usize;
@@ -319,6 +488,12 @@ fn op_set_global(vm: &mut VM) -> ControlFlow {
                     &format!("Undefined variable '{}'", name),
                 ));
             }
+            if vm.is_const_global(&name) {
+                return ControlFlow::Error(vm.create_error(
+                    ErrorKind::TypeError,
+                    &format!("Cannot assign to constant '{}'", name),
+                ));
+            }
             if let Some(value) = vm.peek().cloned() {
                 vm.globals.borrow_mut().insert(name, value);
             }
@@ -374,6 +549,10 @@ fn op_add(vm: &mut VM) -> ControlFlow {
             result.push_str(b_str);
             Value::String(Rc::from(result))
         }
+        (Value::String(_), Value::Instance(_) | Value::Array(_) | Value::Dictionary(_))
+        | (Value::Instance(_) | Value::Array(_) | Value::Dictionary(_), Value::String(_)) => {
+            return add_string_and_object(vm);
+        }
         (Value::String(a_str), b) => {
             use std::fmt::Write;
             let mut result = String::with_capacity(a_str.len() + 32);
@@ -397,6 +576,15 @@ fn op_add(vm: &mut VM) -> ControlFlow {
             ));
         }
     };
+    let null_operand = matches!(
+        (&vm.stack[len - 2], &vm.stack[len - 1]),
+        (Value::String(_), Value::Null) | (Value::Null, Value::String(_))
+    );
+    if null_operand {
+        if let Some(flow) = warn_flow(vm, WarningKind::Coercion, "null concatenated to a string") {
+            return flow;
+        }
+    }
 
     unsafe {
         *vm.stack.get_unchecked_mut(len - 2) = result;
@@ -412,9 +600,66 @@ fn op_sub(vm: &mut VM) -> ControlFlow {
 
 #[inline(always)]
 fn op_mul(vm: &mut VM) -> ControlFlow {
+    let len = vm.stack.len();
+    if len >= 2 {
+        let b = unsafe { vm.stack.get_unchecked(len - 1) };
+        let a = unsafe { vm.stack.get_unchecked(len - 2) };
+        if matches!(
+            (a, b),
+            (Value::String(_) | Value::Array(_), Value::Number(_))
+                | (Value::Number(_), Value::String(_) | Value::Array(_))
+        ) {
+            return repeat_op(vm);
+        }
+    }
     binary_num_op(vm, |a, b| a * b)
 }
 
+/// `*` of a string or array and a count, repeating the contents
+#[cold]
+fn repeat_op(vm: &mut VM) -> ControlFlow {
+    let b = vm.stack.pop().unwrap_or(Value::Null);
+    let a = vm.stack.pop().unwrap_or(Value::Null);
+    let (value, count) = match (a, b) {
+        (Value::Number(n), value) | (value, Value::Number(n)) => (value, n),
+        _ => unreachable!("op_mul only repeats with a number"),
+    };
+    if count < 0.0 || count.fract() != 0.0 {
+        return ControlFlow::Error(vm.create_error(
+            ErrorKind::ValueError,
+            &format!(
+                "Repeat count must be a whole number of at least 0, got {}",
+                count
+            ),
+        ));
+    }
+    let len = match &value {
+        Value::String(s) => s.len(),
+        Value::Array(items) => items.borrow().len(),
+        _ => unreachable!("op_mul only repeats strings and arrays"),
+    };
+    let total = match builtins::repeat_len(len, count) {
+        Ok(total) => total,
+        Err(e) => return ControlFlow::Error(vm.create_error(ErrorKind::ValueError, &e)),
+    };
+    let result = match value {
+        Value::String(s) => Value::String(Rc::from(s.repeat(count as usize))),
+        Value::Array(items) => {
+            let items = items.borrow();
+            let mut repeated = Vec::with_capacity(total);
+            while repeated.len() < total {
+                repeated.extend(items.iter().cloned());
+            }
+            let arr = Rc::new(RefCell::new(repeated.into()));
+            vm.track_array(&arr);
+            Value::Array(arr)
+        }
+        _ => unreachable!("op_mul only repeats strings and arrays"),
+    };
+    vm.stack.push(result);
+    ControlFlow::Continue
+}
+
 #[inline(always)]
 fn op_div(vm: &mut VM) -> ControlFlow {
     let len = vm.stack.len();
@@ -512,6 +757,9 @@ fn op_equal(vm: &mut VM) -> ControlFlow {
     }
     let b = unsafe { vm.stack.get_unchecked(len - 1) };
     let a = unsafe { vm.stack.get_unchecked(len - 2) };
+    if matches!(a, Value::Instance(_)) || matches!(b, Value::Instance(_)) {
+        return user_equality(vm, true);
+    }
     let result = a == b;
     unsafe {
         *vm.stack.get_unchecked_mut(len - 2) = Value::Boolean(result);
@@ -528,6 +776,9 @@ fn op_not_equal(vm: &mut VM) -> ControlFlow {
     }
     let b = unsafe { vm.stack.get_unchecked(len - 1) };
     let a = unsafe { vm.stack.get_unchecked(len - 2) };
+    if matches!(a, Value::Instance(_)) || matches!(b, Value::Instance(_)) {
+        return user_equality(vm, false);
+    }
     let result = a != b;
     unsafe {
         *vm.stack.get_unchecked_mut(len - 2) = Value::Boolean(result);
@@ -536,6 +787,67 @@ fn op_not_equal(vm: &mut VM) -> ControlFlow {
     ControlFlow::Continue
 }
 
+/// `==` with an instance on either side, which uses the left one's `__eq__`,
+/// else the right one's, so that both orders agree
+#[cold]
+fn user_equality(vm: &mut VM, equal: bool) -> ControlFlow {
+    let b = vm.stack.pop().unwrap_or(Value::Null);
+    let a = vm.stack.pop().unwrap_or(Value::Null);
+    let identical =
+        matches!((&a, &b), (Value::Instance(x), Value::Instance(y)) if Rc::ptr_eq(x, y));
+    let same = if identical {
+        true
+    } else {
+        let result = match vm.call_user_method(&a, "__eq__", vec![b.clone()]) {
+            Some(result) => Some(result),
+            None => vm.call_user_method(&b, "__eq__", vec![a.clone()]),
+        };
+        match result {
+            Some(Ok(result)) => result.is_truthy(),
+            Some(Err(e)) => {
+                return match vm.handle_native_error(e) {
+                    Ok(()) => ControlFlow::Continue,
+                    Err(e) => ControlFlow::Error(e),
+                }
+            }
+            None => a == b,
+        }
+    };
+    vm.stack.push(Value::Boolean(same == equal));
+    ControlFlow::Continue
+}
+
+/// Strict mode: comparing values of different types is an error, except
+/// against null.
+fn strict_equality_op(vm: &mut VM, equal: bool) -> ControlFlow {
+    let len = vm.stack.len();
+    if len < 2 {
+        return ControlFlow::Continue;
+    }
+    let (a, b) = (&vm.stack[len - 2], &vm.stack[len - 1]);
+    if !matches!(a, Value::Null) && !matches!(b, Value::Null) && a.type_name() != b.type_name() {
+        let message = format!(
+            "Cannot compare '{}' with '{}' in strict mode",
+            a.type_name(),
+            b.type_name()
+        );
+        return ControlFlow::Error(vm.create_error(ErrorKind::TypeError, &message));
+    }
+    if equal {
+        op_equal(vm)
+    } else {
+        op_not_equal(vm)
+    }
+}
+
+fn op_strict_equal(vm: &mut VM) -> ControlFlow {
+    strict_equality_op(vm, true)
+}
+
+fn op_strict_not_equal(vm: &mut VM) -> ControlFlow {
+    strict_equality_op(vm, false)
+}
+
 #[inline(always)]
 fn op_less(vm: &mut VM) -> ControlFlow {
     comparison_op(vm, |a, b| a < b)
@@ -617,11 +929,31 @@ fn op_jump_if_not_null(vm: &mut VM) -> ControlFlow {
 fn op_loop(vm: &mut VM) -> ControlFlow {
     let offset = vm.read_u16() as usize;
     vm.current_frame_mut().ip -= offset;
+    #[cfg(not(target_arch = "wasm32"))]
+    if crate::vm::reload::is_pending() {
+        vm.apply_pending_reloads();
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if crate::vm::signals::is_pending() {
+        if let Err(e) = crate::vm::signals::dispatch(vm) {
+            return ControlFlow::Error(vm.create_error(ErrorKind::RuntimeError, &e));
+        }
+    }
     ControlFlow::Continue
 }
 
 #[inline(always)]
 fn op_call(vm: &mut VM) -> ControlFlow {
+    #[cfg(not(target_arch = "wasm32"))]
+    if crate::vm::reload::is_pending() {
+        vm.apply_pending_reloads();
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    if crate::vm::signals::is_pending() {
+        if let Err(e) = crate::vm::signals::dispatch(vm) {
+            return ControlFlow::Error(vm.create_error(ErrorKind::RuntimeError, &e));
+        }
+    }
     let arg_count = vm.read_u16() as usize;
     match vm.expand_spread_args(arg_count) {
         Ok(actual_count) => match vm.call_value(actual_count) {
@@ -714,22 +1046,77 @@ fn op_closure(vm: &mut VM) -> ControlFlow {
     let idx = vm.read_u16() as usize;
     let constant = vm.current_frame().function.chunk.constants[idx].clone();
     if let Constant::Function(ref func_const) = constant {
-        let mut function = Function::from_constant(func_const);
-        for upvalue_info in &func_const.upvalues {
-            let upvalue = if upvalue_info.is_local {
-                let slots_start = vm.current_frame().slots_start;
-                let location = slots_start + upvalue_info.index as usize;
-                vm.capture_upvalue(location)
-            } else {
-                vm.current_frame().function.upvalues[upvalue_info.index as usize].clone()
-            };
-            function.upvalues.push(upvalue);
-        }
+        let mut function = match Function::from_constant(func_const) {
+            Ok(function) => function,
+            Err(e) => return ControlFlow::Error(function_load_error(vm, func_const, &e)),
+        };
+        capture_upvalues(vm, func_const, &mut function);
         vm.stack.push(Value::Function(Rc::new(function)));
     }
     ControlFlow::Continue
 }
 
+/// Gives a new closure the variables it captures from the current frame.
+fn capture_upvalues(vm: &mut VM, func_const: &FunctionConstant, function: &mut Function) {
+    for upvalue_info in &func_const.upvalues {
+        let upvalue = if upvalue_info.is_local {
+            let slots_start = vm.current_frame().slots_start;
+            let location = slots_start + upvalue_info.index as usize;
+            vm.capture_upvalue(location)
+        } else {
+            vm.current_frame().function.upvalues[upvalue_info.index as usize].clone()
+        };
+        function.upvalues.push(upvalue);
+    }
+}
+
+/// `Class.method`, `Namespace.function` or the plain function name
+#[cfg(not(target_arch = "wasm32"))]
+fn frame_label(function: &Function) -> String {
+    match function
+        .class_context
+        .as_ref()
+        .or(function.namespace_context.as_ref())
+    {
+        Some(owner) => format!("{}.{}", owner, function.name),
+        None => function.name.clone(),
+    }
+}
+
+/// Code is replaced on reload, data keeps its runtime value.
+#[cfg(not(target_arch = "wasm32"))]
+fn merge_reloaded_value(target: &mut FxHashMap<String, Value>, name: &str, value: &Value) {
+    let keep = target.get(name).is_some_and(|old| {
+        !matches!(
+            old,
+            Value::Function(_)
+                | Value::Class(_)
+                | Value::Enum { .. }
+                | Value::Namespace { .. }
+                | Value::NativeFunction { .. }
+                | Value::HostFunction { .. }
+        )
+    });
+    if !keep {
+        target.insert(name.to_string(), value.clone());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn merge_reloaded(target: &mut FxHashMap<String, Value>, fresh: &FxHashMap<String, Value>) {
+    for (name, value) in fresh {
+        merge_reloaded_value(target, name, value);
+    }
+}
+
+#[cold]
+fn function_load_error(vm: &VM, func_const: &FunctionConstant, error: &str) -> SaldError {
+    vm.create_error(
+        ErrorKind::RuntimeError,
+        &format!("Cannot load function '{}': {}", func_const.name, error),
+    )
+}
+
 #[inline(always)]
 fn op_class(vm: &mut VM) -> ControlFlow {
     let idx = vm.read_u16() as usize;
@@ -757,7 +1144,12 @@ fn op_method_impl(vm: &mut VM, is_static: bool) -> ControlFlow {
     let idx = vm.read_u16() as usize;
     let constant = vm.current_frame().function.chunk.constants[idx].clone();
     if let Constant::Function(ref func_const) = constant {
-        let function = Rc::new(Function::from_constant(func_const));
+        let mut function = match Function::from_constant(func_const) {
+            Ok(function) => function,
+            Err(e) => return ControlFlow::Error(function_load_error(vm, func_const, &e)),
+        };
+        capture_upvalues(vm, func_const, &mut function);
+        let function = Rc::new(function);
         if let Some(Value::Class(class)) = vm.stack.last().cloned() {
             let class_mut = Rc::as_ptr(&class) as *mut Class;
             unsafe {
@@ -787,6 +1179,76 @@ fn op_get_property(vm: &mut VM) -> ControlFlow {
     }
 }
 
+/// A missing property reads as null, so `??=` can assign it
+fn op_get_property_or_null(vm: &mut VM) -> ControlFlow {
+    let idx = vm.read_u16() as usize;
+    match vm.read_string_constant(idx) {
+        Ok(name) => match vm.handle_get_property(&name) {
+            Ok(()) => ControlFlow::Continue,
+            Err(e) if e.kind == ErrorKind::AttributeError => {
+                vm.stack.push(Value::Null);
+                ControlFlow::Continue
+            }
+            Err(e) => ControlFlow::Error(e),
+        },
+        Err(e) => ControlFlow::Error(e),
+    }
+}
+
+/// Arrays, strings and native collections with `length()` and `at(i)` are
+/// read by position; `Iter` pipelines are pulled from
+fn op_for_iter(vm: &mut VM) -> ControlFlow {
+    let slot = vm.read_u16() as usize;
+    let exit = vm.read_u16() as usize;
+    let base = vm.current_frame().slots_start + slot;
+    let iterable = vm.stack[base].clone();
+    let index = match vm.stack[base + 1] {
+        Value::Number(n) => n as usize,
+        _ => 0,
+    };
+    let item = match &iterable {
+        Value::Array(items) => Ok(items.borrow().get(index).cloned()),
+        Value::String(text) => Ok(text
+            .chars()
+            .nth(index)
+            .map(|c| Value::String(Rc::from(c.to_string())))),
+        Value::Instance(inst) => match builtins::iter::next_of(&iterable, vm) {
+            Some(next) => next,
+            None => {
+                let class = inst.borrow().class.clone();
+                let methods = &class.native_instance_methods;
+                let (Some(length), Some(at)) = (methods.get("length"), methods.get("at")) else {
+                    let message = format!("Cannot iterate a {}", class.name);
+                    return ControlFlow::Error(vm.create_error(ErrorKind::TypeError, &message));
+                };
+                length(&iterable, &[]).and_then(|length| match length {
+                    Value::Number(n) if (index as f64) < n => {
+                        at(&iterable, &[Value::Number(index as f64)]).map(Some)
+                    }
+                    _ => Ok(None),
+                })
+            }
+        },
+        other => {
+            let message = format!("Cannot iterate a {}", other.type_name());
+            return ControlFlow::Error(vm.create_error(ErrorKind::TypeError, &message));
+        }
+    };
+    match item {
+        Ok(Some(item)) => {
+            vm.stack[base + 1] = Value::Number((index + 1) as f64);
+            vm.stack.push(item);
+        }
+        Ok(None) => vm.current_frame_mut().ip += exit,
+        Err(e) => {
+            if let Err(e) = vm.handle_native_error(e) {
+                return ControlFlow::Error(e);
+            }
+        }
+    }
+    ControlFlow::Continue
+}
+
 fn op_set_property(vm: &mut VM) -> ControlFlow {
     let idx = vm.read_u16() as usize;
     match vm.read_string_constant(idx) {
@@ -824,7 +1286,7 @@ fn op_build_array(vm: &mut VM) -> ControlFlow {
         elements.push(vm.stack.pop().unwrap_or(Value::Null));
     }
     elements.reverse();
-    let arr = Rc::new(RefCell::new(elements));
+    let arr = Rc::new(RefCell::new(elements.into()));
     vm.track_array(&arr);
     vm.stack.push(Value::Array(arr));
     ControlFlow::Continue
@@ -910,6 +1372,47 @@ fn op_import_as(vm: &mut VM) -> ControlFlow {
     }
 }
 
+fn op_import_namespace(vm: &mut VM) -> ControlFlow {
+    let path_idx = vm.read_u16() as usize;
+    let alias_idx = vm.read_u16() as usize;
+    match (
+        vm.read_string_constant(path_idx),
+        vm.read_string_constant(alias_idx),
+    ) {
+        (Ok(path), Ok(alias)) => match vm.import_namespace(&path, &alias) {
+            Ok(namespace) => {
+                vm.stack.push(namespace);
+                ControlFlow::Continue
+            }
+            Err(e) => ControlFlow::Error(e),
+        },
+        (Err(e), _) | (_, Err(e)) => ControlFlow::Error(e),
+    }
+}
+
+fn op_import_dynamic(vm: &mut VM) -> ControlFlow {
+    let path = match vm.stack.pop() {
+        Some(Value::String(path)) => path,
+        other => {
+            let type_name = other.as_ref().map_or("Null", Value::type_name);
+            return ControlFlow::Error(vm.create_error(
+                ErrorKind::TypeError,
+                &format!("import() expects a String path, got {}", type_name),
+            ));
+        }
+    };
+    let alias = std::path::Path::new(path.as_ref())
+        .file_stem()
+        .map_or_else(|| path.to_string(), |stem| stem.to_string_lossy().into_owned());
+    match vm.import_namespace(&path, &alias) {
+        Ok(namespace) => {
+            vm.stack.push(namespace);
+            ControlFlow::Continue
+        }
+        Err(e) => ControlFlow::Error(e),
+    }
+}
+
 fn op_get_upvalue(vm: &mut VM) -> ControlFlow {
     let idx = vm.read_u16() as usize;
     let upvalue = vm.current_frame().function.upvalues[idx].clone();
@@ -975,10 +1478,8 @@ fn op_throw(vm: &mut VM) -> ControlFlow {
         vm.current_frame_mut().ip = handler.catch_ip;
         ControlFlow::Continue
     } else {
-        let msg = match &exception_value {
-            Value::String(s) => s.to_string(),
-            other => format!("{}", other),
-        };
+        let msg = thrown_message(&exception_value);
+        vm.uncaught = Some(exception_value);
         ControlFlow::Error(vm.create_error(
             ErrorKind::RuntimeError,
             &format!("Uncaught exception: {}", msg),
@@ -986,6 +1487,14 @@ fn op_throw(vm: &mut VM) -> ControlFlow {
     }
 }
 
+/// How a thrown value reads in an error message
+fn thrown_message(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_string(),
+        other => format!("{}", other),
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn op_await(vm: &mut VM) -> ControlFlow {
     let value = vm.stack.pop().unwrap_or(Value::Null);
@@ -1003,12 +1512,10 @@ fn op_await(vm: &mut VM) -> ControlFlow {
                         Ok(Err(err)) => {
                             ControlFlow::Error(vm.create_error(ErrorKind::RuntimeError, &err))
                         }
-                        Err(_) => {
-                            ControlFlow::Error(vm.create_error(
-                                ErrorKind::RuntimeError,
-                                "Async task failed: channel closed",
-                            ))
-                        }
+                        Err(_) => ControlFlow::Error(vm.create_error(
+                            ErrorKind::RuntimeError,
+                            "Async task failed: channel closed",
+                        )),
                     }
                 }
                 None => {
@@ -1030,10 +1537,40 @@ fn op_await(vm: &mut VM) -> ControlFlow {
 fn op_await(vm: &mut VM) -> ControlFlow {
     let value = vm.stack.pop().unwrap_or(Value::Null);
     match value {
-        Value::Future(_) => ControlFlow::Error(vm.create_error(
-            ErrorKind::RuntimeError,
-            "async/await is not supported in WASM playground",
-        )),
+        Value::Future(future_ref) => {
+            let handle_opt = future_ref.borrow().clone();
+            match handle_opt {
+                Some(slot) => {
+                    let settled = slot.borrow_mut().take();
+                    match settled {
+                        Some(result) => {
+                            future_ref.borrow_mut().take();
+                            match result {
+                                Ok(send_value) => {
+                                    vm.stack.push(send_value.to_value());
+                                    ControlFlow::Continue
+                                }
+                                Err(err) => ControlFlow::Error(
+                                    vm.create_error(ErrorKind::RuntimeError, &err),
+                                ),
+                            }
+                        }
+                        None => {
+                            // Still pending: leave the Future in place and rerun
+                            // this instruction once the host resumes the VM
+                            vm.stack.push(Value::Future(future_ref));
+                            vm.current_frame_mut().ip -= 1;
+                            ControlFlow::Suspend
+                        }
+                    }
+                }
+                None => {
+                    // Future already consumed
+                    vm.stack.push(Value::Null);
+                    ControlFlow::Continue
+                }
+            }
+        }
         other => {
             vm.stack.push(other);
             ControlFlow::Continue
@@ -1041,6 +1578,19 @@ fn op_await(vm: &mut VM) -> ControlFlow {
     }
 }
 
+fn op_named_args(vm: &mut VM) -> ControlFlow {
+    if let Some(Value::Dictionary(dict)) = vm.stack.pop() {
+        vm.stack.push(Value::NamedArgs(dict));
+    }
+    ControlFlow::Continue
+}
+
+fn op_kwargs(vm: &mut VM) -> ControlFlow {
+    let kwargs = vm.pending_kwargs.take().unwrap_or_default();
+    vm.stack.push(Value::Dictionary(kwargs));
+    ControlFlow::Continue
+}
+
 fn op_spread_array(vm: &mut VM) -> ControlFlow {
     let len = vm.stack.len();
     if len > 0 {
@@ -1071,7 +1621,11 @@ fn op_bit_not(vm: &mut VM) -> ControlFlow {
     let v = unsafe { vm.stack.get_unchecked(len - 1) };
     match v {
         Value::Number(n) => {
-            let result = Value::Number((!(*n as i64)) as f64);
+            let n = *n;
+            if let Some(flow) = warn_truncated(vm, n) {
+                return flow;
+            }
+            let result = Value::Number((!(n as i64)) as f64);
             vm.stack.truncate(len - 1);
             vm.stack.push(result);
             ControlFlow::Continue
@@ -1108,8 +1662,19 @@ fn op_build_range_exclusive(vm: &mut VM) -> ControlFlow {
     }
 }
 
-fn op_nop(_vm: &mut VM) -> ControlFlow {
-    ControlFlow::Continue
+fn op_debugger(vm: &mut VM) -> ControlFlow {
+    let idx = vm.read_u16() as usize;
+    let Some(hook) = vm.debugger_hook.clone() else {
+        return ControlFlow::Continue;
+    };
+    match vm.read_string_constant(idx) {
+        Ok(names) => {
+            let context = vm.debug_context(&names);
+            hook(&context);
+            ControlFlow::Continue
+        }
+        Err(e) => ControlFlow::Error(e),
+    }
 }
 
 #[inline(always)]
@@ -1117,6 +1682,9 @@ fn op_recursive_call(vm: &mut VM) -> ControlFlow {
     let arg_count = vm.read_u16() as usize;
 
     let function = vm.current_frame().function.clone();
+    if function.has_kwargs {
+        vm.pending_kwargs = None;
+    }
 
     if vm.frames.len() >= FRAMES_MAX {
         return ControlFlow::Error(vm.create_error(
@@ -1194,18 +1762,68 @@ fn comparison_op(vm: &mut VM, op: fn(f64, f64) -> bool) -> ControlFlow {
             }
             ControlFlow::Continue
         }
-        _ => {
-            let a_type = a.type_name();
-            let b_type = b.type_name();
-            ControlFlow::Error(vm.create_error(
-                ErrorKind::TypeError,
-                &format!("Cannot compare '{}' and '{}'", a_type, b_type),
-            ))
-        }
+        _ => match builtins::date::compare_dates(a, b) {
+            Some(Ok(ordering)) => {
+                let result = op(ordering as i8 as f64, 0.0);
+                vm.stack.truncate(len - 2);
+                vm.stack.push(Value::Boolean(result));
+                ControlFlow::Continue
+            }
+            Some(Err(e)) => ControlFlow::Error(vm.create_error(ErrorKind::TypeError, &e)),
+            None => {
+                let a_type = a.type_name();
+                let b_type = b.type_name();
+                ControlFlow::Error(vm.create_error(
+                    ErrorKind::TypeError,
+                    &format!("Cannot compare '{}' and '{}'", a_type, b_type),
+                ))
+            }
+        },
     }
 }
 
-#[inline(always)]
+/// `+` of a string and an instance, array or dictionary, which shows
+/// instances at any depth by their class's `toString` if it has one
+#[cold]
+fn add_string_and_object(vm: &mut VM) -> ControlFlow {
+    let b = vm.stack.pop().unwrap_or(Value::Null);
+    let a = vm.stack.pop().unwrap_or(Value::Null);
+    let mut result = String::new();
+    for operand in [&a, &b] {
+        match vm.user_display_text(operand) {
+            Ok(text) => result.push_str(&operand.display_with(&text)),
+            Err(e) => {
+                return match vm.handle_native_error(e) {
+                    Ok(()) => ControlFlow::Continue,
+                    Err(e) => ControlFlow::Error(e),
+                }
+            }
+        }
+    }
+    vm.stack.push(Value::String(Rc::from(result)));
+    ControlFlow::Continue
+}
+
+#[inline(always)]
+/// Emits a warning from an instruction. Returns how the instruction must end
+/// when `--warnings=error` raised it, or `None` to carry on.
+fn warn_flow(vm: &mut VM, kind: WarningKind, message: &str) -> Option<ControlFlow> {
+    match vm.warn(kind, message) {
+        Ok(true) => None,
+        Ok(false) => Some(ControlFlow::Continue),
+        Err(e) => Some(ControlFlow::Error(e)),
+    }
+}
+
+/// Warns when a bitwise operand loses its fraction or does not fit 64 bits.
+fn warn_truncated(vm: &mut VM, n: f64) -> Option<ControlFlow> {
+    if n.fract() == 0.0 && n.abs() < 9.2e18 {
+        return None;
+    }
+    let message = format!("Bitwise operand {} truncated to {}", n, n as i64);
+    warn_flow(vm, WarningKind::LossyConversion, &message)
+}
+
 fn bitwise_op(vm: &mut VM, op: fn(i64, i64) -> i64) -> ControlFlow {
     let len = vm.stack.len();
     if len < 2 {
@@ -1215,7 +1833,13 @@ fn bitwise_op(vm: &mut VM, op: fn(i64, i64) -> i64) -> ControlFlow {
     let a = unsafe { vm.stack.get_unchecked(len - 2) };
     match (a, b) {
         (Value::Number(av), Value::Number(bv)) => {
-            let result = op(*av as i64, *bv as i64) as f64;
+            let (av, bv) = (*av, *bv);
+            for n in [av, bv] {
+                if let Some(flow) = warn_truncated(vm, n) {
+                    return flow;
+                }
+            }
+            let result = op(av as i64, bv as i64) as f64;
             unsafe {
                 *vm.stack.get_unchecked_mut(len - 2) = Value::Number(result);
                 vm.stack.set_len(len - 1);
@@ -1246,7 +1870,13 @@ fn shift_op(vm: &mut VM, op: fn(i64, u32) -> i64) -> ControlFlow {
     let a = unsafe { vm.stack.get_unchecked(len - 2) };
     match (a, b) {
         (Value::Number(av), Value::Number(bv)) => {
-            let result = op(*av as i64, *bv as u32) as f64;
+            let (av, bv) = (*av, *bv);
+            for n in [av, bv] {
+                if let Some(flow) = warn_truncated(vm, n) {
+                    return flow;
+                }
+            }
+            let result = op(av as i64, bv as u32) as f64;
             unsafe {
                 *vm.stack.get_unchecked_mut(len - 2) = Value::Number(result);
                 vm.stack.set_len(len - 1);
@@ -1265,14 +1895,18 @@ fn shift_op(vm: &mut VM, op: fn(i64, u32) -> i64) -> ControlFlow {
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-impl ValueCaller for VM {
-    fn call(&mut self, callee: &Value, args: Vec<Value>) -> Result<Value, String> {
-        let frame_count_before = self.frames.len();
-        self.push_fast(callee.clone()).map_err(|e| e.message)?;
+impl VM {
+    fn call_nested(
+        &mut self,
+        callee: &Value,
+        args: Vec<Value>,
+        frame_count_before: usize,
+    ) -> SaldResult<Value> {
+        self.push_fast(callee.clone())?;
         for arg in args.iter() {
-            self.push_fast(arg.clone()).map_err(|e| e.message)?;
+            self.push_fast(arg.clone())?;
         }
-        self.call_value(args.len()).map_err(|e| e.message)?;
+        self.call_value(args.len())?;
 
         loop {
             if self.frames.len() == frame_count_before {
@@ -1281,24 +1915,68 @@ impl ValueCaller for VM {
             match self.execute_one_threaded() {
                 ControlFlow::Continue => continue,
                 ControlFlow::Return(v) => return Ok(v),
-                ControlFlow::Error(e) => return Err(e.message),
+                ControlFlow::Error(e) => return Err(e),
             }
         }
     }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ValueCaller for VM {
+    fn call(&mut self, callee: &Value, args: Vec<Value>) -> Result<Value, String> {
+        let _workspace = crate::workspace::enter(&self.workspace);
+        let frame_count_before = self.frames.len();
+        let stack_size_before = self.stack.len();
+        // What the callee throws comes back to the native that called it
+        // rather than jumping to a `catch` around that native
+        let handlers = std::mem::take(&mut self.exception_handlers);
+        self.uncaught = None;
+        let result = self.call_nested(callee, args, frame_count_before);
+        self.exception_handlers = handlers;
+        if result.is_err() {
+            // Drop what the failed call left behind so later calls start clean
+            self.frames.truncate(frame_count_before);
+            self.stack.truncate(stack_size_before);
+        }
+        result.map_err(|e| self.nested_failure(e, false))
+    }
 
     fn get_globals(&self) -> FxHashMap<String, Value> {
-        self.globals.borrow().clone()
+        FxHashMap::clone(&self.globals.borrow())
     }
 
-    fn get_shared_globals(&self) -> Rc<RefCell<FxHashMap<String, Value>>> {
+    fn get_shared_globals(&self) -> Rc<RefCell<Globals>> {
         self.globals.clone()
     }
+
+    fn run_script(
+        &mut self,
+        script: Rc<Function>,
+        source: String,
+        globals: FxHashMap<String, Value>,
+    ) -> Result<Value, String> {
+        self.uncaught = None;
+        self.run_isolated(script, source, Rc::new(RefCell::new(globals.into())))
+            .map_err(|e| self.nested_failure(e, true))
+    }
+
+    fn loaded_modules(&self) -> Vec<(String, ModuleMembers)> {
+        let mut modules: Vec<_> = self
+            .module_registry
+            .iter()
+            .filter(|(_, module)| !module.loading)
+            .map(|(path, module)| (path.clone(), module.members.clone()))
+            .collect();
+        modules.sort_by(|a, b| a.0.cmp(&b.0));
+        modules
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
 impl ValueCaller for VM {
     fn call(&mut self, callee: &Value, args: Vec<Value>) -> Result<Value, String> {
         let frame_count_before = self.frames.len();
+        self.uncaught = None;
         self.push_fast(callee.clone()).map_err(|e| e.message)?;
         for arg in args.iter() {
             self.push_fast(arg.clone()).map_err(|e| e.message)?;
@@ -1312,7 +1990,13 @@ impl ValueCaller for VM {
             match self.execute_one_threaded() {
                 ControlFlow::Continue => continue,
                 ControlFlow::Return(v) => return Ok(v),
-                ControlFlow::Error(e) => return Err(e.message),
+                ControlFlow::Error(e) => return Err(self.nested_failure(e, false)),
+                ControlFlow::Suspend => {
+                    return Err(
+                        "Cannot await a pending Future inside a callback in the browser"
+                            .to_string(),
+                    )
+                }
             }
         }
     }
@@ -1321,9 +2005,31 @@ impl ValueCaller for VM {
         self.globals.borrow().clone()
     }
 
-    fn get_shared_globals(&self) -> Rc<RefCell<FxHashMap<String, Value>>> {
+    fn get_shared_globals(&self) -> Rc<RefCell<Globals>> {
         self.globals.clone()
     }
+
+    fn run_script(
+        &mut self,
+        script: Rc<Function>,
+        source: String,
+        globals: FxHashMap<String, Value>,
+    ) -> Result<Value, String> {
+        self.uncaught = None;
+        self.run_isolated(script, source, Rc::new(RefCell::new(globals)))
+            .map_err(|e| self.nested_failure(e, true))
+    }
+
+    fn loaded_modules(&self) -> Vec<(String, ModuleMembers)> {
+        let mut modules: Vec<_> = self
+            .module_registry
+            .iter()
+            .filter(|(_, module)| !module.loading)
+            .map(|(path, module)| (path.clone(), module.members.clone()))
+            .collect();
+        modules.sort_by(|a, b| a.0.cmp(&b.0));
+        modules
+    }
 }
 
 impl VM {
@@ -1332,7 +2038,7 @@ impl VM {
         Self {
             stack: Vec::with_capacity(STACK_INIT),
             frames: Vec::with_capacity(FRAMES_INIT),
-            globals: Rc::new(RefCell::new(globals)),
+            globals: Rc::new(RefCell::new(globals.into())),
             file: String::new(),
             source: String::new(),
             exception_handlers: SmallVec::new(),
@@ -1343,10 +2049,21 @@ impl VM {
             pending_module_workspace: None,
             args: Vec::new(),
             namespace_context: Vec::new(),
+            precompiled_modules: FxHashMap::default(),
+            debugger_hook: None,
+            workspace: Rc::new(RefCell::new(Workspace::new())),
+            module_loader: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            modules: Vec::new(),
+            module_registry: FxHashMap::default(),
+            import_stack: Vec::new(),
+            pending_kwargs: None,
+            uncaught: None,
+            nested_error: None,
         }
     }
 
-    pub fn new_with_shared_globals(globals: Rc<RefCell<FxHashMap<String, Value>>>) -> Self {
+    pub fn new_with_shared_globals(globals: Rc<RefCell<Globals>>) -> Self {
         Self {
             stack: Vec::with_capacity(STACK_INIT),
             frames: Vec::with_capacity(FRAMES_INIT),
@@ -1361,6 +2078,17 @@ impl VM {
             pending_module_workspace: None,
             args: Vec::new(),
             namespace_context: Vec::new(),
+            precompiled_modules: FxHashMap::default(),
+            debugger_hook: None,
+            workspace: Rc::new(RefCell::new(Workspace::new())),
+            module_loader: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            modules: Vec::new(),
+            module_registry: FxHashMap::default(),
+            import_stack: Vec::new(),
+            pending_kwargs: None,
+            uncaught: None,
+            nested_error: None,
         }
     }
 
@@ -1376,15 +2104,67 @@ impl VM {
     pub fn set_args(&mut self, args: Vec<String>) {
         self.args = args;
     }
+    /// The value of the last throw nothing caught, once `run` has failed with it
+    pub fn uncaught(&self) -> Option<&Value> {
+        self.uncaught.as_ref()
+    }
     pub fn set_gc_stats_enabled(&mut self, enabled: bool) {
         self.gc_stats_enabled = enabled;
     }
+    /// Installs the hook run by `debugger` statements; without one they are no-ops.
+    pub fn set_debugger_hook(&mut self, hook: Option<DebuggerHook>) {
+        self.debugger_hook = hook;
+    }
+    /// Chunks compiled ahead of time (see `compiler::pipeline`), keyed by
+    /// canonical path. Imports of these files skip lexing and compiling.
+    pub fn set_precompiled_modules(&mut self, modules: FxHashMap<String, Chunk>) {
+        self.precompiled_modules = modules;
+    }
+    /// Sets the project root this VM resolves packages and relative paths
+    /// against, independently of other VMs.
+    pub fn set_project_root(&mut self, path: &std::path::Path) {
+        self.workspace.borrow_mut().set_project_root(path);
+    }
+    pub fn project_root(&self) -> Option<std::path::PathBuf> {
+        self.workspace
+            .borrow()
+            .project_root()
+            .map(|p| p.to_path_buf())
+    }
+    pub fn workspace(&self) -> Workspace {
+        self.workspace.borrow().clone()
+    }
+    pub fn set_workspace(&mut self, workspace: Workspace) {
+        *self.workspace.borrow_mut() = workspace;
+    }
+    /// Serves this VM's imports from `loader` instead of the filesystem.
+    pub fn set_module_loader(&mut self, loader: impl ModuleLoader + 'static) {
+        self.module_loader = Some(Rc::new(loader));
+    }
     pub fn get_globals(&self) -> FxHashMap<String, Value> {
-        self.globals.borrow().clone()
+        FxHashMap::clone(&self.globals.borrow())
     }
-    pub fn get_shared_globals(&self) -> Rc<RefCell<FxHashMap<String, Value>>> {
+    pub fn get_shared_globals(&self) -> Rc<RefCell<Globals>> {
         self.globals.clone()
     }
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().get(name).cloned()
+    }
+    pub fn set_global(&mut self, name: impl Into<String>, value: Value) {
+        self.globals.borrow_mut().insert(name.into(), value);
+    }
+    /// Serializes the globals defined so far (see `crate::snapshot`).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn snapshot(&self) -> Result<Vec<u8>, String> {
+        crate::snapshot::snapshot_globals(&self.globals.borrow())
+    }
+    /// Replaces all globals with the contents of a snapshot.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn restore_snapshot(&mut self, data: &[u8]) -> Result<(), String> {
+        let globals = crate::snapshot::restore_globals(data)?;
+        *self.globals.borrow_mut() = globals;
+        Ok(())
+    }
     pub fn gc_stats(&self) -> super::gc::GcStats {
         self.gc.get_stats()
     }
@@ -1419,19 +2199,43 @@ impl VM {
         }
     }
 
-    fn track_array(&mut self, arr: &Rc<RefCell<Vec<Value>>>) {
+    fn track_array(&mut self, arr: &Rc<RefCell<Array>>) {
         self.gc.track_array(arr);
+        self.profile_allocation();
         self.maybe_collect_garbage();
     }
-    fn track_dict(&mut self, dict: &Rc<RefCell<FxHashMap<String, Value>>>) {
+    fn track_dict(&mut self, dict: &Rc<RefCell<Dict>>) {
         self.gc.track_dict(dict);
+        self.profile_allocation();
         self.maybe_collect_garbage();
     }
     fn track_instance(&mut self, inst: &Rc<RefCell<Instance>>) {
         self.gc.track_instance(inst);
+        self.profile_allocation();
         self.maybe_collect_garbage();
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    #[inline(never)]
+    fn profile_step(&self) {
+        crate::vm::profiler::step(self.frames.len(), || {
+            self.frames
+                .iter()
+                .map(|frame| frame_label(&frame.function))
+                .collect()
+        });
+    }
+
+    #[inline(always)]
+    fn profile_allocation(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if crate::vm::profiler::is_active() {
+            if let Some(frame) = self.frames.last() {
+                crate::vm::profiler::record_allocation(&frame_label(&frame.function));
+            }
+        }
+    }
+
     #[inline(always)]
     fn peek(&self) -> Option<&Value> {
         self.stack.last()
@@ -1519,13 +2323,34 @@ impl VM {
 
     #[cfg(target_arch = "wasm32")]
     pub fn run(&mut self, chunk: &Chunk) -> SaldResult<Value> {
-        let mut main_function = Function::new("<script>", 0, chunk.clone());
+        match self.start(chunk)? {
+            Some(value) => Ok(value),
+            None => Err(self.create_error(
+                ErrorKind::RuntimeError,
+                "Awaiting a pending Future needs run_code_async",
+            )),
+        }
+    }
+
+    /// Starts running `chunk`. Returns `None` if the script is suspended on
+    /// an `await`; call `resume` once the awaited Future has settled.
+    #[cfg(target_arch = "wasm32")]
+    pub fn start(&mut self, chunk: &Chunk) -> SaldResult<Option<Value>> {
+        let main_function = Function::new("<script>", 0, chunk.clone());
         let main_function = Rc::new(main_function);
 
         let slots_start = self.stack.len();
         self.stack.push(Value::Null);
         self.frames.push(CallFrame::new(main_function, slots_start));
 
+        let _workspace = crate::workspace::enter(&self.workspace);
+        self.execute_until_complete()
+    }
+
+    /// Continues a script suspended by `start` or a previous `resume`.
+    #[cfg(target_arch = "wasm32")]
+    pub fn resume(&mut self) -> SaldResult<Option<Value>> {
+        let _workspace = crate::workspace::enter(&self.workspace);
         self.execute_until_complete()
     }
 
@@ -1552,6 +2377,7 @@ impl VM {
 
     #[cfg(not(target_arch = "wasm32"))]
     fn run_sync_loop(&mut self) -> SaldResult<Value> {
+        let _workspace = crate::workspace::enter(&self.workspace);
         loop {
             match self.execute_until_complete_native() {
                 ExecutionResult::Completed(value) => return Ok(value),
@@ -1583,14 +2409,15 @@ impl VM {
     }
 
     #[cfg(target_arch = "wasm32")]
-    fn execute_until_complete(&mut self) -> SaldResult<Value> {
+    fn execute_until_complete(&mut self) -> SaldResult<Option<Value>> {
         loop {
             if self.frames.is_empty() {
-                return Ok(self.stack.pop().unwrap_or(Value::Null));
+                return Ok(Some(self.stack.pop().unwrap_or(Value::Null)));
             }
             match self.execute_one_threaded() {
                 ControlFlow::Continue => continue,
-                ControlFlow::Return(v) => return Ok(v),
+                ControlFlow::Return(v) => return Ok(Some(v)),
+                ControlFlow::Suspend => return Ok(None),
                 ControlFlow::Error(e) => {
                     if !self.exception_handlers.is_empty() {
                         self.handle_native_error(e.message.clone())?;
@@ -1604,9 +2431,14 @@ impl VM {
 
     #[inline(always)]
     fn execute_one_threaded(&mut self) -> ControlFlow {
+        #[cfg(not(target_arch = "wasm32"))]
+        if crate::vm::profiler::is_active() {
+            self.profile_step();
+        }
+
         let op = self.read_byte();
 
-        if op < 69 {
+        if (op as usize) < DISPATCH.len() {
             unsafe { DISPATCH.get_unchecked(op as usize)(self) }
         } else {
             ControlFlow::Error(
@@ -1644,13 +2476,21 @@ impl VM {
         self.current_frame_mut().read_u16()
     }
 
+    fn is_const_global(&self, name: &str) -> bool {
+        self.globals.borrow().is_const(name)
+    }
+
     fn read_constant(&self, idx: usize) -> Value {
         let constant = &self.current_frame().function.chunk.constants[idx];
         match constant {
             Constant::Number(n) => Value::Number(*n),
             Constant::String(s) => Value::String(Rc::from(s.as_ref())),
-            Constant::Function(f) => Value::Function(Rc::new(Function::from_constant(f))),
+            // Function constants are loaded by Closure/Method, which report load errors
+            Constant::Function(f) => Function::from_constant(f)
+                .map_or(Value::Null, |function| Value::Function(Rc::new(function))),
             Constant::Class(c) => Value::Class(Rc::new(Class::new(&c.name))),
+            Constant::Date(n) => builtins::date::make_date(*n, builtins::date::DateKind::Date),
+            Constant::Time(n) => builtins::date::make_date(*n, builtins::date::DateKind::Time),
         }
     }
 
@@ -1727,6 +2567,111 @@ impl VM {
         Ok(new_count)
     }
 
+    /// Pops the top `arg_count` arguments for a native call. Named arguments
+    /// stay a trailing `NamedArgs` value, and are an error unless the native
+    /// declares that it takes them.
+    fn take_native_args(
+        &mut self,
+        arg_count: usize,
+        accepts_named: bool,
+    ) -> SaldResult<Vec<Value>> {
+        if !accepts_named && self.has_named_args(arg_count) {
+            if let Some(Value::NamedArgs(named)) = self.stack.last() {
+                let named = named.borrow();
+                let mut names: Vec<&String> = named.keys().collect();
+                names.sort();
+                let name = names.first().map(|name| name.as_str()).unwrap_or_default();
+                let message = format!("Unexpected named argument '{}'", name);
+                drop(named);
+                return Err(self.create_error(ErrorKind::TypeError, &message));
+            }
+        }
+        Ok(self.stack.drain(self.stack.len() - arg_count..).collect())
+    }
+
+    #[inline(always)]
+    fn has_named_args(&self, arg_count: usize) -> bool {
+        arg_count > 0 && matches!(self.stack.last(), Some(Value::NamedArgs(_)))
+    }
+
+    /// Replaces the trailing named arguments of a call to `function` with
+    /// positional ones, leaving the parameters before the last one given
+    /// as null so their defaults apply. Names matching no parameter are
+    /// stashed for the function's `**` parameter. Returns the new argument
+    /// count.
+    #[cold]
+    fn bind_named_args(&mut self, function: &Function, arg_count: usize) -> SaldResult<usize> {
+        if !self.has_named_args(arg_count) {
+            self.pending_kwargs = Some(Rc::default());
+            return Ok(arg_count);
+        }
+        let Some(Value::NamedArgs(named)) = self.stack.pop() else {
+            return Ok(arg_count);
+        };
+        let mut named = named.take();
+        let positional = arg_count - 1;
+        let offset = function.param_names.len().saturating_sub(function.arity);
+        let params = &function.param_names[offset..];
+        let fixed = if function.is_variadic {
+            params.len().saturating_sub(1)
+        } else {
+            params.len()
+        };
+        let required = fixed.saturating_sub(function.default_count);
+
+        let mut bound: Vec<Option<Value>> = Vec::new();
+        for (i, param) in params[..fixed].iter().enumerate() {
+            let Some(value) = named.remove(param) else {
+                continue;
+            };
+            if i < positional {
+                return Err(self.create_error(
+                    ErrorKind::ArgumentError,
+                    &format!(
+                        "Argument '{}' was given both by position and by name",
+                        param
+                    ),
+                ));
+            }
+            let slot = i - positional;
+            if bound.len() <= slot {
+                bound.resize(slot + 1, None);
+            }
+            bound[slot] = Some(value);
+        }
+
+        if !function.has_kwargs {
+            let mut unknown: Vec<&String> = named.keys().collect();
+            unknown.sort();
+            if let Some(name) = unknown.first() {
+                return Err(self.create_error(
+                    ErrorKind::ArgumentError,
+                    &format!(
+                        "Unexpected named argument '{}' for '{}'",
+                        name, function.name
+                    ),
+                ));
+            }
+        }
+        for (slot, value) in bound.iter().enumerate() {
+            let index = positional + slot;
+            if value.is_none() && index < required {
+                return Err(self.create_error(
+                    ErrorKind::ArgumentError,
+                    &format!("Missing argument '{}'", params[index]),
+                ));
+            }
+        }
+
+        if function.has_kwargs {
+            self.pending_kwargs = Some(Rc::new(RefCell::new(named)));
+        }
+        let count = positional + bound.len();
+        self.stack
+            .extend(bound.into_iter().map(|value| value.unwrap_or(Value::Null)));
+        Ok(count)
+    }
+
     fn call_value(&mut self, arg_count: usize) -> SaldResult<()> {
         let callee_idx = self.stack.len() - arg_count - 1;
         let callee = unsafe { self.stack.get_unchecked(callee_idx) };
@@ -1751,7 +2696,11 @@ impl VM {
             }
             Value::NativeFunction { func, .. } => {
                 let func = *func;
-                self.call_native(func, arg_count)
+                self.call_native(&func, arg_count)
+            }
+            Value::HostFunction { func, .. } => {
+                let func = func.clone();
+                self.call_native(&*func, arg_count)
             }
             Value::InstanceMethod {
                 receiver, method, ..
@@ -1765,6 +2714,42 @@ impl VM {
                 let method = method.clone();
                 self.call_bound_method(receiver, method, arg_count)
             }
+            // The given arguments go before the ones passed at the call
+            Value::Partial { callee, args } => {
+                let callee = (**callee).clone();
+                let args = args.clone();
+                self.stack[callee_idx] = callee;
+                let at = callee_idx + 1;
+                let _ = self.stack.splice(at..at, args.iter().cloned());
+                self.call_value(arg_count + args.len())
+            }
+            // Classes with a `__call__` method make their instances callable
+            Value::Instance(instance) => {
+                let class = instance.borrow().class.clone();
+                if let Some(Value::Function(func)) = class.methods.get("__call__").cloned() {
+                    return self.call_function_with_class(func, arg_count, class.name.clone());
+                }
+                let call = class.callable_native_instance_methods.get("__call__").copied();
+                let Some(call) = call else {
+                    return Err(self.create_error(
+                        ErrorKind::TypeError,
+                        &format!("'{}' is not callable", callee.type_name()),
+                    ));
+                };
+                let receiver = callee.clone();
+                let args = self.take_native_args(arg_count, class.takes_named_args("__call__"))?;
+                self.stack.pop();
+                match call(&receiver, &args, self) {
+                    Ok(result) => {
+                        self.stack.push(result);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        self.handle_native_error(e)?;
+                        Ok(())
+                    }
+                }
+            }
             _ => Err(self.create_error(
                 ErrorKind::TypeError,
                 &format!("'{}' is not callable", callee.type_name()),
@@ -1774,6 +2759,11 @@ impl VM {
 
     #[inline(always)]
     fn call_function(&mut self, function: Rc<Function>, arg_count: usize) -> SaldResult<()> {
+        let arg_count = if function.has_kwargs || self.has_named_args(arg_count) {
+            self.bind_named_args(&function, arg_count)?
+        } else {
+            arg_count
+        };
         // Check if async function - spawn to thread pool
         #[cfg(not(target_arch = "wasm32"))]
         if function.is_async {
@@ -1853,7 +2843,7 @@ impl VM {
         }
         variadic_args.reverse();
         self.stack
-            .push(Value::Array(Rc::new(RefCell::new(variadic_args))));
+            .push(Value::Array(Rc::new(RefCell::new(variadic_args.into()))));
         let effective_arg_count = min_arity + 1;
         if self.frames.len() >= FRAMES_MAX {
             return Err(self.create_error(
@@ -1873,7 +2863,7 @@ impl VM {
     fn spawn_async_function(&mut self, function: Rc<Function>, arg_count: usize) -> SaldResult<()> {
         use crate::vm::value::SendValue;
         use crossbeam_channel::bounded;
-        
+
         // Pop arguments from stack and convert to SendValue
         let mut args = Vec::with_capacity(arg_count);
         for _ in 0..arg_count {
@@ -1884,36 +2874,51 @@ impl VM {
             }
         }
         args.reverse();
-        
+        let kwargs = match self.pending_kwargs.take() {
+            Some(kwargs) => match SendValue::from_value(&Value::Dictionary(kwargs)) {
+                Ok(kwargs) => Some(kwargs),
+                Err(e) => return Err(self.create_error(ErrorKind::TypeError, &e)),
+            },
+            None => None,
+        };
+
         // Pop function slot (callee placeholder)
         self.stack.pop();
-        
+
         // Create channel for result
         let (tx, rx) = bounded::<Result<SendValue, String>>(1);
-        
+
         // Clone what we need for the worker thread
         let chunk = function.chunk.clone();
         let arity = function.arity;
         let func_name = function.name.clone();
-        
+        let workspace = self.workspace();
+
         // Spawn to rayon thread pool
         rayon::spawn(move || {
             // Create isolated VM for this worker
             let mut worker_vm = VM::new();
-            
+            worker_vm.set_workspace(workspace);
+            let _workspace = crate::workspace::enter(&worker_vm.workspace);
+
             // Create function and push to stack
             let worker_func = Rc::new(Function::new(&func_name, arity, chunk));
             worker_vm.stack.push(Value::Null); // placeholder
-            
+
             // Push args
             for arg in args {
                 worker_vm.stack.push(arg.to_value());
             }
-            
+            if let Some(Value::Dictionary(kwargs)) = kwargs.map(|kwargs| kwargs.to_value()) {
+                worker_vm.pending_kwargs = Some(kwargs);
+            }
+
             // Set up call frame
             let slots_start = 0;
-            worker_vm.frames.push(CallFrame::new(worker_func, slots_start));
-            
+            worker_vm
+                .frames
+                .push(CallFrame::new(worker_func, slots_start));
+
             // Execute until complete
             let result = loop {
                 if worker_vm.frames.is_empty() {
@@ -1925,7 +2930,7 @@ impl VM {
                     ControlFlow::Error(e) => break Err(e.message),
                 }
             };
-            
+
             // Send result back
             let send_result = match result {
                 Ok(val) => SendValue::from_value(&val),
@@ -1933,11 +2938,11 @@ impl VM {
             };
             let _ = tx.send(send_result);
         });
-        
+
         // Push Future with receiver
         let future = Value::Future(Rc::new(RefCell::new(Some(rx))));
         self.stack.push(future);
-        
+
         Ok(())
     }
 
@@ -1947,6 +2952,11 @@ impl VM {
         arg_count: usize,
         class_name: String,
     ) -> SaldResult<()> {
+        let arg_count = if function.has_kwargs || self.has_named_args(arg_count) {
+            self.bind_named_args(&function, arg_count)?
+        } else {
+            arg_count
+        };
         if !function.is_variadic {
             let required_arity = function.arity.saturating_sub(function.default_count);
             if arg_count < required_arity {
@@ -2006,7 +3016,7 @@ impl VM {
         }
         variadic_args.reverse();
         self.stack
-            .push(Value::Array(Rc::new(RefCell::new(variadic_args))));
+            .push(Value::Array(Rc::new(RefCell::new(variadic_args.into()))));
         let effective_arg_count = min_arity + 1;
         if self.frames.len() >= FRAMES_MAX {
             return Err(self.create_error(
@@ -2023,8 +3033,21 @@ impl VM {
         Ok(())
     }
     fn call_class(&mut self, class: Rc<Class>, arg_count: usize) -> SaldResult<()> {
-        if let Some(constructor) = class.constructor {
-            let args: Vec<Value> = self.stack.drain(self.stack.len() - arg_count..).collect();
+        if let Some(constructor) = class.callable_constructor {
+            let args = self.take_native_args(arg_count, false)?;
+            self.stack.pop();
+            match constructor(&args, self) {
+                Ok(result) => {
+                    self.stack.push(result);
+                    Ok(())
+                }
+                Err(e) => {
+                    self.handle_native_error(e)?;
+                    Ok(())
+                }
+            }
+        } else if let Some(constructor) = class.constructor {
+            let args = self.take_native_args(arg_count, false)?;
             self.stack.pop();
             match constructor(&args) {
                 Ok(result) => {
@@ -2063,10 +3086,10 @@ impl VM {
 
     fn call_native(
         &mut self,
-        func: fn(&[Value]) -> Result<Value, String>,
+        func: &dyn Fn(&[Value]) -> Result<Value, String>,
         arg_count: usize,
     ) -> SaldResult<()> {
-        let args: Vec<Value> = self.stack.drain(self.stack.len() - arg_count..).collect();
+        let args = self.take_native_args(arg_count, false)?;
         self.stack.pop();
         match func(&args) {
             Ok(result) => {
@@ -2086,7 +3109,7 @@ impl VM {
         method: fn(&Value, &[Value]) -> Result<Value, String>,
         arg_count: usize,
     ) -> SaldResult<()> {
-        let args: Vec<Value> = self.stack.drain(self.stack.len() - arg_count..).collect();
+        let args = self.take_native_args(arg_count, false)?;
         self.stack.pop();
         match method(&receiver, &args) {
             Ok(result) => {
@@ -2122,6 +3145,11 @@ impl VM {
         instance: Value,
         class_name: String,
     ) -> SaldResult<()> {
+        let arg_count = if function.has_kwargs || self.has_named_args(arg_count) {
+            self.bind_named_args(&function, arg_count)?
+        } else {
+            arg_count
+        };
         let required_arity = function.arity.saturating_sub(function.default_count);
         if arg_count < required_arity {
             return Err(self.create_error(
@@ -2199,8 +3227,7 @@ impl VM {
                 if let Some(callable_method) =
                     class.callable_native_instance_methods.get(name).copied()
                 {
-                    let args: Vec<Value> =
-                        self.stack.drain(self.stack.len() - arg_count..).collect();
+                    let args = self.take_native_args(arg_count, class.takes_named_args(name))?;
                     self.stack.pop();
                     match callable_method(&receiver, &args, self) {
                         Ok(result) => {
@@ -2214,8 +3241,7 @@ impl VM {
                     }
                 }
                 if let Some(method) = class.native_instance_methods.get(name).copied() {
-                    let args: Vec<Value> =
-                        self.stack.drain(self.stack.len() - arg_count..).collect();
+                    let args = self.take_native_args(arg_count, class.takes_named_args(name))?;
                     self.stack.pop();
                     match method(&receiver, &args) {
                         Ok(result) => {
@@ -2228,6 +3254,38 @@ impl VM {
                         }
                     }
                 }
+                // `__missing_method__(name, args)` handles the call itself
+                let missing = class.methods.get("__missing_method__").cloned();
+                if let Some(Value::Function(func)) = missing {
+                    let mut args: Vec<Value> =
+                        self.stack.drain(self.stack.len() - arg_count..).collect();
+                    if let Some(Value::NamedArgs(named)) = args.last() {
+                        let named = Value::Dictionary(named.clone());
+                        *args.last_mut().unwrap() = named;
+                    }
+                    self.stack.push(Value::String(Rc::from(name)));
+                    self.stack.push(Value::Array(Rc::new(RefCell::new(args.into()))));
+                    return self.call_function_with_class(func, 2, class.name.clone());
+                }
+                // `__getattr__(name)` supplies a value that is then called
+                if let Some(Value::Function(func)) = class.methods.get("__getattr__").cloned() {
+                    let getattr = Value::BoundMethod {
+                        receiver: Box::new(receiver.clone()),
+                        method: func,
+                    };
+                    let callee = match self.call_hook(&getattr, vec![Value::String(Rc::from(name))])
+                    {
+                        Ok(callee) => callee,
+                        Err(e) => {
+                            self.stack.truncate(self.stack.len() - arg_count - 1);
+                            self.handle_native_error(e)?;
+                            return Ok(());
+                        }
+                    };
+                    let stack_idx = self.stack.len() - arg_count - 1;
+                    self.stack[stack_idx] = callee;
+                    return self.call_value(arg_count);
+                }
                 Err(self.create_error(
                     ErrorKind::AttributeError,
                     &format!("Undefined method '{}' on instance", name),
@@ -2251,9 +3309,22 @@ impl VM {
                         return self.call_function_with_class(func, arg_count, class.name.clone());
                     }
                 }
+                if let Some(callable_fn) = class.callable_native_static_methods.get(name).copied() {
+                    let args = self.take_native_args(arg_count, class.takes_named_args(name))?;
+                    self.stack.pop();
+                    match callable_fn(&args, self) {
+                        Ok(result) => {
+                            self.stack.push(result);
+                            return Ok(());
+                        }
+                        Err(e) => {
+                            self.handle_native_error(e)?;
+                            return Ok(());
+                        }
+                    }
+                }
                 if let Some(native_fn) = class.native_static_methods.get(name).copied() {
-                    let args: Vec<Value> =
-                        self.stack.drain(self.stack.len() - arg_count..).collect();
+                    let args = self.take_native_args(arg_count, class.takes_named_args(name))?;
                     self.stack.pop();
                     match native_fn(&args) {
                         Ok(result) => {
@@ -2276,7 +3347,13 @@ impl VM {
             | Value::Boolean(_)
             | Value::Null
             | Value::Array(_)
-            | Value::Dictionary(_) => {
+            | Value::Dictionary(_)
+            | Value::Function(_)
+            | Value::NativeFunction { .. }
+            | Value::HostFunction { .. }
+            | Value::InstanceMethod { .. }
+            | Value::BoundMethod { .. }
+            | Value::Partial { .. } => {
                 let class_name = builtins::get_builtin_class_name(&receiver);
                 let class =
                     if let Some(Value::Class(c)) = self.globals.borrow().get(class_name).cloned() {
@@ -2290,8 +3367,7 @@ impl VM {
                 if let Some(callable_method) =
                     class.callable_native_instance_methods.get(name).copied()
                 {
-                    let args: Vec<Value> =
-                        self.stack.drain(self.stack.len() - arg_count..).collect();
+                    let args = self.take_native_args(arg_count, class.takes_named_args(name))?;
                     self.stack.pop();
                     match callable_method(&receiver, &args, self) {
                         Ok(result) => {
@@ -2305,8 +3381,7 @@ impl VM {
                     }
                 }
                 if let Some(method) = class.native_instance_methods.get(name).copied() {
-                    let args: Vec<Value> =
-                        self.stack.drain(self.stack.len() - arg_count..).collect();
+                    let args = self.take_native_args(arg_count, class.takes_named_args(name))?;
                     self.stack.pop();
                     match method(&receiver, &args) {
                         Ok(result) => {
@@ -2396,10 +3471,22 @@ impl VM {
                 if let Some(value) = inst_guard.fields.get(name).cloned() {
                     drop(inst_guard);
                     self.stack.push(value);
-                } else if let Some(method) = inst_guard.class.methods.get(name).cloned() {
+                } else if let Some(Value::Function(method)) =
+                    inst_guard.class.methods.get(name).cloned()
+                {
+                    drop(inst_guard);
+                    self.stack.push(Value::BoundMethod {
+                        receiver: Box::new(Value::Instance(instance.clone())),
+                        method,
+                    });
+                } else if let Some(Value::Function(getattr)) =
+                    inst_guard.class.methods.get("__getattr__").cloned()
+                {
+                    // `__getattr__(name)` computes properties the instance lacks
                     drop(inst_guard);
                     self.stack.push(Value::Instance(instance.clone()));
-                    self.stack.push(method);
+                    self.stack.push(Value::String(Rc::from(name)));
+                    return self.call_function_with_class(getattr, 1, class_name);
                 } else {
                     drop(inst_guard);
                     return Err(self.create_error(
@@ -2435,12 +3522,28 @@ impl VM {
                     ));
                 }
             }
+            Value::Function(_)
+            | Value::NativeFunction { .. }
+            | Value::HostFunction { .. }
+            | Value::InstanceMethod { .. }
+            | Value::BoundMethod { .. }
+            | Value::Partial { .. }
+                if builtins::function::is_property(name) =>
+            {
+                self.stack.push(builtins::function::property(&obj, name));
+            }
             Value::String(_)
             | Value::Number(_)
             | Value::Boolean(_)
             | Value::Null
             | Value::Array(_)
-            | Value::Dictionary(_) => {
+            | Value::Dictionary(_)
+            | Value::Function(_)
+            | Value::NativeFunction { .. }
+            | Value::HostFunction { .. }
+            | Value::InstanceMethod { .. }
+            | Value::BoundMethod { .. }
+            | Value::Partial { .. } => {
                 let class_name = builtins::get_builtin_class_name(&obj);
                 let method_result = {
                     let globals_guard = self.globals.borrow();
@@ -2518,6 +3621,12 @@ impl VM {
     fn handle_set_property(&mut self, name: &str) -> SaldResult<()> {
         let value = self.stack.pop().unwrap_or(Value::Null);
         let obj = self.stack.pop().unwrap_or(Value::Null);
+        if builtins::is_frozen(&obj) {
+            return Err(self.create_error(
+                ErrorKind::TypeError,
+                &format!("Cannot set '{}' on a frozen instance", name),
+            ));
+        }
         if let Value::Instance(instance) = obj {
             let class_name = {
                 let mut guard = instance.borrow_mut();
@@ -2550,6 +3659,16 @@ impl VM {
     fn handle_get_index(&mut self) -> SaldResult<()> {
         let index = self.stack.pop().unwrap_or(Value::Null);
         let object = self.stack.pop().unwrap_or(Value::Null);
+        if let Value::Number(idx) = index {
+            if matches!(object, Value::Array(_) | Value::String(_))
+                && (idx.fract() != 0.0 || idx < 0.0)
+            {
+                let message = format!("Index {} truncated to {}", idx, idx as usize);
+                if !self.warn(WarningKind::LossyConversion, &message)? {
+                    return Ok(());
+                }
+            }
+        }
         match (&object, &index) {
             (Value::Array(arr), Value::Number(idx)) => {
                 let idx = *idx as usize;
@@ -2587,6 +3706,33 @@ impl VM {
                 let value = dict.borrow().get(&**key).cloned().unwrap_or(Value::Null);
                 self.stack.push(value);
             }
+            (Value::Dictionary(dict), Value::Instance(_)) => match self.dict_slot(dict, &index) {
+                Some(Ok(slot)) => {
+                    let value = dict.borrow().get_slot(&slot).cloned().unwrap_or(Value::Null);
+                    self.stack.push(value);
+                }
+                Some(Err(e)) => self.handle_native_error(e)?,
+                None => {
+                    return Err(self.create_error(
+                        ErrorKind::TypeError,
+                        "Dictionary keys must be strings or define __hash__",
+                    ))
+                }
+            },
+            // Native classes with `at` (Set, Map) index by position, as for-in does
+            (Value::Instance(inst), Value::Number(_))
+                if inst
+                    .borrow()
+                    .class
+                    .native_instance_methods
+                    .contains_key("at") =>
+            {
+                let at = inst.borrow().class.native_instance_methods["at"];
+                match at(&object, std::slice::from_ref(&index)) {
+                    Ok(value) => self.stack.push(value),
+                    Err(e) => self.handle_native_error(e)?,
+                }
+            }
             _ => {
                 return Err(self.create_error(
                     ErrorKind::TypeError,
@@ -2605,6 +3751,12 @@ impl VM {
         let value = self.stack.pop().unwrap_or(Value::Null);
         let index = self.stack.pop().unwrap_or(Value::Null);
         let object = self.stack.pop().unwrap_or(Value::Null);
+        if builtins::is_frozen(&object) {
+            return Err(self.create_error(
+                ErrorKind::TypeError,
+                &format!("Cannot modify a frozen {}", object.type_name()),
+            ));
+        }
         match (&object, &index) {
             (Value::Array(arr), Value::Number(idx)) => {
                 let idx = *idx as usize;
@@ -2627,6 +3779,19 @@ impl VM {
                 dict.borrow_mut().insert(key.to_string(), value.clone());
                 self.stack.push(value);
             }
+            (Value::Dictionary(dict), Value::Instance(_)) => match self.dict_slot(dict, &index) {
+                Some(Ok(slot)) => {
+                    dict.borrow_mut().insert_slot(slot, &index, value.clone());
+                    self.stack.push(value);
+                }
+                Some(Err(e)) => self.handle_native_error(e)?,
+                None => {
+                    return Err(self.create_error(
+                        ErrorKind::TypeError,
+                        "Dictionary keys must be strings or define __hash__",
+                    ))
+                }
+            },
             _ => {
                 return Err(self.create_error(
                     ErrorKind::TypeError,
@@ -2643,8 +3808,7 @@ impl VM {
 
     fn handle_build_dict(&mut self) -> SaldResult<()> {
         let count = self.read_u16() as usize;
-        let mut map: FxHashMap<String, Value> =
-            FxHashMap::with_capacity_and_hasher(count, Default::default());
+        let map = Rc::new(RefCell::new(Dict::with_capacity(count)));
         let mut pairs = Vec::with_capacity(count);
         for _ in 0..count {
             let value = self.stack.pop().unwrap_or(Value::Null);
@@ -2655,8 +3819,10 @@ impl VM {
         for (key, value) in pairs {
             if let (Value::Null, Value::SpreadMarker(spread_value)) = (&key, &value) {
                 if let Value::Dictionary(dict) = spread_value.as_ref() {
-                    for (k, v) in dict.borrow().iter() {
-                        map.insert(k.clone(), v.clone());
+                    let dict = dict.borrow();
+                    let mut map = map.borrow_mut();
+                    for (k, v) in dict.iter() {
+                        map.insert_entry(k, &dict.key(k), v.clone());
                     }
                 } else {
                     return Err(self.create_error(
@@ -2665,19 +3831,31 @@ impl VM {
                     ));
                 }
             } else {
-                let key_str = match key {
-                    Value::String(s) => s.to_string(),
+                let slot = match key {
+                    Value::String(ref s) => DictSlot::Entry(s.to_string()),
+                    Value::Instance(_) => match self.dict_slot(&map, &key) {
+                        Some(Ok(slot)) => slot,
+                        Some(Err(e)) => {
+                            self.handle_native_error(e)?;
+                            return Ok(());
+                        }
+                        None => {
+                            return Err(self.create_error(
+                                ErrorKind::TypeError,
+                                "Dictionary keys must be strings or define __hash__",
+                            ))
+                        }
+                    },
                     _ => {
                         return Err(self
                             .create_error(ErrorKind::TypeError, "Dictionary keys must be strings"))
                     }
                 };
-                map.insert(key_str, value);
+                map.borrow_mut().insert_slot(slot, &key, value);
             }
         }
-        let dict = Rc::new(RefCell::new(map));
-        self.track_dict(&dict);
-        self.stack.push(Value::Dictionary(dict));
+        self.track_dict(&map);
+        self.stack.push(Value::Dictionary(map));
         Ok(())
     }
 
@@ -2710,7 +3888,7 @@ impl VM {
         }
         self.stack.push(Value::Namespace {
             name: String::new(),
-            members: Rc::new(RefCell::new(members)),
+            members: Rc::new(RefCell::new(members.into())),
             module_globals: None,
         });
         Ok(())
@@ -2781,7 +3959,7 @@ impl VM {
                 }
             }
         }
-        let arr = Rc::new(RefCell::new(elements));
+        let arr = Rc::new(RefCell::new(elements.into()));
         self.track_array(&arr);
         self.stack.push(Value::Array(arr));
         Ok(())
@@ -2849,172 +4027,271 @@ impl VM {
         ))
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
     fn handle_import(&mut self, import_path: &str) -> SaldResult<()> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(name) = import_path.strip_prefix(crate::native_module::NATIVE_IMPORT_PREFIX) {
+            let members = crate::native_module::import_native_module(name)
+                .map_err(|e| self.create_error(ErrorKind::ImportError, &e))?;
+            self.globals.borrow_mut().extend(members);
+            return Ok(());
+        }
         let resolved_path = self.resolve_import_path(import_path)?;
-        let module_workspace = self.pending_module_workspace.take();
-        if let Some(ref workspace) = module_workspace {
-            crate::push_module_workspace(workspace);
+        let module = self.load_module(&resolved_path)?;
+        if module.loading {
+            return Err(self.circular_import_error(&resolved_path));
         }
-        let imported_globals = self.import_and_execute(&resolved_path)?;
-        if module_workspace.is_some() {
-            crate::pop_module_workspace();
-        }
-        for (name, value) in imported_globals {
-            let globals_guard = self.globals.borrow();
-            let should_insert = !globals_guard.contains_key(&name)
-                || !matches!(globals_guard.get(&name), Some(Value::Class(_)));
-            drop(globals_guard);
-            if should_insert {
-                self.globals.borrow_mut().insert(name, value);
+        let imported_globals = module.globals.borrow().clone();
+        let mut names = Vec::new();
+        for (name, value) in imported_globals.iter() {
+            let mut globals = self.globals.borrow_mut();
+            let value = match globals.get(name) {
+                Some(Value::Class(_)) => continue,
+                // Partial namespaces from several files combine
+                Some(existing @ Value::Namespace { .. }) => {
+                    merge_namespaces(existing, value.clone())
+                }
+                _ => value.clone(),
+            };
+            if imported_globals.is_const(name) {
+                globals.set_const(name.clone());
             }
+            names.push(name.clone());
+            globals.insert(name.clone(), value);
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.track_module(ModuleBinding {
+            path: resolved_path,
+            target: Rc::downgrade(&self.globals),
+            module_globals: None,
+            names,
+        });
         Ok(())
     }
 
-    #[cfg(target_arch = "wasm32")]
-    fn handle_import(&mut self, import_path: &str) -> SaldResult<()> {
-        Err(self.create_error(
-            ErrorKind::ImportError,
-            &format!(
-                "import is not supported in WASM playground: {}",
-                import_path
-            ),
-        ))
+    fn handle_import_as(&mut self, import_path: &str, alias: &str) -> SaldResult<()> {
+        let namespace = self.import_namespace(import_path, alias)?;
+        self.globals
+            .borrow_mut()
+            .insert(alias.to_string(), namespace);
+        Ok(())
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    fn handle_import_as(&mut self, import_path: &str, alias: &str) -> SaldResult<()> {
-        let resolved_path = self.resolve_import_path(import_path)?;
-        let module_workspace = self.pending_module_workspace.take();
-        if let Some(ref workspace) = module_workspace {
-            crate::push_module_workspace(workspace);
+    /// The namespace `import ... as alias` binds.
+    fn import_namespace(&mut self, import_path: &str, alias: &str) -> SaldResult<Value> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(name) = import_path.strip_prefix(crate::native_module::NATIVE_IMPORT_PREFIX) {
+            let members = crate::native_module::import_native_module(name)
+                .map_err(|e| self.create_error(ErrorKind::ImportError, &e))?;
+            return Ok(Value::Namespace {
+                name: alias.to_string(),
+                members: Rc::new(RefCell::new(members.into())),
+                module_globals: None,
+            });
         }
+        let resolved_path = self.resolve_import_path(import_path)?;
+        let module = self.load_module(&resolved_path)?;
+        let members = module.members;
+        let module_globals_rc = module.globals;
+        #[cfg(not(target_arch = "wasm32"))]
+        self.track_module(ModuleBinding {
+            path: resolved_path,
+            target: Rc::downgrade(&members),
+            module_globals: Some(Rc::downgrade(&module_globals_rc)),
+            names: Vec::new(),
+        });
 
-        let (imported_globals, module_globals_rc) =
-            self.import_and_execute_with_globals(&resolved_path)?;
+        Ok(Value::Namespace {
+            name: alias.to_string(),
+            members,
+            module_globals: Some(module_globals_rc),
+        })
+    }
 
-        if module_workspace.is_some() {
-            crate::pop_module_workspace();
-        }
+    /// Module globals exposed through an `import ... as` namespace.
+    fn module_fields(globals: FxHashMap<String, Value>) -> FxHashMap<String, Value> {
         let mut module_fields = FxHashMap::default();
-        for (name, value) in imported_globals {
+        for (name, value) in globals {
             if !matches!(&value, Value::Class(c) if ["String", "Number", "Boolean", "Null", "Array"].contains(&c.name.as_str()))
             {
                 module_fields.insert(name, value);
             }
         }
-
-        self.globals.borrow_mut().insert(
-            alias.to_string(),
-            Value::Namespace {
-                name: alias.to_string(),
-                members: Rc::new(RefCell::new(module_fields)),
-                module_globals: Some(module_globals_rc),
-            },
-        );
-        Ok(())
+        module_fields
     }
 
-    #[cfg(target_arch = "wasm32")]
-    fn handle_import_as(&mut self, import_path: &str, _alias: &str) -> SaldResult<()> {
-        Err(self.create_error(
-            ErrorKind::ImportError,
-            &format!(
-                "import is not supported in WASM playground: {}",
-                import_path
-            ),
-        ))
+    #[cfg(not(target_arch = "wasm32"))]
+    fn track_module(&mut self, binding: ModuleBinding) {
+        crate::vm::reload::track(&binding.path);
+        self.modules.retain(|m| m.target.strong_count() > 0);
+        self.modules.push(binding);
     }
 
+    /// Paths of the modules imported so far.
     #[cfg(not(target_arch = "wasm32"))]
-    fn resolve_import_path(&mut self, import_path: &str) -> SaldResult<String> {
-        if Self::is_module_import(import_path) {
-            return self.resolve_module_import(import_path);
+    pub fn imported_modules(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.modules.iter().map(|m| m.path.clone()).collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Re-runs a changed module and swaps the new code into every place it
+    /// was imported. Functions, classes and other definitions are replaced;
+    /// existing top-level data such as counters or caches keeps its current
+    /// value, and names new to the module are added. Returns the number of
+    /// imports updated.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_module(&mut self, path: &str) -> Result<usize, String> {
+        let path = std::fs::canonicalize(path)
+            .ok()
+            .and_then(|p| p.to_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| path.to_string());
+        self.modules.retain(|m| m.target.strong_count() > 0);
+        let indices: Vec<usize> = (0..self.modules.len())
+            .filter(|&i| self.modules[i].path == path)
+            .collect();
+        if indices.is_empty() {
+            return Err(format!("Module '{}' has not been imported", path));
+        }
+
+        // The chunk precompiled at startup is the code being replaced
+        self.precompiled_modules.remove(&path);
+        let (fresh, fresh_module_globals) = self
+            .import_and_execute_with_globals(&path)
+            .map_err(|e| e.to_string())?;
+        let fresh_module_globals = fresh_module_globals.borrow().clone();
+        let fresh_fields = Self::module_fields(fresh.clone());
+        if let Some(module) = self.module_registry.get(&path) {
+            merge_reloaded(&mut module.globals.borrow_mut(), &fresh_module_globals);
+            merge_reloaded(&mut module.members.borrow_mut(), &fresh_fields);
         }
-        self.resolve_file_import(import_path)
+
+        for &i in &indices {
+            let binding = &mut self.modules[i];
+            let Some(target) = binding.target.upgrade() else {
+                continue;
+            };
+            let mut target = target.borrow_mut();
+            match binding.module_globals.as_ref().and_then(Weak::upgrade) {
+                Some(module_globals) => {
+                    merge_reloaded(&mut module_globals.borrow_mut(), &fresh_module_globals);
+                    merge_reloaded(&mut target, &fresh_fields);
+                }
+                None => {
+                    for (name, value) in &fresh {
+                        if binding.names.contains(name) {
+                            merge_reloaded_value(&mut target, name, value);
+                        } else if !target.contains_key(name) {
+                            binding.names.push(name.clone());
+                            target.insert(name.clone(), value.clone());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(indices.len())
     }
 
+    /// Reloads the modules the watcher reported as changed. Errors are
+    /// reported without stopping the program so it keeps the old code.
     #[cfg(not(target_arch = "wasm32"))]
-    fn is_module_import(path: &str) -> bool {
-        !path.contains('/')
-            && !path.contains('\\')
-            && !path.ends_with(".sald")
-            && !path.ends_with(".saldc")
+    fn apply_pending_reloads(&mut self) {
+        for path in crate::vm::reload::take_changed() {
+            if !self.modules.iter().any(|m| m.path == path) {
+                continue;
+            }
+            match self.reload_module(&path) {
+                Ok(_) => eprintln!("[reload] {}", path),
+                Err(e) => eprintln!("[reload] {} failed:\n{}", path, e),
+            }
+        }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    fn resolve_module_import(&mut self, module_name: &str) -> SaldResult<String> {
-        let project_root = crate::get_project_root().ok_or_else(|| {
-            self.create_error(
-                ErrorKind::ImportError,
-                &format!(
-                    "Cannot import module '{}': no project root set",
-                    module_name
-                ),
-            )
-        })?;
-        let module_dir = project_root.join("sald_modules").join(module_name);
-        if !module_dir.exists() {
-            return Err(self.create_error(
-                ErrorKind::ImportError,
-                &format!("Module '{}' not found in sald_modules/", module_name),
-            ));
+    fn resolve_import_path(&mut self, import_path: &str) -> SaldResult<String> {
+        use crate::package::{is_bare_specifier, ResolveError};
+        if let Some(loader) = self.module_loader.clone() {
+            return self.resolve_with_loader(loader.as_ref(), import_path);
         }
-        let config_path = module_dir.join("salad.json");
-        if !config_path.exists() {
-            return Err(self.create_error(
-                ErrorKind::ImportError,
-                &format!("Module '{}' has no salad.json config", module_name),
-            ));
+        if !is_bare_specifier(import_path) {
+            return self.resolve_file_import(import_path);
         }
-        let main_entry = self.parse_module_config(&config_path, module_name)?;
-        let main_path = module_dir.join(&main_entry);
-        if !main_path.exists() {
-            return Err(self.create_error(
-                ErrorKind::ImportError,
-                &format!(
-                    "Module '{}' main file '{}' not found",
-                    module_name, main_entry
-                ),
-            ));
+        if !import_path.contains(['/', '\\']) {
+            return self.resolve_module_import(import_path);
+        }
+        // "lib/util" may be a file next to the importer or a file in package `lib`
+        let file = self.resolve_file_import(import_path)?;
+        if std::path::Path::new(&file).exists() {
+            return Ok(file);
+        }
+        match self.locate_module(import_path) {
+            Err(ResolveError::NotFound { .. }) => Ok(file),
+            _ => self.resolve_module_import(import_path),
         }
-        self.pending_module_workspace = Some(module_dir);
-        main_path.to_str().map(|s| s.to_string()).ok_or_else(|| {
-            self.create_error(
-                ErrorKind::ImportError,
-                &format!("Invalid module path for '{}'", module_name),
-            )
-        })
     }
 
-    #[cfg(not(target_arch = "wasm32"))]
-    fn parse_module_config(
+    #[cfg(target_arch = "wasm32")]
+    fn resolve_import_path(&mut self, import_path: &str) -> SaldResult<String> {
+        match self.module_loader.clone() {
+            Some(loader) => self.resolve_with_loader(loader.as_ref(), import_path),
+            None => Err(self
+                .create_error(
+                    ErrorKind::ImportError,
+                    &format!(
+                        "import is not supported without a module loader: {}",
+                        import_path
+                    ),
+                )
+                .with_help("Install one with VM::set_module_loader")),
+        }
+    }
+
+    fn resolve_with_loader(
         &self,
-        config_path: &std::path::Path,
-        module_name: &str,
+        loader: &dyn ModuleLoader,
+        specifier: &str,
     ) -> SaldResult<String> {
-        let content = std::fs::read_to_string(config_path).map_err(|e| {
-            self.create_error(
-                ErrorKind::ImportError,
-                &format!("Failed to read module '{}' config: {}", module_name, e),
-            )
+        loader
+            .resolve(specifier, &self.importer())
+            .map_err(|e| self.create_error(ErrorKind::ImportError, &e))
+    }
+
+    /// File of the code running the import
+    fn importer(&self) -> String {
+        if self.frames.is_empty() || self.current_frame().function.file.is_empty() {
+            self.file.clone()
+        } else {
+            self.current_frame().function.file.clone()
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn locate_module(
+        &self,
+        specifier: &str,
+    ) -> Result<crate::package::ResolvedModule, crate::package::ResolveError> {
+        let importer = self.importer();
+        let workspace = self.workspace.borrow();
+        let from_dir = std::path::Path::new(&importer)
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| workspace.current_dir());
+        crate::package::resolve_module(workspace.project_root(), &from_dir, specifier)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn resolve_module_import(&mut self, specifier: &str) -> SaldResult<String> {
+        let module = self.locate_module(specifier).map_err(|e| {
+            self.create_error(ErrorKind::ImportError, &e.to_string())
+                .with_help(e.help())
         })?;
-        let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| {
+        self.pending_module_workspace = Some(module.dir);
+        module.entry.to_str().map(|s| s.to_string()).ok_or_else(|| {
             self.create_error(
                 ErrorKind::ImportError,
-                &format!("Module '{}' config invalid JSON: {}", module_name, e),
+                &format!("Invalid module path for '{}'", specifier),
             )
-        })?;
-        json.get("main")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .ok_or_else(|| {
-                self.create_error(
-                    ErrorKind::ImportError,
-                    &format!("Module '{}' config missing 'main' field", module_name),
-                )
-            })
+        })
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -3080,169 +4357,228 @@ impl VM {
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    fn import_and_execute(&mut self, path: &str) -> SaldResult<FxHashMap<String, Value>> {
-        let chunk = if path.ends_with(".saldc") {
-            let data = std::fs::read(path).map_err(|e| {
-                self.create_error(
-                    ErrorKind::ImportError,
-                    &format!("Cannot read import file '{}': {}", path, e),
-                )
-            })?;
-            crate::binary::deserialize(&data).map_err(|e| {
-                self.create_error(
-                    ErrorKind::ImportError,
-                    &format!("Error deserializing import '{}': {}", path, e),
-                )
-            })?
-        } else {
-            let source = std::fs::read_to_string(path).map_err(|e| {
-                self.create_error(
-                    ErrorKind::ImportError,
-                    &format!("Cannot read import file '{}': {}", path, e),
-                )
-            })?;
-            let mut scanner = Scanner::new(&source, path);
-            let tokens = scanner.scan_tokens().map_err(|e| {
-                self.create_error(
-                    ErrorKind::SyntaxError,
-                    &format!("Error scanning import '{}': {}", path, e),
-                )
-            })?;
-            let mut parser = Parser::new(tokens, path, &source);
-            let program = parser.parse().map_err(|e| {
-                self.create_error(
-                    ErrorKind::SyntaxError,
-                    &format!("Error parsing import '{}': {}", path, e),
-                )
-            })?;
-            let mut compiler = Compiler::new(path, &source);
-            compiler.compile(&program).map_err(|e| {
-                self.create_error(
-                    ErrorKind::SyntaxError,
-                    &format!("Error compiling import '{}': {}", path, e),
-                )
-            })?
+    fn precompiled_chunk(&self, path: &str) -> Option<Chunk> {
+        if self.precompiled_modules.is_empty() {
+            return None;
+        }
+        let canonical = std::fs::canonicalize(path).ok()?;
+        self.precompiled_modules.get(canonical.to_str()?).cloned()
+    }
+
+    /// Registry key of a resolved import: loader ids are used as they are,
+    /// files by canonical path.
+    fn module_key(&self, path: &str) -> String {
+        if self.module_loader.is_some() {
+            return path.to_string();
+        }
+        std::fs::canonicalize(path)
+            .ok()
+            .and_then(|p| p.to_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| path.to_string())
+    }
+
+    /// Evaluates an imported file once per VM and shares the result with
+    /// every later import of the same canonical path. A module that is
+    /// already being evaluated is returned as is, still marked `loading`.
+    fn load_module(&mut self, path: &str) -> SaldResult<LoadedModule> {
+        let module_workspace = self.pending_module_workspace.take();
+        let key = self.module_key(path);
+        if let Some(module) = self.module_registry.get(&key) {
+            return Ok(module.clone());
+        }
+
+        let module = LoadedModule {
+            globals: Rc::new(RefCell::new(builtins::create_builtin_classes().into())),
+            members: Rc::new(RefCell::new(Globals::new())),
+            loading: true,
         };
-        let saved_stack = std::mem::take(&mut self.stack);
-        let saved_frames = std::mem::take(&mut self.frames);
-        let saved_file = std::mem::replace(&mut self.file, path.to_string());
-        let saved_source = std::mem::replace(&mut self.source, String::new());
-        crate::push_script_dir(path);
-        let import_globals = Rc::new(RefCell::new(builtins::create_builtin_classes()));
-        let saved_globals = std::mem::replace(&mut self.globals, import_globals);
-        let mut main_function = Function::new("<import>", 0, chunk);
-        main_function.file = path.to_string();
-        self.stack.push(Value::Null);
-        self.frames.push(CallFrame::new(Rc::new(main_function), 0));
-        loop {
-            match self.execute_until_complete_native() {
-                ExecutionResult::Completed(_) => break,
-                ExecutionResult::Error(e) => {
-                    crate::pop_script_dir();
-                    self.stack = saved_stack;
-                    self.frames = saved_frames;
-                    self.file = saved_file;
-                    self.source = saved_source;
-                    self.globals = saved_globals;
-                    return Err(e);
-                }
-            }
+        self.module_registry.insert(key.clone(), module.clone());
+        self.import_stack.push(key.clone());
+        if let Some(ref workspace) = module_workspace {
+            self.workspace.borrow_mut().push_module(workspace);
         }
-        let imported_globals = std::mem::take(&mut *self.globals.borrow_mut());
-        crate::pop_script_dir();
-        self.stack = saved_stack;
-        self.frames = saved_frames;
-        self.file = saved_file;
-        self.source = saved_source;
-        self.globals = saved_globals;
-        Ok(imported_globals)
+        let result = self.execute_module(path, module.globals.clone());
+        if module_workspace.is_some() {
+            self.workspace.borrow_mut().pop_module();
+        }
+        self.import_stack.pop();
+
+        if let Err(e) = result {
+            self.module_registry.remove(&key);
+            return Err(e);
+        }
+        let fields = Self::module_fields(FxHashMap::clone(&module.globals.borrow()));
+        module.members.borrow_mut().extend(fields);
+        let module = LoadedModule {
+            loading: false,
+            ..module
+        };
+        self.module_registry.insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// `ImportError` naming every module in the cycle that leads back to `path`.
+    fn circular_import_error(&self, path: &str) -> SaldError {
+        let key = self.module_key(path);
+        let start = self
+            .import_stack
+            .iter()
+            .position(|p| *p == key)
+            .unwrap_or(0);
+        let mut cycle: Vec<&str> = self.import_stack[start..]
+            .iter()
+            .map(|p| p.as_str())
+            .collect();
+        cycle.push(&key);
+        self.create_error(
+            ErrorKind::ImportError,
+            &format!("Circular import: {}", cycle.join(" -> ")),
+        )
+        .with_help(
+            "Use 'import \"...\" as name' so the module is reachable once it finishes \
+             loading, or move the shared code into its own module",
+        )
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     fn import_and_execute_with_globals(
         &mut self,
         path: &str,
-    ) -> SaldResult<(
-        FxHashMap<String, Value>,
-        Rc<RefCell<FxHashMap<String, Value>>>,
-    )> {
-        let chunk = if path.ends_with(".saldc") {
+    ) -> SaldResult<ImportedModule> {
+        let globals = Rc::new(RefCell::new(builtins::create_builtin_classes().into()));
+        self.execute_module(path, globals.clone())?;
+        let imported_globals = FxHashMap::clone(&globals.borrow());
+        Ok((imported_globals, globals))
+    }
+
+    /// Reads an imported file, or the chunk precompiled for it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read_module(&self, path: &str) -> SaldResult<Chunk> {
+        if let Some(chunk) = self.precompiled_chunk(path) {
+            return Ok(chunk);
+        }
+        if path.ends_with(".saldc") {
             let data = std::fs::read(path).map_err(|e| {
                 self.create_error(
                     ErrorKind::ImportError,
                     &format!("Cannot read import file '{}': {}", path, e),
                 )
             })?;
-            crate::binary::deserialize(&data).map_err(|e| {
+            return crate::binary::deserialize_lazy(&data).map_err(|e| {
                 self.create_error(
                     ErrorKind::ImportError,
                     &format!("Error deserializing import '{}': {}", path, e),
                 )
-            })?
-        } else {
-            let source = std::fs::read_to_string(path).map_err(|e| {
-                self.create_error(
+            });
+        }
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            self.create_error(
+                ErrorKind::ImportError,
+                &format!("Cannot read import file '{}': {}", path, e),
+            )
+        })?;
+        self.compile_module(path, &source)
+    }
+
+    fn compile_module(&self, path: &str, source: &str) -> SaldResult<Chunk> {
+        let mut scanner = Scanner::new(source, path);
+        let tokens = scanner.scan_tokens().map_err(|e| {
+            self.create_error(
+                ErrorKind::SyntaxError,
+                &format!("Error scanning import '{}': {}", path, e),
+            )
+        })?;
+        let mut parser = Parser::new(tokens, path, source);
+        let program = parser.parse().map_err(|e| {
+            self.create_error(
+                ErrorKind::SyntaxError,
+                &format!("Error parsing import '{}': {}", path, e),
+            )
+        })?;
+        let mut compiler = Compiler::new(path, source);
+        compiler.compile(&program).map_err(|e| {
+            self.create_error(
+                ErrorKind::SyntaxError,
+                &format!("Error compiling import '{}': {}", path, e),
+            )
+        })
+    }
+
+    /// Runs an imported module with `globals` as its global scope.
+    fn execute_module(&mut self, path: &str, globals: Rc<RefCell<Globals>>) -> SaldResult<()> {
+        let chunk = match self.module_loader.clone() {
+            Some(loader) => {
+                let source = loader.load(path).map_err(|e| {
+                    self.create_error(
+                        ErrorKind::ImportError,
+                        &format!("Cannot load import '{}': {}", path, e),
+                    )
+                })?;
+                match source {
+                    ModuleSource::Source(source) => self.compile_module(path, &source)?,
+                    ModuleSource::Chunk(chunk) => chunk,
+                }
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            None => self.read_module(path)?,
+            #[cfg(target_arch = "wasm32")]
+            None => {
+                return Err(self.create_error(
                     ErrorKind::ImportError,
-                    &format!("Cannot read import file '{}': {}", path, e),
-                )
-            })?;
-            let mut scanner = Scanner::new(&source, path);
-            let tokens = scanner.scan_tokens().map_err(|e| {
-                self.create_error(
-                    ErrorKind::SyntaxError,
-                    &format!("Error scanning import '{}': {}", path, e),
-                )
-            })?;
-            let mut parser = Parser::new(tokens, path, &source);
-            let program = parser.parse().map_err(|e| {
-                self.create_error(
-                    ErrorKind::SyntaxError,
-                    &format!("Error parsing import '{}': {}", path, e),
-                )
-            })?;
-            let mut compiler = Compiler::new(path, &source);
-            compiler.compile(&program).map_err(|e| {
-                self.create_error(
-                    ErrorKind::SyntaxError,
-                    &format!("Error compiling import '{}': {}", path, e),
-                )
-            })?
+                    &format!("Cannot load import '{}': no module loader", path),
+                ))
+            }
         };
-        let saved_stack = std::mem::take(&mut self.stack);
-        let saved_frames = std::mem::take(&mut self.frames);
-        let saved_file = std::mem::replace(&mut self.file, path.to_string());
-        let saved_source = std::mem::replace(&mut self.source, String::new());
-        crate::push_script_dir(path);
-        let import_globals = Rc::new(RefCell::new(builtins::create_builtin_classes()));
-        let module_globals_rc = import_globals.clone();
-        let saved_globals = std::mem::replace(&mut self.globals, import_globals);
         let mut main_function = Function::new("<import>", 0, chunk);
         main_function.file = path.to_string();
-        self.stack.push(Value::Null);
-        self.frames.push(CallFrame::new(Rc::new(main_function), 0));
-        loop {
-            match self.execute_until_complete_native() {
-                ExecutionResult::Completed(_) => break,
-                ExecutionResult::Error(e) => {
-                    crate::pop_script_dir();
-                    self.stack = saved_stack;
-                    self.frames = saved_frames;
-                    self.file = saved_file;
-                    self.source = saved_source;
-                    self.globals = saved_globals;
-                    return Err(e);
-                }
-            }
-        }
+        self.run_isolated(Rc::new(main_function), String::new(), globals)
+            .map(|_| ())
+    }
 
-        let imported_globals = self.globals.borrow().clone();
+    /// Runs `script` to completion on a stack of its own, with `globals` as
+    /// its global variables, and then restores the caller's state. Imported
+    /// modules and `Code.eval` run this way.
+    pub(crate) fn run_isolated(
+        &mut self,
+        script: Rc<Function>,
+        source: String,
+        globals: Rc<RefCell<Globals>>,
+    ) -> SaldResult<Value> {
+        let saved_stack = std::mem::take(&mut self.stack);
+        let saved_frames = std::mem::take(&mut self.frames);
+        // They point into the saved stack, not the module's
+        let saved_upvalues = std::mem::take(&mut self.open_upvalues);
+        // An uncaught throw must not land in the caller's catch blocks
+        let saved_handlers = std::mem::take(&mut self.exception_handlers);
+        let saved_file = std::mem::replace(&mut self.file, script.file.clone());
+        let saved_source = std::mem::replace(&mut self.source, source);
+        crate::push_script_dir(&script.file);
+        let saved_globals = std::mem::replace(&mut self.globals, globals);
+        self.stack.push(Value::Null);
+        self.frames.push(CallFrame::new(script, 0));
+        #[cfg(not(target_arch = "wasm32"))]
+        let result = match self.execute_until_complete_native() {
+            ExecutionResult::Completed(value) => Ok(value),
+            ExecutionResult::Error(e) => Err(e),
+        };
+        #[cfg(target_arch = "wasm32")]
+        let result = match self.execute_until_complete() {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(self.create_error(
+                ErrorKind::RuntimeError,
+                &format!("'{}' cannot await a pending Future in the browser", self.file),
+            )),
+            Err(e) => Err(e),
+        };
         crate::pop_script_dir();
         self.stack = saved_stack;
         self.frames = saved_frames;
+        self.open_upvalues = saved_upvalues;
+        self.exception_handlers = saved_handlers;
         self.file = saved_file;
         self.source = saved_source;
         self.globals = saved_globals;
-        Ok((imported_globals, module_globals_rc))
+        result
     }
 
     fn create_error(&self, kind: ErrorKind, message: &str) -> SaldError {
@@ -3263,6 +4599,10 @@ impl VM {
         } else if let Ok(source) = std::fs::read_to_string(file) {
             error = error.with_source(&source);
         }
+        error.with_stack_trace(self.stack_trace())
+    }
+
+    fn stack_trace(&self) -> Vec<StackFrame> {
         let mut stack_trace = Vec::new();
         for frame in self.frames.iter().rev() {
             let frame_span = frame.current_span();
@@ -3277,10 +4617,114 @@ impl VM {
                 frame_span.start.column,
             ));
         }
-        error.with_stack_trace(stack_trace)
+        stack_trace
+    }
+
+    /// `names` lists the current frame's locals by slot, comma separated,
+    /// as emitted by the compiler for `debugger` statements.
+    fn debug_context(&self, names: &str) -> DebugContext {
+        let slots_start = self.current_frame().slots_start;
+        let locals = names
+            .split(',')
+            .enumerate()
+            .filter(|(_, name)| !name.is_empty() && !name.starts_with("__"))
+            .filter_map(|(slot, name)| {
+                let value = self.stack.get(slots_start + slot)?;
+                Some((name.to_string(), value.clone()))
+            })
+            .collect();
+        DebugContext {
+            frames: self.stack_trace(),
+            locals,
+            globals: FxHashMap::clone(&self.globals.borrow()),
+        }
+    }
+
+    /// Reports a warning at the current location. Returns false when
+    /// `--warnings=error` raised it as an exception that a `catch` took.
+    pub(crate) fn warn(&mut self, kind: WarningKind, message: &str) -> SaldResult<bool> {
+        let location = (!self.frames.is_empty()).then(|| {
+            let frame = self.current_frame();
+            let file = if frame.function.file.is_empty() {
+                &self.file
+            } else {
+                &frame.function.file
+            };
+            format!("{}:{}", file, frame.current_span().start.line)
+        });
+        let warning = Warning {
+            kind,
+            message: message.to_string(),
+            location,
+        };
+        match warnings::emit(warning) {
+            Ok(()) => Ok(true),
+            Err(error) => self.handle_native_error(error).map(|_| false),
+        }
+    }
+
+    /// Calls a method the VM runs on the script's behalf, such as `__getattr__`.
+    /// What the method throws comes back as the error, to be rethrown where
+    /// the hook was triggered.
+    fn call_hook(&mut self, callee: &Value, args: Vec<Value>) -> Result<Value, String> {
+        self.call(callee, args)
+    }
+
+    /// Calls a protocol method such as `__eq__` if the value's class defines it
+    fn call_user_method(
+        &mut self,
+        value: &Value,
+        name: &str,
+        args: Vec<Value>,
+    ) -> Option<Result<Value, String>> {
+        let method = builtins::bound_user_method(value, name)?;
+        Some(self.call_hook(&method, args))
+    }
+
+    /// What the classes' own `toString` methods return for the instances in
+    /// a value, however deeply nested
+    fn user_display_text(&mut self, value: &Value) -> Result<DisplayText, String> {
+        builtins::user_display_text(value, &mut |method, args| self.call_hook(method, args))
+    }
+
+    /// Where an instance whose class defines `__hash__` lives as a key of `dict`
+    fn dict_slot(
+        &mut self,
+        dict: &RefCell<Dict>,
+        key: &Value,
+    ) -> Option<Result<DictSlot, String>> {
+        builtins::dict::key_slot(dict, key, &mut |method, args| self.call_hook(method, args))
+    }
+
+    /// Records how a nested run failed and returns the message handed to
+    /// the native that started it: what was thrown, or the error's message,
+    /// followed by where in the nested code it happened when `located`
+    fn nested_failure(&mut self, error: SaldError, located: bool) -> String {
+        let (message, raised) = match self.uncaught.take() {
+            Some(value) => (thrown_message(&value), Raised::Thrown(value)),
+            None => (error.message.clone(), Raised::Error(error.kind.clone())),
+        };
+        let message = if located {
+            format!(
+                "{} at {}:{}:{}",
+                message, error.file, error.span.start.line, error.span.start.column
+            )
+        } else {
+            message
+        };
+        self.nested_error = Some(NestedError {
+            message: message.clone(),
+            raised,
+        });
+        message
     }
 
     fn handle_native_error(&mut self, error_msg: String) -> SaldResult<()> {
+        // A native passing on a nested failure raises it again as it was
+        let raised = match self.nested_error.take() {
+            Some(nested) if nested.message == error_msg => nested.raised,
+            _ => Raised::Thrown(Value::String(Rc::from(error_msg.as_str()))),
+        };
         if let Some(handler) = self.exception_handlers.pop() {
             while self.frames.len() > handler.frame_index + 1 {
                 self.frames.pop();
@@ -3288,14 +4732,24 @@ impl VM {
             while self.stack.len() > handler.stack_size {
                 self.stack.pop();
             }
-            self.stack.push(Value::String(Rc::from(error_msg)));
+            let exception = match raised {
+                Raised::Thrown(value) => value,
+                Raised::Error(_) => Value::String(Rc::from(error_msg)),
+            };
+            self.stack.push(exception);
             self.current_frame_mut().ip = handler.catch_ip;
             Ok(())
         } else {
-            Err(self.create_error(
-                ErrorKind::RuntimeError,
-                &format!("Uncaught exception: {}", error_msg),
-            ))
+            match raised {
+                Raised::Thrown(value) => {
+                    self.uncaught = Some(value);
+                    Err(self.create_error(
+                        ErrorKind::RuntimeError,
+                        &format!("Uncaught exception: {}", error_msg),
+                    ))
+                }
+                Raised::Error(kind) => Err(self.create_error(kind, &error_msg)),
+            }
         }
     }
 
@@ -3337,3 +4791,502 @@ impl Default for VM {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::builtins::ConsoleStream;
+    use crate::engine::Engine;
+    use crate::test_util::{eval, eval_err};
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
+
+    const ANY: &str = "class Any { fun __eq__(self, other) { return true } }";
+
+    #[test]
+    fn test_eq_is_used_whichever_side_the_instance_is_on() {
+        assert_eq!(eval(&format!("{ANY}\nAny() == 3")), "true");
+        assert_eq!(eval(&format!("{ANY}\n3 == Any()")), "true");
+        assert_eq!(eval(&format!("{ANY}\n3 != Any()")), "false");
+        assert_eq!(eval(&format!("{ANY}\n\"a\" != Any()")), "false");
+    }
+
+    #[test]
+    fn test_instances_without_eq_compare_by_identity() {
+        let setup = "class P {}\nlet p = P()";
+        assert_eq!(eval(&format!("{setup}\np == p")), "true");
+        assert_eq!(eval(&format!("{setup}\np == P()")), "false");
+        assert_eq!(eval(&format!("{setup}\n3 == p")), "false");
+        assert_eq!(eval(&format!("{setup}\nnull != p")), "true");
+    }
+
+    #[test]
+    fn test_natives_reject_named_arguments_they_do_not_declare() {
+        let err = eval_err("let a = [1]\na.push(oops: 5)");
+        assert!(err.contains("TypeError"), "{err}");
+        assert!(err.contains("Unexpected named argument 'oops'"), "{err}");
+        let err = eval_err("Math.max(1, x: 2)");
+        assert!(err.contains("Unexpected named argument 'x'"), "{err}");
+        assert_eq!(
+            eval("let a = [1]\ntry { a.push(oops: 5) } catch (e) {}\na.length()"),
+            "1"
+        );
+    }
+
+    #[test]
+    fn test_trailing_dict_stays_positional() {
+        assert_eq!(eval("let a = [1]\na.push({\"oops\": 5})\na"), "[1, {\"oops\": 5}]");
+        assert_eq!(eval("Json.stringify({\"a\": 1}, null)"), "{\"a\":1}");
+    }
+
+    #[test]
+    fn test_declared_natives_take_named_arguments() {
+        assert_eq!(eval("Json.stringify([1], indent: 1)"), "[\n 1\n]");
+        let err = eval_err("Json.stringify(1, nope: 1)");
+        assert!(err.contains("Unexpected named argument 'nope'"), "{err}");
+    }
+
+    #[test]
+    fn test_repeat_refuses_results_that_are_too_large() {
+        let big = "let big = 1000000000000000000";
+        let err = eval_err(&format!("{big}\n\"ab\" * big"));
+        assert!(err.contains("ValueError"), "{err}");
+        assert!(err.contains("Repeat count 1000000000000000000 is too large"), "{err}");
+        let err = eval_err(&format!("{big}\nlet a = [1, 2] * (big * big)"));
+        assert!(err.contains("is too large"), "{err}");
+        let err = eval_err(&format!("{big}\n\"ab\".repeat(big)"));
+        assert!(err.contains("is too large"), "{err}");
+    }
+
+    #[test]
+    fn test_repeat_of_empty_values_ignores_the_count() {
+        let big = "let big = 1000000000000000000";
+        assert_eq!(eval(&format!("{big}\nlet a = [] * big\na.length()")), "0");
+        assert_eq!(eval(&format!("{big}\nlet s = \"\" * big\ns.length()")), "0");
+        assert_eq!(eval(&format!("{big}\n\"\".repeat(big).length()")), "0");
+        assert_eq!(eval("[1, 2] * 3"), "[1, 2, 1, 2, 1, 2]");
+    }
+
+    #[test]
+    fn test_coalesce_assignment_sets_missing_properties() {
+        let setup = "class P {}\nlet p = P()";
+        assert_eq!(eval(&format!("{setup}\np.count ??= 5\np.count")), "5");
+        assert_eq!(eval(&format!("{setup}\np.count = 1\np.count ??= 5")), "1");
+        assert_eq!(eval(&format!("{setup}\np.count = null\np.count ??= 5")), "5");
+    }
+
+    #[test]
+    fn test_coalesce_assignment_keeps_other_property_errors() {
+        let err = eval_err("class Q { fun init(self) { self._s = null } }\nQ()._s ??= 1");
+        assert!(err.contains("Cannot access private member '_s'"), "{err}");
+        let err = eval_err("let n = null\nn.x ??= 1");
+        assert!(err.contains("'Null' has no method 'x'"), "{err}");
+    }
+
+    #[test]
+    fn test_lambdas_collect_keyword_arguments() {
+        assert_eq!(eval("let f = |a, **kw| [a, kw]\nf(1, x: 2)"), "[1, {\"x\": 2}]");
+        assert_eq!(eval("let f = |...r, **kw| [r, kw]\nf(1, 2)"), "[[1, 2], {}]");
+        assert_eq!(eval("let f = async |**kw| kw\nawait f(y: 3)"), "{\"y\": 3}");
+    }
+
+    #[test]
+    fn test_lambda_keyword_parameter_must_be_last() {
+        let err = eval_err("let f = |**kw, a| a");
+        assert!(err.contains("Parameter cannot follow a '**' parameter"), "{err}");
+        let err = eval_err("let f = |a| a\nf(1, b: 2)");
+        assert!(err.contains("Unexpected named argument 'b'"), "{err}");
+    }
+
+    #[test]
+    fn test_string_concatenation_shows_nested_instances_by_to_string() {
+        let setup = "class P { fun toString(self) { return \"p\" } }\nlet xs = [P(), {\"k\": P()}]";
+        assert_eq!(eval(&format!("{setup}\n\"xs: \" + xs")), "xs: [p, {\"k\": p}]");
+        assert_eq!(eval(&format!("{setup}\n$\"{{xs}}!\"")), "[p, {\"k\": p}]!");
+        assert_eq!(eval(&format!("{setup}\nxs.push(xs)\n\"\" + [xs[0]]")), "[p]");
+    }
+
+    #[test]
+    fn test_string_concatenation_raises_nested_to_string_errors() {
+        let err = eval_err("class B { fun toString(self) { throw \"no\" } }\n\"\" + {\"b\": B()}");
+        assert!(err.contains("no"), "{err}");
+    }
+
+    #[test]
+    fn test_for_loops_advance_on_continue() {
+        let source = "let out = []\nfor x in [1, 2, 3] { if x == 2 { continue }\n out.push(x) }\n\
+                      for c in \"hé\" { out.push(c) }\n\
+                      for x in Set([7]) { out.push(x) }\nout";
+        assert_eq!(eval(source), "[1, 3, h, é, 7]");
+        let err = eval_err("for x in 5 {}");
+        assert!(
+            err.starts_with("TypeError: Cannot iterate a Number"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_callback_throws_return_to_the_native_first() {
+        let source = "let r = []\n\
+                      try { [1, 2].map(|x| { r.push(x)\n throw \"boom\" })\n r.push(\"after\") } \
+                      catch (e) { r.push(e) }\nr";
+        assert_eq!(eval(source), "[1, boom]");
+    }
+
+    #[test]
+    fn test_modules_load_once_and_report_cycles() {
+        let dir = std::env::temp_dir().join(format!("sald_modules_once_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, source: &str| std::fs::write(dir.join(name), source).unwrap();
+        write(
+            "shared.sald",
+            "let loads = 0\nloads = loads + 1\nlet items = []\nfun add(x) { items.push(x) }",
+        );
+        write("a.sald", "import \"shared.sald\" as s\ns.add(\"a\")");
+        write("b.sald", "import \"shared.sald\" as s\ns.add(\"b\")");
+        write(
+            "ping.sald",
+            "import \"pong.sald\" as pong\nfun ping() { return \"ping \" + pong.name }",
+        );
+        write(
+            "pong.sald",
+            "import \"ping.sald\" as ping\nlet name = \"pong\"\nfun call() { return ping.ping() }",
+        );
+        write("x.sald", "import \"y.sald\"");
+        write("y.sald", "import \"x.sald\"");
+        let path = |name: &str| dir.join(name).to_str().unwrap().replace('\\', "/");
+
+        let mut engine = Engine::new();
+        engine
+            .eval(&format!(
+                "import \"{}\"\nimport \"{}\"\nimport \"{}\" as shared\nimport \"{}\" as pong",
+                path("a.sald"),
+                path("b.sald"),
+                path("shared.sald"),
+                path("pong.sald")
+            ))
+            .unwrap();
+        let shared: (f64, Vec<String>) = engine.eval_as("[shared.loads, shared.items]").unwrap();
+        let cycle: String = engine.eval_as("pong.call()").unwrap();
+        let err = engine
+            .eval(&format!("import \"{}\"", path("x.sald")))
+            .unwrap_err();
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(shared, (1.0, vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(cycle, "ping pong");
+        let message = err.message();
+        assert!(message.starts_with("Circular import: "), "{}", message);
+        assert!(
+            message.contains("x.sald -> ") && message.ends_with("x.sald"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_const_belongs_to_its_globals_table() {
+        // Tables dropped by earlier evaluations leave no constants behind
+        let mut engine = Engine::new();
+        engine
+            .eval("for i in 0..50 { Code.eval(\"const A = 1\") }")
+            .unwrap();
+        engine.eval("let A = 2\nA = 3").unwrap();
+        assert_eq!(engine.eval_as::<i64>("A").unwrap(), 3);
+
+        let loader = crate::vm::MemoryLoader::new().with_module("ca.sald", "const LIMIT = 1");
+        let mut engine = Engine::new();
+        engine.set_module_loader(loader);
+        engine.eval("import \"ca.sald\"").unwrap();
+        let err = engine.eval("LIMIT = 2").unwrap_err();
+        assert_eq!(err.message(), "Cannot assign to constant 'LIMIT'");
+        let err = engine.eval("let LIMIT = 3").unwrap_err();
+        assert_eq!(err.message(), "Cannot redeclare constant 'LIMIT'");
+    }
+
+    #[test]
+    fn test_dynamic_import() {
+        use crate::vm::MemoryLoader;
+
+        let loader = MemoryLoader::new()
+            .with_module("plugins/upper.sald", "fun apply(s) { return s.upper() }")
+            .with_module("plugins/twice.sald", "fun apply(s) { return s + s }");
+        let mut engine = Engine::new();
+        engine.set_module_loader(loader);
+        engine
+            .eval(
+                r#"
+let value = "ab"
+for name in ["twice", "upper"] {
+    let plugin = await import("plugins/" + name + ".sald")
+    value = plugin.apply(value)
+}
+let sameModule = Module.of(import("plugins/upper")).name
+"#,
+            )
+            .unwrap();
+        assert_eq!(engine.eval_as::<String>("value").unwrap(), "ABAB");
+        assert_eq!(engine.eval_as::<String>("sameModule").unwrap(), "upper");
+        let err = engine.eval("import(\"plugins/missing\")").unwrap_err();
+        assert!(err.message().contains("not found"));
+        let err = engine.eval("import(42)").unwrap_err();
+        assert!(err.message().contains("expects a String path"));
+    }
+
+    #[test]
+    fn test_dynamic_dispatch_hooks() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                class Settings {
+                    fun init(self, data) { self._data = data }
+                    fun __getattr__(self, name) { return self._data.get(name) }
+                }
+                class Recorder {
+                    fun init(self) { self.seen = [] }
+                    fun __missing_method__(self, name, args) {
+                        self.seen.push(name + "/" + args.length())
+                        return args.length()
+                    }
+                }
+                class Scale {
+                    fun init(self, factor) { self.factor = factor }
+                    fun __call__(self, x) { return x * self.factor }
+                }
+                class Events {
+                    fun __getattr__(self, name) { return |x| name + ":" + x }
+                }
+                let settings = Settings({"port": 80})
+                let rec = Recorder()
+                rec.open("a", "b")
+                rec.close()
+                let double = Scale(2)
+                let parts = [
+                    settings.port,
+                    settings.missing,
+                    rec.seen.join(","),
+                    double(21),
+                    [1, 2].map(double).join(","),
+                    Events().click(3)
+                ]
+                parts.map(|v| "" + v).join(" ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result, "80 null open/2,close/0 42 2,4 click:3");
+
+        let err = engine.eval("Scale(1).missing()").unwrap_err();
+        assert!(err.message().contains("Undefined method 'missing'"));
+    }
+
+    #[test]
+    fn test_user_to_string() {
+        struct Capture(Rc<RefCell<Vec<u8>>>);
+        impl Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let out = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_console_output(ConsoleStream::Stdout, Capture(out.clone()));
+        let result = engine
+            .eval_as::<String>(
+                r#"
+class Money {
+    fun init(self, cents) { self.cents = cents }
+    fun toString(self) { return "$" + (self.cents / 100) }
+}
+class Broken { fun toString(self) { return null } }
+let price = Money(250)
+Console.log("price", price)
+let caught = ""
+try { "" + Broken() } catch e { caught = e }
+$"total {price}, " + price + " " + caught
+"#,
+            )
+            .unwrap();
+        crate::builtins::set_console_writer(ConsoleStream::Stdout, None);
+
+        assert_eq!(
+            result,
+            "total $2.5, $2.5 toString() must return a String, got Null"
+        );
+        assert_eq!(
+            String::from_utf8(out.borrow().clone()).unwrap(),
+            "price $2.5\n"
+        );
+    }
+
+    #[test]
+    fn test_hash_and_equality_protocol() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                class Point {
+                    fun init(self, x, y) {
+                        self.x = x
+                        self.y = y
+                    }
+                    fun __hash__(self) { return self.x + "," + self.y }
+                    fun __eq__(self, other) {
+                        return Reflect.classOf(other) == Point && self.__hash__() == other.__hash__()
+                    }
+                }
+                let cache = {Point(0, 0): "origin"}
+                cache[Point(1, 2)] = "a"
+                cache[Point(1, 2)] = "b"
+                let seen = Set()
+                seen.add(Point(3, 4))
+                seen.add(Point(3, 4))
+                let parts = [
+                    cache[Point(0, 0)],
+                    cache[Point(1, 2)],
+                    cache.keys().length(),
+                    seen.length(),
+                    seen.has(Point(3, 4)),
+                    Point(1, 2) == Point(1, 2),
+                    Point(1, 2) != Point(2, 1),
+                    Point(1, 2) == "1,2"
+                ]
+                parts.map(|v| "" + v).join(" ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result, "origin b 2 1 true true true false");
+
+        let seen = engine.eval_as::<f64>("Set([Point(1, 2), Point(1, 2)]).length()");
+        assert_eq!(seen.unwrap(), 1.0);
+        let err = engine.eval("Set([[Point(1, 2)]])").unwrap_err();
+        assert!(err.message().contains("defines __hash__"));
+    }
+
+    #[test]
+    fn test_string_and_array_repeat() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                let n = 3
+                let grid = [0] * n
+                grid[1] = 5
+                let line = "-" * n
+                line *= 2
+                let parts = [
+                    "ab" * 3,
+                    2 * "x",
+                    "" + grid,
+                    "" + ([1, 2] * 0),
+                    line,
+                    n * 2
+                ]
+                parts.map(|v| "" + v).join(" ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result, "ababab xx [0, 5, 0] [] ------ 6");
+
+        let err = engine.eval("[1] * -2").unwrap_err();
+        assert!(err.message().contains("Repeat count"));
+    }
+
+    #[test]
+    fn test_named_arguments() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                fun greet(name, greeting = "Hello", punct = "!") {
+                    return greeting + ", " + name + punct
+                }
+                class Point {
+                    fun init(self, x, y = 0) {
+                        self.x = x
+                        self.y = y
+                    }
+                    fun moved(self, dx = 0, dy = 0) { return Point(self.x + dx, y: self.y + dy) }
+                }
+                let errors = []
+                try { greet("Ada", mood: "calm") } catch e { errors.push(e) }
+                try { greet("Ada", name: "Bob") } catch e { errors.push(e) }
+                try { Json.stringify(1, 2, indent: 4) } catch e { errors.push(e) }
+                let parts = [
+                    greet("Ada", punct: "?"),
+                    greet(punct: ".", name: "Bob"),
+                    Point(1, y: 2).moved(dy: 3).y,
+                    Json.stringify({"b": 1, "a": 2}, sortKeys: true),
+                    errors.join("; ")
+                ]
+                parts.join(" | ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            "Hello, Ada? | Hello, Bob. | 5 | {\"a\":2,\"b\":1} | \
+             Unexpected named argument 'mood' for 'greet'; \
+             Argument 'name' was given both by position and by name; \
+             Argument 'indent' was given both by position and by name"
+        );
+    }
+
+    #[test]
+    fn test_kwargs_parameters_and_dict_spread() {
+        let mut engine = Engine::new();
+        let result = engine
+            .eval_as::<String>(
+                r#"
+                fun describe(a, ...rest, **opts) {
+                    return $"{a} {rest} {Json.stringify(opts, sortKeys: true)}"
+                }
+                fun target(a, b = 10, **extra) { return a + b + extra.length() }
+                fun forward(...args, **named) { return target(...args, **named) }
+                fun strict(a, b) { return a * b }
+                let error = ""
+                try { strict(1, **{"c": 2}) } catch e { error = e }
+                let parts = [
+                    describe(1),
+                    describe(1, 2, 3, y: 5, x: 4),
+                    forward(1),
+                    forward(1, b: 2, c: 3),
+                    strict(**{"a": 3, "b": 4}),
+                    error
+                ]
+                parts.join(" | ")
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            result,
+            "1 [] {} | 1 [2, 3] {\"x\":4,\"y\":5} | 11 | 4 | 12 | \
+             Unexpected named argument 'c' for 'strict'"
+        );
+    }
+
+    #[test]
+    fn test_callback_throws_reach_the_catch_unchanged() {
+        let source = "class MyErr { fun init(self, code) { self.code = code } }\n\
+                      let r = []\n\
+                      try { [1].map(|x| { throw MyErr(5) }) } catch (e) { r.push(e.code) }\n\
+                      try { [[1, 2]].map(|a| a.toSorted(|x, y| { throw MyErr(7) })) } \
+                      catch (e) { r.push(e.code) }\n\
+                      try { [1].map(|x| { throw \"plain\" }) } catch (e) { r.push(e) }\nr";
+        assert_eq!(eval(source), "[5, 7, plain]");
+    }
+
+    #[test]
+    fn test_uncaught_callback_errors_keep_their_kind() {
+        let err = eval_err("[1].map(|x| undefinedName)");
+        assert!(err.starts_with("NameError"), "{err}");
+        let err = eval_err("[1].map(|x| { throw 5 })");
+        assert!(err.contains("Uncaught exception: 5"), "{err}");
+        assert!(!err.contains("Uncaught exception: Uncaught"), "{err}");
+    }
+}
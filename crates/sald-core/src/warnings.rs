@@ -0,0 +1,178 @@
+//! Non-fatal warnings
+//! The VM and builtins report suspicious but legal operations, such as a
+//! deprecated API or a number silently truncated, here. By default each
+//! distinct warning is printed once to stderr; hosts can install a handler
+//! instead, and `--warnings=error` turns warnings into exceptions.
+
+use parking_lot::{Mutex, RwLock};
+use rustc_hash::FxHashSet;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Use of an API that is going away
+    Deprecated,
+    /// A value implicitly converted to another type
+    Coercion,
+    /// A number converted with loss of precision
+    LossyConversion,
+    /// Raised by a script with `Warning.emit`
+    User,
+}
+
+impl WarningKind {
+    /// Name used by `Warning.emit` and in messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::Deprecated => "deprecated",
+            WarningKind::Coercion => "coercion",
+            WarningKind::LossyConversion => "lossy",
+            WarningKind::User => "user",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            WarningKind::Deprecated,
+            WarningKind::Coercion,
+            WarningKind::LossyConversion,
+            WarningKind::User,
+        ]
+        .into_iter()
+        .find(|kind| kind.name() == name)
+    }
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarningKind::Deprecated => write!(f, "DeprecationWarning"),
+            WarningKind::Coercion => write!(f, "CoercionWarning"),
+            WarningKind::LossyConversion => write!(f, "LossyConversionWarning"),
+            WarningKind::User => write!(f, "Warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub message: String,
+    /// `file:line` of the code that caused it, when the VM knows it
+    pub location: Option<String>,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+/// What happens to a warning that is emitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningMode {
+    /// Pass it to the handler, or print it once to stderr
+    Default,
+    Ignore,
+    /// Raise it as an exception
+    Error,
+}
+
+type Handler = Arc<dyn Fn(&Warning) + Send + Sync>;
+
+static MODE: AtomicU8 = AtomicU8::new(0);
+static HANDLER: RwLock<Option<Handler>> = RwLock::new(None);
+/// Warnings already printed, so loops don't repeat them
+static PRINTED: Mutex<Option<FxHashSet<String>>> = Mutex::new(None);
+
+pub fn set_mode(mode: WarningMode) {
+    MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn mode() -> WarningMode {
+    match MODE.load(Ordering::Relaxed) {
+        1 => WarningMode::Ignore,
+        2 => WarningMode::Error,
+        _ => WarningMode::Default,
+    }
+}
+
+/// Receives every warning in the default mode instead of stderr; `None`
+/// goes back to printing.
+pub fn set_handler(handler: Option<Handler>) {
+    *HANDLER.write() = handler;
+}
+
+/// Reports `warning`. Returns it as an error message in the error mode.
+pub fn emit(warning: Warning) -> Result<(), String> {
+    match mode() {
+        WarningMode::Ignore => Ok(()),
+        WarningMode::Error => Err(warning.to_string()),
+        WarningMode::Default => {
+            if let Some(handler) = HANDLER.read().clone() {
+                handler(&warning);
+                return Ok(());
+            }
+            let mut text = warning.to_string();
+            if let Some(location) = &warning.location {
+                text.push_str(&format!("\n  at {}", location));
+            }
+            let first = PRINTED
+                .lock()
+                .get_or_insert_with(FxHashSet::default)
+                .insert(text.clone());
+            if first {
+                crate::builtins::write_stderr(&format!("{}\n", text));
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Reports a warning from a native, which has no location to give.
+pub fn warn(kind: WarningKind, message: impl Into<String>) -> Result<(), String> {
+    emit(Warning {
+        kind,
+        message: message.into(),
+        location: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_warning_handler() {
+        use crate::warnings::WarningKind;
+
+        let seen = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+        let sink = seen.clone();
+        engine.set_warning_handler(move |w| sink.lock().push(w.clone()));
+        engine
+            .eval(
+                r#"
+let index = 1.5
+let picked = [10, 20][index]
+Warning.deprecated("warned_fn_test is deprecated")
+"#,
+            )
+            .unwrap();
+        crate::warnings::set_handler(None);
+
+        assert_eq!(engine.eval_as::<i64>("picked").unwrap(), 20);
+        let seen = seen.lock();
+        let lossy = seen
+            .iter()
+            .find(|w| w.message == "Index 1.5 truncated to 1")
+            .unwrap();
+        assert_eq!(lossy.kind, WarningKind::LossyConversion);
+        assert!(lossy.location.as_deref().is_some_and(|l| l.ends_with(":3")));
+        assert!(seen.iter().any(|w| w.kind == WarningKind::Deprecated
+            && w.to_string() == "DeprecationWarning: warned_fn_test is deprecated"));
+    }
+}
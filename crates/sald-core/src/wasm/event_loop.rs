@@ -0,0 +1,252 @@
+//! Browser event loop
+//! Pending Futures, timers and `run_code_async` share one queue. JS work
+//! (`fetch`, `setTimeout`) settles a Future's slot and wakes the runner, which
+//! resumes the suspended VM and runs timer callbacks between steps.
+
+use super::js_error;
+use crate::compiler::Chunk;
+use crate::error::{SaldError, SaldResult, Span};
+use crate::vm::value::{SendValue, Value};
+use crate::vm::ValueCaller;
+use crate::vm::VM;
+use js_sys::{Function, Promise, Reflect};
+use rustc_hash::FxHashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+struct Timer {
+    callback: Value,
+    repeat: bool,
+    js_id: JsValue,
+    /// Keeps a `setInterval` callback alive until the timer is cleared
+    _tick: Option<Closure<dyn FnMut()>>,
+}
+
+thread_local! {
+    /// Bumped per run so JS callbacks from an earlier run are ignored
+    static GENERATION: Cell<u32> = const { Cell::new(0) };
+    /// Futures and timers that can still wake the runner
+    static IN_FLIGHT: Cell<usize> = const { Cell::new(0) };
+    static TIMERS: RefCell<FxHashMap<usize, Timer>> = RefCell::new(FxHashMap::default());
+    static NEXT_TIMER: Cell<usize> = const { Cell::new(1) };
+    static READY: RefCell<VecDeque<usize>> = const { RefCell::new(VecDeque::new()) };
+    static WAKE: RefCell<Option<Function>> = const { RefCell::new(None) };
+    /// The latest run's VM, kept after the script ends so JS callbacks can
+    /// still call into it
+    static CURRENT_VM: RefCell<Option<Rc<RefCell<VM>>>> = const { RefCell::new(None) };
+}
+
+fn global_fn(name: &str) -> Result<Function, String> {
+    Reflect::get(&js_sys::global(), &JsValue::from_str(name))
+        .map_err(js_error)?
+        .dyn_into::<Function>()
+        .map_err(|_| format!("{} is not available in this environment", name))
+}
+
+fn wake() {
+    if let Some(resolve) = WAKE.with(|wake| wake.borrow_mut().take()) {
+        let _ = resolve.call0(&JsValue::NULL);
+    }
+}
+
+/// Waits until a Future settles or a timer fires.
+async fn wait_for_wake() {
+    let promise = Promise::new(&mut |resolve, _| {
+        WAKE.with(|wake| *wake.borrow_mut() = Some(resolve));
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+/// Forgets everything left over from a previous run.
+pub(crate) fn reset() {
+    GENERATION.with(|g| g.set(g.get().wrapping_add(1)));
+    IN_FLIGHT.with(|n| n.set(0));
+    READY.with(|ready| ready.borrow_mut().clear());
+    WAKE.with(|wake| wake.borrow_mut().take());
+    let timers: Vec<Timer> =
+        TIMERS.with(|timers| timers.borrow_mut().drain().map(|(_, t)| t).collect());
+    for timer in timers {
+        clear_js_timer(&timer);
+    }
+}
+
+/// Creates the VM for a new run, replacing the previous run's.
+pub(crate) fn new_vm() -> Rc<RefCell<VM>> {
+    reset();
+    let mut vm = VM::new();
+    super::interop::install_globals(&mut vm);
+    vm.set_module_loader(super::VirtualFsLoader);
+    let vm = Rc::new(RefCell::new(vm));
+    CURRENT_VM.with(|current| *current.borrow_mut() = Some(vm.clone()));
+    vm
+}
+
+/// Runs `f` on the latest run's VM, unless it is busy executing.
+pub(crate) fn with_idle_vm<T>(f: impl FnOnce(&mut VM) -> T) -> Option<T> {
+    let vm = CURRENT_VM.with(|current| current.borrow().clone())?;
+    let mut vm = vm.try_borrow_mut().ok()?;
+    Some(f(&mut vm))
+}
+
+fn release(generation: u32) {
+    if GENERATION.with(|g| g.get()) == generation {
+        IN_FLIGHT.with(|n| n.set(n.get().saturating_sub(1)));
+    }
+}
+
+/// Runs `task` on the JS event loop and returns a Future the script can
+/// `await`.
+pub fn spawn_future<F>(task: F) -> Value
+where
+    F: Future<Output = Result<SendValue, String>> + 'static,
+{
+    let slot = Rc::new(RefCell::new(None));
+    let generation = GENERATION.with(|g| g.get());
+    IN_FLIGHT.with(|n| n.set(n.get() + 1));
+
+    let settled = slot.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = task.await;
+        *settled.borrow_mut() = Some(result);
+        release(generation);
+        wake();
+    });
+
+    Value::Future(Rc::new(RefCell::new(Some(slot))))
+}
+
+/// A JS promise that resolves after `ms` milliseconds.
+pub(crate) fn delay(ms: f64) -> Result<JsFuture, String> {
+    let set_timeout = global_fn("setTimeout")?;
+    let mut failure = None;
+    let promise = Promise::new(&mut |resolve, _| {
+        if let Err(e) = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from_f64(ms)) {
+            failure = Some(js_error(e));
+        }
+    });
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(JsFuture::from(promise)),
+    }
+}
+
+/// Schedules `callback` after `ms` milliseconds, or every `ms` if `repeat`.
+pub(crate) fn set_timer(ms: f64, repeat: bool, callback: Value) -> Result<usize, String> {
+    let id = NEXT_TIMER.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    let generation = GENERATION.with(|g| g.get());
+    let fire = move || {
+        if GENERATION.with(|g| g.get()) == generation {
+            READY.with(|ready| ready.borrow_mut().push_back(id));
+            wake();
+        }
+    };
+
+    let delay = JsValue::from_f64(ms);
+    let (js_id, tick) = if repeat {
+        let tick = Closure::<dyn FnMut()>::new(fire);
+        let js_id = global_fn("setInterval")?
+            .call2(&JsValue::NULL, tick.as_ref(), &delay)
+            .map_err(js_error)?;
+        (js_id, Some(tick))
+    } else {
+        let once = Closure::once_into_js(fire);
+        let js_id = global_fn("setTimeout")?
+            .call2(&JsValue::NULL, &once, &delay)
+            .map_err(js_error)?;
+        (js_id, None)
+    };
+
+    IN_FLIGHT.with(|n| n.set(n.get() + 1));
+    TIMERS.with(|timers| {
+        timers.borrow_mut().insert(
+            id,
+            Timer {
+                callback,
+                repeat,
+                js_id,
+                _tick: tick,
+            },
+        )
+    });
+    Ok(id)
+}
+
+fn clear_js_timer(timer: &Timer) {
+    let name = if timer.repeat {
+        "clearInterval"
+    } else {
+        "clearTimeout"
+    };
+    if let Ok(clear) = global_fn(name) {
+        let _ = clear.call1(&JsValue::NULL, &timer.js_id);
+    }
+}
+
+/// Cancels a timer; returns whether it was still scheduled.
+pub(crate) fn clear_timer(id: usize) -> bool {
+    match TIMERS.with(|timers| timers.borrow_mut().remove(&id)) {
+        Some(timer) => {
+            clear_js_timer(&timer);
+            IN_FLIGHT.with(|n| n.set(n.get().saturating_sub(1)));
+            true
+        }
+        None => false,
+    }
+}
+
+pub(crate) fn timer_active(id: usize) -> bool {
+    TIMERS.with(|timers| timers.borrow().contains_key(&id))
+}
+
+/// Runs the callbacks of timers that fired since the last call.
+fn run_ready(vm: &mut VM) -> Result<(), String> {
+    while let Some(id) = READY.with(|ready| ready.borrow_mut().pop_front()) {
+        let callback = TIMERS.with(|timers| {
+            let mut timers = timers.borrow_mut();
+            let timer = timers.get(&id)?;
+            let callback = timer.callback.clone();
+            if !timer.repeat {
+                timers.remove(&id);
+                IN_FLIGHT.with(|n| n.set(n.get().saturating_sub(1)));
+            }
+            Some(callback)
+        });
+        if let Some(callback) = callback {
+            vm.call(&callback, Vec::new())?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs a compiled script to completion, suspending on `await` and running
+/// timers until none are left, like the native runtime does.
+pub(crate) async fn run_to_completion(chunk: &Chunk) -> SaldResult<Value> {
+    let vm = new_vm();
+    let runtime_error =
+        |message: String| SaldError::runtime_error(message, Span::default(), "<wasm>");
+    let mut state = vm.borrow_mut().start(chunk)?;
+    loop {
+        run_ready(&mut vm.borrow_mut()).map_err(runtime_error)?;
+        if IN_FLIGHT.with(|n| n.get()) == 0 {
+            return match state {
+                Some(value) => Ok(value),
+                None => Err(runtime_error(
+                    "Awaited a Future that can never settle".to_string(),
+                )),
+            };
+        }
+        wait_for_wake().await;
+        if state.is_none() {
+            state = vm.borrow_mut().resume()?;
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Sald <-> JS values
+//! Primitives and arrays are copied across. Any other JS value stays on the
+//! JS side and is seen by scripts as a `JsObject` handle; Sald callables
+//! become JS functions that call back into the running VM.
+
+use super::{event_loop, js_error};
+use crate::vm::caller::ValueCaller;
+use crate::vm::value::{SendValue, Value};
+use crate::vm::VM;
+use js_sys::{Array, Function, Object, Reflect};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    /// Natives currently calling into JS, innermost last
+    static CALLERS: RefCell<Vec<*mut (dyn ValueCaller + 'static)>> = const { RefCell::new(Vec::new()) };
+    /// Values the host exposed with `set_global`
+    static EXPOSED: RefCell<Vec<(String, JsValue)>> = const { RefCell::new(Vec::new()) };
+    /// Spreads JS arguments into the single array a Rust closure takes
+    static SPREAD: Function = Function::new_with_args("f", "return function(...args) { return f(args); }");
+}
+
+fn wrap_object(value: JsValue) -> Value {
+    crate::builtins::native_instance(crate::builtins::create_js_object_class(), value)
+}
+
+/// The JS value behind a `JsObject` handle.
+pub(crate) fn object_of(value: &Value) -> Option<JsValue> {
+    match value {
+        Value::Instance(inst) => inst
+            .borrow()
+            .native::<JsValue>()
+            .map(|object| object.borrow().clone()),
+        _ => None,
+    }
+}
+
+fn is_callable(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Function(_)
+            | Value::NativeFunction { .. }
+            | Value::HostFunction { .. }
+            | Value::InstanceMethod { .. }
+            | Value::BoundMethod { .. }
+            | Value::Partial { .. }
+            | Value::Class(_)
+    )
+}
+
+/// Converts a Sald value for passing to JS.
+pub fn to_js(value: &Value) -> JsValue {
+    if let Some(object) = object_of(value) {
+        return object;
+    }
+    match value {
+        Value::Null => JsValue::NULL,
+        Value::Boolean(b) => JsValue::from_bool(*b),
+        Value::Number(n) => JsValue::from_f64(*n),
+        Value::String(s) => JsValue::from_str(s),
+        Value::Array(items) => items.borrow().iter().map(to_js).collect::<Array>().into(),
+        Value::Dictionary(entries) => {
+            let object = Object::new();
+            for (key, value) in entries.borrow().iter() {
+                let _ = Reflect::set(&object, &JsValue::from_str(key), &to_js(value));
+            }
+            object.into()
+        }
+        callee if is_callable(callee) => to_js_function(callee.clone()),
+        other => JsValue::from_str(&other.to_string()),
+    }
+}
+
+/// Converts a JS value for use in Sald.
+pub fn from_js(value: JsValue) -> Value {
+    if value.is_null() || value.is_undefined() {
+        return Value::Null;
+    }
+    if let Some(b) = value.as_bool() {
+        return Value::Boolean(b);
+    }
+    if let Some(n) = value.as_f64() {
+        return Value::Number(n);
+    }
+    if let Some(s) = value.as_string() {
+        return Value::String(Rc::from(s));
+    }
+    if Array::is_array(&value) {
+        let items = Array::from(&value).iter().map(from_js).collect();
+        return Value::Array(Rc::new(RefCell::new(items)));
+    }
+    wrap_object(value)
+}
+
+/// Deep-copies plain JS data (primitives, arrays and plain objects).
+pub(crate) fn to_send(value: &JsValue) -> Result<SendValue, String> {
+    if value.is_null() || value.is_undefined() {
+        return Ok(SendValue::Null);
+    }
+    if let Some(b) = value.as_bool() {
+        return Ok(SendValue::Boolean(b));
+    }
+    if let Some(n) = value.as_f64() {
+        return Ok(SendValue::Number(n));
+    }
+    if let Some(s) = value.as_string() {
+        return Ok(SendValue::String(s));
+    }
+    if Array::is_array(value) {
+        return Array::from(value)
+            .iter()
+            .map(|item| to_send(&item))
+            .collect::<Result<_, _>>()
+            .map(SendValue::Array);
+    }
+    if value.is_function() {
+        return Err("Expected plain data, got a JS function".to_string());
+    }
+    let mut entries = HashMap::new();
+    for entry in Object::entries(value.unchecked_ref()).iter() {
+        let entry = Array::from(&entry);
+        if let Some(key) = entry.get(0).as_string() {
+            entries.insert(key, to_send(&entry.get(1))?);
+        }
+    }
+    Ok(SendValue::Dictionary(entries))
+}
+
+/// Makes `caller` the VM that JS callbacks use while `f` runs.
+pub(crate) fn with_caller<T>(
+    caller: &mut dyn ValueCaller,
+    f: impl FnOnce(&mut dyn ValueCaller) -> T,
+) -> T {
+    // The pointer is only used until `f` returns, while `caller` is alive
+    let caller: *mut (dyn ValueCaller + '_) = caller;
+    let caller: *mut (dyn ValueCaller + 'static) = unsafe { std::mem::transmute(caller) };
+    CALLERS.with(|callers| callers.borrow_mut().push(caller));
+    let result = f(unsafe { &mut *caller });
+    CALLERS.with(|callers| callers.borrow_mut().pop());
+    result
+}
+
+fn call_sald(callee: &Value, args: Vec<Value>) -> Result<Value, String> {
+    match CALLERS.with(|callers| callers.borrow().last().copied()) {
+        Some(caller) => unsafe { &mut *caller }.call(callee, args),
+        None => event_loop::with_idle_vm(|vm| with_caller(vm, |caller| caller.call(callee, args)))
+            .unwrap_or_else(|| Err("No script is running to handle this callback".to_string())),
+    }
+}
+
+fn to_js_function(callee: Value) -> JsValue {
+    let function = Closure::<dyn Fn(Array) -> Result<JsValue, JsValue>>::new(move |args: Array| {
+        let args = args.iter().map(from_js).collect();
+        call_sald(&callee, args)
+            .map(|result| to_js(&result))
+            .map_err(|e| js_sys::Error::new(&e).into())
+    })
+    .into_js_value();
+    SPREAD.with(|spread| spread.call1(&JsValue::NULL, &function).unwrap_or(function))
+}
+
+/// Calls `function` with `this` set to `this` and Sald `args`.
+pub(crate) fn apply(function: &JsValue, this: &JsValue, args: &[Value]) -> Result<Value, String> {
+    let function = function
+        .dyn_ref::<Function>()
+        .ok_or_else(|| "JS value is not a function".to_string())?;
+    let args: Array = args.iter().map(to_js).collect();
+    function.apply(this, &args).map(from_js).map_err(js_error)
+}
+
+/// Makes `value` a global in every script run from now on.
+#[wasm_bindgen]
+pub fn set_global(name: &str, value: JsValue) {
+    EXPOSED.with(|exposed| {
+        let mut exposed = exposed.borrow_mut();
+        exposed.retain(|(existing, _)| existing != name);
+        exposed.push((name.to_string(), value));
+    });
+}
+
+pub(crate) fn install_globals(vm: &mut VM) {
+    let globals = vm.get_shared_globals();
+    EXPOSED.with(|exposed| {
+        for (name, value) in exposed.borrow().iter() {
+            globals
+                .borrow_mut()
+                .insert(name.clone(), from_js(value.clone()));
+        }
+    });
+}
@@ -0,0 +1,540 @@
+//! Browser bindings
+//! Scripts run against a virtual filesystem, import other scripts from it and
+//! talk to the page through console hooks. The filesystem is in-memory by
+//! default; hosts can swap in their own callbacks with `use_host_fs`, or
+//! persist the in-memory one (for example to IndexedDB) through `fs_snapshot`
+//! and `fs_restore`.
+
+mod event_loop;
+mod interop;
+mod playground;
+
+pub use event_loop::spawn_future;
+pub(crate) use event_loop::{clear_timer, delay, set_timer, timer_active};
+pub(crate) use interop::{apply, object_of, to_send, with_caller};
+pub use interop::{from_js, set_global, to_js};
+pub use playground::{check_code, run_script, run_script_async};
+
+use crate::compiler::{Chunk, Compiler};
+use crate::lexer::Scanner;
+use crate::parser::Parser;
+use crate::vm::{ModuleLoader, ModuleSource, Value};
+use js_sys::{Array, Function, Object, Promise, Reflect, Uint8Array};
+use rustc_hash::FxHashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use wasm_bindgen::prelude::*;
+
+/// Storage behind the `File` builtin. Paths are absolute and normalized.
+pub trait VirtualFs {
+    fn read(&self, path: &str) -> Result<Vec<u8>, String>;
+    fn write(&mut self, path: &str, data: &[u8]) -> Result<(), String>;
+    fn is_file(&self, path: &str) -> bool;
+    fn is_dir(&self, path: &str) -> bool;
+    fn remove(&mut self, path: &str) -> Result<(), String>;
+    fn mkdir(&mut self, path: &str) -> Result<(), String>;
+    /// Names of the direct children of a directory
+    fn read_dir(&self, path: &str) -> Result<Vec<String>, String>;
+
+    fn append(&mut self, path: &str, data: &[u8]) -> Result<(), String> {
+        let mut content = if self.is_file(path) {
+            self.read(path)?
+        } else {
+            Vec::new()
+        };
+        content.extend_from_slice(data);
+        self.write(path, &content)
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> Result<(), String> {
+        let content = self.read(from)?;
+        self.write(to, &content)?;
+        self.remove(from)
+    }
+}
+
+/// Files held in memory; lost on reload unless snapshotted by the host.
+#[derive(Debug, Clone)]
+pub struct MemoryFs {
+    files: BTreeMap<String, Vec<u8>>,
+    dirs: BTreeSet<String>,
+}
+
+impl Default for MemoryFs {
+    fn default() -> Self {
+        Self {
+            files: BTreeMap::new(),
+            dirs: BTreeSet::from(["/".to_string()]),
+        }
+    }
+}
+
+impl MemoryFs {
+    fn ensure_parents(&mut self, path: &str) {
+        let mut parent = parent_of(path);
+        while let Some(dir) = parent {
+            if !self.dirs.insert(dir.clone()) {
+                break;
+            }
+            parent = parent_of(&dir);
+        }
+    }
+}
+
+impl VirtualFs for MemoryFs {
+    fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| format!("No such file: {}", path))
+    }
+
+    fn write(&mut self, path: &str, data: &[u8]) -> Result<(), String> {
+        if self.dirs.contains(path) {
+            return Err(format!("Is a directory: {}", path));
+        }
+        self.ensure_parents(path);
+        self.files.insert(path.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn is_file(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        self.dirs.contains(path)
+    }
+
+    fn remove(&mut self, path: &str) -> Result<(), String> {
+        if self.files.remove(path).is_some() {
+            return Ok(());
+        }
+        if !self.dirs.contains(path) {
+            return Err(format!("No such file or directory: {}", path));
+        }
+        if !self.read_dir(path)?.is_empty() {
+            return Err(format!("Directory not empty: {}", path));
+        }
+        if path != "/" {
+            self.dirs.remove(path);
+        }
+        Ok(())
+    }
+
+    fn mkdir(&mut self, path: &str) -> Result<(), String> {
+        if self.files.contains_key(path) {
+            return Err(format!("File exists: {}", path));
+        }
+        self.ensure_parents(path);
+        self.dirs.insert(path.to_string());
+        Ok(())
+    }
+
+    fn read_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        if !self.dirs.contains(path) {
+            return Err(format!("No such directory: {}", path));
+        }
+        let children = self
+            .files
+            .keys()
+            .chain(self.dirs.iter())
+            .filter(|p| parent_of(p).as_deref() == Some(path))
+            .map(|p| p.rsplit('/').next().unwrap_or_default().to_string())
+            .collect::<BTreeSet<_>>();
+        Ok(children.into_iter().collect())
+    }
+}
+
+/// Filesystem implemented by the host page. `callbacks` is an object with
+/// `read(path)` returning a string, `Uint8Array` or null, `write(path, bytes)`,
+/// `stat(path)` returning `"file"`, `"dir"` or null, `remove(path)`,
+/// `mkdir(path)` and `list(path)` returning an array of names.
+pub struct HostFs {
+    callbacks: Object,
+}
+
+impl HostFs {
+    fn call(&self, name: &str, args: &[JsValue]) -> Result<JsValue, String> {
+        let func: Function = Reflect::get(&self.callbacks, &JsValue::from_str(name))
+            .ok()
+            .and_then(|f| f.dyn_into().ok())
+            .ok_or_else(|| format!("Host filesystem has no '{}' callback", name))?;
+        let args: Array = args.iter().collect();
+        func.apply(&JsValue::NULL, &args).map_err(js_error)
+    }
+
+    fn stat(&self, path: &str) -> Option<String> {
+        self.call("stat", &[JsValue::from_str(path)])
+            .ok()
+            .and_then(|v| v.as_string())
+    }
+}
+
+impl VirtualFs for HostFs {
+    fn read(&self, path: &str) -> Result<Vec<u8>, String> {
+        let value = self.call("read", &[JsValue::from_str(path)])?;
+        if let Some(text) = value.as_string() {
+            return Ok(text.into_bytes());
+        }
+        if value.is_null() || value.is_undefined() {
+            return Err(format!("No such file: {}", path));
+        }
+        Ok(Uint8Array::new(&value).to_vec())
+    }
+
+    fn write(&mut self, path: &str, data: &[u8]) -> Result<(), String> {
+        let bytes = Uint8Array::from(data);
+        self.call("write", &[JsValue::from_str(path), bytes.into()])
+            .map(|_| ())
+    }
+
+    fn is_file(&self, path: &str) -> bool {
+        self.stat(path).as_deref() == Some("file")
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        self.stat(path).as_deref() == Some("dir")
+    }
+
+    fn remove(&mut self, path: &str) -> Result<(), String> {
+        self.call("remove", &[JsValue::from_str(path)]).map(|_| ())
+    }
+
+    fn mkdir(&mut self, path: &str) -> Result<(), String> {
+        self.call("mkdir", &[JsValue::from_str(path)]).map(|_| ())
+    }
+
+    fn read_dir(&self, path: &str) -> Result<Vec<String>, String> {
+        let value = self.call("list", &[JsValue::from_str(path)])?;
+        Ok(Array::from(&value)
+            .iter()
+            .filter_map(|v| v.as_string())
+            .collect())
+    }
+}
+
+/// Imports modules from the virtual filesystem. `./` and `../` specifiers
+/// resolve against the importing file, others against the current directory.
+pub struct VirtualFsLoader;
+
+impl ModuleLoader for VirtualFsLoader {
+    fn resolve(&self, specifier: &str, importer: &str) -> Result<String, String> {
+        let relative = specifier.starts_with("./") || specifier.starts_with("../");
+        let path = match parent_of(importer) {
+            Some(dir) if relative && importer.starts_with('/') => {
+                normalize_path(&format!("{}/{}", dir, specifier))
+            }
+            _ => normalize_path(specifier),
+        };
+        let path = if path.ends_with(".sald") {
+            path
+        } else {
+            format!("{}.sald", path)
+        };
+        if with_filesystem(|fs| fs.is_file(&path)) {
+            Ok(path)
+        } else {
+            Err(format!("Module '{}' not found: {}", specifier, path))
+        }
+    }
+
+    fn load(&self, id: &str) -> Result<ModuleSource, String> {
+        let data = with_filesystem(|fs| fs.read(id))?;
+        String::from_utf8(data)
+            .map(ModuleSource::Source)
+            .map_err(|_| format!("Module '{}' is not valid UTF-8", id))
+    }
+}
+
+thread_local! {
+    static FILESYSTEM: RefCell<Box<dyn VirtualFs>> = RefCell::new(Box::new(MemoryFs::default()));
+    static CWD: RefCell<String> = RefCell::new("/".to_string());
+    static STDOUT: RefCell<String> = const { RefCell::new(String::new()) };
+    static STDOUT_HOOK: RefCell<Option<Function>> = const { RefCell::new(None) };
+    static STDERR_HOOK: RefCell<Option<Function>> = const { RefCell::new(None) };
+    static STDIN_HOOK: RefCell<Option<Function>> = const { RefCell::new(None) };
+    static ARGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static ENV: RefCell<FxHashMap<String, String>> = RefCell::new(FxHashMap::default());
+}
+
+pub(crate) fn js_error(error: JsValue) -> String {
+    error
+        .as_string()
+        .or_else(|| {
+            Reflect::get(&error, &JsValue::from_str("message"))
+                .ok()
+                .and_then(|m| m.as_string())
+        })
+        .unwrap_or_else(|| "Host callback failed".to_string())
+}
+
+/// Replaces the filesystem scripts see.
+pub fn set_filesystem(fs: impl VirtualFs + 'static) {
+    FILESYSTEM.with(|current| *current.borrow_mut() = Box::new(fs));
+}
+
+pub fn with_filesystem<T>(f: impl FnOnce(&mut dyn VirtualFs) -> T) -> T {
+    FILESYSTEM.with(|fs| f(fs.borrow_mut().as_mut()))
+}
+
+/// Makes `path` absolute against the current directory and folds `.`/`..`.
+pub fn normalize_path(path: &str) -> String {
+    let joined = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        CWD.with(|cwd| format!("{}/{}", cwd.borrow(), path))
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in joined.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+fn parent_of(path: &str) -> Option<String> {
+    if path == "/" {
+        return None;
+    }
+    match path.rfind('/') {
+        Some(0) => Some("/".to_string()),
+        Some(i) => Some(path[..i].to_string()),
+        None => None,
+    }
+}
+
+pub fn current_dir() -> String {
+    CWD.with(|cwd| cwd.borrow().clone())
+}
+
+pub fn set_current_dir(path: &str) -> Result<(), String> {
+    let path = normalize_path(path);
+    if !with_filesystem(|fs| fs.is_dir(&path)) {
+        return Err(format!("No such directory: {}", path));
+    }
+    CWD.with(|cwd| *cwd.borrow_mut() = path);
+    Ok(())
+}
+
+/// Sends text to the stdout hook, or buffers it for `run_code`'s result.
+pub fn write_stdout(text: &str) {
+    let hook = STDOUT_HOOK.with(|hook| hook.borrow().clone());
+    match hook {
+        Some(hook) => {
+            let _ = hook.call1(&JsValue::NULL, &JsValue::from_str(text));
+        }
+        None => STDOUT.with(|out| out.borrow_mut().push_str(text)),
+    }
+}
+
+/// Sends text to the stderr hook, falling back to stdout.
+pub fn write_stderr(text: &str) {
+    let hook = STDERR_HOOK.with(|hook| hook.borrow().clone());
+    match hook {
+        Some(hook) => {
+            let _ = hook.call1(&JsValue::NULL, &JsValue::from_str(text));
+        }
+        None => write_stdout(text),
+    }
+}
+
+/// Asks the stdin hook for a line; `None` when the host installed none.
+pub fn read_line(prompt: &str) -> Option<Result<String, String>> {
+    let hook = STDIN_HOOK.with(|hook| hook.borrow().clone())?;
+    Some(
+        hook.call1(&JsValue::NULL, &JsValue::from_str(prompt))
+            .map(|line| line.as_string().unwrap_or_default())
+            .map_err(js_error),
+    )
+}
+
+pub fn script_args() -> Vec<String> {
+    ARGS.with(|args| args.borrow().clone())
+}
+
+pub fn env_var(name: &str) -> Option<String> {
+    ENV.with(|env| env.borrow().get(name).cloned())
+}
+
+fn take_output() -> String {
+    STDOUT.with(|out| {
+        let output = std::mem::take(&mut *out.borrow_mut());
+        output.trim_end_matches('\n').to_string()
+    })
+}
+
+#[wasm_bindgen]
+pub fn run_code(source: &str) -> String {
+    take_output();
+    let result = compile(source).and_then(|chunk| {
+        let vm = event_loop::new_vm();
+        let result = vm.borrow_mut().run(&chunk).map_err(|e| e.message);
+        result
+    });
+    format_result(result)
+}
+
+/// Like `run_code`, but resolves once the script and everything it awaits or
+/// schedules with `Timer` has finished.
+#[wasm_bindgen]
+pub fn run_code_async(source: String) -> Promise {
+    wasm_bindgen_futures::future_to_promise(async move {
+        take_output();
+        let result = match compile(&source) {
+            Ok(chunk) => event_loop::run_to_completion(&chunk)
+                .await
+                .map_err(|e| e.message),
+            Err(e) => Err(e),
+        };
+        Ok(JsValue::from_str(&format_result(result)))
+    })
+}
+
+fn compile(source: &str) -> Result<Chunk, String> {
+    let mut scanner = Scanner::new(source, "<wasm>");
+    let tokens = scanner.scan_tokens().map_err(|e| e.message)?;
+
+    let mut parser = Parser::new(tokens, "<wasm>", source);
+    let ast = parser.parse().map_err(|e| e.message)?;
+
+    let mut compiler = Compiler::new("<wasm>", source);
+    compiler.compile(&ast).map_err(|e| e.message)
+}
+
+fn format_result(result: Result<Value, String>) -> String {
+    let output = take_output();
+    match result {
+        Ok(value) => {
+            let result = value.to_string();
+            if output.is_empty() {
+                result
+            } else if result.is_empty() || result == "null" {
+                output
+            } else {
+                format!("{}\n=> {}", output, result)
+            }
+        }
+        Err(e) => {
+            if output.is_empty() {
+                format!("Error: {}", e)
+            } else {
+                format!("{}\nError: {}", output, e)
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+/// Streams `Console.print`/`println` output to `hook(text)` instead of
+/// collecting it into `run_code`'s result. Pass `null` to restore buffering.
+#[wasm_bindgen]
+pub fn set_stdout_hook(hook: Option<Function>) {
+    STDOUT_HOOK.with(|current| *current.borrow_mut() = hook);
+}
+
+#[wasm_bindgen]
+pub fn set_stderr_hook(hook: Option<Function>) {
+    STDERR_HOOK.with(|current| *current.borrow_mut() = hook);
+}
+
+/// `hook(prompt)` answers `Console.input`, e.g. with `window.prompt`.
+#[wasm_bindgen]
+pub fn set_stdin_hook(hook: Option<Function>) {
+    STDIN_HOOK.with(|current| *current.borrow_mut() = hook);
+}
+
+#[wasm_bindgen]
+pub fn set_args(args: Vec<String>) {
+    ARGS.with(|current| *current.borrow_mut() = args);
+}
+
+/// Variables `Process.env` sees, from a plain `{ NAME: "value" }` object.
+#[wasm_bindgen]
+pub fn set_env(vars: &Object) {
+    let vars = Object::entries(vars)
+        .iter()
+        .filter_map(|entry| {
+            let entry = Array::from(&entry);
+            Some((entry.get(0).as_string()?, entry.get(1).as_string()?))
+        })
+        .collect();
+    ENV.with(|current| *current.borrow_mut() = vars);
+}
+
+/// Switches to a fresh, empty in-memory filesystem.
+#[wasm_bindgen]
+pub fn use_memory_fs() {
+    set_filesystem(MemoryFs::default());
+    CWD.with(|cwd| *cwd.borrow_mut() = "/".to_string());
+}
+
+/// Routes all file access through the host's callbacks; see [`HostFs`].
+#[wasm_bindgen]
+pub fn use_host_fs(callbacks: Object) {
+    set_filesystem(HostFs { callbacks });
+}
+
+#[wasm_bindgen]
+pub fn fs_write(path: &str, data: &[u8]) -> Result<(), JsValue> {
+    let path = normalize_path(path);
+    with_filesystem(|fs| fs.write(&path, data)).map_err(|e| JsValue::from_str(&e))
+}
+
+#[wasm_bindgen]
+pub fn fs_read(path: &str) -> Option<Vec<u8>> {
+    let path = normalize_path(path);
+    with_filesystem(|fs| fs.read(&path)).ok()
+}
+
+/// Every file as `{ path: Uint8Array }`, ready to store in IndexedDB.
+#[wasm_bindgen]
+pub fn fs_snapshot() -> Result<Object, JsValue> {
+    let snapshot = Object::new();
+    let mut pending = vec!["/".to_string()];
+    while let Some(dir) = pending.pop() {
+        let children =
+            with_filesystem(|fs| fs.read_dir(&dir)).map_err(|e| JsValue::from_str(&e))?;
+        for name in children {
+            let path = normalize_path(&format!("{}/{}", dir, name));
+            if with_filesystem(|fs| fs.is_dir(&path)) {
+                pending.push(path);
+                continue;
+            }
+            let data = with_filesystem(|fs| fs.read(&path)).map_err(|e| JsValue::from_str(&e))?;
+            Reflect::set(
+                &snapshot,
+                &JsValue::from_str(&path),
+                &Uint8Array::from(data.as_slice()),
+            )?;
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Writes back files saved by [`fs_snapshot`]; values may also be strings.
+#[wasm_bindgen]
+pub fn fs_restore(snapshot: &Object) -> Result<(), JsValue> {
+    for entry in Object::entries(snapshot).iter() {
+        let entry = Array::from(&entry);
+        let Some(path) = entry.get(0).as_string() else {
+            continue;
+        };
+        let value = entry.get(1);
+        let data = match value.as_string() {
+            Some(text) => text.into_bytes(),
+            None => Uint8Array::new(&value).to_vec(),
+        };
+        fs_write(&path, &data)?;
+    }
+    Ok(())
+}
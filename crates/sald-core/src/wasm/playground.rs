@@ -0,0 +1,162 @@
+//! Structured results for browser editors
+//! `run_script` reports output, the value of the last expression, timing and
+//! diagnostics with spans as a plain object, and `check_code` reports
+//! diagnostics without running anything, for inline squiggles while typing.
+
+use super::{event_loop, take_output, to_js};
+use crate::compiler::{Chunk, Compiler};
+use crate::error::{Position, SaldError};
+use crate::lexer::Scanner;
+use crate::parser::Parser;
+use crate::vm::Value;
+use js_sys::{Array, Object, Promise, Reflect};
+use wasm_bindgen::prelude::*;
+
+const FILE: &str = "<wasm>";
+
+fn set(object: &Object, key: &str, value: impl Into<JsValue>) {
+    let _ = Reflect::set(object, &JsValue::from_str(key), &value.into());
+}
+
+fn now() -> f64 {
+    let performance = Reflect::get(&js_sys::global(), &JsValue::from_str("performance"));
+    performance
+        .ok()
+        .and_then(|p| {
+            let now = Reflect::get(&p, &JsValue::from_str("now")).ok()?;
+            now.dyn_ref::<js_sys::Function>()?.call0(&p).ok()?.as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+fn position(pos: &Position) -> Object {
+    let object = Object::new();
+    set(&object, "line", pos.line as f64);
+    set(&object, "column", pos.column as f64);
+    set(&object, "offset", pos.offset as f64);
+    object
+}
+
+/// `{severity, kind, code, message, help, file, start, end, stack}`, with
+/// 1-based lines and columns.
+fn diagnostic(error: &SaldError) -> Object {
+    let object = Object::new();
+    set(&object, "severity", "error");
+    set(&object, "kind", error.kind.to_string());
+    set(&object, "code", error.kind.code());
+    set(&object, "message", error.message.as_str());
+    set(
+        &object,
+        "help",
+        error
+            .help
+            .as_deref()
+            .map_or(JsValue::NULL, JsValue::from_str),
+    );
+    set(&object, "file", error.file.as_str());
+    set(&object, "start", position(&error.span.start));
+    set(&object, "end", position(&error.span.end));
+
+    let stack: Array = error
+        .stack_trace
+        .iter()
+        .map(|frame| {
+            let entry = Object::new();
+            set(&entry, "function", frame.function_name.as_str());
+            set(&entry, "file", frame.file.as_str());
+            set(&entry, "line", frame.line as f64);
+            set(&entry, "column", frame.column as f64);
+            JsValue::from(entry)
+        })
+        .collect();
+    set(&object, "stack", stack);
+    object
+}
+
+fn diagnostics(errors: &[SaldError]) -> Array {
+    errors
+        .iter()
+        .map(|e| JsValue::from(diagnostic(e)))
+        .collect()
+}
+
+/// Compiles in REPL mode so the last expression becomes the result, first
+/// collecting every compile error rather than stopping at the first.
+fn compile(source: &str) -> Result<Chunk, Vec<SaldError>> {
+    let tokens = Scanner::new(source, FILE)
+        .scan_tokens()
+        .map_err(|e| vec![e])?;
+    let program = Parser::new(tokens, FILE, source)
+        .parse()
+        .map_err(|e| vec![e])?;
+
+    let errors = Compiler::new(FILE, source).check(&program);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Compiler::new(FILE, source)
+        .compile_repl(&program)
+        .map_err(|e| vec![e])
+}
+
+fn report(result: Result<Value, Vec<SaldError>>, started: f64) -> Object {
+    let report = Object::new();
+    set(&report, "ok", result.is_ok());
+    set(&report, "stdout", take_output());
+    match &result {
+        Ok(value) => {
+            set(&report, "value", to_js(value));
+            set(&report, "valueText", value.to_string());
+            set(&report, "valueType", value.type_name());
+            set(&report, "diagnostics", Array::new());
+        }
+        Err(errors) => {
+            set(&report, "value", JsValue::NULL);
+            set(&report, "valueText", JsValue::NULL);
+            set(&report, "valueType", JsValue::NULL);
+            set(&report, "diagnostics", diagnostics(errors));
+        }
+    }
+    set(&report, "timeMs", now() - started);
+    report
+}
+
+/// Diagnostics for `source` without running it.
+#[wasm_bindgen]
+pub fn check_code(source: &str) -> Array {
+    match compile(source) {
+        Ok(_) => Array::new(),
+        Err(errors) => diagnostics(&errors),
+    }
+}
+
+/// Runs `source` and returns `{ok, stdout, value, valueText, valueType,
+/// diagnostics, timeMs}`.
+#[wasm_bindgen]
+pub fn run_script(source: &str) -> Object {
+    take_output();
+    let started = now();
+    let result = compile(source).and_then(|chunk| {
+        let vm = event_loop::new_vm();
+        let result = vm.borrow_mut().run(&chunk).map_err(|e| vec![e]);
+        result
+    });
+    report(result, started)
+}
+
+/// `run_script` for scripts that `await` or schedule timers; resolves with
+/// the same object once everything has finished.
+#[wasm_bindgen]
+pub fn run_script_async(source: String) -> Promise {
+    wasm_bindgen_futures::future_to_promise(async move {
+        take_output();
+        let started = now();
+        let result = match compile(&source) {
+            Ok(chunk) => event_loop::run_to_completion(&chunk)
+                .await
+                .map_err(|e| vec![e]),
+            Err(errors) => Err(errors),
+        };
+        Ok(report(result, started).into())
+    })
+}
@@ -0,0 +1,189 @@
+//! Where relative paths resolve
+//! Each VM owns a `Workspace`: its project root plus the directories of the
+//! packages currently being imported. While a VM executes, its workspace is
+//! the current one for the thread, so natives that take paths resolve them
+//! against the right project without needing the VM.
+
+use parking_lot::RwLock;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Project root given to VMs created without one
+static DEFAULT_PROJECT_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+thread_local! {
+    /// Workspaces of the VMs executing on this thread, innermost last
+    static CURRENT: RefCell<Vec<Rc<RefCell<Workspace>>>> = const { RefCell::new(Vec::new()) };
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Sets the project root used by VMs created from now on.
+pub fn set_default_project_root(path: &Path) {
+    *DEFAULT_PROJECT_ROOT.write() = Some(canonical(path));
+}
+
+pub fn default_project_root() -> Option<PathBuf> {
+    DEFAULT_PROJECT_ROOT.read().clone()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    project_root: Option<PathBuf>,
+    module_stack: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// A workspace rooted at the default project root, if one is set.
+    pub fn new() -> Self {
+        Self {
+            project_root: default_project_root(),
+            module_stack: Vec::new(),
+        }
+    }
+
+    pub fn with_project_root(path: &Path) -> Self {
+        Self {
+            project_root: Some(canonical(path)),
+            module_stack: Vec::new(),
+        }
+    }
+
+    pub fn project_root(&self) -> Option<&Path> {
+        self.project_root.as_deref()
+    }
+
+    pub fn set_project_root(&mut self, path: &Path) {
+        self.project_root = Some(canonical(path));
+    }
+
+    /// Makes a package's directory the base for relative paths until the
+    /// matching `pop_module`.
+    pub fn push_module(&mut self, dir: &Path) {
+        self.module_stack.push(canonical(dir));
+    }
+
+    pub fn pop_module(&mut self) {
+        self.module_stack.pop();
+    }
+
+    /// Directory relative paths resolve against: the package being imported,
+    /// then the project root, then the process working directory.
+    pub fn current_dir(&self) -> PathBuf {
+        if let Some(dir) = self.module_stack.last().or(self.project_root.as_ref()) {
+            return dir.clone();
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            PathBuf::from("/virtual")
+        }
+    }
+
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        let path_buf = PathBuf::from(path);
+        if path_buf.is_absolute() {
+            return path_buf;
+        }
+        self.current_dir().join(path)
+    }
+}
+
+impl Default for Workspace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps a workspace current until dropped
+pub struct Entered(());
+
+impl Drop for Entered {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.borrow_mut().pop());
+    }
+}
+
+/// Makes `workspace` the thread's current workspace until the guard drops.
+pub fn enter(workspace: &Rc<RefCell<Workspace>>) -> Entered {
+    CURRENT.with(|current| current.borrow_mut().push(workspace.clone()));
+    Entered(())
+}
+
+/// The workspace of the innermost VM executing on this thread, or a fresh
+/// default one when no VM is running.
+pub fn current() -> Workspace {
+    CURRENT
+        .with(|current| current.borrow().last().map(|w| w.borrow().clone()))
+        .unwrap_or_default()
+}
+
+/// Runs `f` on the innermost executing VM's workspace, if there is one.
+pub fn with_current<T>(f: impl FnOnce(&mut Workspace) -> T) -> Option<T> {
+    let workspace = CURRENT.with(|current| current.borrow().last().cloned())?;
+    let mut workspace = workspace.borrow_mut();
+    Some(f(&mut workspace))
+}
+
+/// Resolves a path a script passed to a native against the current workspace.
+pub fn resolve(path: &str) -> PathBuf {
+    current().resolve(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Engine;
+
+    #[test]
+    fn test_project_root_is_per_vm() {
+        let base = std::env::temp_dir().join(format!("sald_roots_{}", std::process::id()));
+        let mut engines = Vec::new();
+        for name in ["one", "two"] {
+            let root = base.join(name);
+            let package = root.join("sald_modules/cfg");
+            std::fs::create_dir_all(&package).unwrap();
+            std::fs::write(
+                package.join("salad.json"),
+                r#"{"name": "cfg", "version": "1.0.0", "main": "cfg.sald"}"#,
+            )
+            .unwrap();
+            std::fs::write(package.join("cfg.sald"), format!("let name = \"{}\"", name)).unwrap();
+            std::fs::write(root.join("note.txt"), format!("{} note", name)).unwrap();
+
+            let mut engine = Engine::new();
+            engine.vm().set_project_root(&root);
+            engines.push(engine);
+        }
+
+        for engine in engines.iter_mut() {
+            engine.eval("import \"cfg\" as cfg").unwrap();
+        }
+        let results: Vec<(String, String)> = engines
+            .iter_mut()
+            .map(|engine| {
+                engine
+                    .eval_as("[cfg.name, File.read(\"note.txt\")]")
+                    .unwrap()
+            })
+            .collect();
+        std::fs::remove_dir_all(&base).ok();
+
+        assert_eq!(
+            results,
+            [
+                ("one".to_string(), "one note".to_string()),
+                ("two".to_string(), "two note".to_string())
+            ]
+        );
+        assert_eq!(
+            crate::workspace::current(),
+            crate::workspace::Workspace::new()
+        );
+    }
+}
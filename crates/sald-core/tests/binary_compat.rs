@@ -0,0 +1,156 @@
+// Loader compatibility gate for compiled .saldc artifacts.
+//
+// Every `tests/corpus/v<N>/` directory holds binaries produced by the release
+// that introduced format version N. They must keep loading and produce the
+// recorded `result` global. Files in `tests/corpus/rejected/` must fail with
+// the documented error message instead of panicking or running garbage.
+
+use sald_core::binary;
+use sald_core::compiler::chunk::Constant;
+use sald_core::vm::VM;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus")
+}
+
+fn artifacts_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "saldc"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn expected_for(artifact: &Path) -> String {
+    fs::read_to_string(artifact.with_extension("expected"))
+        .unwrap()
+        .trim_end()
+        .to_string()
+}
+
+#[test]
+fn versioned_artifacts_still_run() {
+    let mut checked = 0;
+    for entry in fs::read_dir(corpus_dir()).unwrap() {
+        let dir = entry.unwrap().path();
+        let is_version_dir = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('v'));
+        if !is_version_dir {
+            continue;
+        }
+
+        for artifact in artifacts_in(&dir) {
+            let data = fs::read(&artifact).unwrap();
+            let chunk = binary::deserialize(&data)
+                .unwrap_or_else(|e| panic!("{} failed to load: {}", artifact.display(), e));
+
+            let file = artifact.to_string_lossy().to_string();
+            let mut vm = VM::new();
+            vm.run(chunk, &file, "")
+                .unwrap_or_else(|e| panic!("{} failed to run: {}", artifact.display(), e.message));
+
+            let result = vm
+                .get_global("result")
+                .unwrap_or_else(|| panic!("{} did not define 'result'", artifact.display()));
+            assert_eq!(
+                result.to_string(),
+                expected_for(&artifact),
+                "{} produced a different result",
+                artifact.display()
+            );
+            checked += 1;
+        }
+    }
+    assert!(checked > 0, "no corpus artifacts found");
+}
+
+#[test]
+fn lazy_loading_defers_function_bodies() {
+    for artifact in artifacts_in(&corpus_dir().join("v5")) {
+        let data = fs::read(&artifact).unwrap();
+        let chunk = binary::deserialize_lazy(&data).unwrap();
+        let function = chunk.constants.iter().find_map(|constant| match constant {
+            Constant::Function(f) => Some(f.chunk.clone()),
+            _ => None,
+        });
+        assert!(function.as_ref().is_none_or(|f| !f.is_loaded()));
+
+        let mut vm = VM::new();
+        vm.run(chunk, &artifact.to_string_lossy(), "")
+            .unwrap_or_else(|e| panic!("{} failed to run: {}", artifact.display(), e.message));
+        assert!(function.is_none_or(|f| f.is_loaded()));
+        assert_eq!(
+            vm.get_global("result").unwrap().to_string(),
+            expected_for(&artifact),
+            "{}",
+            artifact.display()
+        );
+    }
+}
+
+#[test]
+fn incompatible_artifacts_are_rejected() {
+    let artifacts = artifacts_in(&corpus_dir().join("rejected"));
+    assert!(!artifacts.is_empty(), "no rejected artifacts found");
+
+    for artifact in artifacts {
+        let data = fs::read(&artifact).unwrap();
+        match binary::deserialize(&data) {
+            Ok(_) => panic!("{} should have been rejected", artifact.display()),
+            Err(e) => assert_eq!(e, expected_for(&artifact), "{}", artifact.display()),
+        }
+    }
+}
+
+#[test]
+fn truncated_artifacts_never_panic() {
+    let artifacts = ["v4", "v5"]
+        .iter()
+        .flat_map(|version| artifacts_in(&corpus_dir().join(version)));
+    for artifact in artifacts {
+        let data = fs::read(&artifact).unwrap();
+        for len in 0..data.len() {
+            assert!(
+                binary::deserialize(&data[..len]).is_err(),
+                "{} truncated to {} bytes was accepted",
+                artifact.display(),
+                len
+            );
+        }
+    }
+}
+
+#[test]
+fn standalone_payload_round_trips() {
+    let artifact = corpus_dir().join("v5/core.saldc");
+    let chunk = binary::deserialize(&fs::read(&artifact).unwrap()).unwrap();
+    let runtime = b"not really an executable".to_vec();
+
+    let exe = binary::build_standalone(&runtime, &chunk);
+    assert!(exe.starts_with(&runtime));
+    // Rebuilding from a standalone executable replaces the old program
+    assert_eq!(binary::build_standalone(&exe, &chunk), exe);
+
+    let path = std::env::temp_dir().join(format!("sald-standalone-{}", std::process::id()));
+    fs::write(&path, &exe).unwrap();
+    let embedded = binary::read_standalone(&path)
+        .unwrap()
+        .expect("no embedded program");
+    fs::write(&path, &runtime).unwrap();
+    let plain = binary::read_standalone(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(plain.is_none());
+
+    let mut vm = VM::new();
+    vm.run(embedded, "standalone", "").unwrap();
+    assert_eq!(
+        vm.get_global("result").unwrap().to_string(),
+        expected_for(&artifact)
+    );
+}
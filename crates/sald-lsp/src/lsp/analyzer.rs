@@ -1,14 +1,23 @@
 use rustc_hash::FxHashMap;
 use rustc_hash::FxHashSet;
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Range};
 
 use super::symbols::span_to_range;
 use sald_core::ast::{Expr, FunctionDef, LambdaBody, Pattern, Program, Stmt, SwitchArrayElement};
 use sald_core::error::Span;
 
+/// Diagnostic codes for a `let` that hides or redeclares a variable. Their
+/// `data` is the range of the `let` keyword, which the quick fix deletes to
+/// assign to the existing variable instead.
+pub const SHADOWED_VARIABLE: &str = "shadowed-variable";
+pub const REDECLARED_VARIABLE: &str = "redeclared-variable";
+
 #[derive(Debug, Clone)]
 struct Scope {
     variables: FxHashMap<String, VarInfo>,
+    /// Outermost scope of a function, lambda or namespace; shadowing across
+    /// it is treated as intentional
+    is_function: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -35,7 +44,9 @@ impl SemanticAnalyzer {
         for cls in &[
             "Console", "Math", "File", "Timer", "Date", "Json", "Path", "Process", "Http", "Type",
             "System", "Array", "Dict", "String", "Ffi", "Number", "Boolean", "Regex", "Channel",
-            "Promise", "Crypto",
+            "Promise", "Crypto", "Kv", "Yaml", "Toml", "MsgPack", "Compress", "Archive",
+            "Encoding", "Uuid", "Random", "Cron", "Term", "Readline", "Args", "Set", "Map",
+            "Counter", "Deque", "Heap", "Iter", "Matrix",
         ] {
             defined_classes.insert(cls.to_string());
         }
@@ -43,6 +54,7 @@ impl SemanticAnalyzer {
         Self {
             scopes: vec![Scope {
                 variables: FxHashMap::default(),
+                is_function: true,
             }],
             diagnostics: Vec::new(),
             defined_classes,
@@ -120,7 +132,7 @@ impl SemanticAnalyzer {
     }
 
     fn collect_declaration(&mut self, stmt: &Stmt) {
-        match stmt {
+        match stmt.without_cfg() {
             Stmt::Function { def } => {
                 self.defined_functions.insert(def.name.clone());
             }
@@ -150,6 +162,14 @@ impl SemanticAnalyzer {
     fn push_scope(&mut self) {
         self.scopes.push(Scope {
             variables: FxHashMap::default(),
+            is_function: false,
+        });
+    }
+
+    fn push_function_scope(&mut self) {
+        self.scopes.push(Scope {
+            variables: FxHashMap::default(),
+            is_function: true,
         });
     }
 
@@ -170,6 +190,63 @@ impl SemanticAnalyzer {
         self.scopes.pop();
     }
 
+    /// Warns about a `let` that hides a variable of an enclosing
+    /// block in the same function, or redeclares a top-level variable.
+    /// Redeclarations in nested scopes are compile errors reported
+    /// elsewhere.
+    fn check_redeclaration(&mut self, name: &str, name_span: &Span, let_span: &Span) {
+        if name.starts_with('_') {
+            return;
+        }
+        let current = self.scopes.len() - 1;
+        let mut found = None;
+        for (index, scope) in self.scopes.iter().enumerate().rev() {
+            if let Some(info) = scope.variables.get(name) {
+                found = Some((index, info.span));
+                break;
+            }
+            if scope.is_function {
+                break;
+            }
+        }
+        let Some((index, previous)) = found else {
+            return;
+        };
+        if previous == Span::default() || (index == current && current != 0) {
+            return;
+        }
+        let (code, message) = if index == current {
+            (
+                REDECLARED_VARIABLE,
+                format!(
+                    "'{}' is already declared on line {}; this declares it again",
+                    name, previous.start.line
+                ),
+            )
+        } else {
+            (
+                SHADOWED_VARIABLE,
+                format!(
+                    "'{}' shadows the variable declared on line {}",
+                    name, previous.start.line
+                ),
+            )
+        };
+        let keyword = Range {
+            start: span_to_range(let_span).start,
+            end: span_to_range(name_span).start,
+        };
+        self.diagnostics.push(Diagnostic {
+            range: span_to_range(name_span),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(code.to_string())),
+            source: Some("sald".to_string()),
+            message,
+            data: serde_json::to_value(keyword).ok(),
+            ..Default::default()
+        });
+    }
+
     fn define_var(&mut self, name: &str, span: &Span, is_const: bool) {
         self.current_scope_mut().variables.insert(
             name.to_string(),
@@ -210,11 +287,12 @@ impl SemanticAnalyzer {
                 name,
                 name_span,
                 initializer,
-                span: _,
+                span,
             } => {
                 if let Some(val) = initializer {
                     self.analyze_expr(val);
                 }
+                self.check_redeclaration(name, name_span, span);
                 self.define_var(name, name_span, false);
             }
             Stmt::LetDestructure {
@@ -293,6 +371,10 @@ impl SemanticAnalyzer {
                 self.pop_scope();
             }
             Stmt::Function { def } => {
+                // Top-level functions are collected up front
+                if self.scopes.len() > 1 {
+                    self.define_var(&def.name, &def.span, false);
+                }
                 self.analyze_function(def);
             }
             Stmt::Return { value, .. } => {
@@ -324,22 +406,40 @@ impl SemanticAnalyzer {
                 self.analyze_expr(value);
             }
             Stmt::Namespace { body, .. } => {
-                self.push_scope();
+                self.push_function_scope();
                 for s in body {
                     self.analyze_stmt(s);
                 }
                 self.pop_scope();
             }
+            Stmt::Cfg { body, .. } => self.analyze_stmt(body),
+            Stmt::Import {
+                alias: Some(alias),
+                span,
+                ..
+            } if self.scopes.len() > 1 => {
+                // A namespace's private alias
+                self.define_var(alias, span, false);
+            }
             Stmt::Import { .. } => {}
+            Stmt::Export { names, span } => {
+                for name in names {
+                    self.analyze_expr(&Expr::Identifier {
+                        name: name.clone(),
+                        span: *span,
+                    });
+                }
+            }
             Stmt::Break { .. }
             | Stmt::Continue { .. }
+            | Stmt::Debugger { .. }
             | Stmt::Enum { .. }
             | Stmt::Interface { .. } => {}
         }
     }
 
     fn analyze_function(&mut self, def: &FunctionDef) {
-        self.push_scope();
+        self.push_function_scope();
 
         for param in &def.params {
             self.define_var(&param.name, &param.span, false);
@@ -481,7 +581,7 @@ impl SemanticAnalyzer {
             Expr::Lambda {
                 params, body, span, ..
             } => {
-                self.push_scope();
+                self.push_function_scope();
                 for param in params {
                     self.define_var(&param.name, span, false);
                 }
@@ -505,7 +605,7 @@ impl SemanticAnalyzer {
                 self.analyze_expr(then_expr);
                 self.analyze_expr(else_expr);
             }
-            Expr::Await { expr, .. } => {
+            Expr::Await { expr, .. } | Expr::Import { path: expr, .. } => {
                 self.analyze_expr(expr);
             }
             Expr::Switch {
@@ -1,18 +1,27 @@
 // Sald Language Server Backend
 // Implements tower_lsp::LanguageServer trait with full LSP support
 
+use dashmap::DashMap;
 use parking_lot::RwLock;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer};
 
-use super::analyzer::SemanticAnalyzer;
+use super::analyzer::{SemanticAnalyzer, REDECLARED_VARIABLE, SHADOWED_VARIABLE};
 use super::completion::{get_builtin_symbols, get_keyword_completions};
+use super::hierarchy::{self, FileOutline};
 use super::import_resolver::ImportResolver;
+use super::inlay_hints;
+use super::on_type;
+use super::semantic_tokens;
+use super::signature_help::{self, CallContext, FunctionSignature};
 use super::symbols::{span_to_range, Symbol, SymbolKind, SymbolTable, WorkspaceIndex};
-use sald_core::ast::{ClassDef, Expr, FunctionDef, Stmt};
+use sald_core::ast::{ClassDef, Expr, FunctionDef, Program, Stmt};
+use sald_core::builtins::docs::builtin_docs;
+use sald_core::compiler::Compiler;
 use sald_core::lexer::Scanner;
 use sald_core::parser::Parser;
 
@@ -22,6 +31,13 @@ pub struct SaldLanguageServer {
     symbols: Arc<SymbolTable>,
     import_resolver: Arc<RwLock<ImportResolver>>,
     workspace_index: Arc<WorkspaceIndex>,
+    /// Latest text of open documents, including ones that fail to parse
+    open_documents: DashMap<Url, String>,
+    /// Last semantic tokens sent per document, keyed for delta requests
+    semantic_tokens: DashMap<Url, (String, Vec<SemanticToken>)>,
+    next_result_id: AtomicU64,
+    /// Function signatures from the last successful parse of each document
+    signatures: DashMap<Url, rustc_hash::FxHashMap<String, FunctionSignature>>,
 }
 
 impl SaldLanguageServer {
@@ -31,16 +47,34 @@ impl SaldLanguageServer {
             symbols: Arc::new(SymbolTable::new()),
             import_resolver: Arc::new(RwLock::new(ImportResolver::new())),
             workspace_index: Arc::new(WorkspaceIndex::new()),
+            open_documents: DashMap::new(),
+            semantic_tokens: DashMap::new(),
+            next_result_id: AtomicU64::new(1),
+            signatures: DashMap::new(),
         }
     }
 
+    /// Compute semantic tokens for an open document and remember them
+    fn compute_semantic_tokens(&self, uri: &Url) -> Option<(String, Vec<SemanticToken>)> {
+        let text = self.open_documents.get(uri).map(|t| t.clone())?;
+        let tokens = semantic_tokens::semantic_tokens(&text)?;
+        let result_id = self
+            .next_result_id
+            .fetch_add(1, Ordering::Relaxed)
+            .to_string();
+        self.semantic_tokens
+            .insert(uri.clone(), (result_id.clone(), tokens.clone()));
+        Some((result_id, tokens))
+    }
+
     /// Convert URL to file path
     fn url_to_path(uri: &Url) -> Option<PathBuf> {
         uri.to_file_path().ok()
     }
 
     /// Analyze a document and publish diagnostics
-    /// Uses Scanner/Parser for syntax errors and SemanticAnalyzer for semantic errors
+    /// Uses Scanner/Parser for syntax errors, SemanticAnalyzer for semantic errors
+    /// and the Compiler for compile-stage errors
     async fn analyze_document(&self, uri: Url, text: String) {
         let mut diagnostics = Vec::new();
         let mut symbols = Vec::new();
@@ -80,6 +114,8 @@ impl SaldLanguageServer {
         for stmt in &program.statements {
             self.extract_symbols_recursive(stmt, &mut symbols);
         }
+        self.signatures
+            .insert(uri.clone(), signature_help::collect(&program, &text));
 
         // Step 4: Resolve imports and add imported symbols
         if let Some(ref path) = file_path {
@@ -87,6 +123,25 @@ impl SaldLanguageServer {
             let all_symbols = resolver.get_all_symbols_for_document(path, &program, &symbols);
             symbols = all_symbols;
 
+            for stmt in &program.statements {
+                if let Stmt::Import {
+                    path: import_path,
+                    span,
+                    ..
+                } = stmt
+                {
+                    if let Err(message) = resolver.check_import(path, import_path) {
+                        diagnostics.push(Diagnostic {
+                            range: span_to_range(span),
+                            severity: Some(DiagnosticSeverity::ERROR),
+                            source: Some("sald".to_string()),
+                            message,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+
             // Re-index this file for workspace tracking
             self.workspace_index.clear_file_references(path);
             self.index_file(path);
@@ -111,6 +166,9 @@ impl SaldLanguageServer {
         let semantic_diagnostics = analyzer.analyze(&program);
         diagnostics.extend(semantic_diagnostics);
 
+        // Step 6: Compile-stage errors the analyzer does not catch
+        self.add_compile_errors(&program, &file_name, &text, &mut diagnostics);
+
         // Update symbol table
         self.symbols.update_document(uri.clone(), text, symbols);
 
@@ -125,6 +183,26 @@ impl SaldLanguageServer {
             .await;
     }
 
+    /// Add the compiler's errors, such as duplicate locals or `break` outside
+    /// a loop, except where a diagnostic is already reported
+    fn add_compile_errors(
+        &self,
+        program: &Program,
+        file_name: &str,
+        text: &str,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        for e in &Compiler::new(file_name, text).check(program) {
+            let diagnostic = self.error_to_diagnostic(e);
+            if !diagnostics
+                .iter()
+                .any(|d| d.range.start == diagnostic.range.start)
+            {
+                diagnostics.push(diagnostic);
+            }
+        }
+    }
+
     /// Convert SaldError to LSP Diagnostic
     fn error_to_diagnostic(&self, e: &sald_core::error::SaldError) -> Diagnostic {
         Diagnostic {
@@ -138,7 +216,7 @@ impl SaldLanguageServer {
 
     /// Extract symbols recursively from statements
     fn extract_symbols_recursive(&self, stmt: &Stmt, symbols: &mut Vec<Symbol>) {
-        match stmt {
+        match stmt.without_cfg() {
             Stmt::Function { def } => symbols.push(self.function_to_symbol(def)),
             Stmt::Class { def } => symbols.push(self.class_to_symbol(def)),
             Stmt::Let {
@@ -320,7 +398,9 @@ impl SaldLanguageServer {
                 self.extract_expr_symbols(then_expr, symbols);
                 self.extract_expr_symbols(else_expr, symbols);
             }
-            Expr::Await { expr: e, .. } | Expr::Grouping { expr: e, .. } => {
+            Expr::Await { expr: e, .. }
+            | Expr::Grouping { expr: e, .. }
+            | Expr::Import { path: e, .. } => {
                 self.extract_expr_symbols(e, symbols);
             }
             _ => {}
@@ -378,6 +458,9 @@ impl SaldLanguageServer {
                 sald_core::ast::Literal::Number(_) => Some("Number".to_string()),
                 sald_core::ast::Literal::String(_) => Some("String".to_string()),
                 sald_core::ast::Literal::Boolean(_) => Some("Boolean".to_string()),
+                sald_core::ast::Literal::Date(_) | sald_core::ast::Literal::Time(_) => {
+                    Some("Date".to_string())
+                }
                 sald_core::ast::Literal::Null => None,
             },
             Expr::Lambda { .. } => Some("Function".to_string()),
@@ -412,7 +495,7 @@ impl SaldLanguageServer {
             range: span_to_range(&def.span),
             selection_range: span_to_range(&def.span),
             detail: Some(detail),
-            documentation: None,
+            documentation: def.doc.clone(),
             children: Vec::new(),
             type_hint: None,
             source_uri: None,
@@ -438,7 +521,7 @@ impl SaldLanguageServer {
                     range: span_to_range(&m.span),
                     selection_range: span_to_range(&m.span),
                     detail: Some(detail),
-                    documentation: None,
+                    documentation: m.doc.clone(),
                     children: Vec::new(),
                     type_hint: None,
                     source_uri: None,
@@ -464,7 +547,7 @@ impl SaldLanguageServer {
             range: span_to_range(&def.span),
             selection_range: span_to_range(&def.span),
             detail: Some(detail),
-            documentation: None,
+            documentation: def.doc.clone(),
             children,
             type_hint: None,
             source_uri: None,
@@ -744,6 +827,95 @@ impl SaldLanguageServer {
         }
     }
 
+    /// Outlines of every workspace file, using the editor's text for open ones
+    fn workspace_outlines(&self) -> Vec<FileOutline> {
+        let mut uris: Vec<Url> = self
+            .workspace_index
+            .scan_workspace_files()
+            .iter()
+            .filter_map(|path| Url::from_file_path(path).ok())
+            .collect();
+        for entry in self.open_documents.iter() {
+            if !uris.contains(entry.key()) {
+                uris.push(entry.key().clone());
+            }
+        }
+
+        uris.into_iter()
+            .filter_map(|uri| {
+                let text = match self.open_documents.get(&uri) {
+                    Some(text) => text.clone(),
+                    None => std::fs::read_to_string(uri.to_file_path().ok()?).ok()?,
+                };
+                hierarchy::outline(uri, &text)
+            })
+            .collect()
+    }
+
+    /// Identifier under the cursor in an open document
+    fn word_at(&self, uri: &Url, position: Position) -> Option<String> {
+        let text = self.open_documents.get(uri)?;
+        let line: Vec<char> = text.lines().nth(position.line as usize)?.chars().collect();
+        let is_word = |c: &char| c.is_alphanumeric() || *c == '_';
+        let cursor = (position.character as usize).min(line.len());
+        let start = line[..cursor]
+            .iter()
+            .rposition(|c| !is_word(c))
+            .map_or(0, |i| i + 1);
+        let end = line[cursor..]
+            .iter()
+            .position(|c| !is_word(c))
+            .map_or(line.len(), |i| cursor + i);
+        (start < end).then(|| line[start..end].iter().collect())
+    }
+
+    /// Find the signature of the function called in `context`
+    fn resolve_signature(
+        &self,
+        uri: &Url,
+        position: Position,
+        context: &CallContext,
+    ) -> Option<FunctionSignature> {
+        let signatures = self.signatures.get(uri)?;
+        let builtin = |class: &str| {
+            builtin_docs()
+                .into_iter()
+                .find(|doc| doc.name == class)?
+                .methods
+                .iter()
+                .find(|m| m.name == context.name)
+                .map(signature_help::from_builtin)
+        };
+
+        let Some(owner) = &context.owner else {
+            return signatures.get(&context.name).cloned();
+        };
+
+        let doc = self.symbols.get_document(uri);
+        let symbols = doc.as_ref().map_or(&[][..], |d| &d.symbols[..]);
+        let class = if owner == "self" {
+            self.find_class_at_position(symbols, position)
+                .map(|c| c.name.clone())
+        } else {
+            self.find_variable_type(symbols, owner)
+        }
+        .unwrap_or_else(|| owner.clone());
+
+        let suffix = format!(".{}", context.name);
+        signatures
+            .get(&format!("{}{}", class, suffix))
+            .cloned()
+            .or_else(|| builtin(&class))
+            .or_else(|| {
+                // Unknown receiver: any class method with this name
+                signatures
+                    .iter()
+                    .find(|(key, _)| key.ends_with(&suffix))
+                    .map(|(_, s)| s.clone())
+            })
+            .or_else(|| ["String", "Array", "Dict"].into_iter().find_map(builtin))
+    }
+
     fn find_variable_type(&self, symbols: &[Symbol], name: &str) -> Option<String> {
         for sym in symbols {
             if sym.name == name && matches!(sym.kind, SymbolKind::Variable | SymbolKind::Constant) {
@@ -1168,6 +1340,11 @@ impl SaldLanguageServer {
             ),
             "break" => ("break", "Exits the current loop.", "break"),
             "continue" => ("continue", "Skips to the next loop iteration.", "continue"),
+            "debugger" => (
+                "debugger",
+                "Pauses in the inspector under `sald run --inspect`; otherwise does nothing.",
+                "debugger",
+            ),
             "try" => (
                 "try",
                 "Starts a try-catch block for error handling.",
@@ -1433,7 +1610,7 @@ impl SaldLanguageServer {
 
     /// Extract top-level exported symbols from a statement
     fn extract_export_symbol(&self, stmt: &Stmt, exports: &mut Vec<Symbol>) {
-        match stmt {
+        match stmt.without_cfg() {
             Stmt::Function { def } => exports.push(self.function_to_symbol(def)),
             Stmt::Class { def } => exports.push(self.class_to_symbol(def)),
             Stmt::Let { name, span, .. } => {
@@ -1608,7 +1785,9 @@ impl SaldLanguageServer {
                 self.collect_references_from_expr(then_expr, refs);
                 self.collect_references_from_expr(else_expr, refs);
             }
-            Expr::Await { expr: e, .. } | Expr::Grouping { expr: e, .. } => {
+            Expr::Await { expr: e, .. }
+            | Expr::Grouping { expr: e, .. }
+            | Expr::Import { path: e, .. } => {
                 self.collect_references_from_expr(e, refs);
             }
             _ => {}
@@ -1626,7 +1805,7 @@ impl LanguageServer for SaldLanguageServer {
                     .write()
                     .set_workspace_root(path.clone());
                 self.workspace_index.set_workspace_root(path.clone());
-                sald_core::set_project_root(&path);
+                sald_core::workspace::set_default_project_root(&path);
                 // Initial workspace indexing
                 self.index_workspace().await;
             }
@@ -1637,7 +1816,7 @@ impl LanguageServer for SaldLanguageServer {
                         .write()
                         .set_workspace_root(path.clone());
                     self.workspace_index.set_workspace_root(path.clone());
-                    sald_core::set_project_root(&path);
+                    sald_core::workspace::set_default_project_root(&path);
                     // Initial workspace indexing
                     self.index_workspace().await;
                 }
@@ -1657,6 +1836,30 @@ impl LanguageServer for SaldLanguageServer {
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
                 document_symbol_provider: Some(OneOf::Left(true)),
                 definition_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: "}".to_string(),
+                    more_trigger_character: Some(vec!["\n".to_string()]),
+                }),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
+                semantic_tokens_provider: Some(
+                    SemanticTokensServerCapabilities::SemanticTokensOptions(
+                        SemanticTokensOptions {
+                            legend: semantic_tokens::legend(),
+                            range: Some(false),
+                            full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
+                            ..Default::default()
+                        },
+                    ),
+                ),
                 ..Default::default()
             },
             server_info: Some(ServerInfo {
@@ -1667,6 +1870,23 @@ impl LanguageServer for SaldLanguageServer {
     }
 
     async fn initialized(&self, _: InitializedParams) {
+        // lsp-types has no static capability for type hierarchy yet
+        let registration = Registration {
+            id: "sald-type-hierarchy".to_string(),
+            method: "textDocument/prepareTypeHierarchy".to_string(),
+            register_options: Some(serde_json::json!({
+                "documentSelector": [{ "language": "sald" }]
+            })),
+        };
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            self.client
+                .log_message(
+                    MessageType::LOG,
+                    format!("Type hierarchy not registered: {}", e),
+                )
+                .await;
+        }
+
         self.client
             .log_message(MessageType::INFO, "Sald LSP server initialized")
             .await;
@@ -1677,12 +1897,18 @@ impl LanguageServer for SaldLanguageServer {
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        self.open_documents.insert(
+            params.text_document.uri.clone(),
+            params.text_document.text.clone(),
+        );
         self.analyze_document(params.text_document.uri, params.text_document.text)
             .await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         if let Some(change) = params.content_changes.into_iter().next() {
+            self.open_documents
+                .insert(params.text_document.uri.clone(), change.text.clone());
             self.analyze_document(params.text_document.uri, change.text)
                 .await;
         }
@@ -1690,6 +1916,9 @@ impl LanguageServer for SaldLanguageServer {
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         self.symbols.remove_document(&params.text_document.uri);
+        self.open_documents.remove(&params.text_document.uri);
+        self.semantic_tokens.remove(&params.text_document.uri);
+        self.signatures.remove(&params.text_document.uri);
         self.client
             .publish_diagnostics(params.text_document.uri, vec![], None)
             .await;
@@ -1722,6 +1951,215 @@ impl LanguageServer for SaldLanguageServer {
         }
     }
 
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let Some(text) = self.open_documents.get(&uri).map(|t| t.clone()) else {
+            return Ok(None);
+        };
+
+        // Leave files with syntax errors alone; diagnostics already report them
+        let Ok(formatted) = sald_core::fmt::format_source(&text, uri.path()) else {
+            return Ok(None);
+        };
+        if formatted == text {
+            return Ok(Some(Vec::new()));
+        }
+
+        Ok(Some(vec![TextEdit {
+            range: Range::new(Position::new(0, 0), end_position(&text)),
+            new_text: formatted,
+        }]))
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let Some(text) = self.open_documents.get(&uri).map(|t| t.clone()) else {
+            return Ok(None);
+        };
+
+        // Whole top-level statements touching the selection are reformatted
+        let start_line = params.range.start.line as usize + 1;
+        let end_line = params.range.end.line as usize + 1;
+        let Some((first, last, new_text)) =
+            sald_core::fmt::format_range(&text, uri.path(), start_line, end_line)
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(vec![TextEdit {
+            range: Range::new(
+                Position::new(first as u32 - 1, 0),
+                Position::new(last as u32, 0),
+            ),
+            new_text,
+        }]))
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let Some(text) = self.open_documents.get(&uri).map(|t| t.clone()) else {
+            return Ok(None);
+        };
+        Ok(on_type::on_type(
+            &text,
+            uri.path(),
+            params.text_document_position.position,
+            &params.ch,
+            &params.options,
+        ))
+    }
+
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(text) = self.open_documents.get(&uri).map(|t| t.clone()) else {
+            return Ok(None);
+        };
+        let Some(context) = signature_help::call_context(&text, position) else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .resolve_signature(&uri, position, &context)
+            .map(|signature| signature_help::to_signature_help(&signature, &context)))
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(word) = self.word_at(&uri, position) else {
+            return Ok(None);
+        };
+        let outlines = self.workspace_outlines();
+        Ok(hierarchy::prepare_call(&outlines, &uri, position, &word).map(|item| vec![item]))
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        let outlines = self.workspace_outlines();
+        Ok(Some(hierarchy::incoming_calls(&outlines, &params.item)))
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let outlines = self.workspace_outlines();
+        Ok(Some(hierarchy::outgoing_calls(&outlines, &params.item)))
+    }
+
+    async fn prepare_type_hierarchy(
+        &self,
+        params: TypeHierarchyPrepareParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        let Some(word) = self.word_at(&uri, position) else {
+            return Ok(None);
+        };
+        let outlines = self.workspace_outlines();
+        Ok(hierarchy::prepare_type(&outlines, &uri, &word).map(|item| vec![item]))
+    }
+
+    async fn supertypes(
+        &self,
+        params: TypeHierarchySupertypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let outlines = self.workspace_outlines();
+        Ok(Some(hierarchy::supertypes(&outlines, &params.item)))
+    }
+
+    async fn subtypes(
+        &self,
+        params: TypeHierarchySubtypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let outlines = self.workspace_outlines();
+        Ok(Some(hierarchy::subtypes(&outlines, &params.item)))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        let uri = params.text_document.uri;
+        let Some(text) = self.open_documents.get(&uri).map(|t| t.clone()) else {
+            return Ok(None);
+        };
+
+        let range = params.range;
+        let mut hints = Vec::new();
+        for site in signature_help::call_sites(&text) {
+            if site.close < range.start || site.close > range.end {
+                continue;
+            }
+            let context = CallContext {
+                owner: site.owner.clone(),
+                name: site.name.clone(),
+                argument: 0,
+                named: None,
+            };
+            if let Some(signature) = self.resolve_signature(&uri, site.close, &context) {
+                hints.extend(inlay_hints::call_hints(&signature, &site));
+            }
+        }
+        Ok(Some(hints))
+    }
+
+    async fn semantic_tokens_full(
+        &self,
+        params: SemanticTokensParams,
+    ) -> Result<Option<SemanticTokensResult>> {
+        let Some((result_id, data)) = self.compute_semantic_tokens(&params.text_document.uri)
+        else {
+            return Ok(None);
+        };
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: Some(result_id),
+            data,
+        })))
+    }
+
+    async fn semantic_tokens_full_delta(
+        &self,
+        params: SemanticTokensDeltaParams,
+    ) -> Result<Option<SemanticTokensFullDeltaResult>> {
+        let uri = params.text_document.uri;
+        let previous = self
+            .semantic_tokens
+            .get(&uri)
+            .filter(|entry| entry.0 == params.previous_result_id)
+            .map(|entry| entry.1.clone());
+        let Some((result_id, data)) = self.compute_semantic_tokens(&uri) else {
+            return Ok(None);
+        };
+
+        // Fall back to the full set when the client's base is unknown
+        let Some(previous) = previous else {
+            return Ok(Some(SemanticTokensFullDeltaResult::Tokens(
+                SemanticTokens {
+                    result_id: Some(result_id),
+                    data,
+                },
+            )));
+        };
+        Ok(Some(SemanticTokensFullDeltaResult::TokensDelta(
+            SemanticTokensDelta {
+                result_id: Some(result_id),
+                edits: semantic_tokens::delta(&previous, &data)
+                    .into_iter()
+                    .collect(),
+            },
+        )))
+    }
+
     async fn document_symbol(
         &self,
         params: DocumentSymbolParams,
@@ -1740,6 +2178,53 @@ impl LanguageServer for SaldLanguageServer {
 
         Ok(None)
     }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let mut actions = Vec::new();
+
+        // A `let` that shadows or redeclares a variable usually meant to
+        // assign it; the fix deletes the `let` keyword
+        for diagnostic in &params.context.diagnostics {
+            let Some(NumberOrString::String(code)) = &diagnostic.code else {
+                continue;
+            };
+            if code != SHADOWED_VARIABLE && code != REDECLARED_VARIABLE {
+                continue;
+            }
+            let Some(keyword) = diagnostic
+                .data
+                .clone()
+                .and_then(|data| serde_json::from_value::<Range>(data).ok())
+            else {
+                continue;
+            };
+            let edit = TextEdit {
+                range: keyword,
+                new_text: String::new(),
+            };
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Assign to the existing variable instead".to_string(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(std::collections::HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(true),
+                ..Default::default()
+            }));
+        }
+
+        Ok(Some(actions))
+    }
+}
+
+/// Position just past the last character of `text`
+fn end_position(text: &str) -> Position {
+    let line = text.matches('\n').count() as u32;
+    let last_line = text.rsplit('\n').next().unwrap_or("");
+    Position::new(line, last_line.encode_utf16().count() as u32)
 }
 
 /// Convert our Symbol to LSP DocumentSymbol
@@ -1762,3 +2247,53 @@ fn symbol_to_document_symbol(sym: &Symbol) -> DocumentSymbol {
         children,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::LspService;
+
+    fn compile_errors(source: &str, reported: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        let (service, _socket) = LspService::new(SaldLanguageServer::new);
+        let tokens = Scanner::new(source, "test.sald").scan_tokens().unwrap();
+        let program = Parser::new(tokens, "test.sald", source).parse().unwrap();
+        let mut diagnostics = reported;
+        service
+            .inner()
+            .add_compile_errors(&program, "test.sald", source, &mut diagnostics);
+        diagnostics
+    }
+
+    #[test]
+    fn test_reports_compile_errors() {
+        let diagnostics =
+            compile_errors("fun f() {\n    let a = 1\n    let a = 2\n}\nbreak", vec![]);
+        let reported: Vec<(u32, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.range.start.line, d.message.as_str()))
+            .collect();
+        assert_eq!(
+            reported,
+            [
+                (2, "Variable 'a' already declared in this scope"),
+                (4, "'break' outside of loop")
+            ]
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.severity == Some(DiagnosticSeverity::ERROR)));
+    }
+
+    #[test]
+    fn test_skips_positions_already_reported() {
+        let existing = Diagnostic {
+            range: Range::new(Position::new(0, 0), Position::new(0, 5)),
+            message: "already reported".to_string(),
+            ..Default::default()
+        };
+        let diagnostics = compile_errors("break\nwhile true { break }", vec![existing]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "already reported");
+        assert!(compile_errors("fun f() { return 1 }", vec![]).is_empty());
+    }
+}
@@ -0,0 +1,455 @@
+// Call and type hierarchy
+// Each workspace file is reduced to an outline of its functions, classes and
+// call sites; callers, callees, supertypes and subtypes are matched by name
+
+use super::signature_help::{call_sites, CallSite};
+use super::symbols::span_to_range;
+use sald_core::ast::{Program, Stmt};
+use sald_core::lexer::Scanner;
+use sald_core::parser::Parser;
+use tower_lsp::lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, Position, Range,
+    SymbolKind, TypeHierarchyItem, Url,
+};
+
+pub struct FileOutline {
+    pub uri: Url,
+    pub functions: Vec<FunctionEntry>,
+    pub types: Vec<TypeEntry>,
+    pub calls: Vec<CallSite>,
+}
+
+pub struct FunctionEntry {
+    /// Name as called, without the class or namespace
+    pub name: String,
+    /// `Class.method` or `Namespace.function`
+    pub qualified: String,
+    pub is_method: bool,
+    pub range: Range,
+}
+
+pub struct TypeEntry {
+    pub name: String,
+    pub supertypes: Vec<String>,
+    pub is_interface: bool,
+    pub range: Range,
+}
+
+/// Outline of a document, or `None` if it does not parse.
+pub fn outline(uri: Url, text: &str) -> Option<FileOutline> {
+    let file_name = uri.path().to_string();
+    let tokens = Scanner::new(text, &file_name).scan_tokens().ok()?;
+    let program: Program = Parser::new(tokens, &file_name, text).parse().ok()?;
+
+    let mut outline = FileOutline {
+        uri,
+        functions: Vec::new(),
+        types: Vec::new(),
+        calls: call_sites(text),
+    };
+    collect(&program.statements, "", &mut outline);
+    Some(outline)
+}
+
+fn collect(statements: &[Stmt], prefix: &str, outline: &mut FileOutline) {
+    for stmt in statements {
+        match stmt.without_cfg() {
+            Stmt::Function { def } => outline.functions.push(FunctionEntry {
+                name: def.name.clone(),
+                qualified: format!("{}{}", prefix, def.name),
+                is_method: false,
+                range: span_to_range(&def.span),
+            }),
+            Stmt::Class { def } => {
+                for method in &def.methods {
+                    outline.functions.push(FunctionEntry {
+                        name: method.name.clone(),
+                        qualified: format!("{}{}.{}", prefix, def.name, method.name),
+                        is_method: true,
+                        range: span_to_range(&method.span),
+                    });
+                }
+                outline.types.push(TypeEntry {
+                    name: def.name.clone(),
+                    supertypes: def
+                        .superclass
+                        .iter()
+                        .chain(&def.implements)
+                        .cloned()
+                        .collect(),
+                    is_interface: false,
+                    range: span_to_range(&def.span),
+                });
+            }
+            Stmt::Interface { def } => outline.types.push(TypeEntry {
+                name: def.name.clone(),
+                supertypes: Vec::new(),
+                is_interface: true,
+                range: span_to_range(&def.span),
+            }),
+            Stmt::Namespace { name, body, .. } => {
+                collect(body, &format!("{}{}.", prefix, name), outline);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn contains(range: &Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
+
+fn call_item(uri: &Url, function: &FunctionEntry) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: function.qualified.clone(),
+        kind: if function.is_method {
+            SymbolKind::METHOD
+        } else {
+            SymbolKind::FUNCTION
+        },
+        tags: None,
+        detail: None,
+        uri: uri.clone(),
+        range: function.range,
+        selection_range: function.range,
+        data: None,
+    }
+}
+
+fn type_item(uri: &Url, entry: &TypeEntry) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: entry.name.clone(),
+        kind: if entry.is_interface {
+            SymbolKind::INTERFACE
+        } else {
+            SymbolKind::CLASS
+        },
+        tags: None,
+        detail: None,
+        uri: uri.clone(),
+        range: entry.range,
+        selection_range: entry.range,
+        data: None,
+    }
+}
+
+fn call_range(site: &CallSite) -> Range {
+    let end = Position::new(
+        site.callee.line,
+        site.callee.character + site.name.chars().count() as u32,
+    );
+    Range::new(site.callee, end)
+}
+
+/// Innermost function around `position`.
+fn enclosing(outline: &FileOutline, position: Position) -> Option<&FunctionEntry> {
+    outline
+        .functions
+        .iter()
+        .filter(|f| contains(&f.range, position))
+        .min_by_key(|f| f.range.end.line - f.range.start.line)
+}
+
+/// Whether a call site can reach `function`: plain calls only reach
+/// functions, `obj.name(...)` calls reach methods and namespace members.
+fn calls(site: &CallSite, function: &FunctionEntry) -> bool {
+    if site.name != function.name {
+        return false;
+    }
+    match &site.owner {
+        None => !function.is_method && !function.qualified.contains('.'),
+        Some(_) => function.is_method || function.qualified.contains('.'),
+    }
+}
+
+/// The function at `position` in `uri`, or the one named by `word`.
+pub fn prepare_call(
+    outlines: &[FileOutline],
+    uri: &Url,
+    position: Position,
+    word: &str,
+) -> Option<CallHierarchyItem> {
+    let current = outlines.iter().find(|o| &o.uri == uri)?;
+    if let Some(function) = current
+        .functions
+        .iter()
+        .find(|f| f.name == word && f.range.start.line == position.line)
+    {
+        return Some(call_item(uri, function));
+    }
+    // A call site: prefer definitions in the same file
+    let site = current
+        .calls
+        .iter()
+        .find(|s| s.name == word && contains(&call_range(s), position));
+    std::iter::once(current)
+        .chain(outlines.iter().filter(|o| &o.uri != uri))
+        .find_map(|o| {
+            o.functions
+                .iter()
+                .find(|f| match site {
+                    Some(site) => calls(site, f),
+                    None => f.name == word,
+                })
+                .map(|f| call_item(&o.uri, f))
+        })
+}
+
+fn find_function<'a>(
+    outlines: &'a [FileOutline],
+    item: &CallHierarchyItem,
+) -> Option<(&'a FileOutline, &'a FunctionEntry)> {
+    let outline = outlines.iter().find(|o| o.uri == item.uri)?;
+    let function = outline
+        .functions
+        .iter()
+        .find(|f| f.qualified == item.name && f.range == item.range)?;
+    Some((outline, function))
+}
+
+pub fn incoming_calls(
+    outlines: &[FileOutline],
+    item: &CallHierarchyItem,
+) -> Vec<CallHierarchyIncomingCall> {
+    let Some((_, target)) = find_function(outlines, item) else {
+        return Vec::new();
+    };
+
+    let mut result: Vec<CallHierarchyIncomingCall> = Vec::new();
+    for outline in outlines {
+        for site in outline.calls.iter().filter(|s| calls(s, target)) {
+            // Calls outside any function are attributed to the file
+            let from = match enclosing(outline, site.callee) {
+                Some(caller) => call_item(&outline.uri, caller),
+                None => file_item(&outline.uri),
+            };
+            match result
+                .iter_mut()
+                .find(|c| c.from.uri == from.uri && c.from.range == from.range)
+            {
+                Some(existing) => existing.from_ranges.push(call_range(site)),
+                None => result.push(CallHierarchyIncomingCall {
+                    from,
+                    from_ranges: vec![call_range(site)],
+                }),
+            }
+        }
+    }
+    result
+}
+
+pub fn outgoing_calls(
+    outlines: &[FileOutline],
+    item: &CallHierarchyItem,
+) -> Vec<CallHierarchyOutgoingCall> {
+    let Some((outline, caller)) = find_function(outlines, item) else {
+        return Vec::new();
+    };
+
+    let mut result: Vec<CallHierarchyOutgoingCall> = Vec::new();
+    for site in outline
+        .calls
+        .iter()
+        .filter(|s| contains(&caller.range, s.callee))
+    {
+        // Only direct calls, not those of functions nested inside
+        if enclosing(outline, site.callee).map(|f| f.range) != Some(caller.range) {
+            continue;
+        }
+        let callee = std::iter::once(outline)
+            .chain(outlines.iter().filter(|o| o.uri != outline.uri))
+            .find_map(|o| {
+                o.functions
+                    .iter()
+                    .find(|f| calls(site, f))
+                    .map(|f| call_item(&o.uri, f))
+            });
+        let Some(to) = callee else {
+            continue;
+        };
+        match result
+            .iter_mut()
+            .find(|c| c.to.uri == to.uri && c.to.range == to.range)
+        {
+            Some(existing) => existing.from_ranges.push(call_range(site)),
+            None => result.push(CallHierarchyOutgoingCall {
+                to,
+                from_ranges: vec![call_range(site)],
+            }),
+        }
+    }
+    result
+}
+
+fn file_item(uri: &Url) -> CallHierarchyItem {
+    let name = uri
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .unwrap_or("<file>")
+        .to_string();
+    CallHierarchyItem {
+        name,
+        kind: SymbolKind::FILE,
+        tags: None,
+        detail: None,
+        uri: uri.clone(),
+        range: Range::default(),
+        selection_range: Range::default(),
+        data: None,
+    }
+}
+
+fn find_type<'a>(outlines: &'a [FileOutline], name: &str) -> Option<(&'a Url, &'a TypeEntry)> {
+    outlines
+        .iter()
+        .find_map(|o| o.types.iter().find(|t| t.name == name).map(|t| (&o.uri, t)))
+}
+
+/// The class or interface named `word`, preferring the current file.
+pub fn prepare_type(outlines: &[FileOutline], uri: &Url, word: &str) -> Option<TypeHierarchyItem> {
+    outlines
+        .iter()
+        .filter(|o| &o.uri == uri)
+        .chain(outlines.iter().filter(|o| &o.uri != uri))
+        .find_map(|o| {
+            o.types
+                .iter()
+                .find(|t| t.name == word)
+                .map(|t| type_item(&o.uri, t))
+        })
+}
+
+pub fn supertypes(outlines: &[FileOutline], item: &TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+    let Some((_, entry)) = find_type(outlines, &item.name) else {
+        return Vec::new();
+    };
+    entry
+        .supertypes
+        .iter()
+        .filter_map(|name| find_type(outlines, name))
+        .map(|(uri, t)| type_item(uri, t))
+        .collect()
+}
+
+pub fn subtypes(outlines: &[FileOutline], item: &TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+    outlines
+        .iter()
+        .flat_map(|o| {
+            o.types
+                .iter()
+                .filter(|t| t.supertypes.contains(&item.name))
+                .map(|t| type_item(&o.uri, t))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outlines(files: &[(&str, &str)]) -> Vec<FileOutline> {
+        files
+            .iter()
+            .map(|(path, text)| outline(uri(path), text).unwrap())
+            .collect()
+    }
+
+    fn uri(path: &str) -> Url {
+        Url::parse(&format!("file:///{}", path)).unwrap()
+    }
+
+    fn names<T>(items: &[T], name: impl Fn(&T) -> &str) -> Vec<String> {
+        let mut names: Vec<String> = items.iter().map(|i| name(i).to_string()).collect();
+        names.sort();
+        names
+    }
+
+    const MAIN: &str = "fun helper() {}\n\
+                        fun run() {\n    helper()\n    helper()\n    util()\n}\n\
+                        class A { fun helper(self) {} fun go(self) { self.helper() } }\n\
+                        run()";
+
+    #[test]
+    fn test_outline_lists_functions_methods_and_types() {
+        let outlines = outlines(&[(
+            "a.sald",
+            "fun f() {}\nclass A extends B implements I { fun m(self) {} }\n\
+             interface I {}\nnamespace N { fun g() {} }",
+        )]);
+        let outline = &outlines[0];
+        assert_eq!(
+            names(&outline.functions, |f| &f.qualified),
+            ["A.m", "N.g", "f"]
+        );
+        let class = outline.types.iter().find(|t| t.name == "A").unwrap();
+        assert_eq!(class.supertypes, ["B", "I"]);
+        assert!(outline
+            .types
+            .iter()
+            .any(|t| t.name == "I" && t.is_interface));
+        assert!(super::outline(uri("b.sald"), "fun (").is_none());
+    }
+
+    #[test]
+    fn test_prepare_call_from_definition_and_call_site() {
+        let outlines = outlines(&[("main.sald", MAIN), ("util.sald", "fun util() {}")]);
+        let main = uri("main.sald");
+
+        let item = prepare_call(&outlines, &main, Position::new(0, 5), "helper").unwrap();
+        assert_eq!(item.name, "helper");
+        assert_eq!(item.kind, SymbolKind::FUNCTION);
+
+        // `self.helper()` reaches the method, not the function
+        let item = prepare_call(&outlines, &main, Position::new(6, 50), "helper").unwrap();
+        assert_eq!(item.name, "A.helper");
+
+        let item = prepare_call(&outlines, &main, Position::new(4, 5), "util").unwrap();
+        assert_eq!(item.uri, uri("util.sald"));
+    }
+
+    #[test]
+    fn test_incoming_and_outgoing_calls() {
+        let outlines = outlines(&[("main.sald", MAIN), ("util.sald", "fun util() {}")]);
+        let main = uri("main.sald");
+        let helper = prepare_call(&outlines, &main, Position::new(0, 5), "helper").unwrap();
+
+        let incoming = incoming_calls(&outlines, &helper);
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].from.name, "run");
+        assert_eq!(incoming[0].from_ranges.len(), 2);
+
+        let run = prepare_call(&outlines, &main, Position::new(1, 5), "run").unwrap();
+        let outgoing = outgoing_calls(&outlines, &run);
+        assert_eq!(names(&outgoing, |c| &c.to.name), ["helper", "util"]);
+
+        // Top-level calls come from the file
+        let incoming = incoming_calls(&outlines, &run);
+        assert_eq!(incoming[0].from.name, "main.sald");
+        assert_eq!(incoming[0].from.kind, SymbolKind::FILE);
+    }
+
+    #[test]
+    fn test_supertypes_and_subtypes() {
+        let outlines = outlines(&[
+            ("a.sald", "interface Shape {}\nclass Base {}"),
+            (
+                "b.sald",
+                "class Circle extends Base implements Shape {}\nclass Square implements Shape {}",
+            ),
+        ]);
+        let circle = prepare_type(&outlines, &uri("b.sald"), "Circle").unwrap();
+        assert_eq!(
+            names(&supertypes(&outlines, &circle), |t| &t.name),
+            ["Base", "Shape"]
+        );
+
+        let shape = prepare_type(&outlines, &uri("b.sald"), "Shape").unwrap();
+        assert_eq!(shape.uri, uri("a.sald"));
+        assert_eq!(shape.kind, SymbolKind::INTERFACE);
+        assert_eq!(
+            names(&subtypes(&outlines, &shape), |t| &t.name),
+            ["Circle", "Square"]
+        );
+        assert!(prepare_type(&outlines, &uri("a.sald"), "Missing").is_none());
+    }
+}
@@ -6,6 +6,7 @@ use std::sync::Arc;
 use super::symbols::{span_to_range, Symbol, SymbolKind};
 use sald_core::ast::{Expr, Program, Stmt};
 use sald_core::lexer::Scanner;
+use sald_core::package::{self, ResolveError};
 use sald_core::parser::Parser;
 
 #[derive(Debug, Clone)]
@@ -40,84 +41,38 @@ impl ImportResolver {
     }
 
     pub fn resolve_import_path(&self, from_file: &Path, import_path: &str) -> Option<PathBuf> {
-        let clean_path = import_path.trim_matches('"').trim_matches('\'');
-
-        let workspace = self.get_workspace_root();
-
-        let is_relative = clean_path.starts_with('.')
-            || clean_path.starts_with('/')
-            || clean_path.starts_with('\\')
-            || clean_path.ends_with(".sald");
-
-        if !is_relative {
-            let from_dir = from_file.parent().unwrap_or(Path::new("."));
-            let local_modules = from_dir.join("sald_modules").join(clean_path);
-
-            if let Some(path) = self.resolve_module_dir(&local_modules) {
-                return Some(path);
-            }
-
-            let workspace_modules = workspace.join("sald_modules").join(clean_path);
-            if let Some(path) = self.resolve_module_dir(&workspace_modules) {
-                return Some(path);
-            }
-
-            let direct_file = workspace
-                .join("sald_modules")
-                .join(format!("{}.sald", clean_path));
-            if direct_file.exists() {
-                return direct_file.canonicalize().ok();
-            }
-        }
-
-        let from_dir = from_file.parent().unwrap_or(Path::new("."));
-        let resolved = from_dir.join(clean_path);
-
-        if resolved.exists() {
-            return resolved.canonicalize().ok();
-        }
-
-        if !clean_path.ends_with(".sald") {
-            let with_ext = from_dir.join(format!("{}.sald", clean_path));
-            if with_ext.exists() {
-                return with_ext.canonicalize().ok();
-            }
-        }
-
-        None
+        self.check_import(from_file, import_path).ok()
     }
 
-    fn resolve_module_dir(&self, module_dir: &Path) -> Option<PathBuf> {
-        if !module_dir.exists() || !module_dir.is_dir() {
-            return None;
-        }
+    /// Resolves an import the way the VM does, or explains why it can't be.
+    pub fn check_import(&self, from_file: &Path, import_path: &str) -> Result<PathBuf, String> {
+        let clean_path = import_path.trim_matches('"').trim_matches('\'');
+        let from_dir = from_file.parent().unwrap_or(Path::new("."));
 
-        let config_path = module_dir.join("salad.json");
-        if config_path.exists() {
-            if let Ok(content) = std::fs::read_to_string(&config_path) {
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
-                    if let Some(main) = json.get("main").and_then(|v| v.as_str()) {
-                        let entry = module_dir.join(main);
-                        if entry.exists() {
-                            return entry.canonicalize().ok();
-                        }
-                    }
+        let file = from_dir.join(clean_path);
+        let file = if file.exists() || clean_path.ends_with(".sald") {
+            file
+        } else {
+            from_dir.join(format!("{}.sald", clean_path))
+        };
+
+        if package::is_bare_specifier(clean_path) {
+            // "lib/util" next to the importer wins over package `lib`
+            let bare_name = !clean_path.contains(['/', '\\']);
+            if bare_name || !file.exists() {
+                let workspace = self.get_workspace_root();
+                match package::resolve_module(Some(&workspace), from_dir, clean_path) {
+                    Ok(module) => return module.entry.canonicalize().map_err(|e| e.to_string()),
+                    Err(ResolveError::NotFound { .. }) if !bare_name => {}
+                    Err(e) => return Err(format!("{}\n{}", e, e.help())),
                 }
             }
         }
 
-        let module_name = module_dir.file_name()?.to_str()?;
-        let default_entry = module_dir.join(format!("{}.sald", module_name));
-        if default_entry.exists() {
-            return default_entry.canonicalize().ok();
-        }
-
-        let main_entry = module_dir.join("main.sald");
-        if main_entry.exists() {
-            return main_entry.canonicalize().ok();
+        if file.exists() {
+            return file.canonicalize().map_err(|e| e.to_string());
         }
-
-        None
+        Err(format!("Cannot find import '{}'", clean_path))
     }
 
     pub fn get_exports(&self, file_path: &Path) -> Option<Vec<Symbol>> {
@@ -178,7 +133,7 @@ impl ImportResolver {
         let mut symbols = Vec::new();
 
         for stmt in &program.statements {
-            match stmt {
+            match stmt.without_cfg() {
                 Stmt::Function { def } => {
                     let params: Vec<String> = def.params.iter().map(|p| p.name.clone()).collect();
                     symbols.push(Symbol {
@@ -187,7 +142,7 @@ impl ImportResolver {
                         range: span_to_range(&def.span),
                         selection_range: span_to_range(&def.span),
                         detail: Some(format!("fun {}({})", def.name, params.join(", "))),
-                        documentation: None,
+                        documentation: def.doc.clone(),
                         children: Vec::new(),
                         type_hint: None,
                         source_uri: None,
@@ -206,7 +161,7 @@ impl ImportResolver {
                                 range: span_to_range(&m.span),
                                 selection_range: span_to_range(&m.span),
                                 detail: Some(format!("fun {}({})", m.name, params.join(", "))),
-                                documentation: None,
+                                documentation: m.doc.clone(),
                                 children: Vec::new(),
                                 type_hint: None,
                                 source_uri: None,
@@ -220,7 +175,7 @@ impl ImportResolver {
                         range: span_to_range(&def.span),
                         selection_range: span_to_range(&def.span),
                         detail: Some(format!("class {}", def.name)),
-                        documentation: None,
+                        documentation: def.doc.clone(),
                         children,
                         type_hint: None,
                         source_uri: None,
@@ -317,7 +272,7 @@ impl ImportResolver {
     }
 
     fn extract_stmt_symbol(&self, stmt: &Stmt, symbols: &mut Vec<Symbol>) {
-        match stmt {
+        match stmt.without_cfg() {
             Stmt::Function { def } => {
                 let params: Vec<String> = def.params.iter().map(|p| p.name.clone()).collect();
                 symbols.push(Symbol {
@@ -471,6 +426,9 @@ impl ImportResolver {
                 sald_core::ast::Literal::Number(_) => Some("Number".to_string()),
                 sald_core::ast::Literal::String(_) => Some("String".to_string()),
                 sald_core::ast::Literal::Boolean(_) => Some("Boolean".to_string()),
+                sald_core::ast::Literal::Date(_) | sald_core::ast::Literal::Time(_) => {
+                    Some("Date".to_string())
+                }
                 sald_core::ast::Literal::Null => None,
             },
             _ => None,
@@ -0,0 +1,140 @@
+// Inlay hints
+// Parameter names before positional arguments, and the default values of
+// trailing parameters a call leaves out
+
+use super::signature_help::{CallSite, FunctionSignature};
+use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel};
+
+pub fn call_hints(signature: &FunctionSignature, site: &CallSite) -> Vec<InlayHint> {
+    let mut hints = Vec::new();
+    let mut positional = 0;
+    let mut spread = false;
+
+    for argument in &site.arguments {
+        if argument.named.is_some() {
+            continue;
+        }
+        if argument.is_spread {
+            // Later arguments no longer line up with parameters
+            spread = true;
+            break;
+        }
+        let Some(param) = signature.params.get(positional) else {
+            break;
+        };
+        if param.is_variadic {
+            break;
+        }
+        positional += 1;
+        if argument.identifier.as_deref() == Some(param.name.as_str()) {
+            continue;
+        }
+        hints.push(InlayHint {
+            position: argument.position,
+            label: InlayHintLabel::String(format!("{}:", param.name)),
+            kind: Some(InlayHintKind::PARAMETER),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: Some(true),
+            data: None,
+        });
+    }
+
+    if spread {
+        return hints;
+    }
+    let omitted: Vec<String> = signature.params[positional.min(signature.params.len())..]
+        .iter()
+        .filter(|p| !p.is_variadic)
+        .filter(|p| {
+            !site
+                .arguments
+                .iter()
+                .any(|a| a.named.as_ref() == Some(&p.name))
+        })
+        .filter_map(|p| Some(format!("{}: {}", p.name, p.default.as_ref()?)))
+        .collect();
+    if !omitted.is_empty() {
+        let separator = if site.arguments.is_empty() { "" } else { ", " };
+        hints.push(InlayHint {
+            position: site.close,
+            label: InlayHintLabel::String(format!("{}{}", separator, omitted.join(", "))),
+            kind: Some(InlayHintKind::PARAMETER),
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: None,
+            data: None,
+        });
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::signature_help::{call_sites, collect};
+    use sald_core::lexer::Scanner;
+    use sald_core::parser::Parser;
+
+    /// `(line, character, label)` of the hints for the last call in `source`
+    fn hints(source: &str, function: &str) -> Vec<(u32, u32, String)> {
+        let tokens = Scanner::new(source, "<test>").scan_tokens().unwrap();
+        let program = Parser::new(tokens, "<test>", source).parse().unwrap();
+        let signatures = collect(&program, source);
+        let site = call_sites(source).pop().unwrap();
+        call_hints(&signatures[function], &site)
+            .into_iter()
+            .map(|hint| {
+                let InlayHintLabel::String(label) = hint.label else {
+                    panic!("expected a plain label");
+                };
+                (hint.position.line, hint.position.character, label)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_names_positional_arguments() {
+        assert_eq!(
+            hints("fun f(a, b) {}\nf(1, 2)", "f"),
+            [(1, 2, "a:".to_string()), (1, 5, "b:".to_string())]
+        );
+        // An argument already spelled like the parameter needs no hint
+        assert_eq!(
+            hints("fun f(a, b) {}\nlet a = 1\nf(a, 2)", "f"),
+            [(2, 5, "b:".to_string())]
+        );
+        assert!(hints("fun f(a, b) {}\nf(b: 1, a: 2)", "f").is_empty());
+    }
+
+    #[test]
+    fn test_shows_omitted_defaults() {
+        assert_eq!(
+            hints("fun f(a, b = 1, c = \"x\") {}\nf(0)", "f"),
+            [
+                (1, 2, "a:".to_string()),
+                (1, 3, ", b: 1, c: \"x\"".to_string())
+            ]
+        );
+        assert_eq!(
+            hints("fun f(a = [], b = 2) {}\nf(b: 3)", "f"),
+            [(1, 6, ", a: []".to_string())]
+        );
+        assert_eq!(
+            hints("fun f(a = 1) {}\nf()", "f"),
+            [(1, 2, "a: 1".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_stops_at_spreads_and_variadics() {
+        assert!(hints("fun f(a, b = 1) {}\nf(...items)", "f").is_empty());
+        assert_eq!(
+            hints("fun f(a, ...rest) {}\nf(1, 2, 3)", "f"),
+            [(1, 2, "a:".to_string())]
+        );
+    }
+}
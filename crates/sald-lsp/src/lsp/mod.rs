@@ -1,7 +1,12 @@
 mod analyzer;
 mod backend;
 mod completion;
+mod hierarchy;
 mod import_resolver;
+mod inlay_hints;
+mod on_type;
+mod semantic_tokens;
+mod signature_help;
 mod symbols;
 
 pub use backend::SaldLanguageServer;
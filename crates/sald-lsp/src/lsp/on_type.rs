@@ -0,0 +1,200 @@
+// On-type formatting
+// Enter after an unclosed `{` inserts the closing brace, Enter inside a `///`
+// comment continues it, and `}` re-indents the line or, when it closes a
+// top-level statement, reformats that statement with the formatter
+
+use sald_core::lexer::{Scanner, TokenKind};
+use tower_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+
+/// Edits for `ch` having just been typed before `position`.
+pub fn on_type(
+    text: &str,
+    file: &str,
+    position: Position,
+    ch: &str,
+    options: &FormattingOptions,
+) -> Option<Vec<TextEdit>> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let line = position.line as usize;
+    match ch {
+        "\n" if line > 0 => after_newline(text, &lines, line, &indent_unit(options)),
+        "}" => after_close_brace(text, file, &lines, line),
+        _ => None,
+    }
+}
+
+fn indent_unit(options: &FormattingOptions) -> String {
+    if options.insert_spaces {
+        " ".repeat(options.tab_size as usize)
+    } else {
+        "\t".to_string()
+    }
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+/// Replaces the whole of `line` with `new_text`.
+fn replace_line(lines: &[&str], line: usize, new_text: String) -> TextEdit {
+    let end = lines[line].chars().count() as u32;
+    TextEdit {
+        range: Range::new(
+            Position::new(line as u32, 0),
+            Position::new(line as u32, end),
+        ),
+        new_text,
+    }
+}
+
+fn after_newline(text: &str, lines: &[&str], line: usize, unit: &str) -> Option<Vec<TextEdit>> {
+    // Only a fresh, empty line is filled in
+    if !lines.get(line)?.trim().is_empty() {
+        return None;
+    }
+    let previous = lines[line - 1];
+    let indent = leading_whitespace(previous);
+    let trimmed = previous.trim();
+
+    if trimmed.starts_with("///") && !trimmed.starts_with("////") {
+        return Some(vec![replace_line(lines, line, format!("{}/// ", indent))]);
+    }
+
+    if trimmed.ends_with('{') && has_unclosed_brace(text) {
+        let new_text = format!("{}{}\n{}}}", indent, unit, indent);
+        return Some(vec![replace_line(lines, line, new_text)]);
+    }
+    None
+}
+
+/// Whether the document opens more braces than it closes.
+fn has_unclosed_brace(text: &str) -> bool {
+    let Ok(tokens) = Scanner::new(text, "<lsp>").scan_tokens() else {
+        return false;
+    };
+    let balance = tokens.iter().fold(0i64, |balance, token| match token.kind {
+        TokenKind::LeftBrace => balance + 1,
+        TokenKind::RightBrace => balance - 1,
+        _ => balance,
+    });
+    balance > 0
+}
+
+fn after_close_brace(text: &str, file: &str, lines: &[&str], line: usize) -> Option<Vec<TextEdit>> {
+    if !lines.get(line)?.trim_start().starts_with('}') {
+        return None;
+    }
+
+    // A `}` ending a top-level statement gets the whole statement formatted
+    if let Some((first, last, new_text)) =
+        sald_core::fmt::format_range(text, file, line + 1, line + 1)
+    {
+        if last == line + 1 {
+            return Some(vec![TextEdit {
+                range: Range::new(
+                    Position::new(first as u32 - 1, 0),
+                    Position::new(last as u32, 0),
+                ),
+                new_text,
+            }]);
+        }
+    }
+
+    let indent = leading_whitespace(lines[opening_line(text, line)?]);
+    let current = leading_whitespace(lines[line]);
+    if indent == current {
+        return None;
+    }
+    Some(vec![TextEdit {
+        range: Range::new(
+            Position::new(line as u32, 0),
+            Position::new(line as u32, current.chars().count() as u32),
+        ),
+        new_text: indent.to_string(),
+    }])
+}
+
+/// 0-based line of the `{` matched by the first `}` on `line`.
+fn opening_line(text: &str, line: usize) -> Option<usize> {
+    let tokens = Scanner::new(text, "<lsp>").scan_tokens().ok()?;
+    let mut open: Vec<usize> = Vec::new();
+    for token in &tokens {
+        match token.kind {
+            TokenKind::LeftBrace => open.push(token.span.start.line),
+            TokenKind::RightBrace if token.span.start.line == line + 1 => {
+                return open.pop().map(|l| l - 1);
+            }
+            TokenKind::RightBrace => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edits(text: &str, line: u32, ch: &str) -> Option<Vec<(Range, String)>> {
+        let options = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            ..Default::default()
+        };
+        let edits = on_type(text, "test.sald", Position::new(line, 0), ch, &options)?;
+        Some(edits.into_iter().map(|e| (e.range, e.new_text)).collect())
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn test_enter_after_open_brace_inserts_the_close() {
+        assert_eq!(
+            edits("fun f() {\n", 1, "\n"),
+            Some(vec![(range((1, 0), (1, 0)), "    \n}".to_string())])
+        );
+        assert_eq!(
+            edits("if x {\n    while y {\n    \n}", 2, "\n"),
+            Some(vec![(range((2, 0), (2, 4)), "        \n    }".to_string())])
+        );
+        // Already balanced
+        assert_eq!(edits("fun f() {\n\n}", 1, "\n"), None);
+    }
+
+    #[test]
+    fn test_enter_continues_doc_comments() {
+        assert_eq!(
+            edits("    /// Adds two numbers\n", 1, "\n"),
+            Some(vec![(range((1, 0), (1, 0)), "    /// ".to_string())])
+        );
+        assert_eq!(edits("//// banner\n", 1, "\n"), None);
+        assert_eq!(edits("// note\n", 1, "\n"), None);
+    }
+
+    #[test]
+    fn test_close_brace_formats_top_level_statements() {
+        let text = "fun f() {\nlet x  =  1\n}\nlet y = 2";
+        assert_eq!(
+            edits(text, 2, "}"),
+            Some(vec![(
+                range((0, 0), (3, 0)),
+                "fun f() {\n    let x = 1\n}\n".to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_close_brace_reindents_nested_blocks() {
+        let text = "fun f() {\n    if x {\n        y()\n        }\n";
+        assert_eq!(
+            edits(text, 3, "}"),
+            Some(vec![(range((3, 0), (3, 8)), "    ".to_string())])
+        );
+        let text = "fun f() {\n    if x {\n        y()\n    }\n";
+        assert_eq!(edits(text, 3, "}"), None);
+    }
+}
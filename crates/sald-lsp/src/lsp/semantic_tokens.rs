@@ -0,0 +1,512 @@
+// Semantic token classification
+// Works on the token stream so highlighting keeps working while the document
+// does not parse; a first pass collects declared names, a second classifies
+// every identifier using them and the surrounding tokens
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use sald_core::builtins::docs::builtin_docs;
+use sald_core::lexer::{Scanner, Token, TokenKind};
+use tower_lsp::lsp_types::{
+    SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit,
+    SemanticTokensLegend,
+};
+
+const TYPES: &[SemanticTokenType] = &[
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::CLASS,
+    SemanticTokenType::ENUM,
+    SemanticTokenType::INTERFACE,
+    SemanticTokenType::ENUM_MEMBER,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::METHOD,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PROPERTY,
+    SemanticTokenType::DECORATOR,
+];
+
+const MODIFIERS: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::STATIC,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Namespace,
+    Class,
+    Enum,
+    Interface,
+    EnumMember,
+    Function,
+    Method,
+    Parameter,
+    Variable,
+    Property,
+    Decorator,
+}
+
+const DECLARATION: u32 = 1;
+const READONLY: u32 = 1 << 1;
+const STATIC: u32 = 1 << 2;
+const DEFAULT_LIBRARY: u32 = 1 << 3;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TYPES.to_vec(),
+        token_modifiers: MODIFIERS.to_vec(),
+    }
+}
+
+/// Semantic tokens of a document, or `None` if it does not tokenize.
+pub fn semantic_tokens(text: &str) -> Option<Vec<SemanticToken>> {
+    let tokens = Scanner::new(text, "<lsp>").scan_tokens().ok()?;
+    let classified = Classifier::new(&tokens).classify();
+
+    // The scanner counts columns in chars, LSP in UTF-16 code units
+    let lines: Vec<&str> = text.lines().collect();
+    let mut result = Vec::with_capacity(classified.len());
+    let (mut prev_line, mut prev_start) = (0u32, 0u32);
+    for (token, kind, modifiers) in classified {
+        let line = token.span.start.line.saturating_sub(1);
+        let column = token.span.start.column.saturating_sub(1);
+        let start = lines
+            .get(line)
+            .map_or(0, |text| utf16_len(text.chars().take(column)));
+        let line = line as u32;
+        let delta_line = line - prev_line;
+        let delta_start = if delta_line == 0 {
+            start - prev_start
+        } else {
+            start
+        };
+        result.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: utf16_len(token.lexeme.chars()),
+            token_type: kind as u32,
+            token_modifiers_bitset: modifiers,
+        });
+        prev_line = line;
+        prev_start = start;
+    }
+    Some(result)
+}
+
+fn utf16_len(chars: impl Iterator<Item = char>) -> u32 {
+    chars.map(|c| c.len_utf16() as u32).sum()
+}
+
+/// Single edit turning `old` into `new`, or `None` if they are equal.
+pub fn delta(old: &[SemanticToken], new: &[SemanticToken]) -> Option<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if prefix == old.len() && prefix == new.len() {
+        return None;
+    }
+    // Edits address the flattened array, five integers per token
+    Some(SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: ((old.len() - prefix - suffix) * 5) as u32,
+        data: Some(new[prefix..new.len() - suffix].to_vec()),
+    })
+}
+
+#[derive(Default)]
+struct Declared {
+    classes: FxHashSet<String>,
+    enums: FxHashSet<String>,
+    interfaces: FxHashSet<String>,
+    namespaces: FxHashSet<String>,
+    functions: FxHashSet<String>,
+    constants: FxHashSet<String>,
+}
+
+/// Parameters declared up to `params_end` and visible until `end`
+struct Scope {
+    names: FxHashSet<String>,
+    params_end: usize,
+    end: usize,
+}
+
+struct Classifier<'a> {
+    tokens: Vec<&'a Token>,
+    declared: Declared,
+    builtins: FxHashSet<String>,
+    /// Index of the matching closing bracket for every opening one
+    closing: FxHashMap<usize, usize>,
+    /// Innermost opening bracket around each token
+    enclosing: Vec<Option<usize>>,
+    /// Opening braces of class bodies
+    class_bodies: FxHashSet<usize>,
+    /// Opening braces of enum bodies
+    enum_bodies: FxHashSet<usize>,
+}
+
+impl<'a> Classifier<'a> {
+    fn new(tokens: &'a [Token]) -> Self {
+        let tokens: Vec<&Token> = tokens
+            .iter()
+            .filter(|t| !matches!(t.kind, TokenKind::Eof))
+            .collect();
+
+        let mut declared = Declared::default();
+        for pair in tokens.windows(2) {
+            let TokenKind::Identifier(name) = &pair[1].kind else {
+                continue;
+            };
+            let set = match pair[0].kind {
+                TokenKind::Class => &mut declared.classes,
+                TokenKind::Enum => &mut declared.enums,
+                TokenKind::Interface => &mut declared.interfaces,
+                TokenKind::Namespace => &mut declared.namespaces,
+                TokenKind::Fun => &mut declared.functions,
+                TokenKind::Const => &mut declared.constants,
+                _ => continue,
+            };
+            set.insert(name.clone());
+        }
+
+        let mut closing = FxHashMap::default();
+        let mut enclosing = Vec::with_capacity(tokens.len());
+        let mut class_bodies = FxHashSet::default();
+        let mut enum_bodies = FxHashSet::default();
+        let mut open: Vec<usize> = Vec::new();
+        // Set after `class` or `enum` until the body opens; true for classes
+        let mut header: Option<bool> = None;
+        for (i, token) in tokens.iter().enumerate() {
+            enclosing.push(open.last().copied());
+            match token.kind {
+                TokenKind::Class => header = Some(true),
+                TokenKind::Enum => header = Some(false),
+                TokenKind::LeftBrace if header.is_some() => {
+                    if header.take() == Some(true) {
+                        class_bodies.insert(i);
+                    } else {
+                        enum_bodies.insert(i);
+                    }
+                    open.push(i);
+                }
+                TokenKind::LeftParen
+                | TokenKind::LeftBrace
+                | TokenKind::LeftBracket
+                | TokenKind::FormatStringStart(_) => open.push(i),
+                TokenKind::RightParen
+                | TokenKind::RightBrace
+                | TokenKind::RightBracket
+                | TokenKind::FormatStringEnd(_) => {
+                    if let Some(start) = open.pop() {
+                        closing.insert(start, i);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self {
+            tokens,
+            declared,
+            builtins: builtin_docs().into_iter().map(|doc| doc.name).collect(),
+            closing,
+            enclosing,
+            class_bodies,
+            enum_bodies,
+        }
+    }
+
+    fn kind(&self, i: usize) -> Option<&TokenKind> {
+        self.tokens.get(i).map(|t| &t.kind)
+    }
+
+    fn prev(&self, i: usize) -> Option<&TokenKind> {
+        i.checked_sub(1).and_then(|i| self.kind(i))
+    }
+
+    /// End of the scope opened by a body starting at `i`: the matching brace
+    /// for a block, otherwise the rest of the enclosing bracket or line.
+    fn body_end(&self, i: usize) -> usize {
+        if let Some(&end) = self.closing.get(&i) {
+            return end;
+        }
+        let line = self.tokens.get(i).map_or(0, |t| t.span.start.line);
+        let mut depth = 0usize;
+        for j in i..self.tokens.len() {
+            match self.tokens[j].kind {
+                TokenKind::LeftParen | TokenKind::LeftBrace | TokenKind::LeftBracket => depth += 1,
+                TokenKind::RightParen | TokenKind::RightBrace | TokenKind::RightBracket => {
+                    if depth == 0 {
+                        return j;
+                    }
+                    depth -= 1;
+                }
+                TokenKind::Comma if depth == 0 => return j,
+                _ if depth == 0 && self.tokens[j].span.start.line != line => return j,
+                _ => {}
+            }
+        }
+        self.tokens.len()
+    }
+
+    /// Parameter names between `open` and the token closing the list.
+    fn params(&self, open: usize, close: &TokenKind) -> (FxHashSet<String>, usize) {
+        let mut names = FxHashSet::default();
+        let mut depth = 0usize;
+        let mut j = open + 1;
+        while j < self.tokens.len() {
+            let kind = &self.tokens[j].kind;
+            if depth == 0 && std::mem::discriminant(kind) == std::mem::discriminant(close) {
+                break;
+            }
+            match kind {
+                TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                TokenKind::Identifier(name) if depth == 0 => {
+                    let after_separator =
+                        matches!(self.prev(j), Some(TokenKind::Comma | TokenKind::DotDotDot))
+                            || j == open + 1;
+                    if after_separator {
+                        names.insert(name.clone());
+                    }
+                }
+                _ => {}
+            }
+            j += 1;
+        }
+        (names, j)
+    }
+
+    /// Whether `|` at `i` opens lambda parameters rather than a bitwise or.
+    fn starts_lambda(&self, i: usize) -> bool {
+        !matches!(
+            self.prev(i),
+            Some(
+                TokenKind::Identifier(_)
+                    | TokenKind::Number(_)
+                    | TokenKind::String(_)
+                    | TokenKind::RawString(_)
+                    | TokenKind::DateLiteral(_)
+                    | TokenKind::TimeLiteral(_)
+                    | TokenKind::FormatStringEnd(_)
+                    | TokenKind::True
+                    | TokenKind::False
+                    | TokenKind::Null
+                    | TokenKind::SelfKeyword
+                    | TokenKind::RightParen
+                    | TokenKind::RightBracket
+                    | TokenKind::RightBrace
+            )
+        )
+    }
+
+    fn classify(&self) -> Vec<(&'a Token, Kind, u32)> {
+        let mut result = Vec::new();
+        let mut scopes: Vec<Scope> = Vec::new();
+
+        for i in 0..self.tokens.len() {
+            scopes.retain(|scope| scope.end > i);
+
+            match self.tokens[i].kind {
+                TokenKind::LeftParen
+                    if matches!(self.prev(i), Some(TokenKind::Identifier(_)))
+                        && matches!(
+                            i.checked_sub(2).and_then(|j| self.kind(j)),
+                            Some(TokenKind::Fun)
+                        ) =>
+                {
+                    let (names, close) = self.params(i, &TokenKind::RightParen);
+                    let end = if matches!(self.kind(close + 1), Some(TokenKind::LeftBrace)) {
+                        self.body_end(close + 1)
+                    } else {
+                        close
+                    };
+                    scopes.push(Scope {
+                        names,
+                        params_end: close,
+                        end,
+                    });
+                }
+                TokenKind::Pipe if self.starts_lambda(i) => {
+                    let (names, close) = self.params(i, &TokenKind::Pipe);
+                    scopes.push(Scope {
+                        names,
+                        params_end: close,
+                        end: self.body_end(close + 1),
+                    });
+                }
+                _ => {}
+            }
+
+            let TokenKind::Identifier(name) = &self.tokens[i].kind else {
+                continue;
+            };
+            let declares_param = scopes
+                .last()
+                .is_some_and(|s| i < s.params_end && s.names.contains(name));
+            let (kind, modifiers) = if declares_param {
+                (Kind::Parameter, DECLARATION)
+            } else {
+                self.identifier(i, name, &scopes)
+            };
+            result.push((self.tokens[i], kind, modifiers));
+        }
+
+        result
+    }
+
+    fn identifier(&self, i: usize, name: &str, scopes: &[Scope]) -> (Kind, u32) {
+        let next = self.kind(i + 1);
+        let enclosing = self.enclosing[i];
+        if enclosing.is_some_and(|open| self.enum_bodies.contains(&open)) {
+            return (Kind::EnumMember, DECLARATION | READONLY);
+        }
+        let in_class = enclosing.is_some_and(|open| self.class_bodies.contains(&open));
+        match self.prev(i) {
+            Some(TokenKind::At) => return (Kind::Decorator, 0),
+            Some(TokenKind::Class) => return (Kind::Class, DECLARATION),
+            Some(TokenKind::Extends) => return (Kind::Class, 0),
+            Some(TokenKind::Implements) => return (Kind::Interface, 0),
+            Some(TokenKind::Enum) => return (Kind::Enum, DECLARATION),
+            Some(TokenKind::Interface) => return (Kind::Interface, DECLARATION),
+            Some(TokenKind::Namespace) => return (Kind::Namespace, DECLARATION),
+            Some(TokenKind::Fun) if in_class => {
+                let is_static = !matches!(self.kind(i + 2), Some(TokenKind::SelfKeyword));
+                let modifiers = if is_static { STATIC } else { 0 };
+                return (Kind::Method, DECLARATION | modifiers);
+            }
+            Some(TokenKind::Fun) => return (Kind::Function, DECLARATION),
+            Some(TokenKind::Const) => return (Kind::Variable, DECLARATION | READONLY),
+            Some(TokenKind::Let) | Some(TokenKind::For) => return (Kind::Variable, DECLARATION),
+            Some(TokenKind::Dot) | Some(TokenKind::QuestionDot) => {
+                let owner = i.checked_sub(2).and_then(|j| self.kind(j));
+                if let Some(TokenKind::Identifier(owner)) = owner {
+                    if self.declared.enums.contains(owner) {
+                        return (Kind::EnumMember, READONLY);
+                    }
+                }
+                return if matches!(next, Some(TokenKind::LeftParen)) {
+                    (Kind::Method, 0)
+                } else {
+                    (Kind::Property, 0)
+                };
+            }
+            _ => {}
+        }
+
+        if scopes.iter().any(|scope| scope.names.contains(name)) {
+            return (Kind::Parameter, 0);
+        }
+        // Named arguments
+        if matches!(next, Some(TokenKind::Colon)) && self.in_call(i) {
+            return (Kind::Parameter, 0);
+        }
+
+        let declared = &self.declared;
+        if declared.classes.contains(name) {
+            (Kind::Class, 0)
+        } else if declared.enums.contains(name) {
+            (Kind::Enum, 0)
+        } else if declared.interfaces.contains(name) {
+            (Kind::Interface, 0)
+        } else if declared.namespaces.contains(name) {
+            (Kind::Namespace, 0)
+        } else if declared.functions.contains(name) {
+            (Kind::Function, 0)
+        } else if declared.constants.contains(name) {
+            (Kind::Variable, READONLY)
+        } else if self.builtins.contains(name) {
+            (Kind::Class, DEFAULT_LIBRARY)
+        } else {
+            (Kind::Variable, 0)
+        }
+    }
+
+    /// Whether the innermost open bracket around `i` is a `(`.
+    fn in_call(&self, i: usize) -> bool {
+        self.enclosing[i].is_some_and(|open| matches!(self.tokens[open].kind, TokenKind::LeftParen))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(line, start, length, type)` of every token, with positions made absolute
+    fn absolute(text: &str) -> Vec<(u32, u32, u32, Kind)> {
+        let kinds = [
+            Kind::Namespace,
+            Kind::Class,
+            Kind::Enum,
+            Kind::Interface,
+            Kind::EnumMember,
+            Kind::Function,
+            Kind::Method,
+            Kind::Parameter,
+            Kind::Variable,
+            Kind::Property,
+            Kind::Decorator,
+        ];
+        let (mut line, mut start) = (0, 0);
+        semantic_tokens(text)
+            .unwrap()
+            .into_iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    start = 0;
+                }
+                line += token.delta_line;
+                start += token.delta_start;
+                (line, start, token.length, kinds[token.token_type as usize])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_classifies_declarations_and_uses() {
+        let tokens = absolute("class A {}\nfun f(x) { return x }\nlet a = A()");
+        assert_eq!(
+            tokens,
+            vec![
+                (0, 6, 1, Kind::Class),
+                (1, 4, 1, Kind::Function),
+                (1, 6, 1, Kind::Parameter),
+                (1, 18, 1, Kind::Parameter),
+                (2, 4, 1, Kind::Variable),
+                (2, 8, 1, Kind::Class),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_positions_count_utf16_code_units() {
+        // "é" is one code unit, "😀" is two
+        let tokens = absolute("let s = \"é😀\" + t\nlet ñé = s");
+        assert_eq!(
+            tokens,
+            vec![
+                (0, 4, 1, Kind::Variable),
+                (0, 16, 1, Kind::Variable),
+                (1, 4, 2, Kind::Variable),
+                (1, 9, 1, Kind::Variable),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delta_replaces_only_changed_tokens() {
+        let old = semantic_tokens("let a = 1\nlet b = 2\nlet c = 3").unwrap();
+        let new = semantic_tokens("let a = 1\nlet bb = 2\nlet c = 3").unwrap();
+        let edit = delta(&old, &new).unwrap();
+        assert_eq!(edit.start, 5);
+        assert_eq!(edit.delete_count, 5);
+        assert_eq!(edit.data.unwrap(), new[1..2].to_vec());
+        assert!(delta(&new, &new).is_none());
+    }
+}
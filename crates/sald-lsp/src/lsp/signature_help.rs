@@ -0,0 +1,511 @@
+// Signature help
+// Signatures come from the parsed FunctionDefs of a document and from the
+// builtin class docs; the call being typed is found on the token stream
+
+use rustc_hash::FxHashMap;
+use sald_core::ast::{FunctionDef, Program, Stmt};
+use sald_core::builtins::docs::MemberDoc;
+use sald_core::error::Span;
+use sald_core::lexer::{Scanner, Token, TokenKind};
+use tower_lsp::lsp_types::{
+    Documentation, ParameterInformation, ParameterLabel, Position, SignatureHelp,
+    SignatureInformation,
+};
+
+#[derive(Debug, Clone)]
+pub struct FunctionSignature {
+    pub name: String,
+    pub params: Vec<ParamSignature>,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParamSignature {
+    pub name: String,
+    /// Name as shown, with its default value or variadic marker
+    pub label: String,
+    /// Source text of the default value
+    pub default: Option<String>,
+    /// Variadic or `**` parameter, which takes no single argument position
+    pub is_variadic: bool,
+}
+
+/// The call surrounding the cursor
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallContext {
+    /// `obj` in `obj.name(`
+    pub owner: Option<String>,
+    pub name: String,
+    /// Positional index of the argument being typed
+    pub argument: usize,
+    /// Set while typing `name: value`
+    pub named: Option<String>,
+}
+
+/// Signatures of a document keyed by `name`, `Class.method` or
+/// `Namespace.name`. A class is keyed by its own name with the signature of
+/// its `init`, which calling the class runs.
+pub fn collect(program: &Program, source: &str) -> FxHashMap<String, FunctionSignature> {
+    let mut signatures = FxHashMap::default();
+    collect_statements(&program.statements, "", source, &mut signatures);
+    signatures
+}
+
+fn collect_statements(
+    statements: &[Stmt],
+    prefix: &str,
+    source: &str,
+    signatures: &mut FxHashMap<String, FunctionSignature>,
+) {
+    for stmt in statements {
+        match stmt.without_cfg() {
+            Stmt::Function { def } => {
+                signatures.insert(
+                    format!("{}{}", prefix, def.name),
+                    from_function(def, source),
+                );
+            }
+            Stmt::Class { def } => {
+                for method in &def.methods {
+                    signatures.insert(
+                        format!("{}{}.{}", prefix, def.name, method.name),
+                        from_function(method, source),
+                    );
+                }
+                if let Some(init) = def.methods.iter().find(|m| m.name == "init") {
+                    let mut constructor = from_function(init, source);
+                    constructor.name = def.name.clone();
+                    signatures.insert(format!("{}{}", prefix, def.name), constructor);
+                }
+            }
+            Stmt::Namespace { name, body, .. } => {
+                collect_statements(body, &format!("{}{}.", prefix, name), source, signatures);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn from_function(def: &FunctionDef, source: &str) -> FunctionSignature {
+    let params = def
+        .params
+        .iter()
+        .filter(|p| p.name != "self")
+        .map(|p| {
+            let default = p
+                .default_value
+                .as_ref()
+                .map(|value| source_text(source, value.span()));
+            let label = if p.is_variadic {
+                format!("...{}", p.name)
+            } else if p.is_kwargs {
+                format!("**{}", p.name)
+            } else if let Some(default) = &default {
+                format!("{} = {}", p.name, default)
+            } else {
+                p.name.clone()
+            };
+            ParamSignature {
+                name: p.name.clone(),
+                label,
+                default,
+                is_variadic: p.is_variadic || p.is_kwargs,
+            }
+        })
+        .collect();
+
+    FunctionSignature {
+        name: def.name.clone(),
+        params,
+        doc: def.doc.clone(),
+    }
+}
+
+/// Parses a builtin signature such as `await read(path)` or
+/// `splice(start, deleteCount, ...items)`.
+pub fn from_builtin(member: &MemberDoc) -> FunctionSignature {
+    let inner = member
+        .signature
+        .split_once('(')
+        .and_then(|(_, rest)| rest.rsplit_once(')'))
+        .map_or("", |(inner, _)| inner);
+    let params = inner
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|label| {
+            let is_variadic = label.starts_with("...");
+            let name = label.trim_start_matches("...").trim_matches('?');
+            ParamSignature {
+                name: name.to_string(),
+                label: label.to_string(),
+                default: None,
+                is_variadic,
+            }
+        })
+        .collect();
+
+    FunctionSignature {
+        name: member.name.clone(),
+        params,
+        doc: Some(member.doc.clone()),
+    }
+}
+
+/// Finds the innermost unclosed call before `position`.
+pub fn call_context(text: &str, position: Position) -> Option<CallContext> {
+    let prefix = text_before(text, position)?;
+    let tokens = Scanner::new(prefix, "<lsp>").scan_tokens().ok()?;
+    let tokens: Vec<_> = tokens
+        .iter()
+        .filter(|t| !matches!(t.kind, TokenKind::Eof))
+        .collect();
+
+    let mut depth = 0usize;
+    let mut argument = 0;
+    let mut argument_start = tokens.len();
+    let mut open = None;
+    for i in (0..tokens.len()).rev() {
+        match tokens[i].kind {
+            TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => depth += 1,
+            TokenKind::LeftParen if depth == 0 => {
+                open = Some(i);
+                break;
+            }
+            // Inside an array, dict or block rather than an argument list
+            TokenKind::LeftBracket | TokenKind::LeftBrace if depth == 0 => return None,
+            TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => depth -= 1,
+            TokenKind::Comma if depth == 0 => {
+                // The first comma seen from the right starts the current argument
+                if argument == 0 {
+                    argument_start = i + 1;
+                }
+                argument += 1;
+            }
+            _ => {}
+        }
+    }
+    let open = open?;
+    if argument == 0 {
+        argument_start = open + 1;
+    }
+
+    let (owner, name) = callee(&tokens, open)?;
+    Some(CallContext {
+        owner,
+        name,
+        argument,
+        named: named_argument(&tokens, argument_start),
+    })
+}
+
+/// A call found in a document, with where its arguments start
+#[derive(Debug, Clone)]
+pub struct CallSite {
+    pub owner: Option<String>,
+    pub name: String,
+    /// Position of the called name
+    pub callee: Position,
+    pub arguments: Vec<CallSiteArgument>,
+    /// Position of the closing parenthesis
+    pub close: Position,
+}
+
+#[derive(Debug, Clone)]
+pub struct CallSiteArgument {
+    pub position: Position,
+    /// Written as `name: value`
+    pub named: Option<String>,
+    /// Spread `...items` or an identifier, which already reads as a name
+    pub identifier: Option<String>,
+    pub is_spread: bool,
+}
+
+/// Every complete call in `text`, or none if it does not tokenize.
+pub fn call_sites(text: &str) -> Vec<CallSite> {
+    let Ok(tokens) = Scanner::new(text, "<lsp>").scan_tokens() else {
+        return Vec::new();
+    };
+    let tokens: Vec<&Token> = tokens
+        .iter()
+        .filter(|t| !matches!(t.kind, TokenKind::Eof))
+        .collect();
+
+    let mut sites = Vec::new();
+    for open in 0..tokens.len() {
+        if !matches!(tokens[open].kind, TokenKind::LeftParen) {
+            continue;
+        }
+        let Some((owner, name)) = callee(&tokens, open) else {
+            continue;
+        };
+
+        let mut arguments = Vec::new();
+        let mut depth = 0usize;
+        let mut argument_start = open + 1;
+        let mut close = None;
+        for i in open + 1..tokens.len() {
+            match tokens[i].kind {
+                TokenKind::LeftParen | TokenKind::LeftBracket | TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightParen if depth == 0 => {
+                    close = Some(i);
+                    break;
+                }
+                TokenKind::RightParen | TokenKind::RightBracket | TokenKind::RightBrace => {
+                    depth = depth.saturating_sub(1)
+                }
+                TokenKind::Comma if depth == 0 => {
+                    arguments.push(argument(&tokens, argument_start, i));
+                    argument_start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        let Some(close) = close else {
+            continue;
+        };
+        if argument_start < close {
+            arguments.push(argument(&tokens, argument_start, close));
+        }
+
+        sites.push(CallSite {
+            owner,
+            name,
+            callee: position(tokens[open - 1]),
+            arguments,
+            close: position(tokens[close]),
+        });
+    }
+    sites
+}
+
+fn argument(tokens: &[&Token], start: usize, end: usize) -> CallSiteArgument {
+    let identifier = match &tokens[start].kind {
+        TokenKind::Identifier(name) if end == start + 1 => Some(name.clone()),
+        _ => None,
+    };
+    CallSiteArgument {
+        position: position(tokens[start]),
+        named: named_argument(tokens, start),
+        identifier,
+        is_spread: matches!(tokens[start].kind, TokenKind::DotDotDot),
+    }
+}
+
+/// Owner and name of the function called by the `(` at `open`.
+fn callee(tokens: &[&Token], open: usize) -> Option<(Option<String>, String)> {
+    let TokenKind::Identifier(name) = &tokens.get(open.checked_sub(1)?)?.kind else {
+        return None;
+    };
+    let before = open.checked_sub(2).map(|i| &tokens[i].kind);
+    let owner = match before {
+        // Declarations and decorators are not calls
+        Some(TokenKind::Fun) | Some(TokenKind::At) => return None,
+        Some(TokenKind::Dot) | Some(TokenKind::QuestionDot) => {
+            match open.checked_sub(3).map(|i| &tokens[i].kind) {
+                Some(TokenKind::Identifier(owner)) => Some(owner.clone()),
+                Some(TokenKind::SelfKeyword) => Some("self".to_string()),
+                _ => return None,
+            }
+        }
+        _ => None,
+    };
+    Some((owner, name.clone()))
+}
+
+fn named_argument(tokens: &[&Token], start: usize) -> Option<String> {
+    match (
+        tokens.get(start).map(|t| &t.kind),
+        tokens.get(start + 1).map(|t| &t.kind),
+    ) {
+        (Some(TokenKind::Identifier(arg)), Some(TokenKind::Colon)) => Some(arg.clone()),
+        _ => None,
+    }
+}
+
+fn position(token: &Token) -> Position {
+    Position::new(
+        token.span.start.line.saturating_sub(1) as u32,
+        token.span.start.column.saturating_sub(1) as u32,
+    )
+}
+
+/// Builds the LSP response with the argument under the cursor highlighted.
+pub fn to_signature_help(signature: &FunctionSignature, context: &CallContext) -> SignatureHelp {
+    let mut label = format!("{}(", signature.name);
+    let mut parameters = Vec::new();
+    for (i, param) in signature.params.iter().enumerate() {
+        if i > 0 {
+            label.push_str(", ");
+        }
+        let start = label.encode_utf16().count() as u32;
+        label.push_str(&param.label);
+        let end = label.encode_utf16().count() as u32;
+        parameters.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, end]),
+            documentation: None,
+        });
+    }
+    label.push(')');
+
+    let active = match &context.named {
+        Some(named) => signature.params.iter().position(|p| &p.name == named),
+        None => match signature.params.iter().position(|p| p.is_variadic) {
+            Some(variadic) if context.argument >= variadic => Some(variadic),
+            _ => Some(context.argument),
+        },
+    };
+
+    SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label,
+            documentation: signature
+                .doc
+                .clone()
+                .filter(|doc| !doc.is_empty())
+                .map(Documentation::String),
+            parameters: Some(parameters),
+            active_parameter: active.map(|i| i as u32),
+        }],
+        active_signature: Some(0),
+        active_parameter: active.map(|i| i as u32),
+    }
+}
+
+/// Source text under `span`, with line breaks collapsed to spaces.
+fn source_text(source: &str, span: Span) -> String {
+    let lines: Vec<&str> = source
+        .lines()
+        .skip(span.start.line.saturating_sub(1))
+        .take(span.end.line + 1 - span.start.line.max(1))
+        .collect();
+    let mut parts = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let from = if i == 0 { span.start.column - 1 } else { 0 };
+        let to = if i + 1 == lines.len() {
+            span.end.column.min(chars.len())
+        } else {
+            chars.len()
+        };
+        parts.push(
+            chars[from.min(to)..to]
+                .iter()
+                .collect::<String>()
+                .trim()
+                .to_string(),
+        );
+    }
+    parts.join(" ")
+}
+
+/// The document text up to `position`.
+fn text_before(text: &str, position: Position) -> Option<&str> {
+    let mut offset = 0;
+    for (i, line) in text.split('\n').enumerate() {
+        if i == position.line as usize {
+            let column: usize = line
+                .chars()
+                .take(position.character as usize)
+                .map(char::len_utf8)
+                .sum();
+            return Some(&text[..offset + column]);
+        }
+        offset += line.len() + 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sald_core::parser::Parser;
+
+    fn signatures(source: &str) -> FxHashMap<String, FunctionSignature> {
+        let tokens = Scanner::new(source, "<test>").scan_tokens().unwrap();
+        let program = Parser::new(tokens, "<test>", source).parse().unwrap();
+        collect(&program, source)
+    }
+
+    fn labels(signature: &FunctionSignature) -> Vec<&str> {
+        signature.params.iter().map(|p| p.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_collects_functions_methods_and_defaults() {
+        let signatures = signatures(
+            "fun f(a, b = 1 + 2, ...rest) {}\n\
+             class A { fun m(self, x) {} fun s(y) {} }\n\
+             namespace N { fun g(**options) {} }",
+        );
+        assert_eq!(labels(&signatures["f"]), ["a", "b = 1 + 2", "...rest"]);
+        assert_eq!(signatures["f"].params[1].default.as_deref(), Some("1 + 2"));
+        assert_eq!(labels(&signatures["A.m"]), ["x"]);
+        assert_eq!(labels(&signatures["A.s"]), ["y"]);
+        assert_eq!(labels(&signatures["N.g"]), ["**options"]);
+        assert!(signatures["N.g"].params[0].is_variadic);
+    }
+
+    #[test]
+    fn test_class_takes_the_signature_of_init() {
+        let signatures = signatures(
+            "class A { fun init(self, x, y = 0) {} }\nclass B {}\n\
+             namespace N { class C { fun init(self, z) {} } }",
+        );
+        assert_eq!(signatures["A"].name, "A");
+        assert_eq!(labels(&signatures["A"]), ["x", "y = 0"]);
+        assert!(!signatures.contains_key("B"));
+        assert_eq!(labels(&signatures["N.C"]), ["z"]);
+
+        let text = "class A { fun init(self, x, y = 0) {} }\nlet a = A(1, ";
+        let context = call_context(text, Position::new(1, 13)).unwrap();
+        assert_eq!(context.owner, None);
+        assert_eq!(context.name, "A");
+        let help = to_signature_help(&signatures[&context.name], &context);
+        assert_eq!(help.signatures[0].label, "A(x, y = 0)");
+        assert_eq!(help.active_parameter, Some(1));
+    }
+
+    #[test]
+    fn test_call_context_finds_the_open_call() {
+        let context = call_context("obj.f(g(1), [2, 3], ", Position::new(0, 20)).unwrap();
+        assert_eq!(context.owner.as_deref(), Some("obj"));
+        assert_eq!(context.name, "f");
+        assert_eq!(context.argument, 2);
+
+        let context = call_context("f(1, b: ", Position::new(0, 8)).unwrap();
+        assert_eq!(context.named.as_deref(), Some("b"));
+
+        assert!(call_context("f(1)", Position::new(0, 4)).is_none());
+        assert!(call_context("f([1, ", Position::new(0, 6)).is_none());
+        assert!(call_context("fun f(a, ", Position::new(0, 9)).is_none());
+    }
+
+    #[test]
+    fn test_active_parameter_stays_on_variadic_and_follows_names() {
+        let signatures = signatures("fun f(a, b, ...rest) {}");
+        let help = |text: &str| {
+            let position = Position::new(0, text.chars().count() as u32);
+            let context = call_context(text, position).unwrap();
+            to_signature_help(&signatures["f"], &context).active_parameter
+        };
+        assert_eq!(help("f("), Some(0));
+        assert_eq!(help("f(1, 2, 3, "), Some(2));
+        assert_eq!(help("f(1, b: "), Some(1));
+    }
+
+    #[test]
+    fn test_call_sites_record_arguments() {
+        let sites = call_sites("f(x, b: 1, ...rest)\nobj.g()");
+        assert_eq!(sites.len(), 2);
+        let arguments = &sites[0].arguments;
+        assert_eq!(arguments.len(), 3);
+        assert_eq!(arguments[0].identifier.as_deref(), Some("x"));
+        assert_eq!(arguments[1].named.as_deref(), Some("b"));
+        assert!(arguments[2].is_spread);
+        assert_eq!(sites[0].close, Position::new(0, 18));
+        assert_eq!(sites[1].owner.as_deref(), Some("obj"));
+        assert_eq!(sites[1].callee, Position::new(1, 4));
+        assert!(sites[1].arguments.is_empty());
+    }
+}
@@ -7,11 +7,15 @@ use std::fs;
 use std::path::PathBuf;
 
 use sald_core::binary;
+use sald_core::builtins::docs::builtin_docs;
 use sald_core::compiler::Compiler;
+use sald_core::docgen::{self, DocFormat, ModuleDoc};
 use sald_core::error::SaldResult;
-use sald_core::lexer::Scanner;
+use sald_core::ffigen::{self, Bindings};
+use sald_core::fmt;
+use sald_core::lexer::{Scanner, KEYWORDS};
 use sald_core::parser;
-use sald_core::vm::VM;
+use sald_core::vm::{profiler, reload, DebugContext, VM};
 
 /// Sald - A fast, class-based interpreted language
 #[derive(Parser)]
@@ -23,6 +27,10 @@ struct Cli {
     /// Source file to run (.sald or .saldc)
     file: Option<PathBuf>,
 
+    /// Arguments passed to the script, after the file
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true, requires = "file")]
+    args: Vec<String>,
+
     /// Debug options: tokens, ast, asm, gc (comma-separated)
     #[arg(short = 'd', long = "debug", value_delimiter = ',')]
     debug: Option<Vec<String>>,
@@ -39,7 +47,7 @@ struct Cli {
     #[arg(long = "check")]
     check: bool,
 
-    /// Run tests (functions with @Test decorator)
+    /// Run tests (@Test functions and Test.describe/Test.it suites)
     #[arg(short = 't', long = "test")]
     test: bool,
 
@@ -47,16 +55,179 @@ struct Cli {
     #[arg(short = 'f', long = "filter")]
     filter: Option<String>,
 
+    /// How test results are printed (requires --test)
+    #[arg(
+        long = "reporter",
+        value_enum,
+        default_value = "pretty",
+        requires = "test"
+    )]
+    reporter: Reporter,
+
+    /// Overwrite snapshots that no longer match (requires --test)
+    #[arg(short = 'u', long = "update-snapshots", requires = "test")]
+    update_snapshots: bool,
+
     /// Output path for compiled file (requires -c)
     #[arg(short = 'o', long = "output")]
     output: Option<PathBuf>,
+
+    /// Compile to a self-contained executable (requires -c)
+    #[arg(long = "standalone", requires = "compile")]
+    standalone: bool,
+
+    /// Compress the compiled file with zstd (requires -c)
+    #[arg(long = "compress", requires = "compile")]
+    compress: bool,
+
+    /// Profile the run and print per-function statistics to stderr
+    #[arg(long = "profile")]
+    profile: bool,
+
+    /// Write collapsed stacks for flamegraph tools (implies --profile)
+    #[arg(long = "flamegraph", value_name = "FILE")]
+    flamegraph: Option<PathBuf>,
+
+    /// Pause at `debugger` statements and open an inspect prompt
+    #[arg(long = "inspect")]
+    inspect: bool,
+
+    /// Hot reload imported modules on change and rerun the script when it exits
+    #[arg(short = 'w', long = "watch")]
+    watch: bool,
+
+    /// What to do with warnings such as deprecated APIs or lossy conversions
+    #[arg(long = "warnings", value_enum, default_value = "default")]
+    warnings: Warnings,
+
+    /// Compile every file in strict mode, as if it started with "use strict"
+    #[arg(long = "strict")]
+    strict: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Warnings {
+    /// Print each distinct warning once to stderr
+    Default,
+    /// Drop warnings
+    Ignore,
+    /// Raise warnings as exceptions
+    Error,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Reporter {
+    /// Colored progress and summary
+    Pretty,
+    /// JSON document on stdout
+    Json,
+    /// JUnit XML on stdout
+    Junit,
+}
+
+/// Format Sald source files in place
+#[derive(Parser)]
+#[command(name = "sald fmt")]
+struct FmtArgs {
+    /// Files or directories to format; reads stdin when omitted
+    paths: Vec<PathBuf>,
+
+    /// Report files that would change without writing them
+    #[arg(long = "check")]
+    check: bool,
+}
+
+/// Generate API documentation from `///` comments
+#[derive(Parser)]
+#[command(name = "sald doc")]
+struct DocArgs {
+    /// Files or directories to document; defaults to the current directory
+    paths: Vec<PathBuf>,
+
+    /// Render HTML instead of Markdown
+    #[arg(long = "html")]
+    html: bool,
+
+    /// Write the documentation to a file instead of stdout
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+
+    /// Title of the generated page
+    #[arg(long = "title", default_value = "API Reference")]
+    title: String,
+
+    /// Leave the builtin classes out
+    #[arg(long = "no-builtins")]
+    no_builtins: bool,
+}
+
+/// Generate a Sald module of `Ffi` bindings from a C header or JSON description
+#[derive(Parser)]
+#[command(name = "sald bindgen")]
+struct BindgenArgs {
+    /// C header (.h) or JSON binding description (.json)
+    input: PathBuf,
+
+    /// Library the module opens; defaults to one named after the input
+    #[arg(short = 'l', long = "lib")]
+    lib: Option<String>,
+
+    /// Write the module to a file instead of stdout
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
 }
 
 fn main() {
+    // Executables built with --standalone carry their program
+    if let Some(result) = run_embedded() {
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("fmt") {
+        let args = FmtArgs::parse_from(std::env::args().skip(1));
+        if let Err(e) = handle_fmt(args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("doc") {
+        let args = DocArgs::parse_from(std::env::args().skip(1));
+        if let Err(e) = handle_doc(args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if std::env::args().nth(1).as_deref() == Some("bindgen") {
+        let args = BindgenArgs::parse_from(std::env::args().skip(1));
+        if let Err(e) = handle_bindgen(args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let cli = Cli::parse();
+    sald_core::builtins::set_script_args(cli.args.clone());
+    sald_core::warnings::set_mode(match cli.warnings {
+        Warnings::Default => sald_core::warnings::WarningMode::Default,
+        Warnings::Ignore => sald_core::warnings::WarningMode::Ignore,
+        Warnings::Error => sald_core::warnings::WarningMode::Error,
+    });
+    Compiler::set_strict_default(cli.strict);
 
     // Parse debug flags
-    let debug = DebugFlags::from_options(&cli.debug);
+    let mut debug = DebugFlags::from_options(&cli.debug);
+    debug.profile = cli.profile || cli.flamegraph.is_some();
+    debug.flamegraph = cli.flamegraph;
+    debug.inspect = cli.inspect;
 
     let result = if let Some(code) = cli.exec {
         // Execute inline code
@@ -67,10 +238,19 @@ fn main() {
             handle_check(&path)
         } else if cli.compile {
             // Compile mode
-            handle_compile(&path, debug, cli.output)
+            handle_compile(&path, debug, cli.output, cli.standalone, cli.compress)
         } else if cli.test {
             // Test mode - run @Test functions
-            handle_test(&path, debug, cli.filter.as_deref())
+            handle_test(
+                &path,
+                debug,
+                cli.filter.as_deref(),
+                cli.reporter,
+                cli.update_snapshots,
+            )
+        } else if cli.watch {
+            // Watch mode - hot reload modules, rerun on change
+            handle_watch(&path, debug)
         } else {
             // Run mode
             handle_run(&path, debug)
@@ -92,6 +272,9 @@ struct DebugFlags {
     ast: bool,
     asm: bool,
     gc: bool,
+    profile: bool,
+    flamegraph: Option<PathBuf>,
+    inspect: bool,
 }
 
 impl DebugFlags {
@@ -135,10 +318,197 @@ fn handle_check(path: &PathBuf) -> Result<(), String> {
     Ok(())
 }
 
+/// Format files in place, or only list the ones that would change with --check
+fn handle_fmt(args: FmtArgs) -> Result<(), String> {
+    if args.paths.is_empty() {
+        use std::io::Read;
+
+        let mut source = String::new();
+        std::io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|e| format!("Error reading stdin: {}", e))?;
+        let formatted = fmt::format_source(&source, "<stdin>").map_err(|e| e.to_string())?;
+        if args.check {
+            if formatted != source {
+                return Err("<stdin> is not formatted".to_string());
+            }
+        } else {
+            print!("{}", formatted);
+        }
+        return Ok(());
+    }
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        collect_sald_files(path, &mut files)?;
+    }
+
+    let mut unformatted = 0;
+    for file in &files {
+        let source = fs::read_to_string(file)
+            .map_err(|e| format!("Error reading file '{}': {}", file.display(), e))?;
+        let file_name = file.to_string_lossy().to_string();
+        let formatted = fmt::format_source(&source, &file_name).map_err(|e| e.to_string())?;
+        if formatted == source {
+            continue;
+        }
+
+        unformatted += 1;
+        if args.check {
+            println!("{} {}", "Would reformat".yellow(), file.display());
+        } else {
+            fs::write(file, formatted)
+                .map_err(|e| format!("Error writing file '{}': {}", file.display(), e))?;
+            println!("{} {}", "Formatted".green(), file.display());
+        }
+    }
+
+    if args.check && unformatted > 0 {
+        return Err(format!("{} file(s) need formatting", unformatted));
+    }
+    Ok(())
+}
+
+/// Render documentation for every `.sald` file under the given paths
+fn handle_doc(args: DocArgs) -> Result<(), String> {
+    let paths = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        args.paths
+    };
+
+    let mut files = Vec::new();
+    for path in &paths {
+        collect_sald_files(path, &mut files)?;
+    }
+
+    let mut modules = Vec::new();
+    for file in &files {
+        let source = fs::read_to_string(file)
+            .map_err(|e| format!("Error reading file '{}': {}", file.display(), e))?;
+        let file_name = file.to_string_lossy().to_string();
+        let tokens = Scanner::new(&source, &file_name)
+            .scan_tokens()
+            .map_err(|e| e.to_string())?;
+        let program = parser::Parser::new(tokens, &file_name, &source)
+            .parse()
+            .map_err(|e| e.to_string())?;
+        let display = file.strip_prefix(".").unwrap_or(file).display().to_string();
+        modules.push(ModuleDoc::from_program(display, &program));
+    }
+
+    let builtins = if args.no_builtins {
+        Vec::new()
+    } else {
+        builtin_docs()
+    };
+    let format = if args.html {
+        DocFormat::Html
+    } else {
+        DocFormat::Markdown
+    };
+    let output = docgen::render(&args.title, &modules, &builtins, format);
+
+    match args.output {
+        Some(path) => {
+            fs::write(&path, output)
+                .map_err(|e| format!("Error writing file '{}': {}", path.display(), e))?;
+            println!("{} {}", "Generated".green(), path.display());
+        }
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+/// Turn a C header or JSON description into a module of `Ffi` declarations
+fn handle_bindgen(args: BindgenArgs) -> Result<(), String> {
+    let source = fs::read_to_string(&args.input)
+        .map_err(|e| format!("Error reading file '{}': {}", args.input.display(), e))?;
+
+    let is_json = args.input.extension().is_some_and(|ext| ext == "json");
+    let bindings = if is_json {
+        Bindings::from_json(&source)?
+    } else {
+        Bindings::from_header(&source)
+    };
+
+    let stem = args
+        .input
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let library = args
+        .lib
+        .or_else(|| bindings.library.clone())
+        .unwrap_or_else(|| {
+            let name = stem.strip_prefix("lib").unwrap_or(&stem);
+            format!(
+                "{}{}{}",
+                std::env::consts::DLL_PREFIX,
+                name,
+                std::env::consts::DLL_SUFFIX
+            )
+        });
+
+    let source_name = args
+        .input
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let output = ffigen::render(&bindings, &library, &source_name);
+
+    for skipped in &bindings.skipped {
+        eprintln!("{} Skipped {}", "!".yellow(), skipped);
+    }
+    match args.output {
+        Some(path) => {
+            fs::write(&path, output)
+                .map_err(|e| format!("Error writing file '{}': {}", path.display(), e))?;
+            println!(
+                "{} {} ({} functions, {} structs, {} constants)",
+                "Generated".green(),
+                path.display(),
+                bindings.functions.len(),
+                bindings.structs.len(),
+                bindings.constants.len()
+            );
+        }
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+/// Collect `.sald` files, descending into directories
+fn collect_sald_files(path: &PathBuf, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if !path.is_dir() {
+        files.push(path.clone());
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(path)
+        .map_err(|e| format!("Error reading directory '{}': {}", path.display(), e))?;
+    let mut entries: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    entries.sort();
+    for entry in entries {
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "sald") {
+            collect_sald_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
 fn handle_compile(
     path: &PathBuf,
     debug: DebugFlags,
     output: Option<PathBuf>,
+    standalone: bool,
+    compress: bool,
 ) -> Result<(), String> {
     let source = fs::read_to_string(path)
         .map_err(|e| format!("Error reading file '{}': {}", path.display(), e))?;
@@ -186,17 +556,74 @@ fn handle_compile(
         return Ok(());
     }
 
+    if standalone {
+        let output_path =
+            output.unwrap_or_else(|| path.with_extension(std::env::consts::EXE_EXTENSION));
+        let exe = std::env::current_exe().map_err(|e| format!("Error locating runtime: {}", e))?;
+        let runtime = fs::read(&exe).map_err(|e| format!("Error reading runtime: {}", e))?;
+        fs::write(&output_path, binary::build_standalone(&runtime, &chunk))
+            .map_err(|e| format!("Error writing file: {}", e))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&output_path, fs::Permissions::from_mode(0o755))
+                .map_err(|e| format!("Error writing file: {}", e))?;
+        }
+
+        println!(
+            "{} Built standalone executable {}",
+            "✓".green(),
+            output_path.display()
+        );
+        return Ok(());
+    }
+
     // Determine output path
     let output_path = output.unwrap_or_else(|| path.with_extension("saldc"));
 
     // Serialize and write
-    let bytes = binary::serialize(&chunk);
+    let bytes = if compress {
+        binary::serialize_compressed(&chunk)
+    } else {
+        binary::serialize(&chunk)
+    };
     fs::write(&output_path, bytes).map_err(|e| format!("Error writing file: {}", e))?;
     println!("{} Compiled to {}", "✓".green(), output_path.display());
 
     Ok(())
 }
 
+/// Run the program embedded in this executable, if there is one
+fn run_embedded() -> Option<Result<(), String>> {
+    let exe = std::env::current_exe().ok()?;
+    let chunk = match binary::read_standalone(&exe) {
+        Ok(chunk) => chunk?,
+        Err(e) => return Some(Err(e)),
+    };
+
+    let file_name = exe.to_string_lossy().to_string();
+    let mut vm = VM::new();
+    let result = vm.run(chunk, &file_name, "");
+    if result.is_err() {
+        exit_for_args(&vm);
+    }
+    Some(result.map(|_| ()).map_err(|e| e.format_with_options(true)))
+}
+
+/// Ends the process the way a command line tool does when the script failed
+/// with help or a usage error from `Args.parse`
+fn exit_for_args(vm: &VM) {
+    if let Some(exit) = vm.uncaught().and_then(sald_core::builtins::args_exit) {
+        if exit.code == 0 {
+            println!("{}", exit.output);
+        } else {
+            eprintln!("{}", exit.output);
+        }
+        std::process::exit(exit.code);
+    }
+}
+
 /// Find project root by looking for salad.json in current or parent directories
 fn find_project_root() -> Option<PathBuf> {
     let mut current = std::env::current_dir().ok()?;
@@ -213,17 +640,28 @@ fn find_project_root() -> Option<PathBuf> {
 fn handle_run(path: &PathBuf, debug: DebugFlags) -> Result<(), String> {
     // Auto-detect project root if salad.json exists (enables module imports)
     if let Some(project_root) = find_project_root() {
-        sald_core::set_project_root(&project_root);
+        sald_core::workspace::set_default_project_root(&project_root);
     }
 
     let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
 
+    // Compile the import graph on worker threads while the entry file compiles
+    let precompile = if ext != "saldc" {
+        let entry = path.clone();
+        let project_root = sald_core::workspace::default_project_root();
+        Some(std::thread::spawn(move || {
+            sald_core::compiler::pipeline::precompile_imports(&entry, project_root.as_deref())
+        }))
+    } else {
+        None
+    };
+
     let (chunk, source) = match ext {
         "saldc" => {
             // Read compiled bytecode
             let data = fs::read(path)
                 .map_err(|e| format!("Error reading file '{}': {}", path.display(), e))?;
-            let chunk = binary::deserialize(&data)?;
+            let chunk = binary::deserialize_lazy(&data)?;
             (chunk, String::new())
         }
         _ => {
@@ -277,19 +715,129 @@ fn handle_run(path: &PathBuf, debug: DebugFlags) -> Result<(), String> {
     // Run with sync VM
     let mut vm = VM::new();
     vm.set_gc_stats_enabled(debug.gc);
-    vm.run(chunk, &file_name, &source)
-        .map_err(|e| e.format_with_options(true))?;
+    if let Some(modules) = precompile.and_then(|handle| handle.join().ok()) {
+        vm.set_precompiled_modules(modules);
+    }
+    if debug.inspect {
+        vm.set_debugger_hook(Some(std::rc::Rc::new(inspect_prompt)));
+    }
+    if debug.profile {
+        profiler::start_session();
+    }
+    let result = vm.run(chunk, &file_name, &source);
+    if debug.profile {
+        report_profile(&debug)?;
+    }
+    if result.is_err() {
+        exit_for_args(&vm);
+    }
+    result.map_err(|e| e.format_with_options(true))?;
+    // Callbacks from Timer.interval and Cron.schedule keep the script alive
+    sald_core::builtins::run_pending_timers(&mut vm)?;
 
     Ok(())
 }
 
-/// Run tests - collect and execute @Test functions
-fn handle_test(path: &PathBuf, debug: DebugFlags, filter: Option<&str>) -> Result<(), String> {
+/// Run a script, hot reloading the modules it imports while it runs and
+/// starting it again when any watched file changes after it exits
+fn handle_watch(path: &PathBuf, debug: DebugFlags) -> Result<(), String> {
+    reload::watch(std::time::Duration::from_millis(250));
+    loop {
+        reload::track(&path.to_string_lossy());
+        if let Err(e) = handle_run(path, debug.clone()) {
+            eprintln!("{}", e);
+        }
+        eprintln!(
+            "{}",
+            "-- Watching for changes (Ctrl+C to quit) --".bright_black()
+        );
+        let changed = reload::wait_for_change();
+        eprintln!("{} {} changed, restarting", "↻".cyan(), changed.join(", "));
+    }
+}
+
+fn report_profile(debug: &DebugFlags) -> Result<(), String> {
+    let report = profiler::stop_session();
+    eprintln!("\n{}", "-- Profile --".cyan());
+    eprint!("{}", report.to_table());
+    if let Some(path) = &debug.flamegraph {
+        fs::write(path, report.to_collapsed())
+            .map_err(|e| format!("Error writing profile: {}", e))?;
+        eprintln!(
+            "{} Collapsed stacks written to {}",
+            "✓".green(),
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Interactive prompt shown at `debugger` statements under --inspect
+fn inspect_prompt(context: &DebugContext) {
+    use std::io::{BufRead, Write};
+
+    let print_locals = || {
+        if context.locals.is_empty() {
+            eprintln!("  (no locals)");
+        }
+        for (name, value) in &context.locals {
+            eprintln!("  {} = {}", name.cyan(), value);
+        }
+    };
+    let print_stack = || {
+        for frame in &context.frames {
+            eprintln!("{}", frame);
+        }
+    };
+
+    eprintln!("\n{}", "-- Paused at debugger --".yellow());
+    if let Some(frame) = context.current_frame() {
+        eprintln!("{}", frame);
+    }
+    print_locals();
+
+    let stdin = std::io::stdin();
+    loop {
+        eprint!("{} ", "inspect>".bright_black());
+        let _ = std::io::stderr().flush();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        let line = line.trim();
+        match line.split_once(' ').unwrap_or((line, "")) {
+            ("c" | "continue", _) => break,
+            ("" | "l" | "locals", _) => print_locals(),
+            ("s" | "stack" | "bt", _) => print_stack(),
+            ("p" | "print", name) if !name.is_empty() => match context.lookup(name.trim()) {
+                Some(value) => eprintln!("  {}", value),
+                None => eprintln!("  {} '{}' is not defined", "!".red(), name.trim()),
+            },
+            _ => {
+                eprintln!("  locals        Show the current frame's locals");
+                eprintln!("  stack         Show the call stack");
+                eprintln!("  p <name>      Print a local or global");
+                eprintln!("  c, continue   Resume execution");
+            }
+        }
+    }
+}
+
+/// Run tests - `@Test` functions and `Test.describe`/`Test.it` suites
+fn handle_test(
+    path: &PathBuf,
+    debug: DebugFlags,
+    filter: Option<&str>,
+    reporter: Reporter,
+    update_snapshots: bool,
+) -> Result<(), String> {
+    use sald_core::ast::{Expr, Literal, Stmt};
+    use sald_core::testing::{self, TestMode, TestStatus};
     use std::time::Instant;
 
     // Auto-detect project root
     if let Some(project_root) = find_project_root() {
-        sald_core::set_project_root(&project_root);
+        sald_core::workspace::set_default_project_root(&project_root);
     }
 
     let source = fs::read_to_string(path)
@@ -302,32 +850,23 @@ fn handle_test(path: &PathBuf, debug: DebugFlags, filter: Option<&str>) -> Resul
     let mut parser = parser::Parser::new(tokens, &file_name, &source);
     let program = parser.parse().map_err(|e| e.to_string())?;
 
-    // Collect @Test functions
-    let mut test_names: Vec<String> = Vec::new();
-    for stmt in &program.statements {
-        if let sald_core::ast::Stmt::Function { def } = stmt {
-            if def.decorators.iter().any(|d| d.name == "Test") {
-                // Apply filter if provided
-                if let Some(f) = filter {
-                    if !def.name.contains(f) {
-                        continue;
-                    }
-                }
-                test_names.push(def.name.clone());
+    // Collect @Test functions; @Test("skip") and @Test("failing") set the mode
+    let mut decorated: Vec<(String, TestMode)> = Vec::new();
+    for stmt in program.statements.iter().filter_map(Stmt::active) {
+        if let Stmt::Function { def } = stmt {
+            if let Some(decorator) = def.decorators.iter().find(|d| d.name == "Test") {
+                let mode = match decorator.args.first() {
+                    Some(Expr::Literal {
+                        value: Literal::String(marker),
+                        ..
+                    }) => TestMode::from_marker(marker).unwrap_or(TestMode::Run),
+                    _ => TestMode::Run,
+                };
+                decorated.push((def.name.clone(), mode));
             }
         }
     }
 
-    if test_names.is_empty() {
-        if filter.is_some() {
-            println!("\n{}", "running 0 tests (filtered)".yellow());
-        } else {
-            println!("\n{}", "running 0 tests".yellow());
-        }
-        println!("\n{}", "test result: ok. 0 passed; 0 failed".green());
-        return Ok(());
-    }
-
     // Compile the full program
     let mut compiler = Compiler::new(&file_name, &source);
     let chunk = compiler.compile(&program).map_err(|e| e.to_string())?;
@@ -336,92 +875,118 @@ fn handle_test(path: &PathBuf, debug: DebugFlags, filter: Option<&str>) -> Resul
         chunk.disassemble(&file_name);
     }
 
-    // Run program first to define all functions
+    // Run program first to define all functions and register suites
+    testing::reset();
+    testing::configure_snapshots(path, update_snapshots);
     let mut vm = VM::new();
     vm.run(chunk, &file_name, &source)
         .map_err(|e| e.format_with_options(true))?;
-
-    // Print test header
-    println!();
-    println!(
-        "running {} test{}",
-        test_names.len(),
-        if test_names.len() == 1 { "" } else { "s" }
+    testing::add_decorated(
+        decorated
+            .into_iter()
+            .filter_map(|(name, mode)| vm.get_global(&name).map(|f| (name, f, mode)))
+            .collect(),
     );
 
-    let mut passed = 0;
-    let mut failed = 0;
-    let mut failed_tests: Vec<(String, String)> = Vec::new();
-    let start = Instant::now();
-
-    // Run each test function
-    for name in &test_names {
-        let test_start = Instant::now();
-
-        // Call the test function using vm.call_global
-        let result = vm.call_global(name, vec![]);
+    let pretty = reporter == Reporter::Pretty;
+    let count = testing::collected(filter).len();
+    if pretty {
+        println!();
+        if count == 0 && filter.is_some() {
+            println!("{}", "running 0 tests (filtered)".yellow());
+        } else if count == 0 {
+            println!("{}", "running 0 tests".yellow());
+        } else {
+            println!(
+                "running {} test{}",
+                count,
+                if count == 1 { "" } else { "s" }
+            );
+        }
+    }
 
-        let duration = test_start.elapsed();
-        let duration_str = if duration.as_millis() > 0 {
-            format!(" ({:.2}ms)", duration.as_secs_f64() * 1000.0)
+    let start = Instant::now();
+    let results = testing::run(&mut vm, filter, |result| {
+        if !pretty {
+            return;
+        }
+        let duration_str = if result.duration.as_millis() > 0 {
+            format!(" ({:.2}ms)", result.duration.as_secs_f64() * 1000.0)
         } else {
             String::new()
         };
+        let status = match &result.status {
+            TestStatus::Passed => "ok".green(),
+            TestStatus::Failed(_) => "FAILED".red(),
+            TestStatus::Skipped => "skipped".yellow(),
+            TestStatus::ExpectedFailure(_) => "ok (failed as expected)".green(),
+        };
+        println!("test {} ... {}{}", result.name, status, duration_str);
+    });
+    let total_duration = start.elapsed();
 
-        match result {
-            Ok(_) => {
-                println!("test {} ... {}{}", name, "ok".green(), duration_str);
-                passed += 1;
-            }
-            Err(e) => {
-                println!("test {} ... {}{}", name, "FAILED".red(), duration_str);
-                failed_tests.push((name.clone(), e.format_with_options(false)));
-                failed += 1;
+    let failed = results.iter().filter(|r| r.is_failure()).count();
+    let skipped = results
+        .iter()
+        .filter(|r| r.status == TestStatus::Skipped)
+        .count();
+    let passed = results.len() - failed - skipped;
+
+    match reporter {
+        Reporter::Json => println!("{}", testing::to_json(&results)),
+        Reporter::Junit => print!("{}", testing::to_junit(&file_name, &results)),
+        Reporter::Pretty => {
+            // Print failures detail
+            if failed > 0 {
+                println!();
+                println!("failures:");
+                println!();
+                for result in &results {
+                    if let TestStatus::Failed(error) = &result.status {
+                        println!("---- {} ----", result.name);
+                        // Message in red; following lines (e.g. snapshot diffs) as-is
+                        let mut lines = error.lines();
+                        println!("{}", lines.next().unwrap_or(error).red());
+                        for line in lines {
+                            println!("{}", line);
+                        }
+                        println!();
+                    }
+                }
+                println!("failures:");
+                for result in results.iter().filter(|r| r.is_failure()) {
+                    println!("    {}", result.name);
+                }
             }
-        }
-    }
-
-    let total_duration = start.elapsed();
 
-    // Print failures detail
-    if !failed_tests.is_empty() {
-        println!();
-        println!("failures:");
-        println!();
-        for (name, error) in &failed_tests {
-            println!("---- {} ----", name);
-            // Print just the error message, not the full stack trace
-            let first_line = error.lines().next().unwrap_or(error);
-            println!("{}", first_line.red());
+            // Print summary
             println!();
-        }
-        println!("failures:");
-        for (name, _) in &failed_tests {
-            println!("    {}", name);
+            let outcome = if failed > 0 {
+                "FAILED".red().bold()
+            } else {
+                "ok".green().bold()
+            };
+            println!(
+                "test result: {}. {} passed; {} failed; {} skipped; finished in {:.2}s",
+                outcome,
+                passed,
+                failed,
+                skipped,
+                total_duration.as_secs_f64()
+            );
+            let snapshots = testing::snapshot_summary();
+            if snapshots.added + snapshots.updated > 0 {
+                println!(
+                    "snapshots: {} written; {} updated",
+                    snapshots.added, snapshots.updated
+                );
+            }
         }
     }
 
-    // Print summary
-    println!();
     if failed > 0 {
-        println!(
-            "test result: {}. {} passed; {} failed; finished in {:.2}s",
-            "FAILED".red().bold(),
-            passed,
-            failed,
-            total_duration.as_secs_f64()
-        );
         return Err(format!("{} test(s) failed", failed));
-    } else {
-        println!(
-            "test result: {}. {} passed; {} failed; finished in {:.2}s",
-            "ok".green().bold(),
-            passed,
-            failed,
-            total_duration.as_secs_f64()
-        );
     }
-
     Ok(())
 }
 
@@ -471,59 +1036,16 @@ fn handle_exec(code: &str, debug: DebugFlags) -> Result<(), String> {
 
 fn repl() -> Result<(), String> {
     use reedline::{
-        FileBackedHistory, Prompt, PromptHistorySearch, PromptHistorySearchStatus, Reedline, Signal,
+        default_emacs_keybindings, ColumnarMenu, Emacs, FileBackedHistory, KeyCode, KeyModifiers,
+        MenuBuilder, Prompt, PromptHistorySearch, PromptHistorySearchStatus, Reedline,
+        ReedlineEvent, ReedlineMenu, Signal,
     };
     use std::borrow::Cow;
     use std::io::Write;
+    use std::sync::{Arc, Mutex};
 
-    // Check if code is incomplete (unbalanced delimiters)
-    fn is_incomplete(code: &str) -> bool {
-        let mut brace_count = 0i32;
-        let mut paren_count = 0i32;
-        let mut bracket_count = 0i32;
-        let mut in_string = false;
-        let mut in_raw_string = false;
-        let mut chars = code.chars().peekable();
-
-        while let Some(c) = chars.next() {
-            // Handle string literals
-            if c == '"' && !in_raw_string {
-                // Check for raw string """
-                if chars.peek() == Some(&'"') {
-                    chars.next();
-                    if chars.peek() == Some(&'"') {
-                        chars.next();
-                        in_raw_string = !in_raw_string;
-                        continue;
-                    }
-                }
-                if !in_raw_string {
-                    in_string = !in_string;
-                }
-                continue;
-            }
-
-            if in_string || in_raw_string {
-                continue;
-            }
-
-            match c {
-                '{' => brace_count += 1,
-                '}' => brace_count -= 1,
-                '(' => paren_count += 1,
-                ')' => paren_count -= 1,
-                '[' => bracket_count += 1,
-                ']' => bracket_count -= 1,
-                _ => {}
-            }
-        }
-
-        brace_count > 0 || paren_count > 0 || bracket_count > 0 || in_string || in_raw_string
-    }
-
-    // Custom prompts
+    // Custom prompt
     struct MainPrompt;
-    struct ContinuePrompt;
 
     impl Prompt for MainPrompt {
         fn render_prompt_left(&self) -> Cow<'_, str> {
@@ -553,34 +1075,6 @@ fn repl() -> Result<(), String> {
         }
     }
 
-    impl Prompt for ContinuePrompt {
-        fn render_prompt_left(&self) -> Cow<'_, str> {
-            Cow::Borrowed("... ")
-        }
-        fn render_prompt_right(&self) -> Cow<'_, str> {
-            Cow::Borrowed("")
-        }
-        fn render_prompt_indicator(&self, _: reedline::PromptEditMode) -> Cow<'_, str> {
-            Cow::Borrowed("")
-        }
-        fn render_prompt_multiline_indicator(&self) -> Cow<'_, str> {
-            Cow::Borrowed("... ")
-        }
-        fn render_prompt_history_search_indicator(
-            &self,
-            history_search: PromptHistorySearch,
-        ) -> Cow<'_, str> {
-            let prefix = match history_search.status {
-                PromptHistorySearchStatus::Passing => "",
-                PromptHistorySearchStatus::Failing => "failing ",
-            };
-            Cow::Owned(format!(
-                "({}reverse-search: {}) ",
-                prefix, history_search.term
-            ))
-        }
-    }
-
     println!();
     println!(
         "  {}  {}",
@@ -589,7 +1083,7 @@ fn repl() -> Result<(), String> {
     );
     println!(
         "  {}",
-        "Type .help for commands, .exit to quit".bright_black()
+        "Type :help for commands, :exit to quit".bright_black()
     );
     println!();
 
@@ -599,93 +1093,97 @@ fn repl() -> Result<(), String> {
         FileBackedHistory::with_file(1000, history_path.clone()).map_err(|e| e.to_string())?,
     );
 
-    // Create reedline editor with history
-    let mut line_editor = Reedline::create().with_history(history);
-    let main_prompt = MainPrompt;
-    let continue_prompt = ContinuePrompt;
+    // Tab opens the completion menu, or moves through it once open
+    let mut keybindings = default_emacs_keybindings();
+    keybindings.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Tab,
+        ReedlineEvent::UntilFound(vec![
+            ReedlineEvent::Menu("completion_menu".to_string()),
+            ReedlineEvent::MenuNext,
+        ]),
+    );
+    let completion_menu = Box::new(ColumnarMenu::default().with_name("completion_menu"));
 
     // Create persistent VM to maintain state across lines
     let mut vm = VM::new();
+    let names = Arc::new(Mutex::new(ReplNames::from_vm(&vm)));
+
+    // Create reedline editor with history, completion and multi-line input
+    let mut line_editor = Reedline::create()
+        .with_history(history)
+        .with_completer(Box::new(ReplCompleter {
+            names: names.clone(),
+        }))
+        .with_menu(ReedlineMenu::EngineCompleter(completion_menu))
+        .with_edit_mode(Box::new(Emacs::new(keybindings)))
+        .with_validator(Box::new(ReplValidator));
+    let prompt = MainPrompt;
     let mut line_count = 0u32;
-    let mut accumulated_input = String::new();
 
     loop {
-        let prompt: &dyn Prompt = if accumulated_input.is_empty() {
-            &main_prompt
-        } else {
-            &continue_prompt
-        };
-
-        match line_editor.read_line(prompt) {
-            Ok(Signal::Success(line)) => {
-                // Handle empty line in multiline mode - execute what we have
-                if line.trim().is_empty() && !accumulated_input.is_empty() {
-                    let input = accumulated_input.trim().to_string();
-                    accumulated_input.clear();
-
-                    line_count += 1;
-                    match run_repl_line(&mut vm, &input) {
-                        Ok(value) => {
-                            print_repl_result(&value, line_count);
-                        }
-                        Err(e) => {
-                            eprintln!("{}", e);
-                        }
-                    }
-                    continue;
-                }
-
-                let input = line.trim();
+        match line_editor.read_line(&prompt) {
+            Ok(Signal::Success(buffer)) => {
+                let input = buffer.trim();
 
                 if input.is_empty() {
                     continue;
                 }
 
-                // Handle REPL commands (only on first line)
-                if accumulated_input.is_empty() && input.starts_with('.') {
-                    match input {
-                        ".exit" | ".quit" => break,
-                        ".help" => {
-                            print_repl_help();
-                            continue;
-                        }
-                        ".clear" => {
+                // Handle REPL commands; `.exit` style is kept as an alias
+                if let Some(command) = input.strip_prefix(':').or_else(|| input.strip_prefix('.')) {
+                    let (command, arg) = command
+                        .split_once(char::is_whitespace)
+                        .map(|(c, a)| (c, a.trim()))
+                        .unwrap_or((command, ""));
+                    match command {
+                        "exit" | "quit" | "q" => break,
+                        "help" | "h" => print_repl_help(),
+                        "clear" => {
                             print!("\x1B[2J\x1B[1;1H");
                             let _ = std::io::stdout().flush();
-                            continue;
                         }
-                        ".reset" => {
+                        "reset" => {
                             vm = VM::new();
                             println!("{}", "  VM state reset".bright_black());
-                            continue;
+                        }
+                        "load" | "l" if !arg.is_empty() => match fs::read_to_string(arg) {
+                            Ok(source) => match run_repl_line(&mut vm, &source, arg) {
+                                Ok(_) => println!("{} Loaded {}", "✓".green(), arg),
+                                Err(e) => eprintln!("{}", e),
+                            },
+                            Err(e) => eprintln!("{} Cannot read '{}': {}", "!".red(), arg, e),
+                        },
+                        "type" | "t" if !arg.is_empty() => {
+                            match run_repl_line(&mut vm, arg, "<repl>") {
+                                Ok(value) => println!("{}", describe_type(&value).magenta()),
+                                Err(e) => eprintln!("{}", e),
+                            }
+                        }
+                        "load" | "l" | "type" | "t" => {
+                            println!(
+                                "{} Usage: :{} <{}>",
+                                "!".red(),
+                                command,
+                                if command.starts_with('l') {
+                                    "file"
+                                } else {
+                                    "expr"
+                                }
+                            );
                         }
                         _ => {
                             println!("{} Unknown command: {}", "!".red(), input);
-                            println!("  Type {} for available commands", ".help".cyan());
-                            continue;
+                            println!("  Type {} for available commands", ":help".cyan());
                         }
                     }
-                }
-
-                // Accumulate input
-                if !accumulated_input.is_empty() {
-                    accumulated_input.push('\n');
-                }
-                accumulated_input.push_str(&line);
-
-                // Check if code is complete
-                if is_incomplete(&accumulated_input) {
-                    // Need more lines
+                    *names.lock().unwrap() = ReplNames::from_vm(&vm);
                     continue;
                 }
 
-                // Execute complete code
-                let full_input = accumulated_input.trim().to_string();
-                accumulated_input.clear();
-
                 line_count += 1;
 
-                match run_repl_line(&mut vm, &full_input) {
+                match run_repl_line(&mut vm, input, "<repl>") {
                     Ok(value) => {
                         print_repl_result(&value, line_count);
                     }
@@ -693,15 +1191,10 @@ fn repl() -> Result<(), String> {
                         eprintln!("{}", e);
                     }
                 }
+                *names.lock().unwrap() = ReplNames::from_vm(&vm);
             }
             Ok(Signal::CtrlC) => {
-                // Cancel multiline input
-                if !accumulated_input.is_empty() {
-                    accumulated_input.clear();
-                    println!("{}", "^C (input cleared)".bright_black());
-                } else {
-                    println!("{}", "^C".bright_black());
-                }
+                println!("{}", "^C".bright_black());
                 continue;
             }
             Ok(Signal::CtrlD) => {
@@ -719,6 +1212,204 @@ fn repl() -> Result<(), String> {
     Ok(())
 }
 
+/// Check if code is incomplete (unclosed brackets, strings or comments)
+fn is_incomplete(code: &str) -> bool {
+    let chars: Vec<char> = code.chars().collect();
+    let at = |i: usize| chars.get(i).copied();
+    let mut depth = 0i32;
+    let mut i = 0;
+
+    while let Some(c) = at(i) {
+        match c {
+            '"' | '\'' => {
+                let triple = at(i + 1) == Some(c) && at(i + 2) == Some(c);
+                i += if triple { 3 } else { 1 };
+                loop {
+                    match at(i) {
+                        None => return true,
+                        Some('\\') => i += 2,
+                        Some(q) if q == c && !triple => break,
+                        Some(q) if q == c && at(i + 1) == Some(c) && at(i + 2) == Some(c) => {
+                            i += 2;
+                            break;
+                        }
+                        Some(_) => i += 1,
+                    }
+                }
+            }
+            '/' if at(i + 1) == Some('/') => {
+                while at(i).is_some_and(|c| c != '\n') {
+                    i += 1;
+                }
+            }
+            '/' if at(i + 1) == Some('*') => {
+                i += 2;
+                while !(at(i) == Some('*') && at(i + 1) == Some('/')) {
+                    if at(i).is_none() {
+                        return true;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    depth > 0
+}
+
+/// Keeps reading lines while the input is incomplete; an empty line submits
+/// it anyway so a stray bracket can't trap the prompt.
+struct ReplValidator;
+
+impl reedline::Validator for ReplValidator {
+    fn validate(&self, line: &str) -> reedline::ValidationResult {
+        if is_incomplete(line) && !line.ends_with('\n') {
+            reedline::ValidationResult::Incomplete
+        } else {
+            reedline::ValidationResult::Complete
+        }
+    }
+}
+
+const REPL_COMMANDS: &[&str] = &[":help", ":load", ":type", ":clear", ":reset", ":exit"];
+
+/// Names offered by tab completion, taken from the VM after each input
+#[derive(Default)]
+struct ReplNames {
+    globals: Vec<String>,
+    members: std::collections::HashMap<String, Vec<String>>,
+}
+
+impl ReplNames {
+    fn from_vm(vm: &VM) -> Self {
+        use sald_core::vm::Value;
+
+        let globals = vm.get_globals();
+        let class_named = |name: &str| match globals.get(name) {
+            Some(Value::Class(class)) => Some(class.clone()),
+            _ => None,
+        };
+        let mut names = Self::default();
+        for (name, value) in &globals {
+            names.globals.push(name.clone());
+            let members = value_members(value, &class_named);
+            if !members.is_empty() {
+                names.members.insert(name.clone(), members);
+            }
+        }
+        names
+    }
+}
+
+/// Properties and methods reachable with `value.`
+fn value_members(
+    value: &sald_core::vm::Value,
+    class_named: &dyn Fn(&str) -> Option<std::rc::Rc<sald_core::vm::Class>>,
+) -> Vec<String> {
+    use sald_core::vm::Value;
+
+    let mut members: Vec<String> = Vec::new();
+    match value {
+        Value::Class(class) => {
+            members.extend(class.user_static_methods.keys().cloned());
+            members.extend(class.native_static_methods.keys().cloned());
+            members.extend(class.callable_native_static_methods.keys().cloned());
+            members.extend(class.native_static_fields.keys().cloned());
+        }
+        Value::Instance(instance) => {
+            let instance = instance.borrow();
+            members.extend(instance.fields.keys().cloned());
+            let mut class = Some(instance.class.clone());
+            while let Some(current) = class {
+                members.extend(current.methods.keys().cloned());
+                members.extend(current.native_instance_methods.keys().cloned());
+                class = current.superclass.clone();
+            }
+        }
+        Value::Namespace { members: ns, .. } => members.extend(ns.borrow().keys().cloned()),
+        Value::Enum { variants, .. } => members.extend(variants.keys().cloned()),
+        other => {
+            if let Some(class) = class_named(other.type_name()) {
+                members.extend(class.native_instance_methods.keys().cloned());
+                members.extend(class.callable_native_instance_methods.keys().cloned());
+            }
+        }
+    }
+    members.sort();
+    members.dedup();
+    members
+}
+
+struct ReplCompleter {
+    names: std::sync::Arc<std::sync::Mutex<ReplNames>>,
+}
+
+impl reedline::Completer for ReplCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<reedline::Suggestion> {
+        let before = &line[..pos];
+        let start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !(c.is_alphanumeric() || *c == '_' || *c == '.' || *c == ':'))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let word = &before[start..];
+        let names = self.names.lock().unwrap();
+
+        let (offset, prefix, candidates): (usize, &str, Vec<&str>) =
+            if start == 0 && word.starts_with(':') {
+                (0, word, REPL_COMMANDS.to_vec())
+            } else if let Some((base, member)) = word.rsplit_once('.') {
+                let members = names
+                    .members
+                    .get(base)
+                    .map(|m| m.iter().map(String::as_str).collect())
+                    .unwrap_or_default();
+                (pos - member.len(), member, members)
+            } else {
+                let globals = names.globals.iter().map(String::as_str);
+                (
+                    start,
+                    word,
+                    KEYWORDS.iter().copied().chain(globals).collect(),
+                )
+            };
+
+        let mut candidates: Vec<&str> = candidates
+            .into_iter()
+            .filter(|c| c.starts_with(prefix))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+            .into_iter()
+            .map(|value| reedline::Suggestion {
+                value: value.to_string(),
+                span: reedline::Span::new(offset, pos),
+                append_whitespace: false,
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+/// Type shown by `:type`
+fn describe_type(value: &sald_core::vm::Value) -> String {
+    use sald_core::vm::Value;
+
+    match value {
+        Value::Instance(instance) => instance.borrow().class_name.clone(),
+        Value::Class(class) => format!("Class<{}>", class.name),
+        Value::Enum { name, .. } => format!("Enum<{}>", name),
+        Value::Namespace { name, .. } => format!("Namespace<{}>", name),
+        other => other.type_name().to_string(),
+    }
+}
+
 /// Get home directory for history file
 fn dirs_home() -> std::path::PathBuf {
     std::env::var("HOME")
@@ -731,121 +1422,61 @@ fn dirs_home() -> std::path::PathBuf {
 fn print_repl_help() {
     println!();
     println!("  {}", "REPL Commands:".cyan().bold());
-    println!("    {}    Exit the REPL", ".exit".yellow());
-    println!("    {}    Clear the screen", ".clear".yellow());
-    println!("    {}    Reset VM state", ".reset".yellow());
-    println!("    {}    Show this help", ".help".yellow());
+    println!("    {}           Exit the REPL", ":exit".yellow());
+    println!("    {}          Clear the screen", ":clear".yellow());
+    println!("    {}          Reset VM state", ":reset".yellow());
+    println!(
+        "    {}    Run a file in this session",
+        ":load <file>".yellow()
+    );
+    println!(
+        "    {}    Show the type of an expression",
+        ":type <expr>".yellow()
+    );
+    println!("    {}           Show this help", ":help".yellow());
     println!();
     println!("  {}", "Keyboard Shortcuts:".cyan().bold());
     println!("    {}      Previous command", "↑".yellow());
     println!("    {}      Next command", "↓".yellow());
+    println!(
+        "    {}     Complete globals, members and commands",
+        "Tab".yellow()
+    );
     println!("    {}    Search history", "Ctrl+R".yellow());
     println!("    {}    Clear line", "Ctrl+U".yellow());
     println!("    {}    Exit", "Ctrl+D".yellow());
     println!();
+    println!(
+        "  {}",
+        "Unclosed brackets continue on the next line; an empty line runs the input.".bright_black()
+    );
+    println!();
 }
 
 /// Format and print REPL result with colors (Node.js style)
 fn print_repl_result(value: &sald_core::vm::Value, _line: u32) {
-    let formatted = format_value(value, 0);
-    if !formatted.is_empty() && formatted != "null" {
+    let options = sald_core::inspect::InspectOptions {
+        colors: colored::control::SHOULD_COLORIZE.should_colorize(),
+        ..Default::default()
+    };
+    let formatted = sald_core::inspect::inspect(value, &options);
+    if !matches!(value, sald_core::vm::Value::Null) {
         println!("{}", formatted);
     }
 }
 
-/// Format a value with syntax highlighting
-fn format_value(value: &sald_core::vm::Value, depth: usize) -> String {
-    use sald_core::vm::Value;
-
-    match value {
-        Value::Null => "null".bright_black().to_string(),
-        Value::Boolean(b) => {
-            if *b {
-                "true".yellow().to_string()
-            } else {
-                "false".yellow().to_string()
-            }
-        }
-        Value::Number(n) => {
-            if n.fract() == 0.0 {
-                format!("{}", (*n as i64)).yellow().to_string()
-            } else {
-                format!("{}", n).yellow().to_string()
-            }
-        }
-        Value::String(s) => format!("'{}'", s).green().to_string(),
-        Value::Array(arr) => {
-            let arr = arr.borrow();
-            if arr.is_empty() {
-                "[]".to_string()
-            } else if arr.len() <= 5 && depth < 2 {
-                let items: Vec<String> = arr.iter().map(|v| format_value(v, depth + 1)).collect();
-                format!("[ {} ]", items.join(", "))
-            } else {
-                format!("[Array({})]", arr.len()).bright_black().to_string()
-            }
-        }
-        Value::Dictionary(dict) => {
-            let dict = dict.borrow();
-            if dict.is_empty() {
-                "{}".to_string()
-            } else if dict.len() <= 3 && depth < 2 {
-                let items: Vec<String> = dict
-                    .iter()
-                    .map(|(k, v)| format!("{}: {}", k.cyan(), format_value(v, depth + 1)))
-                    .collect();
-                format!("{{ {} }}", items.join(", "))
-            } else {
-                format!("{{Object({} keys)}}", dict.len())
-                    .bright_black()
-                    .to_string()
-            }
-        }
-        Value::Function(f) => format!("[Function: {}]", f.name).cyan().to_string(),
-        Value::NativeFunction { class_name, .. } => format!("[NativeFunction: {}]", class_name)
-            .cyan()
-            .to_string(),
-        Value::InstanceMethod { method_name, .. } => {
-            format!("[Method: {}]", method_name).cyan().to_string()
-        }
-        Value::BoundMethod { method, .. } => {
-            format!("[BoundMethod: {}]", method.name).cyan().to_string()
-        }
-        Value::Class(c) => format!("[Class: {}]", c.name).magenta().to_string(),
-        Value::Instance(inst) => {
-            let inst = inst.borrow();
-            if inst.fields.is_empty() {
-                format!("{} {{}}", inst.class_name.magenta())
-            } else if inst.fields.len() <= 3 && depth < 1 {
-                let fields: Vec<String> = inst
-                    .fields
-                    .iter()
-                    .map(|(k, v)| format!("{}: {}", k, format_value(v, depth + 1)))
-                    .collect();
-                format!("{} {{ {} }}", inst.class_name.magenta(), fields.join(", "))
-            } else {
-                format!("{} {{...}}", inst.class_name.magenta())
-            }
-        }
-        Value::Future(_) => "[Future]".bright_black().to_string(),
-        Value::Namespace { name, .. } => format!("[Namespace: {}]", name).magenta().to_string(),
-        Value::Enum { name, .. } => format!("[Enum: {}]", name).magenta().to_string(),
-        Value::SpreadMarker(v) => format!("[Spread: {:?}]", v).bright_black().to_string(),
-    }
-}
-
-fn run_repl_line(vm: &mut VM, source: &str) -> SaldResult<sald_core::vm::Value> {
-    let mut scanner = Scanner::new(source, "<repl>");
+fn run_repl_line(vm: &mut VM, source: &str, file: &str) -> SaldResult<sald_core::vm::Value> {
+    let mut scanner = Scanner::new(source, file);
     let tokens = scanner.scan_tokens()?;
 
-    let mut parser = parser::Parser::new(tokens, "<repl>", source);
+    let mut parser = parser::Parser::new(tokens, file, source);
     let program = parser.parse()?;
 
-    let mut compiler = Compiler::new("<repl>", source);
+    let mut compiler = Compiler::new(file, source);
     // Use compile_repl to keep expression results on stack
     let chunk = compiler.compile_repl(&program)?;
 
-    let result = vm.run(chunk, "<repl>", source)?;
+    let result = vm.run(chunk, file, source)?;
 
     Ok(result)
 }
@@ -990,6 +1621,9 @@ fn build_stmt_tree(tree: &mut ptree::TreeBuilder, stmt: &sald_core::ast::Stmt) {
         Stmt::Continue { .. } => {
             tree.add_empty_child("Continue".to_string());
         }
+        Stmt::Debugger { .. } => {
+            tree.add_empty_child("Debugger".to_string());
+        }
         Stmt::Import { path, alias, .. } => {
             if let Some(a) = alias {
                 tree.add_empty_child(format!("Import '{}' as {}", path, a));
@@ -997,6 +1631,9 @@ fn build_stmt_tree(tree: &mut ptree::TreeBuilder, stmt: &sald_core::ast::Stmt) {
                 tree.add_empty_child(format!("Import '{}'", path));
             }
         }
+        Stmt::Export { names, .. } => {
+            tree.add_empty_child(format!("Export {}", names.join(", ")));
+        }
         Stmt::TryCatch {
             try_body,
             catch_var,
@@ -1044,6 +1681,17 @@ fn build_stmt_tree(tree: &mut ptree::TreeBuilder, stmt: &sald_core::ast::Stmt) {
             }
             tree.end_child();
         }
+        Stmt::Cfg {
+            predicates, body, ..
+        } => {
+            let predicates: Vec<_> = predicates
+                .iter()
+                .map(|p| format!("{}: \"{}\"", p.key, p.value))
+                .collect();
+            tree.begin_child(format!("Cfg ({})", predicates.join(", ")));
+            build_stmt_tree(tree, body);
+            tree.end_child();
+        }
     }
 }
 
@@ -1056,6 +1704,8 @@ fn build_expr_tree(tree: &mut ptree::TreeBuilder, expr: &sald_core::ast::Expr) {
                 Literal::Number(n) => format!("{}", n),
                 Literal::String(s) => format!("\"{}\"", s),
                 Literal::Boolean(b) => format!("{}", b),
+                Literal::Date(s) => format!("d\"{}\"", s),
+                Literal::Time(s) => format!("t\"{}\"", s),
                 Literal::Null => "null".to_string(),
             };
             tree.add_empty_child(val_str);
@@ -1262,6 +1912,11 @@ fn build_expr_tree(tree: &mut ptree::TreeBuilder, expr: &sald_core::ast::Expr) {
             build_expr_tree(tree, expr);
             tree.end_child();
         }
+        Expr::Import { path, .. } => {
+            tree.begin_child("Import".to_string());
+            build_expr_tree(tree, path);
+            tree.end_child();
+        }
         Expr::Return { value, .. } => {
             tree.begin_child("Return".to_string());
             if let Some(v) = value {
@@ -1309,6 +1964,8 @@ fn build_pattern_tree(tree: &mut ptree::TreeBuilder, pattern: &sald_core::ast::P
                 Literal::Number(n) => format!("{}", n),
                 Literal::String(s) => format!("\"{}\"", s),
                 Literal::Boolean(b) => format!("{}", b),
+                Literal::Date(s) => format!("d\"{}\"", s),
+                Literal::Time(s) => format!("t\"{}\"", s),
                 Literal::Null => "null".to_string(),
             };
             tree.add_empty_child(format!("Literal({})", val_str));